@@ -0,0 +1,84 @@
+## Session
+
+session-read-only-banner = Du bist nicht angemeldet. Du kannst stöbern, aber nichts posten.
+session-pubkey-only-banner = Dieses Konto ist schreibgeschützt. Füge deinen privaten Schlüssel hinzu, um zu posten, zu reagieren und zu zappen.
+session-log-in = Anmelden
+session-need-login = Dafür musst du angemeldet sein
+session-need-key = Dieses Konto ist schreibgeschützt. Melde dich mit deinem privaten Schlüssel an, um das zu tun
+
+## Relative times
+
+time-ago-now = jetzt
+time-ago-seconds = { $count } s
+time-ago-minutes = { $count } min
+time-ago-hours = { $count } h
+time-ago-days = { $count } T
+time-ago-weeks = { $count } W
+time-ago-months = { $count } M
+time-ago-years = { $count } J
+
+## Column titles
+
+route-unknown = Unbekannt
+route-thread = Thread
+route-reply = Antworten
+route-quote = Zitieren
+route-profile = Profil
+route-relays = Relays
+route-settings = Einstellungen
+route-notification-settings = Benachrichtigungen
+route-accounts = Konten
+route-add-account = Konto hinzufügen
+route-compose-note = Notiz verfassen
+route-add-column = Spalte hinzufügen
+route-add-hashtag-column = Hashtag-Spalte hinzufügen
+route-support = Damus-Support
+route-new-deck = Deck hinzufügen
+route-edit-deck = Deck bearbeiten
+route-edit-profile = Profil bearbeiten
+
+## Timelines
+
+timeline-notes = Notizen
+timeline-notes-and-replies = Notizen & Antworten
+timeline-jump-to-top = Nach oben
+timeline-jump-to-oldest-new = Zur ältesten neuen Notiz
+
+## Settings
+
+settings-language = Sprache
+settings-language-system = Systemsprache ({ $language })
+settings-time = Datum und Uhrzeit
+settings-clock = Uhrzeit
+settings-clock-auto = Wie die Sprache
+settings-clock-12 = 12 Stunden (10:00 PM)
+settings-clock-24 = 24 Stunden (22:00)
+settings-week-start = Woche beginnt am
+settings-week-start-auto = Wie die Sprache
+settings-week-start-sunday = Sonntag
+settings-week-start-monday = Montag
+settings-week-start-saturday = Samstag
+settings-relays = Relays
+settings-notifications = Benachrichtigungen
+
+## Notification settings
+
+notification-settings-enabled = Desktop-Benachrichtigungen anzeigen
+notification-settings-notify-about = Benachrichtige mich über
+notification-settings-mentions = Erwähnungen
+notification-settings-replies = Antworten
+notification-settings-direct-messages = Direktnachrichten
+notification-settings-zaps = Zaps
+notification-settings-only-follows = Nur von Leuten, denen ich folge
+notification-settings-quiet-hours = Ruhezeiten
+notification-settings-quiet-hours-enabled = Während der Ruhezeiten nicht benachrichtigen
+notification-settings-quiet-from = Von
+notification-settings-quiet-until = bis
+
+## Notifications
+
+notification-someone = Jemand
+notification-mentioned = { $name } hat dich erwähnt
+notification-replied = { $name } hat geantwortet
+notification-direct-message = Neue Nachricht von { $name }
+notification-zapped = { $name } hat dich gezappt
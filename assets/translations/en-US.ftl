@@ -0,0 +1,907 @@
+## Session
+
+session-read-only-banner = You're not logged in. You can browse, but not post.
+session-pubkey-only-banner = This account is read-only. Add your private key to post, react and zap.
+session-log-in = Log in
+session-need-login = You need to log in to do this
+session-need-key = This account is read-only. Log in with your private key to do this
+offline-banner = Offline. You're seeing notes and images saved on this device.
+offline-last-prefetch = Last saved for offline { $ago } ago
+
+## Relative times
+
+time-ago-now = now
+time-ago-seconds = { $count }s
+time-ago-minutes = { $count }m
+time-ago-hours = { $count }h
+time-ago-days = { $count }d
+time-ago-weeks = { $count }wk
+time-ago-months = { $count }mth
+time-ago-years = { $count }yr
+
+## Calendar
+
+calendar-week-short = Wk
+
+## Column titles
+
+route-unknown = Unknown
+route-thread = Thread
+route-reply = Reply
+route-quote = Quote
+route-profile = Profile
+route-relays = Relays
+route-settings = Settings
+route-notification-settings = Notification Settings
+route-accounts = Accounts
+route-add-account = Add Account
+route-compose-note = Compose Note
+route-add-column = Add Column
+route-add-notifications-column = Add Notifications Column
+route-add-external-notifications-column = Add External Notifications Column
+route-add-hashtag-column = Add Hashtag Column
+route-add-individual-column = Subscribe to someone's notes
+route-add-external-individual-column = Subscribe to someone else's notes
+route-support = Damus Support
+route-new-deck = Add Deck
+route-edit-deck = Edit Deck
+route-edit-profile = Edit Profile
+route-poster = Export as Image
+route-source = View Source
+route-edit-note = Edit
+route-subscriptions = Subscriptions
+route-wallet = Wallet
+route-bookmarks = Bookmarks
+route-save-bookmark = Bookmark
+route-hidden = Hidden
+route-export-data = Export data
+route-storage = Storage
+route-report = Report
+route-my-posts = My posts
+route-explore-people = Explore people
+route-trending = Trending
+route-relay-groups = Relay Groups
+route-highlight = Highlight
+route-highlights = Highlights
+
+## Timelines
+
+timeline-notes = Notes
+deck-density = Density
+deck-share = Share this deck
+deck-share-copy = Copy as JSON
+deck-share-publish = Publish and copy naddr
+deck-import = Import a deck
+deck-import-hint = Paste a deck someone shared, or its naddr
+deck-import-read = Read deck
+deck-import-waiting = Looking for it on your relays…
+deck-import-failed = Couldn't read that deck: { $error }
+deck-import-columns = { $count ->
+    [one] { $count } column
+   *[other] { $count } columns
+}
+deck-import-skipped = { $count ->
+    [one] { $count } column can't be shown here and will be left out
+   *[other] { $count } columns can't be shown here and will be left out
+}
+deck-import-unknown = { $count ->
+    [one] Refers to { $count } person we haven't seen yet, their profile will be fetched
+   *[other] Refers to { $count } people we haven't seen yet, their profiles will be fetched
+}
+deck-import-add = Add deck
+deck-import-clear = Clear
+density-compact = Compact
+density-normal = Normal
+density-comfortable = Comfortable
+timeline-untrusted-hidden = { $count ->
+    [one] 1 note from outside your network hidden
+   *[other] { $count } notes from outside your network hidden
+}
+timeline-notes-and-replies = Notes & Replies
+timeline-jump-to-top = Jump to top
+timeline-jump-to-oldest-new = Jump to oldest new note
+timeline-jump-to-date = Jump to date
+timeline-previous-day = Previous day
+timeline-next-day = Next day
+timeline-back-to-latest = Back to latest
+timeline-calendar = Show the month, with how busy each day was
+timeline-month = Month
+timeline-history-until = Up to
+timeline-history-whole-day = Whole day
+timeline-history-utc = That's { $time }
+timeline-history-time-repeated = The clocks went back at this time, so it happened twice. Which one?
+timeline-history-time-skipped = The clocks went forward over this time, so it never happened. Which did you mean?
+timeline-history-end = Nothing older on this day
+timeline-history-backfilling = Asking relays for older notes
+timeline-media = Media
+timeline-media-mode = Show media only
+column-kinds = What this column shows
+column-kinds-notes = Notes
+column-kinds-replies = Replies
+column-kinds-reposts = Reposts
+column-kinds-long-form = Long-form articles
+column-kinds-highlights = Highlights
+column-kinds-live = Live events
+column-kinds-hint = Relays only send what's ticked. Replies are still fetched with notes, just not shown.
+
+## Settings
+
+settings-language = Language
+settings-language-system = System default ({ $language })
+settings-time = Date and time
+settings-clock = Clock
+settings-clock-auto = Language default
+settings-clock-12 = 12-hour (10:00 PM)
+settings-clock-24 = 24-hour (22:00)
+settings-week-start = Week starts on
+settings-week-start-auto = Language default
+settings-week-start-sunday = Sunday
+settings-week-start-monday = Monday
+settings-week-start-saturday = Saturday
+settings-amounts = Amounts
+settings-amount-unit = Show amounts in
+settings-unit-sats = Sats
+settings-unit-bits = Bits (100 sats)
+settings-unit-fiat = Local currency
+settings-currency = Currency
+settings-rate-from = Exchange rate from { $time }
+settings-rate-missing = No exchange rate yet, showing sats until we get one
+settings-hashtags = Hashtags
+settings-merge-hashtags = Show followed hashtags in my home timeline
+settings-media-servers = Media servers
+settings-media-servers-hint = Blossom servers your uploads go to, in order. Files are mirrored to all of them.
+settings-media-servers-add = Add
+settings-media-servers-remove = Remove
+settings-relays = Relays
+settings-notifications = Notifications
+settings-wallet = Wallet
+settings-hidden = Hidden notes and people
+settings-export-data = Export my data
+settings-storage = Storage
+settings-sync = Sync between devices
+settings-sync-enabled = Sync settings and columns
+settings-sync-hint = Keeps your columns, theme and preferences the same on every device you use this account on. They're stored encrypted on your relays.
+settings-sync-now = Sync now
+settings-sync-restore = Restore from relays
+settings-sync-restore-hint = Replace the settings on this device with the copy on your relays
+settings-subscriptions = Subscription inspector
+settings-refresh-timelines = Refresh all timelines
+settings-refresh-timelines-hint = Fetch every column from your relays again, in case some notes didn't come through
+settings-all = All settings
+settings-columns = Columns
+settings-autosave-drafts = Save drafts as I type
+settings-autosave-drafts-hint = Unfinished notes come back after restarting
+settings-starter-columns = Start new accounts with Home, Notifications and Posts & replies
+settings-starter-columns-hint = Otherwise accounts you log in to start with an empty deck
+settings-landing = Open on
+settings-landing-hint = The deck and column you land on when Notedeck starts
+settings-landing-last = Where I left off
+settings-landing-first = The first deck
+settings-landing-home = Home
+settings-landing-notifications = Notifications
+settings-landing-posts = My posts & replies
+settings-trusted-hashtags = Only show hashtag posts from my network
+settings-trusted-hashtags-hint = People you follow and, depending on the settings below, people near them
+settings-wot-depth = How far out my network reaches
+settings-wot-depth-hint = 1 is only people you follow, 2 adds who they follow, 3 goes one step further
+settings-wot-followers = Include followers of people I follow
+settings-wot-followers-hint = Also trust anyone who follows someone you follow
+settings-wot-min-follows = Follows needed to be in my network
+settings-wot-min-follows-hint = Beyond the people you follow, only trust accounts followed by at least this many people in the step before. 0 trusts anyone.
+settings-geocoder = Place search
+settings-geocoder-hint = A Nominatim compatible search url, used when adding a location to a note
+settings-map-tiles = Map tiles
+settings-map-tiles-hint = Where the maps under notes with a location come from, as a tile url template
+settings-gif-search = GIF search
+settings-gif-search-hint = A Tenor or Giphy compatible search url with your API key, with {"{"}query{"}"} where the search goes
+settings-animation = Animated images
+settings-animation-hint = Whether GIFs in timelines, banners and the GIF picker play
+settings-animation-always = Always play
+settings-animation-hover = Play when hovered
+settings-animation-static = Show the first frame
+settings-trending-window = Trending window
+settings-trending-window-hint = How far back the Trending column looks for what's taking off
+settings-trending-window-hour = The last hour
+settings-trending-window-hours = The last 6 hours
+settings-trending-window-day = The last day
+settings-trending-api = Trending service
+settings-trending-api-hint = A url that returns trending notes and hashtags as JSON. Leave empty to work them out from what your relays send.
+settings-spellcheck = Check spelling
+settings-spellcheck-hint = Underline misspelled words as you write. Right click one for suggestions.
+settings-spellcheck-language = Spellcheck language
+settings-spellcheck-language-hint = A language tag like de or en-GB. Leave empty to use the app language. Dictionaries are downloaded the first time they're used.
+settings-moderators = Trusted moderators
+settings-moderators-hint = npubs of people whose reports and labels you trust, separated by commas
+settings-moderation-action = Reported content
+settings-moderation-action-hint = What to do with notes and people your moderators have reported or labeled
+settings-moderation-off = Show
+settings-moderation-blur = Blur
+settings-moderation-hide = Hide
+settings-tombstones = Show where my deleted notes were
+settings-tombstones-hint = Everyone else's deleted notes are always hidden
+settings-blocked-relays = Blocked relays
+settings-blocked-relays-hint = Never connect to these relays, separated by commas
+settings-blocked-media-hosts = Blocked media hosts
+settings-blocked-media-hosts-hint = Never load images or videos from these domains or their subdomains, separated by commas
+settings-hidden-clients = Hidden clients
+settings-hidden-clients-hint = Hide notes posted with these apps, as named under each note, separated by commas. Handy for bots.
+settings-client-tag = Say my notes were posted with Notedeck
+settings-client-tag-hint = Adds a client tag to what you publish. Turn off if you'd rather not share which app you use.
+settings-retention-kinds = Kinds to clear out
+settings-retention-kinds-hint = Note kinds that compacting storage drops once they're old, separated by commas. 1 is posts, 7 is reactions.
+settings-retention-months = Clear out after months
+settings-retention-keep-trusted = Keep notes from people you trust
+settings-retention-keep-trusted-hint = Old notes from your web of trust survive compaction
+settings-prefetch = Save notes for offline reading
+settings-prefetch-hint = Every hour, download recent notes from people you follow, their images, and calendar events in the coming month
+settings-prefetch-hours = Hours of notes to save
+settings-prefetch-images = Save images too
+settings-prefetch-unmetered = Only on Wi-Fi
+settings-prefetch-unmetered-hint = Wait for a connection that isn't metered, like mobile data
+
+## Settings window
+
+settings-window-title = Settings
+settings-window-search = Search settings
+settings-window-no-results = No settings match
+settings-window-show-logs = Show logs
+settings-logging = Logging
+settings-log-level = Log level
+settings-log-level-hint = How much detail goes into the logs. Debug and trace are useful when reporting a problem.
+settings-memory = Memory
+settings-media-budget = Media memory (MB)
+settings-media-budget-hint = How much decoded media to keep around. Images that haven't been seen in a while are dropped first and loaded again from disk when needed.
+settings-window-show-diagnostics = Memory use
+diagnostics-title = Diagnostics
+diagnostics-images = { $count ->
+    [one] { $count } image
+   *[other] { $count } images
+}
+diagnostics-textures = { $count ->
+    [one] { $count } texture in total
+   *[other] { $count } textures in total
+}
+diagnostics-galleys = Laid out text
+diagnostics-evicted = Images dropped this session
+diagnostics-budget = { $used } of { $budget }
+diagnostics-startup = Startup took { $time }
+diagnostics-profiler = Show profiler
+settings-window-per-account = Saved separately for each account
+settings-window-key-protection = Key protection
+settings-window-check-updates = Check for updates
+settings-updates = Updates
+settings-check-updates = Check for updates automatically
+settings-check-updates-hint = Asks GitHub for new releases when Notedeck starts and once a day
+settings-update-channel = Release channel
+settings-update-channel-hint = Beta gets new features first, and sometimes new bugs
+settings-update-channel-stable = Stable
+settings-update-channel-beta = Beta
+settings-control = Scripting
+settings-control-api = Control API
+settings-control-api-hint = Lets scripts on this computer publish notes and open views. They connect using the socket and token in storage/apps/control/info.json, in the notedeck folder.
+settings-links = Links
+settings-links-default = Open links
+settings-links-default-hint = What clicking a link does, unless a domain below says otherwise
+settings-links-behavior-browser = In the browser
+settings-links-behavior-preview = As a preview
+settings-links-behavior-copy = Copy them
+settings-links-browser = Always open in the browser
+settings-links-domains-hint = Domains separated by commas. Subdomains are included.
+settings-links-preview = Always preview
+settings-links-copy = Always copy
+settings-links-web-clients = Open nostr web client links here
+settings-links-web-clients-hint = Links to profiles and notes on sites like njump.me and primal.net open in Notedeck
+link-copied = Link copied
+link-confirm-title = Check this link
+link-confirm-imitates = { $host } looks like { $known }, but it isn't
+link-confirm-unicode = { $host } uses letters that can look like other letters
+link-confirm-cancel = Don't open
+link-confirm-open = Open anyway
+link-preview-title = Link preview
+link-preview-loading = Loading…
+link-preview-failed = Couldn't load the page: { $error }
+link-preview-no-text = There's no text to show
+link-preview-open = Open in browser
+link-preview-copy = Copy link
+link-preview-always-browse = Always open { $host } in the browser
+update-title = Updates
+update-checking = Checking for updates…
+update-up-to-date = Notedeck { $version } is the newest version
+update-failed = Couldn't check for updates: { $error }
+update-retry = Try again
+update-available = Notedeck { $version } is available
+update-download = Download and install on restart
+update-get = Get the update
+update-later = Later
+update-downloading = Downloading Notedeck { $version }…
+update-staged = Notedeck { $version } will be installed when Notedeck closes
+update-restart = Restart now
+
+## Apps
+
+app-columns = Columns
+app-settings = Settings
+settings-apps = Apps
+settings-app-enabled = Show { $name }
+
+## App permissions
+
+settings-permissions = { $app } permissions
+permission-publish = Publish events
+permission-read-contacts = Read your contacts
+permission-camera = Use the camera
+permission-network = Fetch from the web
+permission-ask = Ask
+permission-allow = Allow
+permission-deny = Deny
+permission-title = Permission
+permission-prompt = Allow { $app } to: { $capability }?
+permission-prompt-hint = You can change this later in settings.
+permission-not-declared = This app didn't ask to: { $capability }
+permission-denied = Not allowed to: { $capability }. You can change this in settings.
+permission-asking = Allow "{ $capability }" and try again
+
+## Notification settings
+
+notification-settings-enabled = Show desktop notifications
+notification-settings-notify-about = Notify me about
+notification-settings-mentions = Mentions
+notification-settings-replies = Replies
+notification-settings-direct-messages = Direct messages
+notification-settings-zaps = Zaps
+notification-settings-only-follows = Only from people I follow
+notification-settings-only-follows-hint = Zaps always notify, since they can't be spammed for free
+notification-settings-quiet-hours = Quiet hours
+notification-settings-quiet-hours-enabled = Don't notify me during quiet hours
+notification-settings-quiet-from = From
+notification-settings-quiet-until = until
+notification-settings-background = Background
+notification-settings-background-enabled = Keep listening while Notedeck is in the background
+notification-settings-background-hint = Uses more battery, and shows a notification while it's running
+notification-rules = Rules
+notification-rules-hint = Rules are checked from the top and the first one that matches decides. If none match, the settings above apply.
+notification-rule-enabled = Use this rule
+notification-rule-notify = Notify
+notification-rule-mute = Mute
+notification-rule-when = when
+notification-rule-raise = Move up
+notification-rule-remove = Remove rule
+notification-rule-always = for everything
+notification-rule-add = Add rule
+notification-rule-add-condition = Add condition
+notification-rule-remove-condition = Remove condition
+notification-rule-kind = it's one of
+notification-rule-within-wot = the sender is within this many hops of me
+notification-rule-outside-wot = the sender is further than this many hops from me
+notification-rule-contains = the note contains
+notification-rule-zap-at-least = it's a zap of at least
+notification-rules-copy = Copy rules as JSON
+notification-rules-import = Import rules
+notification-rules-import-hint = Paste rules JSON here to replace the rules above
+
+## Notifications
+
+notification-someone = Someone
+notification-mentioned = { $name } mentioned you
+notification-replied = { $name } replied
+notification-direct-message = New message from { $name }
+notification-zapped = { $name } zapped you
+notification-listening = Listening for messages and mentions
+
+## Thread notifications
+
+thread-notify = Notifications for this thread
+thread-notify-default = Only when I'm mentioned
+thread-notify-watch = Every reply
+thread-notify-mute = Never
+
+## Drafts
+
+draft-restored = Restored your unfinished draft
+draft-discard = Discard draft
+reply-mentions = Replying to
+reply-mention-remove = Don't notify them
+reply-mention-add = Notify them again
+reply-old-note = This note is { $age } old
+
+## Locations
+
+location-add = Add a location
+location-remove = Remove
+location-search = Search
+location-search-hint = Search for a place
+location-search-failed = Couldn't search for places: { $error }
+location-no-results = Nothing found for "{ $query }"
+location-open-in-maps = Open in maps
+
+## Note poster
+
+poster-note-not-found = Couldn't find this note
+poster-save = Save as PNG
+poster-copy = Copy image
+poster-saved = Saved to { $path }
+poster-save-failed = Couldn't save the image: { $error }
+poster-copied = Copied to the clipboard
+poster-copy-failed = Couldn't copy the image: { $error }
+
+## Profiles
+
+profile-qr-code = QR code
+profile-show-qr = Show QR code
+profile-card-open = Open profile
+profile-card-follow = Follow
+profile-card-unfollow = Unfollow
+people-picker-hint = Name, npub or nostr address
+people-picker-invalid = That isn't a key or a nostr address
+people-picker-untrusted = They aren't in your web of trust
+profile-report = Report
+
+## Reports
+
+report-note-title = Report a note from { $name }
+report-profile-title = Report { $name }
+report-hint = Reports are public and go to your relays
+report-spam = Spam
+report-impersonation = Impersonation
+report-illegal = Illegal content
+report-nudity = Nudity
+report-profanity = Profanity or hateful speech
+report-malware = Malware
+report-other = Something else
+report-reason = Anything else moderators should know (optional)
+report-label = Also label it for moderation relays
+report-label-hint = Publishes a NIP-32 label alongside the report
+report-cant-sign = Log in with an account that can post to send reports
+report-send = Send report
+moderation-labeled = Reported by your moderators: { $label }
+moderation-show = Show
+note-deleted = You deleted this note
+note-reposted = reposted
+note-repost-loading = Looking for the reposted note…
+note-repost = Repost
+note-undo-repost = Undo repost
+note-quote = Quote
+note-highlights = { $count ->
+    [one] 1 highlight
+   *[other] { $count } highlights
+}
+note-client = via { $client }
+note-client-hint = The app this note says it was posted with
+
+## Relays
+
+relays-title = Relays
+relays-groups = Groups
+relays-relay-list = Your relay list
+relays-relay-list-read-only = Log in with your private key to edit your relay list.
+relays-relay-list-empty = You haven't published a relay list yet.
+relays-read = Read
+relays-write = Write
+relays-publish = Publish
+relays-discard = Discard changes
+relays-local = Local relays
+relays-local-hint = Used on this device only, not published.
+relays-other = Other connections
+
+## Relay groups
+
+relay-groups-hint = Name a set of relays to send some posts only there, like a paid relay only friends read.
+relay-groups-name-hint = Group name
+relay-groups-add = Add group
+relay-groups-delete = Delete
+relay-groups-not-connected = { $relay } (not connected)
+relay-groups-all-relays = All relays
+relay-groups-post-to = To: { $group }
+relay-groups-post-to-hint = Which relays this post is sent to
+
+## Editing notes
+
+note-edit-republish-hint = A new version is published, and relays keep it in place of this one.
+note-edit-supersede-hint = Notes can't be changed once they're out. A corrected copy that quotes this one is posted, and relays are asked to delete the original. Anyone who already saw it may still have it.
+note-edit-content = Content
+note-edit-tags = Tags, one per line
+note-edit-bad-tag = Line { $line } isn't a tag, write it like ["t", "nostr"]
+note-edit-changes = What changes
+note-edit-no-changes = Nothing has changed yet
+note-edit-republish = Republish
+note-edit-supersede = Delete and repost corrected
+
+## Note source
+
+source-note-not-found = Couldn't find this note
+source-copy = Copy JSON
+source-tags = Tags
+source-no-tags = No tags
+source-valid = Id and signature verified
+source-bad-id = The id doesn't match the note's contents
+source-bad-signature = The signature is invalid
+source-bad-delegation = It claims to be posted on someone's behalf, but they didn't allow it
+source-malformed = Couldn't check this note's signature
+
+## Bookmarks
+
+bookmarks-main-list = Bookmarks
+bookmarks-save-to = Save to
+bookmarks-private = Private
+bookmarks-private-hint = Encrypted so only you can see it's bookmarked
+bookmarks-new-set = New set
+bookmarks-new-set-hint = Set name
+bookmarks-rename = Rename
+bookmarks-delete-set = Delete set
+bookmarks-delete-set-hint = Double click to delete this set
+bookmarks-remove = Remove bookmark
+bookmarks-empty = Nothing bookmarked yet
+bookmarks-loading = Loading bookmarked note…
+
+## My posts
+
+my-posts-all = Everything
+my-posts-empty = Nothing published yet
+my-posts-edit-as-new = Edit as new
+my-posts-edit = Edit…
+my-posts-lists = Profile and lists
+my-posts-delete = Delete
+my-posts-delete-hint = Double click to ask relays to delete this
+my-posts-sending = Sending…
+my-posts-accepted = Accepted by { $accepted }/{ $total } relays
+my-posts-not-sent-here = Not sent from here
+my-posts-not-sent-here-hint = Sent from another device or an earlier session, so we don't know which relays took it
+toast-note-deleted = Asked your relays to delete it
+toast-delete-failed = Couldn't delete it: { $error }
+toast-note-edited = Published your edit
+toast-edit-failed = Couldn't publish your edit: { $error }
+toast-profile-save-failed = Couldn't save your profile: { $error }
+toast-pow-sign-failed = Couldn't sign your post after mining it. Your draft is still here.
+toast-deck-copied = Deck copied to the clipboard
+toast-deck-published = Deck published, its naddr is on the clipboard
+toast-deck-publish-failed = Couldn't publish the deck. Is a signing key set up for this account?
+toast-reposted = Reposted. You can undo it from the repost button for a little while.
+toast-repost-failed = Couldn't repost it: { $error }
+toast-repost-undone = Took back your repost
+
+## Highlights
+
+highlight-title = Highlight a passage
+highlight-hint = Select the part you want to highlight.
+highlight-nothing-selected = Nothing selected yet
+highlight-comment = Add a comment (optional)
+highlight-cant-sign = Log in with an account that can post to highlight
+highlight-publish = Highlight
+highlight-from = from { $source }
+highlights-empty = No highlights yet
+toast-highlighted = Highlighted
+toast-highlight-failed = Couldn't highlight it: { $error }
+
+## Trending
+
+trending-notes = Notes
+trending-hashtags = Hashtags
+trending-loading = Working out what's trending…
+trending-empty = Nothing trending yet
+trending-service-failed = The trending service didn't answer, showing what your relays sent
+trending-people = { $count ->
+    [one] 1 person
+   *[other] { $count } people
+}
+
+## Explore people
+
+explore-people-all = Everything
+explore-people-empty = No follow packs yet. Your relays may still be sending them.
+explore-people-follow-all = Follow { $count ->
+    [one] the one person
+   *[other] all { $count }
+} you don't follow yet
+explore-people-following-all = You follow everyone here
+follow-pack-by = by { $author }
+follow-pack-more = and { $count } more
+follow-pack-everyone = Everyone ({ $count })
+toast-followed-pack = Followed { $count ->
+    [one] one person
+   *[other] { $count } people
+}
+
+## Media
+
+media-empty = No images or videos yet
+media-open-note = Open note
+media-copy-link = Copy link
+media-failed = Couldn't load this image
+media-open-video = Open video in browser
+media-viewer-close = Close
+media-viewer-previous = Previous
+media-viewer-next = Next
+media-rotate-left = Rotate left
+media-rotate-right = Rotate right (R)
+media-save = Save
+media-copy-image = Copy image
+media-working = Working…
+media-saved = Saved to { $path }
+media-copied = Copied to the clipboard
+media-export-failed = Something went wrong: { $error }
+media-blocked-host = It's from a host you blocked
+
+## Logs
+logs-title = Logs
+logs-search = Search
+logs-export = Export
+logs-clear = Clear
+logs-exported = Logs saved to { $path }
+logs-export-failed = Couldn't save the logs: { $error }
+logs-module-relays = Relays
+logs-module-timelines = Timelines
+logs-module-media = Media
+logs-module-accounts = Accounts
+logs-module-wallet = Wallet
+logs-module-other = Other
+
+## Errors
+toast-relay-error = Lost the connection to { $relay }
+toast-relay-reconnected = Reconnected to { $relay }, catching up
+toast-timeline-error = Couldn't update a timeline: { $error }
+toast-event-error = Something went wrong handling relay events: { $error }
+toast-dictionary-error = Couldn't load the { $language } spellcheck dictionary
+toast-hashtags-error = Couldn't update followed hashtags: { $error }
+toast-notification-rules-copied = Notification rules copied
+toast-notification-rules-imported = { $count ->
+    [one] Imported { $count } notification rule
+   *[other] Imported { $count } notification rules
+}
+toast-notification-rules-invalid = Those notification rules couldn't be read: { $error }
+toast-thread-muted = You won't be notified about this thread
+toast-thread-watched = You'll be notified about every reply in this thread
+toast-thread-default = This thread notifies you as usual
+toast-reported = Report sent
+toast-report-failed = Couldn't send the report: { $error }
+toast-follow-error = Couldn't update who you follow: { $error }
+toast-media-servers-error = Couldn't publish your media servers: { $error }
+toast-wallet-error = Couldn't connect the wallet: { $error }
+toast-camera-error = Couldn't open the camera
+toast-camera-no-qr = There's no QR code in that picture
+toast-wallet-no-key = A wallet is saved with an account's private key, sign in with one first
+toast-refreshing-timelines = Fetching all timelines again
+toast-check-in-no-account = Log in with an account that can post to check in
+toast-check-in-error = Couldn't check in: { $error }
+toast-mute-list-error = Couldn't add them to your mute list: { $error }
+crash-title = Notedeck crashed
+crash-body = Notedeck crashed last time it ran. This report can help us fix it, please include it if you file a bug.
+crash-copy = Copy report
+crash-dismiss = Dismiss
+key-lock-title = Unlock your keys
+key-lock-body = Your account keys are locked with a passphrase.
+key-lock-passphrase = Passphrase
+key-lock-unlock = Unlock
+key-lock-wrong-passphrase = That passphrase didn't unlock your keys
+key-lock-system-failed = Your account keys are encrypted with a password kept by your system, and it couldn't be read.
+key-lock-retry = Try again
+key-lock-skip = Continue without keys
+key-lock-skip-hint = Without your keys you can read, but not post or sign.
+key-protection-title = Key protection
+key-protection-locked = Your keys are locked
+key-protection-unlock-first = Unlock them to change how they're protected.
+key-protection-none = Your keys aren't encrypted
+key-protection-system = Your keys are encrypted with a password kept by { $store }
+key-protection-system-missing = Your keys are encrypted with a password kept by a system keychain that isn't available
+key-protection-passphrase = Your keys are locked with a passphrase
+key-protection-use-system = Keep the password in { $store }
+key-protection-passphrase-hint = Or use a passphrase you'll type each time Notedeck starts:
+key-protection-new-passphrase = New passphrase
+key-protection-confirm = Type it again
+key-protection-mismatch = The passphrases don't match
+key-protection-set-passphrase = Use passphrase
+
+## Hidden
+
+hidden-sync-mute-list = Also add people I hide to my mute list
+hidden-sync-mute-list-hint = Publishes your updated mute list, so other Nostr apps hide them too
+hidden-people = People
+hidden-notes = Notes
+hidden-none = Nothing hidden
+hidden-unhide = Unhide
+toast-note-hidden = Note hidden. Bring it back from Settings → Hidden.
+toast-author-hidden = Their notes are hidden. Bring them back from Settings → Hidden.
+
+## Data export
+
+export-title = Export my data
+export-hint = Saves everything you've posted that this device has seen: notes, calendar events, RSVPs and profile versions. You get the signed events as JSON lines and a page you can read in a browser.
+export-dest = Save to folder
+export-start = Export
+export-working = Working…
+import-title = Import an export
+import-hint = Reads an export folder back in, for example after moving to a new computer.
+import-source = Export folder
+import-rebroadcast = Also send the events to my relays
+import-start = Import
+export-digest-title = Nostr archive for { $npub }
+export-kind-profile = Profile
+export-kind-note = Note
+export-kind-follows = Follows
+export-kind-repost = Repost
+export-kind-reaction = Reaction
+export-kind-calendar-event = Calendar event
+export-kind-rsvp = RSVP
+export-kind-other = Kind { $kind }
+toast-export-done = Exported { $count ->
+    [one] one event
+   *[other] { $count } events
+} to { $path }
+toast-export-failed = Export failed: { $error }
+toast-import-done = Imported { $count ->
+    [one] one event
+   *[other] { $count } events
+}
+toast-import-unverified = Imported { $count ->
+    [one] one event
+   *[other] { $count } events
+}, but the archive doesn't match its manifest and may have been changed
+toast-import-failed = Import failed: { $error }
+backup-title = Import from another client
+backup-hint = Reads follows, relays and mutes from a Damus, Amethyst or nos2x backup, or a file of raw events. You'll see what's new before anything is published.
+backup-source = Backup file
+backup-preview = Preview
+backup-new-follows = { $count ->
+    [one] One person to follow
+   *[other] { $count } people to follow
+}
+backup-new-mutes = { $count ->
+    [one] One person to mute
+   *[other] { $count } people to mute
+}
+backup-new-relays = { $count ->
+    [one] One relay to add
+   *[other] { $count } relays to add
+}
+backup-apply = Add to my lists and publish
+backup-discard = Cancel
+toast-backup-nothing-new = Everything in that backup is already on your lists
+toast-backup-applied = Backup added to your lists
+toast-backup-failed = Couldn't import the backup: { $error }
+blocklist-title = Blocklists
+blocklist-hint = Your blocked relays and media hosts. Importing adds to them, nothing is unblocked.
+blocklist-file = File
+blocklist-export = Export
+blocklist-import = Import
+toast-blocklist-exported = Saved your blocklists to { $path }
+toast-blocklist-imported = { $count ->
+    [one] Blocked 1 more relay or host
+   *[other] Blocked { $count } more relays and hosts
+}
+toast-blocklist-failed = Couldn't move your blocklists: { $error }
+storage-title = Storage
+storage-summary = { $count ->
+    [one] One note
+   *[other] { $count } notes
+}, { $size } on disk
+storage-not-counted = Count the notes to see what's taking up space
+storage-other-kinds = Everything else
+storage-count = Count notes
+storage-retention = Compaction
+storage-retention-none = Compacting keeps everything, pick kinds to clear out in settings
+storage-retention-untrusted = Compacting drops { $kinds } older than { $months } months from people outside your web of trust
+storage-retention-all = Compacting drops { $kinds } older than { $months } months from anyone but you
+storage-retention-hint = Compaction copies what's kept into a new database, which takes over the next time Notedeck starts
+storage-retention-edit = Change
+storage-compact = Compact now
+storage-compacted-restart = Restart Notedeck to finish compacting
+storage-counting = Counted { $count } notes
+storage-compacting = Went through { $count } notes
+storage-cancel = Cancel
+toast-compacted = Compacted storage: kept { $kept }, dropped { $dropped }. Restart to use it.
+toast-storage-failed = Storage maintenance failed: { $error }
+toast-sync-needs-key = Syncing settings needs an account you can sign with
+toast-sync-nothing-to-restore = There are no synced settings on your relays yet
+toast-sync-restored = Settings restored from your relays
+toast-sync-published = Settings sent to your relays
+toast-sync-up-to-date = Settings are already in sync
+
+## Spellcheck
+
+spellcheck-no-suggestions = No suggestions
+spellcheck-learn = Add to dictionary
+
+## Composer pickers
+
+emoji-add = Add an emoji
+emoji-skin-tone = Skin tone
+emoji-recent = Recently used
+emoji-smileys = Smileys
+emoji-people = People
+emoji-animals = Animals and nature
+emoji-food = Food and drink
+emoji-things = Things
+emoji-symbols = Symbols
+gif-add = Add a GIF
+gif-not-set-up = Set up GIF search in Settings to find GIFs here
+gif-search-hint = Search GIFs
+gif-search = Search
+gif-search-failed = GIF search failed: { $error }
+gif-no-results = No GIFs found
+
+## Publishing
+
+publish-accepted = Posted, accepted by { $accepted }/{ $total } relays
+publish-failed = No relay accepted your note
+publish-rolled-back = No relay accepted your note, so it was taken out of your columns. You can send it again from Unsent notes.
+note-pending = · sending
+note-pending-hint = No relay has accepted this yet
+unsent-title = Unsent notes
+unsent-hint = No relay accepted these. Send them again or leave them out.
+unsent-retry = Retry
+unsent-discard = Discard
+publish-rejected = { $relay } didn't accept it
+publish-no-answer = { $relay } didn't answer
+
+## NIP-05
+
+nip05-verified = { $nip05 } confirms this is them
+nip05-mismatch = { $nip05 } doesn't list this account. It may be someone pretending to be them.
+
+## Composer JSON preview
+
+preview-json = Preview JSON
+preview-json-copy = Copy JSON
+preview-json-pow = Proof-of-work is mined when you post, so the id, nonce and signature will change.
+preview-tag-root = The thread this replies to
+preview-tag-reply = The note this replies to
+preview-tag-quote = A note this mentions or quotes
+preview-tag-p = Someone who'll be notified
+preview-tag-t = A hashtag
+preview-tag-client = The app it was posted from
+preview-tag-expiration = When relays may delete it (NIP-40)
+preview-tag-location = Where it was posted from
+preview-tag-nonce = Proof-of-work (NIP-13)
+
+## Uploads
+upload-working = Uploading { $count ->
+    [one] 1 file
+   *[other] { $count } files
+}…
+upload-drop-hint = Drop files here to attach them
+upload-failed = Upload failed: { $error }
+upload-dismiss = Dismiss
+upload-no-servers = Add a media server in settings to attach files
+upload-file = { $name } ({ $size })
+upload-cancel = Cancel this upload
+upload-paste-image = Attach the image on the clipboard
+upload-pasted-image = Pasted image
+upload-no-clipboard-image = There's no image on the clipboard: { $error }
+
+## Wallet
+wallet-connect-hint = Paste a Nostr Wallet Connect uri from your wallet, or open a nostr+walletconnect link, to see your balance and pay invoices from here.
+wallet-invalid-uri = That isn't a wallet connect uri
+wallet-connect = Connect
+wallet-loading = Loading…
+wallet-connecting = Connecting…
+wallet-disconnect = Disconnect
+wallet-refresh = Refresh
+wallet-transactions = Transactions
+wallet-no-transactions = No transactions yet
+wallet-send = Send
+wallet-invoice-hint = Lightning invoice
+wallet-pay = Pay
+wallet-invoice-no-amount = Paste an invoice with an amount
+wallet-receive = Receive
+wallet-amount-sats = Amount (sats)
+wallet-description = Description
+wallet-create-invoice = Create invoice
+wallet-working = Waiting for the wallet…
+wallet-paid = Paid
+wallet-copy-invoice = Copy
+wallet-error = The wallet said: { $error }
+wallet-open-note = Note
+wallet-zapped-note = Open the zapped note
+wallet-scan-qr = Scan QR code
+wallet-drop-qr = Or drop a picture of the wallet's QR code on the window
+wallet-pairing = New wallet connection
+wallet-pairing-testing = Asking the wallet about itself…
+wallet-pairing-no-alias = your wallet
+wallet-pairing-works = Connected to { $wallet }
+wallet-pairing-methods = Allowed: { $methods }
+wallet-save = Save
+wallet-cancel = Cancel
+
+## Hashtags
+hashtag-open-column = Open as column
+hashtag-follow = Follow hashtag
+hashtag-unfollow = Unfollow hashtag
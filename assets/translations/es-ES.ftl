@@ -0,0 +1,84 @@
+## Session
+
+session-read-only-banner = No has iniciado sesión. Puedes explorar, pero no publicar.
+session-pubkey-only-banner = Esta cuenta es de solo lectura. Añade tu clave privada para publicar, reaccionar y enviar zaps.
+session-log-in = Iniciar sesión
+session-need-login = Necesitas iniciar sesión para hacer esto
+session-need-key = Esta cuenta es de solo lectura. Inicia sesión con tu clave privada para hacer esto
+
+## Relative times
+
+time-ago-now = ahora
+time-ago-seconds = { $count } s
+time-ago-minutes = { $count } min
+time-ago-hours = { $count } h
+time-ago-days = { $count } d
+time-ago-weeks = { $count } sem
+time-ago-months = { $count } mes
+time-ago-years = { $count } a
+
+## Column titles
+
+route-unknown = Desconocido
+route-thread = Hilo
+route-reply = Responder
+route-quote = Citar
+route-profile = Perfil
+route-relays = Relés
+route-settings = Ajustes
+route-notification-settings = Notificaciones
+route-accounts = Cuentas
+route-add-account = Añadir cuenta
+route-compose-note = Escribir nota
+route-add-column = Añadir columna
+route-add-hashtag-column = Añadir columna de hashtag
+route-support = Soporte de Damus
+route-new-deck = Añadir panel
+route-edit-deck = Editar panel
+route-edit-profile = Editar perfil
+
+## Timelines
+
+timeline-notes = Notas
+timeline-notes-and-replies = Notas y respuestas
+timeline-jump-to-top = Ir arriba
+timeline-jump-to-oldest-new = Ir a la nota nueva más antigua
+
+## Settings
+
+settings-language = Idioma
+settings-language-system = Idioma del sistema ({ $language })
+settings-time = Fecha y hora
+settings-clock = Reloj
+settings-clock-auto = Según el idioma
+settings-clock-12 = 12 horas (10:00 PM)
+settings-clock-24 = 24 horas (22:00)
+settings-week-start = La semana empieza el
+settings-week-start-auto = Según el idioma
+settings-week-start-sunday = Domingo
+settings-week-start-monday = Lunes
+settings-week-start-saturday = Sábado
+settings-relays = Relés
+settings-notifications = Notificaciones
+
+## Notification settings
+
+notification-settings-enabled = Mostrar notificaciones de escritorio
+notification-settings-notify-about = Notificarme sobre
+notification-settings-mentions = Menciones
+notification-settings-replies = Respuestas
+notification-settings-direct-messages = Mensajes directos
+notification-settings-zaps = Zaps
+notification-settings-only-follows = Solo de personas que sigo
+notification-settings-quiet-hours = Horas de silencio
+notification-settings-quiet-hours-enabled = No notificarme durante las horas de silencio
+notification-settings-quiet-from = De
+notification-settings-quiet-until = a
+
+## Notifications
+
+notification-someone = Alguien
+notification-mentioned = { $name } te ha mencionado
+notification-replied = { $name } ha respondido
+notification-direct-message = Nuevo mensaje de { $name }
+notification-zapped = { $name } te ha enviado un zap
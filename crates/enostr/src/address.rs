@@ -0,0 +1,89 @@
+use crate::Pubkey;
+
+static HRP_NADDR: bech32::Hrp = bech32::Hrp::parse_unchecked("naddr");
+
+/// A NIP-19 `naddr`, pointing at the latest version of an addressable
+/// event rather than at one particular note
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Address {
+    pub kind: u32,
+    pub pubkey: Pubkey,
+    /// The event's `d` tag
+    pub identifier: String,
+}
+
+impl Address {
+    /// Decode an naddr, with or without a `nostr:` prefix
+    pub fn from_bech(s: &str) -> Option<Self> {
+        let s = s.strip_prefix("nostr:").unwrap_or(s);
+        let (hrp, data) = bech32::decode(s).ok()?;
+        if hrp != HRP_NADDR {
+            return None;
+        }
+
+        let (mut identifier, mut pubkey, mut kind) = (None, None, None);
+        let mut rest = data.as_slice();
+        while rest.len() >= 2 {
+            let (typ, len) = (rest[0], rest[1] as usize);
+            let value = rest.get(2..2 + len)?;
+            match typ {
+                0 => identifier = Some(String::from_utf8(value.to_vec()).ok()?),
+                2 => pubkey = Some(Pubkey::new(value.try_into().ok()?)),
+                3 => kind = Some(u32::from_be_bytes(value.try_into().ok()?)),
+                // relay hints, and anything newer than us
+                _ => {}
+            }
+            rest = &rest[2 + len..];
+        }
+
+        Some(Address {
+            kind: kind?,
+            pubkey: pubkey?,
+            identifier: identifier?,
+        })
+    }
+
+    pub fn to_bech(&self) -> Option<String> {
+        let mut data = Vec::with_capacity(self.identifier.len() + 44);
+        data.extend([0, u8::try_from(self.identifier.len()).ok()?]);
+        data.extend(self.identifier.as_bytes());
+        data.extend([2, 32]);
+        data.extend(self.pubkey.bytes());
+        data.extend([3, 4]);
+        data.extend(self.kind.to_be_bytes());
+        bech32::encode::<bech32::Bech32>(HRP_NADDR, &data).ok()
+    }
+
+    /// The `kind:pubkey:d` form used in `a` tags
+    pub fn coordinate(&self) -> String {
+        format!("{}:{}:{}", self.kind, self.pubkey.hex(), self.identifier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naddr_roundtrip() {
+        let address = Address {
+            kind: 31923,
+            pubkey: Pubkey::new([7; 32]),
+            identifier: "meetup".to_owned(),
+        };
+        let naddr = address.to_bech().unwrap();
+        assert!(naddr.starts_with("naddr1"));
+        assert_eq!(Address::from_bech(&naddr), Some(address.clone()));
+        assert_eq!(
+            Address::from_bech(&format!("nostr:{naddr}")),
+            Some(address.clone())
+        );
+        assert_eq!(
+            address.coordinate(),
+            format!("31923:{}:meetup", "07".repeat(32))
+        );
+
+        let npub = Pubkey::new([7; 32]).to_bech().unwrap();
+        assert_eq!(Address::from_bech(&npub), None);
+    }
+}
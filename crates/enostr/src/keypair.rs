@@ -1,10 +1,16 @@
+use nostr::nips::nip06::FromMnemonic;
+use nostr::nips::nip44;
 use nostr::nips::nip49::EncryptedSecretKey;
+use nostr::secp256k1::rand::RngCore;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::Error;
 use crate::Pubkey;
 use crate::SecretKey;
 
+static HRP_NSEC: bech32::Hrp = bech32::Hrp::parse_unchecked("nsec");
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Keypair {
     pub pubkey: Pubkey,
@@ -63,6 +69,23 @@ impl<'a> FilledKeypair<'a> {
             secret_key: self.secret_key.to_owned(),
         }
     }
+
+    /// NIP-44 encrypt `plaintext` so only we and `to` can read it. Pass
+    /// our own pubkey for things only we should read, like private list
+    /// items.
+    pub fn nip44_encrypt(&self, to: &Pubkey, plaintext: &str) -> crate::Result<String> {
+        let to = nostr::PublicKey::from_slice(to.bytes()).map_err(|_| Error::InvalidPublicKey)?;
+        nip44::encrypt(self.secret_key, &to, plaintext, nip44::Version::V2)
+            .map_err(|err| Error::Generic(err.to_string()))
+    }
+
+    /// Decrypt a NIP-44 payload sent to us by `from`
+    pub fn nip44_decrypt(&self, from: &Pubkey, payload: &str) -> crate::Result<String> {
+        let from =
+            nostr::PublicKey::from_slice(from.bytes()).map_err(|_| Error::InvalidPublicKey)?;
+        nip44::decrypt(self.secret_key, &from, payload)
+            .map_err(|err| Error::Generic(err.to_string()))
+    }
 }
 
 impl FullKeypair {
@@ -85,12 +108,39 @@ impl FullKeypair {
         }
     }
 
+    /// A new key along with the 12 word recovery phrase it comes from
+    pub fn generate_with_mnemonic() -> (Self, String) {
+        let mut entropy = [0u8; 16];
+        nostr::secp256k1::rand::rngs::OsRng.fill_bytes(&mut entropy);
+        let phrase = nostr::bip39::Mnemonic::from_entropy(&entropy)
+            .expect("16 bytes is a valid entropy length")
+            .to_string();
+        let keypair = Self::from_mnemonic(&phrase).expect("we just made the phrase");
+        (keypair, phrase)
+    }
+
+    /// The key a BIP-39 recovery phrase derives, as in NIP-06
+    pub fn from_mnemonic(phrase: &str) -> crate::Result<Self> {
+        let keys = nostr::Keys::from_mnemonic(phrase, None)
+            .map_err(|err| Error::Generic(err.to_string()))?;
+        Ok(FullKeypair {
+            pubkey: Pubkey::new(keys.public_key().to_bytes()),
+            secret_key: keys.secret_key().clone(),
+        })
+    }
+
     pub fn to_keypair(self) -> Keypair {
         Keypair {
             pubkey: self.pubkey,
             secret_key: Some(self.secret_key),
         }
     }
+
+    /// The bech32 encoded secret key, for showing to the user when they
+    /// need to back it up
+    pub fn nsec(&self) -> Option<String> {
+        bech32::encode::<bech32::Bech32>(HRP_NSEC, &self.secret_key.to_secret_bytes()).ok()
+    }
 }
 
 impl std::fmt::Display for Keypair {
@@ -137,3 +187,50 @@ impl SerializableKeypair {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nip06_vector() {
+        let keypair = FullKeypair::from_mnemonic(
+            "leader monkey parrot ring guide accident before fence cannon height naive bean",
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(keypair.secret_key.to_secret_bytes()),
+            "7f7ff03d123792d6ac594bfa67bf6d0c0ab55b6b1fdb6249303fe861f1ccba9a"
+        );
+        assert_eq!(
+            keypair.pubkey.hex(),
+            "17162c921dc4d2518f9a101db33695df1afb56ab82f5ff3e5da6eec3ca5cd917"
+        );
+
+        let (generated, phrase) = FullKeypair::generate_with_mnemonic();
+        assert_eq!(phrase.split_whitespace().count(), 12);
+        assert_eq!(FullKeypair::from_mnemonic(&phrase).unwrap(), generated);
+        assert!(FullKeypair::from_mnemonic("not a recovery phrase").is_err());
+    }
+
+    #[test]
+    fn test_nip44_to_self() {
+        let keypair = FullKeypair::generate();
+        let filled = keypair.to_filled();
+
+        let payload = filled
+            .nip44_encrypt(&keypair.pubkey, r#"[["e","abc"]]"#)
+            .unwrap();
+        assert_ne!(payload, r#"[["e","abc"]]"#);
+        assert_eq!(
+            filled.nip44_decrypt(&keypair.pubkey, &payload).unwrap(),
+            r#"[["e","abc"]]"#
+        );
+
+        let other = FullKeypair::generate();
+        assert!(other
+            .to_filled()
+            .nip44_decrypt(&keypair.pubkey, &payload)
+            .is_err());
+    }
+}
@@ -1,24 +1,35 @@
+mod address;
 mod client;
 mod error;
 mod filter;
 mod keypair;
 mod note;
+mod nwc;
 mod profile;
 mod pubkey;
 mod relay;
 
+pub use address::Address;
 pub use client::{ClientMessage, EventClientMessage};
 pub use error::Error;
 pub use ewebsock;
 pub use filter::Filter;
 pub use keypair::{FilledKeypair, FullKeypair, Keypair, SerializableKeypair};
 pub use nostr::SecretKey;
-pub use note::{Note, NoteId};
+pub use note::{Note, NoteId, NoteVerification};
+pub use nwc::{
+    NwcDirection, NwcError, NwcInfo, NwcRequest, NwcResponse, NwcTransaction, NwcUri, ZapInfo,
+    NWC_REQUEST_KIND, NWC_RESPONSE_KIND,
+};
 pub use profile::Profile;
 pub use pubkey::{Pubkey, PubkeyRef};
 pub use relay::message::{RelayEvent, RelayMessage};
-pub use relay::pool::{PoolEvent, PoolRelay, RelayPool};
-pub use relay::subs_debug::{OwnedRelayEvent, RelayLogEvent, SubsDebug, TransferStats};
+pub use relay::metrics::RelayMetrics;
+pub use relay::outbox::{Outbox, TokenBucket};
+pub use relay::pool::{PoolEvent, PoolRelay, Reconnect, RelayPool, RelayTarget};
+pub use relay::subs_debug::{
+    OwnedRelayEvent, OwnedRelayMessage, RelayLogEvent, RelayStats, SubsDebug, TransferStats,
+};
 pub use relay::{Relay, RelayStatus};
 
 pub type Result<T> = std::result::Result<T, error::Error>;
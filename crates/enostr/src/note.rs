@@ -1,4 +1,4 @@
-use crate::{Error, Pubkey};
+use crate::{pubkey::tlv_special, Error, Pubkey};
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
@@ -14,6 +14,7 @@ impl fmt::Debug for NoteId {
 }
 
 static HRP_NOTE: bech32::Hrp = bech32::Hrp::parse_unchecked("note");
+static HRP_NEVENT: bech32::Hrp = bech32::Hrp::parse_unchecked("nevent");
 
 impl NoteId {
     pub fn new(bytes: [u8; 32]) -> Self {
@@ -36,6 +37,18 @@ impl NoteId {
     pub fn to_bech(&self) -> Option<String> {
         bech32::encode::<bech32::Bech32>(HRP_NOTE, &self.0).ok()
     }
+
+    /// Decode a note or nevent
+    pub fn from_bech(s: &str) -> Option<Self> {
+        let (hrp, data) = bech32::decode(s).ok()?;
+        if hrp == HRP_NOTE {
+            data.try_into().ok().map(NoteId)
+        } else if hrp == HRP_NEVENT {
+            tlv_special(&data).map(NoteId)
+        } else {
+            None
+        }
+    }
 }
 
 /// Event is the struct used to represent a Nostr event
@@ -105,6 +118,99 @@ impl Note {
     }
 }
 
+/// The result of checking a note's id and signature ourselves, instead of
+/// trusting whoever handed it to us
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum NoteVerification {
+    Valid,
+    /// The id isn't the hash of the note's contents
+    BadId,
+    BadSignature,
+    /// It claims to be written on someone's behalf (NIP-26), but they
+    /// didn't sign off on it or it's outside what they allowed
+    BadDelegation,
+    /// Not a note we could parse
+    Malformed,
+}
+
+impl NoteVerification {
+    pub fn check(json: &str) -> Self {
+        use nostr::JsonUtil;
+
+        let Ok(event) = nostr::Event::from_json(json) else {
+            return NoteVerification::Malformed;
+        };
+
+        if !event.verify_id() {
+            NoteVerification::BadId
+        } else if !event.verify_signature() {
+            NoteVerification::BadSignature
+        } else if !delegation_ok(json) {
+            NoteVerification::BadDelegation
+        } else {
+            NoteVerification::Valid
+        }
+    }
+}
+
+/// Whether a NIP-26 delegation tag, if there is one, is signed by the
+/// delegator and its conditions hold for the note
+fn delegation_ok(json: &str) -> bool {
+    let Ok(note) = Note::from_json(json) else {
+        return false;
+    };
+    let Some(tag) = note
+        .tags
+        .iter()
+        .find(|tag| tag.first().map(String::as_str) == Some("delegation"))
+    else {
+        return true;
+    };
+    let [_, delegator, conditions, sig, ..] = tag.as_slice() else {
+        return false;
+    };
+
+    conditions_hold(conditions, note.kind, note.created_at)
+        && delegation_signed(delegator, &note.pubkey.hex(), conditions, sig)
+}
+
+fn conditions_hold(conditions: &str, kind: u64, created_at: u64) -> bool {
+    conditions.split('&').all(|condition| {
+        if let Some(value) = condition.strip_prefix("kind=") {
+            value.parse::<u64>().is_ok_and(|k| k == kind)
+        } else if let Some(value) = condition.strip_prefix("created_at<") {
+            value.parse().is_ok_and(|before: u64| created_at < before)
+        } else if let Some(value) = condition.strip_prefix("created_at>") {
+            value.parse().is_ok_and(|after: u64| created_at > after)
+        } else {
+            false
+        }
+    })
+}
+
+fn delegation_signed(delegator: &str, delegatee: &str, conditions: &str, sig: &str) -> bool {
+    use nostr::hashes::{sha256, Hash};
+    use nostr::secp256k1::{schnorr::Signature, Message, XOnlyPublicKey};
+    use std::str::FromStr;
+
+    let (Ok(delegator), Ok(sig)) = (
+        XOnlyPublicKey::from_str(delegator),
+        Signature::from_str(sig),
+    ) else {
+        return false;
+    };
+    let token = format!("nostr:delegation:{delegatee}:{conditions}");
+    let digest = sha256::Hash::hash(token.as_bytes());
+
+    nostr::secp256k1::Secp256k1::verification_only()
+        .verify_schnorr(
+            &sig,
+            &Message::from_digest(digest.to_byte_array()),
+            &delegator,
+        )
+        .is_ok()
+}
+
 impl std::str::FromStr for Note {
     type Err = Error;
 
@@ -133,3 +239,46 @@ impl<'de> Deserialize<'de> for NoteId {
         NoteId::from_hex(&s).map_err(serde::de::Error::custom)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_verification() {
+        let json = r#"{"id":"70b10f70c1318967eddf12527799411b1a9780ad9c43858f5e5fcd45486a13a5","pubkey":"379e863e8357163b5bce5d2688dc4f1dcc2d505222fb8d74db600f30535dfdfe","created_at":1612809991,"kind":1,"tags":[],"content":"test","sig":"273a9cd5d11455590f4359500bccb7a89428262b96b3ea87a756b770964472f8c3e87f5d5e64d8d2e859a71462a3f477b554565c4f2f326cb01dd7620db71502"}"#;
+        assert_eq!(NoteVerification::check(json), NoteVerification::Valid);
+
+        let tampered = json.replace(r#""content":"test""#, r#""content":"tset""#);
+        assert_eq!(NoteVerification::check(&tampered), NoteVerification::BadId);
+
+        assert_eq!(NoteVerification::check("{}"), NoteVerification::Malformed);
+    }
+
+    #[test]
+    fn test_delegation() {
+        use nostr::hashes::{sha256, Hash};
+        use nostr::secp256k1::{Keypair, Message, Secp256k1};
+
+        let secp = Secp256k1::new();
+        let delegator = Keypair::new(&secp, &mut nostr::secp256k1::rand::rngs::OsRng);
+        let delegatee = "379e863e8357163b5bce5d2688dc4f1dcc2d505222fb8d74db600f30535dfdfe";
+        let conditions = "kind=1&created_at>1600000000&created_at<1700000000";
+
+        let token = format!("nostr:delegation:{delegatee}:{conditions}");
+        let digest = sha256::Hash::hash(token.as_bytes()).to_byte_array();
+        let sig = secp
+            .sign_schnorr_no_aux_rand(&Message::from_digest(digest), &delegator)
+            .to_string();
+        let delegator = delegator.x_only_public_key().0.to_string();
+
+        assert!(delegation_signed(&delegator, delegatee, conditions, &sig));
+        assert!(!delegation_signed(&delegator, delegatee, "kind=0", &sig));
+        assert!(!delegation_signed(delegatee, delegatee, conditions, &sig));
+
+        assert!(conditions_hold(conditions, 1, 1612809991));
+        assert!(!conditions_hold(conditions, 0, 1612809991));
+        assert!(!conditions_hold(conditions, 1, 1712809991));
+        assert!(!conditions_hold("kind=1&mystery", 1, 1612809991));
+    }
+}
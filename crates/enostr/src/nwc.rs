@@ -0,0 +1,393 @@
+//! Nostr Wallet Connect (NIP-47). Requests go to the wallet service as
+//! encrypted kind 23194 events and it answers with kind 23195 events,
+//! over relays named in the connection uri.
+
+use nostr::nips::nip04;
+use nostrdb::{Filter, NoteBuilder};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{ClientMessage, Error, FullKeypair, Note, NoteId, NoteVerification, Pubkey, Result};
+
+pub const NWC_REQUEST_KIND: u32 = 23194;
+pub const NWC_RESPONSE_KIND: u32 = 23195;
+
+/// A `nostr+walletconnect://` connection string: who the wallet service
+/// is, which relays it listens on, and the key we talk to it with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NwcUri {
+    pub wallet: Pubkey,
+    pub relays: Vec<String>,
+    pub secret: FullKeypair,
+    pub lud16: Option<String>,
+}
+
+impl NwcUri {
+    pub fn parse(uri: &str) -> Result<Self> {
+        let uri = uri.trim();
+        // deep links don't always have the `//`
+        let rest = uri
+            .strip_prefix("nostr+walletconnect:")
+            .or_else(|| uri.strip_prefix("nostrwalletconnect:"))
+            .map(|rest| rest.trim_start_matches('/'))
+            .ok_or_else(|| Error::Generic("not a wallet connect uri".to_owned()))?;
+
+        let (wallet, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let wallet = Pubkey::from_hex(wallet.trim_end_matches('/'))?;
+
+        let mut relays = vec![];
+        let mut secret = None;
+        let mut lud16 = None;
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match key.as_ref() {
+                "relay" => relays.push(value.into_owned()),
+                "secret" => secret = Some(value.into_owned()),
+                "lud16" => lud16 = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+
+        if relays.is_empty() {
+            return Err(Error::Generic("wallet connect uri has no relay".to_owned()));
+        }
+
+        let secret =
+            secret.ok_or_else(|| Error::Generic("wallet connect uri has no secret".to_owned()))?;
+        let secret = nostr::SecretKey::from_hex(&secret).map_err(|_| Error::DecodeFailed)?;
+        let secret = crate::Keypair::from_secret(secret);
+        let secret = FullKeypair::new(secret.pubkey, secret.secret_key.ok_or(Error::DecodeFailed)?);
+
+        Ok(NwcUri {
+            wallet,
+            relays,
+            secret,
+            lud16,
+        })
+    }
+
+    pub fn to_uri(&self) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        for relay in &self.relays {
+            query.append_pair("relay", relay);
+        }
+        query.append_pair("secret", &self.secret.secret_key.to_secret_hex());
+        if let Some(lud16) = &self.lud16 {
+            query.append_pair("lud16", lud16);
+        }
+
+        format!(
+            "nostr+walletconnect://{}?{}",
+            self.wallet.hex(),
+            query.finish()
+        )
+    }
+
+    /// Encrypt and sign a request. Responses reference the returned id.
+    pub fn request(&self, request: &NwcRequest) -> Result<(NoteId, ClientMessage)> {
+        let wallet = nostr::PublicKey::from_slice(self.wallet.bytes())
+            .map_err(|_| Error::InvalidPublicKey)?;
+        let content = nip04::encrypt(&self.secret.secret_key, &wallet, request.to_json())
+            .map_err(|err| Error::Generic(err.to_string()))?;
+
+        let note = NoteBuilder::new()
+            .kind(NWC_REQUEST_KIND)
+            .content(&content)
+            .start_tag()
+            .tag_str("p")
+            .tag_str(&self.wallet.hex())
+            .sign(&self.secret.secret_key.to_secret_bytes())
+            .build()
+            .ok_or_else(|| Error::Generic("failed to build wallet request".to_owned()))?;
+
+        let id = NoteId::new(*note.id());
+        Ok((id, ClientMessage::event(note)?))
+    }
+
+    /// The wallet's answers to us
+    pub fn response_filter(&self, since: u64) -> Filter {
+        Filter::new()
+            .kinds([NWC_RESPONSE_KIND as u64])
+            .authors([self.wallet.bytes()])
+            .pubkeys([self.secret.pubkey.bytes()])
+            .since(since)
+            .build()
+    }
+
+    /// Check and decrypt a response event. Returns the id of the request
+    /// it answers, if it says.
+    pub fn decrypt_response(&self, note_json: &str) -> Result<(Option<NoteId>, NwcResponse)> {
+        if NoteVerification::check(note_json) != NoteVerification::Valid {
+            return Err(Error::InvalidSignature);
+        }
+
+        let note = Note::from_json(note_json)?;
+        if note.kind != NWC_RESPONSE_KIND as u64 || note.pubkey != self.wallet {
+            return Err(Error::Generic("not a response from our wallet".to_owned()));
+        }
+
+        let wallet = nostr::PublicKey::from_slice(self.wallet.bytes())
+            .map_err(|_| Error::InvalidPublicKey)?;
+        let plaintext = nip04::decrypt(&self.secret.secret_key, &wallet, &note.content)
+            .map_err(|err| Error::Generic(err.to_string()))?;
+        let response: NwcResponse = serde_json::from_str(&plaintext)?;
+
+        let request = note
+            .tags
+            .iter()
+            .find(|tag| tag.len() >= 2 && tag[0] == "e")
+            .and_then(|tag| NoteId::from_hex(&tag[1]).ok());
+
+        Ok((request, response))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NwcRequest {
+    GetInfo,
+    GetBalance,
+    ListTransactions {
+        limit: u32,
+        offset: u32,
+    },
+    PayInvoice {
+        invoice: String,
+    },
+    MakeInvoice {
+        amount_msats: u64,
+        description: String,
+    },
+}
+
+impl NwcRequest {
+    pub fn method(&self) -> &'static str {
+        match self {
+            NwcRequest::GetInfo => "get_info",
+            NwcRequest::GetBalance => "get_balance",
+            NwcRequest::ListTransactions { .. } => "list_transactions",
+            NwcRequest::PayInvoice { .. } => "pay_invoice",
+            NwcRequest::MakeInvoice { .. } => "make_invoice",
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let params = match self {
+            NwcRequest::GetInfo | NwcRequest::GetBalance => json!({}),
+            NwcRequest::ListTransactions { limit, offset } => {
+                json!({ "limit": limit, "offset": offset })
+            }
+            NwcRequest::PayInvoice { invoice } => json!({ "invoice": invoice }),
+            NwcRequest::MakeInvoice {
+                amount_msats,
+                description,
+            } => json!({ "amount": amount_msats, "description": description }),
+        };
+
+        json!({ "method": self.method(), "params": params }).to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NwcError {
+    pub code: String,
+    #[serde(default)]
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NwcResponse {
+    pub result_type: String,
+    #[serde(default)]
+    pub error: Option<NwcError>,
+    #[serde(default)]
+    pub result: Option<Value>,
+}
+
+impl NwcResponse {
+    fn field(&self, name: &str) -> Option<&Value> {
+        self.result.as_ref()?.get(name)
+    }
+
+    /// For `get_balance`, in msats
+    pub fn balance(&self) -> Option<u64> {
+        self.field("balance")?.as_u64()
+    }
+
+    /// For `get_info`
+    pub fn info(&self) -> Option<NwcInfo> {
+        serde_json::from_value(self.result.clone()?).ok()
+    }
+
+    pub fn transactions(&self) -> Option<Vec<NwcTransaction>> {
+        serde_json::from_value(self.field("transactions")?.clone()).ok()
+    }
+
+    /// For `make_invoice`
+    pub fn invoice(&self) -> Option<&str> {
+        self.field("invoice")?.as_str()
+    }
+
+    /// For `pay_invoice`
+    pub fn preimage(&self) -> Option<&str> {
+        self.field("preimage")?.as_str()
+    }
+}
+
+/// What the wallet service says about itself and what we're allowed to
+/// ask of it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct NwcInfo {
+    pub alias: Option<String>,
+    pub network: Option<String>,
+    pub methods: Vec<String>,
+}
+
+impl NwcInfo {
+    pub fn supports(&self, request: &NwcRequest) -> bool {
+        self.methods.iter().any(|method| method == request.method())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NwcDirection {
+    Incoming,
+    Outgoing,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NwcTransaction {
+    #[serde(rename = "type")]
+    pub direction: NwcDirection,
+    #[serde(default)]
+    pub invoice: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub payment_hash: Option<String>,
+    /// In msats
+    pub amount: u64,
+    #[serde(default)]
+    pub fees_paid: u64,
+    pub created_at: u64,
+    #[serde(default)]
+    pub settled_at: Option<u64>,
+    #[serde(default)]
+    pub metadata: Option<Value>,
+}
+
+/// Who a zap was between and what it was for, from the zap request
+/// (NIP-57) that wallets use as the invoice description
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZapInfo {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub note: Option<NoteId>,
+}
+
+impl NwcTransaction {
+    pub fn zap(&self) -> Option<ZapInfo> {
+        let request = self
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("zap_request"))
+            .cloned()
+            .or_else(|| serde_json::from_str(self.description.as_deref()?).ok())?;
+
+        if request.get("kind")?.as_u64()? != 9734 {
+            return None;
+        }
+
+        let sender = Pubkey::from_hex(request.get("pubkey")?.as_str()?).ok()?;
+        let tag = |name: &str| -> Option<String> {
+            request
+                .get("tags")?
+                .as_array()?
+                .iter()
+                .filter_map(|tag| tag.as_array())
+                .find(|tag| tag.first().and_then(|t| t.as_str()) == Some(name))?
+                .get(1)?
+                .as_str()
+                .map(str::to_owned)
+        };
+
+        Some(ZapInfo {
+            sender,
+            recipient: Pubkey::from_hex(&tag("p")?).ok()?,
+            note: tag("e").and_then(|id| NoteId::from_hex(&id).ok()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLET: &str = "b889ff5b1513b641e2a139f661a661364979c5beee91842f8f0ef42ab558e9d4";
+    const SECRET: &str = "71a8c14c1407c113601079c4302dab36460f0ccd0ad506f1f2dc73b5100e4f3c";
+
+    #[test]
+    fn test_parse_uri() {
+        let uri = format!(
+            "nostr+walletconnect://{WALLET}?relay=wss%3A%2F%2Frelay.damus.io&secret={SECRET}&lud16=me%40example.com"
+        );
+        let parsed = NwcUri::parse(&uri).unwrap();
+        assert_eq!(parsed.wallet.hex(), WALLET);
+        assert_eq!(parsed.relays, vec!["wss://relay.damus.io"]);
+        assert_eq!(parsed.lud16.as_deref(), Some("me@example.com"));
+
+        // and back
+        assert_eq!(NwcUri::parse(&parsed.to_uri()).unwrap(), parsed);
+
+        // as a deep link
+        let link =
+            format!("nostr+walletconnect:{WALLET}?relay=wss://relay.damus.io&secret={SECRET}");
+        assert_eq!(NwcUri::parse(&link).unwrap().wallet.hex(), WALLET);
+
+        assert!(NwcUri::parse(&format!("nostr+walletconnect://{WALLET}?secret={SECRET}")).is_err());
+        assert!(NwcUri::parse("https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_info() {
+        let response: NwcResponse = serde_json::from_value(json!({
+            "result_type": "get_info",
+            "result": {
+                "alias": "my node",
+                "network": "mainnet",
+                "methods": ["pay_invoice", "get_balance"],
+                "pubkey": WALLET,
+            },
+        }))
+        .unwrap();
+
+        let info = response.info().unwrap();
+        assert_eq!(info.alias.as_deref(), Some("my node"));
+        assert!(info.supports(&NwcRequest::GetBalance));
+        assert!(!info.supports(&NwcRequest::GetInfo));
+    }
+
+    #[test]
+    fn test_transaction_zap() {
+        let sender = "32e1827635450ebb3c5a7d12c1f8e7b2b514439ac10a67eef3d9fd9c5c68e245";
+        let note = "d0a1ffb8761b974cec4a3be8cbcb2e96a7090dcf465ffeac839aa4ca20c9a59e";
+        let request = json!({
+            "kind": 9734,
+            "pubkey": sender,
+            "content": "",
+            "tags": [["p", WALLET], ["e", note], ["amount", "21000"]],
+        });
+        let transaction: NwcTransaction = serde_json::from_value(json!({
+            "type": "incoming",
+            "description": request.to_string(),
+            "amount": 21000,
+            "created_at": 1700000000,
+        }))
+        .unwrap();
+
+        let zap = transaction.zap().unwrap();
+        assert_eq!(zap.sender.hex(), sender);
+        assert_eq!(zap.recipient.hex(), WALLET);
+        assert_eq!(zap.note.map(|id| id.hex()).as_deref(), Some(note));
+    }
+}
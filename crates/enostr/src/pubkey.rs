@@ -13,6 +13,22 @@ pub struct Pubkey([u8; 32]);
 pub struct PubkeyRef<'a>(&'a [u8; 32]);
 
 static HRP_NPUB: bech32::Hrp = bech32::Hrp::parse_unchecked("npub");
+static HRP_NPROFILE: bech32::Hrp = bech32::Hrp::parse_unchecked("nprofile");
+
+/// The 32 byte `special` entry of a NIP-19 TLV entity like nprofile or
+/// nevent
+pub(crate) fn tlv_special(data: &[u8]) -> Option<[u8; 32]> {
+    let mut rest = data;
+    while rest.len() >= 2 {
+        let (typ, len) = (rest[0], rest[1] as usize);
+        let value = rest.get(2..2 + len)?;
+        if typ == 0 {
+            return value.try_into().ok();
+        }
+        rest = &rest[2 + len..];
+    }
+    None
+}
 
 impl<'a> Borrow<[u8; 32]> for PubkeyRef<'a> {
     fn borrow(&self) -> &[u8; 32] {
@@ -118,6 +134,18 @@ impl Pubkey {
     pub fn to_bech(&self) -> Option<String> {
         bech32::encode::<bech32::Bech32>(HRP_NPUB, &self.0).ok()
     }
+
+    /// Decode an npub or nprofile
+    pub fn from_bech(s: &str) -> Option<Self> {
+        let (hrp, data) = bech32::decode(s).ok()?;
+        if hrp == HRP_NPUB {
+            data.try_into().ok().map(Pubkey)
+        } else if hrp == HRP_NPROFILE {
+            tlv_special(&data).map(Pubkey)
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Display for Pubkey {
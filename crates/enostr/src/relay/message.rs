@@ -3,9 +3,26 @@ use ewebsock::{WsEvent, WsMessage};
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct CommandResult<'a> {
-    event_id: &'a str,
-    status: bool,
-    message: &'a str,
+    pub(crate) event_id: &'a str,
+    pub(crate) status: bool,
+    pub(crate) message: &'a str,
+}
+
+impl CommandResult<'_> {
+    /// Hex id of the event the relay is answering about
+    pub fn event_id(&self) -> &str {
+        self.event_id
+    }
+
+    /// Whether the relay accepted the event
+    pub fn status(&self) -> bool {
+        self.status
+    }
+
+    /// Why, usually only given when it wasn't accepted
+    pub fn message(&self) -> &str {
+        self.message
+    }
 }
 
 pub fn calculate_command_result_size(result: &CommandResult) -> usize {
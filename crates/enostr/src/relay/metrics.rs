@@ -0,0 +1,259 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::{ClientMessage, RelayMessage};
+
+/// Events per second are counted over this long
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+/// How many event ids we remember for spotting duplicates
+const SEEN_CAPACITY: usize = 20_000;
+/// A relay that has come back empty this many times in a row for a broad
+/// subscription stops getting it
+const EMPTY_STREAK_LIMIT: u32 = 3;
+/// ...until it's been this long, in case it has picked something up
+const EMPTY_RETRY_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// A REQ we're waiting on an EOSE for
+#[derive(Debug)]
+struct PendingReq {
+    sent_at: Instant,
+    events: u64,
+    /// Set for broad subscriptions, see [`RelayMetrics::should_skip`]
+    key: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EmptyStreak {
+    count: u32,
+    last_tried: Instant,
+}
+
+/// How a relay has been doing, to show in the relay debug view and to
+/// decide what's worth asking it for
+#[derive(Debug, Default)]
+pub struct RelayMetrics {
+    /// Events received since we connected
+    pub events: u64,
+    /// Events another relay had sent us already
+    pub duplicates: u64,
+    recent: VecDeque<Instant>,
+    pending: HashMap<String, PendingReq>,
+    /// Average time from a REQ to its EOSE
+    eose_latency: Option<Duration>,
+    empty_streaks: HashMap<String, EmptyStreak>,
+}
+
+impl RelayMetrics {
+    pub fn events_per_second(&self, now: Instant) -> f32 {
+        let recent = self
+            .recent
+            .iter()
+            .filter(|at| now.duration_since(**at) <= RATE_WINDOW)
+            .count();
+        recent as f32 / RATE_WINDOW.as_secs_f32()
+    }
+
+    pub fn eose_latency(&self) -> Option<Duration> {
+        self.eose_latency
+    }
+
+    /// The share of events we'd already had from another relay
+    pub fn duplicate_ratio(&self) -> f32 {
+        if self.events == 0 {
+            0.0
+        } else {
+            self.duplicates as f32 / self.events as f32
+        }
+    }
+
+    /// Whether to leave a broad subscription out, because this relay has
+    /// had nothing for it the last few times we asked
+    pub fn should_skip(&self, key: &str, now: Instant) -> bool {
+        self.empty_streaks.get(key).is_some_and(|streak| {
+            streak.count >= EMPTY_STREAK_LIMIT
+                && now.duration_since(streak.last_tried) < EMPTY_RETRY_AFTER
+        })
+    }
+
+    /// Relays are asked for things we won't count on, like thread
+    /// replies, so only the subscriptions given a key count towards
+    /// skipping
+    pub fn set_key(&mut self, subid: &str, key: &str) {
+        if let Some(req) = self.pending.get_mut(subid) {
+            req.key = Some(key.to_owned());
+        }
+    }
+
+    pub(crate) fn sent(&mut self, msg: &ClientMessage, now: Instant) {
+        match msg {
+            ClientMessage::Req { sub_id, .. } => {
+                self.pending.insert(
+                    sub_id.clone(),
+                    PendingReq {
+                        sent_at: now,
+                        events: 0,
+                        key: None,
+                    },
+                );
+            }
+            ClientMessage::Close { sub_id } => {
+                self.pending.remove(sub_id);
+            }
+            ClientMessage::Event(_) | ClientMessage::Raw(_) => {}
+        }
+    }
+
+    pub(crate) fn received(&mut self, msg: &RelayMessage, seen: &mut SeenIds, now: Instant) {
+        match msg {
+            RelayMessage::Event(sub_id, raw) => {
+                self.events += 1;
+                self.recent.push_back(now);
+                while self
+                    .recent
+                    .front()
+                    .is_some_and(|at| now.duration_since(*at) > RATE_WINDOW)
+                {
+                    self.recent.pop_front();
+                }
+
+                if let Some(id) = event_id(raw) {
+                    if !seen.insert(id) {
+                        self.duplicates += 1;
+                    }
+                }
+                if let Some(req) = self.pending.get_mut(*sub_id) {
+                    req.events += 1;
+                }
+            }
+
+            RelayMessage::Eose(sub_id) => {
+                let Some(req) = self.pending.remove(*sub_id) else {
+                    return;
+                };
+
+                let latency = now.duration_since(req.sent_at);
+                self.eose_latency = Some(match self.eose_latency {
+                    // weigh recent answers more, relays have good and bad days
+                    Some(average) => (average * 3 + latency) / 4,
+                    None => latency,
+                });
+
+                if let Some(key) = req.key {
+                    if req.events == 0 {
+                        let streak = self.empty_streaks.entry(key).or_insert(EmptyStreak {
+                            count: 0,
+                            last_tried: now,
+                        });
+                        streak.count += 1;
+                        streak.last_tried = now;
+                    } else {
+                        self.empty_streaks.remove(&key);
+                    }
+                }
+            }
+
+            RelayMessage::OK(_) | RelayMessage::Notice(_) => {}
+        }
+    }
+
+    /// Anything waiting on an EOSE won't get one from a dropped connection
+    pub(crate) fn disconnected(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// Event ids we've had from any relay recently
+#[derive(Debug, Default)]
+pub struct SeenIds {
+    ids: HashSet<[u8; 32]>,
+    order: VecDeque<[u8; 32]>,
+}
+
+impl SeenIds {
+    /// Returns whether the id is new
+    fn insert(&mut self, id: [u8; 32]) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+
+        self.order.push_back(id);
+        if self.order.len() > SEEN_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Pull the id out of a raw EVENT message without parsing the whole note
+fn event_id(raw: &str) -> Option<[u8; 32]> {
+    const KEY: &str = "\"id\":";
+    let start = raw.find(KEY)? + KEY.len();
+    let rest = raw[start..].trim_start().strip_prefix('"')?;
+    let hex_id = rest.get(..64)?;
+    hex::decode(hex_id).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(sub_id: &str, id: char) -> String {
+        format!(
+            r#"["EVENT","{sub_id}",{{"id": "{}","kind":31922,"content":""}}]"#,
+            id.to_string().repeat(64)
+        )
+    }
+
+    #[test]
+    fn test_event_id() {
+        assert_eq!(event_id(&event("sub", 'a')), Some([0xaa; 32]));
+        assert_eq!(event_id(r#"["EVENT","sub",{"kind":1}]"#), None);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let start = Instant::now();
+        let mut seen = SeenIds::default();
+        let mut metrics = RelayMetrics::default();
+
+        metrics.sent(&ClientMessage::req("sub".to_owned(), vec![]), start);
+        for (i, id) in ['a', 'b', 'a'].into_iter().enumerate() {
+            let raw = event("sub", id);
+            let msg = RelayMessage::from_json(&raw).unwrap();
+            metrics.received(&msg, &mut seen, start + Duration::from_secs(i as u64));
+        }
+        metrics.received(
+            &RelayMessage::eose("sub"),
+            &mut seen,
+            start + Duration::from_secs(4),
+        );
+
+        assert_eq!(metrics.events, 3);
+        assert_eq!(metrics.duplicates, 1);
+        assert_eq!(metrics.eose_latency(), Some(Duration::from_secs(4)));
+        assert_eq!(
+            metrics.events_per_second(start + Duration::from_secs(4)),
+            0.3
+        );
+    }
+
+    #[test]
+    fn test_empty_broad_subscriptions_are_skipped() {
+        let start = Instant::now();
+        let mut seen = SeenIds::default();
+        let mut metrics = RelayMetrics::default();
+
+        for _ in 0..EMPTY_STREAK_LIMIT {
+            assert!(!metrics.should_skip("#nostr", start));
+            metrics.sent(&ClientMessage::req("sub".to_owned(), vec![]), start);
+            metrics.set_key("sub", "#nostr");
+            metrics.received(&RelayMessage::eose("sub"), &mut seen, start);
+        }
+
+        assert!(metrics.should_skip("#nostr", start));
+        assert!(!metrics.should_skip("#art", start));
+        assert!(!metrics.should_skip("#nostr", start + EMPTY_RETRY_AFTER));
+    }
+}
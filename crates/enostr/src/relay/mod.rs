@@ -12,6 +12,8 @@ use std::net::Ipv4Addr;
 use tracing::{debug, error};
 
 pub mod message;
+pub mod metrics;
+pub mod outbox;
 pub mod pool;
 pub mod subs_debug;
 
@@ -148,6 +150,17 @@ pub fn setup_multicast_relay(
     Ok(MulticastRelay::new(multicast_address, socket, interface))
 }
 
+/// Options for relay connections.
+///
+/// We don't offer permessage-deflate: tungstenite 0.24, under ewebsock,
+/// can't inflate frames and fails the connection on the first compressed
+/// one, so a relay accepting the extension would leave us with nothing.
+/// Once ewebsock is on a tungstenite with the `deflate` feature this is
+/// where to ask for it.
+fn ws_options() -> Options {
+    Options::default()
+}
+
 pub struct Relay {
     pub url: String,
     pub status: RelayStatus,
@@ -182,7 +195,7 @@ impl Eq for Relay {}
 impl Relay {
     pub fn new(url: String, wakeup: impl Fn() + Send + Sync + 'static) -> Result<Self> {
         let status = RelayStatus::Connecting;
-        let (sender, receiver) = ewebsock::connect_with_wakeup(&url, Options::default(), wakeup)?;
+        let (sender, receiver) = ewebsock::connect_with_wakeup(&url, ws_options(), wakeup)?;
 
         Ok(Self {
             url,
@@ -209,8 +222,7 @@ impl Relay {
     }
 
     pub fn connect(&mut self, wakeup: impl Fn() + Send + Sync + 'static) -> Result<()> {
-        let (sender, receiver) =
-            ewebsock::connect_with_wakeup(&self.url, Options::default(), wakeup)?;
+        let (sender, receiver) = ewebsock::connect_with_wakeup(&self.url, ws_options(), wakeup)?;
         self.status = RelayStatus::Connecting;
         self.sender = sender;
         self.receiver = receiver;
@@ -0,0 +1,263 @@
+use crate::ClientMessage;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// A classic token bucket. Each message costs one token, tokens refill
+/// continuously up to `capacity`.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, refill_per_sec: f64) -> Self {
+        TokenBucket {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refill_per_sec,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    pub fn try_take(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// How long until the next token is available
+    pub fn time_until_available(&self, now: Instant) -> Duration {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        let tokens = self.tokens + elapsed * self.refill_per_sec;
+        if tokens >= 1.0 || self.refill_per_sec <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - tokens) / self.refill_per_sec)
+        }
+    }
+}
+
+struct QueuedMessage {
+    msg: ClientMessage,
+    ready_at: Instant,
+}
+
+/// Outgoing messages for a single relay. Messages are rate limited with
+/// a [`TokenBucket`], and REQs are held for a short window so that rapid
+/// filter updates for the same subscription only go out once.
+pub struct Outbox {
+    bucket: TokenBucket,
+    coalesce_window: Duration,
+    queue: VecDeque<QueuedMessage>,
+}
+
+impl Default for Outbox {
+    fn default() -> Self {
+        Outbox::new(TokenBucket::new(20, 5.0), Duration::from_millis(50))
+    }
+}
+
+impl Outbox {
+    pub fn new(bucket: TokenBucket, coalesce_window: Duration) -> Self {
+        Outbox {
+            bucket,
+            coalesce_window,
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, msg: &ClientMessage, now: Instant) {
+        let ready_at = match msg {
+            ClientMessage::Req { sub_id, .. } => {
+                // a newer filter for the same subscription replaces the
+                // one we haven't sent yet
+                self.remove_queued_reqs(sub_id);
+                now + self.coalesce_window
+            }
+
+            ClientMessage::Close { sub_id } => {
+                self.remove_queued_reqs(sub_id);
+                now
+            }
+
+            ClientMessage::Event(_) | ClientMessage::Raw(_) => now,
+        };
+
+        self.queue.push_back(QueuedMessage {
+            msg: msg.clone(),
+            ready_at,
+        });
+    }
+
+    fn remove_queued_reqs(&mut self, sub_id: &str) {
+        self.queue.retain(|queued| {
+            !matches!(&queued.msg, ClientMessage::Req { sub_id: queued_id, .. } if queued_id == sub_id)
+        });
+    }
+
+    /// Pop the next message that is allowed to go out right now. Anything
+    /// ready goes ahead of REQs still waiting out their coalescing window.
+    pub fn pop_ready(&mut self, now: Instant) -> Option<ClientMessage> {
+        let index = self
+            .queue
+            .iter()
+            .position(|queued| queued.ready_at <= now)?;
+
+        if !self.bucket.try_take(now) {
+            return None;
+        }
+
+        self.queue.remove(index).map(|queued| queued.msg)
+    }
+
+    /// How long until [`Outbox::pop_ready`] might return something, if
+    /// there's anything queued at all
+    pub fn next_ready_in(&self, now: Instant) -> Option<Duration> {
+        let coalesce = self
+            .queue
+            .iter()
+            .map(|queued| queued.ready_at.saturating_duration_since(now))
+            .min()?;
+        Some(coalesce.max(self.bucket.time_until_available(now)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// We're holding back messages because we've hit the rate limit, as
+    /// opposed to just waiting out the coalescing window
+    pub fn is_throttled(&self, now: Instant) -> bool {
+        self.queue.iter().any(|queued| queued.ready_at <= now) && !self.bucket_has_token(now)
+    }
+
+    fn bucket_has_token(&self, now: Instant) -> bool {
+        self.bucket.time_until_available(now).is_zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FullKeypair;
+    use nostrdb::NoteBuilder;
+
+    fn req(sub_id: &str) -> ClientMessage {
+        ClientMessage::req(sub_id.to_owned(), vec![])
+    }
+
+    #[test]
+    fn test_token_bucket_limits() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, 1.0);
+
+        assert!(bucket.try_take(now));
+        assert!(bucket.try_take(now));
+        assert!(!bucket.try_take(now));
+        assert!(bucket.try_take(now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_outbox_coalesces_reqs() {
+        let now = Instant::now();
+        let mut outbox = Outbox::new(TokenBucket::new(10, 1.0), Duration::from_millis(50));
+
+        outbox.push(&req("a"), now);
+        outbox.push(&req("b"), now);
+        outbox.push(&req("a"), now);
+        assert_eq!(outbox.len(), 2);
+
+        // still inside the coalescing window
+        assert!(outbox.pop_ready(now).is_none());
+
+        let later = now + Duration::from_millis(60);
+        assert!(matches!(
+            outbox.pop_ready(later),
+            Some(ClientMessage::Req { sub_id, .. }) if sub_id == "b"
+        ));
+        assert!(matches!(
+            outbox.pop_ready(later),
+            Some(ClientMessage::Req { sub_id, .. }) if sub_id == "a"
+        ));
+        assert!(outbox.is_empty());
+    }
+
+    #[test]
+    fn test_outbox_close_drops_pending_req() {
+        let now = Instant::now();
+        let mut outbox = Outbox::default();
+
+        outbox.push(&req("a"), now);
+        outbox.push(&ClientMessage::close("a".to_owned()), now);
+
+        assert_eq!(outbox.len(), 1);
+        assert!(matches!(
+            outbox.pop_ready(now),
+            Some(ClientMessage::Close { .. })
+        ));
+    }
+
+    #[test]
+    fn test_outbox_event_skips_coalescing_req() {
+        let now = Instant::now();
+        let mut outbox = Outbox::default();
+        let keypair = FullKeypair::generate();
+        let note = NoteBuilder::new()
+            .kind(1)
+            .content("gm")
+            .sign(&keypair.secret_key.to_secret_bytes())
+            .build()
+            .unwrap();
+
+        outbox.push(&req("a"), now);
+        outbox.push(&ClientMessage::event(note).unwrap(), now);
+
+        assert!(matches!(
+            outbox.pop_ready(now),
+            Some(ClientMessage::Event(_))
+        ));
+        assert!(outbox.pop_ready(now).is_none());
+        assert_eq!(outbox.len(), 1);
+
+        let later = now + Duration::from_millis(60);
+        assert!(matches!(
+            outbox.pop_ready(later),
+            Some(ClientMessage::Req { sub_id, .. }) if sub_id == "a"
+        ));
+    }
+
+    #[test]
+    fn test_outbox_throttles() {
+        let now = Instant::now();
+        let mut outbox = Outbox::new(TokenBucket::new(1, 1.0), Duration::ZERO);
+
+        outbox.push(&ClientMessage::raw("one".to_owned()), now);
+        outbox.push(&ClientMessage::raw("two".to_owned()), now);
+
+        assert!(outbox.pop_ready(now).is_some());
+        assert!(outbox.pop_ready(now).is_none());
+        assert!(outbox.is_throttled(now));
+        assert!(outbox.next_ready_in(now).is_some());
+    }
+}
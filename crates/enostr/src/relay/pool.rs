@@ -1,9 +1,13 @@
+use crate::relay::metrics::{RelayMetrics, SeenIds};
+use crate::relay::outbox::Outbox;
 use crate::relay::{setup_multicast_relay, MulticastRelay, Relay, RelayStatus};
-use crate::{ClientMessage, Result};
+use crate::{ClientMessage, RelayMessage, Result};
 use nostrdb::Filter;
 
-use std::collections::BTreeSet;
-use std::time::{Duration, Instant};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use url::Url;
 
@@ -15,6 +19,29 @@ use tracing::{debug, error};
 
 use super::subs_debug::SubsDebug;
 
+/// Which relays a message is for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RelayTarget {
+    /// Every relay in the pool
+    #[default]
+    All,
+    /// Just these, the ones that aren't in the pool are skipped
+    Only(BTreeSet<String>),
+}
+
+impl RelayTarget {
+    pub fn only(urls: impl IntoIterator<Item = String>) -> Self {
+        RelayTarget::Only(urls.into_iter().map(RelayPool::canonicalize_url).collect())
+    }
+
+    pub fn includes(&self, url: &str) -> bool {
+        match self {
+            RelayTarget::All => true,
+            RelayTarget::Only(urls) => urls.contains(url),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PoolEvent<'a> {
     pub relay: &'a str,
@@ -35,6 +62,31 @@ pub struct PoolEventBuf {
     pub event: ewebsock::WsEvent,
 }
 
+/// Events can arrive late and relay clocks drift, so subscriptions are
+/// replayed from a little before the connection dropped
+const REPLAY_SINCE_GAP: u64 = 120;
+
+/// The longest we wait between attempts to reach a relay
+const MAX_RECONNECT_DURATION: Duration = Duration::from_secs(5 * 60);
+
+/// A relay came back and we asked it again for everything we're
+/// subscribed to
+#[derive(Debug, Clone)]
+pub struct Reconnect {
+    pub relay: String,
+    /// How many subscriptions were sent again
+    pub replayed: usize,
+    /// How long the relay was gone
+    pub offline: Duration,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 pub enum PoolRelay {
     Websocket(WebsocketRelay),
     Multicast(MulticastRelay),
@@ -45,6 +97,12 @@ pub struct WebsocketRelay {
     pub last_ping: Instant,
     pub last_connect_attempt: Instant,
     pub retry_connect_after: Duration,
+    /// Attempts in a row that didn't get us connected
+    pub reconnect_attempts: u32,
+    /// When we lost a connection we had, in unix seconds
+    pub disconnected_at: Option<u64>,
+    pub outbox: Outbox,
+    pub metrics: RelayMetrics,
 }
 
 impl PoolRelay {
@@ -78,10 +136,30 @@ impl PoolRelay {
         }
     }
 
+    /// Whether we're holding back messages for this relay because of its
+    /// rate limit
+    pub fn is_throttled(&self) -> bool {
+        match self {
+            Self::Websocket(wsr) => wsr.outbox.is_throttled(Instant::now()),
+            Self::Multicast(_mcr) => false,
+        }
+    }
+
+    /// Number of messages waiting to be sent to this relay
+    pub fn queued(&self) -> usize {
+        match self {
+            Self::Websocket(wsr) => wsr.outbox.len(),
+            Self::Multicast(_mcr) => 0,
+        }
+    }
+
     pub fn send(&mut self, msg: &ClientMessage) -> Result<()> {
         match self {
             Self::Websocket(wsr) => {
-                wsr.relay.send(msg);
+                let now = Instant::now();
+                wsr.metrics.sent(msg, now);
+                wsr.outbox.push(msg, now);
+                wsr.flush(now);
                 Ok(())
             }
 
@@ -99,6 +177,40 @@ impl PoolRelay {
         self.send(&ClientMessage::req(subid, filter))
     }
 
+    /// Subscribe to something this relay may well have nothing for.
+    /// Relays that keep coming back empty for `key` are left out for a
+    /// while. Returns whether the REQ was sent.
+    pub fn subscribe_broad(
+        &mut self,
+        key: &str,
+        subid: String,
+        filter: Vec<Filter>,
+    ) -> Result<bool> {
+        if self
+            .metrics()
+            .is_some_and(|metrics| metrics.should_skip(key, Instant::now()))
+        {
+            debug!(
+                "not asking {} for {key}, it's had nothing lately",
+                self.url()
+            );
+            return Ok(false);
+        }
+
+        self.subscribe(subid.clone(), filter)?;
+        if let Self::Websocket(wsr) = self {
+            wsr.metrics.set_key(&subid, key);
+        }
+        Ok(true)
+    }
+
+    pub fn metrics(&self) -> Option<&RelayMetrics> {
+        match self {
+            Self::Websocket(wsr) => Some(&wsr.metrics),
+            Self::Multicast(_mcr) => None,
+        }
+    }
+
     pub fn websocket(relay: Relay) -> Self {
         Self::Websocket(WebsocketRelay::new(relay))
     }
@@ -115,18 +227,48 @@ impl WebsocketRelay {
             last_ping: Instant::now(),
             last_connect_attempt: Instant::now(),
             retry_connect_after: Self::initial_reconnect_duration(),
+            reconnect_attempts: 0,
+            disconnected_at: None,
+            outbox: Outbox::default(),
+            metrics: RelayMetrics::default(),
+        }
+    }
+
+    /// Send whatever the rate limiter allows us to send right now
+    pub fn flush(&mut self, now: Instant) {
+        while let Some(msg) = self.outbox.pop_ready(now) {
+            self.relay.send(&msg);
         }
     }
 
     pub fn initial_reconnect_duration() -> Duration {
         Duration::from_secs(5)
     }
+
+    /// How long to wait before the next attempt: doubling each time, up
+    /// to a limit, with some randomness so relays that dropped together
+    /// don't all come back at once
+    fn reconnect_backoff(attempts: u32) -> Duration {
+        let full = Self::initial_reconnect_duration()
+            .saturating_mul(1 << attempts.min(16))
+            .min(MAX_RECONNECT_DURATION);
+        let jitter = RandomState::new().hash_one(attempts) % 1000;
+        full / 2 + full / 2 * jitter as u32 / 1000
+    }
 }
 
 pub struct RelayPool {
     pub relays: Vec<PoolRelay>,
     pub ping_rate: Duration,
     pub debug: Option<SubsDebug>,
+    /// For counting the events relays send us that we already had
+    seen: SeenIds,
+    /// Subscriptions sent to every relay that haven't been closed, to
+    /// send again when a relay comes back
+    subscriptions: BTreeMap<String, Vec<Filter>>,
+    reconnects: Vec<Reconnect>,
+    /// Relays we won't connect to
+    blocked: BTreeSet<String>,
 }
 
 impl Default for RelayPool {
@@ -142,6 +284,10 @@ impl RelayPool {
             relays: vec![],
             ping_rate: Duration::from_secs(25),
             debug: None,
+            seen: SeenIds::default(),
+            subscriptions: BTreeMap::new(),
+            reconnects: vec![],
+            blocked: BTreeSet::new(),
         }
     }
 
@@ -181,7 +327,26 @@ impl RelayPool {
     }
 
     pub fn send(&mut self, cmd: &ClientMessage) {
+        self.send_targeted(cmd, &RelayTarget::All);
+    }
+
+    /// Send to some of the pool's relays, like a post meant for a
+    /// private relay that shouldn't go anywhere else
+    pub fn send_targeted(&mut self, cmd: &ClientMessage, target: &RelayTarget) {
+        match cmd {
+            ClientMessage::Req { sub_id, filters } => {
+                self.subscriptions.insert(sub_id.clone(), filters.clone());
+            }
+            ClientMessage::Close { sub_id } => {
+                self.subscriptions.remove(sub_id);
+            }
+            _ => {}
+        }
+
         for relay in &mut self.relays {
+            if !target.includes(relay.url()) {
+                continue;
+            }
             if let Some(debug) = &mut self.debug {
                 debug.send_cmd(relay.url().to_owned(), cmd);
             }
@@ -192,6 +357,7 @@ impl RelayPool {
     }
 
     pub fn unsubscribe(&mut self, subid: String) {
+        self.subscriptions.remove(&subid);
         for relay in &mut self.relays {
             let cmd = ClientMessage::close(subid.clone());
             if let Some(debug) = &mut self.debug {
@@ -208,6 +374,7 @@ impl RelayPool {
     }
 
     pub fn subscribe(&mut self, subid: String, filter: Vec<Filter>) {
+        self.subscriptions.insert(subid.clone(), filter.clone());
         for relay in &mut self.relays {
             if let Some(debug) = &mut self.debug {
                 debug.send_cmd(
@@ -237,7 +404,9 @@ impl RelayPool {
                                 relay.last_connect_attempt + relay.retry_connect_after;
                             if now > reconnect_at {
                                 relay.last_connect_attempt = now;
-                                let next_duration = Duration::from_millis(3000);
+                                relay.reconnect_attempts += 1;
+                                let next_duration =
+                                    WebsocketRelay::reconnect_backoff(relay.reconnect_attempts);
                                 debug!(
                                     "bumping reconnect duration from {:?} to {:?} and retrying connect",
                                     relay.retry_connect_after, next_duration
@@ -254,6 +423,7 @@ impl RelayPool {
                         RelayStatus::Connected => {
                             relay.retry_connect_after =
                                 WebsocketRelay::initial_reconnect_duration();
+                            relay.reconnect_attempts = 0;
 
                             let should_ping = now - relay.last_ping > self.ping_rate;
                             if should_ping {
@@ -272,6 +442,91 @@ impl RelayPool {
         }
     }
 
+    /// Send any queued messages that are no longer rate limited or
+    /// waiting to be coalesced. This should be called every frame.
+    pub fn flush(&mut self) {
+        let now = Instant::now();
+        for relay in &mut self.relays {
+            if let PoolRelay::Websocket(wsr) = relay {
+                wsr.flush(now);
+            }
+        }
+    }
+
+    /// How long until the next queued message can be sent, if there are
+    /// any. Use this to schedule a wakeup so the queue doesn't stall.
+    pub fn next_flush_in(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.relays
+            .iter()
+            .filter_map(|relay| match relay {
+                PoolRelay::Websocket(wsr) => wsr.outbox.next_ready_in(now),
+                PoolRelay::Multicast(_mcr) => None,
+            })
+            .min()
+    }
+
+    /// How long until we next try to reach a relay we lost, if any are
+    /// gone. Use this to schedule a wakeup so reconnects happen while idle.
+    pub fn next_reconnect_in(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.relays
+            .iter()
+            .filter_map(|relay| match relay {
+                PoolRelay::Websocket(wsr)
+                    if matches!(wsr.relay.status, RelayStatus::Disconnected) =>
+                {
+                    let reconnect_at = wsr.last_connect_attempt + wsr.retry_connect_after;
+                    Some(reconnect_at.saturating_duration_since(now))
+                }
+                _ => None,
+            })
+            .min()
+    }
+
+    /// Relays that came back since the last call, with their subscriptions
+    /// already sent again. Apps can use this to show that they're catching
+    /// up.
+    pub fn take_reconnects(&mut self) -> Vec<Reconnect> {
+        std::mem::take(&mut self.reconnects)
+    }
+
+    /// Ask a relay that came back for everything since it dropped
+    fn replay(&mut self, index: usize, disconnected_at: u64) {
+        let since = disconnected_at.saturating_sub(REPLAY_SINCE_GAP);
+        let relay = &mut self.relays[index];
+        for (subid, filters) in &self.subscriptions {
+            let filters = filters
+                .iter()
+                .map(|filter| filter.clone().since_mut(since))
+                .collect();
+            let cmd = ClientMessage::req(subid.clone(), filters);
+            if let Some(debug) = &mut self.debug {
+                debug.send_cmd(relay.url().to_owned(), &cmd);
+            }
+            if let Err(err) = relay.send(&cmd) {
+                error!("error replaying {subid} to {}: {err}", relay.url());
+            }
+        }
+
+        debug!(
+            "replayed {} subscriptions to {}",
+            self.subscriptions.len(),
+            relay.url()
+        );
+        self.reconnects.push(Reconnect {
+            relay: relay.url().to_owned(),
+            replayed: self.subscriptions.len(),
+            offline: Duration::from_secs(unix_now().saturating_sub(disconnected_at)),
+        });
+    }
+
+    /// Are any relays holding back messages because of their rate limit?
+    /// UIs can use this to show that sends are pending.
+    pub fn is_throttled(&self) -> bool {
+        self.relays.iter().any(|relay| relay.is_throttled())
+    }
+
     pub fn send_to(&mut self, cmd: &ClientMessage, relay_url: &str) {
         for relay in &mut self.relays {
             if relay.url() == relay_url {
@@ -297,6 +552,10 @@ impl RelayPool {
         if self.has(&url) {
             return Ok(());
         }
+        if self.is_blocked(&url) {
+            debug!("not connecting to blocked relay {url}");
+            return Ok(());
+        }
         let relay = Relay::new(url, wakeup)?;
         let pool_relay = PoolRelay::websocket(relay);
 
@@ -321,6 +580,27 @@ impl RelayPool {
             .retain(|pool_relay| !urls.contains(pool_relay.url()));
     }
 
+    /// Never connect to these relays. Any we're connected to are dropped
+    /// along with whatever they haven't delivered yet. Returns whether
+    /// the list changed, relays that were unblocked have to be added
+    /// back by whoever added them.
+    pub fn set_blocked(&mut self, urls: BTreeSet<String>) -> bool {
+        let blocked: BTreeSet<String> = urls.into_iter().map(Self::canonicalize_url).collect();
+        if blocked == self.blocked {
+            return false;
+        }
+
+        self.blocked = blocked;
+        let blocked = &self.blocked;
+        self.relays
+            .retain(|pool_relay| !blocked.contains(pool_relay.url()));
+        true
+    }
+
+    pub fn is_blocked(&self, url: &str) -> bool {
+        self.blocked.contains(url)
+    }
+
     // standardize the format (ie, trailing slashes)
     fn canonicalize_url(url: String) -> String {
         match Url::parse(&url) {
@@ -334,7 +614,8 @@ impl RelayPool {
     /// receive a message from each. If a message is received, return it.
     /// If no message is received from any relays, None is returned.
     pub fn try_recv(&mut self) -> Option<PoolEvent<'_>> {
-        for relay in &mut self.relays {
+        for index in 0..self.relays.len() {
+            let relay = &mut self.relays[index];
             if let PoolRelay::Multicast(mcr) = relay {
                 // try rejoin on multicast
                 if mcr.should_rejoin() {
@@ -345,6 +626,7 @@ impl RelayPool {
             }
 
             if let Some(event) = relay.try_recv() {
+                let was_connected = matches!(relay.status(), RelayStatus::Connected);
                 match &event {
                     WsEvent::Opened => {
                         relay.set_status(RelayStatus::Connected);
@@ -372,13 +654,36 @@ impl RelayPool {
                     }
                 }
 
+                let mut replay_from = None;
+                if let PoolRelay::Websocket(wsr) = relay {
+                    match &event {
+                        WsEvent::Message(WsMessage::Text(text)) => {
+                            if let Ok(msg) = RelayMessage::from_json(text) {
+                                wsr.metrics.received(&msg, &mut self.seen, Instant::now());
+                            }
+                        }
+                        WsEvent::Opened => replay_from = wsr.disconnected_at.take(),
+                        WsEvent::Closed | WsEvent::Error(_) => {
+                            wsr.metrics.disconnected();
+                            if was_connected {
+                                wsr.disconnected_at.get_or_insert_with(unix_now);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+
                 if let Some(debug) = &mut self.debug {
                     debug.receive_cmd(relay.url().to_owned(), (&event).into());
                 }
 
+                if let Some(disconnected_at) = replay_from {
+                    self.replay(index, disconnected_at);
+                }
+
                 let pool_event = PoolEvent {
                     event,
-                    relay: relay.url(),
+                    relay: self.relays[index].url(),
                 };
 
                 return Some(pool_event);
@@ -388,3 +693,40 @@ impl RelayPool {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_backoff() {
+        for attempts in 0..40 {
+            let full = WebsocketRelay::initial_reconnect_duration()
+                .saturating_mul(1 << attempts.min(16))
+                .min(MAX_RECONNECT_DURATION);
+            let backoff = WebsocketRelay::reconnect_backoff(attempts);
+            assert!(backoff >= full / 2 && backoff <= full);
+        }
+        assert!(WebsocketRelay::reconnect_backoff(30) >= MAX_RECONNECT_DURATION / 2);
+    }
+
+    #[test]
+    fn test_blocked_relays() {
+        let mut pool = RelayPool::new();
+        pool.set_blocked(BTreeSet::from(["wss://relay.bad.example".to_owned()]));
+        assert!(pool.is_blocked("wss://relay.bad.example/"));
+
+        pool.add_url("wss://relay.bad.example".to_owned(), || {})
+            .unwrap();
+        assert!(pool.urls().is_empty());
+    }
+
+    #[test]
+    fn test_relay_target() {
+        assert!(RelayTarget::All.includes("wss://relay.damus.io/"));
+
+        let target = RelayTarget::only(["wss://paid.example".to_owned()]);
+        assert!(target.includes("wss://paid.example/"));
+        assert!(!target.includes("wss://relay.damus.io/"));
+    }
+}
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, mem, time::SystemTime};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    mem,
+    time::SystemTime,
+};
 
 use ewebsock::WsMessage;
 use nostrdb::Filter;
@@ -10,16 +14,26 @@ use super::message::calculate_command_result_size;
 type RelayId = String;
 type SubId = String;
 
+/// How many events we keep per relay before dropping the oldest
+const MAX_LOGGED_EVENTS: usize = 1000;
+
 pub struct SubsDebug {
     data: HashMap<RelayId, RelayStats>,
     time_incd: SystemTime,
     pub relay_events_selection: Option<RelayId>,
+    /// Stop logging events so the log holds still while reading it.
+    /// Transfer stats keep counting.
+    pub paused: bool,
+    /// Event labels (see [`RelayLogEvent::label`]) to leave out of the log
+    pub hidden_labels: HashSet<&'static str>,
+    /// Only show events containing this text
+    pub search: String,
 }
 
 #[derive(Default)]
 pub struct RelayStats {
     pub count: TransferStats,
-    pub events: Vec<RelayLogEvent>,
+    pub events: VecDeque<RelayLogEvent>,
     pub sub_data: HashMap<SubId, SubStats>,
 }
 
@@ -29,13 +43,78 @@ pub enum RelayLogEvent {
     Recieve(OwnedRelayEvent),
 }
 
+impl RelayLogEvent {
+    pub const LABELS: [&'static str; 9] = [
+        "REQ", "CLOSE", "EVENT", "EOSE", "OK", "NOTICE", "CONN", "ERROR", "OTHER",
+    ];
+
+    /// The nostr message type, or what happened to the connection
+    pub fn label(&self) -> &'static str {
+        match self {
+            RelayLogEvent::Send(ClientMessage::Req { .. }) => "REQ",
+            RelayLogEvent::Send(ClientMessage::Close { .. }) => "CLOSE",
+            RelayLogEvent::Send(ClientMessage::Event(_)) => "EVENT",
+            RelayLogEvent::Send(ClientMessage::Raw(raw)) => ["REQ", "CLOSE", "EVENT"]
+                .into_iter()
+                .find(|label| raw.starts_with(&format!("[\"{label}\"")))
+                .unwrap_or("OTHER"),
+            RelayLogEvent::Recieve(OwnedRelayEvent::Opened | OwnedRelayEvent::Closed) => "CONN",
+            RelayLogEvent::Recieve(OwnedRelayEvent::Error(_)) => "ERROR",
+            RelayLogEvent::Recieve(OwnedRelayEvent::Other(_)) => "OTHER",
+            RelayLogEvent::Recieve(OwnedRelayEvent::Message(msg)) => match msg {
+                OwnedRelayMessage::Event { .. } => "EVENT",
+                OwnedRelayMessage::Eose(_) => "EOSE",
+                OwnedRelayMessage::Ok { .. } => "OK",
+                OwnedRelayMessage::Notice(_) => "NOTICE",
+            },
+        }
+    }
+
+    /// Everything we know about the event, as text
+    pub fn details(&self) -> String {
+        match self {
+            RelayLogEvent::Send(msg) => msg.to_json().unwrap_or_default(),
+            RelayLogEvent::Recieve(ev) => match ev {
+                OwnedRelayEvent::Opened => "opened".to_owned(),
+                OwnedRelayEvent::Closed => "closed".to_owned(),
+                OwnedRelayEvent::Other(s) | OwnedRelayEvent::Error(s) => s.clone(),
+                OwnedRelayEvent::Message(msg) => match msg {
+                    OwnedRelayMessage::Event { message, .. } => message.clone(),
+                    OwnedRelayMessage::Eose(sub_id) => sub_id.clone(),
+                    OwnedRelayMessage::Ok {
+                        event_id,
+                        accepted,
+                        message,
+                    } => format!("{event_id} {accepted} {message}"),
+                    OwnedRelayMessage::Notice(notice) => notice.clone(),
+                },
+            },
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum OwnedRelayEvent {
     Opened,
     Closed,
     Other(String),
     Error(String),
-    Message(String),
+    Message(OwnedRelayMessage),
+}
+
+#[derive(Clone)]
+pub enum OwnedRelayMessage {
+    Event {
+        sub_id: String,
+        message: String,
+    },
+    Eose(String),
+    Ok {
+        event_id: String,
+        accepted: bool,
+        message: String,
+    },
+    Notice(String),
 }
 
 impl From<RelayEvent<'_>> for OwnedRelayEvent {
@@ -56,10 +135,17 @@ impl From<RelayEvent<'_>> for OwnedRelayEvent {
             RelayEvent::Error(error) => OwnedRelayEvent::Error(error.to_string()),
             RelayEvent::Message(relay_message) => {
                 let relay_msg = match relay_message {
-                    RelayMessage::OK(_) => "OK".to_owned(),
-                    RelayMessage::Eose(s) => format!("EOSE:{}", s),
-                    RelayMessage::Event(_, s) => format!("EVENT:{}", s),
-                    RelayMessage::Notice(s) => format!("NOTICE:{}", s),
+                    RelayMessage::OK(result) => OwnedRelayMessage::Ok {
+                        event_id: result.event_id.to_owned(),
+                        accepted: result.status,
+                        message: result.message.to_owned(),
+                    },
+                    RelayMessage::Eose(s) => OwnedRelayMessage::Eose(s.to_owned()),
+                    RelayMessage::Event(sub_id, s) => OwnedRelayMessage::Event {
+                        sub_id: sub_id.to_owned(),
+                        message: s.to_owned(),
+                    },
+                    RelayMessage::Notice(s) => OwnedRelayMessage::Notice(s.to_owned()),
                 };
                 OwnedRelayEvent::Message(relay_msg)
             }
@@ -99,6 +185,9 @@ impl Default for SubsDebug {
             data: Default::default(),
             time_incd: SystemTime::now(),
             relay_events_selection: None,
+            paused: false,
+            hidden_labels: HashSet::new(),
+            search: String::new(),
         }
     }
 }
@@ -131,7 +220,9 @@ impl SubsDebug {
 
         data.count.up_sec_cur += msg_num_bytes;
 
-        data.events.push(RelayLogEvent::Send(cmd.clone()));
+        if !self.paused {
+            log_event(&mut data.events, RelayLogEvent::Send(cmd.clone()));
+        }
     }
 
     pub(crate) fn receive_cmd(&mut self, relay: String, cmd: RelayEvent) {
@@ -146,7 +237,24 @@ impl SubsDebug {
 
         data.count.down_sec_cur += msg_num_bytes;
 
-        data.events.push(RelayLogEvent::Recieve(cmd.into()));
+        if !self.paused {
+            log_event(&mut data.events, RelayLogEvent::Recieve(cmd.into()));
+        }
+    }
+
+    /// Should this event show up in the log, given the current filters?
+    pub fn is_shown(&self, event: &RelayLogEvent) -> bool {
+        if self.hidden_labels.contains(event.label()) {
+            return false;
+        }
+
+        self.search.is_empty() || event.details().contains(&self.search)
+    }
+
+    pub fn clear_events(&mut self) {
+        for relay_data in self.data.values_mut() {
+            relay_data.events.clear();
+        }
     }
 
     pub fn try_increment_stats(&mut self) {
@@ -171,6 +279,13 @@ impl SubsDebug {
     }
 }
 
+fn log_event(events: &mut VecDeque<RelayLogEvent>, event: RelayLogEvent) {
+    if events.len() >= MAX_LOGGED_EVENTS {
+        events.pop_front();
+    }
+    events.push_back(event);
+}
+
 fn inc_data_count(c: &mut TransferStats) {
     c.up_total += c.up_sec_cur;
     c.up_sec_prior = c.up_sec_cur;
@@ -1,13 +1,20 @@
 use tracing::{debug, error, info};
 
 use crate::{
-    KeyStorageResponse, KeyStorageType, MuteFun, Muted, SingleUnkIdAction, UnknownIds, UserAccount,
+    backup::Backup,
+    bookmarks::{BOOKMARK_LIST_KIND, BOOKMARK_SET_KIND},
+    contacts, deletion,
+    edit::{self, EditMode},
+    highlight,
+    interests::INTERESTS_KIND,
+    muted, relayspec, repost, BookmarkList, Bookmarks, Capability, Error, Interests, KeyProtection,
+    KeyStorageResponse, KeyStorageType, MuteFun, Muted, Permissions, RelaySpec, Result,
+    SecretStore, SessionCapability, SingleUnkIdAction, UnknownIds, UnsignedNote, UserAccount,
 };
 use enostr::{ClientMessage, FilledKeypair, Keypair, RelayPool};
-use nostrdb::{Filter, Ndb, Note, NoteKey, Subscription, Transaction};
+use nostrdb::{Filter, Ndb, Note, NoteBuilder, NoteKey, Subscription, Transaction};
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet};
-use url::Url;
 use uuid::Uuid;
 
 // TODO: remove this
@@ -38,8 +45,8 @@ pub struct AccountRelayData {
     filter: Filter,
     subid: String,
     sub: Option<Subscription>,
-    local: BTreeSet<String>,      // used locally but not advertised
-    advertised: BTreeSet<String>, // advertised via NIP-65
+    local: BTreeSet<String>,         // used locally but not advertised
+    advertised: BTreeSet<RelaySpec>, // advertised via NIP-65
 }
 
 #[derive(Default)]
@@ -101,21 +108,18 @@ impl AccountRelayData {
 
     // standardize the format (ie, trailing slashes) to avoid dups
     pub fn canonicalize_url(url: &str) -> String {
-        match Url::parse(url) {
-            Ok(parsed_url) => parsed_url.to_string(),
-            Err(_) => url.to_owned(), // If parsing fails, return the original URL.
-        }
+        relayspec::canonicalize_url(url)
     }
 
-    fn harvest_nip65_relays(ndb: &Ndb, txn: &Transaction, nks: &[NoteKey]) -> Vec<String> {
+    fn harvest_nip65_relays(ndb: &Ndb, txn: &Transaction, nks: &[NoteKey]) -> Vec<RelaySpec> {
         let mut relays = Vec::new();
         for nk in nks.iter() {
             if let Ok(note) = ndb.get_note_by_key(txn, *nk) {
                 for tag in note.tags() {
                     match tag.get(0).and_then(|t| t.variant().str()) {
                         Some("r") => {
-                            if let Some(url) = tag.get(1).and_then(|f| f.variant().str()) {
-                                relays.push(Self::canonicalize_url(url));
+                            if let Some(spec) = RelaySpec::from_tag(&tag) {
+                                relays.push(spec);
                             }
                         }
                         Some("alt") => {
@@ -224,9 +228,147 @@ impl AccountMutedData {
     }
 }
 
+pub struct AccountBookmarkData {
+    filter: Filter,
+    subid: String,
+    sub: Option<Subscription>,
+    bookmarks: Bookmarks,
+}
+
+impl AccountBookmarkData {
+    pub fn new(ndb: &Ndb, pool: &mut RelayPool, account: &UserAccount) -> Self {
+        // The main NIP-51 bookmark list and all of the bookmark sets
+        let filter = Filter::new()
+            .authors([account.pubkey.bytes()])
+            .kinds([BOOKMARK_LIST_KIND as u64, BOOKMARK_SET_KIND as u64])
+            .build();
+
+        // Local ndb subscription
+        let ndbsub = ndb
+            .subscribe(&[filter.clone()])
+            .expect("ndb bookmarks subscription");
+
+        // Lists are replaceable so there may be old versions in here too,
+        // harvesting keeps the newest of each
+        let txn = Transaction::new(ndb).expect("transaction");
+        let nks = ndb
+            .query(&txn, &[filter.clone()], 500)
+            .expect("query user bookmarks results")
+            .iter()
+            .map(|qr| qr.note_key)
+            .collect::<Vec<NoteKey>>();
+        let mut bookmarks = Bookmarks::default();
+        Self::harvest_nip51_bookmarks(ndb, &txn, &nks, account, &mut bookmarks);
+        debug!(
+            "pubkey {}: {} initial bookmarks",
+            account.pubkey,
+            bookmarks.main.len()
+        );
+
+        // Id for future remote relay subscriptions
+        let subid = Uuid::new_v4().to_string();
+
+        // Add remote subscription to existing relays
+        pool.subscribe(subid.clone(), vec![filter.clone()]);
+
+        AccountBookmarkData {
+            filter,
+            subid,
+            sub: Some(ndbsub),
+            bookmarks,
+        }
+    }
+
+    fn harvest_nip51_bookmarks(
+        ndb: &Ndb,
+        txn: &Transaction,
+        nks: &[NoteKey],
+        account: &UserAccount,
+        bookmarks: &mut Bookmarks,
+    ) {
+        for nk in nks.iter() {
+            if let Ok(note) = ndb.get_note_by_key(txn, *nk) {
+                if let Some(list) = BookmarkList::from_note(&note, account.to_full()) {
+                    bookmarks.update(list);
+                }
+            }
+        }
+    }
+}
+
+pub struct AccountInterestsData {
+    filter: Filter,
+    subid: String,
+    sub: Option<Subscription>,
+    interests: Interests,
+}
+
+impl AccountInterestsData {
+    pub fn new(ndb: &Ndb, pool: &mut RelayPool, pubkey: &[u8; 32]) -> Self {
+        // The NIP-51 interests list, where followed hashtags live
+        let filter = Filter::new()
+            .authors([pubkey])
+            .kinds([INTERESTS_KIND as u64])
+            .limit(1)
+            .build();
+
+        // Local ndb subscription
+        let ndbsub = ndb
+            .subscribe(&[filter.clone()])
+            .expect("ndb interests subscription");
+
+        // Query the ndb immediately to see if the user's interests are
+        // already there
+        let txn = Transaction::new(ndb).expect("transaction");
+        let nks = ndb
+            .query(&txn, &[filter.clone()], 1)
+            .expect("query user interests results")
+            .iter()
+            .map(|qr| qr.note_key)
+            .collect::<Vec<NoteKey>>();
+        let mut interests = Interests::default();
+        Self::harvest_nip51_interests(ndb, &txn, &nks, &mut interests);
+        debug!(
+            "pubkey {}: initial followed hashtags {:?}",
+            hex::encode(pubkey),
+            interests.hashtags().collect::<Vec<_>>()
+        );
+
+        // Id for future remote relay subscriptions
+        let subid = Uuid::new_v4().to_string();
+
+        // Add remote subscription to existing relays
+        pool.subscribe(subid.clone(), vec![filter.clone()]);
+
+        AccountInterestsData {
+            filter,
+            subid,
+            sub: Some(ndbsub),
+            interests,
+        }
+    }
+
+    fn harvest_nip51_interests(
+        ndb: &Ndb,
+        txn: &Transaction,
+        nks: &[NoteKey],
+        interests: &mut Interests,
+    ) {
+        for nk in nks.iter() {
+            if let Ok(note) = ndb.get_note_by_key(txn, *nk) {
+                if let Some(newer) = Interests::from_note(&note) {
+                    interests.update(newer);
+                }
+            }
+        }
+    }
+}
+
 pub struct AccountData {
     relay: AccountRelayData,
     muted: AccountMutedData,
+    bookmarks: AccountBookmarkData,
+    interests: AccountInterestsData,
 }
 
 /// The interface for managing the user's accounts.
@@ -239,6 +381,9 @@ pub struct Accounts {
     forced_relays: BTreeSet<String>,
     bootstrap_relays: BTreeSet<String>,
     needs_relay_config: bool,
+    /// What the app running right now may do, checked before anything is
+    /// published
+    permissions: Permissions,
 }
 
 impl Accounts {
@@ -275,9 +420,18 @@ impl Accounts {
             forced_relays,
             bootstrap_relays,
             needs_relay_config: true,
+            permissions: Permissions::default(),
         }
     }
 
+    pub fn permissions(&self) -> &Permissions {
+        &self.permissions
+    }
+
+    pub fn permissions_mut(&mut self) -> &mut Permissions {
+        &mut self.permissions
+    }
+
     pub fn get_accounts(&self) -> &Vec<UserAccount> {
         &self.accounts
     }
@@ -371,6 +525,70 @@ impl Accounts {
         }
     }
 
+    /// How keys are kept on disk, none when they aren't kept
+    pub fn key_protection(&self) -> Option<KeyProtection> {
+        self.key_store.file_storage().map(|f| f.protection())
+    }
+
+    /// Waiting for the passphrase. Accounts are there, but can't sign.
+    pub fn keys_locked(&self) -> bool {
+        self.key_store.file_storage().is_some_and(|f| f.is_locked())
+    }
+
+    /// Unlock with the password the OS keeps, or move unprotected keys
+    /// under one. See [`crate::FileKeyStorage::unlock_with`].
+    pub fn unlock_keys_with(&mut self, secrets: Option<&dyn SecretStore>) -> Result<()> {
+        let Some(storage) = self.key_store.file_storage_mut() else {
+            return Ok(());
+        };
+        storage.unlock_with(secrets)?;
+        self.reload_secret_keys();
+        Ok(())
+    }
+
+    pub fn unlock_keys(&mut self, passphrase: &str) -> Result<()> {
+        let Some(storage) = self.key_store.file_storage_mut() else {
+            return Ok(());
+        };
+        storage.unlock(passphrase)?;
+        self.reload_secret_keys();
+        Ok(())
+    }
+
+    pub fn use_secret_store(&mut self, secrets: &dyn SecretStore) -> Result<()> {
+        match self.key_store.file_storage_mut() {
+            Some(storage) => storage.use_secret_store(secrets),
+            None => Ok(()),
+        }
+    }
+
+    pub fn use_passphrase(
+        &mut self,
+        passphrase: &str,
+        secrets: Option<&dyn SecretStore>,
+    ) -> Result<()> {
+        match self.key_store.file_storage_mut() {
+            Some(storage) => storage.use_passphrase(passphrase, secrets),
+            None => Ok(()),
+        }
+    }
+
+    /// Give accounts loaded while locked their secret keys
+    fn reload_secret_keys(&mut self) {
+        let KeyStorageResponse::ReceivedResult(Ok(keys)) = self.key_store.get_keys() else {
+            return;
+        };
+        for key in keys.into_iter().filter(|key| key.secret_key.is_some()) {
+            if let Some(account) = self
+                .accounts
+                .iter_mut()
+                .find(|account| account.pubkey == key.pubkey && account.secret_key.is_none())
+            {
+                *account = key;
+            }
+        }
+    }
+
     pub fn num_accounts(&self) -> usize {
         self.accounts.len()
     }
@@ -409,6 +627,498 @@ impl Accounts {
         self.key_store.select_key(None);
     }
 
+    pub fn session(&self) -> SessionCapability {
+        SessionCapability::new(self.get_selected_account())
+    }
+
+    /// Use a relay for the selected account without advertising it.
+    /// Returns false if there is no selected account to add it to.
+    pub fn add_local_relay(&mut self, relay_url: &str) -> bool {
+        let Some(data) = self.selected_account_data_mut() else {
+            return false;
+        };
+
+        data.relay
+            .local
+            .insert(AccountRelayData::canonicalize_url(relay_url));
+        self.needs_relay_config = true;
+        true
+    }
+
+    /// Stop using a relay that was added locally for the selected account
+    pub fn remove_local_relay(&mut self, relay_url: &str) -> bool {
+        let Some(data) = self.selected_account_data_mut() else {
+            return false;
+        };
+
+        let removed = data
+            .relay
+            .local
+            .remove(&AccountRelayData::canonicalize_url(relay_url));
+        self.needs_relay_config |= removed;
+        removed
+    }
+
+    /// Work out which relays to use again on the next update, eg. after
+    /// the pool's blocklist changed
+    pub fn refresh_relays(&mut self) {
+        self.needs_relay_config = true;
+    }
+
+    /// The selected account's NIP-65 relay list
+    pub fn advertised_relays(&self) -> Vec<RelaySpec> {
+        self.selected_account_data()
+            .map(|data| data.relay.advertised.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Relays the selected account uses without advertising them
+    pub fn local_relays(&self) -> Vec<String> {
+        self.selected_account_data()
+            .map(|data| data.relay.local.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Save a note we just signed and send it to our relays, if the app
+    /// running right now may publish. Apps publishing their own kinds go
+    /// through here too.
+    pub fn publish_note(&self, ndb: &Ndb, pool: &mut RelayPool, note: &Note) -> Result<()> {
+        self.permissions.check(Capability::Publish)?;
+
+        let raw_msg = format!("[\"EVENT\",{}]", note.json()?);
+        if let Err(err) = ndb.process_client_event(&raw_msg) {
+            error!("failed to process our kind {} note: {err}", note.kind());
+        }
+
+        pool.send(&ClientMessage::raw(raw_msg));
+        Ok(())
+    }
+
+    /// Sign and publish a new kind 10002 relay list for the selected
+    /// account. The pool follows the new list on the next update.
+    pub fn publish_relay_list(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        relays: Vec<RelaySpec>,
+    ) -> Result<()> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign a relay list".to_owned(),
+            ));
+        };
+
+        let note = relay_list_note(&keypair.secret_key.to_secret_bytes(), &relays)?;
+        self.publish_note(ndb, pool, &note)?;
+
+        let pubkey = *keypair.pubkey.bytes();
+        if let Some(data) = self.account_data.get_mut(&pubkey) {
+            data.relay.advertised = relays.into_iter().collect();
+        }
+        self.needs_relay_config = true;
+
+        info!("published relay list");
+        Ok(())
+    }
+
+    /// The selected account's bookmark list and bookmark sets
+    pub fn bookmarks(&self) -> Option<&Bookmarks> {
+        self.selected_account_data()
+            .map(|data| &data.bookmarks.bookmarks)
+    }
+
+    /// Sign and publish a new version of one of the selected account's
+    /// bookmark lists
+    pub fn publish_bookmarks(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        list: &BookmarkList,
+    ) -> Result<()> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign bookmarks".to_owned(),
+            ));
+        };
+
+        let note = list.to_note(keypair)?;
+        self.publish_note(ndb, pool, &note)?;
+
+        // parse what we signed so we keep its created_at
+        let published = BookmarkList::from_note(&note, Some(keypair));
+        let pubkey = *keypair.pubkey.bytes();
+        if let (Some(data), Some(published)) = (self.account_data.get_mut(&pubkey), published) {
+            data.bookmarks.bookmarks.update(published);
+        }
+
+        info!("published bookmark list {:?}", list.identifier);
+        Ok(())
+    }
+
+    /// Delete one of the selected account's bookmark sets
+    pub fn delete_bookmark_set(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        identifier: &str,
+    ) -> Result<()> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign bookmarks".to_owned(),
+            ));
+        };
+
+        let emptied = BookmarkList::deleted(identifier).to_note(keypair)?;
+        self.publish_note(ndb, pool, &emptied)?;
+
+        let address = format!("{BOOKMARK_SET_KIND}:{}:{identifier}", keypair.pubkey.hex());
+        let deletion = NoteBuilder::new()
+            .kind(5)
+            .content("")
+            .start_tag()
+            .tag_str("a")
+            .tag_str(&address)
+            .start_tag()
+            .tag_str("k")
+            .tag_str(&BOOKMARK_SET_KIND.to_string())
+            .sign(&keypair.secret_key.to_secret_bytes())
+            .build()
+            .ok_or_else(|| Error::Generic("failed to build deletion".to_owned()))?;
+        self.publish_note(ndb, pool, &deletion)?;
+
+        let emptied = BookmarkList::from_note(&emptied, Some(keypair));
+        let pubkey = *keypair.pubkey.bytes();
+        if let (Some(data), Some(emptied)) = (self.account_data.get_mut(&pubkey), emptied) {
+            data.bookmarks.bookmarks.update(emptied);
+        }
+
+        info!("deleted bookmark set {identifier}");
+        Ok(())
+    }
+
+    /// The selected account's interests list, with its followed hashtags
+    pub fn interests(&self) -> Option<&Interests> {
+        self.selected_account_data()
+            .map(|data| &data.interests.interests)
+    }
+
+    /// Sign and publish a new version of the selected account's interests
+    /// list
+    pub fn publish_interests(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        interests: &Interests,
+    ) -> Result<()> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign an interests list".to_owned(),
+            ));
+        };
+
+        let note = interests.to_note(keypair)?;
+        self.publish_note(ndb, pool, &note)?;
+
+        let pubkey = *keypair.pubkey.bytes();
+        if let (Some(data), Some(published)) = (
+            self.account_data.get_mut(&pubkey),
+            Interests::from_note(&note),
+        ) {
+            data.interests.interests.update(published);
+        }
+
+        info!("published interests list");
+        Ok(())
+    }
+
+    /// Follow or unfollow someone from the selected account
+    pub fn publish_follow(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        them: &[u8; 32],
+        follow: bool,
+    ) -> Result<()> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign a contact list".to_owned(),
+            ));
+        };
+
+        let txn = Transaction::new(ndb)?;
+        // starting a new list would wipe out everyone the account follows
+        // on relays we haven't heard from yet
+        let Some(current) = contacts::contact_list(ndb, &txn, keypair.pubkey.bytes()) else {
+            return Err(Error::Generic(
+                "we haven't seen this account's contact list yet".to_owned(),
+            ));
+        };
+
+        let note = contacts::contact_list_note(&current, keypair, them, follow)?;
+        self.publish_note(ndb, pool, &note)?;
+
+        info!(
+            "{} {}",
+            if follow { "followed" } else { "unfollowed" },
+            hex::encode(them)
+        );
+        Ok(())
+    }
+
+    /// Follow everyone in `them` from the selected account with a single
+    /// new contact list, eg. a whole follow pack
+    pub fn publish_follows(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        them: &[[u8; 32]],
+    ) -> Result<()> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign a contact list".to_owned(),
+            ));
+        };
+
+        let txn = Transaction::new(ndb)?;
+        let Some(current) = contacts::contact_list(ndb, &txn, keypair.pubkey.bytes()) else {
+            return Err(Error::Generic(
+                "we haven't seen this account's contact list yet".to_owned(),
+            ));
+        };
+
+        let note = contacts::contact_list_adding(Some(&current), keypair, them)?;
+        self.publish_note(ndb, pool, &note)?;
+
+        info!("followed {} people", them.len());
+        Ok(())
+    }
+
+    /// Add someone to the selected account's public mute list
+    pub fn publish_mute(&mut self, ndb: &Ndb, pool: &mut RelayPool, them: &[u8; 32]) -> Result<()> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign a mute list".to_owned(),
+            ));
+        };
+
+        let txn = Transaction::new(ndb)?;
+        // like contact lists, starting over would drop mutes we haven't
+        // heard about yet
+        let Some(current) = muted::mute_list(ndb, &txn, keypair.pubkey.bytes()) else {
+            return Err(Error::Generic(
+                "we haven't seen this account's mute list yet".to_owned(),
+            ));
+        };
+
+        let note = muted::mute_list_note(&current, keypair, them)?;
+        self.publish_note(ndb, pool, &note)?;
+
+        info!("muted {}", hex::encode(them));
+        Ok(())
+    }
+
+    /// Ask relays to delete one of the selected account's notes. Returns
+    /// the id of the request, for following it out to relays.
+    pub fn publish_deletion(
+        &self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        note_id: &[u8; 32],
+    ) -> Result<[u8; 32]> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign a deletion".to_owned(),
+            ));
+        };
+
+        let txn = Transaction::new(ndb)?;
+        let note = ndb.get_note_by_id(&txn, note_id)?;
+        if note.pubkey() != keypair.pubkey.bytes() {
+            return Err(Error::Generic(
+                "only our own notes can be deleted".to_owned(),
+            ));
+        }
+
+        let request = deletion::request(keypair, &note)?;
+        self.publish_note(ndb, pool, &request)?;
+
+        info!("asked relays to delete {}", hex::encode(note_id));
+        Ok(*request.id())
+    }
+
+    /// Publish an edit of one of the selected account's notes. Returns the
+    /// id of the new version, and of the deletion request if the original
+    /// couldn't just be replaced.
+    pub fn publish_edit(
+        &self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        note_id: &[u8; 32],
+        content: &str,
+        tags: &[Vec<String>],
+    ) -> Result<([u8; 32], Option<[u8; 32]>)> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign an edit".to_owned(),
+            ));
+        };
+
+        let txn = Transaction::new(ndb)?;
+        let note = ndb.get_note_by_id(&txn, note_id)?;
+        let edited = edit::edited(keypair, &note, content, tags)?;
+        self.publish_note(ndb, pool, &edited)?;
+        info!("published edit of {}", hex::encode(note_id));
+
+        let deletion = match EditMode::of(note.kind()) {
+            EditMode::Republish => None,
+            EditMode::Supersede => {
+                let request = deletion::request(keypair, &note)?;
+                self.publish_note(ndb, pool, &request)?;
+                Some(*request.id())
+            }
+        };
+
+        Ok((*edited.id(), deletion))
+    }
+
+    /// Repost a note from the selected account. Returns the repost's id,
+    /// so it can be followed out to relays or taken back.
+    pub fn publish_repost(
+        &self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        note_id: &[u8; 32],
+    ) -> Result<[u8; 32]> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign a repost".to_owned(),
+            ));
+        };
+
+        let txn = Transaction::new(ndb)?;
+        let note = ndb.get_note_by_id(&txn, note_id)?;
+        let repost = repost::repost(keypair, &note)?;
+        self.publish_note(ndb, pool, &repost)?;
+
+        info!("reposted {}", hex::encode(note_id));
+        Ok(*repost.id())
+    }
+
+    /// Sign and publish a note put together outside of an app, eg. by a
+    /// script. The selected account signs it whatever pubkey it names.
+    /// Returns its id.
+    pub fn publish_unsigned(
+        &self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        note: &UnsignedNote,
+    ) -> Result<[u8; 32]> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign notes".to_owned(),
+            ));
+        };
+
+        let note = UnsignedNote {
+            pubkey: *keypair.pubkey.bytes(),
+            ..note.clone()
+        }
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .ok_or_else(|| Error::Generic("failed to sign the note".to_owned()))?;
+        self.publish_note(ndb, pool, &note)?;
+
+        info!("published a kind {} note", note.kind());
+        Ok(*note.id())
+    }
+
+    /// Publish a highlight of a passage from a note. Returns its id.
+    pub fn publish_highlight(
+        &self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        note_id: &[u8; 32],
+        passage: &str,
+        comment: &str,
+    ) -> Result<[u8; 32]> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign a highlight".to_owned(),
+            ));
+        };
+
+        let txn = Transaction::new(ndb)?;
+        let note = ndb.get_note_by_id(&txn, note_id)?;
+        let highlight = highlight::highlight(keypair, &note, passage, comment)?;
+        self.publish_note(ndb, pool, &highlight)?;
+
+        info!("highlighted {}", hex::encode(note_id));
+        Ok(*highlight.id())
+    }
+
+    /// What a backup would add to the selected account's lists
+    pub fn backup_entries(&self, ndb: &Ndb, backup: &Backup) -> Result<Backup> {
+        let Some(account) = self.get_selected_account() else {
+            return Err(Error::Generic("no account selected".to_owned()));
+        };
+
+        let txn = Transaction::new(ndb)?;
+        let follows = contacts::followed(ndb, &txn, account.pubkey.bytes());
+        let mutes: Vec<[u8; 32]> = muted::mute_list(ndb, &txn, account.pubkey.bytes())
+            .map(|list| contacts::followed_pubkeys(&list))
+            .unwrap_or_default();
+
+        Ok(backup.new_entries(&follows, &self.advertised_relays(), &mutes))
+    }
+
+    /// Merge entries from a backup into the selected account's lists and
+    /// publish the lists that changed. Lists we haven't seen are started
+    /// fresh, the user has already been shown what that means.
+    pub fn publish_backup(&mut self, ndb: &Ndb, pool: &mut RelayPool, new: &Backup) -> Result<()> {
+        let Some(keypair) = self.get_selected_account().and_then(|acc| acc.to_full()) else {
+            return Err(Error::Generic(
+                "the selected account can't sign its lists".to_owned(),
+            ));
+        };
+
+        {
+            let txn = Transaction::new(ndb)?;
+            if !new.follows.is_empty() {
+                let current = contacts::contact_list(ndb, &txn, keypair.pubkey.bytes());
+                let note = contacts::contact_list_adding(current.as_ref(), keypair, &new.follows)?;
+                self.publish_note(ndb, pool, &note)?;
+            }
+            if !new.mutes.is_empty() {
+                let current = muted::mute_list(ndb, &txn, keypair.pubkey.bytes());
+                let note = muted::mute_list_adding(current.as_ref(), keypair, &new.mutes)?;
+                self.publish_note(ndb, pool, &note)?;
+            }
+        }
+
+        if !new.relays.is_empty() {
+            let mut relays = self.advertised_relays();
+            relays.extend(new.relays.iter().cloned());
+            self.publish_relay_list(ndb, pool, relays)?;
+        }
+
+        info!(
+            "imported {} follows, {} relays and {} mutes",
+            new.follows.len(),
+            new.relays.len(),
+            new.mutes.len()
+        );
+        Ok(())
+    }
+
+    fn selected_account_data(&self) -> Option<&AccountData> {
+        let pubkey = self.get_selected_account()?.pubkey;
+        self.account_data.get(pubkey.bytes())
+    }
+
+    fn selected_account_data_mut(&mut self) -> Option<&mut AccountData> {
+        let pubkey = self.get_selected_account()?.pubkey;
+        self.account_data.get_mut(pubkey.bytes())
+    }
+
     pub fn mutefun(&self) -> Box<MuteFun> {
         if let Some(index) = self.currently_selected_account {
             if let Some(account) = self.accounts.get(index) {
@@ -434,6 +1144,20 @@ impl Accounts {
                 &ClientMessage::req(data.muted.subid.clone(), vec![data.muted.filter.clone()]),
                 relay_url,
             );
+            pool.send_to(
+                &ClientMessage::req(
+                    data.bookmarks.subid.clone(),
+                    vec![data.bookmarks.filter.clone()],
+                ),
+                relay_url,
+            );
+            pool.send_to(
+                &ClientMessage::req(
+                    data.interests.subid.clone(),
+                    vec![data.interests.filter.clone()],
+                ),
+                relay_url,
+            );
         }
     }
 
@@ -456,11 +1180,16 @@ impl Accounts {
 
     fn handle_added_account(&mut self, ndb: &Ndb, pool: &mut RelayPool, pubkey: &[u8; 32]) {
         debug!("handle_added_account {}", hex::encode(pubkey));
+        let Some(account) = self.find_account(pubkey).cloned() else {
+            return;
+        };
 
         // Create the user account data
         let new_account_data = AccountData {
             relay: AccountRelayData::new(ndb, pool, pubkey),
             muted: AccountMutedData::new(ndb, pool, pubkey),
+            bookmarks: AccountBookmarkData::new(ndb, pool, &account),
+            interests: AccountInterestsData::new(ndb, pool, pubkey),
         };
         self.account_data.insert(*pubkey, new_account_data);
     }
@@ -473,6 +1202,7 @@ impl Accounts {
 
     fn poll_for_updates(&mut self, ndb: &Ndb) -> bool {
         let mut changed = false;
+        let accounts = &self.accounts;
         for (pubkey, data) in &mut self.account_data {
             if let Some(sub) = data.relay.sub {
                 let nks = ndb.poll_for_notes(sub, 1);
@@ -498,6 +1228,34 @@ impl Accounts {
                     changed = true;
                 }
             }
+            if let Some(sub) = data.bookmarks.sub {
+                let nks = ndb.poll_for_notes(sub, 100);
+                let account = accounts.iter().find(|acc| acc.pubkey.bytes() == pubkey);
+                if let (false, Some(account)) = (nks.is_empty(), account) {
+                    let txn = Transaction::new(ndb).expect("txn");
+                    AccountBookmarkData::harvest_nip51_bookmarks(
+                        ndb,
+                        &txn,
+                        &nks,
+                        account,
+                        &mut data.bookmarks.bookmarks,
+                    );
+                    debug!("pubkey {}: updated bookmarks", hex::encode(pubkey));
+                }
+            }
+            if let Some(sub) = data.interests.sub {
+                let nks = ndb.poll_for_notes(sub, 1);
+                if !nks.is_empty() {
+                    let txn = Transaction::new(ndb).expect("txn");
+                    AccountInterestsData::harvest_nip51_interests(
+                        ndb,
+                        &txn,
+                        &nks,
+                        &mut data.interests.interests,
+                    );
+                    debug!("pubkey {}: updated interests", hex::encode(pubkey));
+                }
+            }
         }
         changed
     }
@@ -514,7 +1272,7 @@ impl Accounts {
         if desired_relays.is_empty() {
             for data in self.account_data.values() {
                 desired_relays.extend(data.relay.local.iter().cloned());
-                desired_relays.extend(data.relay.advertised.iter().cloned());
+                desired_relays.extend(data.relay.advertised.iter().map(|r| r.url.clone()));
             }
         }
 
@@ -613,3 +1371,18 @@ impl AddAccountAction {
         self.unk_id_action.process_action(ids, ndb, txn);
     }
 }
+
+fn relay_list_note(seckey: &[u8; 32], relays: &[RelaySpec]) -> Result<Note<'static>> {
+    let mut builder = NoteBuilder::new().kind(10002).content("");
+    for relay in relays {
+        builder = builder.start_tag().tag_str("r").tag_str(&relay.url);
+        if let Some(marker) = relay.marker() {
+            builder = builder.tag_str(marker);
+        }
+    }
+
+    builder
+        .sign(seckey)
+        .build()
+        .ok_or_else(|| Error::Generic("failed to build relay list note".to_owned()))
+}
@@ -0,0 +1,391 @@
+//! Showing bitcoin amounts in the unit the user prefers: sats, bits, or a
+//! fiat currency at the last exchange rate we fetched.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use crate::{
+    storage::{self, Schema},
+    time::unix_now,
+    AppStorage, DataPath, DataPathType, Directory,
+};
+
+/// Where the amount settings lived before they moved into app storage
+const AMOUNT_SETTINGS_FILE: &str = "amounts.json";
+const AMOUNTS_STORAGE_ID: &str = "amounts";
+const AMOUNT_SETTINGS_KEY: &str = "settings";
+static AMOUNT_SETTINGS_SCHEMA: Schema = Schema::INITIAL;
+const PRICES_FILE: &str = "prices.json";
+/// Returns the price of one bitcoin in each currency we support
+const PRICES_URL: &str = "https://mempool.space/api/v1/prices";
+/// How long an exchange rate is good for before we ask again
+const PRICE_REFRESH_SECS: u64 = 10 * 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AmountUnit {
+    #[default]
+    Sats,
+    /// 100 sats, or a millionth of a bitcoin
+    Bits,
+    Fiat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Currency {
+    #[default]
+    Usd,
+    Eur,
+    Gbp,
+    Cad,
+    Chf,
+    Aud,
+    Jpy,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 7] = [
+        Currency::Usd,
+        Currency::Eur,
+        Currency::Gbp,
+        Currency::Cad,
+        Currency::Chf,
+        Currency::Aud,
+        Currency::Jpy,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Gbp => "GBP",
+            Currency::Cad => "CAD",
+            Currency::Chf => "CHF",
+            Currency::Aud => "AUD",
+            Currency::Jpy => "JPY",
+        }
+    }
+
+    fn symbol(&self) -> Option<&'static str> {
+        match self {
+            Currency::Usd => Some("$"),
+            Currency::Eur => Some("€"),
+            Currency::Gbp => Some("£"),
+            Currency::Jpy => Some("¥"),
+            _ => None,
+        }
+    }
+
+    fn decimals(&self) -> usize {
+        match self {
+            Currency::Jpy => 0,
+            _ => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmountSettings {
+    pub unit: AmountUnit,
+    /// Only used when the unit is [`AmountUnit::Fiat`]
+    pub currency: Currency,
+}
+
+/// Bitcoin prices by currency code, and when we got them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Prices {
+    pub fetched_at: u64,
+    pub rates: HashMap<String, f64>,
+}
+
+impl Prices {
+    pub fn rate(&self, currency: Currency) -> Option<f64> {
+        self.rates
+            .get(currency.code())
+            .copied()
+            .filter(|rate| *rate > 0.0)
+    }
+}
+
+/// `msats` in the given unit. Falls back to sats when we want fiat but
+/// have never had an exchange rate.
+pub fn format_msats_with(msats: u64, settings: &AmountSettings, prices: Option<&Prices>) -> String {
+    let sats = msats / 1000;
+
+    match settings.unit {
+        AmountUnit::Sats => format!("{} sats", group_thousands(&sats.to_string())),
+
+        AmountUnit::Bits => {
+            let bits = format!("{:.2}", sats as f64 / 100.0);
+            let (whole, fraction) = bits.split_once('.').unwrap_or((&bits, ""));
+            let fraction = fraction.trim_end_matches('0');
+            let whole = group_thousands(whole);
+            if fraction.is_empty() {
+                format!("{whole} bits")
+            } else {
+                format!("{whole}.{fraction} bits")
+            }
+        }
+
+        AmountUnit::Fiat => {
+            let currency = settings.currency;
+            let Some(rate) = prices.and_then(|p| p.rate(currency)) else {
+                return format_msats_with(msats, &AmountSettings::default(), None);
+            };
+
+            let value = msats as f64 / 100_000_000_000.0 * rate;
+            let value = format!("{:.*}", currency.decimals(), value);
+            let (whole, fraction) = value.split_once('.').unwrap_or((&value, ""));
+            let mut number = group_thousands(whole);
+            if !fraction.is_empty() {
+                number = format!("{number}.{fraction}");
+            }
+
+            match currency.symbol() {
+                Some(symbol) => format!("{symbol}{number}"),
+                None => format!("{number} {}", currency.code()),
+            }
+        }
+    }
+}
+
+/// `msats` in the user's preferred unit
+pub fn format_msats(msats: u64) -> String {
+    format_msats_with(msats, &settings(), prices().as_ref())
+}
+
+/// The amount of a BOLT11 invoice like `lnbc2500u1...`, if it has one
+pub fn bolt11_msats(invoice: &str) -> Option<u64> {
+    let invoice = invoice.trim().to_ascii_lowercase();
+    let invoice = invoice.strip_prefix("lightning:").unwrap_or(&invoice);
+    // the human readable part ends at the last 1
+    let hrp = &invoice[..invoice.rfind('1')?];
+    let rest = hrp.strip_prefix("ln")?;
+    let amount = rest.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    if amount.is_empty() {
+        return None;
+    }
+
+    let (digits, multiplier) = match amount.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&amount[..i], Some(c)),
+        _ => (amount, None),
+    };
+    let value: u64 = digits.parse().ok()?;
+
+    // in msats per unit
+    match multiplier {
+        None => value.checked_mul(100_000_000_000),
+        Some('m') => value.checked_mul(100_000_000),
+        Some('u') => value.checked_mul(100_000),
+        Some('n') => value.checked_mul(100),
+        Some('p') if value % 10 == 0 => Some(value / 10),
+        _ => None,
+    }
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+static SETTINGS: RwLock<AmountSettings> = RwLock::new(AmountSettings {
+    unit: AmountUnit::Sats,
+    currency: Currency::Usd,
+});
+
+static PRICES: RwLock<Option<Prices>> = RwLock::new(None);
+
+/// When we last asked for prices, so we don't keep asking while offline
+static LAST_FETCH: AtomicU64 = AtomicU64::new(0);
+
+/// The current display settings
+pub fn settings() -> AmountSettings {
+    SETTINGS.read().map(|s| *s).unwrap_or_default()
+}
+
+/// The last prices we fetched, which might be old if we're offline
+pub fn prices() -> Option<Prices> {
+    PRICES.read().ok().and_then(|p| p.clone())
+}
+
+pub fn init(path: &DataPath) {
+    let loaded = load_settings(path);
+    if let Ok(mut settings) = SETTINGS.write() {
+        *settings = loaded;
+    }
+
+    let cached = load_prices(path);
+    if let Ok(mut prices) = PRICES.write() {
+        *prices = cached;
+    }
+}
+
+pub fn set_settings(path: &DataPath, new_settings: AmountSettings) {
+    if let Ok(mut settings) = SETTINGS.write() {
+        *settings = new_settings;
+    }
+    save_settings(path, &new_settings);
+}
+
+/// Fetch prices in the background if amounts are shown in fiat and the
+/// ones we have are stale. Call every frame.
+pub fn refresh_prices(path: &DataPath, ctx: &egui::Context) {
+    if settings().unit != AmountUnit::Fiat {
+        return;
+    }
+
+    let now = unix_now();
+    let fetched_at = prices().map_or(0, |p| p.fetched_at);
+    let last_fetch = LAST_FETCH.load(Ordering::Relaxed);
+    if now.saturating_sub(fetched_at.max(last_fetch)) < PRICE_REFRESH_SECS {
+        return;
+    }
+    LAST_FETCH.store(now, Ordering::Relaxed);
+
+    debug!("fetching bitcoin prices");
+    let path = path.clone();
+    let ctx = ctx.clone();
+    ehttp::fetch(ehttp::Request::get(PRICES_URL), move |response| {
+        let prices = response
+            .map_err(|err| err.to_string())
+            .and_then(|response| parse_prices(&response.bytes, unix_now()));
+
+        match prices {
+            Ok(prices) => {
+                save_prices(&path, &prices);
+                if let Ok(mut current) = PRICES.write() {
+                    *current = Some(prices);
+                }
+                ctx.request_repaint();
+            }
+            // keep showing the last rate we had
+            Err(err) => error!("could not fetch bitcoin prices: {err}"),
+        }
+    });
+}
+
+fn parse_prices(bytes: &[u8], fetched_at: u64) -> Result<Prices, String> {
+    let value: serde_json::Value = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+    let rates: HashMap<String, f64> = Currency::ALL
+        .iter()
+        .filter_map(|c| Some((c.code().to_owned(), value.get(c.code())?.as_f64()?)))
+        .collect();
+
+    if rates.is_empty() {
+        return Err("no prices in the response".to_owned());
+    }
+
+    Ok(Prices { fetched_at, rates })
+}
+
+fn app_storage(path: &DataPath) -> AppStorage {
+    let storage = AppStorage::new(path, AMOUNTS_STORAGE_ID);
+    storage.adopt(
+        AMOUNT_SETTINGS_KEY,
+        &path.path(DataPathType::Setting),
+        AMOUNT_SETTINGS_FILE,
+    );
+    storage
+}
+
+fn load_settings(path: &DataPath) -> AmountSettings {
+    app_storage(path)
+        .get(AMOUNT_SETTINGS_KEY, &AMOUNT_SETTINGS_SCHEMA)
+        .unwrap_or_default()
+}
+
+fn save_settings(path: &DataPath, settings: &AmountSettings) {
+    if let Err(e) = app_storage(path).set(AMOUNT_SETTINGS_KEY, &AMOUNT_SETTINGS_SCHEMA, settings) {
+        error!("Could not write amount settings: {}", e);
+    }
+}
+
+fn load_prices(path: &DataPath) -> Option<Prices> {
+    let contents = Directory::new(path.path(DataPathType::Cache))
+        .get_file(PRICES_FILE.to_owned())
+        .ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn save_prices(path: &DataPath, prices: &Prices) {
+    let serialized = match serde_json::to_string(prices) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Could not serialize prices: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = storage::write_file(
+        &path.path(DataPathType::Cache),
+        PRICES_FILE.to_owned(),
+        &serialized,
+    ) {
+        error!("Could not write prices: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bolt11_msats() {
+        assert_eq!(bolt11_msats("lnbc2500u1pvjluezpp5qqq"), Some(250_000_000));
+        assert_eq!(bolt11_msats("LNBC10N1PJ9QQQ"), Some(1_000));
+        assert_eq!(bolt11_msats("lntb1m1qqq"), Some(100_000_000));
+        // a payee chooses the amount
+        assert_eq!(bolt11_msats("lnbc1pvjluezpp5qqq"), None);
+        assert_eq!(bolt11_msats("not an invoice"), None);
+    }
+
+    #[test]
+    fn test_format_msats() {
+        let sats = AmountSettings::default();
+        assert_eq!(format_msats_with(21_000, &sats, None), "21 sats");
+        assert_eq!(
+            format_msats_with(1_234_567_000, &sats, None),
+            "1,234,567 sats"
+        );
+
+        let bits = AmountSettings {
+            unit: AmountUnit::Bits,
+            ..Default::default()
+        };
+        assert_eq!(format_msats_with(2_100_000, &bits, None), "21 bits");
+        assert_eq!(format_msats_with(10_000_000, &bits, None), "100 bits");
+        assert_eq!(format_msats_with(123_450_000, &bits, None), "1,234.5 bits");
+
+        let usd = AmountSettings {
+            unit: AmountUnit::Fiat,
+            currency: Currency::Usd,
+        };
+        // no rate yet
+        assert_eq!(format_msats_with(21_000, &usd, None), "21 sats");
+
+        let prices = parse_prices(br#"{"time": 1, "USD": 100000, "CHF": 90000}"#, 1).unwrap();
+        assert_eq!(
+            format_msats_with(100_000_000, &usd, Some(&prices)),
+            "$100.00"
+        );
+
+        let chf = AmountSettings {
+            unit: AmountUnit::Fiat,
+            currency: Currency::Chf,
+        };
+        assert_eq!(
+            format_msats_with(2_000_000_000, &chf, Some(&prices)),
+            "1,800.00 CHF"
+        );
+    }
+}
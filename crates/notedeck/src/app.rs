@@ -1,5 +1,100 @@
-use crate::AppContext;
+use enostr::{Address, NoteId, Pubkey};
+use serde::Serialize;
+
+use crate::{AppContext, Capability};
+
+/// Requests that come from outside of an app, such as the system tray
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AppCommand {
+    /// Open the note composer
+    ComposeNote,
+    /// Open the note composer with some text added to the draft
+    ComposeWith(String),
+    OpenProfile(Pubkey),
+    OpenThread(NoteId),
+    /// Open an addressable event, like a calendar event from an naddr
+    /// link. Only sent to an app whose manifest claims the kind.
+    OpenAddress(Address),
+}
+
+/// Requests an app makes of the chrome, for things outside of the app
+/// itself. Profiles and threads are handed to an app that can show them.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AppAction {
+    OpenProfile(Pubkey),
+    OpenThread(NoteId),
+    OpenUrl(String),
+    /// Hand an naddr link to whichever app can open its kind
+    OpenAddress(Address),
+    /// Share something in a note, eg. an event's naddr and title. The text
+    /// is added to whatever the user was already writing.
+    Compose(String),
+}
+
+/// The selected account changed. Apps hear about it before their first
+/// update under the new account.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct AccountChange {
+    pub previous: Option<Pubkey>,
+    pub current: Option<Pubkey>,
+}
+
+/// How an app shows up in the launcher, and what it can open for others
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppManifest {
+    /// Stable id, also the `app` of its settings
+    pub id: &'static str,
+    pub name: String,
+    /// A glyph for the launcher
+    pub icon: &'static str,
+    /// Kinds of addressable events the app can open from an naddr
+    pub kinds: Vec<u32>,
+    /// Title of the app's settings section, if it has one
+    pub settings: Option<String>,
+    /// What the app needs the user's permission for
+    pub capabilities: Vec<Capability>,
+}
+
+impl AppManifest {
+    pub fn handles(&self, kind: u32) -> bool {
+        self.kinds.contains(&kind)
+    }
+}
+
+/// A column, tab or whatever else an app shows side by side, as listed
+/// to scripts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppView {
+    pub index: usize,
+    pub title: String,
+}
 
 pub trait App {
     fn update(&mut self, ctx: &mut AppContext<'_>, ui: &mut egui::Ui);
+
+    /// Apps without a manifest, like previews, aren't in the launcher and
+    /// are only shown when they're the sole app
+    fn manifest(&self) -> Option<AppManifest> {
+        None
+    }
+
+    /// Handle a command from the chrome. Apps can ignore the ones they
+    /// don't support.
+    fn command(&mut self, _ctx: &mut AppContext<'_>, _command: AppCommand) {}
+
+    /// Called when the selected account changes, and once on startup with
+    /// no previous account. This is the place to flush writes that belong
+    /// to the previous account, drop caches keyed on it, and load what the
+    /// new one needs.
+    fn account_changed(&mut self, _ctx: &mut AppContext<'_>, _change: AccountChange) {}
+
+    /// What the app is showing right now
+    fn views(&self, _ctx: &mut AppContext<'_>) -> Vec<AppView> {
+        vec![]
+    }
+
+    /// Number of notifications the user hasn't looked at yet
+    fn unread_count(&self) -> usize {
+        0
+    }
 }
@@ -0,0 +1,288 @@
+//! Follows, relays and mutes from other clients' backups, to start an
+//! account off where the user left off elsewhere. Backups differ in shape
+//! but they all keep the signed list events, or for nos2x a map of relays,
+//! so we look for those anywhere in the file rather than knowing every
+//! client's format.
+
+use std::collections::BTreeSet;
+
+use serde_json::{Map, Value};
+
+use crate::{Error, RelaySpec, Result};
+
+/// How deep into a backup we look for events
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Backup {
+    pub follows: Vec<[u8; 32]>,
+    pub relays: Vec<RelaySpec>,
+    pub mutes: Vec<[u8; 32]>,
+}
+
+/// The newest list event of each kind found so far
+#[derive(Default)]
+struct Found {
+    contacts: Option<(u64, Map<String, Value>)>,
+    relays: Option<(u64, Map<String, Value>)>,
+    mutes: Option<(u64, Map<String, Value>)>,
+    /// nos2x style `{"wss://..": {"read": true, "write": true}}`
+    relay_maps: Vec<RelaySpec>,
+}
+
+impl Backup {
+    /// Read a backup: one JSON document, or JSON lines like a raw event
+    /// dump
+    pub fn parse(text: &str) -> Result<Self> {
+        let values: Vec<Value> = match serde_json::from_str(text) {
+            Ok(value) => vec![value],
+            Err(_) => text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+        };
+
+        let mut found = Found::default();
+        for value in &values {
+            visit(value, &mut found, 0);
+        }
+
+        let backup = found.into_backup();
+        if backup.is_empty() {
+            return Err(Error::Generic(
+                "no follows, relays or mutes in this file".to_owned(),
+            ));
+        }
+        Ok(backup)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.follows.is_empty() && self.relays.is_empty() && self.mutes.is_empty()
+    }
+
+    /// Only what isn't on the account's lists already
+    pub fn new_entries(
+        &self,
+        follows: &[[u8; 32]],
+        relays: &[RelaySpec],
+        mutes: &[[u8; 32]],
+    ) -> Self {
+        Backup {
+            follows: missing(&self.follows, follows),
+            relays: self
+                .relays
+                .iter()
+                .filter(|relay| !relays.iter().any(|r| r.url == relay.url))
+                .cloned()
+                .collect(),
+            mutes: missing(&self.mutes, mutes),
+        }
+    }
+}
+
+fn missing(ours: &[[u8; 32]], theirs: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    ours.iter()
+        .filter(|pk| !theirs.contains(pk))
+        .copied()
+        .collect()
+}
+
+fn visit(value: &Value, found: &mut Found, depth: usize) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(kind) = object.get("kind").and_then(Value::as_u64) {
+                if object.get("tags").is_some_and(Value::is_array) {
+                    found.event(kind, object);
+                    return;
+                }
+            }
+            if let Some(Value::Object(relays)) = object.get("relays") {
+                found.relay_maps.extend(relay_map(relays));
+            }
+            for value in object.values() {
+                visit(value, found, depth + 1);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                visit(value, found, depth + 1);
+            }
+        }
+        // some clients store events as strings of JSON
+        Value::String(s) if s.starts_with('{') || s.starts_with('[') => {
+            if let Ok(value) = serde_json::from_str::<Value>(s) {
+                visit(&value, found, depth + 1);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Found {
+    fn event(&mut self, kind: u64, event: &Map<String, Value>) {
+        let slot = match kind {
+            3 => &mut self.contacts,
+            10002 => &mut self.relays,
+            10000 => &mut self.mutes,
+            _ => return,
+        };
+        let created_at = event.get("created_at").and_then(Value::as_u64).unwrap_or(0);
+        if slot
+            .as_ref()
+            .is_some_and(|(newest, _)| *newest >= created_at)
+        {
+            return;
+        }
+        *slot = Some((created_at, event.clone()));
+    }
+
+    fn into_backup(self) -> Backup {
+        let mut relays: Vec<RelaySpec> = self
+            .relays
+            .as_ref()
+            .map(|(_, event)| {
+                tags(event)
+                    .filter(|tag| tag.first() == Some(&"r"))
+                    .filter_map(|tag| {
+                        let url = tag.get(1)?;
+                        Some(match tag.get(2) {
+                            Some(&"read") => RelaySpec::new(url, true, false),
+                            Some(&"write") => RelaySpec::new(url, false, true),
+                            _ => RelaySpec::new(url, true, true),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // older clients kept relays in the contact list's content
+        if relays.is_empty() {
+            relays = self
+                .contacts
+                .as_ref()
+                .and_then(|(_, event)| event.get("content")?.as_str())
+                .and_then(|content| serde_json::from_str::<Map<String, Value>>(content).ok())
+                .map(|map| relay_map(&map))
+                .unwrap_or_default();
+        }
+        if relays.is_empty() {
+            relays = self.relay_maps;
+        }
+
+        Backup {
+            follows: self
+                .contacts
+                .as_ref()
+                .map(|(_, event)| pubkeys(event))
+                .unwrap_or_default(),
+            relays: dedup(relays),
+            mutes: self
+                .mutes
+                .as_ref()
+                .map(|(_, event)| pubkeys(event))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn tags(event: &Map<String, Value>) -> impl Iterator<Item = Vec<&str>> + '_ {
+    event
+        .get("tags")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_array)
+        .map(|tag| tag.iter().filter_map(Value::as_str).collect())
+}
+
+fn pubkeys(event: &Map<String, Value>) -> Vec<[u8; 32]> {
+    let mut seen = BTreeSet::new();
+    tags(event)
+        .filter(|tag| tag.first() == Some(&"p"))
+        .filter_map(|tag| hex::decode(tag.get(1)?).ok()?.try_into().ok())
+        .filter(|pk: &[u8; 32]| seen.insert(*pk))
+        .collect()
+}
+
+fn relay_map(map: &Map<String, Value>) -> Vec<RelaySpec> {
+    map.iter()
+        .filter(|(url, _)| url.starts_with("wss://") || url.starts_with("ws://"))
+        .map(|(url, policy)| {
+            let read = policy.get("read").and_then(Value::as_bool).unwrap_or(true);
+            let write = policy.get("write").and_then(Value::as_bool).unwrap_or(true);
+            RelaySpec::new(url, read, write)
+        })
+        .collect()
+}
+
+fn dedup(relays: Vec<RelaySpec>) -> Vec<RelaySpec> {
+    let mut seen = BTreeSet::new();
+    relays
+        .into_iter()
+        .filter(|relay| seen.insert(relay.url.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+    #[test]
+    fn test_event_dump() {
+        let dump = format!(
+            r#"{{"kind":3,"created_at":1,"tags":[["p","{A}"]],"content":""}}
+{{"kind":3,"created_at":2,"tags":[["p","{A}"],["p","{B}"],["p","{B}"]],"content":""}}
+{{"kind":10002,"created_at":2,"tags":[["r","wss://relay.damus.io","read"],["r","wss://nos.lol"]],"content":""}}
+{{"kind":1,"created_at":3,"tags":[],"content":"hi"}}"#
+        );
+        let backup = Backup::parse(&dump).unwrap();
+        assert_eq!(backup.follows, vec![[0xaa; 32], [0xbb; 32]]);
+        assert_eq!(backup.relays.len(), 2);
+        assert!(!backup.relays[0].write);
+        assert!(backup.mutes.is_empty());
+    }
+
+    #[test]
+    fn test_nested_backup() {
+        // events stored as strings inside a client's own export, and
+        // relays kept nos2x style
+        let contacts = format!(r#"{{"kind":3,"created_at":5,"tags":[["p","{A}"]],"content":""}}"#);
+        let backup = serde_json::json!({
+            "account": { "contactList": contacts },
+            "relays": { "wss://relay.example.com": { "read": true, "write": false } },
+        });
+        let backup = Backup::parse(&backup.to_string()).unwrap();
+        assert_eq!(backup.follows, vec![[0xaa; 32]]);
+        assert_eq!(
+            backup.relays,
+            vec![RelaySpec::new("wss://relay.example.com", true, false)]
+        );
+
+        assert!(Backup::parse(r#"{"theme":"dark"}"#).is_err());
+    }
+
+    #[test]
+    fn test_new_entries() {
+        let backup = Backup {
+            follows: vec![[1; 32], [2; 32]],
+            relays: vec![RelaySpec::new("wss://a.com", true, true)],
+            mutes: vec![[3; 32]],
+        };
+        let new = backup.new_entries(
+            &[[1; 32]],
+            &[RelaySpec::new("wss://a.com", true, false)],
+            &[],
+        );
+        assert_eq!(new.follows, vec![[2; 32]]);
+        assert!(new.relays.is_empty());
+        assert_eq!(new.mutes, vec![[3; 32]]);
+    }
+}
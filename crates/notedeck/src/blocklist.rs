@@ -0,0 +1,146 @@
+//! Relays and media hosts an account never wants to talk to. They're
+//! kept as per-account settings, as lists separated by commas or
+//! whitespace, and can be exported to a file and merged back in.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::relayspec::canonicalize_url;
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Blocklist {
+    /// Canonical relay urls
+    pub relays: BTreeSet<String>,
+    /// Domains, which block their subdomains too
+    pub media_hosts: BTreeSet<String>,
+}
+
+fn entries(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+}
+
+/// Just the domain, whether we were given one or a whole url
+fn host(entry: &str) -> Option<String> {
+    let parsed = Url::parse(entry).ok();
+    let host = match parsed.as_ref().and_then(Url::host_str) {
+        Some(host) => host,
+        // a bare domain, maybe with a port or path
+        None => entry.split(['/', ':']).next()?,
+    };
+    let host = host
+        .trim_start_matches("*.")
+        .trim_matches('.')
+        .to_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+impl Blocklist {
+    pub fn from_settings(relays: &str, media_hosts: &str) -> Self {
+        Blocklist {
+            relays: entries(relays).map(canonicalize_url).collect(),
+            media_hosts: entries(media_hosts).filter_map(host).collect(),
+        }
+    }
+
+    pub fn relays_setting(&self) -> String {
+        self.relays.iter().cloned().collect::<Vec<_>>().join(", ")
+    }
+
+    pub fn media_hosts_setting(&self) -> String {
+        self.media_hosts
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn blocks_relay(&self, url: &str) -> bool {
+        self.relays.contains(&canonicalize_url(url))
+    }
+
+    /// Whether media at `url` comes from a blocked host
+    pub fn blocks_media(&self, url: &str) -> bool {
+        let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+        else {
+            return false;
+        };
+
+        self.media_hosts.iter().any(|blocked| {
+            host == *blocked
+                || host
+                    .strip_suffix(blocked.as_str())
+                    .is_some_and(|sub| sub.ends_with('.'))
+        })
+    }
+
+    /// Add everything from an exported blocklist. Returns how many
+    /// entries were new.
+    pub fn merge(&mut self, other: Blocklist) -> usize {
+        let before = self.relays.len() + self.media_hosts.len();
+        self.relays
+            .extend(other.relays.iter().map(|url| canonicalize_url(url)));
+        self.media_hosts
+            .extend(other.media_hosts.iter().filter_map(|entry| host(entry)));
+        self.relays.len() + self.media_hosts.len() - before
+    }
+
+    pub fn export(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|err| Error::Generic(err.to_string()))
+    }
+
+    pub fn import(json: &str) -> Result<Blocklist> {
+        serde_json::from_str(json).map_err(|err| Error::Generic(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks_media() {
+        let blocklist = Blocklist::from_settings(
+            "",
+            "Bad.example, https://evil.test/x *.spam.io cdn.test:443",
+        );
+        assert_eq!(
+            blocklist.media_hosts_setting(),
+            "bad.example, cdn.test, evil.test, spam.io"
+        );
+
+        assert!(blocklist.blocks_media("https://bad.example/a.png"));
+        assert!(blocklist.blocks_media("https://cdn.BAD.example/a.png"));
+        assert!(blocklist.blocks_media("http://img.spam.io/b.gif"));
+        assert!(!blocklist.blocks_media("https://notbad.example/a.png"));
+        assert!(!blocklist.blocks_media("https://good.example/bad.example.png"));
+        assert!(!blocklist.blocks_media("not a url"));
+    }
+
+    #[test]
+    fn test_blocks_relay() {
+        let blocklist = Blocklist::from_settings("wss://relay.bad.example", "");
+        assert!(blocklist.blocks_relay("wss://relay.bad.example/"));
+        assert!(!blocklist.blocks_relay("wss://relay.damus.io"));
+    }
+
+    #[test]
+    fn test_export_merge() {
+        let mine = Blocklist::from_settings("wss://a.example", "a.example");
+        let theirs = Blocklist::from_settings("wss://a.example wss://b.example", "B.example");
+
+        let mut merged = mine.clone();
+        let imported = Blocklist::import(&theirs.export().unwrap()).unwrap();
+        assert_eq!(merged.merge(imported), 2);
+        assert!(merged.blocks_relay("wss://b.example"));
+        assert!(merged.blocks_media("https://b.example/x.png"));
+        assert!(Blocklist::import("not json").is_err());
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+
+use enostr::{FilledKeypair, Pubkey};
+use nostrdb::{NdbStrVariant, Note, NoteBuilder, Tag};
+use tracing::error;
+
+use crate::{Error, Result};
+
+pub const BOOKMARK_LIST_KIND: u32 = 10003;
+pub const BOOKMARK_SET_KIND: u32 = 30003;
+
+/// A NIP-51 list of bookmarks: either an account's main kind 10003 list,
+/// or a kind 30003 bookmark set, which works like a folder. Private items
+/// live nip44 encrypted in the note content.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookmarkList {
+    /// The `d` tag of a bookmark set, None for the main list
+    pub identifier: Option<String>,
+    pub title: Option<String>,
+    /// Bookmark tags in the order they were added. Mostly `e` tags, but
+    /// other clients also bookmark hashtags, urls and articles.
+    pub public: Vec<Vec<String>>,
+    pub private: Vec<Vec<String>>,
+    /// Content we couldn't decrypt. Kept so that republishing the list
+    /// doesn't throw away someone's private bookmarks.
+    undecrypted: Option<String>,
+    created_at: u64,
+}
+
+impl BookmarkList {
+    pub fn set(identifier: impl Into<String>, title: impl Into<String>) -> Self {
+        BookmarkList {
+            identifier: Some(identifier.into()),
+            title: Some(title.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn kind(&self) -> u32 {
+        if self.identifier.is_some() {
+            BOOKMARK_SET_KIND
+        } else {
+            BOOKMARK_LIST_KIND
+        }
+    }
+
+    /// Parse a bookmark list note. Private items are only read if we have
+    /// the author's secret key.
+    pub fn from_note(note: &Note, keypair: Option<FilledKeypair>) -> Option<Self> {
+        let mut list = BookmarkList {
+            created_at: note.created_at(),
+            ..Default::default()
+        };
+
+        match note.kind() {
+            BOOKMARK_LIST_KIND => {}
+            BOOKMARK_SET_KIND => list.identifier = Some(String::new()),
+            _ => return None,
+        }
+
+        for tag in note.tags() {
+            let tag = tag_strings(&tag);
+            match tag.first().map(String::as_str) {
+                Some("d") if list.identifier.is_some() => {
+                    list.identifier = Some(tag.get(1).cloned().unwrap_or_default());
+                }
+                Some("title") => list.title = tag.get(1).cloned(),
+                Some(_) => list.public.push(tag),
+                None => {}
+            }
+        }
+
+        let content = note.content();
+        if !content.is_empty() {
+            let decrypted = keypair
+                .ok_or_else(|| Error::Generic("no secret key".to_owned()))
+                .and_then(|keypair| {
+                    let author = Pubkey::new(*note.pubkey());
+                    keypair
+                        .nip44_decrypt(&author, content)
+                        .map_err(|err| Error::Generic(err.to_string()))
+                })
+                .and_then(|json| Ok(serde_json::from_str::<Vec<Vec<String>>>(&json)?));
+
+            match decrypted {
+                Ok(private) => list.private = private,
+                Err(err) => {
+                    if keypair.is_some() {
+                        error!("could not read private bookmarks: {err}");
+                    }
+                    list.undecrypted = Some(content.to_owned());
+                }
+            }
+        }
+
+        Some(list)
+    }
+
+    /// Bookmarked note ids, private ones last
+    pub fn note_ids(&self) -> impl Iterator<Item = [u8; 32]> + '_ {
+        self.public
+            .iter()
+            .chain(self.private.iter())
+            .filter_map(|tag| note_id_from_tag(tag))
+    }
+
+    pub fn contains(&self, note_id: &[u8; 32]) -> bool {
+        self.note_ids().any(|id| &id == note_id)
+    }
+
+    pub fn is_private(&self, note_id: &[u8; 32]) -> bool {
+        self.private
+            .iter()
+            .any(|tag| note_id_from_tag(tag).as_ref() == Some(note_id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.public.len() + self.private.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Deleting a set replaces it with an empty, untitled one, since not
+    /// every relay honours deletions
+    pub fn is_deleted(&self) -> bool {
+        self.identifier.is_some() && self.title.is_none() && self.is_empty()
+    }
+
+    pub fn deleted(identifier: impl Into<String>) -> Self {
+        BookmarkList {
+            identifier: Some(identifier.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn add(&mut self, note_id: &[u8; 32], private: bool) {
+        self.remove(note_id);
+        let tag = vec!["e".to_owned(), hex::encode(note_id)];
+        if private {
+            self.private.push(tag);
+        } else {
+            self.public.push(tag);
+        }
+    }
+
+    pub fn remove(&mut self, note_id: &[u8; 32]) -> bool {
+        let before = self.len();
+        let keep = |tag: &Vec<String>| note_id_from_tag(tag).as_ref() != Some(note_id);
+        self.public.retain(keep);
+        self.private.retain(keep);
+        before != self.len()
+    }
+
+    /// Sign a new version of this list
+    pub fn to_note(&self, keypair: FilledKeypair) -> Result<Note<'static>> {
+        let content = if self.private.is_empty() {
+            self.undecrypted.clone().unwrap_or_default()
+        } else {
+            let json = serde_json::to_string(&self.private)?;
+            keypair
+                .nip44_encrypt(keypair.pubkey, &json)
+                .map_err(|err| Error::Generic(err.to_string()))?
+        };
+
+        let mut builder = NoteBuilder::new().kind(self.kind()).content(&content);
+        if let Some(identifier) = &self.identifier {
+            builder = builder.start_tag().tag_str("d").tag_str(identifier);
+        }
+        if let Some(title) = &self.title {
+            builder = builder.start_tag().tag_str("title").tag_str(title);
+        }
+        for tag in &self.public {
+            builder = builder.start_tag();
+            for field in tag {
+                builder = builder.tag_str(field);
+            }
+        }
+
+        builder
+            .sign(&keypair.secret_key.to_secret_bytes())
+            .build()
+            .ok_or_else(|| Error::Generic("failed to build bookmark list".to_owned()))
+    }
+}
+
+/// An account's main bookmark list and its bookmark sets
+#[derive(Debug, Clone, Default)]
+pub struct Bookmarks {
+    pub main: BookmarkList,
+    sets: BTreeMap<String, BookmarkList>,
+}
+
+impl Bookmarks {
+    /// Bookmarked in the main list or any set
+    pub fn is_bookmarked(&self, note_id: &[u8; 32]) -> bool {
+        self.main.contains(note_id) || self.sets.values().any(|set| set.contains(note_id))
+    }
+
+    pub fn get(&self, identifier: Option<&str>) -> Option<&BookmarkList> {
+        match identifier {
+            None => Some(&self.main),
+            Some(identifier) => self.sets.get(identifier).filter(|set| !set.is_deleted()),
+        }
+    }
+
+    /// Bookmark sets by identifier, leaving out deleted ones
+    pub fn sets(&self) -> impl Iterator<Item = &BookmarkList> {
+        self.sets.values().filter(|set| !set.is_deleted())
+    }
+
+    /// Use `list` unless we already have a newer version of it. Lists are
+    /// replaceable, so nostrdb can hand us old versions too.
+    pub fn update(&mut self, list: BookmarkList) {
+        let current = match &list.identifier {
+            None => Some(&mut self.main),
+            Some(identifier) => self.sets.get_mut(identifier),
+        };
+
+        match current {
+            Some(current) if current.created_at > list.created_at => {}
+            Some(current) => *current = list,
+            None => {
+                if let Some(identifier) = list.identifier.clone() {
+                    self.sets.insert(identifier, list);
+                }
+            }
+        }
+    }
+}
+
+fn note_id_from_tag(tag: &[String]) -> Option<[u8; 32]> {
+    if tag.first()? != "e" {
+        return None;
+    }
+
+    hex::decode(tag.get(1)?).ok()?.try_into().ok()
+}
+
+fn tag_strings(tag: &Tag) -> Vec<String> {
+    (0..tag.count())
+        .filter_map(|i| tag.get(i))
+        .map(|s| match s.variant() {
+            NdbStrVariant::Str(s) => s.to_owned(),
+            NdbStrVariant::Id(id) => hex::encode(id),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_remove() {
+        let a = [1u8; 32];
+        let b = [2u8; 32];
+
+        let mut list = BookmarkList::default();
+        list.add(&a, false);
+        list.add(&b, true);
+        assert_eq!(list.kind(), BOOKMARK_LIST_KIND);
+        assert!(list.contains(&a) && list.contains(&b));
+        assert!(list.is_private(&b) && !list.is_private(&a));
+        assert_eq!(list.note_ids().collect::<Vec<_>>(), vec![a, b]);
+
+        // moving between public and private doesn't duplicate it
+        list.add(&a, true);
+        assert_eq!(list.len(), 2);
+        assert!(list.is_private(&a));
+
+        assert!(list.remove(&a));
+        assert!(!list.remove(&a));
+        assert_eq!(list.note_ids().collect::<Vec<_>>(), vec![b]);
+    }
+
+    #[test]
+    fn test_keep_newest() {
+        let mut bookmarks = Bookmarks::default();
+        let mut newer = BookmarkList::set("reading", "Reading");
+        newer.created_at = 20;
+        newer.add(&[1u8; 32], false);
+        let mut older = BookmarkList::set("reading", "Reading");
+        older.created_at = 10;
+
+        bookmarks.update(newer);
+        bookmarks.update(older);
+        assert!(bookmarks.is_bookmarked(&[1u8; 32]));
+        assert_eq!(
+            bookmarks.get(Some("reading")).map(|set| set.kind()),
+            Some(BOOKMARK_SET_KIND)
+        );
+
+        let mut deleted = BookmarkList::deleted("reading");
+        deleted.created_at = 30;
+        bookmarks.update(deleted);
+        assert!(!bookmarks.is_bookmarked(&[1u8; 32]));
+        assert!(bookmarks.get(Some("reading")).is_none());
+        assert_eq!(bookmarks.sets().count(), 0);
+    }
+}
@@ -0,0 +1,225 @@
+//! Proof that someone was at an event. The organizer shows a QR code with
+//! a secret challenge at the door, attendees scan it and publish a signed
+//! check-in carrying the challenge. Unlike an RSVP, a check-in with the
+//! right challenge means they saw the code.
+
+use std::collections::HashMap;
+
+use enostr::FilledKeypair;
+use nostrdb::{Filter, Ndb, Note, NoteBuilder, Transaction};
+use url::Url;
+use uuid::Uuid;
+
+use crate::note::tag_strings;
+use crate::{Error, Result};
+
+/// Not part of NIP-52, picked from next to its kinds. Addressable, so
+/// scanning twice replaces the first check-in.
+pub const CHECK_IN_KIND: u32 = 31928;
+
+const SCHEME: &str = "nostr-checkin";
+
+/// Most check-ins we read for one event
+const CHECK_IN_LIMIT: i32 = 1000;
+
+/// What an event's check-in QR code holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckInCode {
+    /// The `kind:pubkey:d` address of the calendar event
+    pub event: String,
+    pub challenge: String,
+}
+
+impl CheckInCode {
+    /// A code with a fresh challenge for the event at `event`
+    pub fn new(event: &str) -> Self {
+        CheckInCode {
+            event: event.to_owned(),
+            challenge: Uuid::new_v4().simple().to_string(),
+        }
+    }
+
+    pub fn to_uri(&self) -> String {
+        let mut url = Url::parse(&format!("{SCHEME}:event")).expect("valid check-in url");
+        url.query_pairs_mut()
+            .append_pair("a", &self.event)
+            .append_pair("challenge", &self.challenge);
+        url.to_string()
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let url = Url::parse(text.trim()).ok()?;
+        if url.scheme() != SCHEME {
+            return None;
+        }
+
+        let mut event = None;
+        let mut challenge = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "a" => event = Some(value.into_owned()),
+                "challenge" => challenge = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+
+        Some(CheckInCode {
+            event: event.filter(|event| event_author(event).is_some())?,
+            challenge: challenge.filter(|challenge| !challenge.is_empty())?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckIn {
+    pub author: [u8; 32],
+    pub created_at: u64,
+    pub event: String,
+    challenge: String,
+}
+
+impl CheckIn {
+    pub fn from_note(note: &Note) -> Option<Self> {
+        if note.kind() != CHECK_IN_KIND {
+            return None;
+        }
+
+        let mut event = None;
+        let mut challenge = None;
+        for tag in tag_strings(note) {
+            match (tag[0].as_str(), tag.get(1)) {
+                ("a", Some(address)) => event = Some(address.clone()),
+                ("challenge", Some(value)) => challenge = Some(value.clone()),
+                _ => {}
+            }
+        }
+
+        Some(CheckIn {
+            author: *note.pubkey(),
+            created_at: note.created_at(),
+            event: event?,
+            challenge: challenge?,
+        })
+    }
+
+    /// Whether they scanned this code
+    pub fn matches(&self, code: &CheckInCode) -> bool {
+        self.event == code.event && self.challenge == code.challenge
+    }
+}
+
+/// The hex pubkey out of a `kind:pubkey:d` address
+fn event_author(event: &str) -> Option<&str> {
+    let mut parts = event.splitn(3, ':');
+    parts.next()?.parse::<u32>().ok()?;
+    let author = parts.next()?;
+    parts.next()?;
+    (author.len() == 64 && hex::decode(author).is_ok()).then_some(author)
+}
+
+/// Sign a check-in for a scanned code
+pub fn check_in_note(keypair: FilledKeypair, code: &CheckInCode) -> Result<Note<'static>> {
+    let author = event_author(&code.event)
+        .ok_or_else(|| Error::Generic("not an event address".to_owned()))?;
+
+    NoteBuilder::new()
+        .kind(CHECK_IN_KIND)
+        .content("")
+        .start_tag()
+        .tag_str("d")
+        .tag_str(&code.event)
+        .start_tag()
+        .tag_str("a")
+        .tag_str(&code.event)
+        .start_tag()
+        .tag_str("p")
+        .tag_str(author)
+        .start_tag()
+        .tag_str("challenge")
+        .tag_str(&code.challenge)
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or_else(|| Error::Generic("failed to build check-in".to_owned()))
+}
+
+/// Everyone who checked in with `code`, first to arrive first
+pub fn verified_check_ins(ndb: &Ndb, txn: &Transaction, code: &CheckInCode) -> Vec<CheckIn> {
+    let filter = Filter::new()
+        .kinds([CHECK_IN_KIND as u64])
+        .tags([code.event.clone()], 'a')
+        .limit(CHECK_IN_LIMIT as u64)
+        .build();
+    let Ok(results) = ndb.query(txn, &[filter], CHECK_IN_LIMIT) else {
+        return vec![];
+    };
+
+    verified(
+        results
+            .iter()
+            .filter_map(|result| CheckIn::from_note(&result.note)),
+        code,
+    )
+}
+
+fn verified(check_ins: impl Iterator<Item = CheckIn>, code: &CheckInCode) -> Vec<CheckIn> {
+    let mut first: HashMap<[u8; 32], CheckIn> = HashMap::new();
+    for check_in in check_ins.filter(|check_in| check_in.matches(code)) {
+        match first.get(&check_in.author) {
+            Some(earlier) if earlier.created_at <= check_in.created_at => {}
+            _ => {
+                first.insert(check_in.author, check_in);
+            }
+        }
+    }
+
+    let mut check_ins: Vec<CheckIn> = first.into_values().collect();
+    check_ins.sort_by_key(|check_in| check_in.created_at);
+    check_ins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event() -> String {
+        format!("31923:{}:meetup?", "ab".repeat(32))
+    }
+
+    #[test]
+    fn test_code_round_trip() {
+        let code = CheckInCode::new(&event());
+        assert_eq!(code.challenge.len(), 32);
+        assert_eq!(CheckInCode::parse(&code.to_uri()), Some(code));
+
+        assert_eq!(CheckInCode::parse("nostr:npub1"), None);
+        assert_eq!(CheckInCode::parse("nostr-checkin:event?a=31923:aa:x"), None);
+    }
+
+    #[test]
+    fn test_verified_check_ins() {
+        let code = CheckInCode::new(&event());
+        let check_in = |author: u8, created_at: u64, challenge: &str| CheckIn {
+            author: [author; 32],
+            created_at,
+            event: event(),
+            challenge: challenge.to_owned(),
+        };
+
+        let verified = verified(
+            [
+                check_in(1, 20, &code.challenge),
+                // scanned twice, the first one counts
+                check_in(1, 10, &code.challenge),
+                check_in(2, 15, &code.challenge),
+                // didn't see the code
+                check_in(3, 5, "guessed"),
+            ]
+            .into_iter(),
+            &code,
+        );
+
+        assert_eq!(verified.len(), 2);
+        assert_eq!(verified[0].author, [1; 32]);
+        assert_eq!(verified[0].created_at, 10);
+    }
+}
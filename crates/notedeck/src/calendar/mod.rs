@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use enostr::FilledKeypair;
+use nostrdb::{Filter, Ndb, Note, NoteBuilder, Transaction};
+
+use crate::note::tag_strings;
+use crate::{Error, Result};
+
+pub mod checkin;
+
+pub use checkin::{CheckIn, CheckInCode};
+
+pub const DATE_EVENT_KIND: u32 = 31922;
+pub const TIME_EVENT_KIND: u32 = 31923;
+pub const RSVP_KIND: u32 = 31925;
+
+/// Most RSVPs we read for one event
+const RSVP_LIMIT: i32 = 1000;
+
+/// Someone's answer to a NIP-52 calendar event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RsvpStatus {
+    Accepted,
+    Tentative,
+    Declined,
+}
+
+impl RsvpStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RsvpStatus::Accepted => "accepted",
+            RsvpStatus::Tentative => "tentative",
+            RsvpStatus::Declined => "declined",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "accepted" => Some(RsvpStatus::Accepted),
+            "tentative" => Some(RsvpStatus::Tentative),
+            "declined" => Some(RsvpStatus::Declined),
+            _ => None,
+        }
+    }
+}
+
+/// A kind 31925 RSVP
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rsvp {
+    pub author: [u8; 32],
+    pub created_at: u64,
+    /// The `kind:pubkey:d` address of the calendar event
+    pub event: String,
+    pub status: RsvpStatus,
+    /// Said along with it, like "running a bit late"
+    pub comment: String,
+    /// People they're bringing along, on top of themselves
+    pub guests: u32,
+}
+
+impl Rsvp {
+    pub fn from_note(note: &Note) -> Option<Self> {
+        if note.kind() != RSVP_KIND {
+            return None;
+        }
+
+        let mut event = None;
+        let mut status = None;
+        let mut guests = 0;
+        for tag in tag_strings(note) {
+            let value = tag.get(1).map(String::as_str);
+            match (tag[0].as_str(), value) {
+                ("a", Some(address)) => event = Some(address.to_owned()),
+                ("status", Some(value)) => status = RsvpStatus::parse(value),
+                ("guests", Some(value)) => guests = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+
+        Some(Rsvp {
+            author: *note.pubkey(),
+            created_at: note.created_at(),
+            event: event?,
+            status: status?,
+            comment: note.content().to_owned(),
+            guests,
+        })
+    }
+
+    /// How many people this RSVP brings
+    pub fn headcount(&self) -> u32 {
+        match self.status {
+            RsvpStatus::Accepted => 1 + self.guests,
+            RsvpStatus::Tentative | RsvpStatus::Declined => 0,
+        }
+    }
+}
+
+/// When a NIP-52 event starts, as unix seconds. Date based events start
+/// at midnight UTC on their first day.
+pub fn event_start(note: &Note) -> Option<u64> {
+    let start = tag_strings(note)
+        .into_iter()
+        .find(|tag| tag[0] == "start")?
+        .get(1)?
+        .to_owned();
+    match note.kind() {
+        TIME_EVENT_KIND => start.parse().ok(),
+        DATE_EVENT_KIND => {
+            let day = NaiveDate::parse_from_str(&start, "%Y-%m-%d").ok()?;
+            let midnight = day.and_hms_opt(0, 0, 0)?.and_utc().timestamp();
+            u64::try_from(midnight).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Everyone's newest RSVP to an event, oldest first
+pub fn rsvps(ndb: &Ndb, txn: &Transaction, event: &str) -> Vec<Rsvp> {
+    let filter = Filter::new()
+        .kinds([RSVP_KIND as u64])
+        .tags([event.to_owned()], 'a')
+        .limit(RSVP_LIMIT as u64)
+        .build();
+    let Ok(results) = ndb.query(txn, &[filter], RSVP_LIMIT) else {
+        return vec![];
+    };
+
+    latest(
+        results
+            .iter()
+            .filter_map(|result| Rsvp::from_note(&result.note)),
+    )
+}
+
+/// RSVPs are replaceable, only someone's newest one counts
+pub fn latest(rsvps: impl IntoIterator<Item = Rsvp>) -> Vec<Rsvp> {
+    let mut newest: HashMap<[u8; 32], Rsvp> = HashMap::new();
+    for rsvp in rsvps {
+        match newest.get(&rsvp.author) {
+            Some(current) if current.created_at >= rsvp.created_at => {}
+            _ => {
+                newest.insert(rsvp.author, rsvp);
+            }
+        }
+    }
+
+    let mut rsvps: Vec<Rsvp> = newest.into_values().collect();
+    rsvps.sort_by_key(|rsvp| rsvp.created_at);
+    rsvps
+}
+
+/// Everyone who's coming, and who they're bringing
+pub fn headcount(rsvps: &[Rsvp]) -> u32 {
+    rsvps.iter().map(Rsvp::headcount).sum()
+}
+
+/// Sign an RSVP to the event at `event`. It replaces any earlier RSVP we
+/// sent to the same event.
+pub fn rsvp_note(
+    keypair: FilledKeypair,
+    event: &str,
+    event_author: &[u8; 32],
+    status: RsvpStatus,
+    comment: &str,
+    guests: u32,
+) -> Result<Note<'static>> {
+    let mut builder = NoteBuilder::new()
+        .kind(RSVP_KIND)
+        .content(comment)
+        .start_tag()
+        .tag_str("d")
+        .tag_str(event)
+        .start_tag()
+        .tag_str("a")
+        .tag_str(event)
+        .start_tag()
+        .tag_str("p")
+        .tag_str(&hex::encode(event_author))
+        .start_tag()
+        .tag_str("status")
+        .tag_str(status.as_str());
+
+    if guests > 0 && status != RsvpStatus::Declined {
+        builder = builder
+            .start_tag()
+            .tag_str("guests")
+            .tag_str(&guests.to_string());
+    }
+
+    builder
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or_else(|| Error::Generic("failed to build rsvp".to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rsvp(author: u8, created_at: u64, status: RsvpStatus, guests: u32) -> Rsvp {
+        Rsvp {
+            author: [author; 32],
+            created_at,
+            event: "31923:aa:meetup".to_owned(),
+            status,
+            comment: String::new(),
+            guests,
+        }
+    }
+
+    #[test]
+    fn test_headcount() {
+        let rsvps = latest([
+            rsvp(1, 10, RsvpStatus::Accepted, 2),
+            rsvp(2, 10, RsvpStatus::Tentative, 1),
+            rsvp(3, 10, RsvpStatus::Accepted, 0),
+            // changed their mind
+            rsvp(3, 20, RsvpStatus::Declined, 0),
+            // an older answer doesn't replace a newer one
+            rsvp(1, 5, RsvpStatus::Declined, 0),
+        ]);
+
+        assert_eq!(rsvps.len(), 3);
+        assert_eq!(headcount(&rsvps), 3);
+    }
+
+    #[test]
+    fn test_event_start() {
+        let keypair = enostr::FullKeypair::generate();
+        let event = |kind: u32, start: &str| {
+            NoteBuilder::new()
+                .kind(kind)
+                .content("")
+                .start_tag()
+                .tag_str("start")
+                .tag_str(start)
+                .sign(&keypair.secret_key.to_secret_bytes())
+                .build()
+                .expect("note")
+        };
+
+        assert_eq!(
+            event_start(&event(TIME_EVENT_KIND, "1700000000")),
+            Some(1_700_000_000)
+        );
+        assert_eq!(
+            event_start(&event(DATE_EVENT_KIND, "2024-01-02")),
+            Some(1_704_153_600)
+        );
+        assert_eq!(event_start(&event(DATE_EVENT_KIND, "soon")), None);
+        assert_eq!(event_start(&event(1, "1700000000")), None);
+    }
+
+    #[test]
+    fn test_status() {
+        for status in [
+            RsvpStatus::Accepted,
+            RsvpStatus::Tentative,
+            RsvpStatus::Declined,
+        ] {
+            assert_eq!(RsvpStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(RsvpStatus::parse("maybe"), None);
+    }
+}
@@ -0,0 +1,136 @@
+use enostr::FilledKeypair;
+use nostrdb::{Filter, Ndb, NdbStrVariant, Note, NoteBuilder, Transaction};
+
+use crate::note::tag_strings;
+use crate::{Error, Result};
+
+pub const CONTACTS_KIND: u32 = 3;
+
+/// The newest contact list we have for someone
+pub fn contact_list<'a>(ndb: &Ndb, txn: &'a Transaction, pubkey: &[u8; 32]) -> Option<Note<'a>> {
+    let filter = Filter::new()
+        .authors([pubkey])
+        .kinds([CONTACTS_KIND as u64])
+        .limit(1)
+        .build();
+    let results = ndb.query(txn, &[filter], 1).ok()?;
+    results.into_iter().next().map(|result| result.note)
+}
+
+/// Everyone someone follows, in the order they followed them
+pub fn followed(ndb: &Ndb, txn: &Transaction, pubkey: &[u8; 32]) -> Vec<[u8; 32]> {
+    contact_list(ndb, txn, pubkey)
+        .map(|contacts| followed_pubkeys(&contacts))
+        .unwrap_or_default()
+}
+
+/// Whether `us` follows `them`, if we've seen our contact list
+pub fn follows(ndb: &Ndb, txn: &Transaction, us: &[u8; 32], them: &[u8; 32]) -> Option<bool> {
+    let contacts = contact_list(ndb, txn, us)?;
+    Some(followed_pubkeys(&contacts).contains(them))
+}
+
+pub fn followed_pubkeys(note: &Note) -> Vec<[u8; 32]> {
+    note.tags()
+        .iter()
+        .filter(|tag| tag.count() >= 2 && tag.get_unchecked(0).variant().str() == Some("p"))
+        .filter_map(|tag| match tag.get_unchecked(1).variant() {
+            NdbStrVariant::Id(id) => Some(*id),
+            NdbStrVariant::Str(s) => hex::decode(s).ok()?.try_into().ok(),
+        })
+        .collect()
+}
+
+/// Sign a new version of a contact list with someone followed or
+/// unfollowed. Everything else on the list, including the relays some
+/// clients keep in the content, is carried over.
+pub fn contact_list_note(
+    current: &Note,
+    keypair: FilledKeypair,
+    them: &[u8; 32],
+    follow: bool,
+) -> Result<Note<'static>> {
+    let tags = with_follow(tag_strings(current), &hex::encode(them), follow);
+    sign_contact_list(current.content(), &tags, keypair)
+}
+
+/// Sign a contact list that also follows everyone in `add`. Without a
+/// current list this starts one, so only do that when the user has seen
+/// what they're getting.
+pub fn contact_list_adding(
+    current: Option<&Note>,
+    keypair: FilledKeypair,
+    add: &[[u8; 32]],
+) -> Result<Note<'static>> {
+    let tags = add.iter().fold(
+        current.map(tag_strings).unwrap_or_default(),
+        |tags, them| with_follow(tags, &hex::encode(them), true),
+    );
+    sign_contact_list(current.map_or("", |note| note.content()), &tags, keypair)
+}
+
+fn sign_contact_list(
+    content: &str,
+    tags: &[Vec<String>],
+    keypair: FilledKeypair,
+) -> Result<Note<'static>> {
+    let mut builder = NoteBuilder::new().kind(CONTACTS_KIND).content(content);
+    for tag in tags {
+        builder = builder.start_tag();
+        for field in tag {
+            builder = builder.tag_str(field);
+        }
+    }
+
+    builder
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or_else(|| Error::Generic("failed to build contact list".to_owned()))
+}
+
+fn with_follow(mut tags: Vec<Vec<String>>, them: &str, follow: bool) -> Vec<Vec<String>> {
+    let followed =
+        |tag: &Vec<String>| tag[0] == "p" && tag.get(1).map(String::as_str) == Some(them);
+
+    if !follow {
+        tags.retain(|tag| !followed(tag));
+    } else if !tags.iter().any(followed) {
+        tags.push(vec!["p".to_owned(), them.to_owned()]);
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(pubkey: &str) -> Vec<String> {
+        vec!["p".to_owned(), pubkey.to_owned()]
+    }
+
+    #[test]
+    fn test_with_follow() {
+        let tags = vec![
+            p("aa"),
+            vec!["t".to_owned(), "nostr".to_owned()],
+            vec![
+                "p".to_owned(),
+                "bb".to_owned(),
+                "wss://relay.damus.io".to_owned(),
+            ],
+        ];
+
+        let followed = with_follow(tags.clone(), "cc", true);
+        assert_eq!(followed.len(), 4);
+        assert_eq!(followed[3], p("cc"));
+
+        // following twice doesn't add a second tag
+        assert_eq!(with_follow(followed.clone(), "cc", true), followed);
+
+        let unfollowed = with_follow(tags, "bb", false);
+        assert_eq!(
+            unfollowed,
+            vec![p("aa"), vec!["t".to_owned(), "nostr".to_owned()]]
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{Accounts, Args, DataPath, ImageCache, NoteCache, ThemeHandler, UnknownIds};
+use crate::{
+    storage::AppStorage, Accounts, AppAction, Args, Capability, DataPath, ImageCache, NoteCache,
+    PermissionError, SessionCapability, Settings, ThemeHandler, UnknownIds,
+};
 
 use enostr::RelayPool;
 use nostrdb::Ndb;
@@ -15,4 +18,30 @@ pub struct AppContext<'a> {
     pub path: &'a DataPath,
     pub args: &'a Args,
     pub theme: &'a mut ThemeHandler,
+    pub settings: &'a mut Settings,
+    /// Requests for the chrome, handled after the app updates
+    pub actions: &'a mut Vec<AppAction>,
+}
+
+impl AppContext<'_> {
+    /// What the selected account is allowed to do
+    pub fn session(&self) -> SessionCapability {
+        self.accounts.session()
+    }
+
+    /// Whether the app may use a capability for the selected account. The
+    /// user is asked the first time, so an error may go away later.
+    pub fn check(&mut self, capability: Capability) -> Result<(), PermissionError> {
+        self.accounts.permissions().check(capability)
+    }
+
+    /// Ask the chrome to go somewhere, possibly in another app
+    pub fn request(&mut self, action: AppAction) {
+        self.actions.push(action);
+    }
+
+    /// Persisted state for the app with the given id
+    pub fn storage(&self, app: &str) -> AppStorage {
+        AppStorage::new(self.path, app)
+    }
 }
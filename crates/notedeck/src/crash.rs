@@ -0,0 +1,134 @@
+//! Crash reports. A panic hook writes down what happened before we go
+//! away, and the next launch offers to show it.
+
+use std::backtrace::Backtrace;
+use std::path::{Path, PathBuf};
+
+use tracing::{error, info};
+
+use crate::{time::unix_now, DataPath, DataPathType};
+
+/// The report waiting to be shown. Once seen it's renamed with its time
+/// so older reports stay around in the log directory.
+const PENDING_REPORT: &str = "crash-report.txt";
+
+pub struct CrashReport {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// Write a crash report to the log directory when anything panics. The
+/// previous hook still runs, so the panic is printed as usual too.
+pub fn install_panic_hook(path: &DataPath) {
+    let dir = path.path(DataPathType::Log);
+    let previous = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_owned());
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        let thread = std::thread::current();
+
+        let report = format_report(
+            &message,
+            location.as_deref(),
+            thread.name().unwrap_or("unnamed"),
+            &Backtrace::force_capture().to_string(),
+            unix_now(),
+        );
+
+        match write_report(&dir, &report) {
+            Ok(path) => error!("crashed, report written to {}", path.display()),
+            Err(err) => eprintln!("couldn't write crash report: {err}"),
+        }
+
+        previous(info);
+    }));
+}
+
+fn write_report(dir: &Path, report: &str) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(PENDING_REPORT);
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+fn format_report(
+    message: &str,
+    location: Option<&str>,
+    thread: &str,
+    backtrace: &str,
+    when: u64,
+) -> String {
+    format!(
+        "Notedeck {} crashed\n\
+         time: {when}\n\
+         os: {} {}\n\
+         thread: {thread}\n\
+         location: {}\n\
+         message: {message}\n\
+         \n\
+         {backtrace}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        location.unwrap_or("unknown"),
+    )
+}
+
+/// The report from the last crash, if it hasn't been seen yet
+pub fn pending_report(path: &DataPath) -> Option<CrashReport> {
+    let path = path.path(DataPathType::Log).join(PENDING_REPORT);
+    let contents = std::fs::read_to_string(&path).ok()?;
+    Some(CrashReport { path, contents })
+}
+
+/// Keep the report around under its own name, so it isn't offered again
+pub fn dismiss(report: &CrashReport) {
+    let seen = report
+        .path
+        .with_file_name(format!("crash-{}.txt", unix_now()));
+    match std::fs::rename(&report.path, &seen) {
+        Ok(()) => info!("crash report moved to {}", seen.display()),
+        Err(err) => error!("couldn't move crash report: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_roundtrip() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = DataPath::new(tmp.path());
+
+        assert!(pending_report(&path).is_none());
+
+        let report = format_report(
+            "Failed to find font for Heading3",
+            Some("src/ui/profile/mod.rs:410:17"),
+            "main",
+            "   0: notedeck::main",
+            1700000000,
+        );
+        write_report(&path.path(DataPathType::Log), &report).unwrap();
+
+        let pending = pending_report(&path).unwrap();
+        assert!(pending
+            .contents
+            .contains("message: Failed to find font for Heading3"));
+        assert!(pending
+            .contents
+            .contains("location: src/ui/profile/mod.rs:410:17"));
+
+        dismiss(&pending);
+        assert!(pending_report(&path).is_none());
+    }
+}
@@ -0,0 +1,518 @@
+//! Absolute dates and times, following the user's clock and week
+//! preferences. Relative times like "5m" live in [`crate::time_ago_since`].
+
+use std::ops::Range;
+use std::sync::RwLock;
+
+use chrono::{
+    DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeDelta, TimeZone, Timelike,
+    Utc, Weekday,
+};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{i18n, storage, DataPath, DataPathType, Directory};
+
+const TIME_SETTINGS_FILE: &str = "time.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockFormat {
+    /// Whatever is usual for the current language
+    #[default]
+    Auto,
+    TwelveHour,
+    TwentyFourHour,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WeekStart {
+    /// Whatever is usual for the current language
+    #[default]
+    Auto,
+    Sunday,
+    Monday,
+    Saturday,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeSettings {
+    pub clock: ClockFormat,
+    pub week_start: WeekStart,
+}
+
+impl TimeSettings {
+    pub fn is_24_hour(&self) -> bool {
+        match self.clock {
+            ClockFormat::TwelveHour => false,
+            ClockFormat::TwentyFourHour => true,
+            ClockFormat::Auto => i18n::current_language().id != "en-US",
+        }
+    }
+
+    pub fn first_weekday(&self) -> Weekday {
+        match self.week_start {
+            WeekStart::Sunday => Weekday::Sun,
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Saturday => Weekday::Sat,
+            WeekStart::Auto if i18n::current_language().id == "en-US" => Weekday::Sun,
+            WeekStart::Auto => Weekday::Mon,
+        }
+    }
+
+    /// The days of the week in display order
+    pub fn weekdays(&self) -> [Weekday; 7] {
+        let mut day = self.first_weekday();
+        std::array::from_fn(|_| {
+            let current = day;
+            day = day.succ();
+            current
+        })
+    }
+
+    /// An hour of the day, eg. `22:00` or `10 PM`
+    pub fn format_hour(&self, hour: u32) -> String {
+        if self.is_24_hour() {
+            format!("{:02}:00", hour % 24)
+        } else {
+            let (twelve, suffix) = twelve_hour(hour % 24);
+            format!("{twelve} {suffix}")
+        }
+    }
+
+    /// A time of day on its own, eg. `14:30` or `2:30 PM`
+    pub fn format_clock(&self, time: NaiveTime) -> String {
+        if self.is_24_hour() {
+            time.format("%H:%M").to_string()
+        } else {
+            let (twelve, suffix) = twelve_hour(time.hour());
+            format!("{twelve}:{:02} {suffix}", time.minute())
+        }
+    }
+
+    pub fn format_time(&self, timestamp: u64) -> String {
+        let Some(time) = local(timestamp) else {
+            return String::new();
+        };
+
+        if self.is_24_hour() {
+            time.format("%H:%M").to_string()
+        } else {
+            time.format("%-I:%M %p").to_string()
+        }
+    }
+
+    pub fn format_datetime(&self, timestamp: u64) -> String {
+        let Some(time) = local(timestamp) else {
+            return String::new();
+        };
+
+        format!(
+            "{} {}",
+            time.format("%Y-%m-%d"),
+            self.format_time(timestamp)
+        )
+    }
+}
+
+/// Parse an hour typed in either clock, eg. `22`, `22:00`, `10pm` or
+/// `10 PM`, regardless of which one the user prefers
+pub fn parse_hour(text: &str) -> Option<u32> {
+    let text = text.trim().to_ascii_lowercase();
+    let (number, pm) = if let Some(rest) = text.strip_suffix("am") {
+        (rest, Some(false))
+    } else if let Some(rest) = text.strip_suffix("pm") {
+        (rest, Some(true))
+    } else {
+        (text.as_str(), None)
+    };
+
+    let number = number.trim();
+    let number = number.strip_suffix(":00").unwrap_or(number);
+    let hour: u32 = number.parse().ok()?;
+
+    match pm {
+        None if hour < 24 => Some(hour),
+        Some(pm) if (1..=12).contains(&hour) => Some(hour % 12 + if pm { 12 } else { 0 }),
+        _ => None,
+    }
+}
+
+/// Parse a time of day typed in either clock, eg. `14:30`, `2:30pm`,
+/// `2 PM` or `14`
+pub fn parse_time(text: &str) -> Option<NaiveTime> {
+    let text = text.trim().to_ascii_lowercase();
+    let (clock, pm) = if let Some(rest) = text.strip_suffix("am") {
+        (rest.trim(), Some(false))
+    } else if let Some(rest) = text.strip_suffix("pm") {
+        (rest.trim(), Some(true))
+    } else {
+        (text.as_str(), None)
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour, minute.parse().ok()?),
+        Some(_) => return None,
+        None => (clock, 0),
+    };
+    let hour: u32 = hour.parse().ok()?;
+
+    let hour = match pm {
+        None if hour < 24 => hour,
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+        _ => return None,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// Parse a date like `2024-06-30`
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").ok()
+}
+
+pub fn format_date(day: NaiveDate) -> String {
+    day.format("%Y-%m-%d").to_string()
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// The unix times from the start of `day` in `tz` up to, but not
+/// including, the start of the next day. Not always 24 hours apart when
+/// the clocks change.
+pub fn day_range<Tz: TimeZone>(tz: &Tz, day: NaiveDate) -> Option<Range<u64>> {
+    let start = start_of_day(tz, day)?;
+    let end = start_of_day(tz, day.succ_opt()?)?;
+    Some(start..end)
+}
+
+/// Some zones skip midnight when their clocks go forward, in which case
+/// the day starts at the first time that does exist
+fn start_of_day<Tz: TimeZone>(tz: &Tz, day: NaiveDate) -> Option<u64> {
+    let midnight = day.and_hms_opt(0, 0, 0)?;
+    let start = tz.from_local_datetime(&midnight).earliest().or_else(|| {
+        tz.from_local_datetime(&(midnight + TimeDelta::hours(1)))
+            .earliest()
+    })?;
+    u64::try_from(start.timestamp()).ok()
+}
+
+/// Where a wall clock time lands once the clocks changing is taken into
+/// account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalTime {
+    /// The usual case, the time happens once
+    Single(u64),
+    /// The clocks went back over it, so it happened twice
+    Ambiguous { earlier: u64, later: u64 },
+    /// The clocks went forward over it, so it never happened. `earlier`
+    /// reads it with the offset from after the change and `later` with
+    /// the one from before, eg. 02:30 is either 01:30 or 03:30.
+    Skipped { earlier: u64, later: u64 },
+}
+
+impl LocalTime {
+    /// What to go with when nobody has picked
+    pub fn earliest(&self) -> u64 {
+        match *self {
+            LocalTime::Single(time) => time,
+            LocalTime::Ambiguous { earlier, .. } | LocalTime::Skipped { earlier, .. } => earlier,
+        }
+    }
+
+    /// Both readings when the clocks changed at this time
+    pub fn candidates(&self) -> Option<[u64; 2]> {
+        match *self {
+            LocalTime::Single(_) => None,
+            LocalTime::Ambiguous { earlier, later } | LocalTime::Skipped { earlier, later } => {
+                Some([earlier, later])
+            }
+        }
+    }
+}
+
+/// The unix time(s) a wall clock time in `tz` could mean
+pub fn resolve_local<Tz: TimeZone>(tz: &Tz, time: NaiveDateTime) -> Option<LocalTime> {
+    let mapped = tz.from_local_datetime(&time);
+    if let Some(single) = mapped.single() {
+        return Some(LocalTime::Single(u64::try_from(single.timestamp()).ok()?));
+    }
+    if let (Some(earlier), Some(later)) = (mapped.earliest(), mapped.latest()) {
+        return Some(LocalTime::Ambiguous {
+            earlier: u64::try_from(earlier.timestamp()).ok()?,
+            later: u64::try_from(later.timestamp()).ok()?,
+        });
+    }
+
+    // in a gap, so read it with the offsets from either side of it. Gaps
+    // are an hour almost everywhere, and never more than a few.
+    let offset_at = |local: NaiveDateTime| {
+        tz.from_local_datetime(&local)
+            .earliest()
+            .map(|t| i64::from(t.offset().fix().local_minus_utc()))
+    };
+    let before = offset_at(time - TimeDelta::hours(3))?;
+    let after = offset_at(time + TimeDelta::hours(3))?;
+    let wall = time.and_utc().timestamp();
+
+    Some(LocalTime::Skipped {
+        earlier: u64::try_from(wall - after).ok()?,
+        later: u64::try_from(wall - before).ok()?,
+    })
+}
+
+/// The offset from UTC in effect at a time, eg. `UTC+02:00`
+pub fn utc_offset(timestamp: u64) -> String {
+    local(timestamp)
+        .map(|time| time.format("UTC%:z").to_string())
+        .unwrap_or_default()
+}
+
+/// A time as it is in UTC, eg. `2024-10-27 00:30 UTC`
+pub fn format_utc(timestamp: u64) -> String {
+    Utc.timestamp_opt(timestamp as i64, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+fn twelve_hour(hour: u32) -> (u32, &'static str) {
+    let suffix = if hour < 12 { "AM" } else { "PM" };
+    let twelve = match hour % 12 {
+        0 => 12,
+        h => h,
+    };
+    (twelve, suffix)
+}
+
+fn local(timestamp: u64) -> Option<DateTime<Local>> {
+    Local.timestamp_opt(timestamp as i64, 0).single()
+}
+
+static SETTINGS: RwLock<TimeSettings> = RwLock::new(TimeSettings {
+    clock: ClockFormat::Auto,
+    week_start: WeekStart::Auto,
+});
+
+/// The current time settings
+pub fn settings() -> TimeSettings {
+    SETTINGS.read().map(|s| *s).unwrap_or_default()
+}
+
+pub fn init(path: &DataPath) {
+    let loaded = load_settings(path);
+    if let Ok(mut settings) = SETTINGS.write() {
+        *settings = loaded;
+    }
+}
+
+pub fn set_settings(path: &DataPath, new_settings: TimeSettings) {
+    if let Ok(mut settings) = SETTINGS.write() {
+        *settings = new_settings;
+    }
+    save_settings(path, &new_settings);
+}
+
+fn load_settings(path: &DataPath) -> TimeSettings {
+    let Ok(contents) =
+        Directory::new(path.path(DataPathType::Setting)).get_file(TIME_SETTINGS_FILE.to_owned())
+    else {
+        return TimeSettings::default();
+    };
+
+    match serde_json::from_str(&contents) {
+        Ok(settings) => settings,
+        Err(e) => {
+            error!("Could not parse time settings: {}", e);
+            TimeSettings::default()
+        }
+    }
+}
+
+fn save_settings(path: &DataPath, settings: &TimeSettings) {
+    let serialized = match serde_json::to_string(settings) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Could not serialize time settings: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = storage::write_file(
+        &path.path(DataPathType::Setting),
+        TIME_SETTINGS_FILE.to_owned(),
+        &serialized,
+    ) {
+        error!("Could not write time settings: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_hour() {
+        let twelve = TimeSettings {
+            clock: ClockFormat::TwelveHour,
+            ..Default::default()
+        };
+        let twenty_four = TimeSettings {
+            clock: ClockFormat::TwentyFourHour,
+            ..Default::default()
+        };
+
+        assert_eq!(twelve.format_hour(0), "12 AM");
+        assert_eq!(twelve.format_hour(9), "9 AM");
+        assert_eq!(twelve.format_hour(12), "12 PM");
+        assert_eq!(twelve.format_hour(22), "10 PM");
+        assert_eq!(twenty_four.format_hour(8), "08:00");
+        assert_eq!(twenty_four.format_hour(22), "22:00");
+    }
+
+    #[test]
+    fn test_day_range() {
+        let day = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+        assert_eq!(day_range(&chrono::Utc, day), Some(1719705600..1719792000));
+
+        let plus_two = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        assert_eq!(day_range(&plus_two, day), Some(1719698400..1719784800));
+
+        assert_eq!(parse_date(" 2024-06-30 "), Some(day));
+        assert_eq!(format_date(day), "2024-06-30");
+        assert_eq!(parse_date("30/06/2024"), None);
+    }
+
+    #[test]
+    fn test_parse_hour() {
+        assert_eq!(parse_hour("22"), Some(22));
+        assert_eq!(parse_hour("08:00"), Some(8));
+        assert_eq!(parse_hour("10 PM"), Some(22));
+        assert_eq!(parse_hour("12am"), Some(0));
+        assert_eq!(parse_hour("12 pm"), Some(12));
+        assert_eq!(parse_hour("24"), None);
+        assert_eq!(parse_hour("13pm"), None);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0);
+        assert_eq!(parse_time("14:30"), time(14, 30));
+        assert_eq!(parse_time("2:30pm"), time(14, 30));
+        assert_eq!(parse_time("12:05 AM"), time(0, 5));
+        assert_eq!(parse_time("9"), time(9, 0));
+        assert_eq!(parse_time("9 pm"), time(21, 0));
+        assert_eq!(parse_time("14:3"), None);
+        assert_eq!(parse_time("14:60"), None);
+        assert_eq!(parse_time("13pm"), None);
+
+        let twelve = TimeSettings {
+            clock: ClockFormat::TwelveHour,
+            ..Default::default()
+        };
+        assert_eq!(twelve.format_clock(time(0, 5).unwrap()), "12:05 AM");
+        assert_eq!(twelve.format_clock(time(14, 30).unwrap()), "2:30 PM");
+    }
+
+    /// Central European time: UTC+1, and UTC+2 from the last Sunday of
+    /// March to the last Sunday of October in 2024
+    #[derive(Clone)]
+    struct Cet;
+
+    impl Cet {
+        fn offset_at(utc: &NaiveDateTime) -> chrono::FixedOffset {
+            let summer = NaiveDate::from_ymd_opt(2024, 3, 31)
+                .and_then(|d| d.and_hms_opt(1, 0, 0))
+                .unwrap();
+            let winter = NaiveDate::from_ymd_opt(2024, 10, 27)
+                .and_then(|d| d.and_hms_opt(1, 0, 0))
+                .unwrap();
+            let hours = if (summer..winter).contains(utc) { 2 } else { 1 };
+            chrono::FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for Cet {
+        type Offset = chrono::FixedOffset;
+
+        fn from_offset(_offset: &Self::Offset) -> Self {
+            Cet
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> chrono::LocalResult<Self::Offset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(12, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(
+            &self,
+            local: &NaiveDateTime,
+        ) -> chrono::LocalResult<Self::Offset> {
+            let valid: Vec<_> = [1, 2]
+                .into_iter()
+                .map(|hours| chrono::FixedOffset::east_opt(hours * 3600).unwrap())
+                .filter(|offset| Cet::offset_at(&(*local - *offset)) == *offset)
+                .collect();
+            match valid[..] {
+                [offset] => chrono::LocalResult::Single(offset),
+                // summer time's later offset comes first in wall time
+                [winter, summer] => chrono::LocalResult::Ambiguous(summer, winter),
+                _ => chrono::LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> Self::Offset {
+            Cet::offset_at(&utc.and_hms_opt(12, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> Self::Offset {
+            Cet::offset_at(utc)
+        }
+    }
+
+    #[test]
+    fn test_resolve_local() {
+        let at = |m, d, h, min| {
+            NaiveDate::from_ymd_opt(2024, m, d)
+                .and_then(|d| d.and_hms_opt(h, min, 0))
+                .unwrap()
+        };
+        let utc = |m, d, h, min| at(m, d, h, min).and_utc().timestamp() as u64;
+
+        assert_eq!(
+            resolve_local(&Cet, at(6, 30, 12, 0)),
+            Some(LocalTime::Single(utc(6, 30, 10, 0)))
+        );
+
+        // clocks went back from 03:00 to 02:00
+        let ambiguous = resolve_local(&Cet, at(10, 27, 2, 30)).unwrap();
+        assert_eq!(
+            ambiguous,
+            LocalTime::Ambiguous {
+                earlier: utc(10, 27, 0, 30),
+                later: utc(10, 27, 1, 30),
+            }
+        );
+        assert_eq!(ambiguous.earliest(), utc(10, 27, 0, 30));
+
+        // clocks went forward from 02:00 to 03:00, so 02:30 is either
+        // 01:30 or 03:30
+        let skipped = resolve_local(&Cet, at(3, 31, 2, 30)).unwrap();
+        assert_eq!(
+            skipped.candidates(),
+            Some([utc(3, 31, 0, 30), utc(3, 31, 1, 30)])
+        );
+    }
+
+    #[test]
+    fn test_weekdays() {
+        let saturday = TimeSettings {
+            week_start: WeekStart::Saturday,
+            ..Default::default()
+        };
+        let days = saturday.weekdays();
+        assert_eq!(days[0], Weekday::Sat);
+        assert_eq!(days[1], Weekday::Sun);
+        assert_eq!(days[6], Weekday::Fri);
+    }
+}
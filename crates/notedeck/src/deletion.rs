@@ -0,0 +1,211 @@
+//! NIP-09 deletion requests we've received. People can only delete their
+//! own events, so a request only counts against events by its author. A
+//! request for an address deletes every version of it up to the request,
+//! but not ones published after.
+
+use std::collections::{HashMap, HashSet};
+
+use enostr::FilledKeypair;
+use nostrdb::{Filter, Ndb, Note, NoteBuilder, Transaction};
+
+use crate::note::{address, tag_strings};
+use crate::{Error, Result};
+
+pub const DELETION_KIND: u32 = 5;
+
+/// Most deletion requests we read at once
+const DELETION_LIMIT: i32 = 10_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct Deletions {
+    /// Deleted event ids, with who asked
+    ids: HashSet<([u8; 32], [u8; 32])>,
+    /// Deleted `kind:pubkey:d` addresses, and when the newest request was
+    addresses: HashMap<String, u64>,
+}
+
+/// Ask relays to delete one of our notes. Addressable notes are deleted
+/// by address too, so older versions go with it.
+pub fn request(keypair: FilledKeypair, note: &Note) -> Result<Note<'static>> {
+    let mut builder = NoteBuilder::new()
+        .kind(DELETION_KIND)
+        .content("")
+        .start_tag()
+        .tag_str("e")
+        .tag_str(&hex::encode(note.id()));
+    if let Some(address) = address(note) {
+        builder = builder.start_tag().tag_str("a").tag_str(&address);
+    }
+    builder = builder
+        .start_tag()
+        .tag_str("k")
+        .tag_str(&note.kind().to_string());
+
+    builder
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or_else(|| Error::Generic("failed to build deletion request".to_owned()))
+}
+
+impl Deletions {
+    /// Take in a kind 5 request. Other notes are ignored.
+    pub fn add(&mut self, request: &Note) {
+        if request.kind() != DELETION_KIND {
+            return;
+        }
+
+        let author = hex::encode(request.pubkey());
+        for tag in tag_strings(request) {
+            let Some(value) = tag.get(1) else {
+                continue;
+            };
+            match tag[0].as_str() {
+                "e" => {
+                    if let Some(id) = hex::decode(value).ok().and_then(|id| id.try_into().ok()) {
+                        self.ids.insert((id, *request.pubkey()));
+                    }
+                }
+                // the address has to be one of the author's own
+                "a" if value.split(':').nth(1) == Some(author.as_str()) => {
+                    let deleted_at = self.addresses.entry(value.clone()).or_default();
+                    *deleted_at = (*deleted_at).max(request.created_at());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether the note's author asked for it to be deleted
+    pub fn is_deleted(&self, note: &Note) -> bool {
+        if self.is_deleted_id(note.id(), note.pubkey()) {
+            return true;
+        }
+
+        address(note)
+            .and_then(|address| self.addresses.get(&address))
+            .is_some_and(|deleted_at| note.created_at() <= *deleted_at)
+    }
+
+    /// For events we haven't parsed yet, like ones still on their way to
+    /// nostrdb
+    pub fn is_deleted_id(&self, id: &[u8; 32], author: &[u8; 32]) -> bool {
+        self.ids.contains(&(*id, *author))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty() && self.addresses.is_empty()
+    }
+
+    /// Requests from these people, for nostrdb and relays
+    pub fn filter(authors: &[[u8; 32]]) -> Filter {
+        Filter::new()
+            .authors(authors.iter())
+            .kinds([DELETION_KIND as u64])
+            .limit(DELETION_LIMIT as u64)
+            .build()
+    }
+
+    /// The requests nostrdb has from these people
+    pub fn load(ndb: &Ndb, txn: &Transaction, authors: &[[u8; 32]]) -> Self {
+        let mut deletions = Deletions::default();
+        if authors.is_empty() {
+            return deletions;
+        }
+
+        if let Ok(results) = ndb.query(txn, &[Self::filter(authors)], DELETION_LIMIT) {
+            for result in &results {
+                deletions.add(&result.note);
+            }
+        }
+        deletions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+    use nostrdb::NoteBuilder;
+
+    fn sign(keypair: &FullKeypair, builder: NoteBuilder<'_>) -> Note<'static> {
+        builder
+            .sign(&keypair.secret_key.to_secret_bytes())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_only_authors_delete() {
+        let alice = FullKeypair::generate();
+        let mallory = FullKeypair::generate();
+
+        let post = sign(&alice, NoteBuilder::new().kind(1).content("hi"));
+        let event = |created_at: u64| {
+            sign(
+                &alice,
+                NoteBuilder::new()
+                    .kind(31923)
+                    .content("")
+                    .created_at(created_at)
+                    .start_tag()
+                    .tag_str("d")
+                    .tag_str("party"),
+            )
+        };
+        let address = format!("31923:{}:party", alice.pubkey.hex());
+
+        let mut deletions = Deletions::default();
+        // someone else can't delete alice's things
+        deletions.add(&sign(
+            &mallory,
+            NoteBuilder::new()
+                .kind(DELETION_KIND)
+                .content("")
+                .start_tag()
+                .tag_str("e")
+                .tag_str(&hex::encode(post.id()))
+                .start_tag()
+                .tag_str("a")
+                .tag_str(&address),
+        ));
+        assert!(!deletions.is_deleted(&post));
+        assert!(!deletions.is_deleted(&event(100)));
+
+        deletions.add(&sign(
+            &alice,
+            NoteBuilder::new()
+                .kind(DELETION_KIND)
+                .content("")
+                .created_at(200)
+                .start_tag()
+                .tag_str("e")
+                .tag_str(&hex::encode(post.id()))
+                .start_tag()
+                .tag_str("a")
+                .tag_str(&address),
+        ));
+        assert!(deletions.is_deleted(&post));
+        assert!(deletions.is_deleted(&event(100)));
+        // republished after the request
+        assert!(!deletions.is_deleted(&event(300)));
+    }
+
+    #[test]
+    fn test_request() {
+        let alice = FullKeypair::generate();
+        let event = sign(
+            &alice,
+            NoteBuilder::new()
+                .kind(31923)
+                .content("")
+                .start_tag()
+                .tag_str("d")
+                .tag_str("party"),
+        );
+
+        let mut deletions = Deletions::default();
+        deletions.add(&request(alice.to_filled(), &event).unwrap());
+        assert!(deletions.is_deleted(&event));
+        assert!(deletions.is_deleted_id(event.id(), alice.pubkey.bytes()));
+    }
+}
@@ -0,0 +1,260 @@
+//! Changing something already published. Replaceable events like
+//! profiles, lists and calendar events are signed again with the same
+//! kind (and `d` tag), and relays keep only the newest. A kind 1 note
+//! can't be replaced, so the corrected copy quotes the original and the
+//! original gets a NIP-09 deletion request.
+
+use enostr::FilledKeypair;
+use nostrdb::{Note, NoteBuilder};
+
+use crate::note::{is_addressable, tag_strings};
+use crate::{Error, Result};
+
+pub fn is_replaceable(kind: u32) -> bool {
+    kind == 0 || kind == 3 || (10000..20000).contains(&kind) || is_addressable(kind)
+}
+
+/// How an edit gets published
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditMode {
+    /// A new version that relays swap in for the old one
+    Republish,
+    /// A corrected copy, with the original deleted
+    Supersede,
+}
+
+impl EditMode {
+    pub fn of(kind: u32) -> Self {
+        if is_replaceable(kind) {
+            EditMode::Republish
+        } else {
+            EditMode::Supersede
+        }
+    }
+}
+
+/// Something that's different between the original and the edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A line of content that stays
+    Same(String),
+    Removed(String),
+    Added(String),
+    TagRemoved(Vec<String>),
+    TagAdded(Vec<String>),
+}
+
+impl Change {
+    pub fn is_change(&self) -> bool {
+        !matches!(self, Change::Same(_))
+    }
+}
+
+/// Line by line, so a fixed typo shows as one line out and one in
+fn line_diff(before: &str, after: &str) -> Vec<Change> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+
+    // longest common subsequence, filled from the end
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = vec![];
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            changes.push(Change::Same(a[i].to_owned()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            changes.push(Change::Removed(a[i].to_owned()));
+            i += 1;
+        } else {
+            changes.push(Change::Added(b[j].to_owned()));
+            j += 1;
+        }
+    }
+    changes.extend(
+        a[i..]
+            .iter()
+            .map(|line| Change::Removed((*line).to_owned())),
+    );
+    changes.extend(b[j..].iter().map(|line| Change::Added((*line).to_owned())));
+    changes
+}
+
+/// What publishing `content` and `tags` in place of `note` would change
+pub fn diff(note: &Note, content: &str, tags: &[Vec<String>]) -> Vec<Change> {
+    let before = tag_strings(note);
+    let mut changes = line_diff(note.content(), content);
+    changes.extend(
+        before
+            .iter()
+            .filter(|tag| !tags.contains(tag))
+            .cloned()
+            .map(Change::TagRemoved),
+    );
+    changes.extend(
+        tags.iter()
+            .filter(|tag| !before.contains(tag))
+            .cloned()
+            .map(Change::TagAdded),
+    );
+    changes
+}
+
+/// Tags written one JSON array per line, like `["t", "nostr"]`. Returns
+/// the number of the first line that isn't one.
+pub fn parse_tags(text: &str) -> std::result::Result<Vec<Vec<String>>, usize> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str::<Vec<String>>(line)
+                .ok()
+                .filter(|tag| !tag.is_empty())
+                .ok_or(i + 1)
+        })
+        .collect()
+}
+
+pub fn tags_to_text(tags: &[Vec<String>]) -> String {
+    tags.iter()
+        .filter_map(|tag| serde_json::to_string(tag).ok())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn d_tag(tags: &[Vec<String>]) -> Option<&str> {
+    tags.iter()
+        .find(|tag| tag[0] == "d")
+        .and_then(|tag| tag.get(1))
+        .map(String::as_str)
+}
+
+/// Sign the edited version of one of our notes
+pub fn edited(
+    keypair: FilledKeypair,
+    note: &Note,
+    content: &str,
+    tags: &[Vec<String>],
+) -> Result<Note<'static>> {
+    if note.pubkey() != keypair.pubkey.bytes() {
+        return Err(Error::Generic(
+            "only our own notes can be edited".to_owned(),
+        ));
+    }
+    if is_addressable(note.kind()) && d_tag(tags) != d_tag(&tag_strings(note)) {
+        // it would be a second event next to this one, not a new version
+        return Err(Error::Generic("the d tag can't change".to_owned()));
+    }
+    if note.kind() == 0 && serde_json::from_str::<serde_json::Value>(content).is_err() {
+        return Err(Error::Generic("a profile has to be JSON".to_owned()));
+    }
+
+    let mut builder = NoteBuilder::new().kind(note.kind()).content(content);
+    for tag in tags {
+        builder = builder.start_tag();
+        for value in tag {
+            builder = builder.tag_str(value);
+        }
+    }
+    if EditMode::of(note.kind()) == EditMode::Supersede {
+        builder = builder
+            .start_tag()
+            .tag_str("q")
+            .tag_str(&hex::encode(note.id()));
+    }
+
+    builder
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or_else(|| Error::Generic("failed to build edited note".to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+
+    fn sign(keypair: &FullKeypair, builder: NoteBuilder<'_>) -> Note<'static> {
+        builder
+            .sign(&keypair.secret_key.to_secret_bytes())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_line_diff() {
+        assert_eq!(
+            line_diff("gm\nnostr is grate\nbye", "gm\nnostr is great\nbye"),
+            [
+                Change::Same("gm".to_owned()),
+                Change::Removed("nostr is grate".to_owned()),
+                Change::Added("nostr is great".to_owned()),
+                Change::Same("bye".to_owned()),
+            ]
+        );
+        assert_eq!(line_diff("", "new"), [Change::Added("new".to_owned())]);
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        let tags = parse_tags("[\"t\", \"nostr\"]\n\n[\"d\",\"party\"]").unwrap();
+        assert_eq!(tags, [vec!["t", "nostr"], vec!["d", "party"]]);
+        assert_eq!(tags_to_text(&tags), "[\"t\",\"nostr\"]\n[\"d\",\"party\"]");
+        assert_eq!(parse_tags("[\"t\"]\nnope"), Err(2));
+        assert_eq!(parse_tags("[]"), Err(1));
+    }
+
+    #[test]
+    fn test_edited() {
+        let alice = FullKeypair::generate();
+        let event = sign(
+            &alice,
+            NoteBuilder::new()
+                .kind(31923)
+                .content("")
+                .start_tag()
+                .tag_str("d")
+                .tag_str("party")
+                .start_tag()
+                .tag_str("title")
+                .tag_str("Party"),
+        );
+        let tags = vec![
+            vec!["d".to_owned(), "party".to_owned()],
+            vec!["title".to_owned(), "Big party".to_owned()],
+        ];
+        let edit = edited(alice.to_filled(), &event, "", &tags).unwrap();
+        assert_eq!(edit.kind(), 31923);
+        assert_eq!(tag_strings(&edit), tags);
+        assert_eq!(
+            diff(&event, "", &tags),
+            [
+                Change::TagRemoved(vec!["title".to_owned(), "Party".to_owned()]),
+                Change::TagAdded(vec!["title".to_owned(), "Big party".to_owned()]),
+            ]
+        );
+
+        let moved = vec![vec!["d".to_owned(), "other".to_owned()]];
+        assert!(edited(alice.to_filled(), &event, "", &moved).is_err());
+        assert!(edited(FullKeypair::generate().to_filled(), &event, "", &tags).is_err());
+
+        // a note can't be replaced, the copy points back at it
+        let note = sign(&alice, NoteBuilder::new().kind(1).content("typo"));
+        let fixed = edited(alice.to_filled(), &note, "fixed", &[]).unwrap();
+        assert_eq!(
+            tag_strings(&fixed),
+            [vec!["q".to_owned(), hex::encode(note.id())]]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use std::io;
 
+use crate::PermissionError;
+
 /// App related errors
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -21,6 +23,9 @@ pub enum Error {
     #[error("io error: {0}")]
     Nostrdb(#[from] nostrdb::Error),
 
+    #[error("{}", .0.message())]
+    Permission(#[from] PermissionError),
+
     #[error("generic error: {0}")]
     Generic(String),
 }
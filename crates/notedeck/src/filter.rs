@@ -2,6 +2,7 @@ use crate::error::{Error, FilterError};
 use crate::note::NoteRef;
 use crate::Result;
 use nostrdb::{Filter, FilterBuilder, Note, Subscription};
+use serde_json::Value;
 use std::collections::HashMap;
 use tracing::{debug, warn};
 
@@ -280,3 +281,135 @@ pub fn filter_from_tags(note: &Note, add_pubkey: Option<&[u8; 32]>) -> Result<Fi
         hashtags: hashtag_res,
     })
 }
+
+/// Parse filters as they'd appear in a REQ, either a single filter object
+/// or a list of them
+pub fn filters_from_json(json: &str) -> Result<Vec<Filter>> {
+    match serde_json::from_str(json)? {
+        Value::Array(filters) => filters.iter().map(filter_from_json_value).collect(),
+        filter @ Value::Object(_) => Ok(vec![filter_from_json_value(&filter)?]),
+        _ => Err(Error::Generic(
+            "expected a filter or a list of filters".to_owned(),
+        )),
+    }
+}
+
+/// Swap the kinds of any filter asking for one of `from` over to `to`,
+/// leaving everything else about it alone. Filters for other kinds, like
+/// a contact list fetch, are passed through as they are.
+pub fn replace_kinds(filters: &[Filter], from: &[u64], to: &[u64]) -> Result<Vec<Filter>> {
+    filters
+        .iter()
+        .map(|filter| {
+            let mut value: Value = serde_json::from_str(&filter.json()?)?;
+            let Some(kinds) = value.get_mut("kinds") else {
+                return Ok(filter.clone());
+            };
+            if !json_numbers(kinds)?.iter().any(|kind| from.contains(kind)) {
+                return Ok(filter.clone());
+            }
+            *kinds = Value::from(to.to_vec());
+            filter_from_json_value(&value)
+        })
+        .collect()
+}
+
+fn filter_from_json_value(value: &Value) -> Result<Filter> {
+    let fields = value
+        .as_object()
+        .ok_or_else(|| Error::Generic("expected a filter object".to_owned()))?;
+
+    let mut filter = Filter::new();
+    for (name, field) in fields {
+        match name.as_str() {
+            "ids" => filter = filter.ids(json_ids(field)?.iter()),
+            "authors" => filter = filter.authors(json_ids(field)?.iter()),
+            "kinds" => filter = filter.kinds(json_numbers(field)?),
+            "since" => filter = filter.since(json_number(field)?),
+            "until" => filter = filter.until(json_number(field)?),
+            "limit" => filter = filter.limit(json_number(field)?),
+            name => {
+                let mut chars = name.strip_prefix('#').unwrap_or_default().chars();
+                let (Some(tag), None) = (chars.next(), chars.next()) else {
+                    return Err(Error::Generic(format!("unsupported filter field {name}")));
+                };
+
+                filter.start_tags_field(tag)?;
+                for element in json_strings(field)? {
+                    // e and p tags are stored as ids, so we need to match
+                    // them as ids
+                    match hex_id(element) {
+                        Some(id) if tag == 'e' || tag == 'p' => filter.add_id_element(&id)?,
+                        _ => filter.add_str_element(element)?,
+                    }
+                }
+                filter.end_field();
+            }
+        }
+    }
+
+    Ok(filter.build())
+}
+
+fn hex_id(s: &str) -> Option<[u8; 32]> {
+    hex::decode(s).ok()?.try_into().ok()
+}
+
+fn json_strings(field: &Value) -> Result<Vec<&str>> {
+    field
+        .as_array()
+        .and_then(|elements| elements.iter().map(Value::as_str).collect())
+        .ok_or_else(|| Error::Generic(format!("expected a list of strings, got {field}")))
+}
+
+fn json_ids(field: &Value) -> Result<Vec<[u8; 32]>> {
+    json_strings(field)?
+        .into_iter()
+        .map(|id| hex_id(id).ok_or_else(|| Error::Generic(format!("invalid id {id}"))))
+        .collect()
+}
+
+fn json_number(field: &Value) -> Result<u64> {
+    field
+        .as_u64()
+        .ok_or_else(|| Error::Generic(format!("expected a number, got {field}")))
+}
+
+fn json_numbers(field: &Value) -> Result<Vec<u64>> {
+    field
+        .as_array()
+        .and_then(|elements| elements.iter().map(Value::as_u64).collect())
+        .ok_or_else(|| Error::Generic(format!("expected a list of numbers, got {field}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters_from_json() {
+        let filters = filters_from_json(r#"[{"kinds":[1],"limit":10}]"#).unwrap();
+        let expected = Filter::new().kinds([1]).limit(10).build();
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].json().unwrap(), expected.json().unwrap());
+
+        let filters = filters_from_json(r##"{"#t":["nostr"]}"##).unwrap();
+        assert_eq!(filters.len(), 1);
+
+        assert!(filters_from_json(r#"{"kinds":["one"]}"#).is_err());
+        assert!(filters_from_json(r#"{"nope":[]}"#).is_err());
+        assert!(filters_from_json("3").is_err());
+    }
+
+    #[test]
+    fn test_replace_kinds() {
+        let filters = vec![
+            Filter::new().kinds([1]).limit(10).build(),
+            Filter::new().kinds([3]).limit(1).build(),
+        ];
+        let replaced = replace_kinds(&filters, &[1, 6], &[1, 6]).unwrap();
+        let expected = Filter::new().kinds([1, 6]).limit(10).build();
+        assert_eq!(replaced[0].json().unwrap(), expected.json().unwrap());
+        assert_eq!(replaced[1].json().unwrap(), filters[1].json().unwrap());
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use nostrdb::{Filter, Ndb, Note, Transaction};
+
+use crate::note::tag_strings;
+
+pub const FOLLOW_PACK_KIND: u32 = 39089;
+
+/// A follow pack, sometimes called a starter pack: a named set of people
+/// someone recommends following together, usually around a topic.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FollowPack {
+    pub author: [u8; 32],
+    /// The `d` tag, which together with the author names the pack
+    pub identifier: String,
+    pub title: String,
+    pub description: String,
+    pub image: Option<String>,
+    /// In the order the author listed them, without duplicates
+    pub members: Vec<[u8; 32]>,
+    /// Lowercased `t` tags, what the pack is about
+    pub hashtags: Vec<String>,
+    pub created_at: u64,
+}
+
+impl FollowPack {
+    pub fn from_note(note: &Note) -> Option<Self> {
+        if note.kind() != FOLLOW_PACK_KIND {
+            return None;
+        }
+
+        Self::from_tags(
+            *note.pubkey(),
+            note.created_at(),
+            note.content(),
+            tag_strings(note),
+        )
+    }
+
+    fn from_tags(
+        author: [u8; 32],
+        created_at: u64,
+        content: &str,
+        tags: Vec<Vec<String>>,
+    ) -> Option<Self> {
+        let mut pack = FollowPack {
+            author,
+            created_at,
+            ..Default::default()
+        };
+        let mut identifier = None;
+
+        for tag in tags {
+            let Some(value) = tag.get(1) else {
+                continue;
+            };
+            match tag[0].as_str() {
+                "d" => identifier = Some(value.clone()),
+                "title" | "name" if pack.title.is_empty() => pack.title = value.clone(),
+                "description" | "summary" if pack.description.is_empty() => {
+                    pack.description = value.clone()
+                }
+                "image" if pack.image.is_none() => pack.image = Some(value.clone()),
+                "p" => {
+                    let Some(member) = parse_pubkey(value) else {
+                        continue;
+                    };
+                    if !pack.members.contains(&member) {
+                        pack.members.push(member);
+                    }
+                }
+                "t" => {
+                    let hashtag = value.trim_start_matches('#').to_lowercase();
+                    if !hashtag.is_empty() && !pack.hashtags.contains(&hashtag) {
+                        pack.hashtags.push(hashtag);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if pack.description.is_empty() {
+            pack.description = content.trim().to_owned();
+        }
+
+        // a pack without a name or anyone in it isn't worth suggesting
+        pack.identifier = identifier?;
+        if pack.title.is_empty() || pack.members.is_empty() {
+            return None;
+        }
+
+        Some(pack)
+    }
+
+    pub fn filter(limit: u64) -> Filter {
+        Filter::new()
+            .kinds([FOLLOW_PACK_KIND as u64])
+            .limit(limit)
+            .build()
+    }
+
+    /// Every pack we have, newest version of each, biggest first
+    pub fn load(ndb: &Ndb, txn: &Transaction, limit: i32) -> Vec<FollowPack> {
+        let results = match ndb.query(txn, &[Self::filter(limit as u64)], limit) {
+            Ok(results) => results,
+            Err(_) => return vec![],
+        };
+
+        let mut newest: HashMap<([u8; 32], String), FollowPack> = HashMap::new();
+        for pack in results
+            .iter()
+            .filter_map(|result| FollowPack::from_note(&result.note))
+        {
+            let key = (pack.author, pack.identifier.clone());
+            if !newest
+                .get(&key)
+                .is_some_and(|seen| seen.created_at >= pack.created_at)
+            {
+                newest.insert(key, pack);
+            }
+        }
+
+        let mut packs: Vec<FollowPack> = newest.into_values().collect();
+        packs.sort_by(|a, b| {
+            b.members
+                .len()
+                .cmp(&a.members.len())
+                .then(b.created_at.cmp(&a.created_at))
+        });
+        packs
+    }
+
+    /// Whether the pack is about any of these hashtags, going by its
+    /// hashtags or, for packs without any, its title
+    pub fn is_about(&self, hashtags: &[&str]) -> bool {
+        if !self.hashtags.is_empty() {
+            return self
+                .hashtags
+                .iter()
+                .any(|tag| hashtags.iter().any(|wanted| tag == wanted));
+        }
+
+        let title = self.title.to_lowercase();
+        title
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| hashtags.contains(&word))
+    }
+
+    /// Members the account doesn't follow yet, leaving out the account
+    pub fn new_to<'a>(
+        &'a self,
+        account: &'a [u8; 32],
+        followed: &'a [[u8; 32]],
+    ) -> impl Iterator<Item = &'a [u8; 32]> + 'a {
+        self.members
+            .iter()
+            .filter(move |member| *member != account && !followed.contains(member))
+    }
+}
+
+fn parse_pubkey(hex_key: &str) -> Option<[u8; 32]> {
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(hex_key, &mut bytes).ok()?;
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn test_from_tags() {
+        let alice = "aa".repeat(32);
+        let bob = "bb".repeat(32);
+        let tags = vec![
+            tag(&["d", "artists"]),
+            tag(&["title", "Artists"]),
+            tag(&["p", &alice]),
+            tag(&["p", &bob, "wss://relay.damus.io"]),
+            tag(&["p", &alice]),
+            tag(&["p", "not hex"]),
+            tag(&["t", "#Art"]),
+        ];
+
+        let pack = FollowPack::from_tags([1; 32], 10, "people who draw", tags).unwrap();
+        assert_eq!(pack.identifier, "artists");
+        assert_eq!(pack.title, "Artists");
+        assert_eq!(pack.description, "people who draw");
+        assert_eq!(pack.members, vec![[0xaa; 32], [0xbb; 32]]);
+        assert_eq!(pack.hashtags, vec!["art".to_owned()]);
+
+        assert!(pack.is_about(&["art", "artstr"]));
+        assert!(!pack.is_about(&["music"]));
+        let followed = [[0xbb; 32]];
+        assert_eq!(
+            pack.new_to(&[1; 32], &followed).collect::<Vec<_>>(),
+            vec![&[0xaa; 32]]
+        );
+    }
+
+    #[test]
+    fn test_incomplete_packs() {
+        let member = tag(&["p", &"aa".repeat(32)]);
+
+        // no identifier
+        assert!(FollowPack::from_tags([1; 32], 0, "", vec![tag(&["title", "x"])]).is_none());
+        // nobody in it
+        let empty = vec![tag(&["d", "x"]), tag(&["title", "x"])];
+        assert!(FollowPack::from_tags([1; 32], 0, "", empty).is_none());
+        // untagged packs are matched by their title
+        let untagged = vec![tag(&["d", "x"]), tag(&["title", "Music makers"]), member];
+        let pack = FollowPack::from_tags([1; 32], 0, "", untagged).unwrap();
+        assert!(pack.is_about(&["music"]));
+        assert!(!pack.is_about(&["art"]));
+    }
+}
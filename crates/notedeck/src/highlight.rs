@@ -0,0 +1,211 @@
+//! NIP-84 highlights: a passage someone picked out of a note or an
+//! article. The passage is the content, the note it came from is
+//! referenced by id, and by address too when it's an article, so the
+//! highlight still points at it once the article is edited.
+
+use enostr::FilledKeypair;
+use nostrdb::{Filter, Ndb, Note, NoteBuilder, Transaction};
+
+use crate::note::{address, tag_strings};
+use crate::{Error, Result};
+
+pub const HIGHLIGHT_KIND: u32 = 9802;
+
+/// Where a highlight came from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HighlightSource {
+    Event([u8; 32]),
+    /// `kind:pubkey:d` of an addressable note
+    Address(String),
+}
+
+impl HighlightSource {
+    /// The ways a note can be pointed at by its highlights
+    pub fn of(note: &Note) -> Vec<HighlightSource> {
+        let mut sources = vec![HighlightSource::Event(*note.id())];
+        if let Some(address) = address(note) {
+            sources.push(HighlightSource::Address(address));
+        }
+        sources
+    }
+
+    /// Highlights of this
+    pub fn filter(&self, limit: u64) -> Filter {
+        let filter = Filter::new().kinds([HIGHLIGHT_KIND as u64]).limit(limit);
+        match self {
+            HighlightSource::Event(id) => filter.event(id),
+            HighlightSource::Address(address) => filter.tags([address.clone()], 'a'),
+        }
+        .build()
+    }
+}
+
+/// What a highlight is of. An address wins over an id, it's the more
+/// lasting of the two.
+pub fn source(highlight: &Note) -> Option<HighlightSource> {
+    if highlight.kind() != HIGHLIGHT_KIND {
+        return None;
+    }
+
+    let tags = tag_strings(highlight);
+    let value = |name: &str| {
+        tags.iter()
+            .find(|tag| tag[0] == name)
+            .and_then(|tag| tag.get(1))
+    };
+
+    if let Some(address) = value("a") {
+        return Some(HighlightSource::Address(address.clone()));
+    }
+    value("e")
+        .and_then(|id| hex::decode(id).ok())
+        .and_then(|id| id.try_into().ok())
+        .map(HighlightSource::Event)
+}
+
+/// The note a highlight is of, if we have it. For an address that's
+/// the newest version we've seen.
+pub fn find_source<'a>(
+    ndb: &Ndb,
+    txn: &'a Transaction,
+    source: &HighlightSource,
+) -> Option<Note<'a>> {
+    match source {
+        HighlightSource::Event(id) => ndb.get_note_by_id(txn, id).ok(),
+        HighlightSource::Address(address) => {
+            let mut parts = address.splitn(3, ':');
+            let kind: u64 = parts.next()?.parse().ok()?;
+            let author: [u8; 32] = hex::decode(parts.next()?).ok()?.try_into().ok()?;
+            let d = parts.next()?;
+            let filter = Filter::new()
+                .kinds([kind])
+                .authors([&author])
+                .tags([d.to_owned()], 'd')
+                .limit(1)
+                .build();
+            ndb.query(txn, &[filter], 1)
+                .ok()?
+                .into_iter()
+                .next()
+                .map(|result| result.note)
+        }
+    }
+}
+
+/// The comment someone left with their highlight, if any
+pub fn comment(highlight: &Note) -> Option<String> {
+    tag_strings(highlight)
+        .into_iter()
+        .find(|tag| tag[0] == "comment")
+        .and_then(|tag| tag.get(1).cloned())
+        .filter(|comment| !comment.is_empty())
+}
+
+/// The paragraph `passage` is in, so readers can see it in place. None
+/// when the passage is the whole paragraph anyway.
+pub fn context<'a>(content: &'a str, passage: &str) -> Option<&'a str> {
+    let paragraph = content
+        .split("\n\n")
+        .map(str::trim)
+        .find(|paragraph| paragraph.contains(passage))?;
+    (paragraph != passage.trim()).then_some(paragraph)
+}
+
+/// Highlight a passage of someone's note
+pub fn highlight(
+    keypair: FilledKeypair,
+    source: &Note,
+    passage: &str,
+    comment: &str,
+) -> Result<Note<'static>> {
+    if passage.trim().is_empty() {
+        return Err(Error::Generic("nothing to highlight".to_owned()));
+    }
+
+    let mut builder = NoteBuilder::new()
+        .kind(HIGHLIGHT_KIND)
+        .content(passage)
+        .start_tag()
+        .tag_str("e")
+        .tag_str(&hex::encode(source.id()));
+    if let Some(address) = address(source) {
+        builder = builder.start_tag().tag_str("a").tag_str(&address);
+    }
+    builder = builder
+        .start_tag()
+        .tag_str("p")
+        .tag_str(&hex::encode(source.pubkey()))
+        .tag_str("")
+        .tag_str("author");
+    if let Some(context) = context(source.content(), passage) {
+        builder = builder.start_tag().tag_str("context").tag_str(context);
+    }
+    if !comment.trim().is_empty() {
+        builder = builder
+            .start_tag()
+            .tag_str("comment")
+            .tag_str(comment.trim());
+    }
+
+    builder
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or_else(|| Error::Generic("failed to build highlight".to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+
+    #[test]
+    fn test_context() {
+        let content = "First part.\n\nThe quick brown fox jumps.\n\nLast part.";
+        assert_eq!(
+            context(content, "brown fox"),
+            Some("The quick brown fox jumps.")
+        );
+        assert_eq!(context(content, "Last part."), None);
+        assert_eq!(context(content, "not in it"), None);
+    }
+
+    #[test]
+    fn test_highlight() {
+        let alice = FullKeypair::generate();
+        let bob = FullKeypair::generate();
+
+        let article = NoteBuilder::new()
+            .kind(30023)
+            .content("Intro.\n\nThe good bit is here.")
+            .start_tag()
+            .tag_str("d")
+            .tag_str("essay")
+            .sign(&alice.secret_key.to_secret_bytes())
+            .build()
+            .unwrap();
+
+        let picked = highlight(bob.to_filled(), &article, "good bit", "so true").unwrap();
+        assert_eq!(picked.content(), "good bit");
+        assert_eq!(
+            source(&picked),
+            Some(HighlightSource::Address(format!(
+                "30023:{}:essay",
+                alice.pubkey.hex()
+            )))
+        );
+        assert_eq!(comment(&picked).as_deref(), Some("so true"));
+        assert!(HighlightSource::of(&article).contains(&source(&picked).unwrap()));
+
+        let post = NoteBuilder::new()
+            .kind(1)
+            .content("gm")
+            .sign(&alice.secret_key.to_secret_bytes())
+            .build()
+            .unwrap();
+        let picked = highlight(bob.to_filled(), &post, "gm", "").unwrap();
+        assert_eq!(source(&picked), Some(HighlightSource::Event(*post.id())));
+        assert_eq!(comment(&picked), None);
+
+        assert!(highlight(bob.to_filled(), &post, "  ", "").is_err());
+    }
+}
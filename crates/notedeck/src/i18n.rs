@@ -0,0 +1,286 @@
+//! Translations for user facing strings.
+//!
+//! Strings live in fluent files under `assets/translations`, one per
+//! language, and are looked up by id with the [`tr!`](crate::tr) macro.
+//! Anything missing from the selected language falls back to English, and
+//! anything missing from English falls back to the id itself, so a typo
+//! shows up on screen instead of crashing.
+
+use std::sync::{OnceLock, RwLock};
+
+use fluent_bundle::{concurrent::FluentBundle, FluentResource};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+use unic_langid::LanguageIdentifier;
+
+use crate::{storage, DataPath, DataPathType, Directory};
+
+const LANGUAGE_FILE: &str = "language.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Language {
+    /// BCP 47 tag, eg. `en-US`
+    pub id: &'static str,
+    /// The name of the language in that language
+    pub name: &'static str,
+    ftl: &'static str,
+}
+
+pub const ENGLISH: Language = Language {
+    id: "en-US",
+    name: "English",
+    ftl: include_str!("../../../assets/translations/en-US.ftl"),
+};
+
+pub const LANGUAGES: &[Language] = &[
+    ENGLISH,
+    Language {
+        id: "de-DE",
+        name: "Deutsch",
+        ftl: include_str!("../../../assets/translations/de-DE.ftl"),
+    },
+    Language {
+        id: "es-ES",
+        name: "Español",
+        ftl: include_str!("../../../assets/translations/es-ES.ftl"),
+    },
+];
+
+/// The language picked in settings. `None` means follow the system.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LanguageSetting {
+    language: Option<String>,
+}
+
+struct Localization {
+    language: Language,
+    /// What the user picked, if they didn't want the system language
+    chosen: Option<Language>,
+    system: Option<Language>,
+    bundle: FluentBundle<FluentResource>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+impl Localization {
+    fn new(system: Option<Language>, chosen: Option<Language>) -> Self {
+        let language = chosen.or(system).unwrap_or(ENGLISH);
+        Localization {
+            language,
+            chosen,
+            system,
+            bundle: bundle(language),
+            fallback: bundle(ENGLISH),
+        }
+    }
+
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        format_message(&self.bundle, id, args)
+            .or_else(|| format_message(&self.fallback, id, args))
+            .unwrap_or_else(|| id.to_owned())
+    }
+}
+
+fn bundle(language: Language) -> FluentBundle<FluentResource> {
+    let langid: LanguageIdentifier = language.id.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // unicode isolation marks show up as boxes in egui
+    bundle.set_use_isolating(false);
+
+    let resource = match FluentResource::try_new(language.ftl.to_owned()) {
+        Ok(resource) => resource,
+        Err((resource, errors)) => {
+            error!("{} translations have errors: {:?}", language.id, errors);
+            resource
+        }
+    };
+
+    if let Err(errors) = bundle.add_resource(resource) {
+        error!("failed to load {} translations: {:?}", language.id, errors);
+    }
+
+    bundle
+}
+
+fn format_message(
+    bundle: &FluentBundle<FluentResource>,
+    id: &str,
+    args: Option<&FluentArgs>,
+) -> Option<String> {
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = vec![];
+    let formatted = bundle.format_pattern(pattern, args, &mut errors);
+    if !errors.is_empty() {
+        error!("errors formatting {id}: {:?}", errors);
+    }
+    Some(formatted.into_owned())
+}
+
+fn localization() -> &'static RwLock<Localization> {
+    static LOCALIZATION: OnceLock<RwLock<Localization>> = OnceLock::new();
+    LOCALIZATION.get_or_init(|| RwLock::new(Localization::new(None, None)))
+}
+
+/// The closest language we have a translation for. Matches the full tag
+/// first and then just the language, so `es-MX` gets Spanish.
+pub fn find_language(tag: &str) -> Option<Language> {
+    let tag = tag.replace('_', "-");
+    let tag = tag.split('.').next().unwrap_or(&tag);
+
+    if let Some(language) = LANGUAGES.iter().find(|l| l.id.eq_ignore_ascii_case(tag)) {
+        return Some(*language);
+    }
+
+    let wanted = tag.split('-').next()?;
+    LANGUAGES
+        .iter()
+        .find(|l| {
+            l.id.split('-')
+                .next()
+                .is_some_and(|lang| lang.eq_ignore_ascii_case(wanted))
+        })
+        .copied()
+}
+
+/// Set up translations from the saved setting, falling back to the
+/// system locale
+pub fn init(path: &DataPath, system_locale: Option<&str>) {
+    let system = system_locale.and_then(find_language);
+    let chosen = load_setting(path)
+        .language
+        .and_then(|tag| find_language(&tag));
+
+    let loaded = Localization::new(system, chosen);
+    info!(
+        "using {} translations (system locale {:?})",
+        loaded.language.id, system_locale
+    );
+
+    if let Ok(mut current) = localization().write() {
+        *current = loaded;
+    }
+}
+
+/// Switch languages. `None` follows the system locale.
+pub fn set_language(path: &DataPath, language: Option<Language>) {
+    let Ok(mut current) = localization().write() else {
+        return;
+    };
+
+    *current = Localization::new(current.system, language);
+    save_setting(
+        path,
+        &LanguageSetting {
+            language: language.map(|l| l.id.to_owned()),
+        },
+    );
+}
+
+/// The language we're showing
+pub fn current_language() -> Language {
+    localization().read().map(|l| l.language).unwrap_or(ENGLISH)
+}
+
+/// The language picked in settings, `None` if we're following the system
+pub fn chosen_language() -> Option<Language> {
+    localization().read().ok().and_then(|l| l.chosen)
+}
+
+pub fn tr(id: &str) -> String {
+    localization()
+        .read()
+        .map(|l| l.format(id, None))
+        .unwrap_or_else(|_| id.to_owned())
+}
+
+pub fn tr_args(id: &str, args: &FluentArgs) -> String {
+    localization()
+        .read()
+        .map(|l| l.format(id, Some(args)))
+        .unwrap_or_else(|_| id.to_owned())
+}
+
+/// Look up a translated string by id, with optional named arguments:
+///
+/// ```ignore
+/// tr!("session-log-in");
+/// tr!("notification-mentioned", name = "jb55");
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::i18n::tr($id)
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = $crate::i18n::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::tr_args($id, &args)
+    }};
+}
+
+pub use fluent_bundle::FluentArgs;
+
+fn load_setting(path: &DataPath) -> LanguageSetting {
+    let Ok(contents) =
+        Directory::new(path.path(DataPathType::Setting)).get_file(LANGUAGE_FILE.to_owned())
+    else {
+        return LanguageSetting::default();
+    };
+
+    match serde_json::from_str(&contents) {
+        Ok(setting) => setting,
+        Err(e) => {
+            error!("Could not parse language setting: {}", e);
+            LanguageSetting::default()
+        }
+    }
+}
+
+fn save_setting(path: &DataPath, setting: &LanguageSetting) {
+    let serialized = match serde_json::to_string(setting) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Could not serialize language setting: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = storage::write_file(
+        &path.path(DataPathType::Setting),
+        LANGUAGE_FILE.to_owned(),
+        &serialized,
+    ) {
+        error!("Could not write language setting: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_language() {
+        assert_eq!(find_language("en-US").map(|l| l.id), Some("en-US"));
+        assert_eq!(find_language("es_MX.UTF-8").map(|l| l.id), Some("es-ES"));
+        assert_eq!(find_language("de").map(|l| l.id), Some("de-DE"));
+        assert_eq!(find_language("xx-YY"), None);
+    }
+
+    #[test]
+    fn test_fallbacks() {
+        let german = find_language("de-DE");
+        let localization = Localization::new(german, None);
+
+        assert_eq!(localization.format("session-log-in", None), "Anmelden");
+        assert_eq!(
+            localization.format("not-a-real-message", None),
+            "not-a-real-message"
+        );
+
+        let mut args = FluentArgs::new();
+        args.set("count", 3);
+        assert_eq!(
+            Localization::new(None, None).format("time-ago-days", Some(&args)),
+            "3d"
+        );
+    }
+}
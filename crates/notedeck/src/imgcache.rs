@@ -4,6 +4,7 @@ use poll_promise::Promise;
 
 use egui::ColorImage;
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fs::{create_dir_all, File};
 
@@ -19,6 +20,10 @@ pub type ImageCacheMap = HashMap<String, ImageCacheValue>;
 pub struct ImageCache {
     pub cache_dir: path::PathBuf,
     url_imgs: ImageCacheMap,
+    /// The frame each image was last drawn in, so the least recently
+    /// used ones go first when we're over budget
+    last_used: RefCell<HashMap<String, u64>>,
+    frame: u64,
 }
 
 impl ImageCache {
@@ -26,6 +31,8 @@ impl ImageCache {
         Self {
             cache_dir,
             url_imgs: HashMap::new(),
+            last_used: RefCell::new(HashMap::new()),
+            frame: 0,
         }
     }
 
@@ -118,6 +125,68 @@ impl ImageCache {
         Ok(())
     }
 
+    /// An image that's about to be drawn. Unlike going through [`Self::map`]
+    /// this counts as a use, keeping it from being evicted.
+    pub fn get(&self, url: &str) -> Option<&ImageCacheValue> {
+        let value = self.url_imgs.get(url)?;
+        let mut last_used = self.last_used.borrow_mut();
+        match last_used.get_mut(url) {
+            Some(frame) => *frame = self.frame,
+            None => {
+                last_used.insert(url.to_owned(), self.frame);
+            }
+        }
+        Some(value)
+    }
+
+    /// How many images are decoded and roughly how many bytes of texture
+    /// memory they hold
+    pub fn usage(&self) -> (usize, usize) {
+        self.url_imgs
+            .values()
+            .filter_map(|promise| promise.ready()?.as_ref().ok())
+            .fold((0, 0), |(count, bytes), texture| {
+                (count + 1, bytes + texture.byte_size())
+            })
+    }
+
+    /// Drop the least recently drawn images until their textures fit in
+    /// `budget` bytes. Anything drawn this frame stays. Returns how many
+    /// were evicted. Call once a frame, after drawing.
+    pub fn end_frame(&mut self, budget: usize) -> usize {
+        let frame = self.frame;
+        self.frame += 1;
+
+        let (_, mut used) = self.usage();
+        if used <= budget {
+            return 0;
+        }
+
+        let last_used = self.last_used.get_mut();
+        let mut candidates: Vec<(u64, String, usize)> = self
+            .url_imgs
+            .iter()
+            .filter_map(|(url, promise)| {
+                let texture = promise.ready()?.as_ref().ok()?;
+                let last = last_used.get(url).copied().unwrap_or(0);
+                (last < frame).then(|| (last, url.clone(), texture.byte_size()))
+            })
+            .collect();
+        candidates.sort_unstable();
+
+        let mut evicted = 0;
+        for (_, url, bytes) in candidates {
+            if used <= budget {
+                break;
+            }
+            self.url_imgs.remove(&url);
+            last_used.remove(&url);
+            used = used.saturating_sub(bytes);
+            evicted += 1;
+        }
+        evicted
+    }
+
     pub fn map(&self) -> &ImageCacheMap {
         &self.url_imgs
     }
@@ -126,3 +195,36 @@ impl ImageCache {
         &mut self.url_imgs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_drawn() {
+        let ctx = egui::Context::default();
+        let mut cache = ImageCache::new(PathBuf::from("."));
+        for url in ["a", "b", "c"] {
+            let texture = ctx.load_texture(
+                url,
+                ColorImage::new([16, 16], egui::Color32::RED),
+                Default::default(),
+            );
+            cache
+                .map_mut()
+                .insert(url.to_owned(), Promise::from_ready(Ok(texture)));
+        }
+        let image_bytes = 16 * 16 * 4;
+        assert_eq!(cache.usage(), (3, 3 * image_bytes));
+
+        // a is drawn every frame, b only in the first one, c never
+        cache.get("a");
+        cache.get("b");
+        assert_eq!(cache.end_frame(3 * image_bytes), 0);
+
+        cache.get("a");
+        assert_eq!(cache.end_frame(image_bytes), 2);
+        assert!(cache.map().contains_key("a"));
+        assert_eq!(cache.usage(), (1, image_bytes));
+    }
+}
@@ -0,0 +1,120 @@
+use enostr::FilledKeypair;
+use nostrdb::{Note, NoteBuilder};
+
+use crate::note::tag_strings;
+use crate::{Error, Result};
+
+pub const INTERESTS_KIND: u32 = 10015;
+
+/// A NIP-51 interests list: the hashtags someone follows, plus any
+/// interest sets they point to. Anything we don't understand, including
+/// encrypted private items, is kept so republishing doesn't lose it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interests {
+    tags: Vec<Vec<String>>,
+    content: String,
+    created_at: u64,
+}
+
+impl Interests {
+    pub fn from_note(note: &Note) -> Option<Self> {
+        if note.kind() != INTERESTS_KIND {
+            return None;
+        }
+
+        let tags = tag_strings(note);
+
+        Some(Interests {
+            tags,
+            content: note.content().to_owned(),
+            created_at: note.created_at(),
+        })
+    }
+
+    /// Followed hashtags, in the order they were followed
+    pub fn hashtags(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .iter()
+            .filter(|tag| tag[0] == "t")
+            .filter_map(|tag| tag.get(1))
+            .map(String::as_str)
+    }
+
+    pub fn follows_hashtag(&self, hashtag: &str) -> bool {
+        let hashtag = normalize(hashtag);
+        self.hashtags().any(|t| normalize(t) == hashtag)
+    }
+
+    pub fn follow_hashtag(&mut self, hashtag: &str) {
+        if !self.follows_hashtag(hashtag) {
+            self.tags.push(vec!["t".to_owned(), normalize(hashtag)]);
+        }
+    }
+
+    pub fn unfollow_hashtag(&mut self, hashtag: &str) {
+        let hashtag = normalize(hashtag);
+        self.tags.retain(|tag| {
+            !(tag[0] == "t" && tag.get(1).map(|t| normalize(t)).as_ref() == Some(&hashtag))
+        });
+    }
+
+    /// Keep whichever version is newer. Replaceable lists can come back
+    /// from nostrdb out of order.
+    pub fn update(&mut self, other: Interests) {
+        if other.created_at >= self.created_at {
+            *self = other;
+        }
+    }
+
+    /// Sign a new version of the list
+    pub fn to_note(&self, keypair: FilledKeypair) -> Result<Note<'static>> {
+        let mut builder = NoteBuilder::new()
+            .kind(INTERESTS_KIND)
+            .content(&self.content);
+        for tag in &self.tags {
+            builder = builder.start_tag();
+            for field in tag {
+                builder = builder.tag_str(field);
+            }
+        }
+
+        builder
+            .sign(&keypair.secret_key.to_secret_bytes())
+            .build()
+            .ok_or_else(|| Error::Generic("failed to build interests list".to_owned()))
+    }
+}
+
+/// Hashtags are matched case insensitively and without the leading #
+fn normalize(hashtag: &str) -> String {
+    hashtag.trim().trim_start_matches('#').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follow_unfollow() {
+        let mut interests = Interests {
+            tags: vec![vec!["a".to_owned(), "30015:abc:nostr".to_owned()]],
+            ..Default::default()
+        };
+
+        interests.follow_hashtag("#Bitcoin");
+        interests.follow_hashtag("bitcoin");
+        interests.follow_hashtag("art");
+        assert_eq!(
+            interests.hashtags().collect::<Vec<_>>(),
+            vec!["bitcoin", "art"]
+        );
+        assert!(interests.follows_hashtag("BITCOIN"));
+
+        interests.unfollow_hashtag("#bitcoin");
+        assert!(!interests.follows_hashtag("bitcoin"));
+        assert_eq!(interests.hashtags().collect::<Vec<_>>(), vec!["art"]);
+
+        // the interest set is left alone
+        assert_eq!(interests.tags.len(), 2);
+    }
+}
@@ -1,39 +1,76 @@
 mod accounts;
+pub mod amount;
 mod app;
 mod args;
+pub mod backup;
+pub mod blocklist;
+pub mod bookmarks;
+pub mod calendar;
+pub mod contacts;
 mod context;
+pub mod crash;
+pub mod datetime;
+pub mod deletion;
+pub mod edit;
 mod error;
 pub mod filter;
+pub mod follow_pack;
 pub mod fonts;
+pub mod highlight;
+pub mod i18n;
 mod imgcache;
+pub mod interests;
+pub mod links;
+pub mod maintenance;
 mod muted;
+pub mod network;
 pub mod note;
 mod notecache;
+pub mod permissions;
+mod pow;
+mod relayspec;
+pub mod repost;
 mod result;
+mod session;
+pub mod settings;
+pub mod spell;
 pub mod storage;
 mod style;
+pub mod text;
 pub mod theme;
 mod theme_handler;
-mod time;
+pub mod time;
 mod timecache;
+pub mod toast;
 pub mod ui;
 mod unknowns;
+pub mod updater;
 mod user_account;
+mod wot;
 
 pub use accounts::{AccountData, Accounts, AccountsAction, AddAccountAction, SwitchAccountAction};
-pub use app::App;
+pub use app::{AccountChange, App, AppAction, AppCommand, AppManifest, AppView};
 pub use args::Args;
+pub use bookmarks::{BookmarkList, Bookmarks};
 pub use context::AppContext;
 pub use error::{Error, FilterError};
 pub use filter::{FilterState, FilterStates, UnifiedSubscription};
+pub use follow_pack::FollowPack;
 pub use fonts::NamedFontFamily;
 pub use imgcache::ImageCache;
+pub use interests::Interests;
 pub use muted::{MuteFun, Muted};
 pub use note::{NoteRef, RootIdError, RootNoteId, RootNoteIdBuf};
 pub use notecache::{CachedNote, NoteCache};
+pub use permissions::{Capability, PermissionError, Permissions};
+pub use pow::{leading_zero_bits, PowMiner, PowPoll, PowProgress, UnsignedNote};
+pub use relayspec::RelaySpec;
 pub use result::Result;
+pub use session::SessionCapability;
+pub use settings::Settings;
 pub use storage::{
-    DataPath, DataPathType, Directory, FileKeyStorage, KeyStorageResponse, KeyStorageType,
+    AppStorage, DataPath, DataPathType, Directory, FileKeyStorage, KeyProtection,
+    KeyStorageResponse, KeyStorageType, SecretStore,
 };
 pub use style::NotedeckTextStyle;
 pub use theme::ColorTheme;
@@ -42,3 +79,4 @@ pub use time::time_ago_since;
 pub use timecache::TimeCached;
 pub use unknowns::{get_unknown_note_ids, NoteRefsUnkIdAction, SingleUnkIdAction, UnknownIds};
 pub use user_account::UserAccount;
+pub use wot::{WebOfTrust, WotParams};
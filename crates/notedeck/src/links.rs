@@ -0,0 +1,698 @@
+//! Deciding what happens when a link is clicked. Links to nostr things,
+//! including web clients' links to them, open in the app. Other links
+//! follow the user's per-domain rules: the system browser, a preview
+//! inside the app, or copying. Domains made to look like well known
+//! ones are confirmed first.
+
+use std::sync::{Arc, Mutex};
+
+use enostr::{Address, NoteId, Pubkey};
+use url::Url;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LinkBehavior {
+    Browser,
+    Preview,
+    Copy,
+}
+
+impl LinkBehavior {
+    pub const ALL: [LinkBehavior; 3] = [
+        LinkBehavior::Browser,
+        LinkBehavior::Preview,
+        LinkBehavior::Copy,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            LinkBehavior::Browser => "browser",
+            LinkBehavior::Preview => "preview",
+            LinkBehavior::Copy => "copy",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "preview" => LinkBehavior::Preview,
+            "copy" => LinkBehavior::Copy,
+            _ => LinkBehavior::Browser,
+        }
+    }
+}
+
+/// Something in nostr a link points at
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NostrLink {
+    Profile(Pubkey),
+    Note(NoteId),
+    Address(Address),
+}
+
+impl NostrLink {
+    /// A bech32 entity, with or without `nostr:`
+    pub fn parse(entity: &str) -> Option<Self> {
+        let entity = entity
+            .get(..6)
+            .filter(|scheme| scheme.eq_ignore_ascii_case("nostr:"))
+            .map(|_| &entity[6..])
+            .unwrap_or(entity);
+
+        if let Some(pubkey) = Pubkey::from_bech(entity) {
+            Some(NostrLink::Profile(pubkey))
+        } else if let Some(address) = Address::from_bech(entity) {
+            Some(NostrLink::Address(address))
+        } else {
+            NoteId::from_bech(entity).map(NostrLink::Note)
+        }
+    }
+}
+
+/// Web clients whose links end in the entity they show, like
+/// `https://njump.me/npub1...` or `https://primal.net/e/note1...`
+const WEB_CLIENTS: &[&str] = &[
+    "njump.me",
+    "primal.net",
+    "snort.social",
+    "iris.to",
+    "coracle.social",
+    "nostrudel.ninja",
+    "nostr.band",
+    "yakihonne.com",
+    "habla.news",
+    "nostr.at",
+    "nostr.com",
+];
+
+/// Domains people trust enough that someone might imitate them
+const WELL_KNOWN: &[&str] = &[
+    "damus.io",
+    "njump.me",
+    "primal.net",
+    "snort.social",
+    "iris.to",
+    "coracle.social",
+    "nostr.band",
+    "nostr.com",
+    "zeus.app",
+    "getalby.com",
+    "strike.me",
+    "walletofsatoshi.com",
+    "github.com",
+    "google.com",
+    "apple.com",
+    "microsoft.com",
+    "paypal.com",
+    "coinbase.com",
+    "kraken.com",
+    "binance.com",
+    "youtube.com",
+    "twitter.com",
+    "x.com",
+];
+
+/// Where a url points, if it's a nostr link or a web client's link to
+/// something we can show ourselves
+pub fn nostr_link(url: &str) -> Option<NostrLink> {
+    let url = url.trim();
+    if url
+        .get(..6)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("nostr:"))
+    {
+        return NostrLink::parse(url);
+    }
+
+    let parsed = Url::parse(url).ok()?;
+    let host = host(&parsed)?;
+    if !WEB_CLIENTS.iter().any(|client| within(&host, client)) {
+        return None;
+    }
+
+    parsed
+        .path_segments()?
+        .rev()
+        .find(|segment| !segment.is_empty())
+        .and_then(NostrLink::parse)
+}
+
+fn host(url: &Url) -> Option<String> {
+    let host = url.host_str()?.trim_end_matches('.').to_lowercase();
+    Some(host.strip_prefix("www.").map(str::to_owned).unwrap_or(host))
+}
+
+/// `host` is `domain` or one of its subdomains
+fn within(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.ends_with('.'))
+}
+
+/// The last two labels, near enough to what a person reads as the site
+fn registrable(host: &str) -> &str {
+    let mut dots = host.rmatch_indices('.');
+    dots.next();
+    match dots.next() {
+        Some((i, _)) => &host[i + 1..],
+        None => host,
+    }
+}
+
+/// Digits and letter pairs that pass for other letters at a glance
+fn skeleton(domain: &str) -> String {
+    domain
+        .replace("rn", "m")
+        .replace("vv", "w")
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | 'i' => 'l',
+            '3' => 'e',
+            '5' => 's',
+            _ => c,
+        })
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Why a host looks like it's pretending to be somewhere else, naming
+/// where that is when we can tell
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Suspicion {
+    /// Close to a well known domain without being it
+    Imitates(&'static str),
+    /// Uses letters from other alphabets, which can copy any domain
+    Unicode,
+}
+
+pub fn suspicious(host: &str) -> Option<Suspicion> {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let site = registrable(&host);
+
+    // a real one, or a subdomain of one
+    if WELL_KNOWN.iter().any(|known| within(&host, known)) {
+        return None;
+    }
+
+    for known in WELL_KNOWN {
+        // damus.io.example.com
+        let disguised = host
+            .strip_suffix(site)
+            .is_some_and(|subdomains| subdomains.contains(&format!("{known}.")));
+        // 0 for o, rn for m and small typos. Short domains are too easy
+        // to be near by accident.
+        let lookalike = skeleton(site) == skeleton(known)
+            || (known.len() > 8 && edit_distance(site, known) <= 1);
+        if disguised || lookalike {
+            return Some(Suspicion::Imitates(*known));
+        }
+    }
+
+    if host.split('.').any(|label| label.starts_with("xn--")) {
+        return Some(Suspicion::Unicode);
+    }
+
+    None
+}
+
+/// The user's rules for web links
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LinkRules {
+    pub default: LinkBehavior,
+    /// Domains, which cover their subdomains too, and what to do with
+    /// them
+    pub domains: Vec<(String, LinkBehavior)>,
+    /// Open web clients' links to nostr things in the app
+    pub open_web_clients: bool,
+}
+
+impl Default for LinkRules {
+    fn default() -> Self {
+        LinkRules {
+            default: LinkBehavior::Browser,
+            domains: vec![],
+            open_web_clients: true,
+        }
+    }
+}
+
+/// Domains from a setting, separated by commas or whitespace
+pub fn domains(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|entry| {
+            let host = Url::parse(entry)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_owned))
+                .unwrap_or_else(|| entry.split(['/', ':']).next().unwrap_or("").to_owned());
+            let host = host
+                .trim_start_matches("*.")
+                .trim_matches('.')
+                .to_lowercase();
+            (!host.is_empty()).then_some(host)
+        })
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LinkDecision {
+    /// Show it in the app
+    Nostr(NostrLink),
+    Web(LinkBehavior),
+    /// Ask before going on
+    Confirm(Suspicion),
+    /// Not something we can open
+    Invalid,
+}
+
+impl LinkRules {
+    /// What a domain's rule says, the most specific rule winning
+    pub fn behavior(&self, host: &str) -> LinkBehavior {
+        self.domains
+            .iter()
+            .filter(|(domain, _)| within(host, domain))
+            .max_by_key(|(domain, _)| domain.len())
+            .map(|(_, behavior)| *behavior)
+            .unwrap_or(self.default)
+    }
+
+    pub fn decide(&self, url: &str) -> LinkDecision {
+        let is_nostr_uri = url
+            .trim()
+            .get(..6)
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("nostr:"));
+        if is_nostr_uri || self.open_web_clients {
+            if let Some(link) = nostr_link(url) {
+                return LinkDecision::Nostr(link);
+            }
+        }
+
+        let Some(parsed) = Url::parse(url.trim()).ok() else {
+            return LinkDecision::Invalid;
+        };
+        let Some(host) = host(&parsed) else {
+            // mailto: and the like are the system's business
+            return LinkDecision::Web(LinkBehavior::Browser);
+        };
+
+        if let Some(suspicion) = suspicious(&host) {
+            return LinkDecision::Confirm(suspicion);
+        }
+
+        LinkDecision::Web(self.behavior(&host))
+    }
+}
+
+/// The host of a url, for showing and for rules
+pub fn link_host(url: &str) -> Option<String> {
+    Url::parse(url.trim()).ok().as_ref().and_then(host)
+}
+
+/// What a page says about itself, for previewing it without a browser
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PagePreview {
+    pub title: Option<String>,
+    pub site_name: Option<String>,
+    pub description: Option<String>,
+    /// The page's paragraphs as plain text, up to a limit
+    pub paragraphs: Vec<String>,
+}
+
+/// How much of a page's text a preview keeps
+const PREVIEW_TEXT: usize = 4000;
+
+impl PagePreview {
+    pub fn parse(html: &str) -> Self {
+        let mut preview = PagePreview {
+            title: element_text(html, "title").next(),
+            ..Default::default()
+        };
+
+        for tag in tags(html, "meta") {
+            let key = attribute(tag, "property").or_else(|| attribute(tag, "name"));
+            let Some(content) = attribute(tag, "content").filter(|c| !c.is_empty()) else {
+                continue;
+            };
+            match key.as_deref() {
+                Some("og:title") | Some("twitter:title") => preview.title = Some(content),
+                Some("og:site_name") => preview.site_name = Some(content),
+                Some("og:description") | Some("twitter:description") => {
+                    preview.description = Some(content)
+                }
+                Some("description") if preview.description.is_none() => {
+                    preview.description = Some(content)
+                }
+                _ => {}
+            }
+        }
+
+        let mut total = 0;
+        for paragraph in element_text(html, "p") {
+            if paragraph.is_empty() {
+                continue;
+            }
+            total += paragraph.len();
+            preview.paragraphs.push(paragraph);
+            if total > PREVIEW_TEXT {
+                break;
+            }
+        }
+
+        preview
+    }
+}
+
+/// The opening tags named `name`, without the angle brackets
+fn tags<'a>(html: &'a str, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    let lower = html.to_ascii_lowercase();
+    let open = format!("<{name}");
+    let mut from = 0;
+    std::iter::from_fn(move || loop {
+        let start = from + lower[from..].find(&open)?;
+        let after = start + open.len();
+        // <p> and not <pre>
+        let boundary = lower[after..]
+            .chars()
+            .next()
+            .is_some_and(|c| c == '>' || c == '/' || c.is_whitespace());
+        let end = after + lower[after..].find('>')?;
+        from = end + 1;
+        if boundary {
+            return Some(&html[after..end]);
+        }
+    })
+}
+
+/// The text inside each `name` element
+fn element_text<'a>(html: &'a str, name: &'a str) -> impl Iterator<Item = String> + 'a {
+    let lower = html.to_ascii_lowercase();
+    let open = format!("<{name}");
+    let close = format!("</{name}>");
+    let mut from = 0;
+    std::iter::from_fn(move || loop {
+        let start = from + lower[from..].find(&open)?;
+        let after = start + open.len();
+        let boundary = lower[after..]
+            .chars()
+            .next()
+            .is_some_and(|c| c == '>' || c.is_whitespace());
+        let content = after + lower[after..].find('>')? + 1;
+        if !boundary {
+            from = content;
+            continue;
+        }
+        let end = lower[content..]
+            .find(&close)
+            .map(|i| content + i)
+            .unwrap_or(html.len());
+        from = end;
+        return Some(plain_text(&html[content..end]));
+    })
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(found) = lower[from..].find(name) {
+        let start = from + found;
+        from = start + name.len();
+        let preceded = lower[..start]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_whitespace);
+        let rest = lower[from..].trim_start();
+        if !preceded || !rest.starts_with('=') {
+            continue;
+        }
+
+        let value_start = tag.len() - rest.len() + 1;
+        let value = tag[value_start..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or(""),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap_or(""),
+        };
+        return Some(decode_entities(value).trim().to_owned());
+    }
+    None
+}
+
+/// Pages bigger than this are only read this far
+const MAX_PAGE: usize = 512 * 1024;
+
+/// A page being fetched for a preview. Nothing it links to, like images
+/// or scripts, is loaded.
+pub struct PageFetch {
+    result: Arc<Mutex<Option<Result<PagePreview, String>>>>,
+}
+
+impl PageFetch {
+    pub fn start(ctx: &egui::Context, url: &str) -> Self {
+        let result = Arc::new(Mutex::new(None));
+
+        let done = result.clone();
+        let ctx = ctx.clone();
+        ehttp::fetch(ehttp::Request::get(url), move |response| {
+            let preview = response.and_then(|response| {
+                if !response.ok {
+                    return Err(format!("{} {}", response.status, response.status_text));
+                }
+                let not_html = response.headers.iter().any(|(name, value)| {
+                    name.eq_ignore_ascii_case("content-type") && !value.contains("html")
+                });
+                if not_html {
+                    return Err("not a web page".to_owned());
+                }
+                let bytes = &response.bytes[..response.bytes.len().min(MAX_PAGE)];
+                Ok(PagePreview::parse(&String::from_utf8_lossy(bytes)))
+            });
+
+            if let Ok(mut result) = done.lock() {
+                *result = Some(preview);
+            }
+            ctx.request_repaint();
+        });
+
+        PageFetch { result }
+    }
+
+    /// None while it's loading
+    pub fn result(&self) -> Option<Result<PagePreview, String>> {
+        self.result.lock().ok().and_then(|result| result.clone())
+    }
+}
+
+/// Tags dropped, entities decoded and whitespace collapsed
+fn plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_owned();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" | "#39" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|n| n.parse().ok()))
+                    .and_then(char::from_u32),
+            }?;
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_web_client_links() {
+        let pubkey =
+            Pubkey::from_hex("3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d")
+                .unwrap();
+        let npub = pubkey.to_bech().unwrap();
+        let note_id = NoteId::new([7; 32]);
+        let note = note_id.to_bech().unwrap();
+
+        assert_eq!(
+            nostr_link(&format!("https://njump.me/{npub}")),
+            Some(NostrLink::Profile(pubkey))
+        );
+        assert_eq!(
+            nostr_link(&format!("https://primal.net/e/{note}")),
+            Some(NostrLink::Note(note_id))
+        );
+        assert_eq!(
+            nostr_link(&format!("https://www.primal.net/p/{npub}/")),
+            Some(NostrLink::Profile(pubkey))
+        );
+        assert_eq!(
+            nostr_link(&format!("nostr:{note}")),
+            Some(NostrLink::Note(note_id))
+        );
+        // not a web client we know
+        assert_eq!(nostr_link(&format!("https://example.com/{npub}")), None);
+        assert_eq!(nostr_link("https://primal.net/jb55"), None);
+
+        let mut rules = LinkRules::default();
+        assert_eq!(
+            rules.decide(&format!("https://njump.me/{note}")),
+            LinkDecision::Nostr(NostrLink::Note(note_id))
+        );
+        rules.open_web_clients = false;
+        assert_eq!(
+            rules.decide(&format!("https://njump.me/{note}")),
+            LinkDecision::Web(LinkBehavior::Browser)
+        );
+        assert_eq!(
+            rules.decide(&format!("nostr:{note}")),
+            LinkDecision::Nostr(NostrLink::Note(note_id))
+        );
+    }
+
+    #[test]
+    fn test_domain_rules() {
+        let rules = LinkRules {
+            default: LinkBehavior::Preview,
+            domains: domains("example.com, https://docs.example.com/x")
+                .map(|d| {
+                    let behavior = if d.starts_with("docs") {
+                        LinkBehavior::Copy
+                    } else {
+                        LinkBehavior::Browser
+                    };
+                    (d, behavior)
+                })
+                .collect(),
+            open_web_clients: true,
+        };
+
+        assert_eq!(rules.behavior("example.com"), LinkBehavior::Browser);
+        assert_eq!(rules.behavior("www.example.com"), LinkBehavior::Browser);
+        assert_eq!(rules.behavior("docs.example.com"), LinkBehavior::Copy);
+        assert_eq!(rules.behavior("notexample.com"), LinkBehavior::Preview);
+        assert_eq!(
+            rules.decide("https://a.example.com/page"),
+            LinkDecision::Web(LinkBehavior::Browser)
+        );
+        assert_eq!(rules.decide("not a url"), LinkDecision::Invalid);
+    }
+
+    #[test]
+    fn test_suspicious() {
+        assert_eq!(suspicious("damus.io"), None);
+        assert_eq!(suspicious("www.github.com"), None);
+        assert_eq!(suspicious("example.com"), None);
+        assert_eq!(suspicious("x.co"), None);
+
+        assert_eq!(
+            suspicious("darnus.io"),
+            Some(Suspicion::Imitates("damus.io"))
+        );
+        assert_eq!(
+            suspicious("paypa1.com"),
+            Some(Suspicion::Imitates("paypal.com"))
+        );
+        assert_eq!(
+            suspicious("githhub.com"),
+            Some(Suspicion::Imitates("github.com"))
+        );
+        assert_eq!(
+            suspicious("damus.io.login.example.com"),
+            Some(Suspicion::Imitates("damus.io"))
+        );
+        assert_eq!(suspicious("xn--dmus-hra.io"), Some(Suspicion::Unicode));
+
+        let rules = LinkRules::default();
+        assert_eq!(
+            rules.decide("https://primaI.net/e/whatever"),
+            LinkDecision::Confirm(Suspicion::Imitates("primal.net"))
+        );
+    }
+
+    #[test]
+    fn test_page_preview() {
+        let html = r#"<html><head>
+            <title>Plain title</title>
+            <meta property="og:title" content="Better &amp; title">
+            <meta name="description" content='A page'>
+            <meta property="og:site_name" content="Example" />
+            </head><body>
+            <pre>not a paragraph</pre>
+            <p class="lead">Hello <b>there</b>,&nbsp;world &#x2764;</p>
+            <P>Second</P>
+            <p></p>
+            </body></html>"#;
+
+        let preview = PagePreview::parse(html);
+        assert_eq!(preview.title.as_deref(), Some("Better & title"));
+        assert_eq!(preview.site_name.as_deref(), Some("Example"));
+        assert_eq!(preview.description.as_deref(), Some("A page"));
+        assert_eq!(
+            preview.paragraphs,
+            vec!["Hello there , world ❤".to_owned(), "Second".to_owned()]
+        );
+    }
+}
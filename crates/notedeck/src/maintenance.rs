@@ -0,0 +1,451 @@
+//! Keeping the nostrdb directory from growing forever. Stats count what's
+//! stored by kind, and compaction copies the notes worth keeping into a
+//! fresh database that replaces the old one on the next start. nostrdb
+//! can't delete notes in place, and a copy gives the space back anyway.
+//! Notes that arrive after the copy started are carried over at the swap.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use nostrdb::{Config, Filter, Ndb, Note, NoteKey, Transaction};
+use tracing::{error, info};
+
+use crate::{time::unix_now, Args, DataPath, DataPathType, Error, Result};
+
+/// Notes read per transaction while walking the whole database
+const PAGE: i32 = 5_000;
+/// A month as far as retention cares
+const MONTH: u64 = 30 * 24 * 60 * 60;
+/// Written into a finished copy, so a half done one is never swapped in.
+/// It holds when the copy started.
+const READY_FILE: &str = "compacted";
+/// Notes made this long before a copy started are carried over at the
+/// swap too, for clocks that are a little off
+const CATCH_UP_MARGIN: Duration = Duration::from_secs(60 * 60);
+
+/// How much virtual memory to map for a database
+pub fn mapsize() -> usize {
+    if cfg!(target_os = "windows") {
+        // 16 Gib on windows because it actually creates the file
+        1024usize * 1024usize * 1024usize * 16usize
+    } else {
+        // 1 TiB for everything else since its just virtually mapped
+        1024usize * 1024usize * 1024usize * 1024usize
+    }
+}
+
+/// Where nostrdb lives, `--dbpath` if we were started with one
+pub fn db_dir(path: &DataPath, args: &Args) -> PathBuf {
+    args.dbpath
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.path(DataPathType::Db))
+}
+
+fn open(dir: &Path) -> Result<Ndb> {
+    let config = Config::new().set_mapsize(mapsize());
+    Ok(Ndb::new(dir.to_str().unwrap_or_default(), &config)?)
+}
+
+fn compacted_dir(db_dir: &Path) -> PathBuf {
+    let mut name = db_dir.file_name().unwrap_or_default().to_os_string();
+    name.push("-compacted");
+    db_dir.with_file_name(name)
+}
+
+/// Bytes used by the database files
+pub fn db_size(db_dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(db_dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.metadata().ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Swap in a finished compaction before nostrdb is opened. Returns
+/// whether there was one.
+pub fn use_compacted(db_dir: &Path) -> bool {
+    let compacted = compacted_dir(db_dir);
+    let started = fs::read_to_string(compacted.join(READY_FILE))
+        .ok()
+        .and_then(|started| started.trim().parse().ok());
+    let Some(started) = started else {
+        // an interrupted compaction isn't worth keeping
+        if compacted.exists() {
+            let _ = fs::remove_dir_all(&compacted);
+        }
+        return false;
+    };
+
+    match catch_up(db_dir, &compacted, started) {
+        Ok(copied) => info!("carried {copied} new notes over to the compacted database"),
+        Err(err) => {
+            // better to keep everything than lose what came in since
+            error!("could not catch up the compacted database, keeping the old one: {err}");
+            let _ = fs::remove_dir_all(&compacted);
+            return false;
+        }
+    }
+
+    let mut old_name = db_dir.file_name().unwrap_or_default().to_os_string();
+    old_name.push("-old");
+    let old = db_dir.with_file_name(old_name);
+
+    let swapped = fs::rename(db_dir, &old)
+        .and_then(|()| fs::rename(&compacted, db_dir))
+        .and_then(|()| fs::remove_file(db_dir.join(READY_FILE)));
+    match swapped {
+        Ok(()) => {
+            info!("using compacted database, was {} bytes", db_size(&old));
+            if let Err(err) = fs::remove_dir_all(&old) {
+                error!("could not remove old database: {err}");
+            }
+            true
+        }
+        Err(err) => {
+            error!("could not swap in compacted database: {err}");
+            // put the old one back if it was moved
+            if !db_dir.exists() {
+                let _ = fs::rename(&old, db_dir);
+            }
+            false
+        }
+    }
+}
+
+/// Copy what the old database got while the compaction ran. Notes are
+/// walked by when they were made, so some are copied twice, which nostrdb
+/// ignores.
+fn catch_up(db_dir: &Path, compacted: &Path, started: u64) -> Result<usize> {
+    let old = open(db_dir)?;
+    let copy = open(compacted)?;
+    let since = started.saturating_sub(CATCH_UP_MARGIN.as_secs());
+    let mut copied = 0;
+    each_note(&old, since, &Progress::default(), |note| {
+        if copy_note(&copy, note) {
+            copied += 1;
+        }
+    })?;
+    // dropping the copy waits for it to write everything
+    Ok(copied)
+}
+
+fn copy_note(copy: &Ndb, note: &Note) -> bool {
+    let json = match note.json() {
+        Ok(json) => json,
+        Err(err) => {
+            error!("compact: could not read note: {err}");
+            return false;
+        }
+    };
+    if let Err(err) = copy.process_event(&format!("[\"EVENT\",\"compact\",{json}]")) {
+        error!("compact: could not copy note: {err}");
+    }
+    true
+}
+
+/// Which notes compaction leaves behind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retention {
+    pub kinds: Vec<u32>,
+    pub older_than_months: u32,
+    /// Keep old notes from people in the account's web of trust
+    pub keep_trusted: bool,
+}
+
+impl Retention {
+    /// Kinds as written in settings, like "1, 7"
+    pub fn parse_kinds(kinds: &str) -> Vec<u32> {
+        kinds
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|kind| kind.parse().ok())
+            .collect()
+    }
+
+    /// `keep` is everyone whose notes stay no matter how old: our
+    /// accounts, and who they trust if that's wanted
+    pub fn drops(&self, note: &Note, now: u64, keep: &HashSet<[u8; 32]>) -> bool {
+        let cutoff = now.saturating_sub(self.older_than_months as u64 * MONTH);
+        self.kinds.contains(&note.kind())
+            && note.created_at() < cutoff
+            && !keep.contains(note.pubkey())
+    }
+}
+
+/// How far a background task got, readable from the UI while it runs
+#[derive(Debug, Default)]
+pub struct Progress {
+    scanned: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    pub fn scanned(&self) -> usize {
+        self.scanned.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageStats {
+    pub bytes: u64,
+    pub notes: usize,
+    pub by_kind: BTreeMap<u32, usize>,
+}
+
+impl StorageStats {
+    /// Kinds with the most notes first
+    pub fn largest_kinds(&self) -> Vec<(u32, usize)> {
+        let mut kinds: Vec<(u32, usize)> = self.by_kind.iter().map(|(k, n)| (*k, *n)).collect();
+        kinds.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        kinds
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compacted {
+    pub kept: usize,
+    pub dropped: usize,
+}
+
+/// Walk every note made since `since`, newest first, a page per
+/// transaction
+fn each_note(ndb: &Ndb, since: u64, progress: &Progress, mut f: impl FnMut(&Note)) -> Result<()> {
+    let mut until = u64::MAX;
+    // notes at `until` that were already seen, since pages overlap there
+    let mut boundary: HashSet<NoteKey> = HashSet::new();
+
+    loop {
+        if progress.is_cancelled() {
+            return Err(Error::Generic("cancelled".to_owned()));
+        }
+
+        let txn = Transaction::new(ndb)?;
+        let filter = Filter::new()
+            .since(since)
+            .until(until)
+            .limit(PAGE as u64)
+            .build();
+        let page = ndb.query(&txn, &[filter], PAGE)?;
+        if page.is_empty() {
+            return Ok(());
+        }
+
+        let oldest = page
+            .iter()
+            .map(|qr| qr.note.created_at())
+            .min()
+            .unwrap_or(0);
+        let mut new = 0;
+        for qr in &page {
+            if qr.note.created_at() == until && boundary.contains(&qr.note_key) {
+                continue;
+            }
+            new += 1;
+            f(&qr.note);
+        }
+        progress.scanned.fetch_add(new, Ordering::Relaxed);
+
+        if new == 0 {
+            // a whole page of notes from the same second we've seen
+            if until == 0 {
+                return Ok(());
+            }
+            until -= 1;
+            boundary.clear();
+            continue;
+        }
+
+        if oldest != until {
+            boundary.clear();
+        }
+        boundary.extend(
+            page.iter()
+                .filter(|qr| qr.note.created_at() == oldest)
+                .map(|qr| qr.note_key),
+        );
+        until = oldest;
+    }
+}
+
+/// Count what's stored. Slow on a big database, run it off the UI thread.
+pub fn stats(ndb: &Ndb, db_dir: &Path, progress: &Progress) -> Result<StorageStats> {
+    let mut stats = StorageStats {
+        bytes: db_size(db_dir),
+        ..Default::default()
+    };
+    each_note(ndb, 0, progress, |note| {
+        stats.notes += 1;
+        *stats.by_kind.entry(note.kind()).or_default() += 1;
+    })?;
+    Ok(stats)
+}
+
+/// Copy everything `retention` doesn't drop into a new database, which
+/// takes over on the next start. Keep using `ndb` meanwhile, anything new
+/// is copied over then.
+pub fn compact(
+    ndb: &Ndb,
+    db_dir: &Path,
+    retention: &Retention,
+    keep: &HashSet<[u8; 32]>,
+    progress: &Progress,
+) -> Result<Compacted> {
+    let dest = compacted_dir(db_dir);
+    if dest.exists() {
+        fs::remove_dir_all(&dest)?;
+    }
+    fs::create_dir_all(&dest)?;
+
+    let now = unix_now();
+    let mut compacted = Compacted {
+        kept: 0,
+        dropped: 0,
+    };
+
+    let copied = {
+        let copy = open(&dest)?;
+        each_note(ndb, 0, progress, |note| {
+            if retention.drops(note, now, keep) {
+                compacted.dropped += 1;
+            } else if copy_note(&copy, note) {
+                compacted.kept += 1;
+            }
+        })
+        // dropping the copy waits for it to write everything
+    };
+
+    if let Err(err) = copied {
+        let _ = fs::remove_dir_all(&dest);
+        return Err(err);
+    }
+
+    fs::write(dest.join(READY_FILE), now.to_string())?;
+    info!(
+        "compacted database: kept {}, dropped {}",
+        compacted.kept, compacted.dropped
+    );
+    Ok(compacted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+    use nostrdb::NoteBuilder;
+
+    fn note(keypair: &FullKeypair, content: &str) -> Note<'static> {
+        NoteBuilder::new()
+            .kind(1)
+            .content(content)
+            .sign(&keypair.secret_key.to_secret_bytes())
+            .build()
+            .expect("note")
+    }
+
+    fn has_note(ndb: &Ndb, note: &Note) -> bool {
+        let txn = Transaction::new(ndb).expect("txn");
+        ndb.get_note_by_id(&txn, note.id()).is_ok()
+    }
+
+    /// nostrdb ingests on its own threads
+    fn ingest(ndb: &Ndb, note: &Note) {
+        let json = note.json().expect("json");
+        ndb.process_event(&format!("[\"EVENT\",\"test\",{json}]"))
+            .expect("event");
+        for _ in 0..500 {
+            if has_note(ndb, note) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("note was never ingested");
+    }
+
+    #[test]
+    fn test_notes_after_compact_survive_swap() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_dir = tmp.path().join("db");
+        fs::create_dir_all(&db_dir).unwrap();
+        let keypair = FullKeypair::generate();
+        let before = note(&keypair, "before");
+        let after = note(&keypair, "after");
+
+        {
+            let ndb = open(&db_dir).unwrap();
+            ingest(&ndb, &before);
+            let retention = Retention {
+                kinds: vec![],
+                older_than_months: 0,
+                keep_trusted: false,
+            };
+            compact(
+                &ndb,
+                &db_dir,
+                &retention,
+                &HashSet::new(),
+                &Progress::default(),
+            )
+            .unwrap();
+            // still in use until the restart
+            ingest(&ndb, &after);
+        }
+
+        assert!(use_compacted(&db_dir));
+        let ndb = open(&db_dir).unwrap();
+        assert!(has_note(&ndb, &before));
+        assert!(has_note(&ndb, &after));
+    }
+
+    #[test]
+    fn test_retention() {
+        let stranger = FullKeypair::generate();
+        let friend = FullKeypair::generate();
+        let keep: HashSet<[u8; 32]> = [*friend.pubkey.bytes()].into();
+        let retention = Retention {
+            kinds: Retention::parse_kinds("1, 7 nope"),
+            older_than_months: 6,
+            keep_trusted: true,
+        };
+        assert_eq!(retention.kinds, [1, 7]);
+
+        let now = 1_700_000_000;
+        let note = |keypair: &FullKeypair, kind: u32, age: u64| {
+            NoteBuilder::new()
+                .kind(kind)
+                .content("")
+                .created_at(now - age)
+                .sign(&keypair.secret_key.to_secret_bytes())
+                .build()
+                .expect("note")
+        };
+
+        assert!(retention.drops(&note(&stranger, 1, 7 * MONTH), now, &keep));
+        assert!(!retention.drops(&note(&stranger, 1, 5 * MONTH), now, &keep));
+        assert!(!retention.drops(&note(&stranger, 0, 7 * MONTH), now, &keep));
+        assert!(!retention.drops(&note(&friend, 1, 7 * MONTH), now, &keep));
+    }
+
+    #[test]
+    fn test_largest_kinds() {
+        let stats = StorageStats {
+            bytes: 0,
+            notes: 6,
+            by_kind: [(0, 1), (1, 3), (7, 1), (3, 1)].into(),
+        };
+        assert_eq!(stats.largest_kinds(), [(1, 3), (0, 1), (3, 1), (7, 1)]);
+    }
+}
@@ -1,8 +1,14 @@
-use nostrdb::Note;
+use enostr::FilledKeypair;
+use nostrdb::{Filter, Ndb, Note, NoteBuilder, Transaction};
 use std::collections::BTreeSet;
 
+use crate::note::tag_strings;
+use crate::{Error, Result};
+
 //use tracing::{debug, trace};
 
+pub const MUTE_LIST_KIND: u32 = 10000;
+
 // If the note is muted return a reason string, otherwise None
 pub type MuteFun = dyn Fn(&Note, &[u8; 32]) -> bool;
 
@@ -81,3 +87,87 @@ impl Muted {
         false
     }
 }
+
+/// The newest NIP-51 mute list we have for someone
+pub fn mute_list<'a>(ndb: &Ndb, txn: &'a Transaction, pubkey: &[u8; 32]) -> Option<Note<'a>> {
+    let filter = Filter::new()
+        .authors([pubkey])
+        .kinds([MUTE_LIST_KIND as u64])
+        .limit(1)
+        .build();
+    let results = ndb.query(txn, &[filter], 1).ok()?;
+    results.into_iter().next().map(|result| result.note)
+}
+
+/// Sign a new version of a mute list with someone added. The private
+/// mutes in the encrypted content are carried over untouched.
+pub fn mute_list_note(
+    current: &Note,
+    keypair: FilledKeypair,
+    them: &[u8; 32],
+) -> Result<Note<'static>> {
+    let tags = with_muted(tag_strings(current), &hex::encode(them));
+    sign_mute_list(current.content(), &tags, keypair)
+}
+
+/// Sign a mute list with everyone in `add` muted as well, starting one if
+/// there's no current list
+pub fn mute_list_adding(
+    current: Option<&Note>,
+    keypair: FilledKeypair,
+    add: &[[u8; 32]],
+) -> Result<Note<'static>> {
+    let tags = add.iter().fold(
+        current.map(tag_strings).unwrap_or_default(),
+        |tags, them| with_muted(tags, &hex::encode(them)),
+    );
+    sign_mute_list(current.map_or("", |note| note.content()), &tags, keypair)
+}
+
+fn sign_mute_list(
+    content: &str,
+    tags: &[Vec<String>],
+    keypair: FilledKeypair,
+) -> Result<Note<'static>> {
+    let mut builder = NoteBuilder::new().kind(MUTE_LIST_KIND).content(content);
+    for tag in tags {
+        builder = builder.start_tag();
+        for field in tag {
+            builder = builder.tag_str(field);
+        }
+    }
+
+    builder
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or_else(|| Error::Generic("failed to build mute list".to_owned()))
+}
+
+fn with_muted(mut tags: Vec<Vec<String>>, them: &str) -> Vec<Vec<String>> {
+    let muted = tags
+        .iter()
+        .any(|tag| tag[0] == "p" && tag.get(1).map(String::as_str) == Some(them));
+    if !muted {
+        tags.push(vec!["p".to_owned(), them.to_owned()]);
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_muted() {
+        let tags = vec![
+            vec!["p".to_owned(), "aa".to_owned()],
+            vec!["t".to_owned(), "spam".to_owned()],
+        ];
+
+        let muted = with_muted(tags.clone(), "bb");
+        assert_eq!(muted.len(), 3);
+        assert_eq!(muted[2], vec!["p".to_owned(), "bb".to_owned()]);
+
+        assert_eq!(with_muted(tags.clone(), "aa"), tags);
+    }
+}
@@ -0,0 +1,18 @@
+//! What kind of connection we're on. Only android tells us, through the
+//! connectivity manager; everywhere else is treated as unmetered, which is
+//! what a desktop on Wi-Fi or ethernet usually is.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static METERED: AtomicBool = AtomicBool::new(false);
+
+/// Set by the platform whenever it notices the connection changed
+pub fn set_metered(metered: bool) {
+    METERED.store(metered, Ordering::Relaxed);
+}
+
+/// Whether the connection costs money per byte, like mobile data.
+/// Background downloads should wait for an unmetered one.
+pub fn is_metered() -> bool {
+    METERED.load(Ordering::Relaxed)
+}
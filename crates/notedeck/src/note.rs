@@ -1,6 +1,6 @@
 use crate::notecache::NoteCache;
 use enostr::NoteId;
-use nostrdb::{Ndb, Note, NoteKey, QueryResult, Transaction};
+use nostrdb::{Ndb, NdbStrVariant, Note, NoteKey, QueryResult, Transaction};
 use std::borrow::Borrow;
 use std::cmp::Ordering;
 
@@ -154,3 +154,81 @@ where
             |rnid| Ok(RootNoteId::new_unsafe(rnid.id)),
         )
 }
+
+/// The NIP-40 expiration timestamp of a note, if it has one
+pub fn note_expiration(note: &Note) -> Option<u64> {
+    for tag in note.tags() {
+        if tag.count() < 2 {
+            continue;
+        }
+
+        if tag.get_unchecked(0).variant().str() != Some("expiration") {
+            continue;
+        }
+
+        return tag.get_unchecked(1).variant().str()?.parse().ok();
+    }
+
+    None
+}
+
+/// The client a note says it was published with, from its `client` tag
+pub fn note_client<'a>(note: &Note<'a>) -> Option<&'a str> {
+    for tag in note.tags() {
+        if tag.count() < 2 {
+            continue;
+        }
+
+        if tag.get_unchecked(0).variant().str() != Some("client") {
+            continue;
+        }
+
+        return tag
+            .get_unchecked(1)
+            .variant()
+            .str()
+            .map(str::trim)
+            .filter(|name| !name.is_empty());
+    }
+
+    None
+}
+
+/// A note's tags as plain strings, with ids hex encoded, for building a
+/// new version of a replaceable list without losing anything
+pub fn tag_strings(note: &Note) -> Vec<Vec<String>> {
+    note.tags()
+        .iter()
+        .map(|tag| {
+            (0..tag.count())
+                .filter_map(|i| tag.get(i))
+                .map(|s| match s.variant() {
+                    NdbStrVariant::Str(s) => s.to_owned(),
+                    NdbStrVariant::Id(id) => hex::encode(id),
+                })
+                .collect::<Vec<String>>()
+        })
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+pub fn is_addressable(kind: u32) -> bool {
+    (30000..40000).contains(&kind)
+}
+
+/// An addressable note's `kind:pubkey:d`
+pub fn address(note: &Note) -> Option<String> {
+    if !is_addressable(note.kind()) {
+        return None;
+    }
+    let d = tag_strings(note)
+        .into_iter()
+        .find(|tag| tag[0] == "d")
+        .and_then(|tag| tag.get(1).cloned())
+        .unwrap_or_default();
+    Some(format!(
+        "{}:{}:{d}",
+        note.kind(),
+        hex::encode(note.pubkey())
+    ))
+}
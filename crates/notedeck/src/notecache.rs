@@ -1,4 +1,5 @@
-use crate::{time_ago_since, TimeCached};
+use crate::note::note_expiration;
+use crate::{time::unix_now, time_ago_since, TimeCached};
 use nostrdb::{Note, NoteKey, NoteReply, NoteReplyBuf};
 use std::collections::HashMap;
 use std::time::Duration;
@@ -33,7 +34,10 @@ impl NoteCache {
 #[derive(Clone)]
 pub struct CachedNote {
     reltime: TimeCached<String>,
+    pub created_at: u64,
     pub reply: NoteReplyBuf,
+    /// NIP-40 expiration timestamp, if the note has one
+    pub expiration: Option<u64>,
 }
 
 impl CachedNote {
@@ -44,7 +48,23 @@ impl CachedNote {
             Box::new(move || time_ago_since(created_at)),
         );
         let reply = NoteReply::new(note.tags()).to_owned();
-        CachedNote { reltime, reply }
+        let expiration = note_expiration(note);
+        CachedNote {
+            reltime,
+            created_at,
+            reply,
+            expiration,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        let Some(expiration) = self.expiration else {
+            return false;
+        };
+
+        let now = unix_now();
+
+        expiration <= now
     }
 
     pub fn reltime_str_mut(&mut self) -> &str {
@@ -0,0 +1,207 @@
+//! What each app is allowed to do for the selected account. Apps declare
+//! the capabilities they need in their manifest and check before using
+//! one. The first time, the chrome asks the user, and the answer is kept
+//! as a per-account setting so it can be changed later in settings.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::{
+    settings::{Setting, SettingsSection},
+    tr, Settings,
+};
+
+const ASK: &str = "ask";
+const ALLOW: &str = "allow";
+const DENY: &str = "deny";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Sign and send events to relays
+    Publish,
+    /// Read the account's contact list
+    ReadContacts,
+    Camera,
+    /// Fetch from servers other than relays
+    Network,
+}
+
+impl Capability {
+    /// The setting the user's answer is kept in
+    fn key(&self) -> &'static str {
+        match self {
+            Capability::Publish => "permission-publish",
+            Capability::ReadContacts => "permission-read-contacts",
+            Capability::Camera => "permission-camera",
+            Capability::Network => "permission-network",
+        }
+    }
+
+    /// What the app wants to do, to finish "Allow Columns to…"
+    pub fn label(&self) -> String {
+        match self {
+            Capability::Publish => tr!("permission-publish"),
+            Capability::ReadContacts => tr!("permission-read-contacts"),
+            Capability::Camera => tr!("permission-camera"),
+            Capability::Network => tr!("permission-network"),
+        }
+    }
+}
+
+/// Why an app can't do something right now. None of these are final: the
+/// user can change their mind in settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum PermissionError {
+    #[error("the app didn't declare {0:?}")]
+    NotDeclared(Capability),
+    #[error("{0:?} was denied")]
+    Denied(Capability),
+    /// The user is being asked, try again once they've answered
+    #[error("waiting for the user to allow {0:?}")]
+    Asking(Capability),
+}
+
+impl PermissionError {
+    /// For a toast
+    pub fn message(&self) -> String {
+        match self {
+            PermissionError::NotDeclared(cap) => {
+                tr!("permission-not-declared", capability = cap.label())
+            }
+            PermissionError::Denied(cap) => tr!("permission-denied", capability = cap.label()),
+            PermissionError::Asking(cap) => tr!("permission-asking", capability = cap.label()),
+        }
+    }
+}
+
+/// A question for the user, shown by the chrome
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionPrompt {
+    pub app: &'static str,
+    pub app_name: String,
+    pub capability: Capability,
+}
+
+#[derive(Default)]
+pub struct Permissions {
+    declared: HashMap<&'static str, (String, Vec<Capability>)>,
+    /// The app the chrome is running right now
+    current: Option<&'static str>,
+    /// The current app's answers in settings, read when it was entered
+    answers: HashMap<Capability, bool>,
+    /// Added to while checking, which only needs to look
+    prompts: RefCell<Vec<PermissionPrompt>>,
+    /// Answers given without an account to keep them in, good until the
+    /// account changes
+    session: HashMap<(&'static str, Capability), bool>,
+}
+
+impl Permissions {
+    /// Note what an app asked for, and give each capability a setting
+    pub fn declare(
+        &mut self,
+        settings: &mut Settings,
+        app: &'static str,
+        name: &str,
+        capabilities: &[Capability],
+    ) {
+        let options = vec![
+            (ASK.to_owned(), tr!("permission-ask")),
+            (ALLOW.to_owned(), tr!("permission-allow")),
+            (DENY.to_owned(), tr!("permission-deny")),
+        ];
+        let section = capabilities.iter().fold(
+            SettingsSection::new(app, tr!("settings-permissions", app = name)),
+            |section, cap| {
+                section.setting(
+                    Setting::select(cap.key(), cap.label(), options.clone(), ASK)
+                        .per_account()
+                        .device_local(),
+                )
+            },
+        );
+        settings.register(section);
+        self.declared
+            .insert(app, (name.to_owned(), capabilities.to_vec()));
+    }
+
+    /// Checks from here on are for this app. Apps without a manifest,
+    /// like previews, aren't restricted.
+    pub fn enter(&mut self, settings: &Settings, app: Option<&'static str>) {
+        self.current = app;
+        self.answers = app
+            .and_then(|app| Some((app, self.declared.get(app)?)))
+            .map(|(app, (_, declared))| {
+                declared
+                    .iter()
+                    .filter_map(|cap| match settings.string(app, cap.key()).as_str() {
+                        ALLOW => Some((*cap, true)),
+                        DENY => Some((*cap, false)),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    pub fn account_changed(&mut self) {
+        self.session.clear();
+        self.prompts.get_mut().clear();
+    }
+
+    /// Whether the current app may use a capability. If the user hasn't
+    /// said yet they're asked, and the app should try again later.
+    pub fn check(&self, cap: Capability) -> Result<(), PermissionError> {
+        let Some(app) = self.current else {
+            return Ok(());
+        };
+        let Some((name, declared)) = self.declared.get(app) else {
+            return Ok(());
+        };
+        if !declared.contains(&cap) {
+            return Err(PermissionError::NotDeclared(cap));
+        }
+
+        let answer = self
+            .answers
+            .get(&cap)
+            .or_else(|| self.session.get(&(app, cap)))
+            .copied();
+        match answer {
+            Some(true) => Ok(()),
+            Some(false) => Err(PermissionError::Denied(cap)),
+            None => {
+                let prompt = PermissionPrompt {
+                    app,
+                    app_name: name.clone(),
+                    capability: cap,
+                };
+                let mut prompts = self.prompts.borrow_mut();
+                if !prompts.contains(&prompt) {
+                    prompts.push(prompt);
+                }
+                Err(PermissionError::Asking(cap))
+            }
+        }
+    }
+
+    /// The question to show next, if any
+    pub fn prompt(&self) -> Option<PermissionPrompt> {
+        self.prompts.borrow().first().cloned()
+    }
+
+    /// Record the answer to the current prompt
+    pub fn answer(&mut self, settings: &mut Settings, allow: bool) {
+        let prompts = self.prompts.get_mut();
+        if prompts.is_empty() {
+            return;
+        }
+        let prompt = prompts.remove(0);
+        let value = if allow { ALLOW } else { DENY };
+        settings.set(prompt.app, prompt.capability.key(), value.into());
+        self.session.insert((prompt.app, prompt.capability), allow);
+        if self.current == Some(prompt.app) {
+            self.answers.insert(prompt.capability, allow);
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use nostrdb::{NdbStrVariant, Note, NoteBuildOptions, NoteBuilder};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use tracing::{debug, error, warn};
+
+/// How many hashes we do between checking for cancellation and
+/// reporting progress
+const PROGRESS_INTERVAL: u64 = 4096;
+
+/// Count the leading zero bits of a note id. This is the difficulty of
+/// the note as defined by NIP-13.
+pub fn leading_zero_bits(id: &[u8; 32]) -> u32 {
+    let mut bits = 0;
+    for byte in id {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+/// Everything that goes into a note id. We mine against this instead of
+/// a signed note so that we only have to sign once we've found a nonce.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsignedNote {
+    pub pubkey: [u8; 32],
+    pub created_at: u64,
+    pub kind: u32,
+    pub tags: Vec<Vec<String>>,
+    pub content: String,
+}
+
+impl UnsignedNote {
+    /// Take the id fields from an existing note. Any previous nonce tag is
+    /// dropped since we're going to mine a new one.
+    pub fn from_note(note: &Note) -> Self {
+        let tags = note
+            .tags()
+            .iter()
+            .map(|tag| {
+                (0..tag.count())
+                    .filter_map(|i| tag.get(i))
+                    .map(|s| match s.variant() {
+                        NdbStrVariant::Str(s) => s.to_owned(),
+                        NdbStrVariant::Id(id) => hex::encode(id),
+                    })
+                    .collect::<Vec<String>>()
+            })
+            .filter(|tag| tag.first().map(|t| t.as_str()) != Some("nonce"))
+            .collect();
+
+        UnsignedNote {
+            pubkey: *note.pubkey(),
+            created_at: note.created_at(),
+            kind: note.kind(),
+            tags,
+            content: note.content().to_owned(),
+        }
+    }
+
+    /// Sign the note, keeping created_at and the tags exactly as they are
+    /// so that the mined id stays valid.
+    pub fn sign(&self, seckey: &[u8; 32]) -> Option<Note<'static>> {
+        let mut builder = NoteBuilder::new()
+            .kind(self.kind)
+            .content(&self.content)
+            .options(NoteBuildOptions::default().created_at(false).sign(seckey))
+            .created_at(self.created_at);
+
+        for tag in &self.tags {
+            builder = builder.start_tag();
+            for elem in tag {
+                builder = builder.tag_str(elem);
+            }
+        }
+
+        let note = builder.build()?;
+
+        if self.pubkey != *note.pubkey() {
+            error!("pow: signing key does not match the mined note's pubkey");
+            return None;
+        }
+
+        Some(note)
+    }
+
+    fn with_nonce(mut self, nonce: u64, target: u8) -> Self {
+        self.tags.push(vec![
+            "nonce".to_owned(),
+            nonce.to_string(),
+            target.to_string(),
+        ]);
+        self
+    }
+
+    /// The NIP-01 serialization of the note, split around the nonce value
+    /// of a trailing `["nonce", <nonce>, <target>]` tag.
+    fn nonce_template(&self, target: u8) -> (String, String) {
+        let mut prefix = format!(
+            "[0,\"{}\",{},{},[",
+            hex::encode(self.pubkey),
+            self.created_at,
+            self.kind
+        );
+
+        for tag in &self.tags {
+            prefix.push('[');
+            for (i, elem) in tag.iter().enumerate() {
+                if i > 0 {
+                    prefix.push(',');
+                }
+                push_json_str(&mut prefix, elem);
+            }
+            prefix.push_str("],");
+        }
+        prefix.push_str("[\"nonce\",\"");
+
+        let mut suffix = format!("\",\"{target}\"]],");
+        push_json_str(&mut suffix, &self.content);
+        suffix.push(']');
+
+        (prefix, suffix)
+    }
+}
+
+/// A quoted string the way NIP-01 serializes it for the id, and the way
+/// nostrdb does when it signs: only `"`, `\` and \b \t \n \f \r are
+/// escaped, every other character goes in as it is. serde_json writes
+/// the other control characters as `\u00XX`, which gives a different id.
+fn push_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowProgress {
+    /// The difficulty we're trying to reach
+    pub target: u8,
+    /// The best difficulty we've seen so far
+    pub best: u32,
+    pub attempts: u64,
+}
+
+pub enum PowPoll {
+    Mining(PowProgress),
+    Done(UnsignedNote),
+    Cancelled,
+}
+
+/// Grinds a nonce tag on a background thread until the note id has at
+/// least `target` leading zero bits
+pub struct PowMiner {
+    target: u8,
+    cancel: Arc<AtomicBool>,
+    attempts: Arc<AtomicU64>,
+    best: Arc<AtomicU32>,
+    result: Receiver<Option<UnsignedNote>>,
+}
+
+impl PowMiner {
+    pub fn start(note: UnsignedNote, target: u8) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let attempts = Arc::new(AtomicU64::new(0));
+        let best = Arc::new(AtomicU32::new(0));
+        let (tx, result) = mpsc::channel();
+
+        let thread_cancel = cancel.clone();
+        let thread_attempts = attempts.clone();
+        let thread_best = best.clone();
+
+        let spawned = std::thread::Builder::new()
+            .name("pow-miner".to_owned())
+            .spawn(move || {
+                let mined = mine(note, target, &thread_cancel, &thread_attempts, &thread_best);
+                let _ = tx.send(mined);
+            });
+
+        if let Err(err) = spawned {
+            // the sender was dropped with the closure, so this miner will
+            // just report itself as cancelled
+            error!("failed to spawn pow miner: {err}");
+        }
+
+        PowMiner {
+            target,
+            cancel,
+            attempts,
+            best,
+            result,
+        }
+    }
+
+    pub fn target(&self) -> u8 {
+        self.target
+    }
+
+    pub fn progress(&self) -> PowProgress {
+        PowProgress {
+            target: self.target,
+            best: self.best.load(Ordering::Relaxed),
+            attempts: self.attempts.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Ask the miner to stop. [`PowMiner::poll`] will return
+    /// [`PowPoll::Cancelled`] once it has.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn poll(&self) -> PowPoll {
+        match self.result.try_recv() {
+            Ok(Some(note)) => PowPoll::Done(note),
+            Ok(None) | Err(TryRecvError::Disconnected) => PowPoll::Cancelled,
+            Err(TryRecvError::Empty) => PowPoll::Mining(self.progress()),
+        }
+    }
+}
+
+impl Drop for PowMiner {
+    fn drop(&mut self) {
+        // don't leave a thread spinning if nobody is waiting on it
+        self.cancel();
+    }
+}
+
+fn mine(
+    note: UnsignedNote,
+    target: u8,
+    cancel: &AtomicBool,
+    attempts: &AtomicU64,
+    best: &AtomicU32,
+) -> Option<UnsignedNote> {
+    let (prefix, suffix) = note.nonce_template(target);
+    let mut base = Sha256::new();
+    base.update(prefix.as_bytes());
+
+    debug!(
+        "pow: mining kind {} note to difficulty {}",
+        note.kind, target
+    );
+
+    let mut nonce: u64 = 0;
+    loop {
+        if nonce % PROGRESS_INTERVAL == 0 {
+            if cancel.load(Ordering::Relaxed) {
+                debug!("pow: cancelled after {} attempts", nonce);
+                return None;
+            }
+            attempts.store(nonce, Ordering::Relaxed);
+        }
+
+        let mut hasher = base.clone();
+        hasher.update(nonce.to_string().as_bytes());
+        hasher.update(suffix.as_bytes());
+        let id: [u8; 32] = hasher.finalize().into();
+
+        let bits = leading_zero_bits(&id);
+        best.fetch_max(bits, Ordering::Relaxed);
+
+        if bits >= target as u32 {
+            attempts.store(nonce + 1, Ordering::Relaxed);
+            debug!(
+                "pow: found difficulty {} after {} attempts",
+                bits,
+                nonce + 1
+            );
+            return Some(note.with_nonce(nonce, target));
+        }
+
+        nonce = if let Some(next) = nonce.checked_add(1) {
+            next
+        } else {
+            warn!("pow: exhausted nonce space");
+            return None;
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+    use std::time::Duration;
+
+    #[test]
+    fn test_leading_zero_bits() {
+        let mut id = [0xffu8; 32];
+        assert_eq!(leading_zero_bits(&id), 0);
+
+        id[0] = 0;
+        id[1] = 0x0f;
+        assert_eq!(leading_zero_bits(&id), 12);
+
+        id[1] = 0x01;
+        assert_eq!(leading_zero_bits(&id), 15);
+
+        assert_eq!(leading_zero_bits(&[0u8; 32]), 256);
+    }
+
+    #[test]
+    fn test_mined_note_has_difficulty() {
+        let kp = FullKeypair::generate();
+        let seckey = kp.secret_key.to_secret_bytes();
+        let unsigned = UnsignedNote {
+            pubkey: *kp.pubkey.bytes(),
+            created_at: 1_700_000_000,
+            kind: 1,
+            tags: vec![vec!["t".to_owned(), "nostr".to_owned()]],
+            content: "hello \"pow\"\nworld".to_owned(),
+        };
+
+        let miner = PowMiner::start(unsigned, 8);
+        let mined = loop {
+            match miner.poll() {
+                PowPoll::Done(note) => break note,
+                PowPoll::Cancelled => panic!("miner stopped early"),
+                PowPoll::Mining(_) => std::thread::sleep(Duration::from_millis(5)),
+            }
+        };
+
+        let note = mined.sign(&seckey).expect("signed note");
+        assert_eq!(note.created_at(), 1_700_000_000);
+        assert!(leading_zero_bits(note.id()) >= 8);
+    }
+
+    #[test]
+    fn test_template_matches_signed_id() {
+        let kp = FullKeypair::generate();
+        let unsigned = UnsignedNote {
+            pubkey: *kp.pubkey.bytes(),
+            created_at: 1_700_000_000,
+            kind: 1,
+            tags: vec![vec!["t".to_owned(), "bell\u{7}\u{1f}".to_owned()]],
+            content: "\u{1} \"q\" \\ \u{8}\t\n\u{b}\u{c}\r\u{1b}[0m \u{7f} é 🤙".to_owned(),
+        };
+
+        let (prefix, suffix) = unsigned.nonce_template(3);
+        let id: [u8; 32] = Sha256::digest(format!("{prefix}42{suffix}")).into();
+
+        let note = unsigned
+            .with_nonce(42, 3)
+            .sign(&kp.secret_key.to_secret_bytes())
+            .expect("signed note");
+        assert_eq!(note.id(), &id);
+    }
+}
@@ -0,0 +1,75 @@
+use nostrdb::Tag;
+use url::Url;
+
+/// A relay in a NIP-65 relay list. A relay with neither marker set is
+/// treated as both read and write, same as an `r` tag without a marker.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RelaySpec {
+    pub url: String,
+    pub read: bool,
+    pub write: bool,
+}
+
+impl RelaySpec {
+    pub fn new(url: impl AsRef<str>, read: bool, write: bool) -> Self {
+        RelaySpec {
+            url: canonicalize_url(url.as_ref()),
+            read,
+            write,
+        }
+    }
+
+    /// Parse an `r` tag from a kind 10002 note
+    pub fn from_tag(tag: &Tag<'_>) -> Option<Self> {
+        if tag.get(0)?.variant().str()? != "r" {
+            return None;
+        }
+
+        let url = tag.get(1)?.variant().str()?;
+        let spec = match tag.get(2).and_then(|m| m.variant().str()) {
+            Some("read") => RelaySpec::new(url, true, false),
+            Some("write") => RelaySpec::new(url, false, true),
+            _ => RelaySpec::new(url, true, true),
+        };
+        Some(spec)
+    }
+
+    /// The marker to put in the `r` tag, if any
+    pub fn marker(&self) -> Option<&'static str> {
+        match (self.read, self.write) {
+            (true, false) => Some("read"),
+            (false, true) => Some("write"),
+            _ => None,
+        }
+    }
+}
+
+// standardize the format (ie, trailing slashes) to avoid dups
+pub fn canonicalize_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(parsed_url) => parsed_url.to_string(),
+        Err(_) => url.to_owned(), // If parsing fails, return the original URL.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markers() {
+        assert_eq!(RelaySpec::new("wss://a.com", true, true).marker(), None);
+        assert_eq!(
+            RelaySpec::new("wss://a.com", true, false).marker(),
+            Some("read")
+        );
+        assert_eq!(
+            RelaySpec::new("wss://a.com", false, true).marker(),
+            Some("write")
+        );
+        assert_eq!(
+            RelaySpec::new("wss://a.com", true, true).url,
+            "wss://a.com/"
+        );
+    }
+}
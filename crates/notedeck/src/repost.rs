@@ -0,0 +1,95 @@
+//! NIP-18 reposts. Text notes are reposted with kind 6, anything else
+//! with a generic kind 16 repost that says what kind it was. Either way
+//! the original goes along in the content, so clients that haven't seen
+//! it can still show it.
+
+use enostr::FilledKeypair;
+use nostrdb::{Note, NoteBuilder};
+
+use crate::note::tag_strings;
+use crate::{Error, Result};
+
+pub const REPOST_KIND: u32 = 6;
+pub const GENERIC_REPOST_KIND: u32 = 16;
+
+pub fn is_repost(note: &Note) -> bool {
+    note.kind() == REPOST_KIND || note.kind() == GENERIC_REPOST_KIND
+}
+
+/// Repost someone's note
+pub fn repost(keypair: FilledKeypair, reposted: &Note) -> Result<Note<'static>> {
+    let kind = if reposted.kind() == 1 {
+        REPOST_KIND
+    } else {
+        GENERIC_REPOST_KIND
+    };
+
+    let content = reposted.json()?;
+    let mut builder = NoteBuilder::new()
+        .kind(kind)
+        .content(&content)
+        .start_tag()
+        .tag_str("e")
+        .tag_str(&hex::encode(reposted.id()))
+        .tag_str("")
+        .start_tag()
+        .tag_str("p")
+        .tag_str(&hex::encode(reposted.pubkey()));
+    if kind == GENERIC_REPOST_KIND {
+        builder = builder
+            .start_tag()
+            .tag_str("k")
+            .tag_str(&reposted.kind().to_string());
+    }
+
+    builder
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or_else(|| Error::Generic("failed to build repost".to_owned()))
+}
+
+/// The id of the note a repost is of
+pub fn reposted_id(note: &Note) -> Option<[u8; 32]> {
+    if !is_repost(note) {
+        return None;
+    }
+
+    tag_strings(note)
+        .into_iter()
+        .find(|tag| tag[0] == "e")
+        .and_then(|tag| hex::decode(tag.get(1)?).ok())
+        .and_then(|id| id.try_into().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+
+    #[test]
+    fn test_repost() {
+        let alice = FullKeypair::generate();
+        let bob = FullKeypair::generate();
+
+        let post = NoteBuilder::new()
+            .kind(1)
+            .content("gm")
+            .sign(&alice.secret_key.to_secret_bytes())
+            .build()
+            .unwrap();
+        let shared = repost(bob.to_filled(), &post).unwrap();
+        assert_eq!(shared.kind(), REPOST_KIND);
+        assert_eq!(reposted_id(&shared), Some(*post.id()));
+        assert_eq!(reposted_id(&post), None);
+
+        let article = NoteBuilder::new()
+            .kind(30023)
+            .content("# long")
+            .sign(&alice.secret_key.to_secret_bytes())
+            .build()
+            .unwrap();
+        let shared = repost(bob.to_filled(), &article).unwrap();
+        assert_eq!(shared.kind(), GENERIC_REPOST_KIND);
+        assert_eq!(reposted_id(&shared), Some(*article.id()));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{tr, UserAccount};
+
+/// What the current session is allowed to do. UI that publishes or
+/// changes account state should check this and disable itself instead of
+/// failing silently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SessionCapability {
+    /// No account selected, we're just browsing
+    ReadOnly,
+    /// An account added with only a pubkey. We can show their stuff but
+    /// can't sign anything.
+    PubkeyOnly,
+    /// We have the secret key and can sign notes
+    FullKey,
+}
+
+impl SessionCapability {
+    pub fn new(account: Option<&UserAccount>) -> Self {
+        match account {
+            None => SessionCapability::ReadOnly,
+            Some(account) if account.secret_key.is_some() => SessionCapability::FullKey,
+            Some(_) => SessionCapability::PubkeyOnly,
+        }
+    }
+
+    pub fn is_logged_in(self) -> bool {
+        self >= SessionCapability::PubkeyOnly
+    }
+
+    pub fn can_sign(self) -> bool {
+        self >= SessionCapability::FullKey
+    }
+
+    /// Why an action that needs `required` isn't available, suitable for
+    /// showing in a tooltip. `None` if the action is allowed.
+    pub fn missing(self, required: SessionCapability) -> Option<String> {
+        if self >= required {
+            return None;
+        }
+
+        match self {
+            SessionCapability::ReadOnly => Some(tr!("session-need-login")),
+            SessionCapability::PubkeyOnly => Some(tr!("session-need-key")),
+            SessionCapability::FullKey => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+
+    #[test]
+    fn test_session_capability() {
+        let full = FullKeypair::generate().to_keypair();
+        let pubkey_only = UserAccount::only_pubkey(full.pubkey);
+
+        let read_only = SessionCapability::new(None);
+        let pubkey_only = SessionCapability::new(Some(&pubkey_only));
+        let full = SessionCapability::new(Some(&full));
+
+        assert_eq!(read_only, SessionCapability::ReadOnly);
+        assert_eq!(pubkey_only, SessionCapability::PubkeyOnly);
+        assert_eq!(full, SessionCapability::FullKey);
+
+        assert!(!read_only.is_logged_in());
+        assert!(pubkey_only.is_logged_in() && !pubkey_only.can_sign());
+        assert!(full.can_sign());
+
+        assert!(read_only.missing(SessionCapability::PubkeyOnly).is_some());
+        assert!(pubkey_only.missing(SessionCapability::PubkeyOnly).is_none());
+        assert!(pubkey_only.missing(SessionCapability::FullKey).is_some());
+        assert!(full.missing(SessionCapability::FullKey).is_none());
+    }
+}
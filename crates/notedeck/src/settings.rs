@@ -0,0 +1,478 @@
+//! Settings that apps contribute to the shared settings window. An app
+//! describes its settings once, and the chrome draws, searches and
+//! persists them.
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use enostr::Pubkey;
+use serde_json::{Map, Value};
+use tracing::error;
+
+use crate::storage::{AppStorage, Schema};
+use crate::DataPath;
+
+/// Where setting values are persisted, as if the registry were an app
+pub const SETTINGS_STORAGE_ID: &str = "settings";
+
+const GLOBAL_KEY: &str = "global";
+static VALUES_SCHEMA: Schema = Schema::INITIAL;
+
+/// Whether a value is shared by every account or kept per account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    Global,
+    Account,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingKind {
+    Toggle {
+        default: bool,
+    },
+    /// One of a fixed set of `(value, label)` options
+    Select {
+        options: Vec<(String, String)>,
+        default: String,
+    },
+    Slider {
+        range: RangeInclusive<f64>,
+        step: f64,
+        default: f64,
+    },
+    Text {
+        default: String,
+    },
+}
+
+impl SettingKind {
+    fn default_value(&self) -> Value {
+        match self {
+            SettingKind::Toggle { default } => Value::from(*default),
+            SettingKind::Select { default, .. } => Value::from(default.as_str()),
+            SettingKind::Slider { default, .. } => Value::from(*default),
+            SettingKind::Text { default } => Value::from(default.as_str()),
+        }
+    }
+
+    /// Values read back from disk might not fit anymore, eg. an option
+    /// that was removed
+    fn accepts(&self, value: &Value) -> bool {
+        match self {
+            SettingKind::Toggle { .. } => value.is_boolean(),
+            SettingKind::Select { options, .. } => value
+                .as_str()
+                .is_some_and(|v| options.iter().any(|(option, _)| option == v)),
+            SettingKind::Slider { range, .. } => value.as_f64().is_some_and(|v| range.contains(&v)),
+            SettingKind::Text { .. } => value.is_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    pub key: &'static str,
+    pub label: String,
+    pub description: Option<String>,
+    pub scope: Scope,
+    pub kind: SettingKind,
+    /// Left out of snapshots, so it's never synced from another device
+    pub device_local: bool,
+}
+
+impl Setting {
+    fn new(key: &'static str, label: impl Into<String>, kind: SettingKind) -> Self {
+        Setting {
+            key,
+            label: label.into(),
+            description: None,
+            scope: Scope::Global,
+            kind,
+            device_local: false,
+        }
+    }
+
+    pub fn toggle(key: &'static str, label: impl Into<String>, default: bool) -> Self {
+        Setting::new(key, label, SettingKind::Toggle { default })
+    }
+
+    pub fn select(
+        key: &'static str,
+        label: impl Into<String>,
+        options: Vec<(String, String)>,
+        default: impl Into<String>,
+    ) -> Self {
+        let default = default.into();
+        Setting::new(key, label, SettingKind::Select { options, default })
+    }
+
+    pub fn slider(
+        key: &'static str,
+        label: impl Into<String>,
+        range: RangeInclusive<f64>,
+        step: f64,
+        default: f64,
+    ) -> Self {
+        Setting::new(
+            key,
+            label,
+            SettingKind::Slider {
+                range,
+                step,
+                default,
+            },
+        )
+    }
+
+    pub fn text(key: &'static str, label: impl Into<String>, default: impl Into<String>) -> Self {
+        let default = default.into();
+        Setting::new(key, label, SettingKind::Text { default })
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Keep a separate value for each account
+    pub fn per_account(mut self) -> Self {
+        self.scope = Scope::Account;
+        self
+    }
+
+    /// Only ever set on this device, for settings that decide what's
+    /// allowed to run or change here
+    pub fn device_local(mut self) -> Self {
+        self.device_local = true;
+        self
+    }
+
+    /// Case insensitive match on the label and description. `query` must
+    /// already be lowercase.
+    pub fn matches(&self, query: &str) -> bool {
+        self.label.to_lowercase().contains(query)
+            || self
+                .description
+                .as_ref()
+                .is_some_and(|d| d.to_lowercase().contains(query))
+    }
+}
+
+/// A group of settings from one app
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingsSection {
+    pub app: &'static str,
+    pub title: String,
+    pub settings: Vec<Setting>,
+}
+
+impl SettingsSection {
+    pub fn new(app: &'static str, title: impl Into<String>) -> Self {
+        SettingsSection {
+            app,
+            title: title.into(),
+            settings: vec![],
+        }
+    }
+
+    pub fn setting(mut self, setting: Setting) -> Self {
+        self.settings.push(setting);
+        self
+    }
+}
+
+/// Every registered setting and its current value. Global values and
+/// each account's values are stored separately.
+pub struct Settings {
+    storage: AppStorage,
+    sections: Vec<SettingsSection>,
+    /// Storage key to values, loaded as needed
+    values: HashMap<String, Map<String, Value>>,
+    account: Option<Pubkey>,
+}
+
+impl Settings {
+    pub fn new(path: &DataPath) -> Self {
+        let mut settings = Settings {
+            storage: AppStorage::new(path, SETTINGS_STORAGE_ID),
+            sections: vec![],
+            values: HashMap::new(),
+            account: None,
+        };
+        settings.load(GLOBAL_KEY.to_owned());
+        settings
+    }
+
+    /// Add an app's section, replacing it if it was registered before
+    pub fn register(&mut self, section: SettingsSection) {
+        match self
+            .sections
+            .iter_mut()
+            .find(|s| s.app == section.app && s.title == section.title)
+        {
+            Some(existing) => *existing = section,
+            None => self.sections.push(section),
+        }
+    }
+
+    pub fn sections(&self) -> &[SettingsSection] {
+        &self.sections
+    }
+
+    /// Per account settings follow the selected account
+    pub fn set_account(&mut self, account: Option<Pubkey>) {
+        if self.account == account {
+            return;
+        }
+
+        self.account = account;
+        if let Some(key) = self.storage_key(Scope::Account) {
+            self.load(key);
+        }
+    }
+
+    /// The stored value, or the default if there isn't a valid one. `None`
+    /// if no app registered the setting.
+    pub fn get(&self, app: &str, key: &str) -> Option<Value> {
+        let setting = self.find(app, key)?;
+        let stored = self
+            .storage_key(setting.scope)
+            .and_then(|scope| self.values.get(&scope))
+            .and_then(|values| values.get(&value_key(app, key)))
+            .filter(|value| setting.kind.accepts(value));
+
+        Some(
+            stored
+                .cloned()
+                .unwrap_or_else(|| setting.kind.default_value()),
+        )
+    }
+
+    pub fn bool(&self, app: &str, key: &str) -> bool {
+        self.get(app, key)
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default()
+    }
+
+    pub fn number(&self, app: &str, key: &str) -> f64 {
+        self.get(app, key)
+            .and_then(|v| v.as_f64())
+            .unwrap_or_default()
+    }
+
+    /// The value of a select or text setting
+    pub fn string(&self, app: &str, key: &str) -> String {
+        match self.get(app, key) {
+            Some(Value::String(s)) => s,
+            _ => String::new(),
+        }
+    }
+
+    /// Change a value and write it to disk. Per account values need a
+    /// selected account.
+    pub fn set(&mut self, app: &str, key: &str, value: Value) {
+        let Some(setting) = self.find(app, key) else {
+            error!("no setting {app}.{key}");
+            return;
+        };
+        if !setting.kind.accepts(&value) {
+            error!("{value} doesn't fit setting {app}.{key}");
+            return;
+        }
+        let Some(storage_key) = self.storage_key(setting.scope) else {
+            return;
+        };
+
+        let values = self.values.entry(storage_key.clone()).or_default();
+        values.insert(value_key(app, key), value);
+
+        if let Err(err) = self.storage.set(&storage_key, &VALUES_SCHEMA, values) {
+            error!("could not save settings: {err}");
+        }
+    }
+
+    /// The value of every registered setting as the selected account
+    /// sees it, keyed by `app.key`. Device local settings are left out.
+    pub fn snapshot(&self) -> Map<String, Value> {
+        self.sections
+            .iter()
+            .flat_map(|section| section.settings.iter().map(move |s| (section.app, s)))
+            .filter(|(_, setting)| !setting.device_local)
+            .filter_map(|(app, s)| Some((value_key(app, s.key), self.get(app, s.key)?)))
+            .collect()
+    }
+
+    /// Set everything in a [`Settings::snapshot`]. Settings that aren't
+    /// registered, are device local or values that don't fit are skipped.
+    pub fn restore(&mut self, snapshot: &Map<String, Value>) {
+        for (value_key, value) in snapshot {
+            let Some((app, key)) = value_key.split_once('.') else {
+                continue;
+            };
+            let fits = self
+                .find(app, key)
+                .is_some_and(|setting| !setting.device_local && setting.kind.accepts(value));
+            if fits && self.get(app, key).as_ref() != Some(value) {
+                self.set(app, key, value.clone());
+            }
+        }
+    }
+
+    fn find(&self, app: &str, key: &str) -> Option<&Setting> {
+        self.sections
+            .iter()
+            .filter(|section| section.app == app)
+            .flat_map(|section| &section.settings)
+            .find(|setting| setting.key == key)
+    }
+
+    fn storage_key(&self, scope: Scope) -> Option<String> {
+        match scope {
+            Scope::Global => Some(GLOBAL_KEY.to_owned()),
+            Scope::Account => self
+                .account
+                .as_ref()
+                .map(|pk| format!("account-{}", pk.hex())),
+        }
+    }
+
+    fn load(&mut self, storage_key: String) {
+        if self.values.contains_key(&storage_key) {
+            return;
+        }
+
+        let values = self
+            .storage
+            .get(&storage_key, &VALUES_SCHEMA)
+            .unwrap_or_default();
+        self.values.insert(storage_key, values);
+    }
+}
+
+fn open_request_id() -> egui::Id {
+    egui::Id::new("open-settings-window")
+}
+
+/// Ask the chrome to show the settings window
+pub fn open_window(ctx: &egui::Context) {
+    ctx.data_mut(|d| d.insert_temp(open_request_id(), true));
+}
+
+/// Whether an app asked for the settings window since the last call
+pub fn take_open_request(ctx: &egui::Context) -> bool {
+    ctx.data_mut(|d| d.remove_temp::<bool>(open_request_id()))
+        .unwrap_or(false)
+}
+
+fn value_key(app: &str, key: &str) -> String {
+    format!("{app}.{key}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section() -> SettingsSection {
+        SettingsSection::new("test", "Test")
+            .setting(Setting::toggle("sounds", "Play sounds", true))
+            .setting(
+                Setting::select(
+                    "density",
+                    "Density",
+                    vec![
+                        ("compact".to_owned(), "Compact".to_owned()),
+                        ("cozy".to_owned(), "Cozy".to_owned()),
+                    ],
+                    "cozy",
+                )
+                .per_account(),
+            )
+    }
+
+    #[test]
+    fn test_values_and_scopes() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = DataPath::new(tmp.path());
+        let alice = Pubkey::new([1; 32]);
+        let bob = Pubkey::new([2; 32]);
+
+        let mut settings = Settings::new(&path);
+        settings.register(section());
+        assert!(settings.bool("test", "sounds"));
+        assert_eq!(settings.get("test", "missing"), None);
+
+        settings.set("test", "sounds", Value::from(false));
+        // not one of the options
+        settings.set_account(Some(alice));
+        settings.set("test", "density", Value::from("huge"));
+        assert_eq!(settings.string("test", "density"), "cozy");
+        settings.set("test", "density", Value::from("compact"));
+        assert_eq!(settings.string("test", "density"), "compact");
+
+        settings.set_account(Some(bob));
+        assert_eq!(settings.string("test", "density"), "cozy");
+
+        // and it all comes back from disk
+        let mut settings = Settings::new(&path);
+        settings.register(section());
+        settings.set_account(Some(alice));
+        assert!(!settings.bool("test", "sounds"));
+        assert_eq!(settings.string("test", "density"), "compact");
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::new(&DataPath::new(tmp.path()));
+        settings.register(section());
+        settings.set_account(Some(Pubkey::new([1; 32])));
+        settings.set("test", "sounds", Value::from(false));
+        settings.set("test", "density", Value::from("compact"));
+        let snapshot = settings.snapshot();
+
+        let other = tempfile::TempDir::new().unwrap();
+        let mut restored = Settings::new(&DataPath::new(other.path()));
+        restored.register(section());
+        restored.set_account(Some(Pubkey::new([1; 32])));
+        let mut with_junk = snapshot.clone();
+        with_junk.insert("test.missing".to_owned(), Value::from(1));
+        with_junk.insert("test.sounds".to_owned(), Value::from("loud"));
+        restored.restore(&with_junk);
+        assert!(restored.bool("test", "sounds"));
+        assert_eq!(restored.string("test", "density"), "compact");
+
+        restored.restore(&snapshot);
+        assert_eq!(restored.snapshot(), snapshot);
+    }
+
+    #[test]
+    fn test_device_local_not_synced() {
+        let scripts = || {
+            SettingsSection::new("test", "Scripts")
+                .setting(Setting::toggle("scripts", "Allow scripts", false).device_local())
+        };
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::new(&DataPath::new(tmp.path()));
+        settings.register(scripts());
+        settings.set("test", "scripts", Value::from(true));
+        assert!(settings.bool("test", "scripts"));
+        assert!(!settings.snapshot().contains_key("test.scripts"));
+
+        let other = tempfile::TempDir::new().unwrap();
+        let mut restored = Settings::new(&DataPath::new(other.path()));
+        restored.register(scripts());
+        let mut synced = Map::new();
+        synced.insert("test.scripts".to_owned(), Value::from(true));
+        restored.restore(&synced);
+        assert!(!restored.bool("test", "scripts"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let setting = Setting::toggle("sounds", "Play sounds", true)
+            .description("When a notification arrives");
+        assert!(setting.matches("sound"));
+        assert!(setting.matches("notification"));
+        assert!(!setting.matches("zap"));
+    }
+}
@@ -0,0 +1,607 @@
+//! Spellcheck for text inputs. Dictionaries are hunspell `.aff`/`.dic`
+//! pairs, downloaded per language the first time they're needed and kept
+//! in the cache directory. Affix rules are expanded when a dictionary is
+//! loaded, so checking a word is a set lookup.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use poll_promise::Promise;
+use tracing::{debug, error, info};
+
+use crate::storage::{self, DataPath, DataPathType};
+
+/// Where dictionaries come from. `{lang}` is swapped for the language and
+/// `.aff`/`.dic` are added to the end.
+pub const DEFAULT_SOURCE: &str =
+    "https://raw.githubusercontent.com/wooorm/dictionaries/main/dictionaries/{lang}/index";
+
+/// Words added with "Add to dictionary", one per line
+const PERSONAL_FILE: &str = "personal.txt";
+/// How far off a suggestion can be
+const MAX_EDITS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagFormat {
+    Char,
+    Long,
+    Num,
+}
+
+impl FlagFormat {
+    fn split(self, flags: &str) -> Vec<String> {
+        match self {
+            FlagFormat::Char => flags.chars().map(String::from).collect(),
+            FlagFormat::Long => flags
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|pair| pair.iter().collect())
+                .collect(),
+            FlagFormat::Num => flags.split(',').map(str::to_owned).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    Any,
+    Char(char),
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>),
+}
+
+impl Condition {
+    fn parse(condition: &str) -> Vec<Condition> {
+        if condition == "." {
+            return vec![];
+        }
+
+        let mut parsed = vec![];
+        let mut chars = condition.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => parsed.push(Condition::Any),
+                '[' => {
+                    let set: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                    match set.strip_prefix('^') {
+                        Some(set) => parsed.push(Condition::NoneOf(set.chars().collect())),
+                        None => parsed.push(Condition::OneOf(set.chars().collect())),
+                    }
+                }
+                c => parsed.push(Condition::Char(c)),
+            }
+        }
+        parsed
+    }
+
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Condition::Any => true,
+            Condition::Char(want) => *want == c,
+            Condition::OneOf(set) => set.contains(&c),
+            Condition::NoneOf(set) => !set.contains(&c),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Affix {
+    strip: String,
+    add: String,
+    condition: Vec<Condition>,
+}
+
+impl Affix {
+    fn apply_suffix(&self, word: &str) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < self.condition.len() || !word.ends_with(&self.strip) {
+            return None;
+        }
+        let tail = &chars[chars.len() - self.condition.len()..];
+        if !self
+            .condition
+            .iter()
+            .zip(tail)
+            .all(|(c, ch)| c.matches(*ch))
+        {
+            return None;
+        }
+        let stem = &word[..word.len() - self.strip.len()];
+        Some(format!("{stem}{}", self.add))
+    }
+
+    fn apply_prefix(&self, word: &str) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < self.condition.len() || !word.starts_with(&self.strip) {
+            return None;
+        }
+        if !self
+            .condition
+            .iter()
+            .zip(&chars)
+            .all(|(c, ch)| c.matches(*ch))
+        {
+            return None;
+        }
+        Some(format!("{}{}", self.add, &word[self.strip.len()..]))
+    }
+}
+
+#[derive(Debug, Default)]
+struct AffixGroup {
+    cross: bool,
+    rules: Vec<Affix>,
+}
+
+#[derive(Debug)]
+struct Affixes {
+    format: FlagFormat,
+    prefixes: HashMap<String, AffixGroup>,
+    suffixes: HashMap<String, AffixGroup>,
+}
+
+impl Affixes {
+    fn parse(aff: &str) -> Self {
+        let mut affixes = Affixes {
+            format: FlagFormat::Char,
+            prefixes: HashMap::new(),
+            suffixes: HashMap::new(),
+        };
+
+        for line in aff.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", "long", ..] => affixes.format = FlagFormat::Long,
+                ["FLAG", "num", ..] => affixes.format = FlagFormat::Num,
+                [kind @ ("PFX" | "SFX"), flag, cross, count]
+                    if count.parse::<usize>().is_ok() && matches!(*cross, "Y" | "N") =>
+                {
+                    let groups = if *kind == "PFX" {
+                        &mut affixes.prefixes
+                    } else {
+                        &mut affixes.suffixes
+                    };
+                    groups.entry(flag.to_string()).or_default().cross = *cross == "Y";
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, condition, ..] => {
+                    let groups = if *kind == "PFX" {
+                        &mut affixes.prefixes
+                    } else {
+                        &mut affixes.suffixes
+                    };
+                    let add = add.split('/').next().unwrap_or_default();
+                    groups
+                        .entry(flag.to_string())
+                        .or_default()
+                        .rules
+                        .push(Affix {
+                            strip: if *strip == "0" { "" } else { *strip }.to_owned(),
+                            add: if add == "0" { "" } else { add }.to_owned(),
+                            condition: Condition::parse(condition),
+                        });
+                }
+                _ => {}
+            }
+        }
+
+        affixes
+    }
+
+    /// Every form of `word` its flags allow
+    fn expand(&self, word: &str, flags: &str, out: &mut HashSet<String>) {
+        let flags = self.format.split(flags);
+
+        let mut suffixed = vec![];
+        for group in flags.iter().filter_map(|flag| self.suffixes.get(flag)) {
+            for rule in &group.rules {
+                if let Some(form) = rule.apply_suffix(word) {
+                    if group.cross {
+                        suffixed.push(form.clone());
+                    }
+                    out.insert(form);
+                }
+            }
+        }
+
+        for group in flags.iter().filter_map(|flag| self.prefixes.get(flag)) {
+            for rule in &group.rules {
+                if let Some(form) = rule.apply_prefix(word) {
+                    out.insert(form);
+                }
+                if !group.cross {
+                    continue;
+                }
+                for form in suffixed.iter().filter_map(|w| rule.apply_prefix(w)) {
+                    out.insert(form);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+}
+
+impl Dictionary {
+    pub fn parse(aff: &str, dic: &str) -> Self {
+        let affixes = Affixes::parse(aff);
+        let mut words = HashSet::new();
+
+        // the first line is a word count
+        for line in dic.lines().skip(1) {
+            let Some(entry) = line.split_whitespace().next() else {
+                continue;
+            };
+            let (word, flags) = entry.split_once('/').unwrap_or((entry, ""));
+            if word.is_empty() {
+                continue;
+            }
+            affixes.expand(word, flags, &mut words);
+            words.insert(word.to_owned());
+        }
+
+        Dictionary { words }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn learn(&mut self, word: &str) {
+        self.words.insert(word.to_owned());
+    }
+
+    /// Capitalized and shouted versions of a known word are fine too
+    pub fn check(&self, word: &str) -> bool {
+        self.words.contains(word) || self.words.contains(&word.to_lowercase())
+    }
+
+    /// The closest known words, best first
+    pub fn suggest(&self, word: &str, max: usize) -> Vec<String> {
+        let lower = word.to_lowercase();
+        let len = lower.chars().count();
+
+        let mut found: Vec<(usize, &String)> = self
+            .words
+            .iter()
+            .filter(|candidate| candidate.chars().count().abs_diff(len) <= MAX_EDITS)
+            .filter_map(|candidate| {
+                let distance = edit_distance(&lower, &candidate.to_lowercase());
+                (distance <= MAX_EDITS).then_some((distance, candidate))
+            })
+            .collect();
+        found.sort();
+        found.dedup_by(|a, b| a.1.to_lowercase() == b.1.to_lowercase());
+
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        found
+            .into_iter()
+            .take(max)
+            .map(|(_, candidate)| {
+                if capitalized {
+                    capitalize(candidate)
+                } else {
+                    candidate.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Optimal string alignment distance, so a swapped pair of letters counts
+/// as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=b.len() {
+        rows[0][j] = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+/// Links, mentions, hashtags and the like aren't words
+fn is_checkable_token(token: &str) -> bool {
+    !(token.contains("://")
+        || token.starts_with("www.")
+        || token.starts_with("nostr:")
+        || token.starts_with('#')
+        || token.starts_with('@')
+        || token.contains(['@', '/', '_']))
+}
+
+/// Char ranges of the words in `text` worth checking. Skips links,
+/// mentions, hashtags, anything with digits, single letters and all caps
+/// acronyms.
+pub fn words(text: &str) -> Vec<Range<usize>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = vec![];
+
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token_start = i;
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        let token: String = chars[token_start..i].iter().collect();
+        if !is_checkable_token(&token) {
+            continue;
+        }
+
+        // letters with apostrophes inside, like "don't"
+        let mut j = token_start;
+        while j < i {
+            if !chars[j].is_alphanumeric() {
+                j += 1;
+                continue;
+            }
+            let start = j;
+            while j < i
+                && (chars[j].is_alphanumeric()
+                    || (matches!(chars[j], '\'' | '’')
+                        && chars.get(j + 1).is_some_and(|c| c.is_alphabetic())))
+            {
+                j += 1;
+            }
+
+            let word = &chars[start..j];
+            let has_digits = word.iter().any(|c| c.is_numeric());
+            let acronym = word.iter().all(|c| !c.is_lowercase());
+            if word.len() > 1 && !has_digits && !acronym {
+                words.push(start..j);
+            }
+        }
+    }
+
+    words
+}
+
+fn slice(chars: &[char], range: &Range<usize>) -> String {
+    chars[range.clone()]
+        .iter()
+        .collect::<String>()
+        .replace('’', "'")
+}
+
+/// Char ranges of the words the dictionary doesn't know
+pub fn misspelled(text: &str, dictionary: &Dictionary) -> Vec<Range<usize>> {
+    let chars: Vec<char> = text.chars().collect();
+    words(text)
+        .into_iter()
+        .filter(|range| !dictionary.check(&slice(&chars, range)))
+        .collect()
+}
+
+/// Char ranges of words typed twice in a row, like "the the"
+pub fn repeated(text: &str) -> Vec<Range<usize>> {
+    let chars: Vec<char> = text.chars().collect();
+    words(text)
+        .windows(2)
+        .filter(|pair| {
+            let between = &chars[pair[0].end..pair[1].start];
+            between.iter().all(|c| *c == ' ')
+                && slice(&chars, &pair[0]).to_lowercase() == slice(&chars, &pair[1]).to_lowercase()
+        })
+        .map(|pair| pair[1].clone())
+        .collect()
+}
+
+/// The dictionary name for a language tag. The hosted dictionaries use
+/// the bare language for the most common variant, so `de-DE` is `de`.
+pub fn dictionary_language(tag: &str) -> String {
+    match tag {
+        "en-GB" | "en-AU" | "en-CA" | "en-ZA" | "pt-BR" | "pt-PT" | "es-MX" | "es-AR" => {
+            tag.to_owned()
+        }
+        _ => tag.split(['-', '_']).next().unwrap_or(tag).to_lowercase(),
+    }
+}
+
+struct Spellcheck {
+    language: String,
+    dir: PathBuf,
+    dictionary: Option<Arc<Dictionary>>,
+    loading: Option<Promise<Result<Dictionary, String>>>,
+}
+
+static SPELLCHECK: Mutex<Option<Spellcheck>> = Mutex::new(None);
+
+/// Check spelling in `language`, or turn spellcheck off with `None`. This
+/// is cheap to call every frame, the dictionary only loads when the
+/// language changes.
+pub fn set_language(ctx: &egui::Context, path: &DataPath, language: Option<&str>) {
+    let Ok(mut current) = SPELLCHECK.lock() else {
+        return;
+    };
+
+    let Some(language) = language else {
+        *current = None;
+        return;
+    };
+
+    if current.as_ref().is_some_and(|c| c.language == language) {
+        return;
+    }
+
+    info!("loading the {language} spellcheck dictionary");
+    let dir = path.path(DataPathType::Cache).join("dictionaries");
+    *current = Some(Spellcheck {
+        language: language.to_owned(),
+        loading: Some(load(ctx.clone(), dir.clone(), language.to_owned())),
+        dir,
+        dictionary: None,
+    });
+}
+
+/// The dictionary for the current language, once it has loaded
+pub fn dictionary() -> Option<Arc<Dictionary>> {
+    let mut current = SPELLCHECK.lock().ok()?;
+    let spellcheck = current.as_mut()?;
+
+    if let Some(loading) = spellcheck.loading.take() {
+        match loading.try_take() {
+            Ok(Ok(dictionary)) => {
+                debug!("{} words in the dictionary", dictionary.len());
+                spellcheck.dictionary = Some(Arc::new(dictionary));
+            }
+            Ok(Err(err)) => {
+                error!(
+                    "could not load the {} dictionary: {err}",
+                    spellcheck.language
+                );
+                crate::toast::warning(crate::tr!(
+                    "toast-dictionary-error",
+                    language = spellcheck.language.as_str()
+                ));
+            }
+            Err(loading) => spellcheck.loading = Some(loading),
+        }
+    }
+
+    spellcheck.dictionary.clone()
+}
+
+/// Stop flagging `word`, in this and later sessions
+pub fn learn(word: &str) {
+    let Ok(mut current) = SPELLCHECK.lock() else {
+        return;
+    };
+    let Some(spellcheck) = current.as_mut() else {
+        return;
+    };
+
+    if let Some(dictionary) = &mut spellcheck.dictionary {
+        Arc::make_mut(dictionary).learn(word);
+    }
+
+    let personal = spellcheck.dir.join(PERSONAL_FILE);
+    let mut words = std::fs::read_to_string(&personal).unwrap_or_default();
+    words.push_str(word);
+    words.push('\n');
+    if let Err(err) = storage::write_file(&spellcheck.dir, PERSONAL_FILE.to_owned(), &words) {
+        error!("could not save {word} to the personal dictionary: {err}");
+    }
+}
+
+fn load(ctx: egui::Context, dir: PathBuf, language: String) -> Promise<Result<Dictionary, String>> {
+    Promise::spawn_thread("spellcheck-dictionary", move || {
+        let aff = fetch_cached(&dir, &language, "aff");
+        let dic = fetch_cached(&dir, &language, "dic");
+        let result = aff.and_then(|aff| {
+            let dic = dic?;
+            let mut dictionary = Dictionary::parse(&aff, &dic);
+            if let Ok(personal) = std::fs::read_to_string(dir.join(PERSONAL_FILE)) {
+                for word in personal.lines().filter(|w| !w.is_empty()) {
+                    dictionary.learn(word);
+                }
+            }
+            Ok(dictionary)
+        });
+        ctx.request_repaint();
+        result
+    })
+}
+
+/// Read a dictionary file from the cache, downloading it the first time
+fn fetch_cached(dir: &Path, language: &str, ext: &str) -> Result<String, String> {
+    let file_name = format!("{language}.{ext}");
+    if let Ok(contents) = std::fs::read_to_string(dir.join(&file_name)) {
+        return Ok(contents);
+    }
+
+    let url = format!("{}.{ext}", DEFAULT_SOURCE.replace("{lang}", language));
+    let response = ehttp::fetch_blocking(&ehttp::Request::get(&url))?;
+    if !response.ok {
+        return Err(format!("{} {}", response.status, response.status_text));
+    }
+
+    let contents = String::from_utf8_lossy(&response.bytes).into_owned();
+    if let Err(err) = storage::write_file(dir, file_name, &contents) {
+        error!("could not cache the {language} dictionary: {err}");
+    }
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8\n\
+        SFX S Y 2\n\
+        SFX S y ies [^aeiou]y\n\
+        SFX S 0 s [^y]\n\
+        PFX U Y 1\n\
+        PFX U 0 un .\n";
+    const DIC: &str = "4\nparty/S\nnote/SU\nread/U\nhello\n";
+
+    #[test]
+    fn test_dictionary_affixes() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+        for word in ["party", "parties", "notes", "unnotes", "unread", "Hello"] {
+            assert!(dictionary.check(word), "{word}");
+        }
+        for word in ["partys", "unhello", "reads"] {
+            assert!(!dictionary.check(word), "{word}");
+        }
+        assert_eq!(dictionary.suggest("Helol", 3), vec!["Hello"]);
+    }
+
+    #[test]
+    fn test_misspelled_and_repeated() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+        let text = "hello hello wrld, NASA nostr:npub1abc #hashtag don't https://x.y";
+        let chars: Vec<char> = text.chars().collect();
+        let found: Vec<String> = misspelled(text, &dictionary)
+            .iter()
+            .map(|r| slice(&chars, r))
+            .collect();
+        assert_eq!(found, vec!["wrld", "don't"]);
+        assert_eq!(repeated(text), vec![6..11]);
+    }
+
+    #[test]
+    fn test_dictionary_language() {
+        assert_eq!(dictionary_language("en-US"), "en");
+        assert_eq!(dictionary_language("en-GB"), "en-GB");
+        assert_eq!(dictionary_language("de-DE"), "de");
+    }
+}
@@ -0,0 +1,231 @@
+use std::path::{Path, PathBuf};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{error, info};
+
+use super::file_storage::{delete_file, write_file, DataPath, DataPathType, Directory};
+use crate::{Error, Result};
+
+/// Upgrades a stored value by one schema version
+pub type Migration = fn(Value) -> Value;
+
+/// The shape of a stored value over time. `migrations[n]` upgrades a value
+/// from version `n` to `n + 1`, so the current version is the number of
+/// migrations. Only ever append to the list.
+pub struct Schema {
+    pub migrations: &'static [Migration],
+}
+
+impl Schema {
+    /// A value that hasn't changed shape yet
+    pub const INITIAL: Schema = Schema { migrations: &[] };
+
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+
+    fn migrate(&self, mut value: Value, from: u32) -> Result<Value> {
+        if from > self.version() {
+            return Err(Error::Generic(format!(
+                "stored version {from} is newer than {}",
+                self.version()
+            )));
+        }
+
+        for migration in &self.migrations[from as usize..] {
+            value = migration(value);
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    version: u32,
+    value: Value,
+}
+
+/// Persisted key-value state for one app. Each key is a json file under
+/// the app's own directory, tagged with the schema version it was written
+/// with so older values can be migrated when they're read.
+#[derive(Debug, Clone)]
+pub struct AppStorage {
+    dir: PathBuf,
+}
+
+impl AppStorage {
+    pub fn new(path: &DataPath, app: &str) -> Self {
+        AppStorage {
+            dir: path.path(DataPathType::AppStorage).join(app),
+        }
+    }
+
+    /// The app's own directory, for what isn't a value, eg. a socket
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Read a value, migrating it to the current schema version if it was
+    /// written by an older one
+    pub fn get<T: DeserializeOwned>(&self, key: &str, schema: &Schema) -> Option<T> {
+        let contents = Directory::new(self.dir.clone())
+            .get_file(file_name(key))
+            .ok()?;
+
+        match self.decode(key, schema, &contents) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                error!("could not load {}/{key}: {err}", self.dir.display());
+                None
+            }
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(&self, key: &str, schema: &Schema, contents: &str) -> Result<T> {
+        let stored: Stored = serde_json::from_str(contents)?;
+        let migrated = stored.version < schema.version();
+        let value = schema.migrate(stored.value, stored.version)?;
+
+        if migrated {
+            info!(
+                "migrated {}/{key} from version {} to {}",
+                self.dir.display(),
+                stored.version,
+                schema.version()
+            );
+            self.write(key, schema.version(), value.clone())?;
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn set<T: Serialize>(&self, key: &str, schema: &Schema, value: &T) -> Result<()> {
+        self.write(key, schema.version(), serde_json::to_value(value)?)
+    }
+
+    pub fn remove(&self, key: &str) -> Result<()> {
+        delete_file(&self.dir, file_name(key))
+    }
+
+    /// Move a plain json file from before the app had its own storage in
+    /// under `key` as version 0, unless `key` is already stored
+    pub fn adopt(&self, key: &str, dir: &Path, legacy_file: &str) {
+        if self.dir.join(file_name(key)).exists() {
+            return;
+        }
+
+        let Ok(contents) = Directory::new(dir.to_owned()).get_file(legacy_file.to_owned()) else {
+            return;
+        };
+
+        let adopted = serde_json::from_str(&contents)
+            .map_err(Error::from)
+            .and_then(|value| self.write(key, 0, value))
+            .and_then(|_| delete_file(dir, legacy_file.to_owned()));
+
+        match adopted {
+            Ok(()) => info!("moved {legacy_file} into {}/{key}", self.dir.display()),
+            Err(err) => error!("could not move {legacy_file} into app storage: {err}"),
+        }
+    }
+
+    fn write(&self, key: &str, version: u32, value: Value) -> Result<()> {
+        let serialized = serde_json::to_string(&Stored { version, value })?;
+        write_file(&self.dir, file_name(key), &serialized)
+    }
+}
+
+fn file_name(key: &str) -> String {
+    format!("{key}.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rename_hour_fields(mut value: Value) -> Value {
+        if let Some(obj) = value.as_object_mut() {
+            if let Some(from) = obj.remove("from") {
+                obj.insert("start".to_owned(), from);
+            }
+        }
+        value
+    }
+
+    fn add_enabled(mut value: Value) -> Value {
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("enabled".to_owned(), json!(true));
+        }
+        value
+    }
+
+    static V2: Schema = Schema {
+        migrations: &[rename_hour_fields, add_enabled],
+    };
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        start: u8,
+        enabled: bool,
+    }
+
+    #[test]
+    fn test_roundtrip_and_migrate() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = DataPath::new(tmp.path());
+        let storage = AppStorage::new(&path, "test");
+
+        let settings = Settings {
+            start: 22,
+            enabled: false,
+        };
+        storage.set("settings", &V2, &settings).unwrap();
+        assert_eq!(storage.get::<Settings>("settings", &V2), Some(settings));
+
+        // written by version 0 of the app
+        storage.write("old", 0, json!({ "from": 21 })).unwrap();
+        assert_eq!(
+            storage.get::<Settings>("old", &V2),
+            Some(Settings {
+                start: 21,
+                enabled: true
+            })
+        );
+
+        // and saved back at the current version
+        let contents = Directory::new(storage.dir.clone())
+            .get_file(file_name("old"))
+            .unwrap();
+        let stored: Stored = serde_json::from_str(&contents).unwrap();
+        assert_eq!(stored.version, 2);
+
+        // from a future version we don't understand
+        storage.write("future", 3, json!({})).unwrap();
+        assert_eq!(storage.get::<Settings>("future", &V2), None);
+
+        assert!(storage.remove("settings").is_ok());
+        assert_eq!(storage.get::<Settings>("settings", &V2), None);
+    }
+
+    #[test]
+    fn test_adopt_legacy_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = DataPath::new(tmp.path());
+        let legacy_dir = path.path(DataPathType::Setting);
+        write_file(&legacy_dir, "old.json".to_owned(), r#"{"from": 7}"#).unwrap();
+
+        let storage = AppStorage::new(&path, "test");
+        storage.adopt("settings", &legacy_dir, "old.json");
+
+        assert!(!legacy_dir.join("old.json").exists());
+        assert_eq!(
+            storage.get::<Settings>("settings", &V2),
+            Some(Settings {
+                start: 7,
+                enabled: true
+            })
+        );
+    }
+}
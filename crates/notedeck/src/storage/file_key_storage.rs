@@ -1,45 +1,262 @@
-use crate::Result;
-use enostr::{Keypair, Pubkey, SerializableKeypair};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{Error, Result};
+use enostr::{FullKeypair, Keypair, Pubkey, SerializableKeypair};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
 
 use super::{
     file_storage::{delete_file, write_file, Directory},
     key_storage_impl::KeyStorageResponse,
+    secret_store::SecretStore,
 };
 
 static SELECTED_PUBKEY_FILE_NAME: &str = "selected_pubkey";
+static PROTECTION_FILE_NAME: &str = "key_protection";
+/// Added to a file written next to the one it's about to replace
+static PENDING_SUFFIX: &str = ".pending";
+
+/// scrypt cost for a password the OS keeps, which is random and long, so
+/// it doesn't need to be slow to guess
+const SYSTEM_LOG_N: u8 = 7;
+/// and for a passphrase someone typed
+const PASSPHRASE_LOG_N: u8 = 16;
+
+/// What account keys are encrypted with on disk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyProtection {
+    /// An empty password, how keys were kept before there was a choice
+    #[default]
+    None,
+    /// A random password kept by the OS keychain
+    System,
+    /// A passphrase the user types when the app starts
+    Passphrase,
+}
 
 /// An OS agnostic file key storage implementation
 #[derive(Debug, PartialEq)]
 pub struct FileKeyStorage {
     keys_directory: Directory,
     selected_key_directory: Directory,
+    /// What keys are encrypted with. None until they're unlocked.
+    password: Option<String>,
 }
 
 impl FileKeyStorage {
     pub fn new(keys_directory: Directory, selected_key_directory: Directory) -> Self {
-        Self {
+        let mut storage = Self {
             keys_directory,
             selected_key_directory,
+            password: None,
+        };
+        if storage.protection() == KeyProtection::None {
+            storage.password = Some(String::new());
+        }
+        storage
+    }
+
+    pub fn protection(&self) -> KeyProtection {
+        self.selected_key_directory
+            .get_file(PROTECTION_FILE_NAME.to_owned())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Keys can only be read or added once we have their password
+    pub fn is_locked(&self) -> bool {
+        self.password.is_none()
+    }
+
+    /// The key files by name, without any left pending by a `protect`
+    /// that didn't finish
+    fn stored_files(&self) -> Result<HashMap<String, String>> {
+        if !self.keys_directory.file_path.exists() {
+            return Ok(HashMap::new());
+        }
+        Ok(self
+            .keys_directory
+            .get_files()?
+            .into_iter()
+            .filter(|(name, _)| !name.ends_with(PENDING_SUFFIX))
+            .collect())
+    }
+
+    fn stored_keys(&self) -> Result<Vec<SerializableKeypair>> {
+        Ok(self
+            .stored_files()?
+            .values()
+            .filter_map(|str_key| serde_json::from_str::<SerializableKeypair>(str_key).ok())
+            .collect())
+    }
+
+    /// Every stored secret key, or an error if `password` doesn't open one
+    /// of them
+    fn decrypt_all(&self, password: &str) -> Result<Vec<Keypair>> {
+        self.stored_keys()?
+            .iter()
+            .map(|stored| {
+                let keypair = stored.to_keypair(password);
+                if stored.encrypted_secret_key.is_some() && keypair.secret_key.is_none() {
+                    Err(Error::Generic("wrong password for the keys".to_owned()))
+                } else {
+                    Ok(keypair)
+                }
+            })
+            .collect()
+    }
+
+    pub fn unlock(&mut self, password: &str) -> Result<()> {
+        self.decrypt_all(password)?;
+        self.password = Some(password.to_owned());
+        Ok(())
+    }
+
+    /// Encrypt every key again under a new password. Every key is written
+    /// under it before any is replaced, and the new protection is only
+    /// recorded once they all are, so a failure leaves the keys as they
+    /// were.
+    pub fn protect(&mut self, protection: KeyProtection, password: &str) -> Result<()> {
+        let Some(current) = &self.password else {
+            return Err(Error::Generic("the keys are locked".to_owned()));
+        };
+        let keys = self.decrypt_all(current)?;
+        let previous = self.stored_files()?;
+        let dir = &self.keys_directory.file_path;
+
+        let written = keys.iter().try_for_each(|key| {
+            let stored = SerializableKeypair::from_keypair(key, password, log_n(protection));
+            write_file(
+                dir,
+                pending(&key.pubkey.hex()),
+                &serde_json::to_string(&stored)?,
+            )
+        });
+        if let Err(err) = written {
+            discard_pending(dir, &keys);
+            return Err(err);
+        }
+
+        let mut moved = vec![];
+        for key in &keys {
+            let name = key.pubkey.hex();
+            if let Err(err) = std::fs::rename(dir.join(pending(&name)), dir.join(&name)) {
+                restore(dir, &previous, &moved);
+                discard_pending(dir, &keys);
+                return Err(err.into());
+            }
+            moved.push(name);
+        }
+
+        if let Err(err) = replace_file(
+            &self.selected_key_directory.file_path,
+            PROTECTION_FILE_NAME,
+            &serde_json::to_string(&protection)?,
+        ) {
+            restore(dir, &previous, &moved);
+            return Err(err);
+        }
+        self.password = Some(password.to_owned());
+
+        info!("keys are now protected with {:?}", protection);
+        Ok(())
+    }
+
+    /// Get at the keys on startup. Keys the OS keeps the password for are
+    /// unlocked with it, and keys from before there was a choice are moved
+    /// under it. Keys under a passphrase stay locked until it's typed.
+    pub fn unlock_with(&mut self, secrets: Option<&dyn SecretStore>) -> Result<()> {
+        match (self.protection(), secrets) {
+            (KeyProtection::None, Some(secrets)) => self.use_secret_store(secrets),
+            (KeyProtection::System, Some(secrets)) => {
+                let password = secrets.get()?.ok_or_else(|| {
+                    Error::Generic(format!("{} lost the key password", secrets.name()))
+                })?;
+                self.unlock(&password)
+            }
+            (KeyProtection::System, None) => Err(Error::Generic(
+                "the keys are in a keychain that isn't available".to_owned(),
+            )),
+            (KeyProtection::None | KeyProtection::Passphrase, None)
+            | (KeyProtection::Passphrase, Some(_)) => Ok(()),
+        }
+    }
+
+    /// Have the OS keep the password from now on
+    pub fn use_secret_store(&mut self, secrets: &dyn SecretStore) -> Result<()> {
+        let password = FullKeypair::generate().secret_key.to_secret_hex();
+        let previous = secrets.get()?;
+        secrets.set(&password)?;
+
+        // the keys are lost if the store quietly didn't keep it
+        let result = if secrets.get()?.as_deref() != Some(password.as_str()) {
+            Err(Error::Generic(format!(
+                "{} didn't keep the key password",
+                secrets.name()
+            )))
+        } else {
+            self.protect(KeyProtection::System, &password)
+        };
+
+        if result.is_err() {
+            // the keys are still under the old password
+            let restored = match &previous {
+                Some(previous) => secrets.set(previous),
+                None => secrets.remove(),
+            };
+            if let Err(err) = restored {
+                error!("couldn't restore the old key password: {err}");
+            }
+        }
+        result
+    }
+
+    /// Lock the keys with a passphrase instead, forgetting any password
+    /// the OS was keeping
+    pub fn use_passphrase(
+        &mut self,
+        passphrase: &str,
+        secrets: Option<&dyn SecretStore>,
+    ) -> Result<()> {
+        if passphrase.is_empty() {
+            return Err(Error::Generic("the passphrase is empty".to_owned()));
+        }
+        let was_system = self.protection() == KeyProtection::System;
+        self.protect(KeyProtection::Passphrase, passphrase)?;
+        if let Some(secrets) = secrets.filter(|_| was_system) {
+            secrets.remove()?;
         }
+        Ok(())
     }
 
     fn add_key_internal(&self, key: &Keypair) -> Result<()> {
+        let Some(password) = &self.password else {
+            return Err(Error::Generic("the keys are locked".to_owned()));
+        };
         write_file(
             &self.keys_directory.file_path,
             key.pubkey.hex(),
-            &serde_json::to_string(&SerializableKeypair::from_keypair(key, "", 7))?,
+            &serde_json::to_string(&SerializableKeypair::from_keypair(
+                key,
+                password,
+                log_n(self.protection()),
+            ))?,
         )
     }
 
     fn get_keys_internal(&self) -> Result<Vec<Keypair>> {
-        let keys = self
-            .keys_directory
-            .get_files()?
-            .values()
-            .filter_map(|str_key| serde_json::from_str::<SerializableKeypair>(str_key).ok())
-            .map(|serializable_keypair| serializable_keypair.to_keypair(""))
-            .collect();
-        Ok(keys)
+        let keys = self.stored_keys()?;
+        Ok(match &self.password {
+            Some(password) => keys.iter().map(|key| key.to_keypair(password)).collect(),
+            // locked accounts can still be looked at
+            None => keys
+                .iter()
+                .map(|key| Keypair::only_pubkey(key.pubkey))
+                .collect(),
+        })
     }
 
     fn remove_key_internal(&self, key: &Keypair) -> Result<()> {
@@ -77,6 +294,43 @@ impl FileKeyStorage {
     }
 }
 
+fn log_n(protection: KeyProtection) -> u8 {
+    match protection {
+        KeyProtection::Passphrase => PASSPHRASE_LOG_N,
+        KeyProtection::None | KeyProtection::System => SYSTEM_LOG_N,
+    }
+}
+
+fn pending(file_name: &str) -> String {
+    format!("{file_name}{PENDING_SUFFIX}")
+}
+
+/// Write next to `file_name` and move it into place, so it's never half
+/// written
+fn replace_file(dir: &Path, file_name: &str, data: &str) -> Result<()> {
+    write_file(dir, pending(file_name), data)?;
+    std::fs::rename(dir.join(pending(file_name)), dir.join(file_name))?;
+    Ok(())
+}
+
+fn discard_pending(dir: &Path, keys: &[Keypair]) {
+    for key in keys {
+        let _ = std::fs::remove_file(dir.join(pending(&key.pubkey.hex())));
+    }
+}
+
+/// Put back the key files that `protect` had already replaced
+fn restore(dir: &Path, previous: &HashMap<String, String>, moved: &[String]) {
+    for name in moved {
+        let Some(contents) = previous.get(name) else {
+            continue;
+        };
+        if let Err(err) = replace_file(dir, name, contents) {
+            error!("couldn't restore key {name}: {err}");
+        }
+    }
+}
+
 impl FileKeyStorage {
     pub fn get_keys(&self) -> KeyStorageResponse<Vec<enostr::Keypair>> {
         KeyStorageResponse::ReceivedResult(self.get_keys_internal())
@@ -115,6 +369,7 @@ mod tests {
             Ok(Self {
                 keys_directory: Directory::new(CREATE_TMP_DIR()?),
                 selected_key_directory: Directory::new(CREATE_TMP_DIR()?),
+                password: Some(String::new()),
             })
         }
     }
@@ -149,6 +404,90 @@ mod tests {
         }
     }
 
+    /// A keychain that forgets when the test ends
+    #[derive(Default)]
+    struct MemorySecrets(std::cell::RefCell<Option<String>>);
+
+    impl SecretStore for MemorySecrets {
+        fn name(&self) -> String {
+            "memory".to_owned()
+        }
+
+        fn get(&self) -> Result<Option<String>> {
+            Ok(self.0.borrow().clone())
+        }
+
+        fn set(&self, secret: &str) -> Result<()> {
+            *self.0.borrow_mut() = Some(secret.to_owned());
+            Ok(())
+        }
+
+        fn remove(&self) -> Result<()> {
+            *self.0.borrow_mut() = None;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_secret_store_migration() {
+        let kp = enostr::FullKeypair::generate().to_keypair();
+        let secrets = MemorySecrets::default();
+
+        let mut storage = FileKeyStorage::mock().unwrap();
+        let _ = storage.add_key(&kp);
+        storage.unlock_with(Some(&secrets)).unwrap();
+        assert_eq!(storage.protection(), KeyProtection::System);
+        assert!(secrets.get().unwrap().is_some());
+
+        // next start, the keys on disk need the keychain's password
+        let mut restarted = FileKeyStorage::new(
+            Directory::new(storage.keys_directory.file_path.clone()),
+            Directory::new(storage.selected_key_directory.file_path.clone()),
+        );
+        assert!(restarted.is_locked());
+        assert_eq!(restarted.get_keys_internal().unwrap()[0].secret_key, None);
+        assert!(restarted.add_key_internal(&kp).is_err());
+        assert!(restarted.unlock("").is_err());
+
+        restarted.unlock_with(Some(&secrets)).unwrap();
+        assert_eq!(restarted.get_keys_internal().unwrap(), vec![kp]);
+
+        assert!(restarted.use_passphrase("", Some(&secrets)).is_err());
+        assert!(FileKeyStorage::new(
+            Directory::new(storage.keys_directory.file_path.clone()),
+            Directory::new(storage.selected_key_directory.file_path.clone()),
+        )
+        .unlock_with(None)
+        .is_err());
+    }
+
+    #[test]
+    fn test_protect_replaces_every_key() {
+        let first = enostr::FullKeypair::generate().to_keypair();
+        let second = enostr::FullKeypair::generate().to_keypair();
+
+        let mut storage = FileKeyStorage::mock().unwrap();
+        let _ = storage.add_key(&first);
+        let _ = storage.add_key(&second);
+
+        // left by a protect that didn't finish, not a key
+        let dir = storage.keys_directory.file_path.clone();
+        std::fs::copy(
+            dir.join(first.pubkey.hex()),
+            dir.join(pending(&first.pubkey.hex())),
+        )
+        .unwrap();
+        assert_eq!(storage.stored_keys().unwrap().len(), 2);
+
+        storage
+            .protect(KeyProtection::Passphrase, "hunter2")
+            .unwrap();
+        assert_eq!(storage.protection(), KeyProtection::Passphrase);
+        assert_eq!(storage.decrypt_all("hunter2").unwrap().len(), 2);
+        assert!(storage.decrypt_all("").is_err());
+        assert_eq!(storage.keys_directory.get_file_names().unwrap().len(), 2);
+    }
+
     #[test]
     fn test_select_key() {
         let kp = enostr::FullKeypair::generate().to_keypair();
@@ -36,6 +36,7 @@ impl DataPath {
             DataPathType::SelectedKey => PathBuf::from("storage").join("selected_account"),
             DataPathType::Db => PathBuf::from("db"),
             DataPathType::Cache => PathBuf::from("cache"),
+            DataPathType::AppStorage => PathBuf::from("storage").join("apps"),
         }
     }
 
@@ -57,6 +58,8 @@ pub enum DataPathType {
     SelectedKey,
     Db,
     Cache,
+    /// Per-app persisted state, see [`super::AppStorage`]
+    AppStorage,
 }
 
 #[derive(Debug, PartialEq)]
@@ -39,6 +39,21 @@ impl<R: PartialEq> PartialEq for KeyStorageResponse<R> {
 }
 
 impl KeyStorageType {
+    /// Keys on disk can be locked and unlocked
+    pub fn file_storage(&self) -> Option<&FileKeyStorage> {
+        match self {
+            Self::FileSystem(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn file_storage_mut(&mut self) -> Option<&mut FileKeyStorage> {
+        match self {
+            Self::FileSystem(f) => Some(f),
+            _ => None,
+        }
+    }
+
     pub fn get_keys(&self) -> KeyStorageResponse<Vec<Keypair>> {
         match self {
             Self::None => KeyStorageResponse::ReceivedResult(Ok(Vec::new())),
@@ -1,8 +1,12 @@
+mod app_storage;
 mod file_key_storage;
 mod file_storage;
+mod secret_store;
 
-pub use file_key_storage::FileKeyStorage;
+pub use app_storage::{AppStorage, Migration, Schema};
+pub use file_key_storage::{FileKeyStorage, KeyProtection};
 pub use file_storage::{delete_file, write_file, DataPath, DataPathType, Directory};
+pub use secret_store::{system_secret_store, SecretStore};
 
 #[cfg(target_os = "macos")]
 mod security_framework_key_storage;
@@ -0,0 +1,314 @@
+//! Somewhere the OS keeps a secret for us: the password account keys are
+//! encrypted with on disk. Anyone who can read our data directory gets
+//! the keys files, but not this.
+
+use crate::Result;
+
+/// The account and service the password is filed under
+const SERVICE: &str = "com.damus.notedeck";
+const ACCOUNT: &str = "key-password";
+
+pub trait SecretStore {
+    /// Where the secret is kept, for settings, eg. "macOS Keychain"
+    fn name(&self) -> String;
+    fn get(&self) -> Result<Option<String>>;
+    fn set(&self, secret: &str) -> Result<()>;
+    fn remove(&self) -> Result<()>;
+}
+
+/// The desktop OS's store, if it has one we can reach. Android's is set
+/// up by the chrome, which has the JNI layer.
+pub fn system_secret_store() -> Option<Box<dyn SecretStore>> {
+    #[cfg(target_os = "macos")]
+    {
+        Some(Box::new(keychain::Keychain))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        Some(Box::new(credentials::CredentialManager))
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        secret_service::SecretService::find().map(|s| Box::new(s) as Box<dyn SecretStore>)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        None
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod keychain {
+    use security_framework::passwords::{
+        delete_generic_password, get_generic_password, set_generic_password,
+    };
+
+    use super::{SecretStore, ACCOUNT, SERVICE};
+    use crate::{Error, Result};
+
+    /// errSecItemNotFound
+    const NOT_FOUND: i32 = -25300;
+
+    pub struct Keychain;
+
+    impl SecretStore for Keychain {
+        fn name(&self) -> String {
+            "macOS Keychain".to_owned()
+        }
+
+        fn get(&self) -> Result<Option<String>> {
+            match get_generic_password(SERVICE, ACCOUNT) {
+                Ok(bytes) => Ok(Some(
+                    String::from_utf8(bytes).map_err(|e| Error::Generic(e.to_string()))?,
+                )),
+                Err(e) if e.code() == NOT_FOUND => Ok(None),
+                Err(e) => Err(Error::Generic(e.to_string())),
+            }
+        }
+
+        fn set(&self, secret: &str) -> Result<()> {
+            set_generic_password(SERVICE, ACCOUNT, secret.as_bytes())
+                .map_err(|e| Error::Generic(e.to_string()))
+        }
+
+        fn remove(&self) -> Result<()> {
+            match delete_generic_password(SERVICE, ACCOUNT) {
+                Err(e) if e.code() != NOT_FOUND => Err(Error::Generic(e.to_string())),
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+/// The Windows Credential Manager, called directly since it's only a few
+/// functions in advapi32
+#[cfg(target_os = "windows")]
+mod credentials {
+    use std::ffi::c_void;
+
+    use super::{SecretStore, ACCOUNT, SERVICE};
+    use crate::{Error, Result};
+
+    const CRED_TYPE_GENERIC: u32 = 1;
+    const CRED_PERSIST_LOCAL_MACHINE: u32 = 2;
+    const ERROR_NOT_FOUND: i32 = 1168;
+
+    #[repr(C)]
+    struct FileTime {
+        low: u32,
+        high: u32,
+    }
+
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    struct CredentialW {
+        Flags: u32,
+        Type: u32,
+        TargetName: *mut u16,
+        Comment: *mut u16,
+        LastWritten: FileTime,
+        CredentialBlobSize: u32,
+        CredentialBlob: *mut u8,
+        Persist: u32,
+        AttributeCount: u32,
+        Attributes: *mut c_void,
+        TargetAlias: *mut u16,
+        UserName: *mut u16,
+    }
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn CredReadW(
+            target: *const u16,
+            kind: u32,
+            flags: u32,
+            credential: *mut *mut CredentialW,
+        ) -> i32;
+        fn CredWriteW(credential: *const CredentialW, flags: u32) -> i32;
+        fn CredDeleteW(target: *const u16, kind: u32, flags: u32) -> i32;
+        fn CredFree(buffer: *mut c_void);
+    }
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    fn target() -> Vec<u16> {
+        wide(&format!("{SERVICE}/{ACCOUNT}"))
+    }
+
+    fn last_error() -> std::io::Error {
+        std::io::Error::last_os_error()
+    }
+
+    pub struct CredentialManager;
+
+    impl SecretStore for CredentialManager {
+        fn name(&self) -> String {
+            "Windows Credential Manager".to_owned()
+        }
+
+        fn get(&self) -> Result<Option<String>> {
+            let target = target();
+            let mut credential = std::ptr::null_mut();
+            // SAFETY: target is nul terminated and CredReadW allocates
+            // what it points credential at, which we free below
+            if unsafe { CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) } == 0 {
+                let err = last_error();
+                if err.raw_os_error() == Some(ERROR_NOT_FOUND) {
+                    return Ok(None);
+                }
+                return Err(err.into());
+            }
+
+            // SAFETY: a successful read gives a valid credential whose blob
+            // is CredentialBlobSize bytes long
+            let secret = unsafe {
+                let blob = std::slice::from_raw_parts(
+                    (*credential).CredentialBlob,
+                    (*credential).CredentialBlobSize as usize,
+                );
+                let secret = String::from_utf8(blob.to_vec());
+                CredFree(credential.cast());
+                secret
+            };
+            secret.map(Some).map_err(|e| Error::Generic(e.to_string()))
+        }
+
+        fn set(&self, secret: &str) -> Result<()> {
+            let mut target = target();
+            let mut user = wide(ACCOUNT);
+            let mut blob = secret.as_bytes().to_vec();
+            let credential = CredentialW {
+                Flags: 0,
+                Type: CRED_TYPE_GENERIC,
+                TargetName: target.as_mut_ptr(),
+                Comment: std::ptr::null_mut(),
+                LastWritten: FileTime { low: 0, high: 0 },
+                CredentialBlobSize: blob.len() as u32,
+                CredentialBlob: blob.as_mut_ptr(),
+                Persist: CRED_PERSIST_LOCAL_MACHINE,
+                AttributeCount: 0,
+                Attributes: std::ptr::null_mut(),
+                TargetAlias: std::ptr::null_mut(),
+                UserName: user.as_mut_ptr(),
+            };
+
+            // SAFETY: every pointer in credential outlives the call
+            if unsafe { CredWriteW(&credential, 0) } == 0 {
+                return Err(last_error().into());
+            }
+            Ok(())
+        }
+
+        fn remove(&self) -> Result<()> {
+            let target = target();
+            // SAFETY: target is nul terminated
+            if unsafe { CredDeleteW(target.as_ptr(), CRED_TYPE_GENERIC, 0) } == 0 {
+                let err = last_error();
+                if err.raw_os_error() != Some(ERROR_NOT_FOUND) {
+                    return Err(err.into());
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// The freedesktop Secret Service (GNOME Keyring, KWallet), through
+/// libsecret's `secret-tool`
+#[cfg(target_os = "linux")]
+mod secret_service {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    use super::{SecretStore, ACCOUNT, SERVICE};
+    use crate::{Error, Result};
+
+    const TOOL: &str = "secret-tool";
+
+    pub struct SecretService;
+
+    impl SecretService {
+        /// Only if secret-tool is installed and a secret service answers
+        pub fn find() -> Option<Self> {
+            let status = Command::new(TOOL)
+                .args(["search", "service", SERVICE])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .ok()?;
+            status.success().then_some(SecretService)
+        }
+    }
+
+    fn failed(what: &str, stderr: &[u8]) -> Error {
+        Error::Generic(format!(
+            "{TOOL} {what} failed: {}",
+            String::from_utf8_lossy(stderr).trim()
+        ))
+    }
+
+    impl SecretStore for SecretService {
+        fn name(&self) -> String {
+            "Secret Service".to_owned()
+        }
+
+        fn get(&self) -> Result<Option<String>> {
+            let output = Command::new(TOOL)
+                .args(["lookup", "service", SERVICE, "account", ACCOUNT])
+                .output()?;
+            // lookup exits with 1 and says nothing when there's no secret
+            if !output.status.success() {
+                return if output.stderr.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(failed("lookup", &output.stderr))
+                };
+            }
+
+            let secret =
+                String::from_utf8(output.stdout).map_err(|e| Error::Generic(e.to_string()))?;
+            Ok(Some(secret.trim_end_matches('\n').to_owned()))
+        }
+
+        fn set(&self, secret: &str) -> Result<()> {
+            // the secret goes over stdin so it isn't in our arguments
+            let mut child = Command::new(TOOL)
+                .args([
+                    "store",
+                    "--label=Notedeck",
+                    "service",
+                    SERVICE,
+                    "account",
+                    ACCOUNT,
+                ])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .spawn()?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(secret.as_bytes())?;
+            }
+
+            let output = child.wait_with_output()?;
+            if !output.status.success() {
+                return Err(failed("store", &output.stderr));
+            }
+            Ok(())
+        }
+
+        fn remove(&self) -> Result<()> {
+            let output = Command::new(TOOL)
+                .args(["clear", "service", SERVICE, "account", ACCOUNT])
+                .output()?;
+            if !output.status.success() && !output.stderr.is_empty() {
+                return Err(failed("clear", &output.stderr));
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! Getting text into a shape egui can lay out. egui draws characters one
+//! after another from left to right with no shaping, so Arabic letters
+//! don't join, right to left text comes out backwards, and emoji sequences
+//! our fonts don't have show up as boxes. This fixes up what it can before
+//! layout, and reorders right to left text row by row after egui has
+//! decided where the rows break.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use egui::text::{LayoutJob, LayoutSection, TextFormat};
+use egui::{Color32, FontId, Galley};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Joining {
+    /// Joins to the letter before it only, like alef
+    Right,
+    /// Joins on both sides
+    Dual,
+    /// Makes its neighbours join, like tatweel
+    Causing,
+    Never,
+}
+
+const LAM: char = '\u{0644}';
+const ZWJ: char = '\u{200D}';
+
+/// An Arabic letter's isolated presentation form, and how it joins. The
+/// final, initial and medial forms follow the isolated one.
+fn arabic_letter(c: char) -> Option<(u32, Joining)> {
+    use Joining::*;
+    Some(match c {
+        '\u{0621}' => (0xFE80, Never),
+        '\u{0622}' => (0xFE81, Right),
+        '\u{0623}' => (0xFE83, Right),
+        '\u{0624}' => (0xFE85, Right),
+        '\u{0625}' => (0xFE87, Right),
+        '\u{0626}' => (0xFE89, Dual),
+        '\u{0627}' => (0xFE8D, Right),
+        '\u{0628}' => (0xFE8F, Dual),
+        '\u{0629}' => (0xFE93, Right),
+        '\u{062A}' => (0xFE95, Dual),
+        '\u{062B}' => (0xFE99, Dual),
+        '\u{062C}' => (0xFE9D, Dual),
+        '\u{062D}' => (0xFEA1, Dual),
+        '\u{062E}' => (0xFEA5, Dual),
+        '\u{062F}' => (0xFEA9, Right),
+        '\u{0630}' => (0xFEAB, Right),
+        '\u{0631}' => (0xFEAD, Right),
+        '\u{0632}' => (0xFEAF, Right),
+        '\u{0633}' => (0xFEB1, Dual),
+        '\u{0634}' => (0xFEB5, Dual),
+        '\u{0635}' => (0xFEB9, Dual),
+        '\u{0636}' => (0xFEBD, Dual),
+        '\u{0637}' => (0xFEC1, Dual),
+        '\u{0638}' => (0xFEC5, Dual),
+        '\u{0639}' => (0xFEC9, Dual),
+        '\u{063A}' => (0xFECD, Dual),
+        '\u{0641}' => (0xFED1, Dual),
+        '\u{0642}' => (0xFED5, Dual),
+        '\u{0643}' => (0xFED9, Dual),
+        '\u{0644}' => (0xFEDD, Dual),
+        '\u{0645}' => (0xFEE1, Dual),
+        '\u{0646}' => (0xFEE5, Dual),
+        '\u{0647}' => (0xFEE9, Dual),
+        '\u{0648}' => (0xFEED, Right),
+        '\u{0649}' => (0xFEEF, Right),
+        '\u{064A}' => (0xFEF1, Dual),
+        // Persian and Urdu
+        '\u{067E}' => (0xFB56, Dual),
+        '\u{0686}' => (0xFB7A, Dual),
+        '\u{0698}' => (0xFB8A, Right),
+        '\u{06A9}' => (0xFB8E, Dual),
+        '\u{06AF}' => (0xFB92, Dual),
+        '\u{06CC}' => (0xFBFC, Dual),
+        _ => return None,
+    })
+}
+
+/// Lam followed by one of these is written as a single ligature. Its final
+/// form follows the isolated one.
+fn lam_alef(c: char) -> Option<u32> {
+    match c {
+        '\u{0622}' => Some(0xFEF5),
+        '\u{0623}' => Some(0xFEF7),
+        '\u{0625}' => Some(0xFEF9),
+        '\u{0627}' => Some(0xFEFB),
+        _ => None,
+    }
+}
+
+fn joining(c: char) -> Joining {
+    match c {
+        '\u{0640}' | ZWJ => Joining::Causing,
+        _ => arabic_letter(c).map_or(Joining::Never, |(_, joining)| joining),
+    }
+}
+
+/// Vowel marks and the like, which letters join across
+fn is_transparent(c: char) -> bool {
+    matches!(c, '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{06D6}'..='\u{06ED}')
+}
+
+/// Pick each Arabic letter's joined form, since egui can't
+pub fn shape_arabic(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| arabic_letter(c).is_some()) {
+        return Cow::Borrowed(text);
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let neighbour = |mut indices: Box<dyn Iterator<Item = usize>>| {
+        indices
+            .find(|&i| !is_transparent(chars[i]))
+            .map_or(Joining::Never, |i| joining(chars[i]))
+    };
+
+    let mut shaped = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let Some((isolated, kind)) = arabic_letter(c) else {
+            shaped.push(c);
+            i += 1;
+            continue;
+        };
+
+        let before = neighbour(Box::new((0..i).rev()));
+        let joins_before = matches!(kind, Joining::Right | Joining::Dual)
+            && matches!(before, Joining::Dual | Joining::Causing);
+
+        if let Some(ligature) = (c == LAM)
+            .then(|| chars.get(i + 1).and_then(|&next| lam_alef(next)))
+            .flatten()
+        {
+            shaped.extend(char::from_u32(ligature + joins_before as u32));
+            i += 2;
+            continue;
+        }
+
+        let after = neighbour(Box::new(i + 1..chars.len()));
+        let joins_after = kind == Joining::Dual
+            && matches!(after, Joining::Right | Joining::Dual | Joining::Causing);
+
+        let form = match (joins_before, joins_after) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        shaped.push(char::from_u32(isolated + form).unwrap_or(c));
+        i += 1;
+    }
+
+    Cow::Owned(shaped)
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF)
+}
+
+/// Our emoji font only has single emoji, so sequences are drawn as their
+/// parts. Variation selectors, tag characters and the joiners between
+/// emoji would otherwise show up as boxes.
+pub fn clean_emoji(text: &str) -> Cow<'_, str> {
+    let is_sequence_char =
+        |c: char| matches!(c, '\u{FE0E}' | '\u{FE0F}' | ZWJ | '\u{E0020}'..='\u{E007F}');
+    if !text.chars().any(is_sequence_char) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut cleaned = String::with_capacity(text.len());
+    let mut prev = None;
+    for c in text.chars() {
+        // joiners also hold Arabic and Indic letters together, keep those
+        let keep = match c {
+            ZWJ => !prev.is_some_and(is_emoji),
+            c => !is_sequence_char(c),
+        };
+        if keep {
+            cleaned.push(c);
+            prev = Some(c);
+        }
+    }
+    Cow::Owned(cleaned)
+}
+
+/// Everything that can be fixed before layout
+pub fn prepare(text: &str) -> Cow<'_, str> {
+    match shape_arabic(text) {
+        Cow::Borrowed(text) => clean_emoji(text),
+        Cow::Owned(shaped) => Cow::Owned(clean_emoji(&shaped).into_owned()),
+    }
+}
+
+fn is_rtl(c: char) -> bool {
+    matches!(c, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFE}')
+        && !is_number(c)
+}
+
+fn is_number(c: char) -> bool {
+    c.is_ascii_digit() || matches!(c, '\u{0660}'..='\u{0669}' | '\u{06F0}'..='\u{06F9}')
+}
+
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(is_rtl)
+}
+
+/// The direction of the first letter, which sets the direction of the
+/// paragraph
+pub fn direction(text: &str) -> Option<Direction> {
+    text.chars().find_map(|c| {
+        if is_rtl(c) {
+            Some(Direction::Rtl)
+        } else if c.is_alphabetic() {
+            Some(Direction::Ltr)
+        } else {
+            None
+        }
+    })
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        c => c,
+    }
+}
+
+/// One row in display order, for a paragraph going `base`. This is the
+/// part of the Unicode bidi algorithm notes need: no explicit embeddings,
+/// numbers keep reading left to right, and punctuation between two runs
+/// going the same way goes with them.
+fn reorder(line: &str, base: Direction) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    if !chars.iter().any(|&c| is_rtl(c)) && base == Direction::Ltr {
+        return line.to_owned();
+    }
+
+    let base_level: u8 = (base == Direction::Rtl).into();
+    let ltr_level = if base == Direction::Rtl { 2 } else { 0 };
+
+    // each char's direction, if it has one. Marks go with their letter and
+    // numbers count as the text they're in.
+    let mut last_strong = base;
+    let mut prev = None;
+    let directions: Vec<Option<Direction>> = chars
+        .iter()
+        .map(|&c| {
+            let direction = if is_transparent(c) || ('\u{0591}'..='\u{05C7}').contains(&c) {
+                prev
+            } else if is_rtl(c) {
+                last_strong = Direction::Rtl;
+                Some(Direction::Rtl)
+            } else if c.is_alphabetic() {
+                last_strong = Direction::Ltr;
+                Some(Direction::Ltr)
+            } else if is_number(c) {
+                Some(last_strong)
+            } else {
+                None
+            };
+            prev = direction;
+            direction
+        })
+        .collect();
+
+    let level_of = |direction: Direction| match direction {
+        Direction::Rtl => 1,
+        Direction::Ltr => ltr_level,
+    };
+
+    let mut levels: Vec<u8> = (0..chars.len())
+        .map(|i| {
+            if is_number(chars[i]) {
+                // numbers read left to right even in right to left text
+                return if directions[i] == Some(Direction::Rtl) {
+                    2
+                } else {
+                    ltr_level
+                };
+            }
+            if let Some(direction) = directions[i] {
+                return level_of(direction);
+            }
+            let before = directions[..i]
+                .iter()
+                .rev()
+                .find_map(|d| *d)
+                .unwrap_or(base);
+            let after = directions[i + 1..].iter().find_map(|d| *d).unwrap_or(base);
+            if before == after {
+                level_of(before)
+            } else {
+                base_level
+            }
+        })
+        .collect();
+
+    // trailing whitespace stays at the paragraph's end
+    for (i, c) in chars.iter().enumerate().rev() {
+        if !c.is_whitespace() {
+            break;
+        }
+        levels[i] = base_level;
+    }
+
+    let mut visual: Vec<char> = chars
+        .iter()
+        .zip(&levels)
+        .map(|(&c, level)| if level % 2 == 1 { mirror(c) } else { c })
+        .collect();
+
+    let max_level = levels.iter().copied().max().unwrap_or(0);
+    for level in (1..=max_level).rev() {
+        let mut i = 0;
+        while i < visual.len() {
+            if levels[i] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < visual.len() && levels[i] >= level {
+                i += 1;
+            }
+            visual[start..i].reverse();
+            levels[start..i].reverse();
+        }
+    }
+
+    visual.into_iter().collect()
+}
+
+/// Lay out text the way it should read, wrapped at `wrap_width`. Rows of
+/// right to left paragraphs are put in display order and lined up on the
+/// right.
+pub fn galley(
+    fonts: &egui::text::Fonts,
+    text: &str,
+    font_id: FontId,
+    color: Color32,
+    wrap_width: f32,
+) -> Arc<Galley> {
+    layout(fonts, text, font_id, color, wrap_width, usize::MAX)
+}
+
+/// Like [`galley`], but at most `max_rows` rows, ending with … when the
+/// text doesn't fit. Rows break where the text is too wide for them, so
+/// this works for any script and font, unlike counting characters.
+pub fn clamped_galley(
+    fonts: &egui::text::Fonts,
+    text: &str,
+    font_id: FontId,
+    color: Color32,
+    wrap_width: f32,
+    max_rows: usize,
+) -> Arc<Galley> {
+    layout(fonts, text, font_id, color, wrap_width, max_rows)
+}
+
+fn layout(
+    fonts: &egui::text::Fonts,
+    text: &str,
+    font_id: FontId,
+    color: Color32,
+    wrap_width: f32,
+    max_rows: usize,
+) -> Arc<Galley> {
+    let text = prepare(text);
+    let mut job = LayoutJob::simple(text.to_string(), font_id.clone(), color, wrap_width);
+    job.wrap.max_rows = max_rows;
+    job.wrap.overflow_character = Some('…');
+
+    // egui decides where the rows break, in reading order
+    let logical = fonts.layout_job(job);
+    if !has_rtl(&text) {
+        return logical;
+    }
+
+    let width = if wrap_width.is_finite() {
+        wrap_width
+    } else {
+        logical.size().x
+    };
+
+    let paragraphs: Vec<Direction> = text
+        .split('\n')
+        .map(|paragraph| direction(paragraph).unwrap_or(Direction::Ltr))
+        .collect();
+    let mut paragraph = 0;
+    let mut chars = text.chars();
+
+    let mut job = LayoutJob::default();
+    job.wrap.max_width = f32::INFINITY;
+    for (i, row) in logical.rows.iter().enumerate() {
+        let last = i + 1 == logical.rows.len();
+        // an elided row ends with the … egui added, which isn't in the text
+        let elided = last && logical.elided;
+        let mut line: String = chars
+            .by_ref()
+            .take(row.char_count_excluding_newline() - usize::from(elided))
+            .collect();
+        if elided {
+            line.push('…');
+        }
+        let base = paragraphs.get(paragraph).copied().unwrap_or(Direction::Ltr);
+
+        let start = job.text.len();
+        job.text.push_str(&reorder(&line, base));
+        if row.ends_with_newline {
+            chars.next();
+            paragraph += 1;
+        }
+        if !last {
+            job.text.push('\n');
+        }
+
+        job.sections.push(LayoutSection {
+            leading_space: match base {
+                Direction::Rtl => (width - row.rect.width()).max(0.0),
+                Direction::Ltr => 0.0,
+            },
+            byte_range: start..job.text.len(),
+            format: TextFormat::simple(font_id.clone(), color),
+        });
+    }
+
+    fonts.layout_job(job)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shape_arabic() {
+        // salam: initial seen, medial lam-alef can't be, so final lam-alef,
+        // then an isolated meem
+        assert_eq!(shape_arabic("سلام"), "\u{FEB3}\u{FEFC}\u{FEE1}".to_owned());
+        // beh joins across a vowel mark
+        assert_eq!(shape_arabic("بَب"), "\u{FE91}\u{064E}\u{FE90}".to_owned());
+        assert!(matches!(shape_arabic("hello"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_clean_emoji() {
+        // woman technologist, and a heart with a variation selector
+        assert_eq!(clean_emoji("👩\u{200D}💻 ❤\u{FE0F}"), "👩💻 ❤");
+        // joiners between letters stay
+        assert_eq!(clean_emoji("ب\u{200D}"), "ب\u{200D}");
+    }
+
+    #[test]
+    fn test_reorder() {
+        assert_eq!(reorder("שלום", Direction::Rtl), "םולש");
+        // numbers and english stay left to right inside hebrew
+        assert_eq!(reorder("שלום 123 abc", Direction::Rtl), "abc 123 םולש");
+        // hebrew inside english only reverses itself
+        assert_eq!(reorder("say שלום (now)", Direction::Ltr), "say םולש (now)");
+        assert_eq!(reorder("(שלום)", Direction::Rtl), "(םולש)");
+        assert_eq!(direction("123 שלום abc"), Some(Direction::Rtl));
+    }
+}
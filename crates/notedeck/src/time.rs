@@ -1,10 +1,16 @@
+use crate::tr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn time_ago_since(timestamp: u64) -> String {
-    let now = SystemTime::now()
+/// Seconds since the unix epoch, or 0 if the clock is set before it
+pub fn unix_now() -> u64 {
+    SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_secs();
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn time_ago_since(timestamp: u64) -> String {
+    let now = unix_now();
 
     // Determine if the timestamp is in the future or the past
     let duration = if now >= timestamp {
@@ -18,38 +24,38 @@ pub fn time_ago_since(timestamp: u64) -> String {
 
     let years = duration / 31_536_000; // seconds in a year
     if years >= 1 {
-        return format!("{}{}yr", relstr, years);
+        return format!("{}{}", relstr, tr!("time-ago-years", count = years));
     }
 
     let months = duration / 2_592_000; // seconds in a month (30.44 days)
     if months >= 1 {
-        return format!("{}{}mth", relstr, months);
+        return format!("{}{}", relstr, tr!("time-ago-months", count = months));
     }
 
     let weeks = duration / 604_800; // seconds in a week
     if weeks >= 1 {
-        return format!("{}{}wk", relstr, weeks);
+        return format!("{}{}", relstr, tr!("time-ago-weeks", count = weeks));
     }
 
     let days = duration / 86_400; // seconds in a day
     if days >= 1 {
-        return format!("{}{}d", relstr, days);
+        return format!("{}{}", relstr, tr!("time-ago-days", count = days));
     }
 
     let hours = duration / 3600; // seconds in an hour
     if hours >= 1 {
-        return format!("{}{}h", relstr, hours);
+        return format!("{}{}", relstr, tr!("time-ago-hours", count = hours));
     }
 
     let minutes = duration / 60; // seconds in a minute
     if minutes >= 1 {
-        return format!("{}{}m", relstr, minutes);
+        return format!("{}{}", relstr, tr!("time-ago-minutes", count = minutes));
     }
 
     let seconds = duration;
     if seconds >= 3 {
-        return format!("{}{}s", relstr, seconds);
+        return format!("{}{}", relstr, tr!("time-ago-seconds", count = seconds));
     }
 
-    "now".to_string()
+    tr!("time-ago-now")
 }
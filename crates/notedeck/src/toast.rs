@@ -0,0 +1,173 @@
+//! Short lived messages in the corner of the window, for errors that the
+//! user should hear about rather than only being logged. Anything can
+//! raise one, no context is needed, and the chrome draws them.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use egui::{Align2, RichText};
+
+/// How long a toast stays up
+const TIMEOUT: Duration = Duration::from_secs(6);
+/// Older toasts are dropped past this many
+const MAX_TOASTS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub level: ToastLevel,
+    pub message: String,
+    /// How many times the same thing happened while it was up
+    pub count: usize,
+    raised_at: Instant,
+}
+
+#[derive(Default)]
+struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    /// The same message again just bumps the count and keeps it up,
+    /// so a relay failing every few seconds is one toast
+    fn push(&mut self, level: ToastLevel, message: String, now: Instant) {
+        if let Some(toast) = self
+            .toasts
+            .iter_mut()
+            .find(|t| t.level == level && t.message == message)
+        {
+            toast.count += 1;
+            toast.raised_at = now;
+            return;
+        }
+
+        self.toasts.push(Toast {
+            level,
+            message,
+            count: 1,
+            raised_at: now,
+        });
+
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        self.toasts
+            .retain(|t| now.duration_since(t.raised_at) < TIMEOUT);
+    }
+
+    /// When the next toast goes away
+    fn next_expiry(&self, now: Instant) -> Option<Duration> {
+        self.toasts
+            .iter()
+            .map(|t| TIMEOUT.saturating_sub(now.duration_since(t.raised_at)))
+            .min()
+    }
+}
+
+static TOASTS: Mutex<Toasts> = Mutex::new(Toasts { toasts: vec![] });
+
+fn push(level: ToastLevel, message: String) {
+    if let Ok(mut toasts) = TOASTS.lock() {
+        toasts.push(level, message, Instant::now());
+    }
+}
+
+pub fn info(message: impl Into<String>) {
+    push(ToastLevel::Info, message.into());
+}
+
+pub fn warning(message: impl Into<String>) {
+    push(ToastLevel::Warning, message.into());
+}
+
+pub fn error(message: impl Into<String>) {
+    push(ToastLevel::Error, message.into());
+}
+
+/// Draw the toasts that are up, and drop the ones that have timed out
+pub fn show(ctx: &egui::Context) {
+    let Ok(mut toasts) = TOASTS.lock() else {
+        return;
+    };
+
+    let now = Instant::now();
+    toasts.expire(now);
+    if toasts.toasts.is_empty() {
+        return;
+    }
+
+    if let Some(next) = toasts.next_expiry(now) {
+        ctx.request_repaint_after(next);
+    }
+
+    let mut closed = None;
+    egui::Area::new(egui::Id::new("toasts"))
+        .anchor(Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -16.0))
+        .order(egui::Order::Tooltip)
+        .show(ctx, |ui| {
+            ui.set_max_width(360.0);
+            for (i, toast) in toasts.toasts.iter().enumerate() {
+                let color = match toast.level {
+                    ToastLevel::Info => ui.visuals().text_color(),
+                    ToastLevel::Warning => ui.visuals().warn_fg_color,
+                    ToastLevel::Error => ui.visuals().error_fg_color,
+                };
+
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        let text = if toast.count > 1 {
+                            format!("{} (×{})", toast.message, toast.count)
+                        } else {
+                            toast.message.clone()
+                        };
+                        ui.add(egui::Label::new(RichText::new(text).color(color)).wrap());
+
+                        if ui.add(egui::Button::new("✕").frame(false)).clicked() {
+                            closed = Some(i);
+                        }
+                    });
+                });
+                ui.add_space(4.0);
+            }
+        });
+
+    if let Some(i) = closed {
+        toasts.toasts.remove(i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts() {
+        let mut toasts = Toasts::default();
+        let start = Instant::now();
+
+        toasts.push(ToastLevel::Error, "relay down".to_owned(), start);
+        toasts.push(ToastLevel::Error, "relay down".to_owned(), start);
+        assert_eq!(toasts.toasts.len(), 1);
+        assert_eq!(toasts.toasts[0].count, 2);
+
+        for i in 0..MAX_TOASTS {
+            toasts.push(ToastLevel::Info, format!("toast {i}"), start);
+        }
+        assert_eq!(toasts.toasts.len(), MAX_TOASTS);
+        assert_eq!(toasts.toasts[0].message, "toast 0");
+
+        toasts.push(ToastLevel::Info, "later".to_owned(), start + TIMEOUT / 2);
+        toasts.expire(start + TIMEOUT);
+        assert_eq!(toasts.toasts.len(), 1);
+        assert_eq!(toasts.toasts[0].message, "later");
+    }
+}
@@ -1,3 +1,13 @@
+mod mini_month;
+mod pickers;
+mod safe_text;
+mod spellcheck;
+
+pub use mini_month::MiniMonth;
+pub use pickers::{DatePicker, TimePicker};
+pub use safe_text::safe_markdown;
+pub use spellcheck::spellcheck;
+
 /// Determine if the screen is narrow. This is useful for detecting mobile
 /// contexts, but with the nuance that we may also have a wide android tablet.
 pub fn is_narrow(ctx: &egui::Context) -> bool {
@@ -22,3 +32,24 @@ pub fn is_compiled_as_mobile() -> bool {
         false
     }
 }
+
+/// A count for a badge, capped so it stays small
+pub fn badge_text(count: usize) -> String {
+    if count > 99 {
+        "99+".to_owned()
+    } else {
+        count.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_text() {
+        assert_eq!(badge_text(1), "1");
+        assert_eq!(badge_text(99), "99");
+        assert_eq!(badge_text(100), "99+");
+    }
+}
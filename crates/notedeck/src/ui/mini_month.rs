@@ -0,0 +1,359 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use chrono::{Datelike, Months, NaiveDate, Weekday};
+use egui::{Align2, FontId, Sense, Vec2};
+
+use crate::datetime;
+
+const CELL: Vec2 = Vec2::new(30.0, 30.0);
+const DOT_RADIUS: f32 = 1.5;
+
+/// A month at a glance for picking a day. Rows are labelled with their
+/// ISO week number, and days can show dots for how much happened on them.
+pub struct MiniMonth<'a> {
+    id: egui::Id,
+    selected: NaiveDate,
+    max: Option<NaiveDate>,
+    density: Option<&'a HashMap<NaiveDate, usize>>,
+    loading: Option<&'a HashSet<NaiveDate>>,
+}
+
+/// Which month is being looked at, and the selection it was last shown
+/// with. A new selection brings its own month into view.
+#[derive(Clone, Copy)]
+struct State {
+    month: NaiveDate,
+    selected: NaiveDate,
+}
+
+impl<'a> MiniMonth<'a> {
+    pub fn new(id_salt: impl Hash, selected: NaiveDate) -> Self {
+        MiniMonth {
+            id: egui::Id::new(("mini-month", id_salt)),
+            selected,
+            max: None,
+            density: None,
+            loading: None,
+        }
+    }
+
+    /// Days after this can't be picked
+    pub fn max_date(mut self, max: NaiveDate) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// How many things happened on each day of the month being shown
+    pub fn density(mut self, density: &'a HashMap<NaiveDate, usize>) -> Self {
+        self.density = Some(density);
+        self
+    }
+
+    /// Days that are still being worked out, drawn with a spinner
+    /// instead of their dots
+    pub fn loading(mut self, loading: &'a HashSet<NaiveDate>) -> Self {
+        self.loading = Some(loading);
+        self
+    }
+
+    /// The first of the month that's showing, so callers can work out
+    /// densities for it
+    pub fn shown_month(ctx: &egui::Context, id_salt: impl Hash, selected: NaiveDate) -> NaiveDate {
+        Self::new(id_salt, selected).state(ctx).month
+    }
+
+    fn state(&self, ctx: &egui::Context) -> State {
+        ctx.data(|d| d.get_temp::<State>(self.id))
+            .filter(|state| state.selected == self.selected)
+            .unwrap_or(State {
+                month: first_of_month(self.selected),
+                selected: self.selected,
+            })
+    }
+
+    /// Returns the day that was clicked
+    pub fn ui(self, ui: &mut egui::Ui) -> Option<NaiveDate> {
+        let mut state = self.state(ui.ctx());
+        let mut picked = None;
+
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(2.0, 2.0);
+
+            self.header_ui(ui, &mut state.month);
+
+            let first_weekday = datetime::settings().first_weekday();
+            ui.horizontal(|ui| {
+                cell_label(ui, &crate::tr!("calendar-week-short"));
+                for day in datetime::settings().weekdays() {
+                    cell_label(ui, weekday_letter(day));
+                }
+            });
+
+            for week in weeks(state.month, first_weekday) {
+                ui.horizontal(|ui| {
+                    cell_label(ui, &iso_week(&week).to_string());
+                    for day in week {
+                        if self.day_ui(ui, day, state.month.month()) {
+                            picked = Some(day);
+                        }
+                    }
+                });
+            }
+        });
+
+        if let Some(day) = picked {
+            state.selected = day;
+        }
+        ui.ctx().data_mut(|d| d.insert_temp(self.id, state));
+
+        picked
+    }
+
+    /// Previous and next month, and jumping straight to a month or year
+    fn header_ui(&self, ui: &mut egui::Ui, month: &mut NaiveDate) {
+        ui.horizontal(|ui| {
+            if ui.small_button("◀").clicked() {
+                *month = month.checked_sub_months(Months::new(1)).unwrap_or(*month);
+            }
+
+            // drag values rather than dropdowns, so this still works
+            // inside a popup
+            let mut month_number = month.month();
+            ui.add(
+                egui::DragValue::new(&mut month_number)
+                    .range(1..=12)
+                    .custom_formatter(|m, _| month_name(m as u32))
+                    .custom_parser(|text| parse_month(text).map(f64::from)),
+            );
+
+            let mut year = month.year();
+            ui.add(egui::DragValue::new(&mut year).range(1970..=9999));
+
+            *month = NaiveDate::from_ymd_opt(year, month_number, 1).unwrap_or(*month);
+
+            let next = month.checked_add_months(Months::new(1));
+            let can_go_on = next.is_some_and(|next| self.allowed(next));
+            if ui
+                .add_enabled(can_go_on, egui::Button::new("▶").small())
+                .clicked()
+            {
+                *month = next.unwrap_or(*month);
+            }
+        });
+    }
+
+    fn allowed(&self, day: NaiveDate) -> bool {
+        match self.max {
+            Some(max) => day <= max,
+            None => true,
+        }
+    }
+
+    /// Returns whether the day was clicked
+    fn day_ui(&self, ui: &mut egui::Ui, day: NaiveDate, month: u32) -> bool {
+        let enabled = self.allowed(day);
+        let sense = if enabled {
+            Sense::click()
+        } else {
+            Sense::hover()
+        };
+        let (rect, response) = ui.allocate_exact_size(CELL, sense);
+
+        let visuals = ui.visuals();
+        let selected = day == self.selected;
+        if selected {
+            ui.painter()
+                .rect_filled(rect, 4.0, visuals.selection.bg_fill);
+        } else if response.hovered() {
+            ui.painter()
+                .rect_filled(rect, 4.0, visuals.widgets.hovered.weak_bg_fill);
+        }
+        if day == datetime::today() {
+            ui.painter()
+                .rect_stroke(rect, 4.0, visuals.selection.stroke);
+        }
+
+        let color = if selected {
+            visuals.selection.stroke.color
+        } else if !enabled || day.month() != month {
+            visuals.weak_text_color()
+        } else {
+            visuals.text_color()
+        };
+        ui.painter().text(
+            rect.center() - egui::vec2(0.0, 3.0),
+            Align2::CENTER_CENTER,
+            day.day().to_string(),
+            FontId::proportional(12.0),
+            color,
+        );
+
+        if self.loading.is_some_and(|loading| loading.contains(&day)) {
+            let spinner = egui::Rect::from_center_size(
+                egui::pos2(rect.center().x, rect.bottom() - 5.0),
+                Vec2::splat(6.0),
+            );
+            egui::Spinner::new().color(color).paint_at(ui, spinner);
+            return response.clicked();
+        }
+
+        let count = self
+            .density
+            .and_then(|density| density.get(&day))
+            .copied()
+            .unwrap_or(0);
+        let dots = density_dots(count);
+        let spacing = DOT_RADIUS * 3.0;
+        let left = rect.center().x - spacing * (dots as f32 - 1.0) / 2.0;
+        for i in 0..dots {
+            ui.painter().circle_filled(
+                egui::pos2(left + spacing * i as f32, rect.bottom() - 5.0),
+                DOT_RADIUS,
+                color,
+            );
+        }
+
+        response.clicked()
+    }
+}
+
+fn cell_label(ui: &mut egui::Ui, text: &str) {
+    let (rect, _) = ui.allocate_exact_size(CELL, Sense::hover());
+    ui.painter().text(
+        rect.center(),
+        Align2::CENTER_CENTER,
+        text,
+        FontId::proportional(11.0),
+        ui.visuals().weak_text_color(),
+    );
+}
+
+fn weekday_letter(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "M",
+        Weekday::Tue | Weekday::Thu => "T",
+        Weekday::Wed => "W",
+        Weekday::Fri => "F",
+        Weekday::Sat | Weekday::Sun => "S",
+    }
+}
+
+fn month_name(month: u32) -> String {
+    chrono::Month::try_from(month as u8)
+        .map(|m| m.name().to_owned())
+        .unwrap_or_default()
+}
+
+/// A month number, or the start of its name
+fn parse_month(text: &str) -> Option<u32> {
+    let text = text.trim().to_lowercase();
+    if let Ok(number) = text.parse::<u32>() {
+        return (1..=12).contains(&number).then_some(number);
+    }
+    if text.is_empty() {
+        return None;
+    }
+    (1..=12).find(|m| month_name(*m).to_lowercase().starts_with(&text))
+}
+
+fn first_of_month(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).unwrap_or(day)
+}
+
+/// The weeks that cover a month, padded out with days from the months
+/// either side
+fn weeks(month: NaiveDate, first_weekday: Weekday) -> Vec<[NaiveDate; 7]> {
+    let first = first_of_month(month);
+    let lead =
+        (7 + first.weekday().num_days_from_monday() - first_weekday.num_days_from_monday()) % 7;
+    let mut start = first - chrono::Days::new(lead.into());
+
+    let mut weeks = vec![];
+    while start.month() == first.month() || start < first {
+        let mut day = start;
+        weeks.push(std::array::from_fn(|_| {
+            let current = day;
+            day = day.succ_opt().unwrap_or(day);
+            current
+        }));
+        start = day;
+    }
+    weeks
+}
+
+/// ISO weeks run Monday to Sunday, so a row that starts on another day
+/// goes by its Thursday, which always falls in the right week
+fn iso_week(week: &[NaiveDate; 7]) -> u32 {
+    week.iter()
+        .find(|day| day.weekday() == Weekday::Thu)
+        .unwrap_or(&week[0])
+        .iso_week()
+        .week()
+}
+
+/// A dot for a quiet day, up to three for a busy one
+fn density_dots(count: usize) -> usize {
+    match count {
+        0 => 0,
+        1..=9 => 1,
+        10..=49 => 2,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_weeks() {
+        // June 2024 starts on a Saturday and ends on a Sunday
+        let june = date(2024, 6, 15);
+
+        let monday = weeks(june, Weekday::Mon);
+        assert_eq!(monday.len(), 5);
+        assert_eq!(monday[0][0], date(2024, 5, 27));
+        assert_eq!(monday[4][6], date(2024, 6, 30));
+
+        let sunday = weeks(june, Weekday::Sun);
+        assert_eq!(sunday.len(), 6);
+        assert_eq!(sunday[0][0], date(2024, 5, 26));
+        assert_eq!(sunday[5][0], date(2024, 6, 30));
+    }
+
+    #[test]
+    fn test_iso_week() {
+        // 2020 had 53 ISO weeks, the last running into January 2021
+        let january = weeks(date(2021, 1, 1), Weekday::Mon);
+        assert_eq!(iso_week(&january[0]), 53);
+        assert_eq!(iso_week(&january[1]), 1);
+
+        // a Sunday first row still counts as the week of its Thursday
+        let sunday = weeks(date(2021, 1, 1), Weekday::Sun);
+        assert_eq!(iso_week(&sunday[0]), 53);
+        assert_eq!(iso_week(&sunday[1]), 1);
+    }
+
+    #[test]
+    fn test_parse_month() {
+        assert_eq!(parse_month("3"), Some(3));
+        assert_eq!(parse_month("sep"), Some(9));
+        assert_eq!(parse_month("December"), Some(12));
+        assert_eq!(parse_month("13"), None);
+        assert_eq!(parse_month("ju"), Some(6));
+        assert_eq!(parse_month(""), None);
+    }
+
+    #[test]
+    fn test_density_dots() {
+        assert_eq!(density_dots(0), 0);
+        assert_eq!(density_dots(3), 1);
+        assert_eq!(density_dots(25), 2);
+        assert_eq!(density_dots(400), 3);
+    }
+}
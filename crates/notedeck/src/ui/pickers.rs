@@ -0,0 +1,246 @@
+use std::hash::Hash;
+
+use chrono::{NaiveDate, NaiveTime, Timelike};
+use egui::{PopupCloseBehavior, TextEdit};
+
+use super::MiniMonth;
+use crate::datetime;
+
+/// How tall the hour and minute wheels are
+const WHEEL_HEIGHT: f32 = 180.0;
+
+/// A date that can be typed, or picked from a month that pops up from
+/// the button next to it. The response is marked changed when the date
+/// changes.
+pub struct DatePicker<'a> {
+    id: egui::Id,
+    date: &'a mut NaiveDate,
+    max: Option<NaiveDate>,
+}
+
+impl<'a> DatePicker<'a> {
+    pub fn new(id_salt: impl Hash, date: &'a mut NaiveDate) -> Self {
+        DatePicker {
+            id: egui::Id::new(("date-picker", id_salt)),
+            date,
+            max: None,
+        }
+    }
+
+    /// Days after this can't be picked
+    pub fn max_date(mut self, max: NaiveDate) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    fn allowed(&self, date: NaiveDate) -> bool {
+        match self.max {
+            Some(max) => date <= max,
+            None => true,
+        }
+    }
+}
+
+impl egui::Widget for DatePicker<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let before = *self.date;
+        let popup_id = self.id.with("popup");
+
+        let mut response = ui
+            .horizontal(|ui| {
+                let typed = fallback_text(
+                    ui,
+                    self.id.with("text"),
+                    datetime::format_date(*self.date),
+                    90.0,
+                    |text| datetime::parse_date(text).filter(|date| self.allowed(*date)),
+                );
+                if let Some(date) = typed {
+                    *self.date = date;
+                }
+
+                let button = ui.button("📅");
+                if button.clicked() {
+                    ui.memory_mut(|m| m.toggle_popup(popup_id));
+                }
+
+                egui::popup_below_widget(
+                    ui,
+                    popup_id,
+                    &button,
+                    PopupCloseBehavior::CloseOnClickOutside,
+                    |ui| {
+                        let mut month = MiniMonth::new(self.id, *self.date);
+                        if let Some(max) = self.max {
+                            month = month.max_date(max);
+                        }
+                        if let Some(date) = month.ui(ui) {
+                            *self.date = date;
+                            ui.memory_mut(|m| m.close_popup());
+                        }
+                    },
+                );
+            })
+            .response;
+
+        if *self.date != before {
+            response.mark_changed();
+        }
+        response
+    }
+}
+
+/// A time of day that can be typed, or picked from hour and minute
+/// wheels that scroll with a finger. Shown in the user's clock format.
+pub struct TimePicker<'a> {
+    id: egui::Id,
+    time: &'a mut NaiveTime,
+    minute_step: u32,
+}
+
+impl<'a> TimePicker<'a> {
+    pub fn new(id_salt: impl Hash, time: &'a mut NaiveTime) -> Self {
+        TimePicker {
+            id: egui::Id::new(("time-picker", id_salt)),
+            time,
+            minute_step: 5,
+        }
+    }
+
+    /// The minutes offered on the wheel. Any minute can still be typed.
+    pub fn minute_step(mut self, step: u32) -> Self {
+        self.minute_step = step.clamp(1, 30);
+        self
+    }
+}
+
+impl egui::Widget for TimePicker<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let before = *self.time;
+        let popup_id = self.id.with("popup");
+        let settings = datetime::settings();
+
+        let mut response = ui
+            .horizontal(|ui| {
+                let typed = fallback_text(
+                    ui,
+                    self.id.with("text"),
+                    settings.format_clock(*self.time),
+                    70.0,
+                    datetime::parse_time,
+                );
+                if let Some(time) = typed {
+                    *self.time = time;
+                }
+
+                let button = ui.button("🕒");
+                if button.clicked() {
+                    ui.memory_mut(|m| m.toggle_popup(popup_id));
+                }
+                if !ui.memory(|m| m.is_popup_open(popup_id)) {
+                    ui.data_mut(|d| {
+                        d.remove::<bool>(self.id.with("hours"));
+                        d.remove::<bool>(self.id.with("minutes"));
+                    });
+                }
+
+                egui::popup_below_widget(
+                    ui,
+                    popup_id,
+                    &button,
+                    PopupCloseBehavior::CloseOnClickOutside,
+                    |ui| {
+                        ui.horizontal(|ui| {
+                            let hour = self.time.hour();
+                            let picked = wheel(ui, self.id.with("hours"), 0..24, hour, |h| {
+                                settings.format_hour(h)
+                            });
+                            if let Some(h) = picked {
+                                *self.time = self.time.with_hour(h).unwrap_or(*self.time);
+                            }
+
+                            let minute = self.time.minute();
+                            let minutes = (0..60).step_by(self.minute_step as usize);
+                            let picked = wheel(ui, self.id.with("minutes"), minutes, minute, |m| {
+                                format!(":{m:02}")
+                            });
+                            if let Some(m) = picked {
+                                *self.time = self.time.with_minute(m).unwrap_or(*self.time);
+                            }
+                        });
+                    },
+                );
+            })
+            .response;
+
+        if *self.time != before {
+            response.mark_changed();
+        }
+        response
+    }
+}
+
+/// A scrolling column of values, returning the one that was tapped
+fn wheel(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    values: impl Iterator<Item = u32>,
+    selected: u32,
+    label: impl Fn(u32) -> String,
+) -> Option<u32> {
+    // start with the selected value in view, then leave scrolling to
+    // the user
+    let scrolled = ui.data(|d| d.get_temp::<bool>(id)).unwrap_or(false);
+    ui.data_mut(|d| d.insert_temp(id, true));
+
+    let mut picked = None;
+    egui::ScrollArea::vertical()
+        .id_salt(id)
+        .max_height(WHEEL_HEIGHT)
+        .show(ui, |ui| {
+            ui.vertical(|ui| {
+                for value in values {
+                    let response = ui.selectable_label(value == selected, label(value));
+                    if value == selected && !scrolled {
+                        response.scroll_to_me(Some(egui::Align::Center));
+                    }
+                    if response.clicked() {
+                        picked = Some(value);
+                    }
+                }
+            });
+        });
+    picked
+}
+
+/// A text field for typing a value in by hand. It shows the current value
+/// until it's focused, and hands back what was typed once it parses and
+/// the field is left.
+fn fallback_text<T>(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    current: String,
+    width: f32,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Option<T> {
+    let focused = ui.memory(|m| m.has_focus(id));
+    let mut text = if focused {
+        ui.data(|d| d.get_temp::<String>(id)).unwrap_or(current)
+    } else {
+        current
+    };
+
+    let mut edit = TextEdit::singleline(&mut text).id(id).desired_width(width);
+    if parse(&text).is_none() {
+        edit = edit.text_color(ui.visuals().error_fg_color);
+    }
+    let response = ui.add(edit);
+
+    let typed = if response.lost_focus() {
+        parse(&text)
+    } else {
+        None
+    };
+    ui.data_mut(|d| d.insert_temp(id, text));
+    typed
+}
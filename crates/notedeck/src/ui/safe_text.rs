@@ -0,0 +1,344 @@
+//! Text people write about themselves or their events, rendered as a
+//! small, safe subset of markdown: links, bold, italics and line breaks.
+//! Everything else shows up as the text it is, characters that can be
+//! used to disguise text (direction overrides, zero-width joiners) are
+//! dropped, and links always say where they really go.
+
+use std::sync::Arc;
+
+use egui::util::cache::{ComputerMut, FrameCache};
+use egui::{Hyperlink, RichText};
+use url::Url;
+
+/// Longest url we'll look for in `[label](url)`. Keeps a line full of
+/// unfinished links from being scanned to its end over and over.
+const MAX_URL: usize = 2048;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Span {
+    Text {
+        text: String,
+        bold: bool,
+        italic: bool,
+    },
+    /// Only http(s) links are kept, anything else is shown as its label
+    Link {
+        label: String,
+        url: String,
+    },
+    LineBreak,
+}
+
+/// Characters that change how the text around them looks without being
+/// seen themselves
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200b}'..='\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2060}'..='\u{2069}' | '\u{feff}'
+    ) || (c.is_control() && c != '\n' && c != '\t')
+}
+
+/// The link in `url`, if it's one we'll open
+fn web_link(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let web = matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some();
+    web.then(|| parsed.to_string())
+}
+
+struct Parser {
+    spans: Vec<Span>,
+    text: String,
+    bold: bool,
+    italic: bool,
+}
+
+impl Parser {
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            self.spans.push(Span::Text {
+                text: std::mem::take(&mut self.text),
+                bold: self.bold,
+                italic: self.italic,
+            });
+        }
+    }
+
+    fn link(&mut self, label: &str, url: &str) {
+        match web_link(url) {
+            Some(url) => {
+                self.flush();
+                self.spans.push(Span::Link {
+                    label: label.to_owned(),
+                    url,
+                });
+            }
+            None => self.text.push_str(label),
+        }
+    }
+
+    fn toggle_bold(&mut self) {
+        self.flush();
+        self.bold = !self.bold;
+    }
+
+    fn toggle_italic(&mut self) {
+        self.flush();
+        self.italic = !self.italic;
+    }
+
+    fn line(&mut self, line: &str) {
+        // headings are just bold, so they can't shout over the app
+        let heading = line.trim_start().starts_with('#');
+        let line = if heading {
+            line.trim_start().trim_start_matches('#').trim_start()
+        } else {
+            line
+        };
+        self.bold = heading;
+        self.italic = false;
+
+        let chars: Vec<(usize, char)> = line.char_indices().collect();
+        let next = |i: usize| chars.get(i + 1).map(|(_, c)| *c);
+        let mut i = 0;
+        while i < chars.len() {
+            let (at, c) = chars[i];
+
+            if c == '[' || c == 'h' {
+                let rest = &line[at..];
+                if let Some((label, url)) = markdown_link(rest) {
+                    self.link(label, url);
+                    i += rest[..label.len() + url.len() + 4].chars().count();
+                    continue;
+                }
+                if rest.starts_with("http://") || rest.starts_with("https://") {
+                    let url = bare_url(rest);
+                    self.link(url, url);
+                    i += url.chars().count();
+                    continue;
+                }
+            }
+
+            let word_before = i > 0 && chars[i - 1].1.is_alphanumeric();
+            let word_after = next(i).is_some_and(|c| c.is_alphanumeric());
+            match c {
+                '*' if next(i) == Some('*') => {
+                    self.toggle_bold();
+                    i += 2;
+                }
+                '_' if next(i) == Some('_') && !(word_before && !self.bold) => {
+                    self.toggle_bold();
+                    i += 2;
+                }
+                // a lone `*` between spaces is just a star
+                '*' if self.italic || !next(i).is_some_and(|c| c.is_whitespace()) => {
+                    self.toggle_italic();
+                    i += 1;
+                }
+                // snake_case isn't emphasis
+                '_' if !(word_before && word_after) => {
+                    self.toggle_italic();
+                    i += 1;
+                }
+                _ => {
+                    self.text.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        self.flush();
+    }
+}
+
+/// `[label](url)` at the start of `text`. Only looks as far as the link
+/// could go, so trying at every `[` of a line stays linear.
+fn markdown_link(text: &str) -> Option<(&str, &str)> {
+    let inner = text.strip_prefix('[')?;
+    let close = inner.find(['[', ']'])?;
+    let label = &inner[..close];
+    let after = inner[close..].strip_prefix("](")?;
+    let end = after
+        .char_indices()
+        .take(MAX_URL)
+        .find(|(_, c)| *c == ')' || c.is_whitespace())
+        .filter(|(_, c)| *c == ')')?
+        .0;
+    Some((label, &after[..end]))
+}
+
+/// A url written out in the text, without the punctuation after it
+fn bare_url(text: &str) -> &str {
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    text[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'', '"'])
+}
+
+pub fn parse(text: &str) -> Vec<Span> {
+    let clean: String = text.chars().filter(|c| !is_invisible(*c)).collect();
+    let mut parser = Parser {
+        spans: vec![],
+        text: String::new(),
+        bold: false,
+        italic: false,
+    };
+
+    let mut blank_lines = 0;
+    for (n, line) in clean.trim().lines().enumerate() {
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            // a paragraph break at most
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+
+        if n > 0 {
+            parser.spans.push(Span::LineBreak);
+        }
+        parser.line(line);
+    }
+
+    parser.spans
+}
+
+#[derive(Default)]
+struct SpanParser;
+
+impl ComputerMut<&str, Arc<Vec<Span>>> for SpanParser {
+    fn compute(&mut self, text: &str) -> Arc<Vec<Span>> {
+        Arc::new(parse(text))
+    }
+}
+
+/// Parsed text, kept while it's being shown
+type SpanCache = FrameCache<Arc<Vec<Span>>, SpanParser>;
+
+/// Show text from someone else as safe markdown
+pub fn safe_markdown(ui: &mut egui::Ui, text: &str) -> egui::Response {
+    let spans = ui
+        .ctx()
+        .memory_mut(|mem| mem.caches.cache::<SpanCache>().get(text));
+
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+
+        for span in spans.iter() {
+            match span {
+                Span::Text { text, bold, italic } => {
+                    let mut text = RichText::new(text);
+                    if *bold {
+                        text = text.strong();
+                    }
+                    if *italic {
+                        text = text.italics();
+                    }
+                    ui.label(text);
+                }
+                Span::Link { label, url } => {
+                    let shows_url = web_link(label).as_ref() == Some(url);
+                    ui.add(Hyperlink::from_label_and_url(label, url))
+                        .on_hover_text(url);
+                    if !shows_url {
+                        if let Some(host) = Url::parse(url)
+                            .ok()
+                            .and_then(|u| u.host_str().map(str::to_owned))
+                        {
+                            ui.weak(format!(" ({host})"));
+                        }
+                    }
+                }
+                Span::LineBreak => ui.end_row(),
+            }
+        }
+    })
+    .response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str, bold: bool, italic: bool) -> Span {
+        Span::Text {
+            text: text.to_owned(),
+            bold,
+            italic,
+        }
+    }
+
+    #[test]
+    fn test_emphasis() {
+        assert_eq!(
+            parse("**bold** and *italic* and snake_case_name"),
+            vec![
+                text("bold", true, false),
+                text(" and ", false, false),
+                text("italic", false, true),
+                text(" and snake_case_name", false, false),
+            ]
+        );
+
+        // styles end with the line
+        assert_eq!(
+            parse("# Big\nsmall"),
+            vec![
+                text("Big", true, false),
+                Span::LineBreak,
+                text("small", false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        assert_eq!(
+            parse("[my site](https://example.com) or https://example.org/x."),
+            vec![
+                Span::Link {
+                    label: "my site".to_owned(),
+                    url: "https://example.com/".to_owned(),
+                },
+                text(" or ", false, false),
+                Span::Link {
+                    label: "https://example.org/x".to_owned(),
+                    url: "https://example.org/x".to_owned(),
+                },
+                text(".", false, false),
+            ]
+        );
+
+        // only web links are kept
+        assert_eq!(
+            parse("[click](javascript:alert(1))"),
+            vec![text("click)", false, false)]
+        );
+    }
+
+    #[test]
+    fn test_long_lines_stay_fast() {
+        let start = std::time::Instant::now();
+        parse(&"h[a](".repeat(100_000));
+        parse(&"[".repeat(200_000));
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_hidden_characters() {
+        // a right-to-left override could make this read as something else
+        assert_eq!(
+            parse("safe\u{202e}gnp.exe\u{200b}"),
+            vec![text("safegnp.exe", false, false)]
+        );
+
+        assert_eq!(
+            parse("one\n\n\n\ntwo"),
+            vec![
+                text("one", false, false),
+                Span::LineBreak,
+                Span::LineBreak,
+                text("two", false, false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::ops::Range;
+
+use egui::text::CCursor;
+use egui::text_edit::TextEditOutput;
+use egui::{pos2, Color32, Pos2, Stroke};
+
+use crate::spell;
+use crate::tr;
+
+/// How many suggestions the right-click menu offers
+const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Clone)]
+struct Clicked {
+    word: Range<usize>,
+    suggestions: Vec<String>,
+}
+
+/// Underline misspelled and repeated words in a text edit that has just
+/// been shown, and offer corrections on right-click. Returns true if a
+/// correction was made to `text`.
+pub fn spellcheck(ui: &egui::Ui, output: &TextEditOutput, text: &mut String) -> bool {
+    let Some(dictionary) = spell::dictionary() else {
+        return false;
+    };
+
+    let misspelled = spell::misspelled(text, &dictionary);
+    let painter = ui.painter().with_clip_rect(output.text_clip_rect);
+    for word in &misspelled {
+        underline(&painter, output, word, ui.visuals().error_fg_color);
+    }
+    for word in spell::repeated(text) {
+        underline(&painter, output, &word, ui.visuals().warn_fg_color);
+    }
+
+    let response = &output.response;
+    let clicked_id = response.id.with("spellcheck");
+
+    if response.secondary_clicked() {
+        let clicked = response.interact_pointer_pos().and_then(|pos| {
+            let at = output
+                .galley
+                .cursor_from_pos(pos - output.galley_pos)
+                .ccursor
+                .index;
+            let word = misspelled
+                .iter()
+                .find(|word| word.start <= at && at <= word.end)?
+                .clone();
+            let chars: String = text.chars().skip(word.start).take(word.len()).collect();
+            Some(Clicked {
+                suggestions: dictionary.suggest(&chars, MAX_SUGGESTIONS),
+                word,
+            })
+        });
+
+        ui.ctx().data_mut(|d| match clicked {
+            Some(clicked) => d.insert_temp(clicked_id, clicked),
+            None => d.remove::<Clicked>(clicked_id),
+        });
+    }
+
+    let Some(clicked) = ui.ctx().data(|d| d.get_temp::<Clicked>(clicked_id)) else {
+        return false;
+    };
+
+    let mut replacement = None;
+    response.context_menu(|ui| {
+        if clicked.suggestions.is_empty() {
+            ui.weak(tr!("spellcheck-no-suggestions"));
+        }
+        for suggestion in &clicked.suggestions {
+            if ui.button(suggestion).clicked() {
+                replacement = Some(suggestion.clone());
+                ui.close_menu();
+            }
+        }
+
+        ui.separator();
+        if ui.button(tr!("spellcheck-learn")).clicked() {
+            let word: String = text
+                .chars()
+                .skip(clicked.word.start)
+                .take(clicked.word.len())
+                .collect();
+            spell::learn(&word);
+            ui.close_menu();
+        }
+    });
+
+    let Some(replacement) = replacement else {
+        return false;
+    };
+
+    let bytes = byte_range(text, &clicked.word);
+    text.replace_range(bytes, &replacement);
+    ui.ctx().data_mut(|d| d.remove::<Clicked>(clicked_id));
+    true
+}
+
+fn byte_range(text: &str, chars: &Range<usize>) -> Range<usize> {
+    let offset = |at: usize| text.char_indices().nth(at).map_or(text.len(), |(i, _)| i);
+    offset(chars.start)..offset(chars.end)
+}
+
+/// A squiggle under a word. Words that wrap onto another row only get
+/// the first row underlined.
+fn underline(
+    painter: &egui::Painter,
+    output: &TextEditOutput,
+    word: &Range<usize>,
+    color: Color32,
+) {
+    let galley = &output.galley;
+    let start = galley.pos_from_cursor(&galley.from_ccursor(CCursor::new(word.start)));
+    let end = galley.pos_from_cursor(&galley.from_ccursor(CCursor::new(word.end)));
+
+    let y = output.galley_pos.y + start.max.y;
+    let x0 = output.galley_pos.x + start.min.x;
+    let x1 = if (end.min.y - start.min.y).abs() < 1.0 {
+        output.galley_pos.x + end.min.x
+    } else {
+        output.galley_pos.x + galley.rect.max.x
+    };
+
+    painter.add(egui::Shape::line(
+        squiggle(x0, x1, y),
+        Stroke::new(1.0, color),
+    ));
+}
+
+fn squiggle(x0: f32, x1: f32, y: f32) -> Vec<Pos2> {
+    const STEP: f32 = 2.0;
+    const HEIGHT: f32 = 1.5;
+
+    let mut points = vec![];
+    let mut x = x0;
+    let mut up = true;
+    while x < x1 {
+        points.push(pos2(x, if up { y - HEIGHT } else { y }));
+        up = !up;
+        x += STEP;
+    }
+    points.push(pos2(x1, if up { y - HEIGHT } else { y }));
+    points
+}
@@ -260,6 +260,13 @@ pub fn get_unknown_note_ids<'a>(
         ids.insert(UnknownId::Pubkey(Pubkey::new(*note.pubkey())));
     }
 
+    // and the notes reposts are of
+    if let Some(reposted) = crate::repost::reposted_id(note) {
+        if ndb.get_note_by_id(txn, &reposted).is_err() {
+            ids.insert(UnknownId::Id(NoteId::new(reposted)));
+        }
+    }
+
     // pull notes that notes are replying to
     if cached_note.reply.root.is_some() {
         let note_reply = cached_note.reply.borrow(note.tags());
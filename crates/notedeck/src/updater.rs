@@ -0,0 +1,630 @@
+//! Finding out about new releases. We ask GitHub for the release list,
+//! pick the newest one on the user's channel, and on desktop download its
+//! installer into the cache. The installer is started after the window
+//! closes, so the app isn't running while it's replaced.
+
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+use crate::{DataPath, DataPathType};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/damus-io/notedeck/releases";
+const UPDATES_DIR: &str = "updates";
+/// Which download is waiting to be installed
+const STAGED_FILE: &str = "staged.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Channel {
+    #[default]
+    Stable,
+    /// Stable releases and prereleases, whichever is newer
+    Beta,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 2] = [Channel::Stable, Channel::Beta];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Beta => "beta",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|channel| channel.key() == key)
+            .unwrap_or_default()
+    }
+}
+
+/// A release version like `0.3.1` or `0.4.0-beta.2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    numbers: [u64; 3],
+    pre: Option<String>,
+}
+
+impl Version {
+    /// Tags may have a leading `v`. Missing minor and patch numbers are 0.
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches('v');
+        let (numbers, pre) = match version.split_once('-') {
+            Some((numbers, pre)) if !pre.is_empty() => (numbers, Some(pre.to_owned())),
+            Some(_) => return None,
+            None => (version, None),
+        };
+
+        let mut parsed = [0; 3];
+        let mut parts = numbers.split('.');
+        for (i, part) in parts.by_ref().take(3).enumerate() {
+            parsed[i] = part.parse().ok()?;
+        }
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Version {
+            numbers: parsed,
+            pre,
+        })
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some()
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [major, minor, patch] = self.numbers;
+        write!(f, "{major}.{minor}.{patch}")?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{pre}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.numbers
+            .cmp(&other.numbers)
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                // 1.0.0-beta comes before 1.0.0
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(a), Some(b)) => compare_pre(a, b),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Dot separated parts in order, numbers by value and before words, so
+/// `beta.2` < `beta.10` < `rc.1`
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asset {
+    pub name: String,
+    pub url: String,
+    pub size: u64,
+    /// Hex, when GitHub gave us one. Downloads without one aren't
+    /// installed.
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: Version,
+    pub name: String,
+    /// The changelog, in markdown
+    pub notes: String,
+    /// The release page, which also has the android APK
+    pub url: String,
+    pub assets: Vec<Asset>,
+}
+
+/// Releases in a GitHub release list response, leaving out drafts and
+/// tags that aren't versions
+pub fn parse_releases(bytes: &[u8]) -> Result<Vec<Release>, String> {
+    let value: serde_json::Value = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+    let list = value
+        .as_array()
+        .ok_or_else(|| "the release list isn't a list".to_owned())?;
+
+    let string = |value: &serde_json::Value, key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_owned()
+    };
+
+    Ok(list
+        .iter()
+        .filter(|release| {
+            !release
+                .get("draft")
+                .and_then(|d| d.as_bool())
+                .unwrap_or(false)
+        })
+        .filter_map(|release| {
+            let mut version = Version::parse(&string(release, "tag_name"))?;
+            // a release marked as a prerelease is one, whatever its tag says
+            let prerelease = release.get("prerelease").and_then(|p| p.as_bool());
+            if prerelease == Some(true) && version.pre.is_none() {
+                version.pre = Some("pre".to_owned());
+            }
+
+            let assets = release
+                .get("assets")
+                .and_then(|a| a.as_array())
+                .map(|assets| {
+                    assets
+                        .iter()
+                        .map(|asset| Asset {
+                            name: string(asset, "name"),
+                            url: string(asset, "browser_download_url"),
+                            size: asset.get("size").and_then(|s| s.as_u64()).unwrap_or(0),
+                            sha256: string(asset, "digest")
+                                .strip_prefix("sha256:")
+                                .map(str::to_owned),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            Some(Release {
+                version,
+                name: string(release, "name"),
+                notes: string(release, "body"),
+                url: string(release, "html_url"),
+                assets,
+            })
+        })
+        .collect())
+}
+
+/// The newest release on `channel`, if it's newer than `current`
+pub fn newest<'a>(
+    releases: &'a [Release],
+    channel: Channel,
+    current: &Version,
+) -> Option<&'a Release> {
+    releases
+        .iter()
+        .filter(|release| channel == Channel::Beta || !release.version.is_prerelease())
+        .filter(|release| release.version > *current)
+        .max_by(|a, b| a.version.cmp(&b.version))
+}
+
+/// Installer names we can open here, best first
+fn installer_kinds() -> &'static [&'static str] {
+    if cfg!(target_os = "macos") {
+        &[".dmg"]
+    } else if cfg!(target_os = "windows") {
+        &[".msi", ".exe"]
+    } else if Path::new("/usr/bin/dpkg").exists() {
+        &[".deb"]
+    } else if Path::new("/usr/bin/rpm").exists() {
+        &[".rpm"]
+    } else {
+        &[]
+    }
+}
+
+fn arch_names() -> &'static [&'static str] {
+    match std::env::consts::ARCH {
+        "x86_64" => &["x86_64", "amd64", "x64"],
+        "aarch64" => &["aarch64", "arm64"],
+        _ => &[],
+    }
+}
+
+/// The release's installer for this OS and architecture. An installer
+/// without an architecture in its name is taken if there's no better one.
+pub fn platform_asset(release: &Release) -> Option<&Asset> {
+    let mentions_arch = |name: &str, arches: &[&str]| arches.iter().any(|arch| name.contains(arch));
+    let all_arches = ["x86_64", "amd64", "x64", "aarch64", "arm64"];
+
+    installer_kinds().iter().find_map(|kind| {
+        let installers: Vec<&Asset> = release
+            .assets
+            .iter()
+            .filter(|asset| asset.name.to_lowercase().ends_with(kind))
+            .collect();
+        installers
+            .iter()
+            .find(|asset| mentions_arch(&asset.name.to_lowercase(), arch_names()))
+            .or_else(|| {
+                installers
+                    .iter()
+                    .find(|asset| !mentions_arch(&asset.name.to_lowercase(), &all_arches))
+            })
+            .copied()
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateState {
+    Idle,
+    Checking,
+    UpToDate,
+    Available(Release),
+    Downloading(Release),
+    /// Downloaded, and installed when the app closes
+    Staged(Release),
+    Failed(String),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Staged {
+    version: String,
+    /// In the updates directory
+    name: String,
+    sha256: String,
+}
+
+pub struct Updater {
+    dir: PathBuf,
+    current: Version,
+    state: Arc<Mutex<UpdateState>>,
+}
+
+impl Updater {
+    /// `current` is the version of the running app
+    pub fn new(path: &DataPath, current: &str) -> Self {
+        Updater {
+            dir: path.path(DataPathType::Cache).join(UPDATES_DIR),
+            current: Version::parse(current).unwrap_or(Version {
+                numbers: [0; 3],
+                pre: None,
+            }),
+            state: Arc::new(Mutex::new(UpdateState::Idle)),
+        }
+    }
+
+    pub fn current(&self) -> &Version {
+        &self.current
+    }
+
+    pub fn state(&self) -> UpdateState {
+        self.state
+            .lock()
+            .map(|state| state.clone())
+            .unwrap_or(UpdateState::Idle)
+    }
+
+    fn set_state(state: &Mutex<UpdateState>, new_state: UpdateState) {
+        if let Ok(mut state) = state.lock() {
+            *state = new_state;
+        }
+    }
+
+    /// Whether we can download and install here rather than sending the
+    /// user to the release page
+    pub fn can_install(release: &Release) -> bool {
+        !cfg!(target_os = "android")
+            && platform_asset(release).is_some_and(|asset| asset.sha256.is_some())
+    }
+
+    /// Ask for the release list, unless we're busy with an update already
+    pub fn check(&self, ctx: &egui::Context, channel: Channel) {
+        if matches!(
+            self.state(),
+            UpdateState::Checking | UpdateState::Downloading(_) | UpdateState::Staged(_)
+        ) {
+            return;
+        }
+        Self::set_state(&self.state, UpdateState::Checking);
+
+        let mut request = ehttp::Request::get(RELEASES_URL);
+        // GitHub turns away requests without one
+        request
+            .headers
+            .insert("User-Agent".to_owned(), "notedeck".to_owned());
+
+        let state = self.state.clone();
+        let current = self.current.clone();
+        let ctx = ctx.clone();
+        ehttp::fetch(request, move |response| {
+            let releases = response.and_then(|response| {
+                if !response.ok {
+                    return Err(format!("{} {}", response.status, response.status_text));
+                }
+                parse_releases(&response.bytes)
+            });
+
+            let new_state = match releases {
+                Ok(releases) => match newest(&releases, channel, &current) {
+                    Some(release) => {
+                        info!("notedeck {} is available", release.version);
+                        UpdateState::Available(release.clone())
+                    }
+                    None => UpdateState::UpToDate,
+                },
+                Err(err) => {
+                    error!("couldn't check for updates: {err}");
+                    UpdateState::Failed(err)
+                }
+            };
+            Self::set_state(&state, new_state);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Download the release's installer and keep it for [`install_staged`]
+    pub fn download(&self, ctx: &egui::Context, release: Release) {
+        let Some(asset) = platform_asset(&release).cloned() else {
+            return;
+        };
+        Self::set_state(&self.state, UpdateState::Downloading(release.clone()));
+
+        let state = self.state.clone();
+        let dir = self.dir.clone();
+        let ctx = ctx.clone();
+        ehttp::fetch(ehttp::Request::get(&asset.url), move |response| {
+            let staged = response.and_then(|response| {
+                if !response.ok {
+                    return Err(format!("{} {}", response.status, response.status_text));
+                }
+                stage(&dir, &release.version, &asset, &response.bytes)
+            });
+
+            let new_state = match staged {
+                Ok(()) => {
+                    info!("notedeck {} will be installed on restart", release.version);
+                    UpdateState::Staged(release)
+                }
+                Err(err) => {
+                    error!("couldn't download the update: {err}");
+                    UpdateState::Failed(err)
+                }
+            };
+            Self::set_state(&state, new_state);
+            ctx.request_repaint();
+        });
+    }
+}
+
+fn stage(dir: &Path, version: &Version, asset: &Asset, bytes: &[u8]) -> Result<(), String> {
+    if asset.size != 0 && bytes.len() as u64 != asset.size {
+        return Err(format!(
+            "got {} bytes of {}, expected {}",
+            bytes.len(),
+            asset.name,
+            asset.size
+        ));
+    }
+    let Some(expected) = &asset.sha256 else {
+        return Err(format!("{} has no checksum", asset.name));
+    };
+    if !matches_sha256(bytes, expected) {
+        return Err(format!("{} doesn't match its checksum", asset.name));
+    }
+
+    // only the newest download is kept
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    // the asset name comes from the network, so only its last part is used
+    let name = file_name(&asset.name).ok_or_else(|| format!("bad asset name {}", asset.name))?;
+    std::fs::write(dir.join(&name), bytes).map_err(|e| e.to_string())?;
+
+    let staged = Staged {
+        version: version.to_string(),
+        name,
+        sha256: expected.to_owned(),
+    };
+    let json = serde_json::to_string(&staged).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(STAGED_FILE), json).map_err(|e| e.to_string())
+}
+
+fn matches_sha256(bytes: &[u8], expected: &str) -> bool {
+    hex::encode(Sha256::digest(bytes)).eq_ignore_ascii_case(expected)
+}
+
+/// Only the last part of a name from the network or a file we wrote, so
+/// it can't point outside the updates directory
+fn file_name(name: &str) -> Option<String> {
+    Path::new(name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_owned)
+}
+
+/// Start the installer that was downloaded, if there is one for a version
+/// newer than `current`. Called once the window has closed. Downloads
+/// for versions we already run are cleaned up instead.
+pub fn install_staged(path: &DataPath, current: &str) {
+    let dir = path.path(DataPathType::Cache).join(UPDATES_DIR);
+    let Some((version, file)) = staged_installer(&dir, current) else {
+        let _ = std::fs::remove_dir_all(&dir);
+        return;
+    };
+
+    info!("starting the installer for notedeck {version}");
+    if let Err(err) = open_installer(&file) {
+        error!("couldn't start the installer: {err}");
+    }
+}
+
+/// The staged installer if it's for a newer version and still what we
+/// downloaded. It's checked again since the cache could have been changed
+/// since.
+fn staged_installer(dir: &Path, current: &str) -> Option<(String, PathBuf)> {
+    let json = std::fs::read_to_string(dir.join(STAGED_FILE)).ok()?;
+    let _ = std::fs::remove_file(dir.join(STAGED_FILE));
+    let staged: Staged = serde_json::from_str(&json).ok()?;
+
+    let newer = match (Version::parse(&staged.version), Version::parse(current)) {
+        (Some(staged), Some(current)) => staged > current,
+        _ => false,
+    };
+    if !newer {
+        return None;
+    }
+
+    let file = dir.join(file_name(&staged.name)?);
+    let bytes = std::fs::read(&file).ok()?;
+    if !matches_sha256(&bytes, &staged.sha256) {
+        error!("the downloaded installer changed since it was checked");
+        return None;
+    }
+
+    Some((staged.version, file))
+}
+
+fn open_installer(file: &Path) -> std::io::Result<()> {
+    use std::process::Command;
+
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg(file);
+        command
+    } else if cfg!(target_os = "windows") {
+        if file.extension().is_some_and(|ext| ext == "msi") {
+            let mut command = Command::new("msiexec");
+            command.arg("/i").arg(file);
+            command
+        } else {
+            Command::new(file)
+        }
+    } else {
+        // the desktop's package installer
+        let mut command = Command::new("xdg-open");
+        command.arg(file);
+        command
+    };
+
+    command.spawn().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(v: &str) -> Version {
+        Version::parse(v).unwrap()
+    }
+
+    #[test]
+    fn test_version_order() {
+        assert!(version("v0.3.0") > version("0.2.9"));
+        assert!(version("0.3.0") > version("0.3.0-beta.1"));
+        assert!(version("0.3.0-beta.10") > version("0.3.0-beta.2"));
+        assert!(version("0.3.0-rc.1") > version("0.3.0-beta.3"));
+        assert_eq!(version("1.2"), version("1.2.0"));
+        assert_eq!(version("0.4.0-beta.1").to_string(), "0.4.0-beta.1");
+        assert_eq!(Version::parse("nightly"), None);
+        assert_eq!(Version::parse("1.2.3.4"), None);
+    }
+
+    #[test]
+    fn test_newest_by_channel() {
+        let releases = parse_releases(
+            br#"[
+                {"tag_name": "v0.4.0-beta.1", "prerelease": true, "draft": false,
+                 "name": "Beta", "body": "new things", "html_url": "https://example.com/b",
+                 "assets": [{"name": "notedeck-0.4.0-x86_64.dmg", "size": 3,
+                             "browser_download_url": "https://example.com/b.dmg",
+                             "digest": "sha256:abc"}]},
+                {"tag_name": "v0.5.0", "draft": true, "assets": []},
+                {"tag_name": "v0.3.1", "prerelease": false, "draft": false,
+                 "name": "Stable", "body": "fixes", "html_url": "https://example.com/s",
+                 "assets": []},
+                {"tag_name": "nightly", "prerelease": true, "draft": false, "assets": []}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0].assets[0].sha256.as_deref(), Some("abc"));
+
+        let current = version("0.3.0");
+        let stable = newest(&releases, Channel::Stable, &current).unwrap();
+        assert_eq!(stable.version, version("0.3.1"));
+        let beta = newest(&releases, Channel::Beta, &current).unwrap();
+        assert_eq!(beta.version, version("0.4.0-beta.1"));
+
+        assert_eq!(newest(&releases, Channel::Stable, &version("0.3.1")), None);
+    }
+
+    #[test]
+    fn test_stage_checks_download() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join(UPDATES_DIR);
+        let bytes = b"installer";
+        let mut asset = Asset {
+            name: "../notedeck.deb".to_owned(),
+            url: String::new(),
+            size: bytes.len() as u64,
+            sha256: Some(hex::encode(Sha256::digest(bytes))),
+        };
+
+        stage(&dir, &version("0.4.0"), &asset, bytes).unwrap();
+        assert!(dir.join("notedeck.deb").exists());
+        assert!(dir.join(STAGED_FILE).exists());
+
+        asset.sha256 = Some("00".repeat(32));
+        assert!(stage(&dir, &version("0.4.0"), &asset, bytes).is_err());
+        asset.sha256 = None;
+        assert!(stage(&dir, &version("0.4.0"), &asset, bytes).is_err());
+        assert!(stage(&dir, &version("0.4.0"), &asset, b"short").is_err());
+    }
+
+    #[test]
+    fn test_staged_installer_checked_again() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join(UPDATES_DIR);
+        let bytes = b"installer";
+        let asset = Asset {
+            name: "notedeck.deb".to_owned(),
+            url: String::new(),
+            size: bytes.len() as u64,
+            sha256: Some(hex::encode(Sha256::digest(bytes))),
+        };
+
+        stage(&dir, &version("0.4.0"), &asset, bytes).unwrap();
+        let (staged, file) = staged_installer(&dir, "0.3.0").unwrap();
+        assert_eq!(staged, "0.4.0");
+        assert_eq!(file, dir.join("notedeck.deb"));
+
+        stage(&dir, &version("0.4.0"), &asset, bytes).unwrap();
+        assert!(staged_installer(&dir, "0.4.0").is_none());
+
+        stage(&dir, &version("0.4.0"), &asset, bytes).unwrap();
+        std::fs::write(dir.join("notedeck.deb"), b"tampered").unwrap();
+        assert!(staged_installer(&dir, "0.3.0").is_none());
+    }
+}
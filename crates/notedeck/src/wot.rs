@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use nostrdb::{Filter, Ndb, Transaction};
+use tracing::{debug, error};
+
+use crate::contacts::{followed_pubkeys, CONTACTS_KIND};
+
+/// Contact lists change slowly, so don't rebuild more often than this
+const REBUILD_INTERVAL: Duration = Duration::from_secs(600);
+/// Until the contact lists show up from relays
+const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+/// Contact lists to read per hop, so three hops out doesn't read the
+/// whole database
+const MAX_LAYER: usize = 10_000;
+/// Most contact lists to read when looking for followers of follows
+const FOLLOWERS_LIMIT: i32 = 5_000;
+
+/// How far out trust reaches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WotParams {
+    /// Hops from the account, 1 is only who it follows
+    pub depth: u8,
+    /// Also trust anyone who follows someone the account follows
+    pub followers_of_follows: bool,
+    /// How many accounts in the previous hop must follow someone for
+    /// them to count, 0 or 1 for anyone
+    pub min_follows: u32,
+}
+
+impl Default for WotParams {
+    fn default() -> Self {
+        WotParams {
+            depth: 2,
+            followers_of_follows: false,
+            min_follows: 0,
+        }
+    }
+}
+
+/// Everyone an account follows, and everyone they follow, out to
+/// [`WotParams::depth`] hops. Open feeds like hashtags use it to hide
+/// posts from accounts nobody nearby vouches for. Cheap to clone.
+#[derive(Debug, Default, Clone)]
+pub struct WebOfTrust {
+    root: Option<[u8; 32]>,
+    params: WotParams,
+    /// Everyone trusted, with how many hops out they are
+    trusted: Arc<HashMap<[u8; 32], u8>>,
+    built_at: Option<Instant>,
+    /// Goes up with each rebuild, for caching anything derived from it
+    generation: u64,
+}
+
+impl WebOfTrust {
+    /// Rebuild from nostrdb when the account or params change, or the
+    /// last build is old
+    pub fn update(&mut self, ndb: &Ndb, root: Option<&[u8; 32]>, params: WotParams) {
+        let interval = if self.trusted.is_empty() {
+            RETRY_INTERVAL
+        } else {
+            REBUILD_INTERVAL
+        };
+        let fresh = self
+            .built_at
+            .is_some_and(|built_at| built_at.elapsed() <= interval);
+        if self.root.as_ref() == root && self.params == params && fresh {
+            return;
+        }
+
+        self.root = root.copied();
+        self.params = params;
+        self.built_at = Some(Instant::now());
+        self.generation += 1;
+        self.trusted = Arc::new(match root {
+            Some(root) => build(ndb, root, &params),
+            None => HashMap::new(),
+        });
+        debug!(
+            "web of trust has {} accounts with {:?}",
+            self.trusted.len(),
+            params
+        );
+    }
+
+    /// Without a contact list we have nothing to go on, so everyone is
+    /// trusted
+    pub fn is_trusted(&self, pubkey: &[u8; 32]) -> bool {
+        self.trusted.is_empty() || self.trusted.contains_key(pubkey)
+    }
+
+    /// How many hops out from the account someone is: 0 for the account
+    /// itself, 1 for who it follows. `None` if they aren't trusted, or
+    /// there's no contact list yet.
+    pub fn hops(&self, pubkey: &[u8; 32]) -> Option<u8> {
+        self.trusted.get(pubkey).copied()
+    }
+
+    /// Everyone trusted, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &[u8; 32]> {
+        self.trusted.keys()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn len(&self) -> usize {
+        self.trusted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trusted.is_empty()
+    }
+}
+
+fn build(ndb: &Ndb, root: &[u8; 32], params: &WotParams) -> HashMap<[u8; 32], u8> {
+    let Ok(txn) = Transaction::new(ndb) else {
+        return HashMap::new();
+    };
+
+    let follows: Vec<[u8; 32]> = follow_counts(ndb, &txn, &[*root]).into_keys().collect();
+    if follows.is_empty() {
+        return HashMap::new();
+    }
+
+    let mut trusted: HashMap<[u8; 32], u8> = follows.iter().map(|pk| (*pk, 1)).collect();
+    trusted.insert(*root, 0);
+
+    let mut layer = follows.clone();
+    for hop in 2..=params.depth {
+        layer.truncate(MAX_LAYER);
+        layer = next_layer(
+            follow_counts(ndb, &txn, &layer),
+            &trusted,
+            params.min_follows,
+        );
+        if layer.is_empty() {
+            break;
+        }
+        trusted.extend(layer.iter().map(|pk| (*pk, hop)));
+    }
+
+    if params.followers_of_follows {
+        for follower in followers(ndb, &txn, &follows) {
+            trusted.entry(follower).or_insert(2);
+        }
+    }
+
+    trusted
+}
+
+/// Who made it into the next hop, given how many in this hop follow them
+fn next_layer(
+    counts: HashMap<[u8; 32], u32>,
+    trusted: &HashMap<[u8; 32], u8>,
+    min_follows: u32,
+) -> Vec<[u8; 32]> {
+    counts
+        .into_iter()
+        .filter(|(pubkey, count)| *count >= min_follows && !trusted.contains_key(pubkey))
+        .map(|(pubkey, _)| pubkey)
+        .collect()
+}
+
+/// Everyone followed by any of `authors`, with how many of them follow
+/// each, from the contact lists we have
+fn follow_counts(ndb: &Ndb, txn: &Transaction, authors: &[[u8; 32]]) -> HashMap<[u8; 32], u32> {
+    let filter = Filter::new()
+        .authors(authors.iter())
+        .kinds([CONTACTS_KIND as u64])
+        .limit(authors.len() as u64)
+        .build();
+
+    let results = match ndb.query(txn, &[filter], authors.len() as i32) {
+        Ok(results) => results,
+        Err(err) => {
+            error!("web of trust contact list query failed: {err}");
+            return HashMap::new();
+        }
+    };
+
+    let mut counts = HashMap::new();
+    for pubkey in results
+        .iter()
+        .flat_map(|result| followed_pubkeys(&result.note))
+    {
+        *counts.entry(pubkey).or_default() += 1;
+    }
+    counts
+}
+
+/// Authors of the contact lists we have that follow any of `follows`
+fn followers(ndb: &Ndb, txn: &Transaction, follows: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    let filter = Filter::new()
+        .kinds([CONTACTS_KIND as u64])
+        .pubkeys(follows.iter())
+        .limit(FOLLOWERS_LIMIT as u64)
+        .build();
+
+    match ndb.query(txn, &[filter], FOLLOWERS_LIMIT) {
+        Ok(results) => results.iter().map(|result| *result.note.pubkey()).collect(),
+        Err(err) => {
+            error!("web of trust followers query failed: {err}");
+            vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_layer() {
+        let trusted: HashMap<[u8; 32], u8> = [([1; 32], 1)].into_iter().collect();
+        let counts: HashMap<[u8; 32], u32> = [([1; 32], 5), ([2; 32], 1), ([3; 32], 3)]
+            .into_iter()
+            .collect();
+
+        let mut anyone = next_layer(counts.clone(), &trusted, 0);
+        anyone.sort();
+        assert_eq!(anyone, vec![[2; 32], [3; 32]]);
+
+        assert_eq!(next_layer(counts, &trusted, 2), vec![[3; 32]]);
+    }
+}
@@ -44,7 +44,8 @@ pub async fn android_main(app: AndroidApp) {
         builder.with_android_app(app_clone_for_event_loop);
     }));
 
-    let app_args = get_app_args(app);
+    let mut app_args = get_app_args(app);
+    app_args.extend(launch_link());
 
     let _res = eframe::run_native(
         "Damus Notedeck",
@@ -58,9 +59,38 @@ pub async fn android_main(app: AndroidApp) {
     );
 }
 
+use jni::objects::JString;
 use serde_json::Value;
 use std::fs;
 use std::path::PathBuf;
+use tracing::error;
+
+/// The link we were opened with, like a nostr+walletconnect link tapped
+/// in another app. The apps see it as one more argument.
+fn launch_link() -> Option<String> {
+    let mut link = None;
+    let read = crate::background::with_activity(|env, activity| {
+        let intent = env
+            .call_method(activity, "getIntent", "()Landroid/content/Intent;", &[])?
+            .l()?;
+        if intent.is_null() {
+            return Ok(());
+        }
+        let data = JString::from(
+            env.call_method(&intent, "getDataString", "()Ljava/lang/String;", &[])?
+                .l()?,
+        );
+        if !data.is_null() {
+            link = Some(env.get_string(&data)?.into());
+        }
+        Ok(())
+    });
+
+    if let Err(err) = read {
+        error!("couldn't read the link we were opened with: {err}");
+    }
+    link
+}
 
 /*
 Read args from a config file:
@@ -0,0 +1,242 @@
+//! The key password on android. The Keystore won't hand out secrets, so
+//! it holds an AES key that never leaves it, and we keep the password
+//! encrypted with that key in app storage.
+
+use jni::objects::{JByteArray, JObject, JValue};
+use jni::JNIEnv;
+use notedeck::{storage::Schema, AppStorage, DataPath, DataPathType, Error, Result, SecretStore};
+use serde::{Deserialize, Serialize};
+
+use crate::background::with_activity;
+
+const KEY_ALIAS: &str = "notedeck-key-password";
+/// Where the wrapped password lived before it moved into app storage
+const WRAPPED_FILE: &str = "wrapped_key_password";
+const STORAGE_ID: &str = "android-keystore";
+const WRAPPED_KEY: &str = "wrapped-key-password";
+static WRAPPED_SCHEMA: Schema = Schema::INITIAL;
+const TRANSFORMATION: &str = "AES/GCM/NoPadding";
+const GCM_TAG_BITS: i32 = 128;
+
+const ENCRYPT_MODE: i32 = 1;
+const DECRYPT_MODE: i32 = 2;
+/// KeyProperties.PURPOSE_ENCRYPT | PURPOSE_DECRYPT
+const PURPOSES: i32 = 1 | 2;
+
+#[derive(Serialize, Deserialize)]
+struct Wrapped {
+    iv: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+pub struct AndroidKeystore {
+    storage: AppStorage,
+}
+
+impl AndroidKeystore {
+    pub fn new(path: &DataPath) -> Self {
+        let storage = AppStorage::new(path, STORAGE_ID);
+        storage.adopt(WRAPPED_KEY, &path.path(DataPathType::Setting), WRAPPED_FILE);
+        AndroidKeystore { storage }
+    }
+}
+
+fn jni_error(err: jni::errors::Error) -> Error {
+    Error::Generic(format!("android keystore: {err}"))
+}
+
+/// Run `f` in java, clearing any exception it threw so later calls work
+fn in_java<T>(f: impl FnOnce(&mut JNIEnv) -> jni::errors::Result<T>) -> Result<T> {
+    let mut out = None;
+    with_activity(|env, _activity| {
+        let result = f(env);
+        if result.is_err() && env.exception_check().unwrap_or(false) {
+            env.exception_clear()?;
+        }
+        out = Some(result?);
+        Ok(())
+    })
+    .map_err(jni_error)?;
+
+    out.ok_or_else(|| Error::Generic("android keystore: no result".to_owned()))
+}
+
+fn keystore<'local>(env: &mut JNIEnv<'local>) -> jni::errors::Result<JObject<'local>> {
+    let kind = env.new_string("AndroidKeyStore")?;
+    let keystore = env
+        .call_static_method(
+            "java/security/KeyStore",
+            "getInstance",
+            "(Ljava/lang/String;)Ljava/security/KeyStore;",
+            &[JValue::Object(&kind)],
+        )?
+        .l()?;
+    env.call_method(
+        &keystore,
+        "load",
+        "(Ljava/security/KeyStore$LoadStoreParameter;)V",
+        &[JValue::Object(&JObject::null())],
+    )?;
+    Ok(keystore)
+}
+
+/// Our AES key, made the first time it's needed
+fn secret_key<'local>(env: &mut JNIEnv<'local>) -> jni::errors::Result<JObject<'local>> {
+    let keystore = keystore(env)?;
+    let alias = env.new_string(KEY_ALIAS)?;
+    let key = env
+        .call_method(
+            &keystore,
+            "getKey",
+            "(Ljava/lang/String;[C)Ljava/security/Key;",
+            &[JValue::Object(&alias), JValue::Object(&JObject::null())],
+        )?
+        .l()?;
+    if !key.is_null() {
+        return Ok(key);
+    }
+
+    let builder = env.new_object(
+        "android/security/keystore/KeyGenParameterSpec$Builder",
+        "(Ljava/lang/String;I)V",
+        &[JValue::Object(&alias), JValue::Int(PURPOSES)],
+    )?;
+    let gcm = env.new_string("GCM")?;
+    let block_modes = env.new_object_array(1, "java/lang/String", &gcm)?;
+    env.call_method(
+        &builder,
+        "setBlockModes",
+        "([Ljava/lang/String;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        &[JValue::Object(&block_modes)],
+    )?;
+    let no_padding = env.new_string("NoPadding")?;
+    let paddings = env.new_object_array(1, "java/lang/String", &no_padding)?;
+    env.call_method(
+        &builder,
+        "setEncryptionPaddings",
+        "([Ljava/lang/String;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        &[JValue::Object(&paddings)],
+    )?;
+    let spec = env
+        .call_method(
+            &builder,
+            "build",
+            "()Landroid/security/keystore/KeyGenParameterSpec;",
+            &[],
+        )?
+        .l()?;
+
+    let aes = env.new_string("AES")?;
+    let provider = env.new_string("AndroidKeyStore")?;
+    let generator = env
+        .call_static_method(
+            "javax/crypto/KeyGenerator",
+            "getInstance",
+            "(Ljava/lang/String;Ljava/lang/String;)Ljavax/crypto/KeyGenerator;",
+            &[JValue::Object(&aes), JValue::Object(&provider)],
+        )?
+        .l()?;
+    env.call_method(
+        &generator,
+        "init",
+        "(Ljava/security/spec/AlgorithmParameterSpec;)V",
+        &[JValue::Object(&spec)],
+    )?;
+    env.call_method(&generator, "generateKey", "()Ljavax/crypto/SecretKey;", &[])?
+        .l()
+}
+
+fn cipher<'local>(env: &mut JNIEnv<'local>) -> jni::errors::Result<JObject<'local>> {
+    let transformation = env.new_string(TRANSFORMATION)?;
+    env.call_static_method(
+        "javax/crypto/Cipher",
+        "getInstance",
+        "(Ljava/lang/String;)Ljavax/crypto/Cipher;",
+        &[JValue::Object(&transformation)],
+    )?
+    .l()
+}
+
+fn do_final(env: &mut JNIEnv, cipher: &JObject, input: &[u8]) -> jni::errors::Result<Vec<u8>> {
+    let input = env.byte_array_from_slice(input)?;
+    let output = JByteArray::from(
+        env.call_method(cipher, "doFinal", "([B)[B", &[JValue::Object(&input)])?
+            .l()?,
+    );
+    env.convert_byte_array(&output)
+}
+
+fn encrypt(env: &mut JNIEnv, plaintext: &[u8]) -> jni::errors::Result<Wrapped> {
+    let key = secret_key(env)?;
+    let cipher = cipher(env)?;
+    env.call_method(
+        &cipher,
+        "init",
+        "(ILjava/security/Key;)V",
+        &[JValue::Int(ENCRYPT_MODE), JValue::Object(&key)],
+    )?;
+    // the keystore picks the iv
+    let iv = JByteArray::from(env.call_method(&cipher, "getIV", "()[B", &[])?.l()?);
+    let iv = env.convert_byte_array(&iv)?;
+    let ciphertext = do_final(env, &cipher, plaintext)?;
+    Ok(Wrapped { iv, ciphertext })
+}
+
+fn decrypt(env: &mut JNIEnv, wrapped: &Wrapped) -> jni::errors::Result<Vec<u8>> {
+    let key = secret_key(env)?;
+    let cipher = cipher(env)?;
+    let iv = env.byte_array_from_slice(&wrapped.iv)?;
+    let spec = env.new_object(
+        "javax/crypto/spec/GCMParameterSpec",
+        "(I[B)V",
+        &[JValue::Int(GCM_TAG_BITS), JValue::Object(&iv)],
+    )?;
+    env.call_method(
+        &cipher,
+        "init",
+        "(ILjava/security/Key;Ljava/security/spec/AlgorithmParameterSpec;)V",
+        &[
+            JValue::Int(DECRYPT_MODE),
+            JValue::Object(&key),
+            JValue::Object(&spec),
+        ],
+    )?;
+    do_final(env, &cipher, &wrapped.ciphertext)
+}
+
+impl SecretStore for AndroidKeystore {
+    fn name(&self) -> String {
+        "Android Keystore".to_owned()
+    }
+
+    fn get(&self) -> Result<Option<String>> {
+        let Some(wrapped) = self.storage.get::<Wrapped>(WRAPPED_KEY, &WRAPPED_SCHEMA) else {
+            return Ok(None);
+        };
+        let secret = in_java(|env| decrypt(env, &wrapped))?;
+        String::from_utf8(secret)
+            .map(Some)
+            .map_err(|e| Error::Generic(e.to_string()))
+    }
+
+    fn set(&self, secret: &str) -> Result<()> {
+        let wrapped = in_java(|env| encrypt(env, secret.as_bytes()))?;
+        self.storage.set(WRAPPED_KEY, &WRAPPED_SCHEMA, &wrapped)
+    }
+
+    fn remove(&self) -> Result<()> {
+        // already gone is fine
+        let _ = self.storage.remove(WRAPPED_KEY);
+        in_java(|env| {
+            let keystore = keystore(env)?;
+            let alias = env.new_string(KEY_ALIAS)?;
+            env.call_method(
+                &keystore,
+                "deleteEntry",
+                "(Ljava/lang/String;)V",
+                &[JValue::Object(&alias)],
+            )?;
+            Ok(())
+        })
+    }
+}
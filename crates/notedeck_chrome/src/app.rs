@@ -1,13 +1,43 @@
-use crate::{app_size::AppSizeHandler, persist_zoom::ZoomHandler, setup::setup_cc, theme};
+use crate::{
+    app_size::AppSizeHandler,
+    apps::{Apps, LauncherAction, SharedApp},
+    control::{self, Command, Control, OpenView},
+    crash_window::CrashWindow,
+    diagnostics::{self, Diagnostics},
+    key_lock::KeyLock,
+    lazy_app::{AppInit, LazyApp},
+    link_handler::{self, LinkHandler},
+    locale,
+    log_viewer::LogViewer,
+    logs, permission_prompt,
+    persist_zoom::ZoomHandler,
+    settings_window::{OpenWindow, SettingsWindow},
+    setup::setup_cc,
+    startup::Startup,
+    theme,
+    update_window::{self, UpdateWindow},
+};
+
+#[cfg(all(feature = "tray", not(target_os = "android")))]
+use crate::tray::{Tray, TrayAction};
+
+#[cfg(target_os = "android")]
+use crate::{
+    android_keystore::AndroidKeystore, background::BackgroundListener, camera::CameraScan,
+};
+#[cfg(target_os = "android")]
+use notedeck::SecretStore;
 
 use notedeck::{
-    Accounts, AppContext, Args, DataPath, DataPathType, Directory, FileKeyStorage, ImageCache,
-    KeyStorageType, NoteCache, ThemeHandler, UnknownIds,
+    AccountChange, Accounts, AppAction, AppCommand, AppContext, AppManifest, Args, Capability,
+    DataPath, DataPathType, Directory, FileKeyStorage, ImageCache, KeyStorageType, NoteCache,
+    Settings, ThemeHandler, UnknownIds,
 };
 
-use enostr::RelayPool;
+use enostr::{NoteId, Pubkey, RelayPool};
 use nostrdb::{Config, Ndb, Transaction};
 use notedeck_columns::ui::relay_debug::RelayDebugView;
+use serde_json::{json, Value};
 use std::cell::RefCell;
 use std::path::Path;
 use std::rc::Rc;
@@ -24,9 +54,29 @@ pub struct Notedeck {
     path: DataPath,
     args: Args,
     theme: ThemeHandler,
-    tabs: Tabs,
+    settings: Settings,
+    /// The account apps were last told about. `None` until the first frame.
+    account: Option<Option<Pubkey>>,
+    /// What the app asked of us this frame
+    actions: Vec<AppAction>,
+    settings_window: SettingsWindow,
+    crash_window: CrashWindow,
+    log_viewer: LogViewer,
+    diagnostics: Diagnostics,
+    key_lock: KeyLock,
+    update_window: UpdateWindow,
+    control: Control,
+    link_handler: LinkHandler,
+    startup: Startup,
+    apps: Apps,
     app_rect_handler: AppSizeHandler,
     zoom_handler: ZoomHandler,
+    #[cfg(all(feature = "tray", not(target_os = "android")))]
+    tray: Option<Tray>,
+    #[cfg(target_os = "android")]
+    background: BackgroundListener,
+    #[cfg(target_os = "android")]
+    camera: CameraScan,
 }
 
 fn margin_top(narrow: bool) -> f32 {
@@ -72,29 +122,84 @@ impl eframe::App for Notedeck {
         #[cfg(feature = "profiling")]
         puffin::GlobalProfiler::lock().new_frame();
 
+        self.startup.frame();
+        self.update_account();
+
+        if let Some(LauncherAction::Settings(title)) = self.apps.launcher(ctx, &self.settings) {
+            self.settings_window.search(&title);
+        }
+
         main_panel(&ctx.style(), notedeck::ui::is_narrow(ctx)).show(ctx, |ui| {
             // render app
-            if let Some(app) = &self.tabs.app {
-                let app = app.clone();
-                app.borrow_mut().update(&mut self.app_context(), ui);
+            if let Some(app) = self.apps.selected(&self.settings) {
+                app.borrow_mut().update(&mut self.context_for(&app), ui);
             }
         });
 
+        for request in self.control.poll(ctx, &self.settings, &self.path) {
+            let result = self.control_command(&request.command);
+            request.reply(result);
+        }
+        self.handle_actions(ctx);
+
+        match self.settings_window.update(ctx, &mut self.settings) {
+            Some(OpenWindow::Logs) => self.log_viewer.open(),
+            Some(OpenWindow::Diagnostics) => self.diagnostics.open(),
+            Some(OpenWindow::KeyProtection) => self.key_lock.open_settings(),
+            Some(OpenWindow::Updates) => self.update_window.open(ctx, &self.settings),
+            None => {}
+        }
+        self.log_viewer
+            .apply_level(self.settings.string(logs::APP, logs::LOG_LEVEL));
+        self.log_viewer.update(ctx, &self.path);
+        self.diagnostics.update(
+            ctx,
+            &mut self.img_cache,
+            diagnostics::media_budget(&self.settings),
+            &self.startup,
+        );
+        self.crash_window.update(ctx);
+        self.key_lock.update(ctx, &mut self.accounts);
+        self.update_window.update(ctx, &self.settings);
+        permission_prompt::update(ctx, self.accounts.permissions_mut(), &mut self.settings);
+        notedeck::toast::show(ctx);
+        notedeck::amount::refresh_prices(&self.path, ctx);
+
         self.app_rect_handler.try_save_app_size(ctx);
         self.zoom_handler.try_save_zoom_factor(ctx);
 
+        #[cfg(all(feature = "tray", not(target_os = "android")))]
+        self.update_tray(ctx);
+
+        #[cfg(target_os = "android")]
+        {
+            self.background.heartbeat(
+                self.accounts.get_selected_account().map(|acc| acc.pubkey),
+                &self.pool,
+            );
+            self.camera.update(ctx);
+        }
+
+        // F12 opens the relay debugger, same as starting with --relay-debug
+        if ctx.input(|i| i.key_pressed(egui::Key::F12)) {
+            self.args.relay_debug = !self.args.relay_debug;
+        }
+
         if self.args.relay_debug {
             if self.pool.debug.is_none() {
                 self.pool.use_debug();
             }
 
             if let Some(debug) = &mut self.pool.debug {
-                RelayDebugView::window(ctx, debug);
+                RelayDebugView::window(ctx, debug, &self.pool.relays, &mut self.args.relay_debug);
             }
         }
 
-        #[cfg(feature = "profiling")]
-        puffin_egui::profiler_window(ctx);
+        // links to nostr things show up next frame
+        if let Some(action) = self.link_handler.update(ctx, &mut self.settings) {
+            self.actions.push(action);
+            ctx.request_repaint();
+        }
     }
 }
 
@@ -103,6 +208,21 @@ fn setup_profiling() {
     puffin::set_scopes_on(true); // tell puffin to collect data
 }
 
+pub(crate) fn open_ndb(dbpath: &str) -> Ndb {
+    // ingester threads parse and check the signature of every note
+    let ingesters = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .clamp(2, 8);
+    let config = Config::new()
+        .set_ingester_threads(ingesters as i32)
+        .set_mapsize(notedeck::maintenance::mapsize());
+
+    // a compaction finished last session
+    notedeck::maintenance::use_compacted(Path::new(dbpath));
+    Ndb::new(dbpath, &config).expect("ndb")
+}
+
 impl Notedeck {
     pub fn new<P: AsRef<Path>>(ctx: &egui::Context, data_path: P, args: &[String]) -> Self {
         #[cfg(feature = "profiling")]
@@ -123,13 +243,20 @@ impl Notedeck {
             panic!();
         }
 
-        setup_cc(ctx, is_mobile, parsed_args.light);
+        let mut startup = Startup::default();
+        startup.phase("egui setup", || setup_cc(ctx, is_mobile, parsed_args.light));
 
         let data_path = parsed_args
             .datapath
             .clone()
             .unwrap_or(data_path.as_ref().to_str().expect("db path ok").to_string());
         let path = DataPath::new(&data_path);
+        notedeck::crash::install_panic_hook(&path);
+        startup.phase("locale", || {
+            notedeck::i18n::init(&path, locale::system_locale().as_deref());
+            notedeck::datetime::init(&path);
+            notedeck::amount::init(&path);
+        });
         let dbpath_str = parsed_args
             .dbpath
             .clone()
@@ -140,15 +267,16 @@ impl Notedeck {
         let imgcache_dir = path.path(DataPathType::Cache).join(ImageCache::rel_dir());
         let _ = std::fs::create_dir_all(imgcache_dir.clone());
 
-        let mapsize = if cfg!(target_os = "windows") {
-            // 16 Gib on windows because it actually creates the file
-            1024usize * 1024usize * 1024usize * 16usize
-        } else {
-            // 1 TiB for everything else since its just virtually mapped
-            1024usize * 1024usize * 1024usize * 1024usize
-        };
-
-        let theme = ThemeHandler::new(&path);
+        let (theme, mut settings) = startup.phase("settings", || {
+            let theme = ThemeHandler::new(&path);
+            let mut settings = Settings::new(&path);
+            logs::register(&mut settings);
+            diagnostics::register(&mut settings);
+            update_window::register(&mut settings);
+            link_handler::register(&mut settings);
+            control::register(&mut settings);
+            (theme, settings)
+        });
         ctx.options_mut(|o| {
             let cur_theme = theme.load();
             info!("Loaded theme {:?} from disk", cur_theme);
@@ -160,39 +288,55 @@ impl Notedeck {
         );
         ctx.set_visuals_of(egui::Theme::Light, theme::light_mode());
 
-        let config = Config::new().set_ingester_threads(4).set_mapsize(mapsize);
-
-        let keystore = if parsed_args.use_keystore {
-            let keys_path = path.path(DataPathType::Keys);
-            let selected_key_path = path.path(DataPathType::SelectedKey);
-            KeyStorageType::FileSystem(FileKeyStorage::new(
-                Directory::new(keys_path),
-                Directory::new(selected_key_path),
-            ))
-        } else {
-            KeyStorageType::None
-        };
-
-        let mut accounts = Accounts::new(keystore, parsed_args.relays.clone());
-
-        let num_keys = parsed_args.keys.len();
-
-        let mut unknown_ids = UnknownIds::default();
-        let ndb = Ndb::new(&dbpath_str, &config).expect("ndb");
+        let ndb = startup.phase("nostrdb", || open_ndb(&dbpath_str));
+
+        #[cfg(target_os = "android")]
+        let secrets: Option<Box<dyn SecretStore>> = Some(Box::new(AndroidKeystore::new(&path)));
+        #[cfg(not(target_os = "android"))]
+        let secrets = notedeck::storage::system_secret_store();
+
+        let (mut accounts, unlock_error) = startup.phase("accounts", || {
+            let keystore = if parsed_args.use_keystore {
+                let keys_path = path.path(DataPathType::Keys);
+                let selected_key_path = path.path(DataPathType::SelectedKey);
+                KeyStorageType::FileSystem(FileKeyStorage::new(
+                    Directory::new(keys_path),
+                    Directory::new(selected_key_path),
+                ))
+            } else {
+                KeyStorageType::None
+            };
+
+            let mut accounts = Accounts::new(keystore, parsed_args.relays.clone());
+            // keys saved unencrypted by older versions are moved under the
+            // system's keychain here
+            let unlock_error = accounts
+                .unlock_keys_with(secrets.as_deref())
+                .err()
+                .map(|err| err.to_string());
+            if let Some(err) = &unlock_error {
+                error!("couldn't unlock account keys: {err}");
+            }
 
-        {
-            let txn = Transaction::new(&ndb).expect("txn");
-            for key in &parsed_args.keys {
-                info!("adding account: {}", &key.pubkey);
-                accounts
-                    .add_account(key.clone())
-                    .process_action(&mut unknown_ids, &ndb, &txn);
+            let num_keys = parsed_args.keys.len();
+
+            let mut unknown_ids = UnknownIds::default();
+            {
+                let txn = Transaction::new(&ndb).expect("txn");
+                for key in &parsed_args.keys {
+                    info!("adding account: {}", &key.pubkey);
+                    accounts
+                        .add_account(key.clone())
+                        .process_action(&mut unknown_ids, &ndb, &txn);
+                }
             }
-        }
 
-        if num_keys != 0 {
-            accounts.select_account(0);
-        }
+            if num_keys != 0 {
+                accounts.select_account(0);
+            }
+            (accounts, unlock_error)
+        });
+        control::declare(&mut settings, accounts.permissions_mut());
 
         // AccountManager will setup the pool on first update
         let mut pool = RelayPool::new();
@@ -206,7 +350,6 @@ impl Notedeck {
         let img_cache = ImageCache::new(imgcache_dir);
         let note_cache = NoteCache::default();
         let unknown_ids = UnknownIds::default();
-        let tabs = Tabs::new(None);
         let app_rect_handler = AppSizeHandler::new(&path);
         let zoom_handler = ZoomHandler::new(&path);
 
@@ -214,10 +357,18 @@ impl Notedeck {
             ctx.set_zoom_factor(zoom_factor);
         }
 
+        #[cfg(all(feature = "tray", not(target_os = "android")))]
+        let tray = Tray::new(ctx, &path);
+
+        #[cfg(target_os = "android")]
+        let background = BackgroundListener::new(ndb.clone(), path.clone(), ctx.clone());
+
         // migrate
-        if let Err(e) = img_cache.migrate_v0() {
-            error!("error migrating image cache: {e}");
-        }
+        startup.phase("image cache migration", || {
+            if let Err(e) = img_cache.migrate_v0() {
+                error!("error migrating image cache: {e}");
+            }
+        });
 
         Self {
             ndb,
@@ -230,8 +381,26 @@ impl Notedeck {
             path: path.clone(),
             args: parsed_args,
             theme,
-            tabs,
+            settings,
+            account: None,
+            actions: Vec::new(),
+            settings_window: SettingsWindow::default(),
+            crash_window: CrashWindow::new(&path),
+            log_viewer: LogViewer::default(),
+            diagnostics: Diagnostics::default(),
+            key_lock: KeyLock::new(secrets, unlock_error),
+            update_window: UpdateWindow::new(&path),
+            control: Control::default(),
+            link_handler: LinkHandler::default(),
+            startup,
+            apps: Apps::default(),
             zoom_handler,
+            #[cfg(all(feature = "tray", not(target_os = "android")))]
+            tray,
+            #[cfg(target_os = "android")]
+            background,
+            #[cfg(target_os = "android")]
+            camera: CameraScan::default(),
         }
     }
 
@@ -246,20 +415,198 @@ impl Notedeck {
             path: &self.path,
             args: &self.args,
             theme: &mut self.theme,
+            settings: &mut self.settings,
+            actions: &mut self.actions,
+        }
+    }
+
+    /// The context for one of our apps, checking permissions as that app
+    fn context_for(&mut self, app: &SharedApp) -> AppContext<'_> {
+        let id = self.apps.id_of(app);
+        self.accounts.permissions_mut().enter(&self.settings, id);
+        self.app_context()
+    }
+
+    /// Let the app know when the selected account changes, before it
+    /// updates under the new one
+    fn update_account(&mut self) {
+        let current = self.accounts.get_selected_account().map(|acc| acc.pubkey);
+        if self.account == Some(current) {
+            return;
+        }
+
+        let change = AccountChange {
+            previous: self.account.flatten(),
+            current,
+        };
+        self.account = Some(current);
+        info!("selected account is now {:?}", current.map(|pk| pk.hex()));
+
+        self.settings.set_account(current);
+        self.accounts.permissions_mut().account_changed();
+        for app in self.apps.all() {
+            app.borrow_mut()
+                .account_changed(&mut self.context_for(&app), change);
+        }
+    }
+
+    /// Run a command from a script. What it opens goes through the same
+    /// actions apps use.
+    fn control_command(&mut self, command: &Command) -> Result<Value, String> {
+        match command {
+            Command::Schema => serde_json::from_str(control::SCHEMA).map_err(|e| e.to_string()),
+
+            Command::ListApps => Ok(self
+                .apps
+                .summaries(&self.settings)
+                .into_iter()
+                .map(|(manifest, enabled, selected)| {
+                    json!({
+                        "id": manifest.id,
+                        "name": manifest.name,
+                        "enabled": enabled,
+                        "selected": selected,
+                    })
+                })
+                .collect()),
+
+            Command::ListColumns(id) => {
+                let app = match id {
+                    Some(id) => self.apps.find(id),
+                    None => self.apps.selected(&self.settings),
+                }
+                .ok_or_else(|| "no such app".to_owned())?;
+                let views = app.borrow().views(&mut self.context_for(&app));
+                serde_json::to_value(views).map_err(|e| e.to_string())
+            }
+
+            Command::Open(view) => {
+                let action = match view.clone() {
+                    OpenView::Profile(pubkey) => AppAction::OpenProfile(pubkey),
+                    OpenView::Thread(note_id) => AppAction::OpenThread(note_id),
+                    OpenView::Address(address) => AppAction::OpenAddress(address),
+                    OpenView::Compose(text) => AppAction::Compose(text),
+                    OpenView::App(id) => {
+                        self.apps
+                            .select_id(&id, &self.settings)
+                            .ok_or_else(|| format!("no enabled app {id}"))?;
+                        return Ok(Value::Null);
+                    }
+                    OpenView::Settings(search) => {
+                        self.settings_window.search(&search);
+                        return Ok(Value::Null);
+                    }
+                };
+                self.actions.push(action);
+                Ok(Value::Null)
+            }
+
+            Command::Publish(note) => {
+                self.accounts
+                    .permissions_mut()
+                    .enter(&self.settings, Some(control::SCRIPTS));
+                let ctx = &mut self.app_context();
+                ctx.check(Capability::Publish).map_err(|e| e.message())?;
+                let id = ctx
+                    .accounts
+                    .publish_unsigned(ctx.ndb, ctx.pool, note)
+                    .map_err(|e| e.to_string())?;
+                Ok(json!({ "id": NoteId::new(id).hex() }))
+            }
+        }
+    }
+
+    fn handle_actions(&mut self, ctx: &egui::Context) {
+        for action in std::mem::take(&mut self.actions) {
+            let command = match action {
+                AppAction::OpenUrl(url) => {
+                    ctx.open_url(egui::OpenUrl::new_tab(url));
+                    continue;
+                }
+                AppAction::OpenProfile(pubkey) => AppCommand::OpenProfile(pubkey),
+                AppAction::OpenThread(note_id) => AppCommand::OpenThread(note_id),
+                AppAction::Compose(text) => AppCommand::ComposeWith(text),
+                AppAction::OpenAddress(address) => {
+                    match self.apps.select_for_kind(address.kind, &self.settings) {
+                        Some(app) => app.borrow_mut().command(
+                            &mut self.context_for(&app),
+                            AppCommand::OpenAddress(address),
+                        ),
+                        // nothing here opens it, a web client might
+                        None => {
+                            if let Some(naddr) = address.to_bech() {
+                                self.link_handler
+                                    .browse(ctx, format!("https://njump.me/{naddr}"));
+                            }
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            if let Some(app) = self.apps.selected(&self.settings) {
+                app.borrow_mut()
+                    .command(&mut self.context_for(&app), command);
+            }
         }
     }
 
     pub fn add_app<T: notedeck::App + 'static>(&mut self, app: T) {
-        self.tabs.app = Some(Rc::new(RefCell::new(app)));
+        self.apps.add(
+            &mut self.settings,
+            self.accounts.permissions_mut(),
+            Rc::new(RefCell::new(app)),
+        );
     }
-}
 
-struct Tabs {
-    app: Option<Rc<RefCell<dyn notedeck::App>>>,
-}
+    /// Add an app that's only built when it's first opened, or first
+    /// asked to open something
+    pub fn add_lazy_app(&mut self, manifest: AppManifest, init: AppInit) {
+        self.add_app(LazyApp::new(manifest, init));
+    }
+
+    #[cfg(all(feature = "tray", not(target_os = "android")))]
+    fn update_tray(&mut self, ctx: &egui::Context) {
+        let Some(tray) = &mut self.tray else {
+            return;
+        };
 
-impl Tabs {
-    pub fn new(app: Option<Rc<RefCell<dyn notedeck::App>>>) -> Self {
-        Self { app }
+        tray.set_unread(
+            self.apps
+                .all()
+                .iter()
+                .map(|app| app.borrow().unread_count())
+                .sum(),
+        );
+
+        let actions = tray.poll();
+        tray.minimize_if_needed(ctx);
+
+        for action in actions {
+            match action {
+                TrayAction::NewNote => {
+                    if let Some(tray) = &mut self.tray {
+                        tray.set_window_hidden(ctx, false);
+                    }
+
+                    if let Some(app) = self.apps.selected(&self.settings) {
+                        app.borrow_mut()
+                            .command(&mut self.context_for(&app), AppCommand::ComposeNote);
+                    }
+                }
+
+                TrayAction::ToggleWindow => {
+                    if let Some(tray) = &mut self.tray {
+                        tray.toggle_window(ctx);
+                    }
+                }
+
+                TrayAction::Quit => {
+                    if let Some(tray) = &mut self.tray {
+                        tray.quit(ctx);
+                    }
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,207 @@
+//! The apps the chrome hosts, and the launcher for switching between them.
+//! Apps describe themselves with an [`AppManifest`], and can be turned off
+//! per account in settings.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use notedeck::{
+    settings::{Setting, SettingsSection},
+    tr, App, AppManifest, Permissions, Settings,
+};
+
+use crate::logs::APP;
+
+const LAUNCHER_WIDTH: f32 = 48.0;
+
+pub type SharedApp = Rc<RefCell<dyn App>>;
+
+struct Registered {
+    manifest: Option<AppManifest>,
+    app: SharedApp,
+}
+
+/// Something picked from the launcher besides switching apps
+pub enum LauncherAction {
+    /// Show the settings section with this title
+    Settings(String),
+}
+
+#[derive(Default)]
+pub struct Apps {
+    apps: Vec<Registered>,
+    selected: usize,
+}
+
+impl Apps {
+    pub fn add(&mut self, settings: &mut Settings, permissions: &mut Permissions, app: SharedApp) {
+        let manifest = app.borrow().manifest();
+        if let Some(manifest) = manifest.as_ref().filter(|m| !m.capabilities.is_empty()) {
+            permissions.declare(
+                settings,
+                manifest.id,
+                &manifest.name,
+                &manifest.capabilities,
+            );
+        }
+        self.apps.push(Registered { manifest, app });
+        self.register(settings);
+    }
+
+    /// One toggle per app with a manifest. Registering again replaces
+    /// the section, so it grows as apps are added.
+    fn register(&self, settings: &mut Settings) {
+        let section = self.manifests().fold(
+            SettingsSection::new(APP, tr!("settings-apps")),
+            |section, manifest| {
+                section.setting(
+                    Setting::toggle(
+                        manifest.id,
+                        tr!("settings-app-enabled", name = manifest.name.as_str()),
+                        true,
+                    )
+                    .per_account(),
+                )
+            },
+        );
+        settings.register(section);
+    }
+
+    fn manifests(&self) -> impl Iterator<Item = &AppManifest> {
+        self.apps.iter().filter_map(|app| app.manifest.as_ref())
+    }
+
+    fn is_enabled(&self, i: usize, settings: &Settings) -> bool {
+        match &self.apps[i].manifest {
+            Some(manifest) => settings.bool(APP, manifest.id),
+            // previews and the like are always on
+            None => true,
+        }
+    }
+
+    /// The app to show. If every app was turned off the first one is
+    /// shown anyway, there'd be no way back to settings otherwise.
+    pub fn selected(&self, settings: &Settings) -> Option<SharedApp> {
+        let i = if self.selected < self.apps.len() && self.is_enabled(self.selected, settings) {
+            self.selected
+        } else {
+            (0..self.apps.len())
+                .find(|i| self.is_enabled(*i, settings))
+                .unwrap_or(0)
+        };
+        self.apps.get(i).map(|registered| registered.app.clone())
+    }
+
+    /// Switch to the enabled app that opens this kind, if there is one
+    pub fn select_for_kind(&mut self, kind: u32, settings: &Settings) -> Option<SharedApp> {
+        let i = (0..self.apps.len()).find(|i| {
+            self.is_enabled(*i, settings)
+                && self.apps[*i]
+                    .manifest
+                    .as_ref()
+                    .is_some_and(|manifest| manifest.handles(kind))
+        })?;
+        self.selected = i;
+        Some(self.apps[i].app.clone())
+    }
+
+    /// Switch to an enabled app by its manifest id
+    pub fn select_id(&mut self, id: &str, settings: &Settings) -> Option<SharedApp> {
+        let i = (0..self.apps.len()).find(|i| {
+            self.is_enabled(*i, settings)
+                && self.apps[*i]
+                    .manifest
+                    .as_ref()
+                    .is_some_and(|manifest| manifest.id == id)
+        })?;
+        self.selected = i;
+        Some(self.apps[i].app.clone())
+    }
+
+    pub fn find(&self, id: &str) -> Option<SharedApp> {
+        self.apps
+            .iter()
+            .find(|registered| registered.manifest.as_ref().is_some_and(|m| m.id == id))
+            .map(|registered| registered.app.clone())
+    }
+
+    /// Apps with a manifest, whether each is enabled and whether it's the
+    /// one being shown
+    pub fn summaries(&self, settings: &Settings) -> Vec<(&AppManifest, bool, bool)> {
+        let selected = self.selected(settings);
+        self.apps
+            .iter()
+            .enumerate()
+            .filter_map(|(i, registered)| {
+                let manifest = registered.manifest.as_ref()?;
+                let shown = selected
+                    .as_ref()
+                    .is_some_and(|app| Rc::ptr_eq(app, &registered.app));
+                Some((manifest, self.is_enabled(i, settings), shown))
+            })
+            .collect()
+    }
+
+    /// The manifest id of an app we host, for checking its permissions
+    pub fn id_of(&self, app: &SharedApp) -> Option<&'static str> {
+        self.apps
+            .iter()
+            .find(|registered| Rc::ptr_eq(&registered.app, app))
+            .and_then(|registered| registered.manifest.as_ref())
+            .map(|manifest| manifest.id)
+    }
+
+    /// Every app, enabled or not, eg. to tell them the account changed
+    pub fn all(&self) -> Vec<SharedApp> {
+        self.apps
+            .iter()
+            .map(|registered| registered.app.clone())
+            .collect()
+    }
+
+    /// A strip of app icons down the side. Hidden while there's only one
+    /// app to pick.
+    pub fn launcher(&mut self, ctx: &egui::Context, settings: &Settings) -> Option<LauncherAction> {
+        let shown: Vec<usize> = (0..self.apps.len())
+            .filter(|i| self.apps[*i].manifest.is_some() && self.is_enabled(*i, settings))
+            .collect();
+        if shown.len() < 2 {
+            return None;
+        }
+
+        let mut action = None;
+        egui::SidePanel::left("app-launcher")
+            .resizable(false)
+            .exact_width(LAUNCHER_WIDTH)
+            .show(ctx, |ui| {
+                ui.add_space(8.0);
+                ui.vertical_centered(|ui| {
+                    for i in shown {
+                        let Some(manifest) = &self.apps[i].manifest else {
+                            continue;
+                        };
+                        let response = ui
+                            .add(egui::SelectableLabel::new(
+                                i == self.selected,
+                                egui::RichText::new(manifest.icon).size(24.0),
+                            ))
+                            .on_hover_text(&manifest.name);
+                        if response.clicked() {
+                            self.selected = i;
+                        }
+                        if let Some(title) = &manifest.settings {
+                            response.context_menu(|ui| {
+                                if ui.button(tr!("app-settings")).clicked() {
+                                    action = Some(LauncherAction::Settings(title.clone()));
+                                    ui.close_menu();
+                                }
+                            });
+                        }
+                        ui.add_space(4.0);
+                    }
+                });
+            });
+
+        action
+    }
+}
@@ -0,0 +1,496 @@
+//! Keeping an eye on notifications while we're in the background on
+//! android.
+//!
+//! Once the activity is paused eframe stops calling `update`, so our relay
+//! pool is never polled and nothing new comes in. When the user opts in, a
+//! watchdog thread notices frames have stopped and opens a small relay
+//! pool of its own that only asks for DMs, mentions, replies and zaps. New
+//! notes go straight into nostrdb, so the timelines already have them when
+//! the app resumes, and matching ones are posted as android notifications.
+//!
+//! We're a NativeActivity built with cargo-apk, which means we can't ship
+//! a java `Service`. Instead we post an ongoing notification while we're
+//! listening, which tells the user what's going on and makes android less
+//! eager to kill the process, but it isn't a real foreground service.
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use enostr::{ClientMessage, Pubkey, RelayEvent, RelayMessage, RelayPool};
+use jni::objects::{JObject, JValue};
+use jni::JavaVM;
+use nostrdb::{Ndb, Subscription, Transaction};
+use notedeck::{time::unix_now, tr, DataPath, WebOfTrust, WotParams};
+use notedeck_columns::notifications::{self, DesktopNotification, ThreadNotifications};
+use notedeck_columns::storage::{load_notification_settings, load_thread_notifications};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// How long without a frame before we consider ourselves backgrounded.
+/// The columns app repaints at least once a second while it's visible.
+const BACKGROUND_AFTER: Duration = Duration::from_secs(10);
+
+/// How often we ask android whether the connection is metered
+const METERED_EVERY: Duration = Duration::from_secs(30);
+
+const CHANNEL_ID: &str = "notedeck-notifications";
+const LISTENING_NOTIFICATION_ID: i32 = 1;
+
+/// What the listener needs to know about the foreground app
+#[derive(Default)]
+struct Heartbeat {
+    last_frame: Option<Instant>,
+    account: Option<Pubkey>,
+    relays: Vec<String>,
+}
+
+pub struct BackgroundListener {
+    heartbeat: Arc<Mutex<Heartbeat>>,
+}
+
+impl BackgroundListener {
+    pub fn new(ndb: Ndb, path: DataPath, ctx: egui::Context) -> Self {
+        let heartbeat = Arc::new(Mutex::new(Heartbeat::default()));
+
+        let watched = heartbeat.clone();
+        let spawned = thread::Builder::new()
+            .name("background-listener".to_owned())
+            .spawn(move || watchdog(watched, ndb, path, ctx));
+
+        if let Err(err) = spawned {
+            error!("background: failed to spawn watchdog: {err}");
+        }
+
+        BackgroundListener { heartbeat }
+    }
+
+    /// Let the watchdog know we're still in the foreground. Called every
+    /// frame.
+    pub fn heartbeat(&self, account: Option<Pubkey>, pool: &RelayPool) {
+        let Ok(mut heartbeat) = self.heartbeat.lock() else {
+            return;
+        };
+
+        heartbeat.last_frame = Some(Instant::now());
+        heartbeat.account = account;
+        if heartbeat.relays.len() != pool.relays.len() {
+            heartbeat.relays = pool.urls().into_iter().collect();
+        }
+    }
+}
+
+fn watchdog(heartbeat: Arc<Mutex<Heartbeat>>, ndb: Ndb, path: DataPath, ctx: egui::Context) {
+    let mut listening: Option<Listening> = None;
+    let mut asked_permission = false;
+    let mut metered_checked: Option<Instant> = None;
+
+    loop {
+        if !metered_checked.is_some_and(|checked| checked.elapsed() < METERED_EVERY) {
+            metered_checked = Some(Instant::now());
+            check_metered();
+        }
+
+        let (backgrounded, account, relays) = {
+            let Ok(heartbeat) = heartbeat.lock() else {
+                return;
+            };
+            let backgrounded = heartbeat
+                .last_frame
+                .is_some_and(|last| last.elapsed() > BACKGROUND_AFTER);
+            (backgrounded, heartbeat.account, heartbeat.relays.clone())
+        };
+
+        match (&mut listening, backgrounded) {
+            (None, true) => {
+                let settings = load_notification_settings(&path);
+                if let Some(account) = account.filter(|_| settings.enabled && settings.background) {
+                    info!("background: app went to the background, listening for notifications");
+                    listening = Listening::start(&ndb, &path, account, &relays);
+                    if listening.is_some() {
+                        post(&listening_notification(), LISTENING_NOTIFICATION_ID, true);
+                    }
+                }
+            }
+
+            (Some(active), true) => active.poll(&ndb, &path),
+
+            (Some(_), false) => {
+                info!("background: app resumed, closing background subscription");
+                if let Some(active) = listening.take() {
+                    active.stop(&ndb);
+                }
+                cancel(LISTENING_NOTIFICATION_ID);
+                // pick up whatever came in while we were away
+                ctx.request_repaint();
+            }
+
+            (None, false) => {
+                if !asked_permission && load_notification_settings(&path).background {
+                    asked_permission = true;
+                    request_notification_permission();
+                }
+            }
+        }
+
+        let nap = if listening.is_some() {
+            Duration::from_millis(250)
+        } else {
+            Duration::from_secs(1)
+        };
+        thread::sleep(nap);
+    }
+}
+
+/// A minimal relay subscription for notifications only
+struct Listening {
+    pool: RelayPool,
+    account: Pubkey,
+    subid: String,
+    sub: Subscription,
+    next_notification_id: i32,
+    /// For notification rules that look at who's nearby
+    wot: WebOfTrust,
+    threads: ThreadNotifications,
+}
+
+impl Listening {
+    fn start(ndb: &Ndb, path: &DataPath, account: Pubkey, relays: &[String]) -> Option<Self> {
+        let threads = load_thread_notifications(path, &account);
+        let filters = notifications::filters(&account, unix_now(), &threads);
+        let sub = match ndb.subscribe(&filters) {
+            Ok(sub) => sub,
+            Err(err) => {
+                error!("background: subscribe failed: {err}");
+                return None;
+            }
+        };
+
+        let mut pool = RelayPool::new();
+        if let Err(err) = pool.add_urls(relays.iter().cloned().collect(), || {}) {
+            error!("background: failed to add relays: {err}");
+        }
+
+        Some(Listening {
+            pool,
+            account,
+            subid: Uuid::new_v4().to_string(),
+            sub,
+            next_notification_id: LISTENING_NOTIFICATION_ID + 1,
+            wot: WebOfTrust::default(),
+            threads,
+        })
+    }
+
+    fn poll(&mut self, ndb: &Ndb, path: &DataPath) {
+        self.pool.keepalive_ping(|| {});
+
+        // NOTE: we don't use the while let loop due to borrow issues
+        #[allow(clippy::while_let_loop)]
+        loop {
+            let ev = if let Some(ev) = self.pool.try_recv() {
+                ev.into_owned()
+            } else {
+                break;
+            };
+
+            match (&ev.event).into() {
+                RelayEvent::Opened => {
+                    let filters = notifications::filters(&self.account, unix_now(), &self.threads);
+                    self.pool
+                        .send_to(&ClientMessage::req(self.subid.clone(), filters), &ev.relay);
+                }
+                RelayEvent::Message(RelayMessage::Event(_subid, note)) => {
+                    if let Err(err) = ndb.process_event(note) {
+                        error!("background: error processing event: {err}");
+                    }
+                }
+                RelayEvent::Error(err) => debug!("background: relay {} error: {err}", ev.relay),
+                _ => {}
+            }
+        }
+
+        let note_keys = ndb.poll_for_notes(self.sub, 50);
+        if note_keys.is_empty() {
+            return;
+        }
+
+        let settings = load_notification_settings(path);
+        self.wot
+            .update(ndb, Some(self.account.bytes()), WotParams::default());
+
+        let Ok(txn) = Transaction::new(ndb) else {
+            return;
+        };
+
+        for key in note_keys {
+            let Ok(note) = ndb.get_note_by_key(&txn, key) else {
+                continue;
+            };
+
+            if let Some(notification) =
+                settings.notification(ndb, &txn, &note, &self.account, &self.wot, &self.threads)
+            {
+                post(&notification, self.next_notification_id, false);
+                self.next_notification_id += 1;
+            }
+        }
+    }
+
+    fn stop(mut self, ndb: &Ndb) {
+        self.pool.unsubscribe(self.subid.clone());
+        if let Err(err) = ndb.unsubscribe(self.sub) {
+            error!("background: unsubscribe failed: {err}");
+        }
+    }
+}
+
+fn listening_notification() -> DesktopNotification {
+    DesktopNotification {
+        title: "Notedeck".to_owned(),
+        body: tr!("notification-listening"),
+        target: None,
+    }
+}
+
+fn post(notification: &DesktopNotification, id: i32, ongoing: bool) {
+    if let Err(err) =
+        with_activity(|env, activity| post_notification(env, activity, notification, id, ongoing))
+    {
+        error!("background: failed to post notification: {err}");
+    }
+}
+
+fn cancel(id: i32) {
+    if let Err(err) = with_activity(|env, activity| {
+        let manager = notification_manager(env, activity)?;
+        env.call_method(&manager, "cancel", "(I)V", &[JValue::Int(id)])?;
+        Ok(())
+    }) {
+        error!("background: failed to cancel notification: {err}");
+    }
+}
+
+/// Android 13 and up need the user to allow notifications at runtime
+fn request_notification_permission() {
+    let requested = with_activity(|env, activity| {
+        let sdk = env
+            .get_static_field("android/os/Build$VERSION", "SDK_INT", "I")?
+            .i()?;
+        if sdk < 33 {
+            return Ok(());
+        }
+
+        let permission = env.new_string("android.permission.POST_NOTIFICATIONS")?;
+        let permissions = env.new_object_array(1, "java/lang/String", &permission)?;
+        env.call_method(
+            activity,
+            "requestPermissions",
+            "([Ljava/lang/String;I)V",
+            &[JValue::Object(&permissions), JValue::Int(0)],
+        )?;
+        Ok(())
+    });
+
+    if let Err(err) = requested {
+        error!("background: failed to request notification permission: {err}");
+    }
+}
+
+/// Tell the apps whether we're on mobile data, so prefetching can wait
+/// for Wi-Fi
+fn check_metered() {
+    let checked = with_activity(|env, activity| {
+        let service = env
+            .get_static_field(
+                "android/content/Context",
+                "CONNECTIVITY_SERVICE",
+                "Ljava/lang/String;",
+            )?
+            .l()?;
+        let manager = env
+            .call_method(
+                activity,
+                "getSystemService",
+                "(Ljava/lang/String;)Ljava/lang/Object;",
+                &[JValue::Object(&service)],
+            )?
+            .l()?;
+        let metered = env
+            .call_method(&manager, "isActiveNetworkMetered", "()Z", &[])?
+            .z()?;
+        notedeck::network::set_metered(metered);
+        Ok(())
+    });
+
+    if let Err(err) = checked {
+        error!("background: failed to check for a metered connection: {err}");
+    }
+}
+
+pub(crate) fn with_activity<F>(f: F) -> jni::errors::Result<()>
+where
+    F: FnOnce(&mut jni::JNIEnv, &JObject) -> jni::errors::Result<()>,
+{
+    let context = ndk_context::android_context();
+    // SAFETY: android-activity sets these up before android_main runs and
+    // they live as long as the process
+    let vm = unsafe { JavaVM::from_raw(context.vm().cast()) }?;
+    let activity = unsafe { JObject::from_raw(context.context().cast()) };
+    let mut env = vm.attach_current_thread()?;
+    f(&mut env, &activity)
+}
+
+fn notification_manager<'local>(
+    env: &mut jni::JNIEnv<'local>,
+    activity: &JObject,
+) -> jni::errors::Result<JObject<'local>> {
+    let service = env
+        .get_static_field(
+            "android/content/Context",
+            "NOTIFICATION_SERVICE",
+            "Ljava/lang/String;",
+        )?
+        .l()?;
+
+    env.call_method(
+        activity,
+        "getSystemService",
+        "(Ljava/lang/String;)Ljava/lang/Object;",
+        &[JValue::Object(&service)],
+    )?
+    .l()
+}
+
+fn post_notification(
+    env: &mut jni::JNIEnv,
+    activity: &JObject,
+    notification: &DesktopNotification,
+    id: i32,
+    ongoing: bool,
+) -> jni::errors::Result<()> {
+    let manager = notification_manager(env, activity)?;
+
+    // creating a channel that already exists is a no-op
+    let channel_id = env.new_string(CHANNEL_ID)?;
+    let channel_name = env.new_string("Notifications")?;
+    let importance_default = 3;
+    let channel = env.new_object(
+        "android/app/NotificationChannel",
+        "(Ljava/lang/String;Ljava/lang/CharSequence;I)V",
+        &[
+            JValue::Object(&channel_id),
+            JValue::Object(&channel_name),
+            JValue::Int(importance_default),
+        ],
+    )?;
+    env.call_method(
+        &manager,
+        "createNotificationChannel",
+        "(Landroid/app/NotificationChannel;)V",
+        &[JValue::Object(&channel)],
+    )?;
+
+    let builder = env.new_object(
+        "android/app/Notification$Builder",
+        "(Landroid/content/Context;Ljava/lang/String;)V",
+        &[JValue::Object(activity), JValue::Object(&channel_id)],
+    )?;
+
+    let title = env.new_string(&notification.title)?;
+    let body = env.new_string(&notification.body)?;
+    let icon = env
+        .get_static_field("android/R$drawable", "stat_notify_chat", "I")?
+        .i()?;
+    let open_app = launch_intent(env, activity)?;
+
+    let setters: [(&str, &str, JValue); 6] = [
+        (
+            "setContentTitle",
+            "(Ljava/lang/CharSequence;)Landroid/app/Notification$Builder;",
+            JValue::Object(&title),
+        ),
+        (
+            "setContentText",
+            "(Ljava/lang/CharSequence;)Landroid/app/Notification$Builder;",
+            JValue::Object(&body),
+        ),
+        (
+            "setSmallIcon",
+            "(I)Landroid/app/Notification$Builder;",
+            JValue::Int(icon),
+        ),
+        (
+            "setOngoing",
+            "(Z)Landroid/app/Notification$Builder;",
+            JValue::Bool(ongoing.into()),
+        ),
+        (
+            "setAutoCancel",
+            "(Z)Landroid/app/Notification$Builder;",
+            JValue::Bool((!ongoing).into()),
+        ),
+        (
+            "setContentIntent",
+            "(Landroid/app/PendingIntent;)Landroid/app/Notification$Builder;",
+            JValue::Object(&open_app),
+        ),
+    ];
+
+    for (name, sig, arg) in setters {
+        env.call_method(&builder, name, sig, &[arg])?;
+    }
+
+    let built = env
+        .call_method(&builder, "build", "()Landroid/app/Notification;", &[])?
+        .l()?;
+
+    env.call_method(
+        &manager,
+        "notify",
+        "(ILandroid/app/Notification;)V",
+        &[JValue::Int(id), JValue::Object(&built)],
+    )?;
+
+    Ok(())
+}
+
+/// Tapping a notification brings us back to the front
+fn launch_intent<'local>(
+    env: &mut jni::JNIEnv<'local>,
+    activity: &JObject,
+) -> jni::errors::Result<JObject<'local>> {
+    let package_manager = env
+        .call_method(
+            activity,
+            "getPackageManager",
+            "()Landroid/content/pm/PackageManager;",
+            &[],
+        )?
+        .l()?;
+    let package_name = env
+        .call_method(activity, "getPackageName", "()Ljava/lang/String;", &[])?
+        .l()?;
+    let intent = env
+        .call_method(
+            &package_manager,
+            "getLaunchIntentForPackage",
+            "(Ljava/lang/String;)Landroid/content/Intent;",
+            &[JValue::Object(&package_name)],
+        )?
+        .l()?;
+
+    let flag_immutable = 0x0400_0000;
+    env.call_static_method(
+        "android/app/PendingIntent",
+        "getActivity",
+        "(Landroid/content/Context;ILandroid/content/Intent;I)Landroid/app/PendingIntent;",
+        &[
+            JValue::Object(activity),
+            JValue::Int(0),
+            JValue::Object(&intent),
+            JValue::Int(flag_immutable),
+        ],
+    )?
+    .l()
+}
@@ -0,0 +1,246 @@
+//! Taking a picture of a QR code on android, for apps that ask with
+//! `qr_scan::request_camera`.
+//!
+//! We're a NativeActivity without any java of our own, so we can't be
+//! handed an activity result. Instead we make an empty image in the
+//! MediaStore, ask the camera app to save its picture there, and read it
+//! back once frames start again. Writing our own MediaStore images without
+//! a storage permission needs android 10 or newer.
+
+use std::time::{Duration, Instant};
+
+use jni::objects::{GlobalRef, JObject, JValue};
+use notedeck::{toast, tr};
+use notedeck_columns::qr_scan;
+use tracing::{error, info};
+
+use crate::background::with_activity;
+
+/// A gap between frames this long means the camera app was in front
+const AWAY_AFTER: Duration = Duration::from_secs(3);
+
+const READ_CHUNK: i32 = 64 * 1024;
+
+/// A picture the camera app was asked to take
+struct Capture {
+    image: GlobalRef,
+    last_frame: Instant,
+    away: bool,
+}
+
+#[derive(Default)]
+pub struct CameraScan {
+    capture: Option<Capture>,
+}
+
+impl CameraScan {
+    /// Called every frame
+    pub fn update(&mut self, ctx: &egui::Context) {
+        if qr_scan::take_camera_request(ctx) && self.capture.is_none() {
+            match open_camera() {
+                Ok(image) => {
+                    self.capture = Some(Capture {
+                        image,
+                        last_frame: Instant::now(),
+                        away: false,
+                    })
+                }
+                Err(err) => {
+                    error!("camera: couldn't open the camera: {err}");
+                    toast::error(tr!("toast-camera-error"));
+                }
+            }
+        }
+
+        let Some(capture) = &mut self.capture else {
+            return;
+        };
+        let now = Instant::now();
+        capture.away |= now.duration_since(capture.last_frame) > AWAY_AFTER;
+        capture.last_frame = now;
+        if !capture.away {
+            return;
+        }
+
+        // back from the camera app, with a picture or without one
+        let Some(capture) = self.capture.take() else {
+            return;
+        };
+        let bytes = match take_picture(&capture.image) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                error!("camera: couldn't read the picture: {err}");
+                return;
+            }
+        };
+        if bytes.is_empty() {
+            info!("camera: no picture was taken");
+            return;
+        }
+
+        let codes = qr_scan::decode_image(&bytes);
+        if codes.is_empty() {
+            toast::warning(tr!("toast-camera-no-qr"));
+        } else {
+            qr_scan::add_scanned(ctx, codes);
+        }
+    }
+}
+
+/// Run `f`, clearing any java exception it left behind so later calls
+/// still work
+fn with_activity_checked<F>(f: F) -> jni::errors::Result<()>
+where
+    F: FnOnce(&mut jni::JNIEnv, &JObject) -> jni::errors::Result<()>,
+{
+    with_activity(|env, activity| {
+        let result = f(env, activity);
+        if result.is_err() && env.exception_check().unwrap_or(false) {
+            env.exception_clear()?;
+        }
+        result
+    })
+}
+
+fn content_resolver<'local>(
+    env: &mut jni::JNIEnv<'local>,
+    activity: &JObject,
+) -> jni::errors::Result<JObject<'local>> {
+    env.call_method(
+        activity,
+        "getContentResolver",
+        "()Landroid/content/ContentResolver;",
+        &[],
+    )?
+    .l()
+}
+
+/// Make the image for the picture and start the camera app on it
+fn open_camera() -> jni::errors::Result<GlobalRef> {
+    let mut image = None;
+    with_activity_checked(|env, activity| {
+        let resolver = content_resolver(env, activity)?;
+        let images = env
+            .get_static_field(
+                "android/provider/MediaStore$Images$Media",
+                "EXTERNAL_CONTENT_URI",
+                "Landroid/net/Uri;",
+            )?
+            .l()?;
+        let values = env.new_object("android/content/ContentValues", "()V", &[])?;
+        let key = env.new_string("mime_type")?;
+        let mime = env.new_string("image/jpeg")?;
+        env.call_method(
+            &values,
+            "put",
+            "(Ljava/lang/String;Ljava/lang/String;)V",
+            &[JValue::Object(&key), JValue::Object(&mime)],
+        )?;
+        let uri = env
+            .call_method(
+                &resolver,
+                "insert",
+                "(Landroid/net/Uri;Landroid/content/ContentValues;)Landroid/net/Uri;",
+                &[JValue::Object(&images), JValue::Object(&values)],
+            )?
+            .l()?;
+        if uri.is_null() {
+            return Err(jni::errors::Error::NullPtr("MediaStore insert"));
+        }
+
+        let action = env.new_string("android.media.action.IMAGE_CAPTURE")?;
+        let intent = env.new_object(
+            "android/content/Intent",
+            "(Ljava/lang/String;)V",
+            &[JValue::Object(&action)],
+        )?;
+        let output = env.new_string("output")?;
+        env.call_method(
+            &intent,
+            "putExtra",
+            "(Ljava/lang/String;Landroid/os/Parcelable;)Landroid/content/Intent;",
+            &[JValue::Object(&output), JValue::Object(&uri)],
+        )?;
+        // FLAG_GRANT_READ_URI_PERMISSION | FLAG_GRANT_WRITE_URI_PERMISSION
+        env.call_method(
+            &intent,
+            "addFlags",
+            "(I)Landroid/content/Intent;",
+            &[JValue::Int(0x1 | 0x2)],
+        )?;
+        env.call_method(
+            activity,
+            "startActivity",
+            "(Landroid/content/Intent;)V",
+            &[JValue::Object(&intent)],
+        )?;
+
+        image = Some(env.new_global_ref(uri)?);
+        Ok(())
+    })?;
+
+    image.ok_or(jni::errors::Error::NullPtr("camera image"))
+}
+
+/// Read the picture, empty if none was taken, and remove it from the
+/// user's photos
+fn take_picture(image: &GlobalRef) -> jni::errors::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    with_activity_checked(|env, activity| {
+        let resolver = content_resolver(env, activity)?;
+        let read = read_all(env, &resolver, image.as_obj());
+        if read.is_err() && env.exception_check().unwrap_or(false) {
+            env.exception_clear()?;
+        }
+
+        let null = JObject::null();
+        env.call_method(
+            &resolver,
+            "delete",
+            "(Landroid/net/Uri;Ljava/lang/String;[Ljava/lang/String;)I",
+            &[
+                JValue::Object(image.as_obj()),
+                JValue::Object(&null),
+                JValue::Object(&null),
+            ],
+        )?;
+
+        bytes = read?;
+        Ok(())
+    })?;
+
+    Ok(bytes)
+}
+
+fn read_all(
+    env: &mut jni::JNIEnv,
+    resolver: &JObject,
+    uri: &JObject,
+) -> jni::errors::Result<Vec<u8>> {
+    let stream = env
+        .call_method(
+            resolver,
+            "openInputStream",
+            "(Landroid/net/Uri;)Ljava/io/InputStream;",
+            &[JValue::Object(uri)],
+        )?
+        .l()?;
+    let buffer = env.new_byte_array(READ_CHUNK)?;
+    let mut chunk = vec![0i8; READ_CHUNK as usize];
+
+    let mut bytes = vec![];
+    loop {
+        let read = env
+            .call_method(&stream, "read", "([B)I", &[JValue::Object(&buffer)])?
+            .i()?;
+        if read < 0 {
+            break;
+        }
+        let read = &mut chunk[..read as usize];
+        env.get_byte_array_region(&buffer, 0, read)?;
+        bytes.extend(read.iter().map(|b| *b as u8));
+    }
+    env.call_method(&stream, "close", "()V", &[])?;
+
+    Ok(bytes)
+}
@@ -0,0 +1,551 @@
+//! A local API for scripting notedeck, off until it's turned on in
+//! settings. Scripts find a unix socket (a localhost port on windows) and
+//! a token under `value` in `storage/apps/control/info.json`, which only
+//! we can read, send one JSON request per line and read one JSON response
+//! line back. The commands are described by
+//! `assets/control-api.schema.json`, which the `schema` command returns.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+use enostr::{Address, FullKeypair, NoteId, Pubkey};
+use notedeck::{
+    settings::{Setting, SettingsSection},
+    storage::Schema,
+    time::unix_now,
+    tr, AppStorage, Capability, DataPath, Permissions, Settings, UnsignedNote,
+};
+use serde_json::{json, Value};
+use tracing::{error, info, warn};
+
+use crate::logs::APP;
+
+pub const CONTROL_API: &str = "control-api";
+/// Scripts are asked about, and allowed, like an app with this id
+pub const SCRIPTS: &str = "control-api-scripts";
+
+pub const SCHEMA: &str = include_str!("../../../assets/control-api.schema.json");
+
+const STORAGE_ID: &str = "control";
+/// Where scripts find the socket and token
+const INFO_KEY: &str = "info";
+static INFO_SCHEMA: Schema = Schema::INITIAL;
+#[cfg(unix)]
+const SOCKET_FILE: &str = "control.sock";
+/// How often the accept loop looks for connections, and whether it
+/// should stop
+const ACCEPT_POLL: Duration = Duration::from_millis(200);
+/// A command the UI hasn't answered by now has been dropped
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest request we'll read. Anyone who can reach the socket can send,
+/// token or not, so a line without an end mustn't fill up memory.
+const MAX_LINE: usize = 1024 * 1024;
+
+pub fn register(settings: &mut Settings) {
+    settings.register(
+        SettingsSection::new(APP, tr!("settings-control")).setting(
+            Setting::toggle(CONTROL_API, tr!("settings-control-api"), false)
+                .description(tr!("settings-control-api-hint"))
+                .device_local(),
+        ),
+    );
+}
+
+/// Scripts need the user's say before they publish, like apps do
+pub fn declare(settings: &mut Settings, permissions: &mut Permissions) {
+    permissions.declare(
+        settings,
+        SCRIPTS,
+        &tr!("settings-control-api"),
+        &[Capability::Publish],
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenView {
+    Profile(Pubkey),
+    Thread(NoteId),
+    Address(Address),
+    Compose(String),
+    App(String),
+    Settings(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Schema,
+    ListApps,
+    /// The columns of an app, the selected one if there's no id
+    ListColumns(Option<String>),
+    Open(OpenView),
+    /// Signed by the selected account, whatever pubkey it has
+    Publish(UnsignedNote),
+}
+
+fn string_param<'a>(params: &'a Value, key: &str) -> Result<&'a str, String> {
+    params
+        .get(key)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| format!("missing string parameter {key}"))
+}
+
+impl Command {
+    pub fn parse(command: &str, params: &Value) -> Result<Self, String> {
+        match command {
+            "schema" => Ok(Command::Schema),
+            "list_apps" => Ok(Command::ListApps),
+            "list_columns" => Ok(Command::ListColumns(
+                string_param(params, "app").ok().map(str::to_owned),
+            )),
+            "open" => OpenView::parse(params).map(Command::Open),
+            "publish" => {
+                let event = params
+                    .get("event")
+                    .ok_or_else(|| "missing parameter event".to_owned())?;
+                parse_event(event).map(Command::Publish)
+            }
+            _ => Err(format!("unknown command {command}")),
+        }
+    }
+}
+
+impl OpenView {
+    fn parse(params: &Value) -> Result<Self, String> {
+        let optional = |key| string_param(params, key).unwrap_or_default().to_owned();
+        match string_param(params, "view")? {
+            "profile" => Pubkey::parse(string_param(params, "pubkey")?)
+                .map(OpenView::Profile)
+                .map_err(|_| "pubkey isn't hex or an npub".to_owned()),
+            "thread" => {
+                let note = string_param(params, "note")?;
+                NoteId::from_hex(note)
+                    .ok()
+                    .or_else(|| NoteId::from_bech(note))
+                    .map(OpenView::Thread)
+                    .ok_or_else(|| "note isn't hex or a note1".to_owned())
+            }
+            "address" => Address::from_bech(string_param(params, "naddr")?)
+                .map(OpenView::Address)
+                .ok_or_else(|| "naddr isn't an naddr".to_owned()),
+            "compose" => Ok(OpenView::Compose(optional("text"))),
+            "app" => Ok(OpenView::App(string_param(params, "app")?.to_owned())),
+            "settings" => Ok(OpenView::Settings(optional("search"))),
+            view => Err(format!("unknown view {view}")),
+        }
+    }
+}
+
+fn parse_event(event: &Value) -> Result<UnsignedNote, String> {
+    let kind = event
+        .get("kind")
+        .and_then(|k| k.as_u64())
+        .filter(|k| *k <= u16::MAX as u64)
+        .ok_or_else(|| "the event needs a kind from 0 to 65535".to_owned())?;
+    if replaces_account_data(kind) {
+        return Err(format!(
+            "kind {kind} replaces or deletes the account's data, publish it from the app"
+        ));
+    }
+    let content = event
+        .get("content")
+        .and_then(|c| c.as_str())
+        .unwrap_or_default()
+        .to_owned();
+    let tags = match event.get("tags") {
+        None => vec![],
+        Some(tags) => serde_json::from_value::<Vec<Vec<String>>>(tags.clone())
+            .map_err(|_| "tags must be a list of lists of strings".to_owned())?,
+    };
+    let created_at = match event.get("created_at") {
+        None => unix_now(),
+        Some(created_at) => created_at
+            .as_u64()
+            .ok_or_else(|| "created_at must be unix seconds".to_owned())?,
+    };
+
+    Ok(UnsignedNote {
+        // filled in with the selected account's when it's signed
+        pubkey: [0; 32],
+        created_at,
+        kind: kind as u32,
+        tags,
+        content,
+    })
+}
+
+/// Profiles, follows, deletions and replaceable lists like the relay list.
+/// A script getting one wrong would wipe out what the account has.
+fn replaces_account_data(kind: u64) -> bool {
+    matches!(kind, 0 | 3 | 5 | 10000..=19999)
+}
+
+/// A command waiting for the UI to run it
+pub struct Request {
+    pub command: Command,
+    reply: Sender<Result<Value, String>>,
+}
+
+impl Request {
+    pub fn reply(self, result: Result<Value, String>) {
+        // the script may have hung up already
+        let _ = self.reply.send(result);
+    }
+}
+
+struct Listener {
+    requests: Receiver<Request>,
+    stop: Arc<AtomicBool>,
+    storage: AppStorage,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.storage.remove(INFO_KEY);
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(self.storage.dir().join(SOCKET_FILE));
+        info!("control api stopped");
+    }
+}
+
+/// Starts and stops the API with its setting, and hands over what
+/// scripts asked for
+#[derive(Default)]
+pub struct Control {
+    listener: Option<Listener>,
+    /// Starting failed, don't try again until the setting is turned off
+    /// and on
+    failed: bool,
+}
+
+impl Control {
+    /// Call every frame. The requests have to be answered.
+    pub fn poll(
+        &mut self,
+        ctx: &egui::Context,
+        settings: &Settings,
+        path: &DataPath,
+    ) -> Vec<Request> {
+        let enabled = settings.bool(APP, CONTROL_API);
+        if !enabled {
+            self.listener = None;
+            self.failed = false;
+        } else if self.listener.is_none() && !self.failed {
+            match start(ctx, AppStorage::new(path, STORAGE_ID)) {
+                Ok(listener) => self.listener = Some(listener),
+                Err(err) => {
+                    error!("couldn't start the control api: {err}");
+                    self.failed = true;
+                }
+            }
+        }
+
+        self.listener
+            .as_ref()
+            .map(|listener| listener.requests.try_iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+fn start(ctx: &egui::Context, storage: AppStorage) -> std::io::Result<Listener> {
+    create_private_dir(storage.dir())?;
+    let token = FullKeypair::generate().secret_key.to_secret_hex();
+    let (sender, requests) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let endpoint = bind(storage.dir(), ctx, &token, sender, stop.clone())?;
+    let info = json!({
+        "version": 1,
+        "endpoint": endpoint,
+        "token": token,
+    });
+    storage
+        .set(INFO_KEY, &INFO_SCHEMA, &info)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))?;
+
+    info!("control api listening");
+    Ok(Listener {
+        requests,
+        stop,
+        storage,
+    })
+}
+
+/// Only we can read the token or connect to the socket
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn bind(
+    dir: &Path,
+    ctx: &egui::Context,
+    token: &str,
+    sender: Sender<Request>,
+    stop: Arc<AtomicBool>,
+) -> std::io::Result<Value> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let socket = dir.join(SOCKET_FILE);
+    if UnixStream::connect(&socket).is_ok() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AddrInUse,
+            "another notedeck is serving the control api",
+        ));
+    }
+    // nobody is listening, it was left behind by one that didn't clean up
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)?;
+    listener.set_nonblocking(true)?;
+
+    let endpoint = json!({ "socket": socket });
+    let ctx = ctx.clone();
+    let token = token.to_owned();
+    std::thread::spawn(move || {
+        accept_loop(&stop, || {
+            let (stream, _) = listener.accept()?;
+            stream.set_nonblocking(false)?;
+            spawn_client(stream, &ctx, &token, &sender);
+            Ok(())
+        })
+    });
+    Ok(endpoint)
+}
+
+#[cfg(not(unix))]
+fn bind(
+    _dir: &Path,
+    ctx: &egui::Context,
+    token: &str,
+    sender: Sender<Request>,
+    stop: Arc<AtomicBool>,
+) -> std::io::Result<Value> {
+    use std::net::TcpListener;
+
+    // any free port, only reachable from this machine
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    listener.set_nonblocking(true)?;
+
+    let endpoint = json!({ "address": listener.local_addr()?.to_string() });
+    let ctx = ctx.clone();
+    let token = token.to_owned();
+    std::thread::spawn(move || {
+        accept_loop(&stop, || {
+            let (stream, _) = listener.accept()?;
+            stream.set_nonblocking(false)?;
+            spawn_client(stream, &ctx, &token, &sender);
+            Ok(())
+        })
+    });
+    Ok(endpoint)
+}
+
+fn spawn_client<S>(stream: S, ctx: &egui::Context, token: &str, sender: &Sender<Request>)
+where
+    S: Send + 'static,
+    for<'a> &'a S: Read + Write,
+{
+    let ctx = ctx.clone();
+    let token = token.to_owned();
+    let sender = sender.clone();
+    std::thread::spawn(move || serve(&stream, &stream, &ctx, &token, &sender));
+}
+
+fn accept_loop(stop: &AtomicBool, mut accept: impl FnMut() -> std::io::Result<()>) {
+    while !stop.load(Ordering::Relaxed) {
+        match accept() {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(ACCEPT_POLL)
+            }
+            Err(err) => warn!("control api: couldn't accept a connection: {err}"),
+        }
+    }
+}
+
+/// Answer requests on one connection until the script hangs up
+fn serve(
+    reader: impl Read,
+    mut writer: impl Write,
+    ctx: &egui::Context,
+    token: &str,
+    sender: &Sender<Request>,
+) {
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match (&mut reader).take(MAX_LINE as u64 + 1).read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        if line.len() > MAX_LINE {
+            // the rest of it would be read as more requests, so hang up
+            let _ = writeln!(
+                writer,
+                "{}",
+                json!({ "ok": false, "error": "request too long" })
+            );
+            return;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = respond(&line, ctx, token, sender);
+        if writeln!(writer, "{response}").is_err() {
+            return;
+        }
+    }
+}
+
+fn respond(line: &str, ctx: &egui::Context, token: &str, sender: &Sender<Request>) -> Value {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(err) => return json!({ "ok": false, "error": format!("bad json: {err}") }),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let result = run(&request, ctx, token, sender);
+
+    match result {
+        Ok(result) => json!({ "id": id, "ok": true, "result": result }),
+        Err(error) => json!({ "id": id, "ok": false, "error": error }),
+    }
+}
+
+fn run(
+    request: &Value,
+    ctx: &egui::Context,
+    token: &str,
+    sender: &Sender<Request>,
+) -> Result<Value, String> {
+    let given = request.get("token").and_then(|t| t.as_str());
+    if !given.is_some_and(|given| same_token(given, token)) {
+        return Err("bad token".to_owned());
+    }
+
+    let command = string_param(request, "command")?;
+    let command = Command::parse(command, request.get("params").unwrap_or(&Value::Null))?;
+    if command == Command::Schema {
+        return serde_json::from_str(SCHEMA).map_err(|e| e.to_string());
+    }
+
+    let (reply, replied) = mpsc::channel();
+    sender
+        .send(Request { command, reply })
+        .map_err(|_| "notedeck is shutting down".to_owned())?;
+    ctx.request_repaint();
+
+    replied
+        .recv_timeout(REPLY_TIMEOUT)
+        .map_err(|_| "notedeck didn't answer".to_owned())?
+}
+
+/// Compared without stopping at the first difference, so response times
+/// don't give the token away
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_parses() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        assert_eq!(schema["title"], "Notedeck control API");
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            Command::parse("list_columns", &Value::Null),
+            Ok(Command::ListColumns(None))
+        );
+        assert_eq!(
+            Command::parse("open", &json!({ "view": "compose", "text": "gm" })),
+            Ok(Command::Open(OpenView::Compose("gm".to_owned())))
+        );
+        assert!(Command::parse("open", &json!({ "view": "profile", "pubkey": "nope" })).is_err());
+        assert!(Command::parse("delete_everything", &Value::Null).is_err());
+
+        let publish = Command::parse(
+            "publish",
+            &json!({ "event": {
+                "kind": 31923,
+                "content": "meetup",
+                "tags": [["d", "meetup"], ["start", "1700000000"]],
+                "created_at": 1700000000,
+            }}),
+        );
+        let Ok(Command::Publish(note)) = publish else {
+            panic!("expected a publish command, got {publish:?}");
+        };
+        assert_eq!(note.kind, 31923);
+        assert_eq!(note.tags[1], ["start", "1700000000"]);
+        assert_eq!(note.created_at, 1700000000);
+
+        assert!(Command::parse("publish", &json!({ "event": { "kind": 70000 } })).is_err());
+        for kind in [0, 3, 5, 10002] {
+            assert!(Command::parse("publish", &json!({ "event": { "kind": kind } })).is_err());
+        }
+        assert!(
+            Command::parse("publish", &json!({ "event": { "kind": 1, "tags": [1] } })).is_err()
+        );
+    }
+
+    #[test]
+    fn test_bad_token() {
+        let ctx = egui::Context::default();
+        let (sender, _requests) = mpsc::channel();
+        let response = respond(
+            r#"{"id": 7, "token": "wrong", "command": "schema"}"#,
+            &ctx,
+            "right",
+            &sender,
+        );
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["ok"], false);
+
+        let response = respond(
+            r#"{"token": "right", "command": "schema"}"#,
+            &ctx,
+            "right",
+            &sender,
+        );
+        assert_eq!(response["ok"], true);
+    }
+
+    #[test]
+    fn test_long_line_rejected() {
+        let ctx = egui::Context::default();
+        let (sender, requests) = mpsc::channel();
+        let mut input = "x".repeat(MAX_LINE + 10);
+        input.push_str("\n{\"token\": \"right\", \"command\": \"schema\"}\n");
+
+        let mut output = vec![];
+        serve(input.as_bytes(), &mut output, &ctx, "right", &sender);
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        let response: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(response["error"], "request too long");
+        assert!(requests.try_recv().is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use egui::RichText;
+use notedeck::{
+    crash::{self, CrashReport},
+    tr, DataPath,
+};
+
+/// Offers the report from the last crash, if there was one, so it can be
+/// copied into a bug report
+pub struct CrashWindow {
+    report: Option<CrashReport>,
+}
+
+impl CrashWindow {
+    pub fn new(path: &DataPath) -> Self {
+        CrashWindow {
+            report: crash::pending_report(path),
+        }
+    }
+
+    pub fn update(&mut self, ctx: &egui::Context) {
+        let Some(report) = &self.report else {
+            return;
+        };
+
+        let mut dismissed = false;
+        egui::Window::new(tr!("crash-title"))
+            .collapsible(false)
+            .default_width(560.0)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(tr!("crash-body"));
+                ui.weak(report.path.display().to_string());
+                ui.add_space(8.0);
+
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        ui.label(RichText::new(&report.contents).monospace().small());
+                    });
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("crash-copy")).clicked() {
+                        ui.ctx().copy_text(report.contents.clone());
+                    }
+                    if ui.button(tr!("crash-dismiss")).clicked() {
+                        dismissed = true;
+                    }
+                });
+            });
+
+        if dismissed {
+            if let Some(report) = self.report.take() {
+                crash::dismiss(&report);
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! What the caches that outlive a frame are holding, the budget that
+//! keeps decoded media from piling up over a long session, and how long
+//! startup took. Ctrl+Shift+M toggles the panel.
+
+use notedeck::{
+    settings::{Setting, SettingsSection},
+    tr, ImageCache, Settings,
+};
+
+use crate::{logs::APP, startup::Startup};
+
+pub const MEDIA_BUDGET: &str = "media-budget";
+
+const MB: usize = 1 << 20;
+
+pub fn register(settings: &mut Settings) {
+    settings.register(
+        SettingsSection::new(APP, tr!("settings-memory")).setting(
+            Setting::slider(
+                MEDIA_BUDGET,
+                tr!("settings-media-budget"),
+                64.0..=4096.0,
+                64.0,
+                512.0,
+            )
+            .description(tr!("settings-media-budget-hint")),
+        ),
+    );
+}
+
+/// The media budget in bytes
+pub fn media_budget(settings: &Settings) -> usize {
+    settings.number(APP, MEDIA_BUDGET).max(64.0) as usize * MB
+}
+
+fn megabytes(bytes: usize) -> String {
+    format!("{:.1} MB", bytes as f64 / MB as f64)
+}
+
+fn millis(duration: std::time::Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[derive(Default)]
+pub struct Diagnostics {
+    open: bool,
+    /// Images dropped to stay in budget this session
+    evicted: usize,
+    /// The puffin window, opt-in since it's big and busy
+    #[cfg(feature = "profiling")]
+    profiler: bool,
+}
+
+impl Diagnostics {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Bring the image cache back under budget, then show the panel if
+    /// it's open
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        img_cache: &mut ImageCache,
+        budget: usize,
+        startup: &Startup,
+    ) {
+        self.evicted += img_cache.end_frame(budget);
+
+        #[cfg(feature = "profiling")]
+        if self.profiler {
+            self.profiler = puffin_egui::profiler_window(ctx);
+        }
+
+        if ctx.input_mut(|i| {
+            i.consume_key(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::M,
+            )
+        }) {
+            self.open = !self.open;
+        }
+
+        if !self.open {
+            return;
+        }
+
+        let (images, image_bytes) = img_cache.usage();
+        let (textures, texture_bytes) = ctx
+            .tex_manager()
+            .read()
+            .allocated()
+            .fold((0, 0), |(count, bytes), (_, meta)| {
+                (count + 1, bytes + meta.bytes_used())
+            });
+        let galleys = ctx.fonts(|fonts| fonts.num_galleys_in_cache());
+
+        let mut open = self.open;
+        egui::Window::new(tr!("diagnostics-title"))
+            .open(&mut open)
+            .default_width(360.0)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("diagnostics")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label(tr!("diagnostics-images", count = images));
+                        ui.label(megabytes(image_bytes));
+                        ui.end_row();
+
+                        ui.label(tr!("diagnostics-textures", count = textures));
+                        ui.label(megabytes(texture_bytes));
+                        ui.end_row();
+
+                        ui.label(tr!("diagnostics-galleys"));
+                        ui.label(galleys.to_string());
+                        ui.end_row();
+
+                        ui.label(tr!("diagnostics-evicted"));
+                        ui.label(self.evicted.to_string());
+                        ui.end_row();
+                    });
+
+                ui.add_space(8.0);
+                ui.add(
+                    egui::ProgressBar::new((image_bytes as f32 / budget as f32).min(1.0)).text(
+                        tr!(
+                            "diagnostics-budget",
+                            used = megabytes(image_bytes),
+                            budget = megabytes(budget)
+                        ),
+                    ),
+                );
+
+                ui.add_space(8.0);
+                ui.collapsing(
+                    tr!("diagnostics-startup", time = millis(startup.total())),
+                    |ui| {
+                        egui::Grid::new("diagnostics-startup")
+                            .num_columns(2)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (phase, took) in startup.phases() {
+                                    ui.label(*phase);
+                                    ui.label(millis(*took));
+                                    ui.end_row();
+                                }
+                            });
+                    },
+                );
+
+                #[cfg(feature = "profiling")]
+                ui.checkbox(&mut self.profiler, tr!("diagnostics-profiler"));
+            });
+        self.open = open;
+    }
+}
@@ -118,7 +118,8 @@ pub fn setup_fonts(ctx: &egui::Context) {
     let mut bold = vec!["OnestBold".to_owned()];
     bold.extend(base_fonts.clone());
 
-    let emoji = vec!["NotoEmoji".to_owned()];
+    // emoji sequences are drawn as their parts, some of which aren't emoji
+    let emoji = vec!["NotoEmoji".to_owned(), "DejaVuSans".to_owned()];
 
     families.insert(egui::FontFamily::Proportional, proportional);
     families.insert(egui::FontFamily::Monospace, mono);
@@ -0,0 +1,629 @@
+//! Running without a window, eg. on a server or to keep nostrdb warm for
+//! when the app is opened. `--headless` connects to the selected
+//! account's relays, ingests what they send into nostrdb, runs the
+//! notification rules and sends whatever is queued or published through
+//! the control API. It uses the same data directory as the app, so
+//! opening the app asks a running headless instance to stop first.
+//!
+//! While it runs it keeps its status in app storage, which
+//! `--headless-status` prints. `--headless-stop` asks it to finish
+//! up, as does ctrl-c or SIGTERM.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use enostr::{ClientMessage, Pubkey, RelayEvent, RelayMessage, RelayPool, RelayStatus};
+use nostrdb::{Ndb, Subscription, Transaction};
+use notedeck::{
+    storage::Schema, time::unix_now, Accounts, AppStorage, Args, Capability, DataPath,
+    DataPathType, Directory, FileKeyStorage, KeyStorageType, PermissionError, Settings, UnknownIds,
+    WebOfTrust, WotParams,
+};
+use notedeck_columns::notifications::{self, ThreadNotifications};
+use notedeck_columns::storage::{load_notification_settings, load_thread_notifications};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, error, info, warn};
+
+use crate::app::open_ndb;
+use crate::control::{self, Command, Control};
+use crate::{locale, logs};
+
+const STORAGE_ID: &str = "headless";
+const STATUS_KEY: &str = "status";
+static STATUS_SCHEMA: Schema = Schema::INITIAL;
+/// Dropped in the settings directory to ask a running instance to stop
+const STOP_FILE: &str = "headless.stop";
+const NOTIFICATIONS_SUBID: &str = "headless-notifications";
+
+const TICK: Duration = Duration::from_millis(100);
+const STATUS_EVERY: Duration = Duration::from_secs(5);
+/// A status that hasn't been written for this long was left by an
+/// instance that died
+const STALE_AFTER: u64 = 30;
+/// How long we keep sending queued messages once we're asked to stop
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+const STOP_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadlessCommand {
+    Run,
+    Status,
+    Stop,
+}
+
+impl HeadlessCommand {
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        args.iter().find_map(|arg| match arg.as_str() {
+            "--headless" => Some(HeadlessCommand::Run),
+            "--headless-status" => Some(HeadlessCommand::Status),
+            "--headless-stop" => Some(HeadlessCommand::Stop),
+            _ => None,
+        })
+    }
+}
+
+/// Run `command` and return the process exit code
+pub fn main(command: HeadlessCommand, path: &DataPath, args: &[String]) -> i32 {
+    let parsed = Args::parse(args);
+    let path = data_path(path, &parsed);
+
+    match command {
+        HeadlessCommand::Run => run(&path, &parsed),
+        HeadlessCommand::Status => print_status(&path),
+        HeadlessCommand::Stop => stop(&path),
+    }
+}
+
+/// Call before opening the window. The app and a headless instance can't
+/// share a data directory, so a running one is asked to stop first.
+/// Returns false if it's still running.
+pub fn take_over(path: &DataPath, args: &[String]) -> bool {
+    let path = data_path(path, &Args::parse(args));
+    if !is_running(&path) {
+        return true;
+    }
+
+    info!("headless: stopping the running instance so the app can start");
+    match request_stop(&path) {
+        Ok(()) => true,
+        Err(err) => {
+            error!("headless: {err}");
+            false
+        }
+    }
+}
+
+fn data_path(path: &DataPath, args: &Args) -> DataPath {
+    args.datapath
+        .as_ref()
+        .map(DataPath::new)
+        .unwrap_or_else(|| path.clone())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct RelayState {
+    url: String,
+    connected: bool,
+    /// Messages held back by the relay's rate limit
+    #[serde(default)]
+    queued: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Status {
+    pid: u32,
+    version: String,
+    started_at: u64,
+    updated_at: u64,
+    #[serde(default)]
+    stopped: bool,
+    /// npub of the selected account
+    #[serde(default)]
+    account: Option<String>,
+    #[serde(default)]
+    relays: Vec<RelayState>,
+    #[serde(default)]
+    events: u64,
+    #[serde(default)]
+    notifications: u64,
+    #[serde(default)]
+    published: u64,
+}
+
+impl Status {
+    fn new(now: u64) -> Self {
+        Status {
+            pid: std::process::id(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            started_at: now,
+            updated_at: now,
+            stopped: false,
+            account: None,
+            relays: vec![],
+            events: 0,
+            notifications: 0,
+            published: 0,
+        }
+    }
+
+    fn is_running(&self, now: u64) -> bool {
+        !self.stopped && now.saturating_sub(self.updated_at) <= STALE_AFTER
+    }
+}
+
+fn settings_dir(path: &DataPath) -> PathBuf {
+    path.path(DataPathType::Setting)
+}
+
+fn read_status(path: &DataPath) -> Option<Status> {
+    AppStorage::new(path, STORAGE_ID).get(STATUS_KEY, &STATUS_SCHEMA)
+}
+
+fn write_status(path: &DataPath, status: &Status) {
+    if let Err(err) = AppStorage::new(path, STORAGE_ID).set(STATUS_KEY, &STATUS_SCHEMA, status) {
+        error!("headless: couldn't write status: {err}");
+    }
+}
+
+/// Set by ctrl-c and SIGTERM
+static STOP: AtomicBool = AtomicBool::new(false);
+
+fn handle_signals() {
+    if let Err(err) = ctrlc::set_handler(|| STOP.store(true, Ordering::Relaxed)) {
+        warn!("headless: couldn't handle ctrl-c: {err}");
+    }
+}
+
+fn run(path: &DataPath, args: &Args) -> i32 {
+    let now = unix_now();
+    if let Some(status) = read_status(path).filter(|status| status.is_running(now)) {
+        eprintln!("notedeck is already running headless (pid {})", status.pid);
+        return 1;
+    }
+
+    let stop_file = settings_dir(path).join(STOP_FILE);
+    let _ = std::fs::remove_file(&stop_file);
+    handle_signals();
+
+    let mut headless = Headless::new(path, args);
+    info!("headless: running, stop with ctrl-c or --headless-stop");
+
+    let mut status_written: Option<Instant> = None;
+    while !STOP.load(Ordering::Relaxed) && !stop_file.exists() {
+        headless.update();
+
+        if !status_written.is_some_and(|written| written.elapsed() < STATUS_EVERY) {
+            status_written = Some(Instant::now());
+            headless.write_status();
+        }
+
+        thread::sleep(TICK);
+    }
+
+    let _ = std::fs::remove_file(&stop_file);
+    headless.shutdown();
+    0
+}
+
+/// The relay pool, nostrdb and notifications of the app, minus the app
+struct Headless {
+    path: DataPath,
+    settings: Settings,
+    ndb: Ndb,
+    accounts: Accounts,
+    pool: RelayPool,
+    /// Nothing is drawn with it, the shared code just wants something to
+    /// wake up when things happen. We poll on a timer instead.
+    ctx: egui::Context,
+    control: Control,
+    account: Option<Pubkey>,
+    notification_sub: Option<Subscription>,
+    subid: String,
+    threads: ThreadNotifications,
+    /// For notification rules that look at who's nearby
+    wot: WebOfTrust,
+    status: Status,
+}
+
+impl Headless {
+    fn new(path: &DataPath, args: &Args) -> Self {
+        notedeck::crash::install_panic_hook(path);
+        notedeck::i18n::init(path, locale::system_locale().as_deref());
+
+        let mut settings = Settings::new(path);
+        logs::register(&mut settings);
+        control::register(&mut settings);
+
+        let dbpath = args.dbpath.clone().unwrap_or_else(|| {
+            path.path(DataPathType::Db)
+                .to_str()
+                .expect("db path ok")
+                .to_string()
+        });
+        let _ = std::fs::create_dir_all(&dbpath);
+        let ndb = open_ndb(&dbpath);
+
+        let keystore = if args.use_keystore {
+            KeyStorageType::FileSystem(FileKeyStorage::new(
+                Directory::new(path.path(DataPathType::Keys)),
+                Directory::new(path.path(DataPathType::SelectedKey)),
+            ))
+        } else {
+            KeyStorageType::None
+        };
+        let mut accounts = Accounts::new(keystore, args.relays.clone());
+        control::declare(&mut settings, accounts.permissions_mut());
+        // there's no one to ask for a passphrase, but we can still read
+        if let Err(err) =
+            accounts.unlock_keys_with(notedeck::storage::system_secret_store().as_deref())
+        {
+            warn!("headless: account keys are locked, publishing won't work: {err}");
+        }
+
+        if !args.keys.is_empty() {
+            let mut unknown_ids = UnknownIds::default();
+            let txn = Transaction::new(&ndb).expect("txn");
+            for key in &args.keys {
+                accounts
+                    .add_account(key.clone())
+                    .process_action(&mut unknown_ids, &ndb, &txn);
+            }
+            accounts.select_account(0);
+        }
+
+        Headless {
+            path: path.clone(),
+            settings,
+            ndb,
+            accounts,
+            pool: RelayPool::new(),
+            ctx: egui::Context::default(),
+            control: Control::default(),
+            account: None,
+            notification_sub: None,
+            subid: NOTIFICATIONS_SUBID.to_owned(),
+            threads: ThreadNotifications::default(),
+            wot: WebOfTrust::default(),
+            status: Status::new(unix_now()),
+        }
+    }
+
+    fn update(&mut self) {
+        self.accounts.update(&self.ndb, &mut self.pool, &self.ctx);
+        self.pool.keepalive_ping(|| {});
+        self.pool.flush();
+
+        let selected = self.accounts.get_selected_account().map(|acc| acc.pubkey);
+        if selected != self.account {
+            // per-account settings, like whether scripts may publish
+            self.settings.set_account(selected);
+            self.accounts.permissions_mut().account_changed();
+            self.subscribe_notifications(selected);
+        }
+
+        // NOTE: we don't use the while let loop due to borrow issues
+        #[allow(clippy::while_let_loop)]
+        loop {
+            let ev = if let Some(ev) = self.pool.try_recv() {
+                ev.into_owned()
+            } else {
+                break;
+            };
+
+            match (&ev.event).into() {
+                RelayEvent::Opened => {
+                    self.accounts
+                        .send_initial_filters(&mut self.pool, &ev.relay);
+                    if let Some(account) = &self.account {
+                        let filters = notifications::filters(account, unix_now(), &self.threads);
+                        self.pool
+                            .send_to(&ClientMessage::req(self.subid.clone(), filters), &ev.relay);
+                    }
+                }
+                RelayEvent::Closed => warn!("headless: {} connection closed", &ev.relay),
+                RelayEvent::Error(err) => debug!("headless: relay {} error: {err}", ev.relay),
+                RelayEvent::Other(_) => {}
+                RelayEvent::Message(RelayMessage::Event(_subid, note)) => {
+                    self.status.events += 1;
+                    if let Err(err) = self.ndb.process_event(note) {
+                        error!("headless: error processing event: {err}");
+                    }
+                }
+                RelayEvent::Message(RelayMessage::OK(result)) => {
+                    if result.status() {
+                        debug!("headless: {} took {}", ev.relay, result.event_id());
+                    } else {
+                        warn!(
+                            "headless: {} rejected {}: {}",
+                            ev.relay,
+                            result.event_id(),
+                            result.message()
+                        );
+                    }
+                }
+                RelayEvent::Message(RelayMessage::Notice(msg)) => {
+                    warn!("headless: notice from {}: {msg}", ev.relay)
+                }
+                RelayEvent::Message(RelayMessage::Eose(_)) => {}
+            }
+        }
+
+        self.answer_control();
+        self.notify();
+    }
+
+    fn subscribe_notifications(&mut self, account: Option<Pubkey>) {
+        if let Some(sub) = self.notification_sub.take() {
+            if let Err(err) = self.ndb.unsubscribe(sub) {
+                error!("headless: unsubscribe failed: {err}");
+            }
+            self.pool.unsubscribe(self.subid.clone());
+        }
+
+        self.account = account;
+        self.status.account = account.and_then(|pk| pk.to_bech());
+        let Some(account) = account else {
+            return;
+        };
+
+        self.threads = load_thread_notifications(&self.path, &account);
+        let filters = notifications::filters(&account, unix_now(), &self.threads);
+        match self.ndb.subscribe(&filters) {
+            Ok(sub) => self.notification_sub = Some(sub),
+            Err(err) => error!("headless: subscribe failed: {err}"),
+        }
+        self.pool.subscribe(self.subid.clone(), filters);
+    }
+
+    /// Run the notification rules on what came in. There's no window to
+    /// be focused, so anything that passes is shown.
+    fn notify(&mut self) {
+        let (Some(sub), Some(account)) = (self.notification_sub, self.account) else {
+            return;
+        };
+
+        let note_keys = self.ndb.poll_for_notes(sub, 50);
+        if note_keys.is_empty() {
+            return;
+        }
+
+        // picks up changes made in the app
+        let settings = load_notification_settings(&self.path);
+        self.wot
+            .update(&self.ndb, Some(account.bytes()), WotParams::default());
+
+        let Ok(txn) = Transaction::new(&self.ndb) else {
+            return;
+        };
+
+        for key in note_keys {
+            let Ok(note) = self.ndb.get_note_by_key(&txn, key) else {
+                continue;
+            };
+
+            if let Some(notification) =
+                settings.notification(&self.ndb, &txn, &note, &account, &self.wot, &self.threads)
+            {
+                info!(
+                    "headless: notification: {}: {}",
+                    notification.title, notification.body
+                );
+                self.status.notifications += 1;
+                notifications::show(notification, None);
+            }
+        }
+    }
+
+    /// Scripts can publish through us. Everything else needs the app.
+    fn answer_control(&mut self) {
+        for request in self.control.poll(&self.ctx, &self.settings, &self.path) {
+            let result = match &request.command {
+                Command::Schema => {
+                    serde_json::from_str::<Value>(control::SCHEMA).map_err(|e| e.to_string())
+                }
+                Command::Publish(note) => {
+                    self.accounts
+                        .permissions_mut()
+                        .enter(&self.settings, Some(control::SCRIPTS));
+                    match self.accounts.permissions().check(Capability::Publish) {
+                        // nobody is here to ask, so it has to be allowed already
+                        Err(PermissionError::Asking(_)) => {
+                            Err("allow scripts to publish in the app first".to_owned())
+                        }
+                        _ => self
+                            .accounts
+                            .publish_unsigned(&self.ndb, &mut self.pool, note)
+                            .map(|id| {
+                                self.status.published += 1;
+                                serde_json::json!({ "id": enostr::NoteId::new(id).hex() })
+                            })
+                            .map_err(|e| e.to_string()),
+                    }
+                }
+                _ => Err("not available while running headless".to_owned()),
+            };
+            request.reply(result);
+        }
+    }
+
+    fn write_status(&mut self) {
+        self.status.updated_at = unix_now();
+        self.status.relays = self
+            .pool
+            .relays
+            .iter()
+            .map(|relay| RelayState {
+                url: relay.url().to_owned(),
+                connected: matches!(relay.status(), RelayStatus::Connected),
+                queued: relay.queued(),
+            })
+            .collect();
+        write_status(&self.path, &self.status);
+    }
+
+    fn shutdown(mut self) {
+        info!("headless: stopping");
+
+        self.subscribe_notifications(None);
+
+        // give what's held back by rate limits a chance to go out
+        let deadline = Instant::now() + FLUSH_TIMEOUT;
+        while self.pool.next_flush_in().is_some() && Instant::now() < deadline {
+            self.pool.flush();
+            thread::sleep(TICK);
+        }
+
+        self.status.stopped = true;
+        self.write_status();
+        info!("headless: stopped");
+    }
+}
+
+fn print_status(path: &DataPath) -> i32 {
+    let Some(status) = read_status(path) else {
+        println!("not running");
+        return 1;
+    };
+
+    let now = unix_now();
+    if !status.is_running(now) {
+        let how = if status.stopped { "stopped" } else { "died" };
+        println!(
+            "not running ({how} {} ago)",
+            ago(now.saturating_sub(status.updated_at))
+        );
+        return 1;
+    }
+
+    let connected = status.relays.iter().filter(|r| r.connected).count();
+    println!(
+        "running, pid {}, version {}, up {}",
+        status.pid,
+        status.version,
+        ago(now.saturating_sub(status.started_at))
+    );
+    println!(
+        "account: {}",
+        status.account.as_deref().unwrap_or("none selected")
+    );
+    println!("relays: {connected}/{} connected", status.relays.len());
+    for relay in &status.relays {
+        let state = if relay.connected {
+            "connected"
+        } else {
+            "disconnected"
+        };
+        if relay.queued > 0 {
+            println!("  {} {state}, {} queued", relay.url, relay.queued);
+        } else {
+            println!("  {} {state}", relay.url);
+        }
+    }
+    println!(
+        "events: {}, notifications: {}, published: {}",
+        status.events, status.notifications, status.published
+    );
+    0
+}
+
+fn is_running(path: &DataPath) -> bool {
+    read_status(path).is_some_and(|status| status.is_running(unix_now()))
+}
+
+fn stop(path: &DataPath) -> i32 {
+    if !is_running(path) {
+        println!("not running");
+        return 1;
+    }
+
+    match request_stop(path) {
+        Ok(()) => {
+            println!("stopped");
+            0
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            1
+        }
+    }
+}
+
+/// Ask the running instance to stop and wait for it to finish up
+fn request_stop(path: &DataPath) -> Result<(), String> {
+    std::fs::write(settings_dir(path).join(STOP_FILE), b"")
+        .map_err(|err| format!("couldn't ask it to stop: {err}"))?;
+
+    let started = Instant::now();
+    while started.elapsed() < STOP_TIMEOUT {
+        if read_status(path).is_some_and(|status| status.stopped) {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    Err("it didn't stop in time".to_owned())
+}
+
+fn ago(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(HeadlessCommand::from_args(&args(&["notedeck"])), None);
+        assert_eq!(
+            HeadlessCommand::from_args(&args(&["notedeck", "--headless", "-r", "wss://a"])),
+            Some(HeadlessCommand::Run)
+        );
+        assert_eq!(
+            HeadlessCommand::from_args(&args(&["notedeck", "--headless-status"])),
+            Some(HeadlessCommand::Status)
+        );
+        assert_eq!(
+            HeadlessCommand::from_args(&args(&["notedeck", "--headless-stop"])),
+            Some(HeadlessCommand::Stop)
+        );
+    }
+
+    #[test]
+    fn test_status_freshness() {
+        let mut status = Status::new(1000);
+        assert!(status.is_running(1000 + STALE_AFTER));
+        assert!(!status.is_running(1001 + STALE_AFTER));
+
+        status.stopped = true;
+        assert!(!status.is_running(1000));
+
+        // older status files without the newer fields still load
+        let old: Status = serde_json::from_str(
+            r#"{"pid": 1, "version": "0.1", "started_at": 1, "updated_at": 2}"#,
+        )
+        .expect("status");
+        assert!(!old.stopped);
+        assert!(old.relays.is_empty());
+    }
+
+    #[test]
+    fn test_ago() {
+        assert_eq!(ago(5), "5s");
+        assert_eq!(ago(125), "2m");
+        assert_eq!(ago(3 * 3600 + 120), "3h 2m");
+        assert_eq!(ago(2 * 86400 + 3600), "2d 1h");
+    }
+}
@@ -0,0 +1,195 @@
+use egui::RichText;
+use notedeck::{tr, Accounts, KeyProtection, SecretStore};
+use tracing::error;
+
+/// Asks for the passphrase when account keys are locked on start, and lets
+/// the user choose how keys are protected from settings
+pub struct KeyLock {
+    secrets: Option<Box<dyn SecretStore>>,
+    passphrase: String,
+    confirm: String,
+    error: Option<String>,
+    /// The user chose to carry on with locked keys
+    skipped: bool,
+    settings_open: bool,
+}
+
+impl KeyLock {
+    /// `error` is why the keys couldn't be unlocked on start, if they
+    /// couldn't
+    pub fn new(secrets: Option<Box<dyn SecretStore>>, error: Option<String>) -> Self {
+        KeyLock {
+            secrets,
+            passphrase: String::new(),
+            confirm: String::new(),
+            error,
+            skipped: false,
+            settings_open: false,
+        }
+    }
+
+    pub fn open_settings(&mut self) {
+        self.settings_open = true;
+        self.error = None;
+        self.passphrase.clear();
+        self.confirm.clear();
+    }
+
+    pub fn update(&mut self, ctx: &egui::Context, accounts: &mut Accounts) {
+        if accounts.keys_locked() && !self.skipped {
+            self.unlock_window(ctx, accounts);
+        } else if self.settings_open {
+            self.settings_window(ctx, accounts);
+        }
+    }
+
+    fn unlock_window(&mut self, ctx: &egui::Context, accounts: &mut Accounts) {
+        let protection = accounts.key_protection().unwrap_or_default();
+        egui::Window::new(tr!("key-lock-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                if protection == KeyProtection::Passphrase {
+                    ui.label(tr!("key-lock-body"));
+                    ui.add_space(8.0);
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.passphrase)
+                            .password(true)
+                            .hint_text(tr!("key-lock-passphrase"))
+                            .desired_width(f32::INFINITY),
+                    );
+                    response.request_focus();
+                    let entered =
+                        response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                    self.error_label(ui);
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button(tr!("key-lock-unlock")).clicked() || entered {
+                            let result = accounts.unlock_keys(&self.passphrase);
+                            self.finish(result, tr!("key-lock-wrong-passphrase"));
+                        }
+                        if ui.button(tr!("key-lock-skip")).clicked() {
+                            self.skipped = true;
+                        }
+                    });
+                } else {
+                    // the keychain had the password and didn't give it to us
+                    ui.label(tr!("key-lock-system-failed"));
+                    self.error_label(ui);
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button(tr!("key-lock-retry")).clicked() {
+                            let result = accounts.unlock_keys_with(self.secrets.as_deref());
+                            let message = result.as_ref().err().map(|e| e.to_string());
+                            self.finish(result, message.unwrap_or_default());
+                        }
+                        if ui.button(tr!("key-lock-skip")).clicked() {
+                            self.skipped = true;
+                        }
+                    });
+                }
+                ui.weak(tr!("key-lock-skip-hint"));
+            });
+    }
+
+    fn settings_window(&mut self, ctx: &egui::Context, accounts: &mut Accounts) {
+        let Some(protection) = accounts.key_protection() else {
+            // keys aren't kept on disk, so there's nothing to protect
+            self.settings_open = false;
+            return;
+        };
+
+        let mut open = self.settings_open;
+        egui::Window::new(tr!("key-protection-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(380.0)
+            .show(ctx, |ui| {
+                if accounts.keys_locked() {
+                    ui.label(RichText::new(tr!("key-protection-locked")).strong());
+                    ui.weak(tr!("key-protection-unlock-first"));
+                    return;
+                }
+
+                let status = match (protection, &self.secrets) {
+                    (KeyProtection::None, _) => tr!("key-protection-none"),
+                    (KeyProtection::System, Some(secrets)) => {
+                        tr!("key-protection-system", store = secrets.name())
+                    }
+                    (KeyProtection::System, None) => tr!("key-protection-system-missing"),
+                    (KeyProtection::Passphrase, _) => tr!("key-protection-passphrase"),
+                };
+                ui.label(RichText::new(status).strong());
+                ui.add_space(8.0);
+
+                if let Some(secrets) = &self.secrets {
+                    if protection != KeyProtection::System
+                        && ui
+                            .button(tr!("key-protection-use-system", store = secrets.name()))
+                            .clicked()
+                    {
+                        let result = accounts.use_secret_store(secrets.as_ref());
+                        let message = result.as_ref().err().map(|e| e.to_string());
+                        self.finish(result, message.unwrap_or_default());
+                    }
+                    ui.add_space(8.0);
+                }
+
+                ui.label(tr!("key-protection-passphrase-hint"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.passphrase)
+                        .password(true)
+                        .hint_text(tr!("key-protection-new-passphrase"))
+                        .desired_width(f32::INFINITY),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.confirm)
+                        .password(true)
+                        .hint_text(tr!("key-protection-confirm"))
+                        .desired_width(f32::INFINITY),
+                );
+
+                let matching = !self.passphrase.is_empty() && self.passphrase == self.confirm;
+                if !self.confirm.is_empty() && !matching {
+                    ui.colored_label(ui.visuals().warn_fg_color, tr!("key-protection-mismatch"));
+                }
+                if ui
+                    .add_enabled(
+                        matching,
+                        egui::Button::new(tr!("key-protection-set-passphrase")),
+                    )
+                    .clicked()
+                {
+                    let result = accounts.use_passphrase(&self.passphrase, self.secrets.as_deref());
+                    let message = result.as_ref().err().map(|e| e.to_string());
+                    self.finish(result, message.unwrap_or_default());
+                }
+
+                self.error_label(ui);
+            });
+        self.settings_open = open;
+    }
+
+    fn error_label(&self, ui: &mut egui::Ui) {
+        if let Some(err) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+    }
+
+    /// Clear the form when something worked, otherwise show `message`
+    fn finish(&mut self, result: notedeck::Result<()>, message: String) {
+        match result {
+            Ok(()) => {
+                self.error = None;
+                self.passphrase.clear();
+                self.confirm.clear();
+            }
+            Err(err) => {
+                error!("key protection: {err}");
+                self.error = Some(message);
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! An app that isn't built until it's first needed, so apps that aren't
+//! opened don't slow down startup. Until then only its manifest exists,
+//! which is all the launcher and settings need.
+
+use notedeck::{AccountChange, App, AppCommand, AppContext, AppManifest, AppView};
+use tracing::info;
+
+pub type AppInit = Box<dyn FnOnce(&mut AppContext<'_>) -> Box<dyn App>>;
+
+pub struct LazyApp {
+    manifest: AppManifest,
+    init: Option<AppInit>,
+    app: Option<Box<dyn App>>,
+}
+
+impl LazyApp {
+    pub fn new(manifest: AppManifest, init: AppInit) -> Self {
+        LazyApp {
+            manifest,
+            init: Some(init),
+            app: None,
+        }
+    }
+
+    fn app(&mut self, ctx: &mut AppContext<'_>) -> &mut Box<dyn App> {
+        if let Some(init) = self.init.take() {
+            #[cfg(feature = "profiling")]
+            puffin::profile_scope!("lazy app init", self.manifest.id);
+
+            let start = std::time::Instant::now();
+            let mut app = init(ctx);
+            // it missed being told about the account at startup
+            let current = ctx.accounts.get_selected_account().map(|acc| acc.pubkey);
+            app.account_changed(
+                ctx,
+                AccountChange {
+                    previous: None,
+                    current,
+                },
+            );
+            info!("started {} in {:?}", self.manifest.id, start.elapsed());
+            self.app = Some(app);
+        }
+
+        self.app.as_mut().expect("app started")
+    }
+}
+
+impl App for LazyApp {
+    fn update(&mut self, ctx: &mut AppContext<'_>, ui: &mut egui::Ui) {
+        self.app(ctx).update(ctx, ui);
+    }
+
+    fn manifest(&self) -> Option<AppManifest> {
+        Some(self.manifest.clone())
+    }
+
+    fn command(&mut self, ctx: &mut AppContext<'_>, command: AppCommand) {
+        self.app(ctx).command(ctx, command);
+    }
+
+    fn account_changed(&mut self, ctx: &mut AppContext<'_>, change: AccountChange) {
+        // an app that hasn't started yet hears about the account when it does
+        if let Some(app) = &mut self.app {
+            app.account_changed(ctx, change);
+        }
+    }
+
+    fn views(&self, ctx: &mut AppContext<'_>) -> Vec<AppView> {
+        // nothing to show before it's started
+        self.app
+            .as_ref()
+            .map(|app| app.views(ctx))
+            .unwrap_or_default()
+    }
+
+    fn unread_count(&self) -> usize {
+        self.app.as_ref().map(|app| app.unread_count()).unwrap_or(0)
+    }
+}
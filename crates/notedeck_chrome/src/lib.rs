@@ -1,13 +1,38 @@
 pub mod app_size;
 pub mod fonts;
+pub mod locale;
+pub mod logs;
 pub mod persist_zoom;
 pub mod setup;
 pub mod theme;
 pub mod timed_serializer;
+#[cfg(all(feature = "tray", not(target_os = "android")))]
+pub mod tray;
 
 mod app;
+mod apps;
+mod control;
+mod crash_window;
+mod diagnostics;
+#[cfg(not(target_os = "android"))]
+pub mod headless;
+mod key_lock;
+mod lazy_app;
+mod link_handler;
+mod log_viewer;
+mod permission_prompt;
+mod settings_window;
+mod startup;
+mod update_window;
 
 pub use app::Notedeck;
+pub use lazy_app::AppInit;
 
 #[cfg(target_os = "android")]
 mod android;
+#[cfg(target_os = "android")]
+mod android_keystore;
+#[cfg(target_os = "android")]
+mod background;
+#[cfg(target_os = "android")]
+mod camera;
@@ -0,0 +1,316 @@
+//! Every link opened through egui ends up here at the end of the frame,
+//! whether it's in a note, a profile or one of our windows. What happens
+//! next is up to `notedeck::links` and the user's rules in settings.
+
+use egui::RichText;
+use notedeck::{
+    links::{self, LinkBehavior, LinkDecision, LinkRules, NostrLink, PageFetch, Suspicion},
+    settings::{Setting, SettingsSection},
+    toast, tr, AppAction, Settings,
+};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::logs::APP;
+
+pub const LINKS_DEFAULT: &str = "links-default";
+pub const LINKS_BROWSER: &str = "links-browser";
+pub const LINKS_PREVIEW: &str = "links-preview";
+pub const LINKS_COPY: &str = "links-copy";
+pub const LINKS_WEB_CLIENTS: &str = "links-web-clients";
+
+pub fn register(settings: &mut Settings) {
+    let behaviors = LinkBehavior::ALL
+        .iter()
+        .map(|behavior| (behavior.key().to_owned(), label(*behavior)))
+        .collect();
+
+    settings.register(
+        SettingsSection::new(APP, tr!("settings-links"))
+            .setting(
+                Setting::select(
+                    LINKS_DEFAULT,
+                    tr!("settings-links-default"),
+                    behaviors,
+                    LinkBehavior::Browser.key(),
+                )
+                .description(tr!("settings-links-default-hint")),
+            )
+            .setting(
+                Setting::text(LINKS_BROWSER, tr!("settings-links-browser"), "")
+                    .description(tr!("settings-links-domains-hint")),
+            )
+            .setting(Setting::text(
+                LINKS_PREVIEW,
+                tr!("settings-links-preview"),
+                "",
+            ))
+            .setting(Setting::text(LINKS_COPY, tr!("settings-links-copy"), ""))
+            .setting(
+                Setting::toggle(LINKS_WEB_CLIENTS, tr!("settings-links-web-clients"), true)
+                    .description(tr!("settings-links-web-clients-hint")),
+            ),
+    );
+}
+
+fn label(behavior: LinkBehavior) -> String {
+    match behavior {
+        LinkBehavior::Browser => tr!("settings-links-behavior-browser"),
+        LinkBehavior::Preview => tr!("settings-links-behavior-preview"),
+        LinkBehavior::Copy => tr!("settings-links-behavior-copy"),
+    }
+}
+
+fn rules(settings: &Settings) -> LinkRules {
+    let mut domains = vec![];
+    for (key, behavior) in [
+        (LINKS_BROWSER, LinkBehavior::Browser),
+        (LINKS_PREVIEW, LinkBehavior::Preview),
+        (LINKS_COPY, LinkBehavior::Copy),
+    ] {
+        domains.extend(links::domains(&settings.string(APP, key)).map(|d| (d, behavior)));
+    }
+
+    LinkRules {
+        default: LinkBehavior::from_key(&settings.string(APP, LINKS_DEFAULT)),
+        domains,
+        open_web_clients: settings.bool(APP, LINKS_WEB_CLIENTS),
+    }
+}
+
+/// Remember `behavior` for `host`, dropping any other rule it had
+fn remember(settings: &mut Settings, host: &str, behavior: LinkBehavior) {
+    for (key, keeps) in [
+        (LINKS_BROWSER, LinkBehavior::Browser),
+        (LINKS_PREVIEW, LinkBehavior::Preview),
+        (LINKS_COPY, LinkBehavior::Copy),
+    ] {
+        let mut list: Vec<String> = links::domains(&settings.string(APP, key))
+            .filter(|domain| domain != host)
+            .collect();
+        if keeps == behavior {
+            list.push(host.to_owned());
+        }
+        settings.set(APP, key, Value::String(list.join(", ")));
+    }
+}
+
+struct Preview {
+    url: String,
+    host: String,
+    page: PageFetch,
+}
+
+struct Confirm {
+    url: String,
+    host: String,
+    suspicion: Suspicion,
+}
+
+#[derive(Default)]
+pub struct LinkHandler {
+    /// Goes straight to the browser, we already decided on it
+    decided: Option<String>,
+    confirm: Option<Confirm>,
+    preview: Option<Preview>,
+}
+
+impl LinkHandler {
+    /// Open `url` in the browser without going through the rules, eg.
+    /// for nostr links nothing in the app can show
+    pub fn browse(&mut self, ctx: &egui::Context, url: String) {
+        self.decided = Some(url.clone());
+        ctx.open_url(egui::OpenUrl::new_tab(url));
+    }
+
+    /// Call last thing in the frame, so every link opened in it goes
+    /// through here. Links to nostr things come back as actions. Links
+    /// opened from our own windows are past this point and go straight
+    /// out.
+    pub fn update(&mut self, ctx: &egui::Context, settings: &mut Settings) -> Option<AppAction> {
+        let opened = ctx.output_mut(|o| o.open_url.take());
+        let action = match opened {
+            Some(open) if self.decided.as_ref() == Some(&open.url) => {
+                ctx.output_mut(|o| o.open_url = Some(open));
+                None
+            }
+            Some(open) => self.open(ctx, settings, open.url),
+            None => None,
+        };
+        self.decided = None;
+
+        self.confirm_window(ctx, settings);
+        self.preview_window(ctx, settings);
+
+        action
+    }
+
+    fn open(&mut self, ctx: &egui::Context, settings: &Settings, url: String) -> Option<AppAction> {
+        let host = links::link_host(&url).unwrap_or_default();
+        match rules(settings).decide(&url) {
+            LinkDecision::Nostr(link) => {
+                info!("opening {url} in the app");
+                return Some(match link {
+                    NostrLink::Profile(pubkey) => AppAction::OpenProfile(pubkey),
+                    NostrLink::Note(note_id) => AppAction::OpenThread(note_id),
+                    NostrLink::Address(address) => AppAction::OpenAddress(address),
+                });
+            }
+            LinkDecision::Web(behavior) => self.follow(ctx, url, host, behavior),
+            LinkDecision::Confirm(suspicion) => {
+                warn!("{url} looks suspicious: {suspicion:?}");
+                self.confirm = Some(Confirm {
+                    url,
+                    host,
+                    suspicion,
+                });
+            }
+            LinkDecision::Invalid => warn!("not opening {url}, it isn't a link"),
+        }
+        None
+    }
+
+    fn follow(&mut self, ctx: &egui::Context, url: String, host: String, behavior: LinkBehavior) {
+        match behavior {
+            LinkBehavior::Browser => ctx.open_url(egui::OpenUrl::new_tab(url)),
+            LinkBehavior::Copy => {
+                ctx.copy_text(url);
+                toast::info(tr!("link-copied"));
+            }
+            LinkBehavior::Preview => {
+                let page = PageFetch::start(ctx, &url);
+                self.preview = Some(Preview { url, host, page });
+            }
+        }
+    }
+
+    fn confirm_window(&mut self, ctx: &egui::Context, settings: &Settings) {
+        let Some(confirm) = &self.confirm else {
+            return;
+        };
+
+        let mut answer = None;
+        egui::Window::new(tr!("link-confirm-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                let warning = match confirm.suspicion {
+                    Suspicion::Imitates(known) => tr!(
+                        "link-confirm-imitates",
+                        host = confirm.host.as_str(),
+                        known = known
+                    ),
+                    Suspicion::Unicode => {
+                        tr!("link-confirm-unicode", host = confirm.host.as_str())
+                    }
+                };
+                ui.label(RichText::new(warning).strong());
+                ui.add(egui::Label::new(RichText::new(&confirm.url).weak()).wrap());
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("link-confirm-cancel")).clicked() {
+                        answer = Some(false);
+                    }
+                    if ui.button(tr!("link-confirm-open")).clicked() {
+                        answer = Some(true);
+                    }
+                });
+            });
+
+        match answer {
+            Some(true) => {
+                if let Some(confirm) = self.confirm.take() {
+                    let behavior = rules(settings).behavior(&confirm.host);
+                    self.follow(ctx, confirm.url, confirm.host, behavior);
+                }
+            }
+            Some(false) => self.confirm = None,
+            None => {}
+        }
+    }
+
+    fn preview_window(&mut self, ctx: &egui::Context, settings: &mut Settings) {
+        let Some(preview) = &self.preview else {
+            return;
+        };
+
+        let mut open = true;
+        let mut browse = false;
+        let mut always_browse = false;
+        egui::Window::new(tr!("link-preview-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.weak(&preview.host);
+                ui.add_space(4.0);
+
+                match preview.page.result() {
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(tr!("link-preview-loading"));
+                        });
+                    }
+                    Some(Err(err)) => {
+                        ui.label(tr!("link-preview-failed", error = err));
+                    }
+                    Some(Ok(page)) => {
+                        if let Some(site) = &page.site_name {
+                            ui.weak(site);
+                        }
+                        if let Some(title) = &page.title {
+                            ui.heading(title);
+                        }
+                        if let Some(description) = &page.description {
+                            ui.label(RichText::new(description).italics());
+                        }
+                        ui.separator();
+                        egui::ScrollArea::vertical()
+                            .max_height(360.0)
+                            .show(ui, |ui| {
+                                if page.paragraphs.is_empty() {
+                                    ui.weak(tr!("link-preview-no-text"));
+                                }
+                                for paragraph in &page.paragraphs {
+                                    ui.label(paragraph);
+                                    ui.add_space(6.0);
+                                }
+                            });
+                    }
+                }
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("link-preview-open")).clicked() {
+                        browse = true;
+                    }
+                    if ui.button(tr!("link-preview-copy")).clicked() {
+                        ctx.copy_text(preview.url.clone());
+                        toast::info(tr!("link-copied"));
+                    }
+                });
+                if ui
+                    .small_button(tr!(
+                        "link-preview-always-browse",
+                        host = preview.host.as_str()
+                    ))
+                    .clicked()
+                {
+                    always_browse = true;
+                }
+            });
+
+        if always_browse {
+            remember(settings, &preview.host, LinkBehavior::Browser);
+            browse = true;
+        }
+        if browse {
+            ctx.open_url(egui::OpenUrl::new_tab(&preview.url));
+            self.preview = None;
+        } else if !open {
+            self.preview = None;
+        }
+    }
+}
@@ -0,0 +1,39 @@
+/// The user's preferred locale from the OS, eg. `en-US`
+#[cfg(not(target_os = "android"))]
+pub fn system_locale() -> Option<String> {
+    sys_locale::get_locale()
+}
+
+/// The user's preferred locale from the OS, eg. `en-US`
+#[cfg(target_os = "android")]
+pub fn system_locale() -> Option<String> {
+    match android_locale() {
+        Ok(locale) => Some(locale),
+        Err(err) => {
+            tracing::error!("failed to get locale from android: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+fn android_locale() -> jni::errors::Result<String> {
+    let context = ndk_context::android_context();
+    // SAFETY: android-activity sets this up before android_main runs
+    let vm = unsafe { jni::JavaVM::from_raw(context.vm().cast()) }?;
+    let mut env = vm.attach_current_thread()?;
+
+    let locale = env
+        .call_static_method(
+            "java/util/Locale",
+            "getDefault",
+            "()Ljava/util/Locale;",
+            &[],
+        )?
+        .l()?;
+    let tag = env
+        .call_method(&locale, "toLanguageTag", "()Ljava/lang/String;", &[])?
+        .l()?;
+
+    Ok(env.get_string(&tag.into())?.into())
+}
@@ -0,0 +1,246 @@
+use std::collections::HashSet;
+
+use egui::{ComboBox, RichText};
+use notedeck::{datetime, time::unix_now, toast, tr, DataPath, DataPathType};
+use tracing::Level;
+
+use crate::logs::{self, LogLine, LEVELS};
+
+/// Parts of the app that can be hidden, by the targets that log for them
+const MODULES: &[(&str, &[&str])] = &[
+    ("logs-module-relays", &["enostr"]),
+    (
+        "logs-module-timelines",
+        &[
+            "notedeck_columns::timeline",
+            "notedeck_columns::subscriptions",
+            "notedeck_columns::app",
+        ],
+    ),
+    (
+        "logs-module-media",
+        &[
+            "notedeck_columns::images",
+            "notedeck_columns::media",
+            "notedeck_columns::ui::media",
+            "notedeck_columns::blossom",
+            "notedeck::imgcache",
+        ],
+    ),
+    (
+        "logs-module-accounts",
+        &["notedeck::accounts", "notedeck_columns::accounts"],
+    ),
+    ("logs-module-wallet", &["notedeck_columns::wallet"]),
+];
+
+/// Which of [`MODULES`] a line belongs to, or `MODULES.len()` for
+/// everything else
+fn module_of(line: &LogLine) -> usize {
+    MODULES
+        .iter()
+        .position(|(_, targets)| targets.iter().any(|t| line.target.starts_with(t)))
+        .unwrap_or(MODULES.len())
+}
+
+/// Recent logs, for capturing diagnostics without a terminal. Ctrl+Shift+L
+/// toggles it.
+pub struct LogViewer {
+    open: bool,
+    /// Least severe level shown
+    level: Level,
+    hidden: HashSet<usize>,
+    query: String,
+    /// The level last given to the subscriber
+    applied_level: Option<String>,
+}
+
+impl Default for LogViewer {
+    fn default() -> Self {
+        LogViewer {
+            open: false,
+            level: Level::TRACE,
+            hidden: HashSet::new(),
+            query: String::new(),
+            applied_level: None,
+        }
+    }
+}
+
+impl LogViewer {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Follow the level picked in settings
+    pub fn apply_level(&mut self, level: String) {
+        if self.applied_level.as_ref() == Some(&level) {
+            return;
+        }
+
+        // the subscriber was set up with it already
+        if self.applied_level.is_some() {
+            logs::set_level(&level);
+        }
+        self.applied_level = Some(level);
+    }
+
+    pub fn update(&mut self, ctx: &egui::Context, path: &DataPath) {
+        if ctx.input_mut(|i| {
+            i.consume_key(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::L,
+            )
+        }) {
+            self.open = !self.open;
+        }
+
+        if !self.open {
+            return;
+        }
+
+        let lines = logs::lines();
+        let query = self.query.trim().to_lowercase();
+        let shown: Vec<&LogLine> = lines
+            .iter()
+            .filter(|line| line.level <= self.level)
+            .filter(|line| !self.hidden.contains(&module_of(line)))
+            .filter(|line| line.matches(&query))
+            .collect();
+
+        let mut open = self.open;
+        egui::Window::new(tr!("logs-title"))
+            .open(&mut open)
+            .default_width(720.0)
+            .default_height(480.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ComboBox::from_id_salt("logs-level")
+                        .selected_text(self.level.as_str())
+                        .show_ui(ui, |ui| {
+                            for level in LEVELS {
+                                ui.selectable_value(&mut self.level, level, level.as_str());
+                            }
+                        });
+
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.query)
+                            .hint_text(tr!("logs-search"))
+                            .desired_width(200.0),
+                    );
+
+                    if ui.button(tr!("logs-export")).clicked() {
+                        export(path, &shown);
+                    }
+                    if ui.button(tr!("logs-clear")).clicked() {
+                        logs::clear();
+                    }
+                });
+
+                ui.horizontal_wrapped(|ui| {
+                    let names = MODULES
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .chain(std::iter::once("logs-module-other"));
+                    for (i, name) in names.enumerate() {
+                        let mut visible = !self.hidden.contains(&i);
+                        if ui.checkbox(&mut visible, tr!(name)).changed() {
+                            if visible {
+                                self.hidden.remove(&i);
+                            } else {
+                                self.hidden.insert(i);
+                            }
+                        }
+                    }
+                });
+
+                ui.separator();
+
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::both()
+                    .stick_to_bottom(true)
+                    .auto_shrink([false, false])
+                    .show_rows(ui, row_height, shown.len(), |ui, rows| {
+                        for line in &shown[rows] {
+                            ui.label(
+                                RichText::new(format_line(line))
+                                    .monospace()
+                                    .color(level_color(ui, line.level)),
+                            );
+                        }
+                    });
+            });
+
+        self.open = open;
+    }
+}
+
+fn level_color(ui: &egui::Ui, level: Level) -> egui::Color32 {
+    match level {
+        Level::ERROR => ui.visuals().error_fg_color,
+        Level::WARN => ui.visuals().warn_fg_color,
+        Level::INFO => ui.visuals().text_color(),
+        _ => ui.visuals().weak_text_color(),
+    }
+}
+
+fn format_line(line: &LogLine) -> String {
+    format!(
+        "{} {:5} {}: {}",
+        datetime::settings().format_time(line.time),
+        line.level.as_str(),
+        line.target,
+        line.message
+    )
+}
+
+/// Write what's shown to a file in the log directory
+fn export(path: &DataPath, lines: &[&LogLine]) {
+    let now = unix_now();
+    let file = path
+        .path(DataPathType::Log)
+        .join(format!("notedeck-export-{now}.log"));
+
+    let contents: String = lines.iter().map(|line| format_line(line) + "\n").collect();
+
+    match std::fs::write(&file, contents) {
+        Ok(()) => toast::info(tr!("logs-exported", path = file.display().to_string())),
+        Err(err) => toast::error(tr!("logs-export-failed", error = err.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(target: &str) -> LogLine {
+        LogLine {
+            time: 0,
+            level: Level::INFO,
+            target: target.to_owned(),
+            message: "Connected to wss://relay.damus.io".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_modules() {
+        assert_eq!(
+            MODULES[module_of(&line("enostr::relay::pool"))].0,
+            "logs-module-relays"
+        );
+        assert_eq!(
+            MODULES[module_of(&line("notedeck_columns::blossom"))].0,
+            "logs-module-media"
+        );
+        assert_eq!(module_of(&line("notedeck_chrome::app")), MODULES.len());
+    }
+
+    #[test]
+    fn test_search() {
+        let line = line("enostr::relay::pool");
+        assert!(line.matches(""));
+        assert!(line.matches("relay.damus"));
+        assert!(line.matches("pool"));
+        assert!(!line.matches("nos.lol"));
+    }
+}
@@ -0,0 +1,141 @@
+//! Keeps recent log lines in memory for the log viewer, and lets the
+//! log level be changed while running.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+
+use notedeck::{
+    settings::{Setting, SettingsSection},
+    time::unix_now,
+    tr, Settings,
+};
+use tracing::field::{Field, Visit};
+use tracing::Level;
+use tracing_subscriber::{layer::Context, reload, EnvFilter, Layer, Registry};
+
+pub const APP: &str = "chrome";
+pub const LOG_LEVEL: &str = "log-level";
+
+/// How many lines the viewer can scroll back through
+const CAPACITY: usize = 5000;
+
+pub const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub time: u64,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogLine {
+    pub fn matches(&self, query: &str) -> bool {
+        query.is_empty()
+            || self.message.to_lowercase().contains(query)
+            || self.target.to_lowercase().contains(query)
+    }
+}
+
+static LINES: Mutex<VecDeque<LogLine>> = Mutex::new(VecDeque::new());
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+pub fn register(settings: &mut Settings) {
+    let options = LEVELS
+        .iter()
+        .map(|level| {
+            let level = level.as_str().to_lowercase();
+            (level.clone(), level)
+        })
+        .collect();
+
+    settings.register(
+        SettingsSection::new(APP, tr!("settings-logging")).setting(
+            Setting::select(LOG_LEVEL, tr!("settings-log-level"), options, "info")
+                .description(tr!("settings-log-level-hint")),
+        ),
+    );
+}
+
+/// The filter for a level picked in settings. `RUST_LOG` wins when it's
+/// set, so running from a terminal works as it always has.
+pub fn filter(level: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("notedeck={level},enostr={level}")))
+}
+
+/// Wrap the filter so [`set_level`] can change it later
+pub fn reloadable(filter: EnvFilter) -> reload::Layer<EnvFilter, Registry> {
+    let (layer, handle) = reload::Layer::new(filter);
+    let _ = FILTER.set(handle);
+    layer
+}
+
+pub fn set_level(level: &str) {
+    let Some(handle) = FILTER.get() else {
+        return;
+    };
+
+    if let Err(err) = handle.reload(filter(level)) {
+        tracing::error!("couldn't change the log level: {err}");
+    } else {
+        tracing::info!("log level is now {level}");
+    }
+}
+
+pub fn lines() -> Vec<LogLine> {
+    LINES
+        .lock()
+        .map(|lines| lines.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn clear() {
+    if let Ok(mut lines) = LINES.lock() {
+        lines.clear();
+    }
+}
+
+/// Collects events into the buffer the log viewer reads
+pub struct LogLayer;
+
+impl<S: tracing::Subscriber> Layer<S> for LogLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+
+        let line = LogLine {
+            time: unix_now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_owned(),
+            message: message.0,
+        };
+
+        if let Ok(mut lines) = LINES.lock() {
+            if lines.len() == CAPACITY {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
@@ -1,7 +1,12 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
-use notedeck_chrome::{setup::generate_native_options, Notedeck};
-
-use notedeck::{DataPath, DataPathType};
+use notedeck_chrome::{
+    headless::{self, HeadlessCommand},
+    logs,
+    setup::generate_native_options,
+    Notedeck,
+};
+
+use notedeck::{DataPath, DataPathType, Settings};
 use notedeck_columns::Damus;
 use tracing_subscriber::EnvFilter;
 
@@ -42,14 +47,18 @@ fn setup_logging(path: &DataPath) {
             .with_ansi(false)
             .with_writer(non_blocking_writer);
 
-        let env_filter =
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("notedeck=info"));
+        // the level picked in settings, unless RUST_LOG says otherwise
+        let mut settings = Settings::new(path);
+        logs::register(&mut settings);
+        let env_filter = logs::filter(&settings.string(logs::APP, logs::LOG_LEVEL));
 
-        // Set up the subscriber to combine both layers
+        // Set up the subscriber to combine both layers, and keep recent
+        // lines for the log viewer
         tracing_subscriber::registry()
+            .with(logs::reloadable(env_filter))
+            .with(logs::LogLayer)
             .with(console_layer)
             .with(file_layer)
-            .with(env_filter)
             .init();
     } else {
         tracing_subscriber::fmt()
@@ -67,11 +76,20 @@ async fn main() {
 
     setup_logging(&path);
 
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(command) = HeadlessCommand::from_args(&args) {
+        std::process::exit(headless::main(command, &path, &args));
+    }
+
+    if !headless::take_over(&path, &args) {
+        eprintln!("notedeck is running headless and didn't stop, try --headless-stop");
+        std::process::exit(1);
+    }
+
     let _res = eframe::run_native(
         "Damus Notedeck",
-        generate_native_options(path),
-        Box::new(|cc| {
-            let args: Vec<String> = std::env::args().collect();
+        generate_native_options(path.clone()),
+        Box::new(move |cc| {
             let mut notedeck = Notedeck::new(&cc.egui_ctx, base_path, &args);
 
             let damus = Damus::new(&mut notedeck.app_context(), &args);
@@ -80,6 +98,9 @@ async fn main() {
             Ok(Box::new(notedeck))
         }),
     );
+
+    // a downloaded update installs now that we're closed
+    notedeck::updater::install_staged(&path, env!("CARGO_PKG_VERSION"));
 }
 
 /*
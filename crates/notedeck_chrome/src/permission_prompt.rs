@@ -0,0 +1,36 @@
+use notedeck::{tr, Permissions, Settings};
+
+/// Asks whether an app may do what it just tried. The answer is kept per
+/// account, and the app is expected to try again.
+pub fn update(ctx: &egui::Context, permissions: &mut Permissions, settings: &mut Settings) {
+    let Some(prompt) = permissions.prompt() else {
+        return;
+    };
+
+    let mut answer = None;
+    egui::Window::new(tr!("permission-title"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(tr!(
+                "permission-prompt",
+                app = prompt.app_name.as_str(),
+                capability = prompt.capability.label()
+            ));
+            ui.weak(tr!("permission-prompt-hint"));
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button(tr!("permission-allow")).clicked() {
+                    answer = Some(true);
+                }
+                if ui.button(tr!("permission-deny")).clicked() {
+                    answer = Some(false);
+                }
+            });
+        });
+
+    if let Some(allow) = answer {
+        permissions.answer(settings, allow);
+    }
+}
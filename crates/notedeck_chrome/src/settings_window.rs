@@ -0,0 +1,197 @@
+use egui::{ComboBox, RichText};
+use notedeck::{
+    settings::{self, Scope, Setting, SettingKind, SettingsSection},
+    tr, Settings,
+};
+use serde_json::Value;
+
+/// Other windows that can be opened from settings
+pub enum OpenWindow {
+    Logs,
+    Diagnostics,
+    KeyProtection,
+    Updates,
+}
+
+/// The settings every app contributed, in one searchable window. Opened
+/// with Ctrl+, or by an app through [`settings::open_window`].
+#[derive(Default)]
+pub struct SettingsWindow {
+    open: bool,
+    query: String,
+}
+
+impl SettingsWindow {
+    /// Open the window searching for something, eg. an app's section
+    pub fn search(&mut self, query: &str) {
+        self.open = true;
+        self.query = query.to_owned();
+    }
+
+    /// Returns the window that was asked for, if any
+    pub fn update(&mut self, ctx: &egui::Context, settings: &mut Settings) -> Option<OpenWindow> {
+        if settings::take_open_request(ctx) {
+            self.open = true;
+        }
+
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Comma)) {
+            self.open = !self.open;
+        }
+
+        if !self.open {
+            return None;
+        }
+
+        let mut open = self.open;
+        let mut changes: Vec<(&'static str, &'static str, Value)> = vec![];
+        let mut open_window = None;
+
+        egui::Window::new(tr!("settings-window-title"))
+            .open(&mut open)
+            .default_width(420.0)
+            .default_height(480.0)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(tr!("settings-window-search"))
+                        .desired_width(f32::INFINITY),
+                );
+                ui.add_space(8.0);
+
+                let query = self.query.trim().to_lowercase();
+                let mut shown = 0;
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for section in settings.sections() {
+                        let matching = matching_settings(section, &query);
+                        if matching.is_empty() {
+                            continue;
+                        }
+                        shown += matching.len();
+
+                        ui.label(RichText::new(&section.title).strong());
+                        ui.add_space(4.0);
+
+                        for setting in matching {
+                            let current = settings.get(section.app, setting.key);
+                            if let Some(value) = setting_ui(ui, section.app, setting, current) {
+                                changes.push((section.app, setting.key, value));
+                            }
+                        }
+
+                        ui.add_space(12.0);
+                    }
+
+                    if shown == 0 {
+                        ui.weak(tr!("settings-window-no-results"));
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("settings-window-show-logs")).clicked() {
+                        open_window = Some(OpenWindow::Logs);
+                    }
+                    if ui.button(tr!("settings-window-show-diagnostics")).clicked() {
+                        open_window = Some(OpenWindow::Diagnostics);
+                    }
+                    if ui.button(tr!("settings-window-key-protection")).clicked() {
+                        open_window = Some(OpenWindow::KeyProtection);
+                    }
+                    if ui.button(tr!("settings-window-check-updates")).clicked() {
+                        open_window = Some(OpenWindow::Updates);
+                    }
+                });
+            });
+
+        for (app, key, value) in changes {
+            settings.set(app, key, value);
+        }
+
+        self.open = open;
+        open_window
+    }
+}
+
+/// Everything in a section whose title matches, otherwise just the
+/// settings that match
+fn matching_settings<'a>(section: &'a SettingsSection, query: &str) -> Vec<&'a Setting> {
+    if query.is_empty() || section.title.to_lowercase().contains(query) {
+        return section.settings.iter().collect();
+    }
+
+    section
+        .settings
+        .iter()
+        .filter(|setting| setting.matches(query))
+        .collect()
+}
+
+/// Returns the new value when it changes
+fn setting_ui(
+    ui: &mut egui::Ui,
+    app: &str,
+    setting: &Setting,
+    current: Option<Value>,
+) -> Option<Value> {
+    let current = current?;
+    let mut changed = None;
+
+    ui.horizontal(|ui| match &setting.kind {
+        SettingKind::Toggle { .. } => {
+            let mut on = current.as_bool().unwrap_or_default();
+            if ui.checkbox(&mut on, &setting.label).changed() {
+                changed = Some(Value::from(on));
+            }
+        }
+
+        SettingKind::Select { options, .. } => {
+            ui.label(&setting.label);
+            let selected = current.as_str().unwrap_or_default();
+            let selected_label = options
+                .iter()
+                .find(|(value, _)| value == selected)
+                .map_or(selected, |(_, label)| label.as_str());
+
+            ComboBox::from_id_salt((app, setting.key))
+                .selected_text(selected_label)
+                .show_ui(ui, |ui| {
+                    for (value, label) in options {
+                        if ui.selectable_label(value == selected, label).clicked() {
+                            changed = Some(Value::from(value.as_str()));
+                        }
+                    }
+                });
+        }
+
+        SettingKind::Slider { range, step, .. } => {
+            ui.label(&setting.label);
+            let mut number = current.as_f64().unwrap_or(*range.start());
+            if ui
+                .add(egui::Slider::new(&mut number, range.clone()).step_by(*step))
+                .changed()
+            {
+                changed = Some(Value::from(number));
+            }
+        }
+
+        SettingKind::Text { .. } => {
+            ui.label(&setting.label);
+            let mut text = current.as_str().unwrap_or_default().to_owned();
+            if ui.text_edit_singleline(&mut text).changed() {
+                changed = Some(Value::from(text));
+            }
+        }
+    });
+
+    if let Some(description) = &setting.description {
+        ui.weak(description);
+    }
+    if setting.scope == Scope::Account {
+        ui.weak(tr!("settings-window-per-account"));
+    }
+    ui.add_space(4.0);
+
+    changed
+}
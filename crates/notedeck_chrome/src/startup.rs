@@ -0,0 +1,80 @@
+//! How long each part of startup took, so a slow start can be pinned on
+//! something. Phases are logged, show up in the diagnostics panel, and
+//! are puffin scopes when built with profiling.
+
+use std::time::{Duration, Instant};
+
+use tracing::{debug, info};
+
+pub struct Startup {
+    began: Instant,
+    /// When the last phase ended
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+    done: bool,
+}
+
+impl Default for Startup {
+    fn default() -> Self {
+        let now = Instant::now();
+        Startup {
+            began: now,
+            last: now,
+            phases: vec![],
+            done: false,
+        }
+    }
+}
+
+impl Startup {
+    pub fn phase<T>(&mut self, name: &'static str, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "profiling")]
+        puffin::profile_scope!(name);
+
+        let start = Instant::now();
+        let out = f();
+        self.last = Instant::now();
+        let took = self.last - start;
+        debug!("startup: {name} took {took:?}");
+        self.phases.push((name, took));
+        out
+    }
+
+    /// Called every frame. The first one finishes startup, counting
+    /// everything since the last phase as getting the apps going.
+    pub fn frame(&mut self) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        self.phases
+            .push(("apps and first frame", self.last.elapsed()));
+        info!("started in {:?}", self.began.elapsed());
+    }
+
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, took)| *took).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases() {
+        let mut startup = Startup::default();
+        assert_eq!(startup.phase("one", || 1), 1);
+        startup.phase("two", || std::thread::sleep(Duration::from_millis(5)));
+        startup.frame();
+        startup.frame();
+
+        let names: Vec<_> = startup.phases().iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["one", "two", "apps and first frame"]);
+        assert!(startup.total() >= Duration::from_millis(5));
+    }
+}
@@ -0,0 +1,311 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use notedeck::{ui::badge_text, DataPath, DataPathType};
+use tracing::{error, info};
+use tray_icon::{
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent,
+};
+
+use crate::{setup::app_icon, timed_serializer::TimedSerializer};
+
+const NEW_NOTE: &str = "tray-new-note";
+const TOGGLE_WINDOW: &str = "tray-toggle-window";
+const MINIMIZE_TO_TRAY: &str = "tray-minimize-to-tray";
+const QUIT: &str = "tray-quit";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TrayAction {
+    NewNote,
+    ToggleWindow,
+    Quit,
+}
+
+/// Updates for the tray icon itself
+enum TrayUpdate {
+    Unread(usize),
+}
+
+/// The system tray icon and its menu. On linux the icon has to live on a
+/// gtk thread, so everything goes through a channel to keep the two
+/// cases the same.
+pub struct Tray {
+    updates: Sender<TrayUpdate>,
+    events: Receiver<TrayEvent>,
+    #[cfg(not(target_os = "linux"))]
+    handle: TrayHandle,
+    #[cfg(not(target_os = "linux"))]
+    pending: Receiver<TrayUpdate>,
+    minimize_to_tray: TimedSerializer<bool>,
+    unread: usize,
+    /// The window is hidden in the tray
+    window_hidden: bool,
+    /// Quit was picked from the menu, so closing shouldn't minimize
+    quitting: bool,
+}
+
+enum TrayEvent {
+    Menu(MenuEvent),
+    Icon(TrayIconEvent),
+}
+
+impl Tray {
+    pub fn new(ctx: &egui::Context, path: &DataPath) -> Option<Self> {
+        let minimize_to_tray = TimedSerializer::new(
+            path,
+            DataPathType::Setting,
+            "minimize_to_tray.json".to_owned(),
+        )
+        .with_delay(std::time::Duration::ZERO);
+        let minimize = minimize_to_tray.get_item().unwrap_or(false);
+
+        let (events_tx, events) = mpsc::channel();
+        forward_events(ctx, events_tx);
+
+        let (updates, pending) = mpsc::channel();
+
+        #[cfg(target_os = "linux")]
+        {
+            spawn_gtk_tray(minimize, pending);
+            Some(Tray {
+                updates,
+                events,
+                minimize_to_tray,
+                unread: 0,
+                window_hidden: false,
+                quitting: false,
+            })
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let handle = TrayHandle::new(minimize)?;
+            Some(Tray {
+                updates,
+                events,
+                handle,
+                pending,
+                minimize_to_tray,
+                unread: 0,
+                window_hidden: false,
+                quitting: false,
+            })
+        }
+    }
+
+    pub fn minimize_to_tray(&self) -> bool {
+        self.minimize_to_tray.get_item().unwrap_or(false)
+    }
+
+    pub fn set_unread(&mut self, unread: usize) {
+        if self.unread == unread {
+            return;
+        }
+
+        self.unread = unread;
+        let _ = self.updates.send(TrayUpdate::Unread(unread));
+    }
+
+    pub fn set_window_hidden(&mut self, ctx: &egui::Context, hidden: bool) {
+        self.window_hidden = hidden;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Visible(!hidden));
+        if !hidden {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+    }
+
+    pub fn toggle_window(&mut self, ctx: &egui::Context) {
+        self.set_window_hidden(ctx, !self.window_hidden);
+    }
+
+    pub fn quit(&mut self, ctx: &egui::Context) {
+        self.quitting = true;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+
+    /// Hide in the tray instead of closing or minimizing, if that's
+    /// what the user asked for
+    pub fn minimize_if_needed(&mut self, ctx: &egui::Context) {
+        if self.quitting || !self.minimize_to_tray() {
+            return;
+        }
+
+        let (close_requested, minimized) = ctx.input(|i| {
+            (
+                i.viewport().close_requested(),
+                i.viewport().minimized.unwrap_or(false),
+            )
+        });
+
+        if close_requested {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.set_window_hidden(ctx, true);
+        } else if minimized && !self.window_hidden {
+            self.set_window_hidden(ctx, true);
+        }
+    }
+
+    /// Apply pending icon updates and collect the actions the user picked
+    /// from the tray since the last frame
+    pub fn poll(&mut self) -> Vec<TrayAction> {
+        #[cfg(not(target_os = "linux"))]
+        while let Ok(update) = self.pending.try_recv() {
+            self.handle.apply(update);
+        }
+
+        let mut actions = vec![];
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                TrayEvent::Menu(event) => match event.id.0.as_str() {
+                    NEW_NOTE => actions.push(TrayAction::NewNote),
+                    TOGGLE_WINDOW => actions.push(TrayAction::ToggleWindow),
+                    QUIT => actions.push(TrayAction::Quit),
+                    MINIMIZE_TO_TRAY => {
+                        // the check item flips itself, we just need to
+                        // remember it
+                        let minimize = !self.minimize_to_tray();
+                        info!("minimize to tray: {minimize}");
+                        self.minimize_to_tray.try_save(minimize);
+                    }
+                    _ => {}
+                },
+
+                TrayEvent::Icon(TrayIconEvent::Click {
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Up,
+                    ..
+                }) => actions.push(TrayAction::ToggleWindow),
+
+                TrayEvent::Icon(_) => {}
+            }
+        }
+
+        actions
+    }
+}
+
+/// Setting our own handlers means we can wake up egui when something is
+/// clicked, even when the window is hidden and isn't repainting
+fn forward_events(ctx: &egui::Context, events: Sender<TrayEvent>) {
+    let menu_ctx = ctx.clone();
+    let menu_events = events.clone();
+    MenuEvent::set_event_handler(Some(move |event| {
+        let _ = menu_events.send(TrayEvent::Menu(event));
+        menu_ctx.request_repaint();
+    }));
+
+    let icon_ctx = ctx.clone();
+    TrayIconEvent::set_event_handler(Some(move |event| {
+        let _ = events.send(TrayEvent::Icon(event));
+        icon_ctx.request_repaint();
+    }));
+}
+
+struct TrayHandle {
+    icon: TrayIcon,
+}
+
+impl TrayHandle {
+    fn new(minimize_to_tray: bool) -> Option<Self> {
+        let menu = Menu::new();
+        let items_added = menu.append_items(&[
+            &MenuItem::with_id(NEW_NOTE, "New note", true, None),
+            &MenuItem::with_id(TOGGLE_WINDOW, "Show/Hide window", true, None),
+            &PredefinedMenuItem::separator(),
+            &CheckMenuItem::with_id(
+                MINIMIZE_TO_TRAY,
+                "Minimize to tray",
+                true,
+                minimize_to_tray,
+                None,
+            ),
+            &PredefinedMenuItem::separator(),
+            &MenuItem::with_id(QUIT, "Quit", true, None),
+        ]);
+
+        if let Err(err) = items_added {
+            error!("tray: failed to build menu: {err}");
+            return None;
+        }
+
+        let icon = match tray_icon() {
+            Some(icon) => icon,
+            None => {
+                error!("tray: failed to load icon");
+                return None;
+            }
+        };
+
+        let icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip("Notedeck")
+            .with_icon(icon)
+            .build();
+
+        match icon {
+            Ok(icon) => Some(TrayHandle { icon }),
+            Err(err) => {
+                error!("tray: failed to create icon: {err}");
+                None
+            }
+        }
+    }
+
+    fn apply(&self, update: TrayUpdate) {
+        match update {
+            TrayUpdate::Unread(unread) => {
+                let (tooltip, title) = if unread == 0 {
+                    ("Notedeck".to_owned(), None)
+                } else {
+                    (
+                        format!("Notedeck - {unread} unread"),
+                        Some(badge_text(unread)),
+                    )
+                };
+
+                if let Err(err) = self.icon.set_tooltip(Some(tooltip)) {
+                    error!("tray: failed to set tooltip: {err}");
+                }
+
+                // shows next to the icon on macos and linux, ignored on
+                // windows
+                self.icon.set_title(title);
+            }
+        }
+    }
+}
+
+fn tray_icon() -> Option<tray_icon::Icon> {
+    let icon = eframe::icon_data::from_png_bytes(app_icon()).ok()?;
+    tray_icon::Icon::from_rgba(icon.rgba, icon.width, icon.height).ok()
+}
+
+#[cfg(target_os = "linux")]
+fn spawn_gtk_tray(minimize_to_tray: bool, updates: Receiver<TrayUpdate>) {
+    let spawned = std::thread::Builder::new()
+        .name("tray".to_owned())
+        .spawn(move || {
+            if let Err(err) = gtk::init() {
+                error!("tray: failed to init gtk: {err}");
+                return;
+            }
+
+            let Some(handle) = TrayHandle::new(minimize_to_tray) else {
+                return;
+            };
+
+            gtk::glib::timeout_add_local(std::time::Duration::from_millis(250), move || {
+                while let Ok(update) = updates.try_recv() {
+                    handle.apply(update);
+                }
+                gtk::glib::ControlFlow::Continue
+            });
+
+            gtk::main();
+        });
+
+    if let Err(err) = spawned {
+        error!("tray: failed to spawn gtk thread: {err}");
+    }
+}
@@ -0,0 +1,189 @@
+//! Telling the user about new releases. We check on start and once a day
+//! while it's on in settings, and the settings window can ask any time.
+
+use std::time::{Duration, Instant};
+
+use egui::RichText;
+use notedeck::{
+    settings::{Setting, SettingsSection},
+    tr,
+    updater::{Channel, UpdateState, Updater, Version},
+    DataPath, Settings,
+};
+
+use crate::logs::APP;
+
+pub const CHECK_UPDATES: &str = "check-updates";
+pub const UPDATE_CHANNEL: &str = "update-channel";
+
+const CHECK_EVERY: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub fn register(settings: &mut Settings) {
+    let channels = Channel::ALL
+        .iter()
+        .map(|channel| {
+            let label = match channel {
+                Channel::Stable => tr!("settings-update-channel-stable"),
+                Channel::Beta => tr!("settings-update-channel-beta"),
+            };
+            (channel.key().to_owned(), label)
+        })
+        .collect();
+
+    settings.register(
+        SettingsSection::new(APP, tr!("settings-updates"))
+            .setting(
+                Setting::toggle(CHECK_UPDATES, tr!("settings-check-updates"), true)
+                    .description(tr!("settings-check-updates-hint"))
+                    .device_local(),
+            )
+            .setting(
+                Setting::select(
+                    UPDATE_CHANNEL,
+                    tr!("settings-update-channel"),
+                    channels,
+                    Channel::Stable.key(),
+                )
+                .description(tr!("settings-update-channel-hint"))
+                .device_local(),
+            ),
+    );
+}
+
+pub struct UpdateWindow {
+    updater: Updater,
+    open: bool,
+    last_check: Option<Instant>,
+    /// The newest release we've opened the window for, so closing it
+    /// keeps it closed
+    shown: Option<Version>,
+}
+
+impl UpdateWindow {
+    pub fn new(path: &DataPath) -> Self {
+        UpdateWindow {
+            updater: Updater::new(path, env!("CARGO_PKG_VERSION")),
+            open: false,
+            last_check: None,
+            shown: None,
+        }
+    }
+
+    /// Check now and show whatever we find, even that we're up to date
+    pub fn open(&mut self, ctx: &egui::Context, settings: &Settings) {
+        self.open = true;
+        self.check(ctx, settings);
+    }
+
+    fn check(&mut self, ctx: &egui::Context, settings: &Settings) {
+        self.last_check = Some(Instant::now());
+        let channel = Channel::from_key(&settings.string(APP, UPDATE_CHANNEL));
+        self.updater.check(ctx, channel);
+    }
+
+    pub fn update(&mut self, ctx: &egui::Context, settings: &Settings) {
+        let due = !self
+            .last_check
+            .is_some_and(|last| last.elapsed() < CHECK_EVERY);
+        if due && settings.bool(APP, CHECK_UPDATES) {
+            self.check(ctx, settings);
+        }
+
+        let state = self.updater.state();
+        if let UpdateState::Available(release) = &state {
+            if self.shown.as_ref() != Some(&release.version) {
+                self.shown = Some(release.version.clone());
+                self.open = true;
+            }
+        }
+
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+        let mut close = false;
+        egui::Window::new(tr!("update-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(480.0)
+            .show(ctx, |ui| match state {
+                UpdateState::Idle | UpdateState::Checking => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(tr!("update-checking"));
+                    });
+                }
+
+                UpdateState::UpToDate => {
+                    ui.label(tr!(
+                        "update-up-to-date",
+                        version = self.updater.current().to_string()
+                    ));
+                }
+
+                UpdateState::Failed(error) => {
+                    ui.label(tr!("update-failed", error = error));
+                    if ui.button(tr!("update-retry")).clicked() {
+                        self.check(ctx, settings);
+                    }
+                }
+
+                UpdateState::Available(release) => {
+                    ui.label(
+                        RichText::new(tr!(
+                            "update-available",
+                            version = release.version.to_string()
+                        ))
+                        .strong(),
+                    );
+                    if !release.name.is_empty() {
+                        ui.weak(&release.name);
+                    }
+                    ui.add_space(8.0);
+                    egui::ScrollArea::vertical()
+                        .max_height(320.0)
+                        .show(ui, |ui| ui.label(&release.notes));
+                    ui.add_space(8.0);
+
+                    ui.horizontal(|ui| {
+                        if Updater::can_install(&release) {
+                            if ui.button(tr!("update-download")).clicked() {
+                                self.updater.download(ctx, release.clone());
+                            }
+                        } else if ui.button(tr!("update-get")).clicked() {
+                            ctx.open_url(egui::OpenUrl::new_tab(&release.url));
+                        }
+                        if ui.button(tr!("update-later")).clicked() {
+                            close = true;
+                        }
+                    });
+                }
+
+                UpdateState::Downloading(release) => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(tr!(
+                            "update-downloading",
+                            version = release.version.to_string()
+                        ));
+                    });
+                }
+
+                UpdateState::Staged(release) => {
+                    ui.label(tr!("update-staged", version = release.version.to_string()));
+                    ui.horizontal(|ui| {
+                        // the installer starts once the window is gone
+                        if ui.button(tr!("update-restart")).clicked() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                        if ui.button(tr!("update-later")).clicked() {
+                            close = true;
+                        }
+                    });
+                }
+            });
+
+        self.open = open && !close;
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
     column::Columns,
+    moderation::ReportTarget,
     route::{Route, Router},
+    selection::Selection,
     timeline::{TimelineCache, TimelineCacheKey},
 };
 
@@ -13,8 +15,26 @@ use tracing::error;
 pub enum NoteAction {
     Reply(NoteId),
     Quote(NoteId),
+    /// Repost a note as it is, from the selected account
+    Repost(NoteId),
+    /// Take back a repost we just made
+    UndoRepost(NoteId),
     OpenThread(NoteId),
     OpenProfile(Pubkey),
+    ExportImage(NoteId),
+    ViewSource(NoteId),
+    /// Pick out a passage of a note to highlight
+    Highlight(NoteId),
+    /// List the highlights of a note
+    Highlights(NoteId),
+    /// Pick which bookmark lists to save a note in
+    Bookmark(NoteId),
+    /// Stop showing a note in columns
+    Hide(NoteId),
+    /// Stop showing someone's notes in columns
+    HideAuthor(Pubkey),
+    /// Report a note or its author to relays and moderators
+    Report(ReportTarget),
 }
 
 pub struct NewNotes<'a> {
@@ -115,6 +135,42 @@ impl NoteAction {
                 router.route_to(Route::quote(*note_id));
                 None
             }
+
+            NoteAction::ExportImage(note_id) => {
+                router.route_to(Route::Poster(*note_id));
+                None
+            }
+
+            NoteAction::ViewSource(note_id) => {
+                router.route_to(Route::Source(*note_id));
+                None
+            }
+
+            NoteAction::Bookmark(note_id) => {
+                router.route_to(Route::SaveBookmark(*note_id));
+                None
+            }
+
+            NoteAction::Highlight(note_id) => {
+                router.route_to(Route::Highlight(*note_id));
+                None
+            }
+
+            NoteAction::Highlights(note_id) => {
+                router.route_to(Route::Highlights(*note_id));
+                None
+            }
+
+            NoteAction::Report(target) => {
+                router.route_to(Route::Report(*target));
+                None
+            }
+
+            // the app owns the hidden list and our reposts, see nav
+            NoteAction::Hide(_)
+            | NoteAction::HideAuthor(_)
+            | NoteAction::Repost(_)
+            | NoteAction::UndoRepost(_) => None,
         }
     }
 
@@ -130,7 +186,29 @@ impl NoteAction {
         pool: &mut RelayPool,
         txn: &Transaction,
         unknown_ids: &mut UnknownIds,
+        selection: &mut Selection,
     ) {
+        let showing = match self {
+            NoteAction::OpenThread(note_id) => {
+                selection.select_note(note_id);
+                Some(Route::thread(note_id))
+            }
+            NoteAction::OpenProfile(pubkey) => Some(Route::profile(pubkey)),
+            _ => None,
+        };
+
+        // go to a column that already shows it rather than opening it twice
+        if let Some(existing) = showing
+            .filter(|_| selection.multi_column)
+            .and_then(|route| columns.find_showing(&route))
+        {
+            columns.select_column(existing);
+            if existing != col {
+                selection.focus_column(existing);
+            }
+            return;
+        }
+
         let router = columns.column_mut(col).router_mut();
         if let Some(br) = self.execute(ndb, router, timeline_cache, note_cache, pool, txn) {
             br.process(ndb, note_cache, txn, timeline_cache, unknown_ids);
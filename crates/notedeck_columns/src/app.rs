@@ -1,31 +1,80 @@
 use crate::{
+    actionbar::NoteAction,
     args::ColumnsArgs,
+    blossom::ServerListSubscription,
     column::Columns,
+    deck_share::DeckImport,
     decks::{Decks, DecksCache, FALLBACK_PUBKEY},
+    deep_link::{self, DeepLink},
+    deletions::DeletionWatch,
     draft::Drafts,
-    nav, storage,
+    export::DataExport,
+    follow_packs::FollowPacks,
+    geo, gifs,
+    hashtags::{HashtagSettings, HomeHashtags},
+    hidden::Hidden,
+    highlights::Highlights,
+    images,
+    maintenance::Maintenance,
+    moderation::Moderation,
+    nav,
+    nip05::Nip05Cache,
+    notifications::{NotificationSettings, Notifier},
+    onboarding::{self, Onboarding, OnboardingStep, StarterColumns, StarterLists},
+    post,
+    prefetch::Prefetch,
+    publish::Publishes,
+    qr_scan,
+    relay_groups::{self, RelayGroups},
+    reposts::Reposts,
+    route::Route,
+    selection::Selection,
+    settings,
+    settings_sync::{self, SettingsSync},
+    storage,
     subscriptions::{SubKind, Subscriptions},
     support::Support,
-    timeline::{self, TimelineCache},
-    ui::{self, DesktopSidePanel},
+    timeline::{
+        self, kind::ListKind, ColumnTitle, PubkeySource, Timeline, TimelineCache, TimelineKind,
+    },
+    trending::Trending,
+    ui::{
+        self,
+        density::Density,
+        hashtag::HashtagAction,
+        onboarding::{OnboardingResponse, OnboardingView},
+        profile::card::ProfileCardAction,
+        unsent::UnsentAction,
+        DesktopSidePanel,
+    },
     unknowns,
+    verify::Verifier,
     view_state::ViewState,
+    wallet::Wallet,
     Result,
 };
 
-use notedeck::{Accounts, AppContext, DataPath, DataPathType, FilterState, ImageCache, UnknownIds};
+use notedeck::{
+    calendar::{checkin, CheckInCode},
+    time::unix_now,
+    toast, tr, AccountChange, Accounts, AppAction, AppCommand, AppContext, AppManifest, AppView,
+    Capability, DataPath, DataPathType, FilterState, ImageCache, UnknownIds, WebOfTrust,
+};
 
-use enostr::{ClientMessage, Keypair, PoolRelay, Pubkey, RelayEvent, RelayMessage, RelayPool};
+use enostr::{
+    ClientMessage, Keypair, NwcUri, PoolRelay, Pubkey, RelayEvent, RelayMessage, RelayPool,
+};
 use uuid::Uuid;
 
 use egui_extras::{Size, StripBuilder};
 
 use nostrdb::{Ndb, Transaction};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
-use tracing::{error, info, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum DamusState {
@@ -42,6 +91,60 @@ pub struct Damus {
     pub timeline_cache: TimelineCache,
     pub subscriptions: Subscriptions,
     pub support: Support,
+    /// Set on first run until the user finishes or skips onboarding
+    pub onboarding: Option<Onboarding>,
+    /// A new account's relay and contact lists, until they can be sent
+    pub starter_lists: StarterLists,
+    /// Notifications that arrived while the window wasn't focused
+    pub unread: usize,
+    /// When the user last had the window focused
+    notifications_seen: u64,
+    pub notifier: Notifier,
+    pub hashtag_settings: HashtagSettings,
+    home_hashtags: HomeHashtags,
+    /// Notes and people hidden from columns on this device
+    pub hidden: Hidden,
+    /// Who the selected account trusts, for filtering open feeds
+    pub wot: WebOfTrust,
+    /// Connected over Nostr Wallet Connect, if the user set one up
+    pub wallet: Option<Wallet>,
+    /// A connection being tried out before it replaces `wallet`
+    pub wallet_pairing: Option<Wallet>,
+    /// A wallet connect link we were started with, paired once we're up
+    wallet_link: Option<NwcUri>,
+    /// Connect the selected account's saved wallet on the next update
+    reload_wallet: bool,
+    media_servers: ServerListSubscription,
+    /// Our notes that relays haven't all answered about yet
+    pub publishes: Publishes,
+    /// Sets of relays a post can be sent to instead of all of them
+    pub relay_groups: RelayGroups,
+    /// Data exports and imports running in the background
+    pub export: DataExport,
+    /// Database stats and compaction
+    pub maintenance: Maintenance,
+    /// Keeps layout and preferences in step across devices
+    pub settings_sync: SettingsSync,
+    /// A shared deck being read in from the new deck screen
+    pub deck_import: DeckImport,
+    /// Saving notes and images ahead of time for reading offline
+    prefetch: Prefetch,
+    /// Reports and labels from moderators the user trusts
+    moderation: Moderation,
+    nip05: Nip05Cache,
+    deletions: DeletionWatch,
+    /// Suggested people to follow, fetched once someone goes looking
+    pub follow_packs: FollowPacks,
+    /// What the Trending column shows
+    pub trending: Trending,
+    /// Our reposts that can still be taken back
+    pub reposts: Reposts,
+    /// How many highlights notes have
+    pub highlights: Highlights,
+    /// The open note and focused column, shared by every column
+    pub selection: Selection,
+    /// Hands relay events to nostrdb off the ui thread
+    pub verifier: Verifier,
 
     //frame_history: crate::frame_history::FrameHistory,
 
@@ -93,6 +196,17 @@ fn try_process_event(
 
     app_ctx.pool.keepalive_ping(wakeup);
 
+    // send anything that was held back by relay rate limits, and make sure
+    // we wake up again if there's still more to send
+    app_ctx.pool.flush();
+    if let Some(next_flush) = app_ctx.pool.next_flush_in() {
+        ctx.request_repaint_after(next_flush);
+    }
+    // come back to retry relays we lost even if nothing else happens
+    if let Some(next_reconnect) = app_ctx.pool.next_reconnect_in() {
+        ctx.request_repaint_after(next_reconnect);
+    }
+
     // NOTE: we don't use the while let loop due to borrow issues
     #[allow(clippy::while_let_loop)]
     loop {
@@ -108,6 +222,15 @@ fn try_process_event(
                     .accounts
                     .send_initial_filters(app_ctx.pool, &ev.relay);
 
+                damus.notifier.send_initial_filter(app_ctx.pool, &ev.relay);
+                damus
+                    .moderation
+                    .send_initial_filter(app_ctx.pool, &ev.relay);
+                damus.deletions.send_initial_filter(app_ctx.pool, &ev.relay);
+                damus
+                    .follow_packs
+                    .send_initial_filter(app_ctx.pool, &ev.relay);
+
                 timeline::send_initial_timeline_filters(
                     app_ctx.ndb,
                     damus.since_optimize,
@@ -117,17 +240,40 @@ fn try_process_event(
                     &ev.relay,
                 );
             }
-            // TODO: handle reconnects
             RelayEvent::Closed => warn!("{} connection closed", &ev.relay),
-            RelayEvent::Error(e) => error!("{}: {}", &ev.relay, e),
+            RelayEvent::Error(e) => {
+                error!("{}: {}", &ev.relay, e);
+                toast::error(tr!("toast-relay-error", relay = ev.relay.as_str()));
+            }
             RelayEvent::Other(msg) => trace!("other event {:?}", &msg),
             RelayEvent::Message(msg) => {
                 process_message(damus, app_ctx, &ev.relay, &msg);
             }
         }
     }
+    damus.verifier.flush(app_ctx.ndb);
+
+    // the pool already sent our subscriptions again, let the user know why
+    // things are arriving
+    for reconnect in app_ctx.pool.take_reconnects() {
+        if reconnect.replayed > 0 {
+            toast::info(tr!(
+                "toast-relay-reconnected",
+                relay = reconnect.relay.as_str()
+            ));
+        }
+    }
 
     let current_columns = get_active_columns_mut(app_ctx.accounts, &mut damus.decks_cache);
+    timeline::subscribe_visible(
+        app_ctx.ndb,
+        damus.since_optimize,
+        app_ctx.note_cache,
+        &mut damus.subscriptions,
+        app_ctx.pool,
+        current_columns,
+    );
+
     let n_timelines = current_columns.timelines().len();
     for timeline_ind in 0..n_timelines {
         let is_ready = {
@@ -135,6 +281,7 @@ fn try_process_event(
             timeline::is_timeline_ready(
                 app_ctx.ndb,
                 app_ctx.pool,
+                &mut damus.subscriptions,
                 app_ctx.note_cache,
                 timeline,
                 app_ctx
@@ -158,6 +305,7 @@ fn try_process_event(
                 reversed,
             ) {
                 error!("poll_notes_into_view: {err}");
+                toast::error(tr!("toast-timeline-error", error = err.to_string()));
             }
         } else {
             // TODO: show loading?
@@ -192,12 +340,9 @@ fn update_damus(damus: &mut Damus, app_ctx: &mut AppContext<'_>, ctx: &egui::Con
             damus
                 .subscriptions()
                 .insert("unknownids".to_string(), SubKind::OneShot);
-            if let Err(err) = timeline::setup_initial_nostrdb_subs(
-                app_ctx.ndb,
-                app_ctx.note_cache,
-                &mut damus.decks_cache,
-            ) {
-                warn!("update_damus init: {err}");
+            land(damus, app_ctx, ctx);
+            if let Some(uri) = damus.wallet_link.take() {
+                pair_wallet(damus, app_ctx, ctx, uri);
             }
         }
 
@@ -206,7 +351,426 @@ fn update_damus(damus: &mut Damus, app_ctx: &mut AppContext<'_>, ctx: &egui::Con
 
     if let Err(err) = try_process_event(damus, app_ctx, ctx) {
         error!("error processing event: {}", err);
+        toast::error(tr!("toast-event-error", error = err.to_string()));
+    }
+
+    if let Some(note_id) = damus.notifier.update(
+        app_ctx.ndb,
+        app_ctx.pool,
+        app_ctx.path,
+        app_ctx.accounts,
+        &damus.wot,
+        ctx,
+    ) {
+        // a desktop notification was clicked
+        ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        damus
+            .columns_mut(app_ctx.accounts)
+            .get_first_router()
+            .route_to(Route::thread(note_id));
+    }
+
+    let selected = app_ctx
+        .accounts
+        .get_selected_account()
+        .map(|acc| acc.pubkey);
+    damus.wot.update(
+        app_ctx.ndb,
+        selected.as_ref().map(|pk| pk.bytes()),
+        settings::wot_params(app_ctx.settings),
+    );
+
+    let interests = app_ctx.accounts.interests();
+    let followed: HashSet<String> = interests
+        .into_iter()
+        .flat_map(|interests| interests.hashtags())
+        .map(str::to_lowercase)
+        .collect();
+    ui::hashtag::set_followed(ctx, Arc::new(followed));
+
+    let home_hashtags = damus.hashtag_settings.home_hashtags(interests);
+    damus.home_hashtags.update(
+        &home_hashtags,
+        get_active_columns_mut(app_ctx.accounts, &mut damus.decks_cache),
+        app_ctx.ndb,
+        &mut damus.subscriptions,
+        app_ctx.pool,
+        app_ctx.note_cache,
+        selected.as_ref(),
+    );
+
+    damus.reposts.update(
+        ctx,
+        app_ctx.ndb,
+        get_active_columns_mut(app_ctx.accounts, &mut damus.decks_cache),
+        &mut damus.timeline_cache,
+        app_ctx.note_cache,
+        app_ctx.unknown_ids,
+        selected.as_ref(),
+    );
+
+    damus.highlights.update(ctx, app_ctx.ndb);
+
+    if std::mem::take(&mut damus.reload_wallet) {
+        damus.wallet = connect_saved_wallet(app_ctx.path, app_ctx.accounts, ctx);
+    }
+    if let Some(wallet) = &mut damus.wallet {
+        wallet.poll();
+    }
+    if let Some(pairing) = &mut damus.wallet_pairing {
+        pairing.poll();
+    }
+
+    let unsent = damus.publishes.poll(ctx);
+    if !unsent.is_empty() {
+        for id in unsent {
+            damus.hidden.roll_back(id);
+        }
+        storage::save_hidden(app_ctx.path, &damus.hidden);
+    }
+    damus.export.poll(app_ctx.accounts, app_ctx.pool);
+    damus.maintenance.poll(ctx);
+    let deck_user = app_ctx
+        .accounts
+        .get_selected_account()
+        .map(|acc| acc.pubkey);
+    damus
+        .deck_import
+        .poll(app_ctx.ndb, app_ctx.pool, deck_user.as_ref());
+    let prefetch = settings::prefetch(app_ctx.settings);
+    damus.prefetch.update(app_ctx, prefetch);
+    settings_sync::update(damus, app_ctx, ctx);
+    damus.starter_lists.update(app_ctx);
+    damus
+        .moderation
+        .update(app_ctx.ndb, app_ctx.pool, app_ctx.settings, ctx);
+    damus.nip05.update(ctx);
+    if let Some(deletions) = damus.deletions.update(
+        app_ctx.ndb,
+        app_ctx.pool,
+        app_ctx.accounts,
+        app_ctx.settings,
+        ctx,
+    ) {
+        damus.verifier.set_deletions(deletions);
+    }
+
+    timeline::update_backfills(
+        get_active_columns_mut(app_ctx.accounts, &mut damus.decks_cache),
+        &mut damus.subscriptions,
+        app_ctx.pool,
+    );
+
+    geo::set_services(
+        &app_ctx.settings.string(settings::APP, settings::GEOCODER),
+        &app_ctx.settings.string(settings::APP, settings::MAP_TILES),
+    );
+    gifs::set_search_url(&app_ctx.settings.string(settings::APP, settings::GIF_SEARCH));
+    let blocklist = settings::blocklist(app_ctx.settings);
+    if app_ctx.pool.set_blocked(blocklist.relays.clone()) {
+        app_ctx.accounts.refresh_relays();
+    }
+    images::set_blocklist(blocklist);
+    post::set_client_tag(app_ctx.settings.bool(settings::APP, settings::CLIENT_TAG));
+    images::set_animation_mode(images::AnimationMode::from_setting(
+        &app_ctx.settings.string(settings::APP, settings::ANIMATION),
+    ));
+    notedeck::spell::set_language(
+        ctx,
+        app_ctx.path,
+        settings::spellcheck_language(app_ctx.settings).as_deref(),
+    );
+
+    if let Some(action) = ui::hashtag::take_action(ctx) {
+        handle_hashtag_action(damus, app_ctx, action);
+    }
+
+    let viewer = selected.filter(|_| app_ctx.session().can_sign());
+    ui::profile::card::set_viewer(ctx, viewer);
+    if let Some(action) = ui::profile::card::take_follow(ctx) {
+        handle_follow_action(app_ctx, action);
+    }
+
+    if damus.drafts.should_save()
+        && app_ctx
+            .settings
+            .bool(settings::APP, settings::AUTOSAVE_DRAFTS)
+    {
+        storage::save_drafts(app_ctx.path, &damus.drafts);
+    }
+
+    if damus.subscriptions.synced.should_save() {
+        storage::save_synced(app_ctx.path, &damus.subscriptions.synced);
+    }
+
+    // QR codes in images dropped on the window, eg. a screenshot of a
+    // profile's QR code or an event's check-in code, and ones taken with
+    // the camera
+    let codes = qr_scan::dropped_codes(ctx)
+        .into_iter()
+        .chain(qr_scan::take_scanned(ctx));
+    for code in codes {
+        if let Some(check_in) = CheckInCode::parse(&code) {
+            check_in_to(damus, app_ctx, &check_in);
+            continue;
+        }
+
+        match DeepLink::parse(&code) {
+            Some(DeepLink::Address(address)) => {
+                app_ctx.actions.push(AppAction::OpenAddress(address));
+            }
+            Some(DeepLink::WalletConnect(uri)) => pair_wallet(damus, app_ctx, ctx, uri),
+            Some(link) => {
+                if let Some(action) = link.action() {
+                    open_in_first_column(damus, app_ctx, action);
+                }
+            }
+            None => warn!("dropped QR code isn't a nostr link: {code}"),
+        }
     }
+
+    if let Some(address) = deep_link::take_address(ctx) {
+        app_ctx.actions.push(AppAction::OpenAddress(address));
+    }
+}
+
+/// Publish a check-in for an event's check-in code
+fn check_in_to(damus: &mut Damus, app_ctx: &mut AppContext<'_>, code: &CheckInCode) {
+    if let Err(err) = app_ctx.check(Capability::Publish) {
+        toast::warning(err.message());
+        return;
+    }
+
+    let Some(keypair) = app_ctx
+        .accounts
+        .get_selected_account()
+        .and_then(|account| account.to_full())
+    else {
+        toast::warning(tr!("toast-check-in-no-account"));
+        return;
+    };
+
+    let note = match checkin::check_in_note(keypair, code) {
+        Ok(note) => note,
+        Err(err) => {
+            error!("could not check in: {err}");
+            toast::error(tr!("toast-check-in-error", error = err.to_string()));
+            return;
+        }
+    };
+
+    let raw_msg = match note.json() {
+        Ok(json) => format!("[\"EVENT\",{json}]"),
+        Err(err) => {
+            error!("could not check in: {err}");
+            return;
+        }
+    };
+    if let Err(err) = app_ctx.ndb.process_client_event(&raw_msg) {
+        error!("failed to process our check-in: {err}");
+    }
+    app_ctx.pool.send(&ClientMessage::raw(raw_msg));
+    damus.publishes.track(note.id(), app_ctx.pool);
+
+    info!("checked in to {}", code.event);
+}
+
+fn connect_saved_wallet(
+    path: &DataPath,
+    accounts: &Accounts,
+    ctx: &egui::Context,
+) -> Option<Wallet> {
+    let keypair = accounts.get_selected_account()?.to_full()?;
+    let uri = match NwcUri::parse(&storage::load_wallet_uri(path, keypair)?) {
+        Ok(uri) => uri,
+        Err(err) => {
+            error!("saved wallet connection is invalid: {err}");
+            return None;
+        }
+    };
+
+    let ctx = ctx.clone();
+    Some(Wallet::connect(uri, move || ctx.request_repaint()))
+}
+
+/// Try a wallet connection and show how it went in the wallet view,
+/// where it can be saved
+fn pair_wallet(damus: &mut Damus, app_ctx: &mut AppContext<'_>, ctx: &egui::Context, uri: NwcUri) {
+    if let Some(previous) = damus.wallet_pairing.take() {
+        previous.disconnect();
+    }
+    let egui_ctx = ctx.clone();
+    damus.wallet_pairing = Some(Wallet::pair(uri, move || egui_ctx.request_repaint()));
+
+    let router = damus.columns_mut(app_ctx.accounts).get_first_router();
+    if *router.top() != Route::Wallet {
+        router.route_to(Route::Wallet);
+    }
+}
+
+fn handle_hashtag_action(damus: &mut Damus, app_ctx: &mut AppContext<'_>, action: HashtagAction) {
+    let (hashtag, follow) = match action {
+        HashtagAction::OpenColumn(hashtag) => {
+            let mut timeline = Timeline::hashtag(hashtag);
+            timeline::setup_new_timeline(
+                &mut timeline,
+                app_ctx.ndb,
+                &mut damus.subscriptions,
+                app_ctx.pool,
+                app_ctx.note_cache,
+                damus.since_optimize,
+                app_ctx
+                    .accounts
+                    .get_selected_account()
+                    .as_ref()
+                    .map(|sa| &sa.pubkey),
+            );
+            damus
+                .columns_mut(app_ctx.accounts)
+                .add_new_timeline_column(timeline);
+            if !damus.tmp_columns {
+                storage::save_decks_cache(app_ctx.path, &damus.decks_cache);
+            }
+            return;
+        }
+        HashtagAction::Follow(hashtag) => (hashtag, true),
+        HashtagAction::Unfollow(hashtag) => (hashtag, false),
+    };
+
+    if let Err(err) = app_ctx.check(Capability::Publish) {
+        toast::warning(err.message());
+        return;
+    }
+
+    let mut interests = app_ctx.accounts.interests().cloned().unwrap_or_default();
+    if follow {
+        interests.follow_hashtag(&hashtag);
+    } else {
+        interests.unfollow_hashtag(&hashtag);
+    }
+
+    if let Err(err) = app_ctx
+        .accounts
+        .publish_interests(app_ctx.ndb, app_ctx.pool, &interests)
+    {
+        error!("could not update followed hashtags: {err}");
+        toast::error(tr!("toast-hashtags-error", error = err.to_string()));
+    }
+}
+
+fn handle_follow_action(app_ctx: &mut AppContext<'_>, action: ProfileCardAction) {
+    let (pubkey, follow) = match action {
+        ProfileCardAction::Follow(pubkey) => (pubkey, true),
+        ProfileCardAction::Unfollow(pubkey) => (pubkey, false),
+        ProfileCardAction::OpenProfile(_) => return,
+    };
+
+    if let Err(err) = app_ctx.check(Capability::Publish) {
+        toast::warning(err.message());
+        return;
+    }
+
+    if let Err(err) =
+        app_ctx
+            .accounts
+            .publish_follow(app_ctx.ndb, app_ctx.pool, pubkey.bytes(), follow)
+    {
+        error!("could not update contact list: {err}");
+        toast::error(tr!("toast-follow-error", error = err.to_string()));
+    }
+}
+
+/// Open the note composer in the first column, or the login screen if
+/// the account can't post
+fn open_composer(damus: &mut Damus, app_ctx: &mut AppContext<'_>) {
+    let route = if app_ctx.session().can_sign() {
+        Route::ComposeNote
+    } else {
+        Route::add_account()
+    };
+
+    let router = damus.columns_mut(app_ctx.accounts).get_first_router();
+    if *router.top() != route {
+        router.route_to(route);
+    }
+}
+
+/// Go to the deck and column the user wants to start on
+fn land(damus: &mut Damus, app_ctx: &mut AppContext<'_>, ctx: &egui::Context) {
+    let landing = app_ctx.settings.string(settings::APP, settings::LANDING);
+    let our_pk = app_ctx.accounts.get_selected_account().map(|a| a.pubkey);
+    let wanted = |kind: &TimelineKind| match landing.as_str() {
+        "home" => matches!(kind, TimelineKind::List(ListKind::Contact(_))),
+        "notifications" => matches!(kind, TimelineKind::Notifications(_)),
+        "posts" => match kind {
+            TimelineKind::Profile(PubkeySource::DeckAuthor) => true,
+            TimelineKind::Profile(PubkeySource::Explicit(pk)) => Some(*pk) == our_pk,
+            _ => false,
+        },
+        _ => false,
+    };
+
+    let decks = get_decks_mut(app_ctx.accounts, &mut damus.decks_cache);
+    match landing.as_str() {
+        "last" => return,
+        "first" => {
+            decks.set_active(0);
+            return;
+        }
+        _ => {}
+    }
+
+    let found = decks.decks().iter().enumerate().find_map(|(deck, d)| {
+        let columns = d.columns();
+        (0..columns.num_columns())
+            .find(|&col| {
+                columns
+                    .find_timeline_for_column_index(col)
+                    .is_some_and(|timeline| wanted(&timeline.kind))
+            })
+            .map(|col| (deck, col))
+    });
+    let Some((deck, col)) = found else {
+        return;
+    };
+
+    decks.set_active(deck);
+    if notedeck::ui::is_narrow(ctx) {
+        // only the first column shows on a phone, so open it there
+        if let Some(timeline) = decks
+            .active()
+            .columns()
+            .find_timeline_for_column_index(col)
+            .map(|timeline| timeline.id)
+        {
+            if col != 0 {
+                decks
+                    .active_mut()
+                    .columns_mut()
+                    .get_first_router()
+                    .route_to(Route::timeline(timeline));
+            }
+        }
+    } else {
+        damus.selection.focus_column(col);
+    }
+}
+
+/// Open a profile or thread in the first column
+fn open_in_first_column(damus: &mut Damus, app_ctx: &mut AppContext<'_>, action: NoteAction) {
+    let txn = Transaction::new(app_ctx.ndb).expect("txn");
+    action.execute_and_process_result(
+        app_ctx.ndb,
+        get_active_columns_mut(app_ctx.accounts, &mut damus.decks_cache),
+        0,
+        &mut damus.timeline_cache,
+        app_ctx.note_cache,
+        app_ctx.pool,
+        &txn,
+        app_ctx.unknown_ids,
+        &mut damus.selection,
+    );
 }
 
 fn handle_eose(
@@ -231,6 +795,8 @@ fn handle_eose(
             // eose on timeline? whatevs
         }
         SubKind::Initial => {
+            record_synced(damus, ctx, subid, relay_url);
+
             let txn = Transaction::new(ctx.ndb)?;
             unknowns::update_from_columns(
                 &txn,
@@ -299,9 +865,37 @@ fn handle_eose(
     Ok(())
 }
 
+/// A relay has sent us everything it had for a timeline. Remember how
+/// far that got us so the next launch can carry on from there.
+fn record_synced(damus: &mut Damus, ctx: &mut AppContext<'_>, subid: &str, relay_url: &str) {
+    let Some(req) = damus.subscriptions.timeline_reqs.get(subid) else {
+        return;
+    };
+    let Some(timeline) = get_active_columns_mut(ctx.accounts, &mut damus.decks_cache)
+        .find_timeline_mut(req.timeline)
+    else {
+        return;
+    };
+    let Some(newest) = timeline.all_or_any_notes().first() else {
+        return;
+    };
+    // notes dated in the future don't count
+    let created_at = newest.created_at.min(unix_now());
+
+    if let FilterState::Ready(filter) = timeline.filter.get(relay_url) {
+        damus
+            .subscriptions
+            .synced
+            .record(relay_url, filter, created_at);
+    }
+}
+
 fn process_message(damus: &mut Damus, ctx: &mut AppContext<'_>, relay: &str, msg: &RelayMessage) {
     match msg {
-        RelayMessage::Event(_subid, ev) => {
+        RelayMessage::Event(subid, ev) => {
+            damus.subscriptions.got_event(subid, relay);
+            damus.publishes.got_event(relay, ev);
+
             let relay = if let Some(relay) = ctx.pool.relays.iter().find(|r| r.url() == relay) {
                 relay
             } else {
@@ -310,23 +904,21 @@ fn process_message(damus: &mut Damus, ctx: &mut AppContext<'_>, relay: &str, msg
             };
 
             match relay {
-                PoolRelay::Websocket(_) => {
-                    //info!("processing event {}", event);
-                    if let Err(err) = ctx.ndb.process_event(ev) {
-                        error!("error processing event {ev}: {err}");
-                    }
-                }
-                PoolRelay::Multicast(_) => {
-                    // multicast events are client events
-                    if let Err(err) = ctx.ndb.process_client_event(ev) {
-                        error!("error processing multicast event {ev}: {err}");
-                    }
-                }
+                PoolRelay::Websocket(_) => damus.verifier.queue(ev),
+                // multicast events are client events
+                PoolRelay::Multicast(_) => damus.verifier.queue_client(ev),
             }
         }
         RelayMessage::Notice(msg) => warn!("Notice from {}: {}", relay, msg),
-        RelayMessage::OK(cr) => info!("OK {:?}", cr),
+        RelayMessage::OK(cr) => {
+            debug!("OK {:?}", cr);
+            damus
+                .publishes
+                .got_ok(relay, cr.event_id(), cr.status(), cr.message());
+        }
         RelayMessage::Eose(sid) => {
+            damus.subscriptions.got_eose(sid, relay);
+
             if let Err(err) = handle_eose(damus, ctx, sid, relay) {
                 error!("error handling eose: {}", err);
             }
@@ -335,12 +927,91 @@ fn process_message(damus: &mut Damus, ctx: &mut AppContext<'_>, relay: &str, msg
 }
 
 fn render_damus(damus: &mut Damus, app_ctx: &mut AppContext<'_>, ui: &mut egui::Ui) {
-    if notedeck::ui::is_narrow(ui.ctx()) {
+    if let Some(onboarding) = &mut damus.onboarding {
+        let packs = if onboarding.step == OnboardingStep::Interests {
+            damus.follow_packs.packs(app_ctx.ndb, app_ctx.pool)
+        } else {
+            &[]
+        };
+        let resp = OnboardingView::new(
+            onboarding,
+            app_ctx.ndb,
+            app_ctx.img_cache,
+            app_ctx.unknown_ids,
+            packs,
+        )
+        .ui(ui);
+        match resp {
+            Some(OnboardingResponse::Finished(result)) => {
+                damus.onboarding = None;
+                result.apply(damus, app_ctx);
+            }
+            Some(OnboardingResponse::Skipped) => {
+                damus.onboarding = None;
+                // save so we don't ask again next time
+                storage::save_decks_cache(app_ctx.path, &damus.decks_cache);
+            }
+            None => {}
+        }
+        return;
+    }
+
+    let density = get_decks(app_ctx.accounts, &damus.decks_cache)
+        .active()
+        .density;
+    Density::set(ui.ctx(), density);
+    relay_groups::set_names(ui.ctx(), &damus.relay_groups);
+
+    let session = app_ctx.session();
+    if !session.can_sign() {
+        let login = egui::TopBottomPanel::top("session-banner")
+            .show_inside(ui, |ui| ui::session_banner::session_banner(ui, session))
+            .inner;
+
+        if login {
+            get_active_columns_mut(app_ctx.accounts, &mut damus.decks_cache)
+                .get_first_router()
+                .route_to(Route::add_account());
+        }
+    }
+
+    if damus.prefetch.is_offline() {
+        egui::TopBottomPanel::top("offline-banner").show_inside(ui, |ui| {
+            ui::offline_banner::offline_banner(ui, damus.prefetch.last_run)
+        });
+    }
+
+    let narrow = notedeck::ui::is_narrow(ui.ctx());
+    damus.selection.multi_column = !narrow;
+    damus.selection.publish(ui.ctx());
+
+    if narrow {
         render_damus_mobile(damus, app_ctx, ui);
     } else {
         render_damus_desktop(damus, app_ctx, ui);
     }
 
+    match ui::unsent::unsent_window(ui.ctx(), app_ctx.ndb, &damus.hidden) {
+        Some(UnsentAction::Retry(id)) => {
+            if let Err(err) = app_ctx.check(Capability::Publish) {
+                toast::warning(err.message());
+            } else if damus.publishes.resend(app_ctx.ndb, app_ctx.pool, &id) {
+                damus.hidden.unsent.remove(&id);
+                storage::save_hidden(app_ctx.path, &damus.hidden);
+            }
+        }
+        Some(UnsentAction::Discard(id)) => {
+            // still ours to bring back from the hidden notes
+            damus.hidden.unsent.remove(&id);
+            damus.hidden.hide_note(id);
+            storage::save_hidden(app_ctx.path, &damus.hidden);
+        }
+        None => {}
+    }
+
+    let fallback_dir = app_ctx.path.path(DataPathType::Cache);
+    ui::media::MediaViewer::show(ui.ctx(), app_ctx.img_cache, &fallback_dir);
+
     // We use this for keeping timestamps and things up to date
     ui.ctx().request_repaint_after(Duration::from_secs(1));
 }
@@ -369,6 +1040,9 @@ impl Damus {
     pub fn new(ctx: &mut AppContext<'_>, args: &[String]) -> Self {
         // arg parsing
 
+        // registered first so the defaults apply to building the decks
+        settings::register(ctx.settings);
+
         let parsed_args = ColumnsArgs::parse(args);
         let account = ctx
             .accounts
@@ -377,6 +1051,7 @@ impl Damus {
             .map(|a| a.pubkey.bytes());
 
         let tmp_columns = !parsed_args.columns.is_empty();
+        let mut onboarding = None;
         let decks_cache = if tmp_columns {
             info!("DecksCache: loading from command line arguments");
             let mut columns: Columns = Columns::new();
@@ -401,9 +1076,23 @@ impl Damus {
             columns_to_decks_cache(cols, account)
         } else {
             info!("DecksCache: creating new with demo configuration");
+            if ctx.accounts.get_accounts().is_empty() {
+                info!("first run, starting onboarding");
+                onboarding = Some(Onboarding::default());
+            }
             let mut cache = DecksCache::new_with_demo_config(ctx.ndb);
+            let starter = ctx.settings.bool(settings::APP, settings::STARTER_COLUMNS);
             for account in ctx.accounts.get_accounts() {
-                cache.add_deck_default(account.pubkey);
+                if starter {
+                    let deck = onboarding::unsubscribed_starter_deck(
+                        ctx.ndb,
+                        &account.pubkey,
+                        &StarterColumns::login(),
+                    );
+                    cache.add_decks(account.pubkey, Decks::new(deck));
+                } else {
+                    cache.add_deck_default(account.pubkey);
+                }
             }
             set_demo(&mut cache, ctx.ndb, ctx.accounts, ctx.unknown_ids);
 
@@ -414,16 +1103,49 @@ impl Damus {
         let support = Support::new(ctx.path);
 
         Self {
-            subscriptions: Subscriptions::default(),
+            subscriptions: Subscriptions {
+                synced: storage::load_synced(ctx.path),
+                ..Default::default()
+            },
             since_optimize: parsed_args.since_optimize,
             timeline_cache: TimelineCache::default(),
-            drafts: Drafts::default(),
+            drafts: storage::load_drafts(ctx.path),
             state: DamusState::Initializing,
             textmode: parsed_args.textmode,
             //frame_history: FrameHistory::default(),
             view_state: ViewState::default(),
             tmp_columns,
             support,
+            onboarding,
+            starter_lists: StarterLists::default(),
+            unread: 0,
+            notifications_seen: unix_now(),
+            notifier: Notifier::new(storage::load_notification_settings(ctx.path)),
+            hashtag_settings: storage::load_hashtag_settings(ctx.path),
+            home_hashtags: HomeHashtags::default(),
+            hidden: storage::load_hidden(ctx.path),
+            wot: WebOfTrust::default(),
+            wallet: None,
+            wallet_pairing: None,
+            wallet_link: parsed_args.wallet_connect,
+            reload_wallet: true,
+            media_servers: ServerListSubscription::default(),
+            publishes: Publishes::default(),
+            relay_groups: storage::load_relay_groups(ctx.path),
+            export: DataExport::default(),
+            maintenance: Maintenance::default(),
+            settings_sync: SettingsSync::default(),
+            deck_import: DeckImport::default(),
+            prefetch: Prefetch::default(),
+            moderation: Moderation::default(),
+            nip05: Nip05Cache::default(),
+            deletions: DeletionWatch::default(),
+            follow_packs: FollowPacks::default(),
+            trending: Trending::default(),
+            reposts: Reposts::default(),
+            highlights: Highlights::default(),
+            selection: Selection::default(),
+            verifier: Verifier::default(),
             decks_cache,
             debug,
         }
@@ -467,6 +1189,36 @@ impl Damus {
             //frame_history: FrameHistory::default(),
             view_state: ViewState::default(),
             support,
+            onboarding: None,
+            starter_lists: StarterLists::default(),
+            unread: 0,
+            notifications_seen: unix_now(),
+            notifier: Notifier::new(NotificationSettings::default()),
+            hashtag_settings: HashtagSettings::default(),
+            home_hashtags: HomeHashtags::default(),
+            hidden: Hidden::default(),
+            wot: WebOfTrust::default(),
+            wallet: None,
+            wallet_pairing: None,
+            wallet_link: None,
+            reload_wallet: false,
+            media_servers: ServerListSubscription::default(),
+            publishes: Publishes::default(),
+            relay_groups: RelayGroups::default(),
+            export: DataExport::default(),
+            maintenance: Maintenance::default(),
+            settings_sync: SettingsSync::default(),
+            deck_import: DeckImport::default(),
+            prefetch: Prefetch::default(),
+            moderation: Moderation::default(),
+            nip05: Nip05Cache::default(),
+            deletions: DeletionWatch::default(),
+            follow_packs: FollowPacks::default(),
+            trending: Trending::default(),
+            reposts: Reposts::default(),
+            highlights: Highlights::default(),
+            selection: Selection::default(),
+            verifier: Verifier::default(),
             decks_cache,
         }
     }
@@ -525,6 +1277,21 @@ fn render_damus_desktop(app: &mut Damus, app_ctx: &mut AppContext<'_>, ui: &mut
     }
 }
 
+/// Outline a column we sent the user to instead of opening a duplicate
+fn focused_column_outline(ui: &mut egui::Ui, rect: egui::Rect, scroll: bool) {
+    if scroll {
+        ui.scroll_to_rect(rect, None);
+    }
+
+    ui.painter().rect_stroke(
+        rect.shrink(1.0),
+        4.0,
+        egui::Stroke::new(2.0, ui.visuals().selection.stroke.color),
+    );
+    // keep repainting until the outline times out
+    ui.ctx().request_repaint_after(Duration::from_millis(100));
+}
+
 fn timelines_view(ui: &mut egui::Ui, sizes: Size, app: &mut Damus, ctx: &mut AppContext<'_>) {
     StripBuilder::new(ui)
         .size(Size::exact(ui::side_panel::SIDE_PANEL_WIDTH))
@@ -568,6 +1335,7 @@ fn timelines_view(ui: &mut egui::Ui, sizes: Size, app: &mut Damus, ctx: &mut App
             let mut save_cols = false;
             if let Some(action) = side_panel_action {
                 save_cols = save_cols || action.process(&mut app.decks_cache, ctx);
+                app.selection.columns_changed();
             }
 
             let num_cols = app.columns(ctx.accounts).num_columns();
@@ -583,6 +1351,10 @@ fn timelines_view(ui: &mut egui::Ui, sizes: Size, app: &mut Damus, ctx: &mut App
                         rect.y_range(),
                         ui.visuals().widgets.noninteractive.bg_stroke,
                     );
+
+                    if app.selection.focused_column() == Some(col_index) {
+                        focused_column_outline(ui, rect, app.selection.take_scroll());
+                    }
                 });
 
                 //strip.cell(|ui| timeline::timeline_view(ui, app, timeline_ind));
@@ -613,7 +1385,123 @@ impl notedeck::App for Damus {
 
         update_damus(self, ctx, ui.ctx());
         render_damus(self, ctx, ui);
+        update_unread(self, ctx.accounts, ui.ctx());
+    }
+
+    fn command(&mut self, ctx: &mut AppContext<'_>, command: AppCommand) {
+        match command {
+            AppCommand::ComposeNote => open_composer(self, ctx),
+
+            AppCommand::ComposeWith(text) => {
+                self.drafts.compose_mut().append(&text);
+                open_composer(self, ctx);
+            }
+
+            AppCommand::OpenProfile(pubkey) => {
+                open_in_first_column(self, ctx, NoteAction::OpenProfile(pubkey));
+            }
+
+            AppCommand::OpenThread(note_id) => {
+                open_in_first_column(self, ctx, NoteAction::OpenThread(note_id));
+            }
+
+            // we don't claim any kinds
+            AppCommand::OpenAddress(_) => {}
+        }
+    }
+
+    fn views(&self, ctx: &mut AppContext<'_>) -> Vec<AppView> {
+        let columns = self.columns(ctx.accounts);
+        let deck_author = ctx.accounts.get_selected_account().map(|acc| &acc.pubkey);
+        let Ok(txn) = Transaction::new(ctx.ndb) else {
+            return vec![];
+        };
+
+        columns
+            .columns()
+            .into_iter()
+            .enumerate()
+            .map(|(index, column)| {
+                let title = match column.router().top().title(columns) {
+                    ColumnTitle::Simple(title) => title.into_owned(),
+                    ColumnTitle::NeedsDb(need_db) => {
+                        need_db.title(&txn, ctx.ndb, deck_author).to_owned()
+                    }
+                };
+                AppView { index, title }
+            })
+            .collect()
+    }
+
+    fn manifest(&self) -> Option<AppManifest> {
+        Some(AppManifest {
+            id: settings::APP,
+            name: tr!("app-columns"),
+            icon: "📰",
+            kinds: vec![],
+            settings: Some(tr!("settings-columns")),
+            capabilities: vec![
+                Capability::Publish,
+                Capability::ReadContacts,
+                Capability::Network,
+                Capability::Camera,
+            ],
+        })
     }
+
+    fn account_changed(&mut self, ctx: &mut AppContext<'_>, change: AccountChange) {
+        // drafts are only written every so often, don't lose the last
+        // few keystrokes
+        if ctx.settings.bool(settings::APP, settings::AUTOSAVE_DRAFTS) {
+            storage::save_drafts(ctx.path, &self.drafts);
+        }
+
+        // an unsaved relay list edit belongs to the previous account
+        self.view_state.relay_list_draft = Default::default();
+
+        self.wot.update(
+            ctx.ndb,
+            change.current.as_ref().map(|pk| pk.bytes()),
+            settings::wot_params(ctx.settings),
+        );
+        self.media_servers.update(ctx.pool, change.current.as_ref());
+
+        // each account has its own wallet
+        if let Some(wallet) = self.wallet.take() {
+            wallet.disconnect();
+        }
+        if let Some(pairing) = self.wallet_pairing.take() {
+            pairing.disconnect();
+        }
+        self.reload_wallet = true;
+    }
+
+    fn unread_count(&self) -> usize {
+        self.unread
+    }
+}
+
+/// Count notifications that came in since the window was last focused
+fn update_unread(damus: &mut Damus, accounts: &Accounts, ctx: &egui::Context) {
+    if ctx.input(|i| i.focused) {
+        damus.notifications_seen = unix_now();
+        damus.unread = 0;
+        return;
+    }
+
+    let seen = damus.notifications_seen;
+    damus.unread = get_active_columns(accounts, &damus.decks_cache)
+        .timelines()
+        .iter()
+        .filter(|timeline| matches!(timeline.kind, TimelineKind::Notifications(_)))
+        .map(|timeline| {
+            timeline
+                .all_or_any_notes()
+                .iter()
+                .take_while(|note| note.created_at > seen)
+                .count()
+        })
+        .sum();
 }
 
 pub fn get_active_columns<'a>(accounts: &Accounts, decks_cache: &'a DecksCache) -> &'a Columns {
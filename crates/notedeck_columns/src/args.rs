@@ -1,7 +1,7 @@
 use notedeck::FilterState;
 
 use crate::timeline::{PubkeySource, Timeline, TimelineKind, TimelineTab};
-use enostr::{Filter, Pubkey};
+use enostr::{Filter, NwcUri, Pubkey};
 use nostrdb::Ndb;
 use tracing::{debug, error, info};
 
@@ -9,6 +9,9 @@ pub struct ColumnsArgs {
     pub columns: Vec<ArgColumn>,
     pub since_optimize: bool,
     pub textmode: bool,
+    /// A `nostr+walletconnect:` link we were opened with, eg. by the OS
+    /// handing us a link that was clicked somewhere else
+    pub wallet_connect: Option<NwcUri>,
 }
 
 impl ColumnsArgs {
@@ -17,6 +20,7 @@ impl ColumnsArgs {
             columns: vec![],
             since_optimize: true,
             textmode: false,
+            wallet_connect: None,
         };
 
         let mut i = 0;
@@ -128,6 +132,9 @@ impl ColumnsArgs {
                 } else {
                     error!("failed to parse filter in '{}'", filter_file);
                 }
+            } else if let Ok(uri) = NwcUri::parse(arg) {
+                info!("got a wallet connect link");
+                res.wallet_connect = Some(uri);
             }
 
             i += 1;
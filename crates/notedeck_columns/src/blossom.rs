@@ -0,0 +1,377 @@
+//! Blossom media servers (BUD-01 to BUD-04). Blobs are addressed by the
+//! sha256 of their contents, so the same file can live on any number of
+//! servers and a broken link can be found again on another one.
+
+use notedeck::{time::unix_now, Accounts};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use base64::Engine;
+use enostr::{FilledKeypair, Pubkey, RelayPool};
+use nostrdb::{Filter, Ndb, Note, NoteBuilder, Transaction};
+use poll_promise::Promise;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// BUD-03 user server list
+pub const SERVER_LIST_KIND: u32 = 10063;
+/// BUD-01 authorization event
+const AUTH_KIND: u32 = 24242;
+/// How long an upload authorization is good for, in seconds
+const AUTH_EXPIRY: u64 = 300;
+
+/// What servers hand back for a stored blob
+#[derive(Debug, Clone, Deserialize)]
+struct BlobDescriptor {
+    url: String,
+}
+
+/// The servers a pubkey keeps their media on, in order of preference
+pub fn server_list(ndb: &Ndb, txn: &Transaction, pubkey: &[u8; 32]) -> Vec<String> {
+    let filter = Filter::new()
+        .authors([pubkey])
+        .kinds([SERVER_LIST_KIND as u64])
+        .limit(1)
+        .build();
+    let Ok(results) = ndb.query(txn, &[filter], 1) else {
+        return vec![];
+    };
+    let Some(list) = results.first() else {
+        return vec![];
+    };
+
+    list.note
+        .tags()
+        .iter()
+        .filter(|tag| tag.count() >= 2 && tag.get_unchecked(0).variant().str() == Some("server"))
+        .filter_map(|tag| tag.get_unchecked(1).variant().str())
+        .map(|server| server.trim_end_matches('/').to_owned())
+        .collect()
+}
+
+/// Sign and publish a new server list for an account
+pub fn publish_server_list(
+    accounts: &Accounts,
+    ndb: &Ndb,
+    pool: &mut RelayPool,
+    keypair: FilledKeypair,
+    servers: &[String],
+) -> crate::Result<()> {
+    let mut builder = NoteBuilder::new().kind(SERVER_LIST_KIND).content("");
+    for server in servers {
+        builder = builder.start_tag().tag_str("server").tag_str(server);
+    }
+
+    let note = builder
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or_else(|| crate::Error::Generic("failed to build media server list".to_owned()))?;
+
+    accounts.publish_note(ndb, pool, &note)?;
+
+    info!("published media server list");
+    Ok(())
+}
+
+/// Keeps the selected account's server list coming in from relays
+#[derive(Default)]
+pub struct ServerListSubscription {
+    subscribed: Option<(Pubkey, String)>,
+}
+
+impl ServerListSubscription {
+    pub fn update(&mut self, pool: &mut RelayPool, selected: Option<&Pubkey>) {
+        if self.subscribed.as_ref().map(|(pk, _)| pk) == selected {
+            return;
+        }
+
+        if let Some((_, subid)) = self.subscribed.take() {
+            pool.unsubscribe(subid);
+        }
+
+        if let Some(pubkey) = selected {
+            let subid = Uuid::new_v4().to_string();
+            let filter = Filter::new()
+                .authors([pubkey.bytes()])
+                .kinds([SERVER_LIST_KIND as u64])
+                .limit(1)
+                .build();
+            pool.subscribe(subid.clone(), vec![filter]);
+            self.subscribed = Some((*pubkey, subid));
+        }
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// The blob hash in a media url, and its file extension if it has one
+pub fn blob_hash(url: &str) -> Option<(&str, &str)> {
+    let url = url.split(['?', '#']).next()?;
+    let name = url.rsplit('/').next()?;
+    let (hash, ext) = match name.find('.') {
+        Some(dot) => name.split_at(dot),
+        None => (name, ""),
+    };
+
+    let is_hash = hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit());
+    is_hash.then_some((hash, ext))
+}
+
+/// Other places the blob behind a broken url might be. Each url is only
+/// repaired once, so a blob that's gone everywhere doesn't get retried
+/// every frame.
+pub fn repair_urls(ctx: &egui::Context, url: &str, servers: &[String]) -> Vec<String> {
+    let Some((hash, ext)) = blob_hash(url) else {
+        return vec![];
+    };
+
+    let id = egui::Id::new("blossom-repaired");
+    let first_time = ctx.data_mut(|d| {
+        d.get_temp_mut_or_default::<HashSet<String>>(id)
+            .insert(url.to_owned())
+    });
+    if !first_time {
+        return vec![];
+    }
+
+    servers
+        .iter()
+        .map(|server| format!("{server}/{hash}{ext}"))
+        .filter(|candidate| candidate != url)
+        .collect()
+}
+
+/// The `Authorization` header for a BUD-01 request
+fn authorization(keypair: FilledKeypair, verb: &str, hash: &str) -> Result<String, String> {
+    let expiration = (unix_now() + AUTH_EXPIRY).to_string();
+    let note: Note = NoteBuilder::new()
+        .kind(AUTH_KIND)
+        .content(&format!("{verb} {hash}"))
+        .start_tag()
+        .tag_str("t")
+        .tag_str(verb)
+        .start_tag()
+        .tag_str("x")
+        .tag_str(hash)
+        .start_tag()
+        .tag_str("expiration")
+        .tag_str(&expiration)
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or_else(|| "failed to sign upload authorization".to_owned())?;
+
+    let json = note.json().map_err(|err| err.to_string())?;
+    Ok(format!(
+        "Nostr {}",
+        base64::engine::general_purpose::STANDARD.encode(json)
+    ))
+}
+
+fn put(url: String, auth: &str, content_type: &str, body: Vec<u8>) -> Result<String, String> {
+    let mut request = ehttp::Request::post(url, body);
+    request.method = "PUT".to_owned();
+    request
+        .headers
+        .insert("Authorization".to_owned(), auth.to_owned());
+    request
+        .headers
+        .insert("Content-Type".to_owned(), content_type.to_owned());
+
+    let response = ehttp::fetch_blocking(&request)?;
+    if !response.ok {
+        let reason = response
+            .headers
+            .get("x-reason")
+            .cloned()
+            .unwrap_or(response.status_text);
+        return Err(format!("{} {reason}", response.status));
+    }
+
+    serde_json::from_slice::<BlobDescriptor>(&response.bytes)
+        .map(|blob| blob.url)
+        .map_err(|err| err.to_string())
+}
+
+/// A file on its way to the media servers
+pub struct Upload {
+    /// What the user knows the file as
+    pub name: String,
+    pub size: usize,
+    servers: usize,
+    /// Servers we're done with, counting mirrors
+    done: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    promise: Promise<Result<String, String>>,
+}
+
+impl Upload {
+    /// How far along the upload and its mirrors are, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        (self.done.load(Ordering::Relaxed) as f32 / self.servers.max(1) as f32).min(1.0)
+    }
+
+    /// Stop before the next server. A request that's already underway
+    /// still finishes, but nobody is waiting for it anymore.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.promise.ready().is_some()
+    }
+
+    /// The url of the first copy. Blocks if the upload isn't done.
+    pub fn take(self) -> Result<String, String> {
+        self.promise.block_and_take()
+    }
+}
+
+/// Upload a file to the first server that takes it and mirror it to the
+/// rest (BUD-04). Resolves to the url of the first copy.
+pub fn upload(
+    ctx: &egui::Context,
+    servers: Vec<String>,
+    keypair: FilledKeypair,
+    name: String,
+    bytes: Vec<u8>,
+    content_type: String,
+) -> Upload {
+    let done = Arc::new(AtomicUsize::new(0));
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (size, server_count) = (bytes.len(), servers.len());
+
+    let hash = sha256_hex(&bytes);
+    let promise = match authorization(keypair, "upload", &hash) {
+        Ok(auth) => upload_thread(
+            ctx.clone(),
+            servers,
+            auth,
+            hash,
+            bytes,
+            content_type,
+            done.clone(),
+            cancel.clone(),
+        ),
+        Err(err) => Promise::from_ready(Err(err)),
+    };
+
+    Upload {
+        name,
+        size,
+        servers: server_count,
+        done,
+        cancel,
+        promise,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn upload_thread(
+    ctx: egui::Context,
+    servers: Vec<String>,
+    auth: String,
+    hash: String,
+    bytes: Vec<u8>,
+    content_type: String,
+    done: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+) -> Promise<Result<String, String>> {
+    Promise::spawn_thread("blossom-upload", move || {
+        let mut uploaded: Option<(usize, String)> = None;
+        let mut last_err = "no media servers".to_owned();
+
+        for (i, server) in servers.iter().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                return Err("cancelled".to_owned());
+            }
+
+            let result = put(
+                format!("{server}/upload"),
+                &auth,
+                &content_type,
+                bytes.clone(),
+            );
+            done.fetch_add(1, Ordering::Relaxed);
+            ctx.request_repaint();
+
+            match result {
+                Ok(url) => {
+                    info!("uploaded {hash} to {server}");
+                    uploaded = Some((i, url));
+                    break;
+                }
+                Err(err) => {
+                    error!("upload to {server} failed: {err}");
+                    last_err = err;
+                }
+            }
+        }
+
+        let result = match uploaded {
+            Some((first, url)) => {
+                let body = serde_json::json!({ "url": url }).to_string().into_bytes();
+                for server in servers.iter().skip(first + 1) {
+                    if cancel.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    match put(
+                        format!("{server}/mirror"),
+                        &auth,
+                        "application/json",
+                        body.clone(),
+                    ) {
+                        Ok(_) => debug!("mirrored {hash} to {server}"),
+                        Err(err) => error!("mirroring to {server} failed: {err}"),
+                    }
+                    done.fetch_add(1, Ordering::Relaxed);
+                    ctx.request_repaint();
+                }
+                Ok(url)
+            }
+            None => Err(last_err),
+        };
+
+        ctx.request_repaint();
+        result
+    })
+}
+
+/// A guess at a file's type from its contents, for the upload headers
+pub fn content_type(bytes: &[u8]) -> String {
+    image::guess_format(bytes)
+        .map(|format| format.to_mime_type().to_owned())
+        .unwrap_or_else(|_| "application/octet-stream".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "b1674191a88ec5cdd733e4240a81803105dc412d6c6708d53ab94fc248f4f553";
+
+    #[test]
+    fn test_blob_hash() {
+        let url = format!("https://cdn.example.com/{HASH}.png");
+        assert_eq!(blob_hash(&url), Some((HASH, ".png")));
+
+        let url = format!("https://cdn.example.com/media/{HASH}?size=large");
+        assert_eq!(blob_hash(&url), Some((HASH, "")));
+
+        assert_eq!(blob_hash("https://example.com/cat.png"), None);
+        assert_eq!(blob_hash("https://example.com/abcd.png"), None);
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::route::{Route, Router};
-use crate::timeline::{Timeline, TimelineId};
+use crate::timeline::{PubkeySource, Timeline, TimelineId, TimelineKind, TimelineRoute};
 use indexmap::IndexMap;
 use std::iter::Iterator;
 use std::sync::atomic::{AtomicU32, Ordering};
@@ -178,6 +178,33 @@ impl Columns {
         self.timelines.get(&col_id)
     }
 
+    /// A column already showing `route`, so it can be reused instead of
+    /// opening the same thing twice. A profile timeline column counts as
+    /// showing that person's profile.
+    pub fn find_showing(&self, route: &Route) -> Option<usize> {
+        (0..self.num_columns()).find(|&ind| {
+            let top = self.column(ind).router().top();
+            if top == route {
+                return true;
+            }
+
+            let Route::Timeline(TimelineRoute::Profile(pubkey)) = route else {
+                return false;
+            };
+            top.timeline_id().is_some()
+                && matches!(
+                    self.find_timeline_for_column_index(ind).map(|tl| &tl.kind),
+                    Some(TimelineKind::Profile(PubkeySource::Explicit(pk))) if pk == pubkey
+                )
+        })
+    }
+
+    pub fn select_column(&mut self, ind: usize) {
+        if ind < self.columns.len() {
+            self.selected = ind as i32;
+        }
+    }
+
     pub fn select_down(&mut self) {
         warn!("todo: implement select_down");
     }
@@ -241,3 +268,31 @@ pub enum ColumnsAction {
     Switch(usize, usize), // from Switch.0 to Switch.1,
     Remove(usize),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline::TimelineTab;
+    use enostr::{NoteId, Pubkey};
+    use notedeck::FilterState;
+
+    #[test]
+    fn test_find_showing() {
+        let alice = Pubkey::new([1; 32]);
+        let bob = Pubkey::new([2; 32]);
+        let thread = Route::thread(NoteId::new([3; 32]));
+
+        let mut columns = Columns::new();
+        columns.add_new_timeline_column(Timeline::new(
+            TimelineKind::profile(PubkeySource::Explicit(alice)),
+            FilterState::Ready(vec![]),
+            TimelineTab::profile_tabs(),
+        ));
+        columns.add_column(Column::new(vec![Route::profile(bob), thread]));
+
+        assert_eq!(columns.find_showing(&Route::profile(alice)), Some(0));
+        assert_eq!(columns.find_showing(&thread), Some(1));
+        // bob's profile is under the thread, so it isn't showing
+        assert_eq!(columns.find_showing(&Route::profile(bob)), None);
+    }
+}
@@ -0,0 +1,179 @@
+//! Sharing a deck with someone else, either as JSON to paste or published
+//! as NIP-78 app data that anyone can import from its naddr. An import is
+//! read into a preview first, so the user sees what will be added and
+//! which columns didn't survive the trip.
+
+use enostr::{Address, FilledKeypair, Pubkey, RelayPool};
+use nostrdb::{Filter, Ndb, NoteBuilder, Transaction};
+use notedeck::Accounts;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    decks::Deck,
+    storage::{self, ImportedDeck},
+};
+
+const DECK_KIND: u32 = 30078;
+/// Shared decks are told apart from other app data by their `d` tag
+const D_PREFIX: &str = "notedeck/deck/";
+
+/// `d` tag for a deck, so sharing it again replaces the last copy
+fn identifier(name: &str) -> String {
+    let slug: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{D_PREFIX}{slug}")
+}
+
+/// Sign and send a deck, returning where it can be found
+pub fn publish(
+    accounts: &Accounts,
+    ndb: &Ndb,
+    pool: &mut RelayPool,
+    keypair: &FilledKeypair,
+    deck: &Deck,
+) -> Option<Address> {
+    let json = storage::deck_to_json(deck)?;
+    let address = Address {
+        kind: DECK_KIND,
+        pubkey: *keypair.pubkey,
+        identifier: identifier(&deck.name),
+    };
+
+    let note = NoteBuilder::new()
+        .kind(DECK_KIND)
+        .content(&json)
+        .start_tag()
+        .tag_str("d")
+        .tag_str(&address.identifier)
+        .start_tag()
+        .tag_str("alt")
+        .tag_str(&format!("Notedeck deck: {}", deck.name))
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()?;
+
+    if let Err(err) = accounts.publish_note(ndb, pool, &note) {
+        error!("could not publish deck: {err}");
+        return None;
+    }
+
+    Some(address)
+}
+
+fn filter(address: &Address) -> Filter {
+    Filter::new()
+        .authors([address.pubkey.bytes()])
+        .kinds([address.kind as u64])
+        .tags([address.identifier.clone()], 'd')
+        .limit(1)
+        .build()
+}
+
+/// The deck's JSON, if we have the event
+fn find(ndb: &Ndb, address: &Address) -> Option<String> {
+    let txn = Transaction::new(ndb).ok()?;
+    let results = ndb.query(&txn, &[filter(address)], 1).ok()?;
+    Some(results.first()?.note.content().to_owned())
+}
+
+/// The import flow on the new deck screen
+#[derive(Default)]
+pub struct DeckImport {
+    /// Pasted JSON or an naddr
+    pub input: String,
+    /// Read and waiting to be added
+    pub preview: Option<ImportedDeck>,
+    pub error: Option<String>,
+    /// An naddr we don't have yet, and the relay subscription asking for it
+    waiting: Option<(Address, String)>,
+}
+
+impl DeckImport {
+    pub fn is_waiting(&self) -> bool {
+        self.waiting.is_some()
+    }
+
+    /// Read whatever was pasted, asking relays for it if it's an naddr we
+    /// haven't seen
+    pub fn read(&mut self, ndb: &Ndb, pool: &mut RelayPool, deck_user: &Pubkey) {
+        self.stop_waiting(pool);
+        self.preview = None;
+        self.error = None;
+
+        let input = self.input.trim();
+        if !input.starts_with('{') {
+            let Some(address) = Address::from_bech(input)
+                .filter(|a| a.kind == DECK_KIND && a.identifier.starts_with(D_PREFIX))
+            else {
+                self.error = Some("not a shared deck or deck naddr".to_owned());
+                return;
+            };
+
+            match find(ndb, &address) {
+                Some(json) => self.parse(&json, ndb, deck_user),
+                None => {
+                    let subid = Uuid::new_v4().to_string();
+                    pool.subscribe(subid.clone(), vec![filter(&address)]);
+                    info!("asking relays for shared deck {}", address.coordinate());
+                    self.waiting = Some((address, subid));
+                }
+            }
+            return;
+        }
+
+        let json = input.to_owned();
+        self.parse(&json, ndb, deck_user);
+    }
+
+    fn parse(&mut self, json: &str, ndb: &Ndb, deck_user: &Pubkey) {
+        match storage::deck_from_json(json, ndb, deck_user) {
+            Ok(imported) => self.preview = Some(imported),
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    /// Called every frame, picks up a deck we asked relays for
+    pub fn poll(&mut self, ndb: &Ndb, pool: &mut RelayPool, deck_user: Option<&Pubkey>) {
+        let Some((address, _)) = &self.waiting else {
+            return;
+        };
+        let (Some(json), Some(deck_user)) = (find(ndb, address), deck_user) else {
+            return;
+        };
+
+        self.stop_waiting(pool);
+        self.parse(&json, ndb, deck_user);
+    }
+
+    fn stop_waiting(&mut self, pool: &mut RelayPool) {
+        if let Some((_, subid)) = self.waiting.take() {
+            pool.unsubscribe(subid);
+        }
+    }
+
+    /// Start over, leaving nothing behind on relays
+    pub fn clear(&mut self, pool: &mut RelayPool) {
+        self.stop_waiting(pool);
+        *self = DeckImport::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier(" My Deck! "), "notedeck/deck/my-deck-");
+        assert_eq!(identifier("News"), identifier("news"));
+    }
+}
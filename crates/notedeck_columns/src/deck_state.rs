@@ -1,9 +1,10 @@
-use crate::{app_style::emoji_font_family, decks::Deck};
+use crate::{app_style::emoji_font_family, decks::Deck, ui::density::Density};
 
 /// State for UI creating/editing deck
 pub struct DeckState {
     pub deck_name: String,
     pub selected_glyph: Option<char>,
+    pub density: Density,
     pub selecting_glyph: bool,
     pub warn_no_title: bool,
     pub warn_no_icon: bool,
@@ -14,6 +15,7 @@ impl DeckState {
     pub fn load(&mut self, deck: &Deck) {
         self.deck_name = deck.name.clone();
         self.selected_glyph = Some(deck.icon);
+        self.density = deck.density;
     }
 
     pub fn from_deck(deck: &Deck) -> Self {
@@ -22,6 +24,7 @@ impl DeckState {
         Self {
             deck_name,
             selected_glyph,
+            density: deck.density,
             ..Default::default()
         }
     }
@@ -41,6 +44,7 @@ impl Default for DeckState {
         Self {
             deck_name: Default::default(),
             selected_glyph: Default::default(),
+            density: Default::default(),
             selecting_glyph: true,
             warn_no_icon: Default::default(),
             warn_no_title: Default::default(),
@@ -9,7 +9,7 @@ use crate::{
     column::{Column, Columns},
     route::Route,
     timeline::{self, Timeline, TimelineKind},
-    ui::{add_column::AddColumnRoute, configure_deck::ConfigureDeckResponse},
+    ui::{add_column::AddColumnRoute, configure_deck::ConfigureDeckResponse, density::Density},
 };
 
 pub static FALLBACK_PUBKEY: fn() -> Pubkey = || {
@@ -250,6 +250,7 @@ impl Decks {
 pub struct Deck {
     pub icon: char,
     pub name: String,
+    pub density: Density,
     columns: Columns,
 }
 
@@ -260,6 +261,7 @@ impl Default for Deck {
         Self {
             icon: '🇩',
             name: String::from("Default Deck"),
+            density: Density::default(),
             columns,
         }
     }
@@ -272,6 +274,7 @@ impl Deck {
         Self {
             icon,
             name,
+            density: Density::default(),
             columns,
         }
     }
@@ -280,6 +283,7 @@ impl Deck {
         Self {
             icon,
             name,
+            density: Density::default(),
             columns,
         }
     }
@@ -295,6 +299,7 @@ impl Deck {
     pub fn edit(&mut self, changes: ConfigureDeckResponse) {
         self.name = changes.name;
         self.icon = changes.icon;
+        self.density = changes.density;
     }
 }
 
@@ -318,6 +323,7 @@ pub fn demo_decks(demo_pubkey: Pubkey, ndb: &Ndb) -> Decks {
         Deck {
             icon: '🇩',
             name: String::from("Demo Deck"),
+            density: Density::default(),
             columns,
         }
     };
@@ -0,0 +1,109 @@
+use enostr::{Address, NoteId, NwcUri, Pubkey};
+
+use crate::actionbar::NoteAction;
+
+/// Somewhere a `nostr:` URI, or a bare bech32 entity, can take us. Wallet
+/// connect links come the same ways, from QR codes and the command line.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DeepLink {
+    Profile(Pubkey),
+    Note(NoteId),
+    /// Addressable events belong to whichever app claims their kind
+    Address(Address),
+    /// A wallet to pair with, tested before it's saved
+    WalletConnect(NwcUri),
+}
+
+impl DeepLink {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Ok(uri) = NwcUri::parse(text) {
+            return Some(DeepLink::WalletConnect(uri));
+        }
+
+        let entity = text
+            .get(..6)
+            .filter(|scheme| scheme.eq_ignore_ascii_case("nostr:"))
+            .map(|_| &text[6..])
+            .unwrap_or(text);
+
+        if let Some(pubkey) = Pubkey::from_bech(entity) {
+            Some(DeepLink::Profile(pubkey))
+        } else if let Some(address) = Address::from_bech(entity) {
+            Some(DeepLink::Address(address))
+        } else {
+            NoteId::from_bech(entity).map(DeepLink::Note)
+        }
+    }
+
+    /// What to do in a column. Addresses are for the chrome to route.
+    pub fn action(&self) -> Option<NoteAction> {
+        match self {
+            DeepLink::Profile(pubkey) => Some(NoteAction::OpenProfile(*pubkey)),
+            DeepLink::Note(note_id) => Some(NoteAction::OpenThread(*note_id)),
+            DeepLink::Address(_) | DeepLink::WalletConnect(_) => None,
+        }
+    }
+}
+
+fn open_address_id() -> egui::Id {
+    egui::Id::new("open-address")
+}
+
+/// An naddr link was clicked in a note. The app hands it to the chrome.
+pub fn open_address(ctx: &egui::Context, address: Address) {
+    ctx.data_mut(|d| d.insert_temp(open_address_id(), address));
+}
+
+pub fn take_address(ctx: &egui::Context) -> Option<Address> {
+    ctx.data_mut(|d| d.remove_temp::<Address>(open_address_id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_deep_links() {
+        let pubkey =
+            Pubkey::from_hex("3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d")
+                .unwrap();
+        let npub = pubkey.to_bech().unwrap();
+
+        assert_eq!(DeepLink::parse(&npub), Some(DeepLink::Profile(pubkey)));
+        assert_eq!(
+            DeepLink::parse(&format!("nostr:{npub}")),
+            Some(DeepLink::Profile(pubkey))
+        );
+        assert_eq!(
+            DeepLink::parse("nostr:nprofile1qqsrhuxx8l9ex335q7he0f09aej04zpazpl0ne2cgukyawd24mayt8gpp4mhxue69uhhytnc9e3k7mgpz4mhxue69uhkg6nzv9ejuumpv34kytnrdaksjlyr9p"),
+            Some(DeepLink::Profile(pubkey))
+        );
+
+        let note_id = NoteId::new([7; 32]);
+        let note = note_id.to_bech().unwrap();
+        assert_eq!(
+            DeepLink::parse(&format!("NOSTR:{note}")),
+            Some(DeepLink::Note(note_id))
+        );
+
+        let address = Address {
+            kind: 31922,
+            pubkey,
+            identifier: "party".to_owned(),
+        };
+        let naddr = address.to_bech().unwrap();
+        assert_eq!(
+            DeepLink::parse(&format!("nostr:{naddr}")),
+            Some(DeepLink::Address(address))
+        );
+
+        let nwc = "nostr+walletconnect://b889ff5b1513b641e2a139f661a661364979c5beee91842f8f0ef42ab558e9d4?relay=wss://relay.damus.io&secret=71a8c14c1407c113601079c4302dab36460f0ccd0ad506f1f2dc73b5100e4f3c";
+        assert_eq!(
+            DeepLink::parse(nwc),
+            Some(DeepLink::WalletConnect(NwcUri::parse(nwc).unwrap()))
+        );
+
+        assert_eq!(DeepLink::parse("https://damus.io"), None);
+    }
+}
@@ -0,0 +1,164 @@
+//! Keeps up with NIP-09 deletion requests from the account and the people
+//! it follows, so deleted notes disappear from every view. The author can
+//! choose to see a tombstone where their own deleted notes were.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use enostr::{ClientMessage, RelayPool};
+use nostrdb::{Ndb, Note, Subscription, Transaction};
+use notedeck::{contacts, deletion::Deletions, Accounts, Settings};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::settings;
+
+/// How often to check whether the people we follow changed
+const AUTHORS_REFRESH: Duration = Duration::from_secs(30);
+
+/// What note views need to know about deletions
+#[derive(Debug, Default)]
+pub struct Tombstones {
+    deletions: Arc<Deletions>,
+    /// The account that sees its own deleted notes as tombstones
+    shown_to: Option<[u8; 32]>,
+}
+
+pub enum Deleted {
+    /// Show nothing
+    Hide,
+    /// Show that it was deleted, to its author
+    Tombstone,
+}
+
+impl Tombstones {
+    pub fn deleted(&self, note: &Note) -> Option<Deleted> {
+        if !self.deletions.is_deleted(note) {
+            return None;
+        }
+        Some(if self.shown_to.as_ref() == Some(note.pubkey()) {
+            Deleted::Tombstone
+        } else {
+            Deleted::Hide
+        })
+    }
+}
+
+fn tombstones_id() -> egui::Id {
+    egui::Id::new("deletion-tombstones")
+}
+
+pub fn tombstones(ctx: &egui::Context) -> Option<Arc<Tombstones>> {
+    ctx.data(|d| d.get_temp::<Arc<Tombstones>>(tombstones_id()))
+}
+
+#[derive(Default)]
+pub struct DeletionWatch {
+    account: Option<[u8; 32]>,
+    authors: Vec<[u8; 32]>,
+    authors_checked: Option<Instant>,
+    sub: Option<(Subscription, String)>,
+    tombstones: Arc<Tombstones>,
+}
+
+impl DeletionWatch {
+    pub fn send_initial_filter(&self, pool: &mut RelayPool, relay_url: &str) {
+        if let Some((_, subid)) = &self.sub {
+            pool.send_to(
+                &ClientMessage::req(subid.clone(), vec![Deletions::filter(&self.authors)]),
+                relay_url,
+            );
+        }
+    }
+
+    /// Returns the deletions when they change, eg. to drop deleted events
+    /// before they reach nostrdb
+    pub fn update(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        accounts: &Accounts,
+        app_settings: &Settings,
+        ctx: &egui::Context,
+    ) -> Option<Arc<Deletions>> {
+        let selected = accounts
+            .get_selected_account()
+            .map(|acc| *acc.pubkey.bytes());
+        let shown_to = selected.filter(|_| app_settings.bool(settings::APP, settings::TOMBSTONES));
+        let mut changed = shown_to != self.tombstones.shown_to;
+
+        let now = Instant::now();
+        let stale = selected != self.account
+            || !self
+                .authors_checked
+                .is_some_and(|checked| now.duration_since(checked) < AUTHORS_REFRESH);
+        if stale {
+            self.account = selected;
+            self.authors_checked = Some(now);
+            let authors = authors(ndb, selected);
+            if authors != self.authors {
+                self.authors = authors;
+                self.resubscribe(ndb, pool);
+                changed = true;
+            }
+        }
+
+        if let Some((sub, _)) = &self.sub {
+            if !ndb.poll_for_notes(*sub, 100).is_empty() {
+                changed = true;
+            }
+        }
+
+        let reloaded = changed.then(|| {
+            let deletions = match Transaction::new(ndb) {
+                Ok(txn) => Arc::new(Deletions::load(ndb, &txn, &self.authors)),
+                Err(_) => self.tombstones.deletions.clone(),
+            };
+            self.tombstones = Arc::new(Tombstones {
+                deletions: deletions.clone(),
+                shown_to,
+            });
+            deletions
+        });
+        ctx.data_mut(|d| d.insert_temp(tombstones_id(), self.tombstones.clone()));
+
+        reloaded
+    }
+
+    fn resubscribe(&mut self, ndb: &Ndb, pool: &mut RelayPool) {
+        if let Some((sub, subid)) = self.sub.take() {
+            if let Err(err) = ndb.unsubscribe(sub) {
+                error!("deletions: unsubscribe failed: {err}");
+            }
+            pool.unsubscribe(subid);
+        }
+        if self.authors.is_empty() {
+            return;
+        }
+
+        match ndb.subscribe(&[Deletions::filter(&self.authors)]) {
+            Ok(sub) => {
+                let subid = Uuid::new_v4().to_string();
+                pool.subscribe(subid.clone(), vec![Deletions::filter(&self.authors)]);
+                self.sub = Some((sub, subid));
+            }
+            Err(err) => error!("deletions: subscribe failed: {err}"),
+        }
+    }
+}
+
+/// The account and the people it follows
+fn authors(ndb: &Ndb, account: Option<[u8; 32]>) -> Vec<[u8; 32]> {
+    let Some(account) = account else {
+        return vec![];
+    };
+    let Ok(txn) = Transaction::new(ndb) else {
+        return vec![account];
+    };
+
+    let mut authors = contacts::followed(ndb, &txn, &account);
+    authors.push(account);
+    authors.sort();
+    authors.dedup();
+    authors
+}
@@ -1,9 +1,44 @@
-use crate::ui::note::PostType;
-use std::collections::HashMap;
+use crate::blossom::Upload;
+use crate::geo::Place;
+use crate::gifs::Gif;
+use crate::ui::note::{PostPreview, PostType};
+use notedeck::PowMiner;
+use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Don't write drafts to disk more often than this while typing
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(2);
 
 #[derive(Default)]
 pub struct Draft {
     pub buffer: String,
+    /// NIP-13 target difficulty for this draft, 0 disables proof-of-work
+    pub pow: u8,
+    /// Set while we're mining proof-of-work for this draft
+    pub miner: Option<PowMiner>,
+    /// How long after posting the note should expire (NIP-40), in seconds
+    pub expires_in: Option<u64>,
+    /// This draft was left over from last time
+    pub restored: bool,
+    /// Edited since the drafts were last saved
+    pub dirty: bool,
+    /// Attachments on their way to the author's media servers
+    pub uploads: Vec<Upload>,
+    pub upload_error: Option<String>,
+    /// Where the note is being posted from
+    pub location: Option<Place>,
+    /// A place search that's underway or finished, and what was searched
+    pub location_search: Option<(String, Promise<Result<Vec<Place>, String>>)>,
+    /// A GIF search that's underway or finished
+    pub gif_search: Option<Promise<Result<Vec<Gif>, String>>>,
+    /// The signed event shown in the composer's JSON preview
+    pub preview: Option<PostPreview>,
+    /// People taken off a reply's mentions
+    pub unmentioned: HashSet<[u8; 32]>,
+    /// The relay group to post to, every relay if there isn't one
+    pub relay_group: Option<String>,
 }
 
 #[derive(Default)]
@@ -11,6 +46,31 @@ pub struct Drafts {
     replies: HashMap<[u8; 32], Draft>,
     quotes: HashMap<[u8; 32], Draft>,
     compose: Draft,
+    /// When the oldest unsaved edit happened
+    unsaved_since: Option<Instant>,
+}
+
+/// What we keep of a draft on disk
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedDraft {
+    pub buffer: String,
+    pub pow: u8,
+    pub expires_in: Option<u64>,
+    pub location: Option<Place>,
+    /// Hex pubkeys
+    pub unmentioned: Vec<String>,
+    pub relay_group: Option<String>,
+}
+
+/// The drafts worth keeping, with replies and quotes keyed by the hex id
+/// of the note they're responding to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedDrafts {
+    pub compose: Option<SavedDraft>,
+    pub replies: HashMap<String, SavedDraft>,
+    pub quotes: HashMap<String, SavedDraft>,
 }
 
 impl Drafts {
@@ -33,6 +93,65 @@ impl Drafts {
     pub fn quote_mut(&mut self, id: &[u8; 32]) -> &mut Draft {
         self.quotes.entry(*id).or_default()
     }
+
+    /// Whether it's time to autosave. Edits are batched so we write at
+    /// most once every couple of seconds while someone is typing.
+    pub fn should_save(&mut self) -> bool {
+        let mut dirty = false;
+        for draft in self.all_mut() {
+            dirty |= std::mem::take(&mut draft.dirty);
+        }
+
+        if dirty && self.unsaved_since.is_none() {
+            self.unsaved_since = Some(Instant::now());
+        }
+
+        match self.unsaved_since {
+            Some(since) if since.elapsed() >= AUTOSAVE_DELAY => {
+                self.unsaved_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn to_saved(&self) -> SavedDrafts {
+        fn keep(map: &HashMap<[u8; 32], Draft>) -> HashMap<String, SavedDraft> {
+            map.iter()
+                .filter_map(|(id, draft)| Some((hex::encode(id), draft.to_saved()?)))
+                .collect()
+        }
+
+        SavedDrafts {
+            compose: self.compose.to_saved(),
+            replies: keep(&self.replies),
+            quotes: keep(&self.quotes),
+        }
+    }
+
+    pub fn from_saved(saved: SavedDrafts) -> Self {
+        fn restore(map: HashMap<String, SavedDraft>) -> HashMap<[u8; 32], Draft> {
+            map.into_iter()
+                .filter_map(|(id, draft)| {
+                    let id: [u8; 32] = hex::decode(id).ok()?.try_into().ok()?;
+                    Some((id, Draft::from_saved(draft)))
+                })
+                .collect()
+        }
+
+        Drafts {
+            replies: restore(saved.replies),
+            quotes: restore(saved.quotes),
+            compose: saved.compose.map(Draft::from_saved).unwrap_or_default(),
+            unsaved_since: None,
+        }
+    }
+
+    fn all_mut(&mut self) -> impl Iterator<Item = &mut Draft> {
+        std::iter::once(&mut self.compose)
+            .chain(self.replies.values_mut())
+            .chain(self.quotes.values_mut())
+    }
 }
 
 impl Draft {
@@ -42,5 +161,135 @@ impl Draft {
 
     pub fn clear(&mut self) {
         self.buffer = "".to_string();
+        self.miner = None;
+        self.location = None;
+        self.location_search = None;
+        self.gif_search = None;
+        self.preview = None;
+        self.unmentioned.clear();
+        self.restored = false;
+        self.dirty = true;
+    }
+
+    pub fn is_mining(&self) -> bool {
+        self.miner.is_some()
+    }
+
+    pub fn is_uploading(&self) -> bool {
+        !self.uploads.is_empty()
+    }
+
+    /// Add the urls of finished uploads to the end of the note
+    pub fn poll_uploads(&mut self) {
+        let (done, pending) = std::mem::take(&mut self.uploads)
+            .into_iter()
+            .partition::<Vec<_>, _>(Upload::is_done);
+        self.uploads = pending;
+
+        for upload in done {
+            match upload.take() {
+                Ok(url) => {
+                    self.append(&url);
+                    self.upload_error = None;
+                }
+                Err(err) => self.upload_error = Some(err),
+            }
+        }
+    }
+
+    /// Give up on an attachment. Nothing gets added to the note even if
+    /// the file makes it to a server.
+    pub fn cancel_upload(&mut self, index: usize) {
+        if index < self.uploads.len() {
+            self.uploads.remove(index).cancel();
+        }
+    }
+
+    /// Add text to the end of the note, on its own line if there's
+    /// something there already
+    pub fn append(&mut self, text: &str) {
+        if !self.buffer.is_empty() && !self.buffer.ends_with(char::is_whitespace) {
+            self.buffer.push('\n');
+        }
+        self.buffer.push_str(text);
+        self.dirty = true;
+    }
+
+    /// Empty drafts aren't worth saving
+    fn to_saved(&self) -> Option<SavedDraft> {
+        if self.buffer.trim().is_empty() {
+            return None;
+        }
+
+        Some(SavedDraft {
+            buffer: self.buffer.clone(),
+            pow: self.pow,
+            expires_in: self.expires_in,
+            location: self.location.clone(),
+            unmentioned: self.unmentioned.iter().map(hex::encode).collect(),
+            relay_group: self.relay_group.clone(),
+        })
+    }
+
+    fn from_saved(saved: SavedDraft) -> Self {
+        Draft {
+            buffer: saved.buffer,
+            pow: saved.pow,
+            expires_in: saved.expires_in,
+            location: saved.location,
+            unmentioned: saved
+                .unmentioned
+                .iter()
+                .filter_map(|pk| hex::decode(pk).ok()?.try_into().ok())
+                .collect(),
+            relay_group: saved.relay_group,
+            restored: true,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_drafts_roundtrip() {
+        let mut drafts = Drafts::default();
+        drafts.compose_mut().buffer = "gm".to_owned();
+        drafts.compose_mut().relay_group = Some("friends".to_owned());
+        drafts.reply_mut(&[1; 32]).buffer = "nice".to_owned();
+        drafts.reply_mut(&[1; 32]).pow = 8;
+        drafts.reply_mut(&[1; 32]).unmentioned.insert([3; 32]);
+        // empty drafts are dropped
+        drafts.quote_mut(&[2; 32]);
+
+        let saved = drafts.to_saved();
+        assert!(saved.quotes.is_empty());
+
+        let mut restored = Drafts::from_saved(saved);
+        assert_eq!(restored.compose_mut().buffer, "gm");
+        assert!(restored.compose_mut().restored);
+        assert_eq!(
+            restored.compose_mut().relay_group.as_deref(),
+            Some("friends")
+        );
+
+        let reply = restored.reply_mut(&[1; 32]);
+        assert_eq!(reply.buffer, "nice");
+        assert_eq!(reply.pow, 8);
+        assert!(reply.unmentioned.contains(&[3; 32]));
+    }
+
+    #[test]
+    fn test_append() {
+        let mut draft = Draft::new();
+        draft.append("naddr1abc");
+        assert_eq!(draft.buffer, "naddr1abc");
+
+        draft.buffer.push_str(" is on");
+        draft.append("https://example.com/a.png");
+        assert_eq!(draft.buffer, "naddr1abc is on\nhttps://example.com/a.png");
+        assert!(draft.dirty);
     }
 }
@@ -0,0 +1,395 @@
+//! "Export my data": every event we have from an account written out as
+//! JSON lines, with an HTML digest for people and a signed manifest that
+//! pins the archive's hash. An archive can be read back in on another
+//! machine and optionally sent to relays again.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use enostr::{ClientMessage, FullKeypair, Pubkey, RelayPool};
+use nostrdb::{Filter, Ndb, Note, NoteBuilder, Transaction};
+use notedeck::{
+    backup::Backup, calendar::RSVP_KIND, toast, tr, Accounts, Capability, PermissionError,
+    Permissions,
+};
+use poll_promise::Promise;
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+pub const EVENTS_FILE: &str = "events.jsonl";
+pub const DIGEST_FILE: &str = "index.html";
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The manifest is NIP-78 app data, signed but never published
+const MANIFEST_KIND: u32 = 30078;
+const MANIFEST_D: &str = "notedeck-export";
+/// More events than any one account should have
+const EXPORT_LIMIT: i32 = 1_000_000;
+
+#[derive(Debug)]
+pub struct Exported {
+    pub dir: PathBuf,
+    pub count: usize,
+}
+
+#[derive(Debug)]
+pub struct Imported {
+    pub count: usize,
+    /// `None` if the archive has no manifest to check against
+    pub verified: Option<bool>,
+    /// Events to send to relays, if asked to
+    pub rebroadcast: Vec<String>,
+}
+
+/// Another client's backup, and what it would add to the account's
+/// lists, waiting for the user to apply it
+#[derive(Debug)]
+pub struct BackupPreview {
+    pub path: PathBuf,
+    pub new: Backup,
+}
+
+/// Exports and imports in flight
+#[derive(Default)]
+pub struct DataExport {
+    exporting: Option<Promise<Result<Exported, String>>>,
+    importing: Option<Promise<Result<Imported, String>>>,
+    /// Where the last export went, offered as the place to import from
+    pub last_export: Option<PathBuf>,
+    pub backup: Option<BackupPreview>,
+    /// Imported events waiting for the user to let us send them
+    rebroadcast: Vec<String>,
+}
+
+impl DataExport {
+    pub fn is_busy(&self) -> bool {
+        self.exporting.is_some() || self.importing.is_some()
+    }
+
+    /// Gather everything `keypair` wrote into a new folder in `dest`.
+    /// Watch-only accounts get an archive without a signed manifest.
+    pub fn export(&mut self, ndb: &Ndb, pubkey: Pubkey, keypair: Option<FullKeypair>, dest: &Path) {
+        let ndb = ndb.clone();
+        let dir = dest.join(format!(
+            "notedeck-export-{}",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        self.exporting = Some(Promise::spawn_thread("export-data", move || {
+            export(&ndb, &pubkey, keypair.as_ref(), &dir).map(|count| Exported { dir, count })
+        }));
+    }
+
+    pub fn import(&mut self, ndb: &Ndb, dir: PathBuf, rebroadcast: bool) {
+        let ndb = ndb.clone();
+        self.importing = Some(Promise::spawn_thread("import-data", move || {
+            import(&ndb, &dir, rebroadcast)
+        }));
+    }
+
+    /// Report finished exports and imports, sending imported events on
+    /// to relays if that was asked for
+    pub fn poll(&mut self, accounts: &Accounts, pool: &mut RelayPool) {
+        if let Some(exporting) = self.exporting.take() {
+            match exporting.try_take() {
+                Ok(Ok(exported)) => {
+                    info!("exported {} events to {:?}", exported.count, exported.dir);
+                    toast::info(tr!(
+                        "toast-export-done",
+                        count = exported.count,
+                        path = exported.dir.display().to_string()
+                    ));
+                    self.last_export = Some(exported.dir);
+                }
+                Ok(Err(err)) => {
+                    error!("export failed: {err}");
+                    toast::error(tr!("toast-export-failed", error = err));
+                }
+                Err(exporting) => self.exporting = Some(exporting),
+            }
+        }
+
+        if let Some(importing) = self.importing.take() {
+            match importing.try_take() {
+                Ok(Ok(imported)) => {
+                    info!("imported {} events", imported.count);
+                    self.rebroadcast.extend(imported.rebroadcast);
+                    if imported.verified == Some(false) {
+                        toast::warning(tr!("toast-import-unverified", count = imported.count));
+                    } else {
+                        toast::info(tr!("toast-import-done", count = imported.count));
+                    }
+                }
+                Ok(Err(err)) => {
+                    error!("import failed: {err}");
+                    toast::error(tr!("toast-import-failed", error = err));
+                }
+                Err(importing) => self.importing = Some(importing),
+            }
+        }
+
+        rebroadcast(&mut self.rebroadcast, accounts.permissions(), |raw| {
+            pool.send(&ClientMessage::raw(raw))
+        });
+    }
+}
+
+/// Send imported events on to relays once we may publish. They wait while
+/// the user is asked, and are dropped if they say no.
+fn rebroadcast(pending: &mut Vec<String>, permissions: &Permissions, send: impl FnMut(String)) {
+    if pending.is_empty() {
+        return;
+    }
+
+    match permissions.check(Capability::Publish) {
+        Ok(()) => pending.drain(..).for_each(send),
+        Err(PermissionError::Asking(_)) => {}
+        Err(err) => {
+            toast::warning(err.message());
+            pending.clear();
+        }
+    }
+}
+
+/// Where exports go unless the user says otherwise
+pub fn default_dest() -> PathBuf {
+    dirs::download_dir()
+        .or_else(dirs::document_dir)
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn export(
+    ndb: &Ndb,
+    pubkey: &Pubkey,
+    keypair: Option<&FullKeypair>,
+    dir: &Path,
+) -> Result<usize, String> {
+    let txn = Transaction::new(ndb).map_err(|e| e.to_string())?;
+    let filter = Filter::new().authors([pubkey.bytes()]).build();
+    let results = ndb
+        .query(&txn, &[filter], EXPORT_LIMIT)
+        .map_err(|e| e.to_string())?;
+
+    let mut notes: Vec<Note> = results.into_iter().map(|r| r.note).collect();
+    notes.sort_by_key(|note| note.created_at());
+
+    let mut events = String::new();
+    for note in &notes {
+        events.push_str(&note.json().map_err(|e| e.to_string())?);
+        events.push('\n');
+    }
+
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    fs::write(dir.join(EVENTS_FILE), &events).map_err(|e| e.to_string())?;
+    fs::write(dir.join(DIGEST_FILE), digest(pubkey, &notes)).map_err(|e| e.to_string())?;
+
+    if let Some(keypair) = keypair {
+        let manifest = manifest(keypair, &events, notes.len())?;
+        fs::write(dir.join(MANIFEST_FILE), manifest).map_err(|e| e.to_string())?;
+    }
+
+    Ok(notes.len())
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn manifest(keypair: &FullKeypair, events: &str, count: usize) -> Result<String, String> {
+    let note = NoteBuilder::new()
+        .kind(MANIFEST_KIND)
+        .content("")
+        .start_tag()
+        .tag_str("d")
+        .tag_str(MANIFEST_D)
+        .start_tag()
+        .tag_str("x")
+        .tag_str(&sha256_hex(events.as_bytes()))
+        .start_tag()
+        .tag_str("count")
+        .tag_str(&count.to_string())
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()
+        .ok_or("could not sign the manifest")?;
+
+    note.json().map_err(|e| e.to_string())
+}
+
+/// The archive hash the manifest vouches for
+fn manifest_hash(manifest: &str) -> Option<String> {
+    let event: serde_json::Value = serde_json::from_str(manifest).ok()?;
+    if event.get("kind")?.as_u64()? != MANIFEST_KIND as u64 {
+        return None;
+    }
+
+    event
+        .get("tags")?
+        .as_array()?
+        .iter()
+        .filter_map(|tag| tag.as_array())
+        .find(|tag| tag.first().and_then(|t| t.as_str()) == Some("x"))
+        .and_then(|tag| tag.get(1)?.as_str().map(str::to_owned))
+}
+
+fn import(ndb: &Ndb, dir: &Path, rebroadcast: bool) -> Result<Imported, String> {
+    let events = fs::read_to_string(dir.join(EVENTS_FILE)).map_err(|e| e.to_string())?;
+
+    let verified = fs::read_to_string(dir.join(MANIFEST_FILE))
+        .ok()
+        .map(|manifest| manifest_hash(&manifest) == Some(sha256_hex(events.as_bytes())));
+
+    let mut imported = Imported {
+        count: 0,
+        verified,
+        rebroadcast: vec![],
+    };
+
+    // ndb checks each signature, so edited events don't get in
+    for line in events.lines().filter(|line| !line.trim().is_empty()) {
+        let raw = format!("[\"EVENT\",{line}]");
+        if let Err(err) = ndb.process_client_event(&raw) {
+            error!("could not import an event: {err}");
+            continue;
+        }
+        imported.count += 1;
+        if rebroadcast {
+            imported.rebroadcast.push(raw);
+        }
+    }
+
+    Ok(imported)
+}
+
+pub fn kind_label(kind: u32) -> String {
+    match kind {
+        0 => tr!("export-kind-profile"),
+        1 => tr!("export-kind-note"),
+        3 => tr!("export-kind-follows"),
+        6 => tr!("export-kind-repost"),
+        7 => tr!("export-kind-reaction"),
+        31922 | 31923 => tr!("export-kind-calendar-event"),
+        RSVP_KIND => tr!("export-kind-rsvp"),
+        kind => tr!("export-kind-other", kind = kind),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn tag_value<'a>(note: &'a Note, name: &str) -> Option<&'a str> {
+    note.tags()
+        .iter()
+        .filter(|tag| tag.count() >= 2)
+        .find(|tag| tag.get_unchecked(0).variant().str() == Some(name))?
+        .get_unchecked(1)
+        .variant()
+        .str()
+}
+
+/// What a person would want to read of an event
+pub fn summary(note: &Note) -> String {
+    match note.kind() {
+        0 => serde_json::from_str::<serde_json::Value>(note.content())
+            .ok()
+            .and_then(|profile| {
+                let name = profile
+                    .get("display_name")
+                    .or_else(|| profile.get("name"))?;
+                name.as_str().map(str::to_owned)
+            })
+            .unwrap_or_default(),
+        31922 | 31923 => tag_value(note, "title").unwrap_or_default().to_owned(),
+        RSVP_KIND => tag_value(note, "status").unwrap_or_default().to_owned(),
+        _ => note.content().to_owned(),
+    }
+}
+
+/// A page listing the archive, newest first
+fn digest(pubkey: &Pubkey, notes: &[Note]) -> String {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for note in notes {
+        *counts.entry(kind_label(note.kind())).or_default() += 1;
+    }
+
+    let title = escape_html(&tr!(
+        "export-digest-title",
+        npub = pubkey.to_bech().unwrap_or_else(|| pubkey.hex())
+    ));
+    let mut html = format!(
+        "<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n<ul>\n"
+    );
+    for (label, count) in &counts {
+        html.push_str(&format!("<li>{}: {count}</li>\n", escape_html(label)));
+    }
+    html.push_str("</ul>\n");
+
+    for note in notes.iter().rev() {
+        let when = chrono::DateTime::from_timestamp(note.created_at() as i64, 0)
+            .map(|at| at.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default();
+        html.push_str(&format!(
+            "<article>\n<h3>{} &middot; {when}</h3>\n<p style=\"white-space: pre-wrap\">{}</p>\n</article>\n",
+            escape_html(&kind_label(note.kind())),
+            escape_html(&summary(note)),
+        ));
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_denied_rebroadcast_sends_nothing() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = notedeck::Settings::new(&notedeck::DataPath::new(tmp.path()));
+        let mut permissions = Permissions::default();
+        permissions.declare(&mut settings, "columns", "Columns", &[Capability::Publish]);
+        permissions.enter(&settings, Some("columns"));
+
+        let mut pending = vec!["[\"EVENT\",{}]".to_owned()];
+        let mut sent = vec![];
+
+        // still waiting on an answer
+        rebroadcast(&mut pending, &permissions, |raw| sent.push(raw));
+        assert_eq!(pending.len(), 1);
+
+        permissions.answer(&mut settings, false);
+        rebroadcast(&mut pending, &permissions, |raw| sent.push(raw));
+        assert!(pending.is_empty());
+        assert!(sent.is_empty());
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let keypair = FullKeypair::generate();
+        let events = "{\"id\":\"a\"}\n{\"id\":\"b\"}\n";
+        let manifest = manifest(&keypair, events, 2).unwrap();
+
+        assert_eq!(
+            manifest_hash(&manifest),
+            Some(sha256_hex(events.as_bytes()))
+        );
+        assert_ne!(manifest_hash(&manifest), Some(sha256_hex(b"tampered")));
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Follow packs from relays, for suggesting people to follow during
+//! onboarding and in the Explore people view. We only ask relays for them
+//! once one of those is opened.
+
+use enostr::{ClientMessage, RelayPool};
+use nostrdb::{Ndb, Subscription, Transaction};
+use notedeck::FollowPack;
+use tracing::error;
+use uuid::Uuid;
+
+/// Most packs we ask relays for and keep around
+const PACK_LIMIT: i32 = 500;
+
+/// Topics people can pick during onboarding, with the hashtags that
+/// packs about them tend to use
+pub const INTERESTS: [(&str, &[&str]); 12] = [
+    ("Nostr", &["nostr", "nostrdev", "grownostr"]),
+    ("Bitcoin", &["bitcoin", "btc", "lightning"]),
+    ("Art", &["art", "artstr", "illustration"]),
+    ("Photography", &["photography", "photostr", "photo"]),
+    ("Music", &["music", "musicstr"]),
+    ("Tech", &["tech", "technology", "programming", "dev"]),
+    ("Science", &["science", "sciencestr"]),
+    ("Books", &["books", "bookstr", "reading"]),
+    ("Food", &["food", "foodstr", "cooking"]),
+    ("Gaming", &["gaming", "games"]),
+    ("News", &["news", "journalism"]),
+    ("Nature", &["nature", "outdoors", "plebchain"]),
+];
+
+/// The hashtags packs about an interest use
+pub fn interest_hashtags(interest: &str) -> &'static [&'static str] {
+    INTERESTS
+        .iter()
+        .find(|(name, _)| *name == interest)
+        .map_or(&[], |(_, hashtags)| hashtags)
+}
+
+#[derive(Default)]
+pub struct FollowPacks {
+    sub: Option<(Subscription, String)>,
+    packs: Vec<FollowPack>,
+    loaded: bool,
+}
+
+impl FollowPacks {
+    pub fn send_initial_filter(&self, pool: &mut RelayPool, relay_url: &str) {
+        if let Some((_, subid)) = &self.sub {
+            pool.send_to(
+                &ClientMessage::req(subid.clone(), vec![FollowPack::filter(PACK_LIMIT as u64)]),
+                relay_url,
+            );
+        }
+    }
+
+    /// The packs we know about, asking relays for them the first time
+    pub fn packs(&mut self, ndb: &Ndb, pool: &mut RelayPool) -> &[FollowPack] {
+        if self.sub.is_none() {
+            match ndb.subscribe(&[FollowPack::filter(PACK_LIMIT as u64)]) {
+                Ok(sub) => {
+                    let subid = Uuid::new_v4().to_string();
+                    pool.subscribe(subid.clone(), vec![FollowPack::filter(PACK_LIMIT as u64)]);
+                    self.sub = Some((sub, subid));
+                }
+                Err(err) => error!("follow packs: subscribe failed: {err}"),
+            }
+        }
+
+        let arrived = self
+            .sub
+            .as_ref()
+            .is_some_and(|(sub, _)| !ndb.poll_for_notes(*sub, 100).is_empty());
+        if arrived || !self.loaded {
+            if let Ok(txn) = Transaction::new(ndb) {
+                self.packs = FollowPack::load(ndb, &txn, PACK_LIMIT);
+                self.loaded = true;
+            }
+        }
+
+        &self.packs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interest_hashtags() {
+        assert!(interest_hashtags("Art").contains(&"artstr"));
+        assert!(interest_hashtags("Knitting").is_empty());
+        for (_, hashtags) in INTERESTS {
+            assert!(hashtags.iter().all(|tag| *tag == tag.to_lowercase()));
+        }
+    }
+}
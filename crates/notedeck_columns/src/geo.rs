@@ -0,0 +1,308 @@
+//! Locations on notes. A place is looked up by name with a geocoder and
+//! stored as a geohash `g` tag (as NIP-52 does for calendar events), with
+//! the name alongside in a `location` tag.
+
+use std::f64::consts::PI;
+use std::sync::RwLock;
+
+use nostrdb::Note;
+use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+pub const DEFAULT_GEOCODER: &str = "https://nominatim.openstreetmap.org/search";
+pub const DEFAULT_MAP_TILES: &str = "https://tile.openstreetmap.org/{z}/{x}/{y}.png";
+
+/// Zoom level of the map tile shown under a note, about a neighbourhood
+pub const TILE_ZOOM: u32 = 14;
+/// Geohash length we tag notes with, a few meters across
+const GEOHASH_PRECISION: usize = 9;
+/// How many matches to offer for a search
+const SEARCH_LIMIT: usize = 5;
+
+const BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Place {
+    pub fn geohash(&self) -> String {
+        encode_geohash(self.lat, self.lon, GEOHASH_PRECISION)
+    }
+}
+
+/// Where the geocoder and map tiles come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapServices {
+    pub geocoder: String,
+    /// Url template with `{z}`, `{x}` and `{y}` placeholders
+    pub tiles: String,
+}
+
+impl Default for MapServices {
+    fn default() -> Self {
+        MapServices {
+            geocoder: DEFAULT_GEOCODER.to_owned(),
+            tiles: DEFAULT_MAP_TILES.to_owned(),
+        }
+    }
+}
+
+static SERVICES: RwLock<Option<MapServices>> = RwLock::new(None);
+
+pub fn services() -> MapServices {
+    SERVICES
+        .read()
+        .ok()
+        .and_then(|s| s.clone())
+        .unwrap_or_default()
+}
+
+/// Follow the services picked in settings. Blank ones fall back to
+/// OpenStreetMap.
+pub fn set_services(geocoder: &str, tiles: &str) {
+    let mut new = MapServices::default();
+    if !geocoder.trim().is_empty() {
+        new.geocoder = geocoder.trim().to_owned();
+    }
+    if !tiles.trim().is_empty() {
+        new.tiles = tiles.trim().to_owned();
+    }
+
+    if let Ok(mut services) = SERVICES.write() {
+        if services.as_ref() != Some(&new) {
+            *services = Some(new);
+        }
+    }
+}
+
+pub fn encode_geohash(lat: f64, lon: f64, precision: usize) -> String {
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut hash = String::with_capacity(precision);
+    let mut even = true;
+    let (mut bits, mut ch) = (0, 0usize);
+
+    while hash.len() < precision {
+        let (range, value) = if even {
+            (&mut lon_range, lon)
+        } else {
+            (&mut lat_range, lat)
+        };
+
+        let mid = (range.0 + range.1) / 2.0;
+        ch <<= 1;
+        if value >= mid {
+            ch |= 1;
+            range.0 = mid;
+        } else {
+            range.1 = mid;
+        }
+        even = !even;
+
+        bits += 1;
+        if bits == 5 {
+            hash.push(BASE32[ch] as char);
+            bits = 0;
+            ch = 0;
+        }
+    }
+
+    hash
+}
+
+/// The middle of a geohash cell
+pub fn decode_geohash(hash: &str) -> Option<(f64, f64)> {
+    if hash.is_empty() {
+        return None;
+    }
+
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut even = true;
+
+    for c in hash.bytes() {
+        let value = BASE32.iter().position(|b| *b == c.to_ascii_lowercase())?;
+        for bit in (0..5).rev() {
+            let range: &mut (f64, f64) = if even { &mut lon_range } else { &mut lat_range };
+            let mid = (range.0 + range.1) / 2.0;
+            if value & (1 << bit) != 0 {
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+    }
+
+    Some((
+        (lat_range.0 + lat_range.1) / 2.0,
+        (lon_range.0 + lon_range.1) / 2.0,
+    ))
+}
+
+/// Where a note says it is. Notes may carry several `g` tags of
+/// different precision, we go with the most precise.
+pub fn note_location(note: &Note) -> Option<Place> {
+    let mut geohash: Option<&str> = None;
+    let mut name: Option<&str> = None;
+
+    for tag in note.tags() {
+        if tag.count() < 2 {
+            continue;
+        }
+        let Some(value) = tag.get_unchecked(1).variant().str() else {
+            continue;
+        };
+
+        match tag.get_unchecked(0).variant().str() {
+            Some("g") if value.len() > geohash.map_or(0, str::len) => geohash = Some(value),
+            Some("location") if name.is_none() => name = Some(value),
+            _ => {}
+        }
+    }
+
+    let (lat, lon) = decode_geohash(geohash?)?;
+    Some(Place {
+        name: name
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("{lat:.4}, {lon:.4}")),
+        lat,
+        lon,
+    })
+}
+
+/// The slippy map tile a point falls in, and where in the tile it is
+/// from 0 to 1 across and down
+pub fn tile_position(lat: f64, lon: f64, zoom: u32) -> ((u32, u32), (f32, f32)) {
+    let n = f64::from(1u32 << zoom);
+    let lat = lat.clamp(-85.0511, 85.0511).to_radians();
+    let x = (lon + 180.0) / 360.0 * n;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+
+    let (tx, ty) = (x.floor().min(n - 1.0), y.floor().min(n - 1.0));
+    ((tx as u32, ty as u32), ((x - tx) as f32, (y - ty) as f32))
+}
+
+pub fn tile_url(template: &str, lat: f64, lon: f64, zoom: u32) -> String {
+    let ((x, y), _) = tile_position(lat, lon, zoom);
+    template
+        .replace("{z}", &zoom.to_string())
+        .replace("{x}", &x.to_string())
+        .replace("{y}", &y.to_string())
+}
+
+/// A link that opens the place in a maps app. On Android the system hands
+/// `geo:` uris to whichever maps app is installed.
+pub fn maps_url(place: &Place) -> String {
+    if cfg!(target_os = "android") {
+        format!(
+            "geo:{lat},{lon}?q={lat},{lon}",
+            lat = place.lat,
+            lon = place.lon
+        )
+    } else {
+        format!(
+            "https://www.openstreetmap.org/?mlat={lat}&mlon={lon}#map=16/{lat}/{lon}",
+            lat = place.lat,
+            lon = place.lon
+        )
+    }
+}
+
+/// What Nominatim style geocoders return for each match
+#[derive(Debug, Deserialize)]
+struct GeocoderPlace {
+    display_name: String,
+    lat: String,
+    lon: String,
+}
+
+fn parse_places(bytes: &[u8]) -> Result<Vec<Place>, String> {
+    let places: Vec<GeocoderPlace> = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+    Ok(places
+        .into_iter()
+        .filter_map(|place| {
+            Some(Place {
+                name: place.display_name,
+                lat: place.lat.parse().ok()?,
+                lon: place.lon.parse().ok()?,
+            })
+        })
+        .collect())
+}
+
+/// Look up places matching a name
+pub fn geocode(ctx: &egui::Context, query: &str) -> Promise<Result<Vec<Place>, String>> {
+    let geocoder = services().geocoder;
+    let url = format!(
+        "{geocoder}?format=json&limit={SEARCH_LIMIT}&q={}",
+        urlencoding::encode(query)
+    );
+
+    let (sender, promise) = Promise::new();
+    let mut request = ehttp::Request::get(&url);
+    // nominatim's usage policy asks clients to say who they are
+    request
+        .headers
+        .insert("User-Agent".to_owned(), "Notedeck".to_owned());
+
+    let ctx = ctx.clone();
+    ehttp::fetch(request, move |response| {
+        let places = response.and_then(|response| {
+            if response.ok {
+                parse_places(&response.bytes)
+            } else {
+                Err(format!("{} {}", response.status, response.status_text))
+            }
+        });
+
+        if let Err(err) = &places {
+            debug!("geocoding failed: {err}");
+        }
+
+        sender.send(places);
+        ctx.request_repaint();
+    });
+
+    promise
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geohash() {
+        assert_eq!(encode_geohash(57.64911, 10.40744, 11), "u4pruydqqvj");
+
+        let (lat, lon) = decode_geohash("u4pruydqqvj").unwrap();
+        assert!((lat - 57.64911).abs() < 0.0001);
+        assert!((lon - 10.40744).abs() < 0.0001);
+
+        assert_eq!(decode_geohash(""), None);
+        assert_eq!(decode_geohash("u4a"), None);
+    }
+
+    #[test]
+    fn test_tiles() {
+        assert_eq!(tile_position(0.0, 0.0, 1), ((1, 1), (0.0, 0.0)));
+        assert_eq!(
+            tile_url(DEFAULT_MAP_TILES, 51.5074, -0.1278, 10),
+            "https://tile.openstreetmap.org/10/511/340.png"
+        );
+    }
+
+    #[test]
+    fn test_parse_places() {
+        let places = parse_places(
+            br#"[{"display_name":"Vancouver, BC, Canada","lat":"49.2608724","lon":"-123.113952","importance":0.8}]"#,
+        )
+        .unwrap();
+        assert_eq!(places.len(), 1);
+        assert_eq!(places[0].name, "Vancouver, BC, Canada");
+        assert!((places[0].lat - 49.2608724).abs() < 1e-9);
+    }
+}
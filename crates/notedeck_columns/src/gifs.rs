@@ -0,0 +1,183 @@
+//! GIF search for the composer. Works with Tenor and Giphy style search
+//! APIs, the user brings the url (and key) in settings.
+
+use std::sync::RwLock;
+
+use poll_promise::Promise;
+use serde::Deserialize;
+use tracing::debug;
+
+/// How many GIFs to show for a search
+const SEARCH_LIMIT: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gif {
+    /// Small version to show in the picker
+    pub preview: String,
+    /// What goes in the note
+    pub url: String,
+}
+
+static SEARCH_URL: RwLock<String> = RwLock::new(String::new());
+
+/// The search url template from settings, with a `{query}` placeholder.
+/// Empty when GIF search isn't set up.
+pub fn search_url() -> String {
+    SEARCH_URL.read().map(|url| url.clone()).unwrap_or_default()
+}
+
+pub fn set_search_url(url: &str) {
+    if let Ok(mut current) = SEARCH_URL.write() {
+        if current.as_str() != url.trim() {
+            *current = url.trim().to_owned();
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TenorResponse {
+    results: Vec<TenorResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TenorResult {
+    media_formats: TenorFormats,
+}
+
+#[derive(Debug, Deserialize)]
+struct TenorFormats {
+    gif: Option<TenorMedia>,
+    tinygif: Option<TenorMedia>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TenorMedia {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiphyResponse {
+    data: Vec<GiphyResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiphyResult {
+    images: GiphyImages,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiphyImages {
+    original: Option<GiphyMedia>,
+    fixed_height_small: Option<GiphyMedia>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiphyMedia {
+    url: String,
+}
+
+fn parse_gifs(bytes: &[u8]) -> Result<Vec<Gif>, String> {
+    if let Ok(tenor) = serde_json::from_slice::<TenorResponse>(bytes) {
+        return Ok(tenor
+            .results
+            .into_iter()
+            .filter_map(|result| {
+                let formats = result.media_formats;
+                let url = formats.gif?.url;
+                let preview = formats.tinygif.map_or_else(|| url.clone(), |m| m.url);
+                Some(Gif { preview, url })
+            })
+            .collect());
+    }
+
+    let giphy: GiphyResponse = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+    Ok(giphy
+        .data
+        .into_iter()
+        .filter_map(|result| {
+            let images = result.images;
+            let url = images.original?.url;
+            let preview = images
+                .fixed_height_small
+                .map_or_else(|| url.clone(), |m| m.url);
+            Some(Gif { preview, url })
+        })
+        .collect())
+}
+
+fn query_url(template: &str, query: &str) -> String {
+    let url = template.replace("{query}", &urlencoding::encode(query));
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{url}{separator}limit={SEARCH_LIMIT}")
+}
+
+/// Search for GIFs matching `query`
+pub fn search(ctx: &egui::Context, query: &str) -> Promise<Result<Vec<Gif>, String>> {
+    let (sender, promise) = Promise::new();
+
+    let template = search_url();
+    if template.is_empty() {
+        sender.send(Err("GIF search isn't set up".to_owned()));
+        return promise;
+    }
+
+    let request = ehttp::Request::get(query_url(&template, query));
+    let ctx = ctx.clone();
+    ehttp::fetch(request, move |response| {
+        let gifs = response.and_then(|response| {
+            if response.ok {
+                parse_gifs(&response.bytes)
+            } else {
+                Err(format!("{} {}", response.status, response.status_text))
+            }
+        });
+
+        if let Err(err) = &gifs {
+            debug!("gif search failed: {err}");
+        }
+
+        sender.send(gifs);
+        ctx.request_repaint();
+    });
+
+    promise
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gifs() {
+        let tenor = parse_gifs(
+            br#"{"results":[{"id":"1","media_formats":{"gif":{"url":"https://media.tenor.com/a.gif"},"tinygif":{"url":"https://media.tenor.com/a-tiny.gif"}}}],"next":"2"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tenor,
+            vec![Gif {
+                preview: "https://media.tenor.com/a-tiny.gif".to_owned(),
+                url: "https://media.tenor.com/a.gif".to_owned(),
+            }]
+        );
+
+        let giphy = parse_gifs(
+            br#"{"data":[{"images":{"original":{"url":"https://media.giphy.com/b.gif"}}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(giphy[0].preview, "https://media.giphy.com/b.gif");
+
+        assert!(parse_gifs(b"{}").is_err());
+    }
+
+    #[test]
+    fn test_query_url() {
+        assert_eq!(
+            query_url(
+                "https://tenor.googleapis.com/v2/search?key=k&q={query}",
+                "hi there"
+            ),
+            "https://tenor.googleapis.com/v2/search?key=k&q=hi%20there&limit=24"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use enostr::{Filter, Pubkey, RelayPool};
+use nostrdb::Ndb;
+use notedeck::{filter::default_limit, FilterState, Interests, NoteCache};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    column::Columns,
+    subscriptions::Subscriptions,
+    timeline::{self, kind::ListKind, TimelineId, TimelineKind},
+};
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HashtagSettings {
+    /// Also show notes with followed hashtags in home timelines
+    pub merge_into_home: bool,
+}
+
+impl HashtagSettings {
+    /// The hashtags home timelines should include, sorted so they can be
+    /// compared
+    pub fn home_hashtags(&self, interests: Option<&Interests>) -> Vec<String> {
+        if !self.merge_into_home {
+            return vec![];
+        }
+
+        let mut hashtags: Vec<String> = interests
+            .into_iter()
+            .flat_map(|interests| interests.hashtags())
+            .map(str::to_lowercase)
+            .collect();
+        hashtags.sort();
+        hashtags.dedup();
+        hashtags
+    }
+}
+
+/// Keeps home timelines' filters in step with the followed hashtags
+#[derive(Debug, Default)]
+pub struct HomeHashtags {
+    /// What's merged into each home timeline right now
+    merged: HashMap<TimelineId, Vec<String>>,
+}
+
+impl HomeHashtags {
+    /// Rebuild the filter of any home timeline whose merged hashtags are
+    /// out of date. Timelines still waiting on their contact list are
+    /// left for later.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        hashtags: &[String],
+        columns: &mut Columns,
+        ndb: &mut Ndb,
+        subs: &mut Subscriptions,
+        pool: &mut RelayPool,
+        note_cache: &mut NoteCache,
+        our_pk: Option<&Pubkey>,
+    ) {
+        for timeline in columns.timelines_mut() {
+            if !matches!(timeline.kind, TimelineKind::List(ListKind::Contact(_))) {
+                continue;
+            }
+
+            let merged = self.merged.get(&timeline.id).map_or(&[][..], Vec::as_slice);
+            if merged == hashtags || timeline.filter.get_any_ready().is_none() {
+                continue;
+            }
+
+            // start over from the contact list so we don't stack filters
+            let fresh = timeline
+                .kind
+                .clone()
+                .into_timeline(ndb, our_pk.map(|pk| pk.bytes()));
+            let Some(FilterState::Ready(base)) = fresh.map(|fresh| fresh.filter.initial_state)
+            else {
+                continue;
+            };
+
+            info!(
+                "merging {} followed hashtags into {}",
+                hashtags.len(),
+                timeline.id
+            );
+            let filters = timeline.kinds.apply(with_hashtags(base, hashtags));
+            timeline::replace_timeline_filter(
+                timeline, filters, ndb, subs, pool, note_cache, our_pk,
+            );
+            self.merged.insert(timeline.id, hashtags.to_vec());
+        }
+    }
+}
+
+fn with_hashtags(mut filters: Vec<Filter>, hashtags: &[String]) -> Vec<Filter> {
+    if !hashtags.is_empty() {
+        filters.push(
+            Filter::new()
+                .kinds([1])
+                .tags(hashtags.iter().cloned(), 't')
+                .limit(default_limit())
+                .build(),
+        );
+    }
+    filters
+}
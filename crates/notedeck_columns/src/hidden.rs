@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+
+use enostr::{NoteId, Pubkey};
+use nostrdb::Note;
+use serde::{Deserialize, Serialize};
+
+/// Notes and people the user never wants to see in their columns again.
+/// Kept on this device, unlike the account's mute list.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hidden {
+    pub notes: HashSet<NoteId>,
+    pub authors: HashSet<Pubkey>,
+    /// Also add hidden people to the account's NIP-51 mute list
+    pub sync_mute_list: bool,
+    /// Our own notes that no relay took, out of the columns until they're
+    /// sent again or discarded
+    pub unsent: HashSet<NoteId>,
+}
+
+impl Hidden {
+    pub fn is_hidden(&self, note: &Note) -> bool {
+        let id = NoteId::new(*note.id());
+        self.notes.contains(&id)
+            || self.unsent.contains(&id)
+            || self.authors.contains(note.pubkey())
+    }
+
+    /// Returns false if it was already hidden
+    pub fn hide_note(&mut self, id: NoteId) -> bool {
+        self.notes.insert(id)
+    }
+
+    /// Returns false if they were already hidden
+    pub fn hide_author(&mut self, pubkey: Pubkey) -> bool {
+        self.authors.insert(pubkey)
+    }
+
+    /// Hidden notes, in a stable order for listing
+    pub fn sorted_notes(&self) -> Vec<NoteId> {
+        let mut notes: Vec<NoteId> = self.notes.iter().copied().collect();
+        notes.sort_by_key(|id| *id.bytes());
+        notes
+    }
+
+    /// Returns false if it was already rolled back
+    pub fn roll_back(&mut self, id: NoteId) -> bool {
+        self.unsent.insert(id)
+    }
+
+    pub fn sorted_unsent(&self) -> Vec<NoteId> {
+        let mut notes: Vec<NoteId> = self.unsent.iter().copied().collect();
+        notes.sort_by_key(|id| *id.bytes());
+        notes
+    }
+
+    pub fn sorted_authors(&self) -> Vec<Pubkey> {
+        let mut authors: Vec<Pubkey> = self.authors.iter().copied().collect();
+        authors.sort();
+        authors
+    }
+}
+
+/// Clients whose notes the user doesn't want to see, going by the note's
+/// `client` tag. Mostly for bots that say what they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HiddenClients(Vec<String>);
+
+impl HiddenClients {
+    /// From the comma separated names in settings. Case doesn't matter.
+    pub fn from_setting(setting: &str) -> Self {
+        Self(
+            setting
+                .split(',')
+                .map(|name| name.trim().to_lowercase())
+                .filter(|name| !name.is_empty())
+                .collect(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn hides_client(&self, client: &str) -> bool {
+        let client = client.trim().to_lowercase();
+        self.0.iter().any(|name| *name == client)
+    }
+
+    pub fn hides(&self, note: &Note) -> bool {
+        !self.is_empty() && notedeck::note::note_client(note).is_some_and(|c| self.hides_client(c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hidden_round_trip() {
+        let mut hidden = Hidden::default();
+        assert!(hidden.hide_note(NoteId::new([1; 32])));
+        assert!(!hidden.hide_note(NoteId::new([1; 32])));
+        assert!(hidden.hide_author(Pubkey::new([2; 32])));
+
+        let json = serde_json::to_string(&hidden).unwrap();
+        let loaded: Hidden = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.sorted_notes(), vec![NoteId::new([1; 32])]);
+        assert_eq!(loaded.sorted_authors(), vec![Pubkey::new([2; 32])]);
+        assert!(!loaded.sync_mute_list);
+    }
+
+    #[test]
+    fn test_hidden_clients() {
+        let clients = HiddenClients::from_setting(" SpamBot, ,feed relay ");
+        assert!(clients.hides_client("spambot"));
+        assert!(clients.hides_client("Feed Relay"));
+        assert!(!clients.hides_client("Damus Notedeck"));
+        assert!(!clients.hides_client("spam"));
+        assert!(HiddenClients::from_setting("").is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+//! Highlights nostrdb has seen, counted by what they're of, so notes can
+//! say how many they have. Relays are only asked for a note's highlights
+//! once someone opens the list of them.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use enostr::RelayPool;
+use nostrdb::{Filter, Ndb, Note, NoteKey, Subscription, Transaction};
+use notedeck::highlight::{self, HighlightSource, HIGHLIGHT_KIND};
+use tracing::error;
+use uuid::Uuid;
+
+/// How many highlights to count from what's already stored
+const STORED_LIMIT: i32 = 10_000;
+
+/// How many notes' highlights to keep a relay subscription open for
+const MAX_FETCHES: usize = 8;
+
+#[derive(Default)]
+pub struct Highlights {
+    sub: Option<Subscription>,
+    seen: HashSet<NoteKey>,
+    counts: Arc<HashMap<HighlightSource, usize>>,
+    /// Relay subscriptions for the notes whose highlights were looked at
+    /// most recently, oldest first
+    fetched: VecDeque<(Vec<HighlightSource>, String)>,
+}
+
+fn counts_id() -> egui::Id {
+    egui::Id::new("highlight-counts")
+}
+
+/// How many highlights of a note we've seen
+pub fn count(ctx: &egui::Context, note: &Note) -> usize {
+    let Some(counts) =
+        ctx.data(|d| d.get_temp::<Arc<HashMap<HighlightSource, usize>>>(counts_id()))
+    else {
+        return 0;
+    };
+    HighlightSource::of(note)
+        .iter()
+        .filter_map(|source| counts.get(source))
+        .sum()
+}
+
+fn local_filter() -> Filter {
+    Filter::new().kinds([HIGHLIGHT_KIND as u64]).build()
+}
+
+impl Highlights {
+    /// Count highlights nostrdb has taken in since last time
+    pub fn update(&mut self, ctx: &egui::Context, ndb: &Ndb) {
+        let keys = match self.sub {
+            Some(sub) => ndb.poll_for_notes(sub, 100),
+            None => match ndb.subscribe(&[local_filter()]) {
+                Ok(sub) => {
+                    self.sub = Some(sub);
+                    stored(ndb)
+                }
+                Err(err) => {
+                    error!("highlights: subscribe failed: {err}");
+                    return;
+                }
+            },
+        };
+
+        let keys: Vec<NoteKey> = keys
+            .into_iter()
+            .filter(|key| self.seen.insert(*key))
+            .collect();
+        if !keys.is_empty() {
+            let txn = Transaction::new(ndb).expect("txn");
+            let counts = Arc::make_mut(&mut self.counts);
+            for key in keys {
+                let Ok(note) = ndb.get_note_by_key(&txn, key) else {
+                    continue;
+                };
+                if let Some(source) = highlight::source(&note) {
+                    *counts.entry(source).or_default() += 1;
+                }
+            }
+        }
+
+        ctx.data_mut(|d| d.insert_temp(counts_id(), self.counts.clone()));
+    }
+
+    /// Ask relays for the highlights of a note. Only the last few notes
+    /// keep their subscriptions.
+    pub fn fetch(&mut self, pool: &mut RelayPool, note: &Note) {
+        let sources = HighlightSource::of(note);
+        if self.fetched.iter().any(|(fetched, _)| *fetched == sources) {
+            return;
+        }
+
+        if self.fetched.len() >= MAX_FETCHES {
+            if let Some((_, subid)) = self.fetched.pop_front() {
+                pool.unsubscribe(subid);
+            }
+        }
+
+        let subid = Uuid::new_v4().to_string();
+        let filters = sources.iter().map(|source| source.filter(500)).collect();
+        pool.subscribe(subid.clone(), filters);
+        self.fetched.push_back((sources, subid));
+    }
+}
+
+fn stored(ndb: &Ndb) -> Vec<NoteKey> {
+    let Ok(txn) = Transaction::new(ndb) else {
+        return vec![];
+    };
+    match ndb.query(&txn, &[local_filter()], STORED_LIMIT) {
+        Ok(results) => results.into_iter().map(|qr| qr.note_key).collect(),
+        Err(err) => {
+            error!("highlights: query failed: {err}");
+            vec![]
+        }
+    }
+}
@@ -1,10 +1,15 @@
-use egui::{pos2, Color32, ColorImage, Rect, Sense, SizeHint, TextureHandle};
+use egui::load::{SizedTexture, TexturePoll};
+use egui::{pos2, Color32, ColorImage, Rect, Sense, SizeHint, TextureHandle, TextureOptions, Vec2};
 use image::imageops::FilterType;
+use notedeck::blocklist::Blocklist;
 use notedeck::ImageCache;
 use notedeck::Result;
 use poll_promise::Promise;
 use std::path;
+use std::sync::RwLock;
+use std::time::Duration;
 use tokio::fs;
+use tracing::{error, info};
 
 //pub type ImageCacheKey = String;
 //pub type ImageCacheValue = Promise<Result<TextureHandle>>;
@@ -207,12 +212,40 @@ pub enum ImageType {
     Content(u32, u32),
 }
 
+static BLOCKLIST: RwLock<Option<Blocklist>> = RwLock::new(None);
+
+/// The account's blocklist, from settings. Media from blocked hosts is
+/// never fetched.
+pub fn set_blocklist(blocklist: Blocklist) {
+    if let Ok(mut current) = BLOCKLIST.write() {
+        if current.as_ref() != Some(&blocklist) {
+            *current = Some(blocklist);
+        }
+    }
+}
+
+pub fn is_blocked(url: &str) -> bool {
+    BLOCKLIST
+        .read()
+        .is_ok_and(|blocklist| blocklist.as_ref().is_some_and(|b| b.blocks_media(url)))
+}
+
+fn blocked(url: &str) -> Promise<Result<TextureHandle>> {
+    Promise::from_ready(Err(notedeck::Error::Generic(format!(
+        "{url} is from a blocked host"
+    ))))
+}
+
 pub fn fetch_img(
     img_cache: &ImageCache,
     ctx: &egui::Context,
     url: &str,
     imgtyp: ImageType,
 ) -> Promise<Result<TextureHandle>> {
+    if is_blocked(url) {
+        return blocked(url);
+    }
+
     let key = ImageCache::key(url);
     let path = img_cache.cache_dir.join(key);
 
@@ -255,3 +288,179 @@ fn fetch_img_from_net(
 
     promise
 }
+
+/// Prefetched images are kept at this size, enough for a wide column
+const PREFETCH_SIZE: u32 = 1024;
+
+/// Download an image into the disk cache so it's there when we're offline.
+/// Blocks, so call it off the UI thread. Returns whether it downloaded
+/// anything, which it doesn't for images already cached or blocked.
+pub fn prefetch_img(cache_dir: &path::Path, url: &str) -> Result<bool> {
+    if is_blocked(url) || cache_dir.join(ImageCache::key(url)).exists() {
+        return Ok(false);
+    }
+
+    let img = ehttp::fetch_blocking(&ehttp::Request::get(url))
+        .map_err(notedeck::Error::Generic)
+        .and_then(|resp| {
+            parse_img_response(resp, ImageType::Content(PREFETCH_SIZE, PREFETCH_SIZE))
+        })?;
+    ImageCache::write(cache_dir, url, img)?;
+    Ok(true)
+}
+
+/// Fetch an image that's gone missing from its url from the first mirror
+/// that has it. It's cached under the original url, so it's found on disk
+/// next time.
+pub fn fetch_img_from_mirrors(
+    img_cache: &ImageCache,
+    ctx: &egui::Context,
+    url: &str,
+    mirrors: Vec<String>,
+    imgtyp: ImageType,
+) -> Promise<Result<TextureHandle>> {
+    let mirrors: Vec<String> = mirrors
+        .into_iter()
+        .filter(|mirror| !is_blocked(mirror))
+        .collect();
+    let ctx = ctx.clone();
+    let url = url.to_owned();
+    let cache_path = img_cache.cache_dir.clone();
+
+    Promise::spawn_thread("fetch-img-mirrors", move || {
+        let mut result = Err(notedeck::Error::Generic("no mirrors".to_owned()));
+        for mirror in mirrors {
+            result = ehttp::fetch_blocking(&ehttp::Request::get(&mirror))
+                .map_err(notedeck::Error::Generic)
+                .and_then(|resp| parse_img_response(resp, imgtyp));
+            if result.is_ok() {
+                info!("found {url} at {mirror}");
+                break;
+            }
+        }
+
+        let handle = result.map(|img| {
+            let texture_handle = ctx.load_texture(&url, img.clone(), Default::default());
+            if let Err(err) = ImageCache::write(&cache_path, &url, img) {
+                error!("failed to cache {url}: {err}");
+            }
+            texture_handle
+        });
+
+        ctx.request_repaint();
+        handle
+    })
+}
+
+/// Whether GIFs play, from settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnimationMode {
+    #[default]
+    Always,
+    /// Only while the pointer is over them
+    OnHover,
+    /// Never, they show their first frame
+    Static,
+}
+
+impl AnimationMode {
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "hover" => AnimationMode::OnHover,
+            "static" => AnimationMode::Static,
+            _ => AnimationMode::Always,
+        }
+    }
+
+    pub fn animates(&self, hovered: bool) -> bool {
+        match self {
+            AnimationMode::Always => true,
+            AnimationMode::OnHover => hovered,
+            AnimationMode::Static => false,
+        }
+    }
+}
+
+static ANIMATION_MODE: RwLock<AnimationMode> = RwLock::new(AnimationMode::Always);
+
+/// How long a GIF frame stays up before we draw again
+const GIF_FRAME_TIME: Duration = Duration::from_millis(50);
+
+pub fn animation_mode() -> AnimationMode {
+    ANIMATION_MODE.read().map(|mode| *mode).unwrap_or_default()
+}
+
+pub fn set_animation_mode(mode: AnimationMode) {
+    if let Ok(mut current) = ANIMATION_MODE.write() {
+        if *current != mode {
+            *current = mode;
+        }
+    }
+}
+
+pub fn is_gif(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.to_ascii_lowercase().ends_with(".gif")
+}
+
+/// Whether the image drawn under `hover_id` was hovered last frame
+fn was_hovered(ctx: &egui::Context, hover_id: egui::Id) -> bool {
+    ctx.data(|d| d.get_temp::<bool>(hover_id)).unwrap_or(false)
+}
+
+/// Remember whether an image is hovered, so the next frame knows whether
+/// to play it
+pub fn set_hovered(ctx: &egui::Context, hover_id: egui::Id, hovered: bool) {
+    if was_hovered(ctx, hover_id) != hovered {
+        ctx.data_mut(|d| d.insert_temp(hover_id, hovered));
+    }
+}
+
+/// Whether `url` is a GIF that should be playing right now
+pub fn should_animate(ctx: &egui::Context, url: &str, hover_id: egui::Id) -> bool {
+    is_gif(url) && animation_mode().animates(was_hovered(ctx, hover_id))
+}
+
+/// Load an image with egui's loaders. GIFs play or stay on their first
+/// frame depending on the animation setting.
+pub fn load_texture(
+    ui: &egui::Ui,
+    url: &str,
+    size: Vec2,
+    hover_id: egui::Id,
+) -> Option<SizedTexture> {
+    let poll = if is_gif(url) && !should_animate(ui.ctx(), url, hover_id) {
+        // the gif loader serves single frames as `url#frame`
+        ui.ctx().try_load_texture(
+            &format!("{url}#0"),
+            TextureOptions::default(),
+            SizeHint::default(),
+        )
+    } else {
+        if is_gif(url) {
+            ui.ctx().request_repaint_after(GIF_FRAME_TIME);
+        }
+        egui::Image::new(url).load_for_size(ui.ctx(), size)
+    };
+
+    match poll {
+        Ok(TexturePoll::Ready { texture }) => Some(texture),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_animation_mode() {
+        assert!(is_gif("https://example.com/cat.GIF?width=200"));
+        assert!(!is_gif("https://example.com/gif/cat.png"));
+
+        assert!(AnimationMode::from_setting("always").animates(false));
+        assert!(!AnimationMode::from_setting("hover").animates(false));
+        assert!(AnimationMode::from_setting("hover").animates(true));
+        assert!(!AnimationMode::from_setting("static").animates(true));
+    }
+}
@@ -3,7 +3,7 @@ use std::str::FromStr;
 
 use crate::Error;
 use ehttp::{Request, Response};
-use enostr::{Keypair, Pubkey, SecretKey};
+use enostr::{FullKeypair, Keypair, Pubkey, SecretKey};
 use poll_promise::Promise;
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +11,9 @@ use serde::{Deserialize, Serialize};
 pub enum AcquireKeyError {
     InvalidKey,
     Nip05Failed(String),
+    /// bunker:// and nostrconnect:// logins need a NIP-46 remote signer,
+    /// and everything that signs still expects the key on this device
+    BunkerUnsupported,
 }
 
 impl std::fmt::Display for AcquireKeyError {
@@ -20,6 +23,10 @@ impl std::fmt::Display for AcquireKeyError {
             AcquireKeyError::Nip05Failed(e) => {
                 write!(f, "Failed to get pubkey from Nip05 address: {e}")
             }
+            AcquireKeyError::BunkerUnsupported => write!(
+                f,
+                "Logging in with a bunker isn't supported yet. Use your nsec or recovery phrase."
+            ),
         }
     }
 }
@@ -96,6 +103,15 @@ fn retrieving_nip05_pubkey(key: &str) -> bool {
     key.contains('@')
 }
 
+fn is_bunker_uri(key: &str) -> bool {
+    key.starts_with("bunker://") || key.starts_with("nostrconnect://")
+}
+
+/// BIP-39 phrases are 12 to 24 words
+fn is_mnemonic(key: &str) -> bool {
+    (12..=24).contains(&key.split_whitespace().count())
+}
+
 fn nip05_promise_wrapper(id: &str) -> Promise<Result<Keypair, AcquireKeyError>> {
     let (sender, promise) = Promise::new();
     let original_promise = get_nip05_pubkey(id);
@@ -119,6 +135,7 @@ fn nip05_promise_wrapper(id: &str) -> Promise<Result<Keypair, AcquireKeyError>>
 /// - Public hex key: "02a1..."
 /// - Private hex key: "5dab..."
 /// - NIP-05 address: "example@nostr.com"
+/// - NIP-06 recovery phrase: "leader monkey parrot ..."
 ///
 pub fn perform_key_retrieval(key: &str) -> Promise<Result<Keypair, AcquireKeyError>> {
     let tmp_key: &str = if let Some(stripped) = key.strip_prefix('@') {
@@ -127,7 +144,15 @@ pub fn perform_key_retrieval(key: &str) -> Promise<Result<Keypair, AcquireKeyErr
         key
     };
 
-    if retrieving_nip05_pubkey(tmp_key) {
+    if is_bunker_uri(tmp_key) {
+        Promise::from_ready(Err(AcquireKeyError::BunkerUnsupported))
+    } else if is_mnemonic(tmp_key) {
+        Promise::from_ready(
+            FullKeypair::from_mnemonic(&tmp_key.split_whitespace().collect::<Vec<_>>().join(" "))
+                .map(FullKeypair::to_keypair)
+                .map_err(|_| AcquireKeyError::InvalidKey),
+        )
+    } else if retrieving_nip05_pubkey(tmp_key) {
         nip05_promise_wrapper(tmp_key)
     } else {
         let res = if let Ok(pubkey) = Pubkey::try_from_bech32_string(tmp_key, true) {
@@ -189,6 +214,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mnemonic() {
+        let phrase =
+            "leader monkey parrot ring guide accident before fence cannon height naive bean";
+        let expected = FullKeypair::from_mnemonic(phrase).unwrap().to_keypair();
+        let login_key_result = perform_key_retrieval(&format!("  {phrase} "));
+
+        promise_assert!(assert_eq, Ok(expected), &login_key_result);
+    }
+
+    #[test]
+    fn test_bunker_unsupported() {
+        let login_key_result = perform_key_retrieval(
+            "bunker://79be667ef9dcbbac55a06295ce870b07?relay=wss://relay.example",
+        );
+
+        promise_assert!(
+            assert_eq,
+            Err(AcquireKeyError::BunkerUnsupported),
+            &login_key_result
+        );
+    }
+
     #[test]
     fn test_hex_privkey() {
         let privkey_str = "41dcb8dfee907b53abc627c711bff8c7126fac99b5c7dc9b303fc1b08557cce0";
@@ -9,30 +9,60 @@ mod actionbar;
 pub mod app_creation;
 mod app_style;
 mod args;
+mod blossom;
 mod colors;
 mod column;
+mod deck_share;
 mod deck_state;
 mod decks;
+mod deep_link;
+mod deletions;
 mod draft;
+mod export;
+mod follow_packs;
 mod frame_history;
+mod geo;
+mod gifs;
+mod hashtags;
+mod hidden;
+mod highlights;
 mod images;
 mod key_parsing;
 pub mod login_manager;
+mod maintenance;
+mod media;
+mod moderation;
 mod multi_subscriber;
 mod nav;
+mod nip05;
+pub mod notification_rules;
+pub mod notifications;
+mod onboarding;
 mod post;
+mod prefetch;
 mod profile;
 mod profile_state;
+mod publish;
+pub mod qr_scan;
+mod relay_groups;
+mod relay_info;
 pub mod relay_pool_manager;
+mod reposts;
 mod route;
+mod selection;
+mod settings;
+mod settings_sync;
 mod subscriptions;
 mod support;
 mod test_data;
 mod thread;
 mod timeline;
+mod trending;
 pub mod ui;
 mod unknowns;
+mod verify;
 mod view_state;
+mod wallet;
 
 #[cfg(test)]
 #[macro_use]
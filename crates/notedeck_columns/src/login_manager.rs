@@ -126,6 +126,9 @@ fn show_error(ui: &mut egui::Ui, err: &AcquireKeyError) {
             AcquireKeyError::Nip05Failed(e) => {
                 egui::Label::new(egui::RichText::new(e).color(ui.visuals().error_fg_color))
             }
+            AcquireKeyError::BunkerUnsupported => egui::Label::new(
+                egui::RichText::new(err.to_string()).color(ui.visuals().error_fg_color),
+            ),
         };
         ui.add(error_label.truncate());
     });
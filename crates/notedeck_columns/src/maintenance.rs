@@ -0,0 +1,142 @@
+//! Counting what the database holds and compacting it, on a background
+//! thread so a big database doesn't freeze the columns. One task runs at
+//! a time, and its progress is shown while it goes.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use nostrdb::Ndb;
+use notedeck::{
+    maintenance::{self, Compacted, Progress, Retention, StorageStats},
+    toast, tr,
+};
+use poll_promise::Promise;
+use tracing::{error, info};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Task {
+    Stats,
+    Compact,
+}
+
+enum Finished {
+    Stats(StorageStats),
+    Compacted(Compacted),
+}
+
+struct Running {
+    task: Task,
+    progress: Arc<Progress>,
+    promise: Promise<Result<Finished, String>>,
+}
+
+#[derive(Default)]
+pub struct Maintenance {
+    /// From the last time they were counted
+    pub stats: Option<StorageStats>,
+    /// A compaction finished and is waiting for a restart
+    pub compacted: bool,
+    running: Option<Running>,
+}
+
+impl Maintenance {
+    /// What's running and how many notes it's been through
+    pub fn running(&self) -> Option<(Task, usize)> {
+        self.running
+            .as_ref()
+            .map(|running| (running.task, running.progress.scanned()))
+    }
+
+    pub fn count(&mut self, ndb: &Ndb, db_dir: PathBuf) {
+        self.start(Task::Stats, ndb, move |ndb, progress| {
+            maintenance::stats(ndb, &db_dir, progress).map(Finished::Stats)
+        });
+    }
+
+    /// Copy everything `retention` keeps into a new database. `keep` are
+    /// the people whose notes are kept no matter what.
+    pub fn compact(
+        &mut self,
+        ndb: &Ndb,
+        db_dir: PathBuf,
+        retention: Retention,
+        keep: HashSet<[u8; 32]>,
+    ) {
+        self.start(Task::Compact, ndb, move |ndb, progress| {
+            maintenance::compact(ndb, &db_dir, &retention, &keep, progress).map(Finished::Compacted)
+        });
+    }
+
+    fn start(
+        &mut self,
+        task: Task,
+        ndb: &Ndb,
+        run: impl FnOnce(&Ndb, &Progress) -> notedeck::Result<Finished> + Send + 'static,
+    ) {
+        if self.running.is_some() {
+            return;
+        }
+
+        let ndb = ndb.clone();
+        let progress = Arc::new(Progress::default());
+        let thread_progress = progress.clone();
+        self.running = Some(Running {
+            task,
+            progress,
+            promise: Promise::spawn_thread("storage-maintenance", move || {
+                run(&ndb, &thread_progress).map_err(|err| err.to_string())
+            }),
+        });
+    }
+
+    pub fn cancel(&self) {
+        if let Some(running) = &self.running {
+            running.progress.cancel();
+        }
+    }
+
+    /// Pick up a finished task, and keep the progress moving while one
+    /// runs
+    pub fn poll(&mut self, ctx: &egui::Context) {
+        let Some(running) = self.running.take() else {
+            return;
+        };
+
+        let Running {
+            task,
+            progress,
+            promise,
+        } = running;
+        match promise.try_take() {
+            Ok(Ok(Finished::Stats(stats))) => {
+                info!("{} notes in {} bytes", stats.notes, stats.bytes);
+                self.stats = Some(stats);
+            }
+            Ok(Ok(Finished::Compacted(compacted))) => {
+                self.compacted = true;
+                toast::info(tr!(
+                    "toast-compacted",
+                    kept = compacted.kept,
+                    dropped = compacted.dropped
+                ));
+            }
+            Ok(Err(err)) if progress.is_cancelled() => {
+                info!("storage maintenance cancelled: {err}");
+            }
+            Ok(Err(err)) => {
+                error!("storage maintenance failed: {err}");
+                toast::error(tr!("toast-storage-failed", error = err));
+            }
+            Err(promise) => {
+                ctx.request_repaint_after(Duration::from_millis(200));
+                self.running = Some(Running {
+                    task,
+                    progress,
+                    promise,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use nostrdb::{Note, Tag};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Image,
+    Video,
+}
+
+/// An image or video attached to a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Media {
+    pub url: String,
+    pub kind: MediaKind,
+}
+
+impl Media {
+    pub fn image(url: impl Into<String>) -> Self {
+        Media {
+            url: url.into(),
+            kind: MediaKind::Image,
+        }
+    }
+}
+
+impl MediaKind {
+    /// Guess from the file extension, ignoring any query string
+    pub fn from_url(url: &str) -> Option<Self> {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        let ext = path.rsplit_once('.')?.1.to_ascii_lowercase();
+
+        match ext.as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "webp" => Some(MediaKind::Image),
+            "mp4" | "mov" | "webm" | "m4v" => Some(MediaKind::Video),
+            _ => None,
+        }
+    }
+
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        if mime.starts_with("image/") {
+            Some(MediaKind::Image)
+        } else if mime.starts_with("video/") {
+            Some(MediaKind::Video)
+        } else {
+            None
+        }
+    }
+}
+
+/// Media in a note: NIP-92 `imeta` tags first, since they can tell us what
+/// an extensionless url is, then media links in the content
+pub fn note_media(note: &Note) -> Vec<Media> {
+    let mut media: Vec<Media> = note.tags().iter().filter_map(|tag| imeta(&tag)).collect();
+
+    for url in content_urls(note.content()) {
+        if media.iter().any(|m| m.url == url) {
+            continue;
+        }
+
+        if let Some(kind) = MediaKind::from_url(url) {
+            media.push(Media {
+                url: url.to_owned(),
+                kind,
+            });
+        }
+    }
+
+    media
+}
+
+pub fn has_media(note: &Note) -> bool {
+    !note_media(note).is_empty()
+}
+
+fn imeta(tag: &Tag) -> Option<Media> {
+    if tag.get(0)?.variant().str()? != "imeta" {
+        return None;
+    }
+
+    let mut url = None;
+    let mut mime = None;
+    for i in 1..tag.count() {
+        let Some(field) = tag.get(i).and_then(|f| f.variant().str()) else {
+            continue;
+        };
+
+        match field.split_once(' ') {
+            Some(("url", value)) => url = Some(value.trim()),
+            Some(("m", value)) => mime = Some(value.trim()),
+            _ => {}
+        }
+    }
+
+    let url = url?;
+    let kind = mime
+        .and_then(MediaKind::from_mime)
+        .or_else(|| MediaKind::from_url(url))?;
+
+    Some(Media {
+        url: url.to_owned(),
+        kind,
+    })
+}
+
+fn content_urls(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .split_whitespace()
+        .filter(|word| word.starts_with("https://") || word.starts_with("http://"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_kind_from_url() {
+        assert_eq!(
+            MediaKind::from_url("https://example.com/cat.JPG"),
+            Some(MediaKind::Image)
+        );
+        assert_eq!(
+            MediaKind::from_url("https://example.com/cat.webp?w=400#x"),
+            Some(MediaKind::Image)
+        );
+        assert_eq!(
+            MediaKind::from_url("https://example.com/clip.mp4"),
+            Some(MediaKind::Video)
+        );
+        assert_eq!(MediaKind::from_url("https://example.com/page"), None);
+        assert_eq!(MediaKind::from_url("https://example.com/page.html"), None);
+    }
+
+    #[test]
+    fn test_content_urls() {
+        let urls: Vec<&str> =
+            content_urls("look https://a.com/x.png\nand http://b.com/y.mp4 nice").collect();
+        assert_eq!(urls, vec!["https://a.com/x.png", "http://b.com/y.mp4"]);
+    }
+}
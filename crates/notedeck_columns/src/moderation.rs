@@ -0,0 +1,424 @@
+//! Reporting notes and people (NIP-56), with optional NIP-32 labels for
+//! moderation relays, and acting on reports and labels from moderators
+//! the user trusts.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use enostr::{ClientMessage, FilledKeypair, NoteId, Pubkey, RelayPool};
+use nostrdb::{Filter, Ndb, Note, NoteBuilder, Subscription, Transaction};
+use notedeck::{tr, Accounts, Settings};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::settings;
+
+pub const REPORT_KIND: u32 = 1984;
+pub const LABEL_KIND: u32 = 1985;
+/// The NIP-32 namespace our labels go in
+pub const LABEL_NAMESPACE: &str = "social.notedeck.report";
+/// Most reports and labels to read from moderators
+const LABEL_LIMIT: i32 = 10_000;
+
+/// The report types from NIP-56
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReportType {
+    Spam,
+    Impersonation,
+    Illegal,
+    Nudity,
+    Profanity,
+    Malware,
+    Other,
+}
+
+impl ReportType {
+    pub const ALL: [ReportType; 7] = [
+        ReportType::Spam,
+        ReportType::Impersonation,
+        ReportType::Illegal,
+        ReportType::Nudity,
+        ReportType::Profanity,
+        ReportType::Malware,
+        ReportType::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportType::Spam => "spam",
+            ReportType::Impersonation => "impersonation",
+            ReportType::Illegal => "illegal",
+            ReportType::Nudity => "nudity",
+            ReportType::Profanity => "profanity",
+            ReportType::Malware => "malware",
+            ReportType::Other => "other",
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            ReportType::Spam => tr!("report-spam"),
+            ReportType::Impersonation => tr!("report-impersonation"),
+            ReportType::Illegal => tr!("report-illegal"),
+            ReportType::Nudity => tr!("report-nudity"),
+            ReportType::Profanity => tr!("report-profanity"),
+            ReportType::Malware => tr!("report-malware"),
+            ReportType::Other => tr!("report-other"),
+        }
+    }
+}
+
+/// Who, and optionally which of their notes, is being reported
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ReportTarget {
+    pub pubkey: Pubkey,
+    pub note: Option<NoteId>,
+}
+
+impl ReportTarget {
+    pub fn profile(pubkey: Pubkey) -> Self {
+        ReportTarget { pubkey, note: None }
+    }
+
+    pub fn note(note: &Note) -> Self {
+        ReportTarget {
+            pubkey: Pubkey::new(*note.pubkey()),
+            note: Some(NoteId::new(*note.id())),
+        }
+    }
+}
+
+fn report_note(
+    keypair: &FilledKeypair,
+    target: &ReportTarget,
+    kind: ReportType,
+    reason: &str,
+) -> Option<Note<'static>> {
+    let builder = NoteBuilder::new().kind(REPORT_KIND).content(reason);
+    let builder = match target.note {
+        Some(note) => builder
+            .start_tag()
+            .tag_str("e")
+            .tag_str(&note.hex())
+            .tag_str(kind.as_str())
+            .start_tag()
+            .tag_str("p")
+            .tag_str(&target.pubkey.hex()),
+        None => builder
+            .start_tag()
+            .tag_str("p")
+            .tag_str(&target.pubkey.hex())
+            .tag_str(kind.as_str()),
+    };
+
+    builder.sign(&keypair.secret_key.to_secret_bytes()).build()
+}
+
+fn label_note(
+    keypair: &FilledKeypair,
+    target: &ReportTarget,
+    kind: ReportType,
+    reason: &str,
+) -> Option<Note<'static>> {
+    let builder = NoteBuilder::new()
+        .kind(LABEL_KIND)
+        .content(reason)
+        .start_tag()
+        .tag_str("L")
+        .tag_str(LABEL_NAMESPACE)
+        .start_tag()
+        .tag_str("l")
+        .tag_str(kind.as_str())
+        .tag_str(LABEL_NAMESPACE);
+    let builder = match target.note {
+        Some(note) => builder.start_tag().tag_str("e").tag_str(&note.hex()),
+        None => builder
+            .start_tag()
+            .tag_str("p")
+            .tag_str(&target.pubkey.hex()),
+    };
+
+    builder.sign(&keypair.secret_key.to_secret_bytes()).build()
+}
+
+/// Publish a report, and a label for moderation relays if asked to
+pub fn report(
+    accounts: &Accounts,
+    ndb: &Ndb,
+    pool: &mut RelayPool,
+    keypair: &FilledKeypair,
+    target: &ReportTarget,
+    kind: ReportType,
+    reason: &str,
+    label: bool,
+) -> Result<(), String> {
+    let mut notes =
+        vec![report_note(keypair, target, kind, reason).ok_or("could not sign the report")?];
+    if label {
+        notes.push(label_note(keypair, target, kind, reason).ok_or("could not sign the label")?);
+    }
+
+    for note in notes {
+        accounts
+            .publish_note(ndb, pool, &note)
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// What to do with content moderators have labeled
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum ModerationAction {
+    #[default]
+    Off,
+    Blur,
+    Hide,
+}
+
+impl ModerationAction {
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "blur" => ModerationAction::Blur,
+            "hide" => ModerationAction::Hide,
+            _ => ModerationAction::Off,
+        }
+    }
+}
+
+/// Notes and people trusted moderators have reported or labeled, with
+/// the first label seen for each
+#[derive(Debug, Default)]
+pub struct Labels {
+    pub action: ModerationAction,
+    notes: HashMap<[u8; 32], String>,
+    authors: HashMap<[u8; 32], String>,
+}
+
+impl Labels {
+    /// Why a note should be blurred or hidden, if it should
+    pub fn label(&self, note: &Note) -> Option<&str> {
+        if self.action == ModerationAction::Off {
+            return None;
+        }
+
+        self.notes
+            .get(note.id())
+            .or_else(|| self.authors.get(note.pubkey()))
+            .map(String::as_str)
+    }
+
+    fn add(&mut self, note: &Note) {
+        let mut labeled_notes = vec![];
+        let mut labeled_authors = vec![];
+        let mut label = None;
+
+        for tag in note.tags() {
+            if tag.count() < 2 {
+                continue;
+            }
+
+            let kind = tag.get(2).and_then(|t| t.variant().str());
+            match tag.get_unchecked(0).variant().str() {
+                Some("e") => {
+                    if let Some(id) = tag.get_unchecked(1).variant().id() {
+                        labeled_notes.push((*id, kind));
+                    }
+                }
+                // in a report about a note, an untyped p is just its author
+                Some("p") if note.kind() == LABEL_KIND || kind.is_some() => {
+                    if let Some(pk) = tag.get_unchecked(1).variant().id() {
+                        labeled_authors.push((*pk, kind));
+                    }
+                }
+                Some("l") if label.is_none() => {
+                    label = tag.get_unchecked(1).variant().str();
+                }
+                _ => {}
+            }
+        }
+
+        let label_for = |kind: Option<&str>| {
+            label
+                .or(kind)
+                .filter(|label| !label.is_empty())
+                .unwrap_or("other")
+                .to_owned()
+        };
+        for (id, kind) in labeled_notes {
+            self.notes.entry(id).or_insert_with(|| label_for(kind));
+        }
+        for (pk, kind) in labeled_authors {
+            self.authors.entry(pk).or_insert_with(|| label_for(kind));
+        }
+    }
+}
+
+fn labels_id() -> egui::Id {
+    egui::Id::new("moderation-labels")
+}
+
+/// The labels note views act on
+pub fn labels(ctx: &egui::Context) -> Option<Arc<Labels>> {
+    ctx.data(|d| d.get_temp::<Arc<Labels>>(labels_id()))
+}
+
+/// Parse moderators from a setting, as npubs or hex separated by commas
+/// or whitespace
+pub fn parse_moderators(value: &str) -> Vec<Pubkey> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| Pubkey::parse(s).ok())
+        .collect()
+}
+
+/// Follows reports and labels from the moderators in the settings
+#[derive(Default)]
+pub struct Moderation {
+    moderators: Vec<Pubkey>,
+    action: ModerationAction,
+    sub: Option<(Subscription, String)>,
+    labels: Arc<Labels>,
+}
+
+impl Moderation {
+    fn filter(&self) -> Filter {
+        Filter::new()
+            .authors(self.moderators.iter().map(|pk| pk.bytes()))
+            .kinds([REPORT_KIND as u64, LABEL_KIND as u64])
+            .limit(LABEL_LIMIT as u64)
+            .build()
+    }
+
+    pub fn send_initial_filter(&self, pool: &mut RelayPool, relay_url: &str) {
+        if let Some((_, subid)) = &self.sub {
+            pool.send_to(
+                &ClientMessage::req(subid.clone(), vec![self.filter()]),
+                relay_url,
+            );
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        app_settings: &Settings,
+        ctx: &egui::Context,
+    ) {
+        let moderators =
+            parse_moderators(&app_settings.string(settings::APP, settings::MODERATORS));
+        let action = ModerationAction::from_setting(
+            &app_settings.string(settings::APP, settings::MODERATION_ACTION),
+        );
+
+        let mut changed = action != self.action;
+        self.action = action;
+
+        if moderators != self.moderators {
+            if let Some((sub, subid)) = self.sub.take() {
+                if let Err(err) = ndb.unsubscribe(sub) {
+                    error!("moderation: unsubscribe failed: {err}");
+                }
+                pool.unsubscribe(subid);
+            }
+
+            self.moderators = moderators;
+            if !self.moderators.is_empty() {
+                let subid = Uuid::new_v4().to_string();
+                match ndb.subscribe(&[self.filter()]) {
+                    Ok(sub) => {
+                        pool.subscribe(subid.clone(), vec![self.filter()]);
+                        self.sub = Some((sub, subid));
+                    }
+                    Err(err) => error!("moderation: subscribe failed: {err}"),
+                }
+            }
+            changed = true;
+        }
+
+        if let Some((sub, _)) = &self.sub {
+            if !ndb.poll_for_notes(*sub, LABEL_LIMIT as u32).is_empty() {
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.labels = Arc::new(self.load(ndb));
+        }
+        ctx.data_mut(|d| d.insert_temp(labels_id(), self.labels.clone()));
+    }
+
+    fn load(&self, ndb: &Ndb) -> Labels {
+        let mut labels = Labels {
+            action: self.action,
+            ..Default::default()
+        };
+        if self.moderators.is_empty() || self.action == ModerationAction::Off {
+            return labels;
+        }
+
+        let Ok(txn) = Transaction::new(ndb) else {
+            return labels;
+        };
+        match ndb.query(&txn, &[self.filter()], LABEL_LIMIT) {
+            Ok(results) => {
+                for result in &results {
+                    labels.add(&result.note);
+                }
+            }
+            Err(err) => error!("moderation: label query failed: {err}"),
+        }
+        labels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+
+    #[test]
+    fn test_reports_become_labels() {
+        let moderator = FullKeypair::generate();
+        let keypair = moderator.to_filled();
+        let spammer = Pubkey::new([1; 32]);
+        let note = ReportTarget {
+            pubkey: Pubkey::new([2; 32]),
+            note: Some(NoteId::new([3; 32])),
+        };
+
+        let mut labels = Labels {
+            action: ModerationAction::Blur,
+            ..Default::default()
+        };
+        labels.add(
+            &report_note(
+                &keypair,
+                &ReportTarget::profile(spammer),
+                ReportType::Spam,
+                "",
+            )
+            .unwrap(),
+        );
+        labels.add(&label_note(&keypair, &note, ReportType::Nudity, "").unwrap());
+        labels.add(&report_note(&keypair, &note, ReportType::Illegal, "").unwrap());
+
+        assert_eq!(
+            labels.authors.get(spammer.bytes()).map(String::as_str),
+            Some("spam")
+        );
+        // the first label wins, and the note's author isn't labeled
+        assert_eq!(
+            labels.notes.get(&[3; 32]).map(String::as_str),
+            Some("nudity")
+        );
+        assert!(!labels.authors.contains_key(&[2; 32]));
+
+        assert_eq!(
+            parse_moderators(&format!("{}, {}", spammer.hex(), moderator.pubkey.hex())),
+            vec![spammer, moderator.pubkey]
+        );
+    }
+}
@@ -20,6 +20,10 @@ impl MultiSubscriber {
         }
     }
 
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
     fn real_subscribe(
         ndb: &Ndb,
         pool: &mut RelayPool,
@@ -1,35 +1,75 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
 use crate::{
     accounts::render_accounts_route,
     actionbar::NoteAction,
     app::{get_active_columns, get_active_columns_mut, get_decks_mut},
+    blossom,
     column::ColumnsAction,
+    deck_share,
     deck_state::DeckState,
-    decks::{Deck, DecksAction, DecksCache},
+    decks::{Deck, Decks, DecksAction, DecksCache},
+    export::BackupPreview,
+    moderation,
+    notifications::ThreadNotify,
+    onboarding::{self, StarterColumns},
     profile::{ProfileAction, SaveProfileChanges},
     profile_state::ProfileState,
+    qr_scan,
     relay_pool_manager::RelayPoolManager,
+    reposts,
     route::Route,
+    settings,
+    settings_sync::SyncRequest,
+    storage,
     timeline::{
+        self,
         route::{render_timeline_route, TimelineRoute},
-        Timeline,
+        ContentKinds, Timeline, TimelineId,
     },
     ui::{
         self,
         add_column::render_add_column_routes,
+        bookmarks::{BookmarkAction, BookmarksView, SaveBookmarkView},
         column::NavTitle,
         configure_deck::ConfigureDeckView,
         edit_deck::{EditDeckResponse, EditDeckView},
-        note::{PostAction, PostType},
+        explore_people::{ExplorePeopleAction, ExplorePeopleView},
+        export::{ExportAction, ExportView},
+        hidden::HiddenView,
+        highlight::{HighlightAction, HighlightView},
+        highlights::HighlightsView,
+        import_deck::{ImportDeckAction, ImportDeckView},
+        my_posts::{MyPostsAction, MyPostsView},
+        note::{NoteOptions, PostAction, PostStatus, PostType},
+        note_edit::{NoteEditAction, NoteEditView},
+        notification_settings::NotificationSettingsView,
+        poster::PosterView,
         profile::EditProfileView,
+        relay::RelayAction,
+        relay_groups::RelayGroupsView,
+        report::ReportView,
+        settings::{SettingsAction, SettingsView},
+        source::SourceView,
+        storage::{StorageAction, StorageView},
+        subscriptions::{SubscriptionAction, SubscriptionsView},
         support::SupportView,
-        RelayView, View,
+        trending::TrendingView,
+        wallet::{WalletAction, WalletView},
+        RelayView,
     },
+    wallet::Wallet,
     Damus,
 };
 
-use notedeck::{AccountsAction, AppContext, RootIdError};
+use notedeck::{
+    backup::Backup, blocklist::Blocklist, toast, tr, AccountsAction, AppContext, Capability,
+    DataPathType, RootIdError,
+};
 
 use egui_nav::{Nav, NavAction, NavResponse, NavUiType};
+use enostr::{NoteId, Pubkey};
 use nostrdb::{Ndb, Transaction};
 use tracing::{error, info};
 
@@ -41,6 +81,10 @@ pub enum RenderNavAction {
     NoteAction(NoteAction),
     ProfileAction(ProfileAction),
     SwitchingAction(SwitchingAction),
+    /// Mute or watch a thread, by its root
+    ThreadNotify(NoteId, Option<ThreadNotify>),
+    /// Change which kinds of notes a timeline column shows
+    ContentKinds(TimelineId, ContentKinds),
 }
 
 pub enum SwitchingAction {
@@ -106,6 +150,36 @@ pub struct RenderNavResponse {
     response: NotedeckNavResponse,
 }
 
+fn post(app: &mut Damus, ctx: &mut AppContext<'_>, col: usize, post_action: &PostAction) {
+    // keep the draft so it can be sent once allowed
+    if let Err(err) = ctx.check(Capability::Publish) {
+        toast::warning(err.message());
+        return;
+    }
+
+    let group = app
+        .drafts
+        .get_from_post_type(post_action.post_type())
+        .relay_group
+        .clone();
+    let target = app.relay_groups.target(group.as_deref());
+
+    let txn = Transaction::new(ctx.ndb).expect("txn");
+    let status = post_action.execute(ctx.ndb, &txn, ctx.pool, &target, &mut app.drafts);
+    if let Ok(PostStatus::Published(id)) = &status {
+        app.publishes.track_to(id, ctx.pool, &target);
+    }
+
+    // stay in the composer while proof-of-work is mining so that we can
+    // show progress
+    if !matches!(status, Ok(PostStatus::Mining)) {
+        get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
+            .column_mut(col)
+            .router_mut()
+            .go_back();
+    }
+}
+
 impl RenderNavResponse {
     #[allow(private_interfaces)]
     pub fn new(column: usize, response: NotedeckNavResponse) -> Self {
@@ -141,16 +215,28 @@ impl RenderNavResponse {
                     }
 
                     app.columns_mut(ctx.accounts).delete_column(col);
+                    app.selection.columns_changed();
                     switching_occured = true;
                 }
 
                 RenderNavAction::PostAction(post_action) => {
-                    let txn = Transaction::new(ctx.ndb).expect("txn");
-                    let _ = post_action.execute(ctx.ndb, &txn, ctx.pool, &mut app.drafts);
-                    get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
-                        .column_mut(col)
-                        .router_mut()
-                        .go_back();
+                    post(app, ctx, col, post_action);
+                }
+
+                RenderNavAction::NoteAction(NoteAction::Hide(note_id)) => {
+                    hide_note(app, ctx, *note_id);
+                }
+
+                RenderNavAction::NoteAction(NoteAction::HideAuthor(pubkey)) => {
+                    hide_author(app, ctx, *pubkey);
+                }
+
+                RenderNavAction::NoteAction(NoteAction::Repost(note_id)) => {
+                    repost(app, ctx, note_id);
+                }
+
+                RenderNavAction::NoteAction(NoteAction::UndoRepost(note_id)) => {
+                    undo_repost(app, ctx, note_id);
                 }
 
                 RenderNavAction::NoteAction(note_action) => {
@@ -165,14 +251,31 @@ impl RenderNavResponse {
                         ctx.pool,
                         &txn,
                         ctx.unknown_ids,
+                        &mut app.selection,
                     );
                 }
 
                 RenderNavAction::SwitchingAction(switching_action) => {
                     switching_occured = switching_action.process(&mut app.decks_cache, ctx);
+                    app.selection.columns_changed();
+                }
+
+                RenderNavAction::ThreadNotify(root, notify) => {
+                    app.notifier.set_thread(ctx.path, *root, *notify);
+                    toast::info(match notify {
+                        Some(ThreadNotify::Mute) => tr!("toast-thread-muted"),
+                        Some(ThreadNotify::Watch) => tr!("toast-thread-watched"),
+                        None => tr!("toast-thread-default"),
+                    });
+                }
+                RenderNavAction::ContentKinds(timeline_id, kinds) => {
+                    set_content_kinds(app, ctx, *timeline_id, *kinds);
+                    // saves the deck
+                    switching_occured = true;
                 }
                 RenderNavAction::ProfileAction(profile_action) => {
                     profile_action.process(
+                        ctx.accounts,
                         &mut app.view_state.pubkey_to_profile_state,
                         ctx.ndb,
                         ctx.pool,
@@ -250,6 +353,238 @@ impl RenderNavResponse {
     }
 }
 
+fn hide_note(app: &mut Damus, ctx: &mut AppContext<'_>, note_id: NoteId) {
+    if app.hidden.hide_note(note_id) {
+        storage::save_hidden(ctx.path, &app.hidden);
+        toast::info(tr!("toast-note-hidden"));
+    }
+}
+
+fn hide_author(app: &mut Damus, ctx: &mut AppContext<'_>, pubkey: Pubkey) {
+    if !app.hidden.hide_author(pubkey) {
+        return;
+    }
+
+    storage::save_hidden(ctx.path, &app.hidden);
+    toast::info(tr!("toast-author-hidden"));
+
+    if app.hidden.sync_mute_list {
+        if let Err(err) = ctx.accounts.publish_mute(ctx.ndb, ctx.pool, pubkey.bytes()) {
+            error!("could not add {} to the mute list: {err}", pubkey.hex());
+            toast::error(tr!("toast-mute-list-error", error = err.to_string()));
+        }
+    }
+}
+
+fn process_bookmark_action(ctx: &mut AppContext<'_>, action: BookmarkAction) {
+    let result = match action {
+        BookmarkAction::Publish(list) => ctx.accounts.publish_bookmarks(ctx.ndb, ctx.pool, &list),
+        BookmarkAction::DeleteSet(identifier) => {
+            ctx.accounts
+                .delete_bookmark_set(ctx.ndb, ctx.pool, &identifier)
+        }
+        BookmarkAction::Note(_) => Ok(()),
+    };
+
+    if let Err(err) = result {
+        error!("could not update bookmarks: {err}");
+    }
+}
+
+/// Start a new note in the composer with the text of one of ours
+fn edit_as_new(app: &mut Damus, ctx: &mut AppContext<'_>, col: usize, note_id: &NoteId) {
+    let txn = Transaction::new(ctx.ndb).expect("txn");
+    let Ok(note) = ctx.ndb.get_note_by_id(&txn, note_id.bytes()) else {
+        return;
+    };
+
+    let draft = app.drafts.compose_mut();
+    draft.buffer = note.content().to_owned();
+    draft.dirty = true;
+
+    get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
+        .column_mut(col)
+        .router_mut()
+        .route_to(Route::ComposeNote);
+}
+
+fn publish_edit(
+    app: &mut Damus,
+    ctx: &mut AppContext<'_>,
+    note_id: &NoteId,
+    content: &str,
+    tags: &[Vec<String>],
+) -> Option<RenderNavAction> {
+    if let Err(err) = ctx.check(Capability::Publish) {
+        toast::warning(err.message());
+        return None;
+    }
+
+    match ctx
+        .accounts
+        .publish_edit(ctx.ndb, ctx.pool, note_id.bytes(), content, tags)
+    {
+        Ok((edited, deletion)) => {
+            app.publishes.track(&edited, ctx.pool);
+            if let Some(deletion) = deletion {
+                app.publishes.track(&deletion, ctx.pool);
+            }
+            app.view_state.note_edit.clear();
+            toast::info(tr!("toast-note-edited"));
+            Some(RenderNavAction::Back)
+        }
+        Err(err) => {
+            error!("could not edit {}: {err}", note_id.hex());
+            toast::error(tr!("toast-edit-failed", error = err.to_string()));
+            None
+        }
+    }
+}
+
+fn delete_note(app: &mut Damus, ctx: &mut AppContext<'_>, note_id: &NoteId) {
+    if let Err(err) = ctx.check(Capability::Publish) {
+        toast::warning(err.message());
+        return;
+    }
+
+    match ctx
+        .accounts
+        .publish_deletion(ctx.ndb, ctx.pool, note_id.bytes())
+    {
+        Ok(request) => {
+            app.publishes.track(&request, ctx.pool);
+            toast::info(tr!("toast-note-deleted"));
+        }
+        Err(err) => {
+            error!("could not delete {}: {err}", note_id.hex());
+            toast::error(tr!("toast-delete-failed", error = err.to_string()));
+        }
+    }
+}
+
+fn repost(app: &mut Damus, ctx: &mut AppContext<'_>, note_id: &NoteId) {
+    if let Err(err) = ctx.check(Capability::Publish) {
+        toast::warning(err.message());
+        return;
+    }
+
+    match ctx
+        .accounts
+        .publish_repost(ctx.ndb, ctx.pool, note_id.bytes())
+    {
+        Ok(repost) => {
+            app.publishes.track(&repost, ctx.pool);
+            app.reposts.add(*note_id.bytes(), repost, Instant::now());
+            toast::info(tr!("toast-reposted"));
+        }
+        Err(err) => {
+            error!("could not repost {}: {err}", note_id.hex());
+            toast::error(tr!("toast-repost-failed", error = err.to_string()));
+        }
+    }
+}
+
+/// Take back a repost by asking relays to delete it, while it's recent
+/// enough that few will have seen it
+fn undo_repost(app: &mut Damus, ctx: &mut AppContext<'_>, note_id: &NoteId) {
+    let Some(repost) = app.reposts.take_back(note_id.bytes(), Instant::now()) else {
+        return;
+    };
+
+    reposts::remove(
+        ctx.ndb,
+        get_active_columns_mut(ctx.accounts, &mut app.decks_cache),
+        &mut app.timeline_cache,
+        &repost,
+    );
+    match ctx.accounts.publish_deletion(ctx.ndb, ctx.pool, &repost) {
+        Ok(request) => {
+            app.publishes.track(&request, ctx.pool);
+            toast::info(tr!("toast-repost-undone"));
+        }
+        Err(err) => {
+            error!("could not undo repost of {}: {err}", note_id.hex());
+            toast::error(tr!("toast-delete-failed", error = err.to_string()));
+        }
+    }
+}
+
+fn highlight(
+    app: &mut Damus,
+    ctx: &mut AppContext<'_>,
+    note_id: &NoteId,
+    action: &HighlightAction,
+) -> Option<RenderNavAction> {
+    if let Err(err) = ctx.check(Capability::Publish) {
+        toast::warning(err.message());
+        return None;
+    }
+
+    match ctx.accounts.publish_highlight(
+        ctx.ndb,
+        ctx.pool,
+        note_id.bytes(),
+        &action.passage,
+        &action.comment,
+    ) {
+        Ok(highlight) => {
+            app.publishes.track(&highlight, ctx.pool);
+            toast::info(tr!("toast-highlighted"));
+            Some(RenderNavAction::Back)
+        }
+        Err(err) => {
+            error!("could not highlight {}: {err}", note_id.hex());
+            toast::error(tr!("toast-highlight-failed", error = err.to_string()));
+            None
+        }
+    }
+}
+
+fn follow_all(ctx: &mut AppContext<'_>, them: &[[u8; 32]]) {
+    if let Err(err) = ctx.check(Capability::Publish) {
+        toast::warning(err.message());
+        return;
+    }
+
+    match ctx.accounts.publish_follows(ctx.ndb, ctx.pool, them) {
+        Ok(()) => toast::info(tr!("toast-followed-pack", count = them.len())),
+        Err(err) => {
+            error!("could not follow pack: {err}");
+            toast::error(tr!("toast-follow-error", error = err.to_string()));
+        }
+    }
+}
+
+/// Ask for different kinds of notes in a timeline column, starting it
+/// over with the new filters once they're ready
+fn set_content_kinds(
+    app: &mut Damus,
+    ctx: &mut AppContext<'_>,
+    timeline_id: TimelineId,
+    kinds: ContentKinds,
+) {
+    let our_pk = ctx.accounts.get_selected_account().map(|a| a.pubkey);
+    let columns = get_active_columns_mut(ctx.accounts, &mut app.decks_cache);
+    let Some(timeline) = columns.find_timeline_mut(timeline_id) else {
+        return;
+    };
+
+    timeline.set_kinds(kinds);
+    // still waiting on a contact list, these get applied when it comes
+    let Some(filters) = timeline.filter.get_any_ready().cloned() else {
+        return;
+    };
+    timeline::replace_timeline_filter(
+        timeline,
+        kinds.apply(filters),
+        ctx.ndb,
+        &mut app.subscriptions,
+        ctx.pool,
+        ctx.note_cache,
+        our_pk.as_ref(),
+    );
+}
+
 fn render_nav_body(
     ui: &mut egui::Ui,
     app: &mut Damus,
@@ -267,12 +602,16 @@ fn render_nav_body(
             ctx.note_cache,
             &mut app.timeline_cache,
             ctx.accounts,
+            ctx.settings,
+            &app.wot,
+            &app.hidden,
             *tlr,
             col,
             app.textmode,
             ui,
         ),
         Route::Accounts(amr) => {
+            let known_accounts = ctx.accounts.num_accounts();
             let mut action = render_accounts_route(
                 ui,
                 ctx.ndb,
@@ -285,13 +624,188 @@ fn render_nav_body(
             );
             let txn = Transaction::new(ctx.ndb).expect("txn");
             action.process_action(ctx.unknown_ids, ctx.ndb, &txn);
+
+            // accounts that log in get their starter columns instead of an
+            // empty deck
+            let new_account = (ctx.accounts.num_accounts() > known_accounts)
+                .then(|| ctx.accounts.get_account(known_accounts).map(|a| a.pubkey))
+                .flatten();
+            if let Some(pubkey) = new_account {
+                if ctx.settings.bool(settings::APP, settings::STARTER_COLUMNS) {
+                    let deck =
+                        onboarding::starter_deck(app, ctx, &pubkey, &StarterColumns::login());
+                    app.decks_cache.add_decks(pubkey, Decks::new(deck));
+                }
+            }
+
             action
                 .accounts_action
                 .map(|f| RenderNavAction::SwitchingAction(SwitchingAction::Accounts(f)))
         }
         Route::Relays => {
+            let session = ctx.session();
+            let account = ctx.accounts.get_selected_account().map(|a| a.pubkey);
+            let advertised = ctx.accounts.advertised_relays();
+            let local = ctx.accounts.local_relays();
             let manager = RelayPoolManager::new(ctx.pool);
-            RelayView::new(manager).ui(ui);
+            let action = RelayView::new(
+                manager,
+                session,
+                &mut app.view_state.id_string_map,
+                &mut app.view_state.relay_list_draft,
+                &mut app.view_state.relay_info,
+                account,
+                advertised,
+                local,
+            )
+            .ui(ui);
+
+            // the pool follows the account's relay config, so changes go
+            // through the account instead of the pool
+            match action {
+                Some(RelayAction::AddLocal(relay_url)) => {
+                    if !ctx.accounts.add_local_relay(&relay_url) {
+                        error!("no selected account to add relay {relay_url} to");
+                    }
+                }
+                Some(RelayAction::RemoveLocal(relay_url)) => {
+                    if !ctx.accounts.remove_local_relay(&relay_url) {
+                        error!("no selected account to remove relay {relay_url} from");
+                    }
+                }
+                Some(RelayAction::Publish(relays)) => {
+                    match ctx.accounts.publish_relay_list(ctx.ndb, ctx.pool, relays) {
+                        Ok(()) => app.view_state.relay_list_draft.clear(),
+                        Err(err) => error!("could not publish relay list: {err}"),
+                    }
+                }
+                Some(RelayAction::RelayGroups) => {
+                    get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
+                        .column_mut(col)
+                        .router_mut()
+                        .route_to(Route::RelayGroups);
+                }
+                None => {}
+            }
+
+            None
+        }
+        Route::Settings => {
+            let txn = Transaction::new(ctx.ndb).expect("txn");
+            let media_servers = ctx
+                .accounts
+                .get_selected_account()
+                .filter(|account| account.secret_key.is_some())
+                .map(|account| blossom::server_list(ctx.ndb, &txn, account.pubkey.bytes()));
+            let new_server = app
+                .view_state
+                .id_string_map
+                .entry(egui::Id::new("new-media-server"))
+                .or_default();
+
+            let sync_enabled = ctx
+                .accounts
+                .get_selected_account()
+                .and_then(|account| account.to_full())
+                .map(|_| app.settings_sync.state.enabled);
+            let action = SettingsView::new(
+                &app.hashtag_settings,
+                media_servers.as_deref(),
+                new_server,
+                sync_enabled,
+            )
+            .ui(ui);
+            let route = match action {
+                Some(SettingsAction::Relays) => Some(Route::Relays),
+                Some(SettingsAction::Notifications) => Some(Route::NotificationSettings),
+                Some(SettingsAction::Subscriptions) => Some(Route::Subscriptions),
+                Some(SettingsAction::Wallet) => Some(Route::Wallet),
+                Some(SettingsAction::Hidden) => Some(Route::Hidden),
+                Some(SettingsAction::ExportData) => Some(Route::ExportData),
+                Some(SettingsAction::Storage) => Some(Route::Storage),
+                Some(SettingsAction::SyncSettings(enabled)) => {
+                    app.settings_sync.set_enabled(ctx.path, enabled);
+                    None
+                }
+                Some(SettingsAction::SyncNow) => {
+                    app.settings_sync.request(SyncRequest::Now);
+                    None
+                }
+                Some(SettingsAction::RestoreSettings) => {
+                    app.settings_sync.request(SyncRequest::Restore);
+                    None
+                }
+                Some(SettingsAction::RefreshTimelines) => {
+                    timeline::refresh_timelines(
+                        ctx.ndb,
+                        get_active_columns_mut(ctx.accounts, &mut app.decks_cache),
+                        &mut app.subscriptions,
+                        ctx.pool,
+                    );
+                    toast::info(tr!("toast-refreshing-timelines"));
+                    None
+                }
+                Some(SettingsAction::Language(language)) => {
+                    notedeck::i18n::set_language(ctx.path, language);
+                    None
+                }
+                Some(SettingsAction::Time(settings)) => {
+                    notedeck::datetime::set_settings(ctx.path, settings);
+                    None
+                }
+                Some(SettingsAction::Amounts(settings)) => {
+                    notedeck::amount::set_settings(ctx.path, settings);
+                    None
+                }
+                Some(SettingsAction::Hashtags(settings)) => {
+                    storage::save_hashtag_settings(ctx.path, &settings);
+                    app.hashtag_settings = settings;
+                    None
+                }
+                Some(SettingsAction::MediaServers(servers)) => {
+                    if let Some(keypair) = ctx
+                        .accounts
+                        .get_selected_account()
+                        .and_then(|account| account.to_full())
+                    {
+                        if let Err(err) = blossom::publish_server_list(
+                            ctx.accounts,
+                            ctx.ndb,
+                            ctx.pool,
+                            keypair,
+                            &servers,
+                        ) {
+                            error!("could not publish media servers: {err}");
+                            toast::error(tr!("toast-media-servers-error", error = err.to_string()));
+                        }
+                    }
+                    None
+                }
+                None => None,
+            };
+
+            if let Some(route) = route {
+                get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
+                    .column_mut(col)
+                    .router_mut()
+                    .route_to(route);
+            }
+
+            None
+        }
+        Route::NotificationSettings => {
+            if NotificationSettingsView::new(&mut app.notifier.settings).ui(ui) {
+                storage::save_notification_settings(ctx.path, &app.notifier.settings);
+            }
+
+            None
+        }
+        Route::RelayGroups => {
+            let relays = ctx.pool.urls().into_iter().collect();
+            if RelayGroupsView::new(&mut app.relay_groups, relays).ui(ui) {
+                storage::save_relay_groups(ctx.path, &app.relay_groups);
+            }
+
             None
         }
         Route::ComposeNote => {
@@ -320,16 +834,480 @@ fn render_nav_body(
             SupportView::new(&mut app.support).show(ui);
             None
         }
+        Route::Poster(note_id) => {
+            let fallback_dir = ctx.path.path(DataPathType::Cache);
+            PosterView::new(ctx.ndb, ctx.img_cache, note_id, &fallback_dir).ui(ui);
+            None
+        }
+        Route::Source(note_id) => {
+            SourceView::new(ctx.ndb, note_id).ui(ui);
+            None
+        }
+        Route::EditNote(note_id) => {
+            match NoteEditView::new(ctx.ndb, note_id, &mut app.view_state.note_edit).ui(ui)? {
+                NoteEditAction::Publish { content, tags } => {
+                    publish_edit(app, ctx, note_id, &content, &tags)
+                }
+            }
+        }
+        Route::Highlight(note_id) => {
+            let can_sign = ctx
+                .accounts
+                .get_selected_account()
+                .is_some_and(|account| account.to_full().is_some());
+            let action = HighlightView::new(ctx.ndb, note_id, can_sign).ui(ui)?;
+            highlight(app, ctx, note_id, &action)
+        }
+        Route::Highlights(note_id) => {
+            if let Ok(txn) = Transaction::new(ctx.ndb) {
+                if let Ok(note) = ctx.ndb.get_note_by_id(&txn, note_id.bytes()) {
+                    app.highlights.fetch(ctx.pool, &note);
+                }
+            }
+            let mut note_options = NoteOptions::new(false);
+            note_options.set_textmode(app.textmode);
+
+            HighlightsView::new(
+                ctx.ndb,
+                ctx.note_cache,
+                ctx.img_cache,
+                note_id,
+                note_options,
+            )
+            .ui(ui)
+            .map(RenderNavAction::NoteAction)
+        }
+        Route::Wallet => {
+            let action = WalletView::new(
+                app.wallet.as_ref(),
+                app.wallet_pairing.as_ref(),
+                ctx.ndb,
+                ctx.img_cache,
+                &mut app.view_state.id_string_map,
+            )
+            .ui(ui);
+
+            match action? {
+                WalletAction::Pair(uri) => match enostr::NwcUri::parse(&uri) {
+                    Ok(uri) => {
+                        let egui_ctx = ui.ctx().clone();
+                        app.wallet_pairing =
+                            Some(Wallet::pair(uri, move || egui_ctx.request_repaint()));
+                    }
+                    Err(err) => {
+                        error!("could not connect wallet: {err}");
+                        toast::error(tr!("toast-wallet-error", error = err.to_string()));
+                    }
+                },
+                WalletAction::ScanQr => match ctx.check(Capability::Camera) {
+                    Ok(()) => qr_scan::request_camera(ui.ctx()),
+                    Err(err) => toast::warning(err.message()),
+                },
+                WalletAction::SavePairing => {
+                    let Some(keypair) = ctx
+                        .accounts
+                        .get_selected_account()
+                        .and_then(|account| account.to_full())
+                    else {
+                        toast::warning(tr!("toast-wallet-no-key"));
+                        return None;
+                    };
+                    if let Some(mut pairing) = app.wallet_pairing.take() {
+                        storage::save_wallet_uri(ctx.path, keypair, Some(&pairing.uri().to_uri()));
+                        if let Some(previous) = app.wallet.take() {
+                            previous.disconnect();
+                        }
+                        pairing.refresh();
+                        app.wallet = Some(pairing);
+                    }
+                }
+                WalletAction::CancelPairing => {
+                    if let Some(pairing) = app.wallet_pairing.take() {
+                        pairing.disconnect();
+                    }
+                }
+                WalletAction::Disconnect => {
+                    if let Some(wallet) = app.wallet.take() {
+                        wallet.disconnect();
+                    }
+                    if let Some(keypair) = ctx
+                        .accounts
+                        .get_selected_account()
+                        .and_then(|account| account.to_full())
+                    {
+                        storage::save_wallet_uri(ctx.path, keypair, None);
+                    }
+                }
+                WalletAction::Refresh => {
+                    if let Some(wallet) = &mut app.wallet {
+                        wallet.refresh();
+                    }
+                }
+                WalletAction::Pay(invoice) => {
+                    if let Some(wallet) = &mut app.wallet {
+                        wallet.pay(invoice);
+                    }
+                }
+                WalletAction::MakeInvoice {
+                    amount_msats,
+                    description,
+                } => {
+                    if let Some(wallet) = &mut app.wallet {
+                        wallet.make_invoice(amount_msats, description);
+                    }
+                }
+                WalletAction::Note(action) => return Some(RenderNavAction::NoteAction(action)),
+            }
+
+            None
+        }
+        Route::Subscriptions => {
+            let action = SubscriptionsView::new(
+                ctx.ndb,
+                get_active_columns(ctx.accounts, &app.decks_cache),
+                &app.timeline_cache,
+                &app.subscriptions,
+                &app.verifier.stats,
+                &mut app.view_state.id_string_map,
+            )
+            .ui(ui);
+
+            match action {
+                Some(SubscriptionAction::Resend { sub_id, filters }) => {
+                    app.subscriptions.reset_status(&sub_id);
+                    ctx.pool.subscribe(sub_id, filters);
+                }
+                Some(SubscriptionAction::EditFilter {
+                    timeline: timeline_id,
+                    filters,
+                }) => {
+                    let our_pk = ctx.accounts.get_selected_account().map(|a| a.pubkey);
+                    let columns = get_active_columns_mut(ctx.accounts, &mut app.decks_cache);
+                    if let Some(timeline) = columns.find_timeline_mut(timeline_id) {
+                        timeline::replace_timeline_filter(
+                            timeline,
+                            filters,
+                            ctx.ndb,
+                            &mut app.subscriptions,
+                            ctx.pool,
+                            ctx.note_cache,
+                            our_pk.as_ref(),
+                        );
+                    }
+                }
+                None => {}
+            }
+
+            None
+        }
+        Route::Hidden => {
+            if HiddenView::new(ctx.ndb, &mut app.hidden).ui(ui) {
+                storage::save_hidden(ctx.path, &app.hidden);
+            }
+
+            None
+        }
+        Route::Report(target) => {
+            let keypair = ctx
+                .accounts
+                .get_selected_account()
+                .and_then(|account| account.to_full());
+            let action = ReportView::new(ctx.ndb, target, keypair.is_some()).ui(ui)?;
+            let keypair = keypair?;
+
+            match moderation::report(
+                ctx.accounts,
+                ctx.ndb,
+                ctx.pool,
+                &keypair,
+                target,
+                action.kind,
+                &action.reason,
+                action.label,
+            ) {
+                Ok(()) => {
+                    toast::info(tr!("toast-reported"));
+                    Some(RenderNavAction::Back)
+                }
+                Err(err) => {
+                    error!("could not send report: {err}");
+                    toast::error(tr!("toast-report-failed", error = err));
+                    None
+                }
+            }
+        }
+        Route::ExportData => {
+            match ExportView::new(&app.export).ui(ui) {
+                Some(ExportAction::Export { dest }) => {
+                    if let Some(account) = ctx.accounts.get_selected_account() {
+                        let keypair = account.to_full().map(|kp| kp.to_full());
+                        app.export.export(ctx.ndb, account.pubkey, keypair, &dest);
+                    }
+                }
+                Some(ExportAction::Import { dir, rebroadcast }) => {
+                    app.export.import(ctx.ndb, dir, rebroadcast);
+                }
+                Some(ExportAction::PreviewBackup { path }) => {
+                    let new = std::fs::read_to_string(&path)
+                        .map_err(|err| err.to_string())
+                        .and_then(|text| Backup::parse(&text).map_err(|err| err.to_string()))
+                        .and_then(|backup| {
+                            ctx.accounts
+                                .backup_entries(ctx.ndb, &backup)
+                                .map_err(|err| err.to_string())
+                        });
+                    match new {
+                        Ok(new) if new.is_empty() => toast::info(tr!("toast-backup-nothing-new")),
+                        Ok(new) => app.export.backup = Some(BackupPreview { path, new }),
+                        Err(err) => {
+                            error!("could not read backup: {err}");
+                            toast::error(tr!("toast-backup-failed", error = err));
+                        }
+                    }
+                }
+                Some(ExportAction::ApplyBackup) => {
+                    if let Some(preview) = app.export.backup.take() {
+                        match ctx.accounts.publish_backup(ctx.ndb, ctx.pool, &preview.new) {
+                            Ok(()) => toast::info(tr!("toast-backup-applied")),
+                            Err(err) => {
+                                error!("could not apply backup: {err}");
+                                toast::error(tr!("toast-backup-failed", error = err.to_string()));
+                            }
+                        }
+                    }
+                }
+                Some(ExportAction::DiscardBackup) => app.export.backup = None,
+                Some(ExportAction::ExportBlocklist { path }) => {
+                    let written = settings::blocklist(ctx.settings)
+                        .export()
+                        .map_err(|err| err.to_string())
+                        .and_then(|json| {
+                            std::fs::write(&path, json).map_err(|err| err.to_string())
+                        });
+                    match written {
+                        Ok(()) => toast::info(tr!(
+                            "toast-blocklist-exported",
+                            path = path.display().to_string()
+                        )),
+                        Err(err) => {
+                            error!("could not export blocklist: {err}");
+                            toast::error(tr!("toast-blocklist-failed", error = err));
+                        }
+                    }
+                }
+                Some(ExportAction::ImportBlocklist { path }) => {
+                    let imported = std::fs::read_to_string(&path)
+                        .map_err(|err| err.to_string())
+                        .and_then(|json| Blocklist::import(&json).map_err(|err| err.to_string()));
+                    match imported {
+                        Ok(imported) => {
+                            let mut blocklist = settings::blocklist(ctx.settings);
+                            let added = blocklist.merge(imported);
+                            settings::set_blocklist(ctx.settings, &blocklist);
+                            toast::info(tr!("toast-blocklist-imported", count = added));
+                        }
+                        Err(err) => {
+                            error!("could not import blocklist: {err}");
+                            toast::error(tr!("toast-blocklist-failed", error = err));
+                        }
+                    }
+                }
+                None => {}
+            }
+
+            None
+        }
+        Route::Storage => {
+            let retention = settings::retention(ctx.settings);
+            let db_dir = notedeck::maintenance::db_dir(ctx.path, ctx.args);
+            match StorageView::new(&app.maintenance, &retention).ui(ui) {
+                Some(StorageAction::Count) => app.maintenance.count(ctx.ndb, db_dir),
+                Some(StorageAction::Compact) => {
+                    // our own notes are never dropped
+                    let mut keep: HashSet<[u8; 32]> = ctx
+                        .accounts
+                        .get_accounts()
+                        .iter()
+                        .map(|account| *account.pubkey.bytes())
+                        .collect();
+                    if retention.keep_trusted {
+                        keep.extend(app.wot.iter().copied());
+                    }
+                    app.maintenance.compact(ctx.ndb, db_dir, retention, keep);
+                }
+                Some(StorageAction::Cancel) => app.maintenance.cancel(),
+                Some(StorageAction::EditRetention) => notedeck::settings::open_window(ui.ctx()),
+                None => {}
+            }
+
+            None
+        }
+        Route::Bookmarks => {
+            let bookmarks = ctx.accounts.bookmarks().cloned().unwrap_or_default();
+            let mut note_options = NoteOptions::new(false);
+            note_options.set_textmode(app.textmode);
+
+            let action = BookmarksView::new(
+                ctx.ndb,
+                ctx.note_cache,
+                ctx.img_cache,
+                ctx.unknown_ids,
+                &bookmarks,
+                &mut app.view_state.id_string_map,
+                note_options,
+            )
+            .ui(ui);
+
+            match action? {
+                BookmarkAction::Note(note_action) => Some(RenderNavAction::NoteAction(note_action)),
+                action => {
+                    process_bookmark_action(ctx, action);
+                    None
+                }
+            }
+        }
+        Route::MyPosts => {
+            let pubkey = ctx.accounts.get_selected_account()?.pubkey;
+            let mut note_options = NoteOptions::new(false);
+            note_options.set_textmode(app.textmode);
+
+            let action = MyPostsView::new(
+                ctx.ndb,
+                ctx.note_cache,
+                ctx.img_cache,
+                &app.publishes,
+                &pubkey,
+                note_options,
+            )
+            .ui(ui);
+
+            match action? {
+                MyPostsAction::Note(note_action) => Some(RenderNavAction::NoteAction(note_action)),
+                MyPostsAction::EditAsNew(note_id) => {
+                    edit_as_new(app, ctx, col, &note_id);
+                    None
+                }
+                MyPostsAction::Edit(note_id) => {
+                    get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
+                        .column_mut(col)
+                        .router_mut()
+                        .route_to(Route::EditNote(note_id));
+                    None
+                }
+                MyPostsAction::Delete(note_id) => {
+                    delete_note(app, ctx, &note_id);
+                    None
+                }
+            }
+        }
+        Route::ExplorePeople => {
+            let account = ctx.accounts.get_selected_account().map(|acc| acc.pubkey);
+            let packs = app.follow_packs.packs(ctx.ndb, ctx.pool);
+            let action = ExplorePeopleView::new(
+                ctx.ndb,
+                ctx.img_cache,
+                ctx.unknown_ids,
+                packs,
+                account.as_ref().map(|pk| pk.bytes()),
+            )
+            .ui(ui);
+
+            match action? {
+                ExplorePeopleAction::FollowAll(them) => follow_all(ctx, &them),
+            }
+            None
+        }
+        Route::Trending => {
+            let hours: u64 = ctx
+                .settings
+                .string(settings::APP, settings::TRENDING_WINDOW)
+                .parse()
+                .unwrap_or(6);
+            let api = ctx.settings.string(settings::APP, settings::TRENDING_API);
+            let mut note_options = NoteOptions::new(false);
+            note_options.set_textmode(app.textmode);
+
+            app.trending.update(
+                ctx.ndb,
+                &app.wot,
+                Duration::from_secs(hours * 60 * 60),
+                &api,
+                ui.ctx(),
+            );
+            TrendingView::new(
+                ctx.ndb,
+                ctx.note_cache,
+                ctx.img_cache,
+                ctx.unknown_ids,
+                app.trending.results(),
+                app.trending.is_refreshing(),
+                note_options,
+            )
+            .ui(ui)
+            .map(RenderNavAction::NoteAction)
+        }
+        Route::SaveBookmark(note_id) => {
+            let bookmarks = ctx.accounts.bookmarks().cloned().unwrap_or_default();
+            let action =
+                SaveBookmarkView::new(&bookmarks, note_id, &mut app.view_state.id_string_map)
+                    .ui(ui);
+
+            if let Some(action) = action {
+                process_bookmark_action(ctx, action);
+            }
+
+            None
+        }
         Route::NewDeck => {
             let id = ui.id().with("new-deck");
             let new_deck_state = app.view_state.id_to_deck_state.entry(id).or_default();
-            let mut resp = None;
+            let deck_user = ctx.accounts.get_selected_account().map(|acc| acc.pubkey);
+            let mut new_deck = None;
             if let Some(config_resp) = ConfigureDeckView::new(new_deck_state).ui(ui) {
-                if let Some(cur_acc) = ctx.accounts.get_selected_account() {
-                    app.decks_cache.add_deck(
-                        cur_acc.pubkey,
-                        Deck::new(config_resp.icon, config_resp.name),
-                    );
+                let mut deck = Deck::new(config_resp.icon, config_resp.name);
+                deck.density = config_resp.density;
+                new_deck = Some(deck);
+                new_deck_state.clear();
+            }
+
+            match ImportDeckView::new(&mut app.deck_import).ui(ui) {
+                Some(ImportDeckAction::Read) => {
+                    if let Some(deck_user) = &deck_user {
+                        app.deck_import.read(ctx.ndb, ctx.pool, deck_user);
+                    }
+                }
+                Some(ImportDeckAction::Add) => {
+                    if let (Some(mut imported), Some(deck_user)) =
+                        (app.deck_import.preview.take(), &deck_user)
+                    {
+                        for timeline in imported.deck.columns_mut().timelines_mut() {
+                            timeline::setup_new_timeline(
+                                timeline,
+                                ctx.ndb,
+                                &mut app.subscriptions,
+                                ctx.pool,
+                                ctx.note_cache,
+                                app.since_optimize,
+                                Some(deck_user),
+                            );
+                        }
+                        if let Ok(txn) = Transaction::new(ctx.ndb) {
+                            for pubkey in &imported.unknown {
+                                ctx.unknown_ids.add_pubkey_if_missing(ctx.ndb, &txn, pubkey);
+                            }
+                        }
+                        new_deck = Some(imported.deck);
+                    }
+                    app.deck_import.clear(ctx.pool);
+                }
+                Some(ImportDeckAction::Clear) => app.deck_import.clear(ctx.pool),
+                None => {}
+            }
+
+            let mut resp = None;
+            if let Some(deck) = new_deck {
+                if let Some(deck_user) = deck_user {
+                    app.decks_cache.add_deck(deck_user, deck);
 
                     // set new deck as active
                     let cur_index = get_decks_mut(ctx.accounts, &mut app.decks_cache)
@@ -341,7 +1319,6 @@ fn render_nav_body(
                     )));
                 }
 
-                new_deck_state.clear();
                 get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
                     .get_first_router()
                     .go_back();
@@ -365,19 +1342,50 @@ fn render_nav_body(
                 .entry(id)
                 .or_insert_with(|| DeckState::from_deck(cur_deck));
             if let Some(resp) = EditDeckView::new(deck_state).ui(ui) {
-                match resp {
+                // sharing leaves the deck open
+                let close = match resp {
                     EditDeckResponse::Edit(configure_deck_response) => {
                         cur_deck.edit(configure_deck_response);
+                        true
                     }
                     EditDeckResponse::Delete => {
                         action = Some(RenderNavAction::SwitchingAction(SwitchingAction::Decks(
                             DecksAction::Removing(*index),
                         )));
+                        true
                     }
+                    EditDeckResponse::CopyJson => {
+                        if let Some(json) = storage::deck_to_json(cur_deck) {
+                            ui.ctx().copy_text(json);
+                            toast::info(tr!("toast-deck-copied"));
+                        }
+                        false
+                    }
+                    EditDeckResponse::Publish => {
+                        let shared = ctx.accounts.get_selected_account().and_then(|acc| {
+                            deck_share::publish(
+                                ctx.accounts,
+                                ctx.ndb,
+                                ctx.pool,
+                                &acc.to_full()?,
+                                cur_deck,
+                            )
+                        });
+                        match shared.and_then(|address| address.to_bech()) {
+                            Some(naddr) => {
+                                ui.ctx().copy_text(naddr);
+                                toast::info(tr!("toast-deck-published"));
+                            }
+                            None => toast::error(tr!("toast-deck-publish-failed")),
+                        }
+                        false
+                    }
+                };
+                if close {
+                    get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
+                        .get_first_router()
+                        .go_back();
                 }
-                get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
-                    .get_first_router()
-                    .go_back();
             }
 
             action
@@ -452,6 +1460,7 @@ pub fn render_nav(
             ctx.accounts.get_selected_account().map(|a| &a.pubkey),
             nav.routes(),
             col,
+            &app.notifier.threads,
         )
         .show(ui),
         NavUiType::Body => render_nav_body(ui, app, ctx, nav.routes().last().expect("top"), col),
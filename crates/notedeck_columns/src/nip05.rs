@@ -0,0 +1,234 @@
+//! NIP-05 identifiers only count once the domain's `.well-known/nostr.json`
+//! lists the profile's key. Lookups are cached and checked again in the
+//! background while the profile is still being shown, so a domain that
+//! drops someone stops vouching for them here too.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use poll_promise::Promise;
+use tracing::debug;
+
+use crate::key_parsing::Nip05Result;
+
+/// How long until we check a verified identifier again
+const VERIFIED_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Domains that didn't list the key get another look sooner, in case they
+/// were fixing it
+const MISMATCH_TTL: Duration = Duration::from_secs(60 * 60);
+/// Domains we couldn't reach, or that sent something we couldn't read
+const UNREACHABLE_TTL: Duration = Duration::from_secs(5 * 60);
+/// Identifiers that haven't been on screen this long are forgotten
+const FORGET_AFTER: Duration = Duration::from_secs(60 * 60);
+/// Most lookups running at once
+const MAX_FETCHES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nip05Status {
+    /// Not looked up yet
+    Unchecked,
+    /// The domain lists this key for the name
+    Verified,
+    /// The domain doesn't list the name, or lists another key for it
+    Mismatch,
+    /// We couldn't ask the domain
+    Unreachable,
+}
+
+impl Nip05Status {
+    fn ttl(&self) -> Duration {
+        match self {
+            Nip05Status::Unchecked => Duration::ZERO,
+            Nip05Status::Verified => VERIFIED_TTL,
+            Nip05Status::Mismatch => MISMATCH_TTL,
+            Nip05Status::Unreachable => UNREACHABLE_TTL,
+        }
+    }
+}
+
+struct Entry {
+    /// The last answer. Kept while checking again so badges don't flicker.
+    status: Nip05Status,
+    checked: Option<Instant>,
+    wanted: Instant,
+    fetch: Option<Promise<Nip05Status>>,
+}
+
+impl Entry {
+    fn is_due(&self, now: Instant) -> bool {
+        self.fetch.is_none()
+            && !self
+                .checked
+                .is_some_and(|checked| now.duration_since(checked) < self.status.ttl())
+    }
+}
+
+#[derive(Default)]
+struct Lookups {
+    entries: HashMap<([u8; 32], String), Entry>,
+}
+
+impl Lookups {
+    fn fetching(&self) -> usize {
+        self.entries
+            .values()
+            .filter(|entry| entry.fetch.is_some())
+            .count()
+    }
+}
+
+/// Shared with note and profile views through the egui context
+#[derive(Clone, Default)]
+pub struct Nip05Cache {
+    lookups: Arc<Mutex<Lookups>>,
+}
+
+fn cache_id() -> egui::Id {
+    egui::Id::new("nip05-cache")
+}
+
+impl Nip05Cache {
+    /// Collect finished lookups, start due rechecks for identifiers still
+    /// on screen and make the cache available to views
+    pub fn update(&self, ctx: &egui::Context) {
+        let now = Instant::now();
+        if let Ok(mut lookups) = self.lookups.lock() {
+            lookups
+                .entries
+                .retain(|_, entry| now.duration_since(entry.wanted) < FORGET_AFTER);
+
+            for entry in lookups.entries.values_mut() {
+                let Some(status) = entry.fetch.as_ref().and_then(|f| f.ready()).copied() else {
+                    continue;
+                };
+                entry.status = status;
+                entry.checked = Some(now);
+                entry.fetch = None;
+            }
+
+            let mut room = MAX_FETCHES.saturating_sub(lookups.fetching());
+            for ((pubkey, nip05), entry) in lookups.entries.iter_mut() {
+                if room == 0 {
+                    break;
+                }
+                if entry.is_due(now) {
+                    entry.fetch = Some(fetch(ctx, pubkey, nip05));
+                    room -= 1;
+                }
+            }
+        }
+
+        ctx.data_mut(|d| d.insert_temp(cache_id(), self.clone()));
+    }
+}
+
+/// What we know about someone's identifier. Asking is what keeps it
+/// being checked.
+pub fn status(ctx: &egui::Context, pubkey: &[u8; 32], nip05: &str) -> Nip05Status {
+    let Some(cache) = ctx.data(|d| d.get_temp::<Nip05Cache>(cache_id())) else {
+        return Nip05Status::Unchecked;
+    };
+    let Ok(mut lookups) = cache.lookups.lock() else {
+        return Nip05Status::Unchecked;
+    };
+
+    let now = Instant::now();
+    let entry = lookups
+        .entries
+        .entry((*pubkey, nip05.to_lowercase()))
+        .or_insert_with(|| Entry {
+            status: Nip05Status::Unchecked,
+            checked: None,
+            wanted: now,
+            fetch: None,
+        });
+    entry.wanted = now;
+    entry.status
+}
+
+/// The name and domain of an identifier. A bare domain is the `_` name.
+fn parse(nip05: &str) -> Option<(&str, &str)> {
+    let (name, domain) = nip05.split_once('@').unwrap_or(("_", nip05));
+    let valid = !name.is_empty()
+        && !domain.is_empty()
+        && !domain.contains(['/', '@', '?', '#'])
+        && domain.contains('.');
+    valid.then_some((name, domain))
+}
+
+fn check(body: &[u8], name: &str, pubkey: &[u8; 32]) -> Nip05Status {
+    let Ok(result) = serde_json::from_slice::<Nip05Result>(body) else {
+        return Nip05Status::Unreachable;
+    };
+    match result.names.get(name) {
+        Some(listed) if listed.eq_ignore_ascii_case(&hex::encode(pubkey)) => Nip05Status::Verified,
+        _ => Nip05Status::Mismatch,
+    }
+}
+
+fn fetch(ctx: &egui::Context, pubkey: &[u8; 32], nip05: &str) -> Promise<Nip05Status> {
+    let Some((name, domain)) = parse(nip05) else {
+        return Promise::from_ready(Nip05Status::Mismatch);
+    };
+
+    let url = format!("https://{domain}/.well-known/nostr.json?name={name}");
+    let (sender, promise) = Promise::new();
+    let ctx = ctx.clone();
+    let name = name.to_owned();
+    let pubkey = *pubkey;
+    ehttp::fetch(ehttp::Request::get(&url), move |response| {
+        let status = match response {
+            Ok(response) if response.ok => check(&response.bytes, &name, &pubkey),
+            Ok(response) => {
+                debug!("nip05 lookup {url} failed: {}", response.status);
+                Nip05Status::Unreachable
+            }
+            Err(err) => {
+                debug!("nip05 lookup {url} failed: {err}");
+                Nip05Status::Unreachable
+            }
+        };
+        sender.send(status);
+        ctx.request_repaint();
+    });
+
+    promise
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("bob@example.com"), Some(("bob", "example.com")));
+        assert_eq!(parse("example.com"), Some(("_", "example.com")));
+        assert_eq!(parse("bob@"), None);
+        assert_eq!(parse("bob@example.com/evil"), None);
+    }
+
+    #[test]
+    fn test_check() {
+        let pubkey = [0xab; 32];
+        let body = format!(
+            r#"{{"names":{{"bob":"{}","eve":"{}"}}}}"#,
+            "AB".repeat(32),
+            "cd".repeat(32)
+        );
+
+        assert_eq!(
+            check(body.as_bytes(), "bob", &pubkey),
+            Nip05Status::Verified
+        );
+        assert_eq!(
+            check(body.as_bytes(), "eve", &pubkey),
+            Nip05Status::Mismatch
+        );
+        assert_eq!(
+            check(body.as_bytes(), "alice", &pubkey),
+            Nip05Status::Mismatch
+        );
+        assert_eq!(check(b"<html>", "bob", &pubkey), Nip05Status::Unreachable);
+    }
+}
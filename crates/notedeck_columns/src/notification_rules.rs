@@ -0,0 +1,175 @@
+//! User-defined rules that decide which notifications get through, like
+//! "only mentions from people I follow" or "always zaps over 1000 sats".
+//! Rules are checked in order and the first one that matches decides.
+//! When none match, the plain notification settings apply.
+
+use serde::{Deserialize, Serialize};
+
+use crate::notifications::NotificationKind;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Notify, even if the kind is turned off, the sender isn't followed
+    /// or it's quiet hours
+    Notify,
+    Mute,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// The notification is one of these kinds
+    Kind { kinds: Vec<NotificationKind> },
+    /// The sender is at most this many hops out in the web of trust,
+    /// 1 being people we follow
+    WithinWot { hops: u8 },
+    /// The sender is further out than this, or not in the web of trust
+    OutsideWot { hops: u8 },
+    /// The note's text contains this, ignoring case
+    Contains { text: String },
+    /// A zap of at least this many sats
+    ZapAtLeast { sats: u64 },
+}
+
+impl Condition {
+    pub fn matches(&self, input: &RuleInput) -> bool {
+        match self {
+            Condition::Kind { kinds } => kinds.contains(&input.kind),
+            Condition::WithinWot { hops } => input.hops.is_some_and(|h| h <= *hops),
+            Condition::OutsideWot { hops } => !input.hops.is_some_and(|h| h <= *hops),
+            Condition::Contains { text } => {
+                !text.is_empty() && input.content.to_lowercase().contains(&text.to_lowercase())
+            }
+            Condition::ZapAtLeast { sats } => input
+                .zap_msats
+                .is_some_and(|msats| msats >= sats.saturating_mul(1000)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NotificationRule {
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    /// All of these have to match. A rule without conditions matches
+    /// everything.
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    pub action: RuleAction,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Default for NotificationRule {
+    fn default() -> Self {
+        NotificationRule {
+            enabled: true,
+            conditions: vec![],
+            action: RuleAction::Mute,
+        }
+    }
+}
+
+impl NotificationRule {
+    pub fn matches(&self, input: &RuleInput) -> bool {
+        self.enabled && self.conditions.iter().all(|c| c.matches(input))
+    }
+}
+
+/// What the rules get to look at for a notification
+pub struct RuleInput<'a> {
+    pub kind: NotificationKind,
+    /// The sender's hops in the web of trust, `None` if they aren't in it
+    /// or we don't know who sent it
+    pub hops: Option<u8>,
+    pub content: &'a str,
+    pub zap_msats: Option<u64>,
+}
+
+/// The action of the first rule that matches
+pub fn evaluate(rules: &[NotificationRule], input: &RuleInput) -> Option<RuleAction> {
+    rules
+        .iter()
+        .find(|rule| rule.matches(input))
+        .map(|rule| rule.action)
+}
+
+pub fn to_json(rules: &[NotificationRule]) -> String {
+    serde_json::to_string_pretty(rules).unwrap_or_default()
+}
+
+pub fn from_json(json: &str) -> Result<Vec<NotificationRule>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(kind: NotificationKind, hops: Option<u8>, content: &str) -> RuleInput<'_> {
+        RuleInput {
+            kind,
+            hops,
+            content,
+            zap_msats: None,
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = vec![
+            NotificationRule {
+                conditions: vec![Condition::Contains {
+                    text: "GM".to_owned(),
+                }],
+                action: RuleAction::Mute,
+                ..Default::default()
+            },
+            NotificationRule {
+                conditions: vec![
+                    Condition::Kind {
+                        kinds: vec![NotificationKind::Mention],
+                    },
+                    Condition::WithinWot { hops: 1 },
+                ],
+                action: RuleAction::Notify,
+                ..Default::default()
+            },
+            NotificationRule {
+                conditions: vec![Condition::Kind {
+                    kinds: vec![NotificationKind::Mention],
+                }],
+                action: RuleAction::Mute,
+                ..Default::default()
+            },
+            NotificationRule {
+                conditions: vec![Condition::ZapAtLeast { sats: 1000 }],
+                action: RuleAction::Notify,
+                ..Default::default()
+            },
+        ];
+
+        let mention =
+            |hops, content| evaluate(&rules, &input(NotificationKind::Mention, hops, content));
+        assert_eq!(mention(Some(1), "hello"), Some(RuleAction::Notify));
+        assert_eq!(mention(Some(2), "hello"), Some(RuleAction::Mute));
+        assert_eq!(mention(None, "hello"), Some(RuleAction::Mute));
+        assert_eq!(mention(Some(1), "gm friends"), Some(RuleAction::Mute));
+        assert_eq!(
+            evaluate(&rules, &input(NotificationKind::Reply, None, "hi")),
+            None
+        );
+
+        let mut zap = input(NotificationKind::Zap, None, "");
+        zap.zap_msats = Some(999_000);
+        assert_eq!(evaluate(&rules, &zap), None);
+        zap.zap_msats = Some(1_000_000);
+        assert_eq!(evaluate(&rules, &zap), Some(RuleAction::Notify));
+
+        let round_trip = from_json(&to_json(&rules)).unwrap();
+        assert_eq!(round_trip, rules);
+    }
+}
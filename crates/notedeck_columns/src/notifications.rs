@@ -0,0 +1,658 @@
+use chrono::{Local, Timelike};
+use enostr::{ClientMessage, NoteId, Pubkey, RelayPool};
+use nostrdb::{Filter, Ndb, Note, NoteReply, Subscription, Transaction};
+use notedeck::{contacts, time::unix_now, tr, Accounts, DataPath, WebOfTrust};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::notification_rules::{self, NotificationRule, RuleAction, RuleInput};
+use crate::profile::get_display_name;
+use crate::storage;
+
+/// Longest note preview we put in a notification body
+const MAX_BODY_CHARS: usize = 140;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    Mention,
+    Reply,
+    DirectMessage,
+    Zap,
+}
+
+/// A range of local hours where we don't pop up notifications. The end
+/// is exclusive and the range can wrap past midnight.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: u8,
+    pub end: u8,
+}
+
+impl Default for QuietHours {
+    fn default() -> Self {
+        QuietHours { start: 22, end: 8 }
+    }
+}
+
+impl QuietHours {
+    pub fn contains(&self, hour: u8) -> bool {
+        if self.start <= self.end {
+            hour >= self.start && hour < self.end
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+/// Overrides for one thread
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadNotify {
+    /// Nothing from this thread, even when we're tagged
+    Mute,
+    /// Every reply, even when we aren't tagged
+    Watch,
+}
+
+/// Threads an account muted or is watching, by their root note. Kept
+/// per account.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThreadNotifications {
+    threads: HashMap<NoteId, ThreadNotify>,
+}
+
+impl ThreadNotifications {
+    pub fn get(&self, root: &NoteId) -> Option<ThreadNotify> {
+        self.threads.get(root).copied()
+    }
+
+    /// `None` goes back to the usual settings. Returns true if anything
+    /// changed.
+    pub fn set(&mut self, root: NoteId, notify: Option<ThreadNotify>) -> bool {
+        let old = match notify {
+            Some(notify) => self.threads.insert(root, notify),
+            None => self.threads.remove(&root),
+        };
+        old != notify
+    }
+
+    /// Roots of the threads being watched, in a stable order
+    pub fn watched(&self) -> Vec<NoteId> {
+        let mut watched: Vec<NoteId> = self
+            .threads
+            .iter()
+            .filter(|(_, notify)| **notify == ThreadNotify::Watch)
+            .map(|(root, _)| *root)
+            .collect();
+        watched.sort_by_key(|id| *id.bytes());
+        watched
+    }
+}
+
+/// The root of the thread a note is in, or the note itself if it
+/// isn't a reply
+pub fn thread_root(note: &Note) -> NoteId {
+    NoteId::new(
+        NoteReply::new(note.tags())
+            .root()
+            .map_or(*note.id(), |root| *root.id),
+    )
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub mentions: bool,
+    pub replies: bool,
+    pub direct_messages: bool,
+    pub zaps: bool,
+    /// Only notify about mentions, replies and DMs from people we follow
+    pub only_follows: bool,
+    pub quiet_hours: Option<QuietHours>,
+    /// Keep listening for notifications while the app is in the
+    /// background. Only used on android.
+    pub background: bool,
+    /// Checked before everything above except `enabled`, see
+    /// [`notification_rules`]
+    pub rules: Vec<NotificationRule>,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            enabled: true,
+            mentions: true,
+            replies: true,
+            direct_messages: true,
+            zaps: true,
+            only_follows: true,
+            quiet_hours: None,
+            background: false,
+            rules: vec![],
+        }
+    }
+}
+
+impl NotificationSettings {
+    pub fn wants(&self, kind: NotificationKind) -> bool {
+        self.enabled
+            && match kind {
+                NotificationKind::Mention => self.mentions,
+                NotificationKind::Reply => self.replies,
+                NotificationKind::DirectMessage => self.direct_messages,
+                NotificationKind::Zap => self.zaps,
+            }
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        self.quiet_hours
+            .is_some_and(|quiet| quiet.contains(Local::now().hour() as u8))
+    }
+
+    /// Build the notification for a note, if it's something these
+    /// settings want to hear about
+    pub fn notification(
+        &self,
+        ndb: &Ndb,
+        txn: &Transaction,
+        note: &Note,
+        us: &Pubkey,
+        wot: &WebOfTrust,
+        threads: &ThreadNotifications,
+    ) -> Option<DesktopNotification> {
+        if !self.enabled {
+            return None;
+        }
+
+        let root = thread_root(note);
+        let thread = threads.get(&root);
+        if thread == Some(ThreadNotify::Mute) {
+            debug!("notifier: skipping a note in a muted thread");
+            return None;
+        }
+
+        let watching = thread == Some(ThreadNotify::Watch);
+        let kind = classify(note, us.bytes()).or_else(|| {
+            let reply = note.kind() == 1 && root.bytes() != note.id();
+            (watching && reply && note.pubkey() != us.bytes()).then_some(NotificationKind::Reply)
+        })?;
+        let sender = if kind == NotificationKind::Zap {
+            zap_sender(note)
+        } else {
+            Some(*note.pubkey())
+        };
+
+        let input = RuleInput {
+            kind,
+            hops: sender.and_then(|pk| wot.hops(&pk)),
+            // DMs are encrypted, there's nothing to match
+            content: if kind == NotificationKind::DirectMessage {
+                ""
+            } else {
+                note.content()
+            },
+            zap_msats: if kind == NotificationKind::Zap {
+                zap_msats(note)
+            } else {
+                None
+            },
+        };
+
+        match notification_rules::evaluate(&self.rules, &input) {
+            Some(RuleAction::Mute) => {
+                debug!("notifier: a rule muted {:?}", kind);
+                return None;
+            }
+            Some(RuleAction::Notify) => {}
+            None => {
+                if !self.wants(kind) || self.is_quiet() {
+                    return None;
+                }
+
+                // watching a thread means wanting to hear from anyone in it
+                let spammable = kind != NotificationKind::Zap && !watching;
+                if spammable && self.only_follows {
+                    let sender = sender?;
+                    if !contacts::follows(ndb, txn, us.bytes(), &sender).unwrap_or(false) {
+                        debug!("notifier: skipping {:?} from someone we don't follow", kind);
+                        return None;
+                    }
+                }
+            }
+        }
+
+        let profile = sender.and_then(|pk| ndb.get_profile_by_pubkey(txn, &pk).ok());
+        let name = if sender.is_some() {
+            get_display_name(profile.as_ref()).name().to_owned()
+        } else {
+            tr!("notification-someone")
+        };
+
+        let (title, body) = match kind {
+            NotificationKind::Mention => (
+                tr!("notification-mentioned", name = name),
+                preview(note.content()),
+            ),
+            NotificationKind::Reply => (
+                tr!("notification-replied", name = name),
+                preview(note.content()),
+            ),
+            NotificationKind::DirectMessage => (
+                tr!("notification-direct-message", name = name),
+                String::new(),
+            ),
+            NotificationKind::Zap => (
+                tr!("notification-zapped", name = name),
+                input
+                    .zap_msats
+                    .map(notedeck::amount::format_msats)
+                    .unwrap_or_default(),
+            ),
+        };
+
+        Some(DesktopNotification {
+            title,
+            body,
+            target: click_target(note, kind),
+        })
+    }
+}
+
+/// Everything that can notify `pubkey`, from `since` onwards, including
+/// replies in threads they're watching
+pub fn filters(pubkey: &Pubkey, since: u64, threads: &ThreadNotifications) -> Vec<Filter> {
+    let mut filters = vec![Filter::new()
+        .pubkeys([pubkey.bytes()])
+        .kinds([1, 4, 9735])
+        .since(since)
+        .build()];
+
+    for root in threads.watched() {
+        filters.push(
+            Filter::new()
+                .kinds([1])
+                .event(root.bytes())
+                .since(since)
+                .build(),
+        );
+    }
+
+    filters
+}
+
+/// Figure out if a note is something we should tell `our_pubkey` about
+pub fn classify(note: &Note, our_pubkey: &[u8; 32]) -> Option<NotificationKind> {
+    if note.pubkey() == our_pubkey {
+        return None;
+    }
+
+    let mut tags_us = false;
+    let mut has_e = false;
+
+    for tag in note.tags() {
+        if tag.count() < 2 {
+            continue;
+        }
+
+        match tag.get_unchecked(0).variant().str() {
+            Some("p") => {
+                if tag.get_unchecked(1).variant().id() == Some(our_pubkey) {
+                    tags_us = true;
+                }
+            }
+            Some("e") => has_e = true,
+            _ => {}
+        }
+    }
+
+    if !tags_us {
+        return None;
+    }
+
+    match note.kind() {
+        1 if has_e => Some(NotificationKind::Reply),
+        1 => Some(NotificationKind::Mention),
+        4 => Some(NotificationKind::DirectMessage),
+        9735 => Some(NotificationKind::Zap),
+        _ => None,
+    }
+}
+
+fn tag_value<'a>(note: &'a Note, name: &str) -> Option<&'a str> {
+    note.tags()
+        .iter()
+        .filter(|tag| tag.count() >= 2)
+        .find(|tag| tag.get_unchecked(0).variant().str() == Some(name))?
+        .get_unchecked(1)
+        .variant()
+        .str()
+}
+
+/// The zap request embedded in a zap receipt
+fn zap_request(note: &Note) -> Option<serde_json::Value> {
+    serde_json::from_str(tag_value(note, "description")?).ok()
+}
+
+/// The pubkey of whoever actually sent a zap. Zap receipts are signed by
+/// the lightning service, the sender is in the embedded zap request.
+fn zap_sender(note: &Note) -> Option<[u8; 32]> {
+    let request = zap_request(note)?;
+    Pubkey::from_hex(request.get("pubkey")?.as_str()?)
+        .ok()
+        .map(|pk| *pk.bytes())
+}
+
+/// How much was zapped. The invoice is what was actually paid, the zap
+/// request's amount is only what the sender asked for.
+fn zap_msats(note: &Note) -> Option<u64> {
+    if let Some(msats) = tag_value(note, "bolt11").and_then(notedeck::amount::bolt11_msats) {
+        return Some(msats);
+    }
+
+    zap_request(note)?
+        .get("tags")?
+        .as_array()?
+        .iter()
+        .filter_map(|tag| tag.as_array())
+        .find(|tag| tag.first().and_then(|t| t.as_str()) == Some("amount"))?
+        .get(1)?
+        .as_str()?
+        .parse()
+        .ok()
+}
+
+/// The note a notification should open when clicked
+fn click_target(note: &Note, kind: NotificationKind) -> Option<NoteId> {
+    match kind {
+        NotificationKind::Mention | NotificationKind::Reply => Some(NoteId::new(*note.id())),
+        NotificationKind::Zap => note
+            .tags()
+            .iter()
+            .filter(|tag| tag.count() >= 2)
+            .find(|tag| tag.get_unchecked(0).variant().str() == Some("e"))
+            .and_then(|tag| tag.get_unchecked(1).variant().id())
+            .map(|id| NoteId::new(*id)),
+        // we don't have a DM view yet
+        NotificationKind::DirectMessage => None,
+    }
+}
+
+fn preview(content: &str) -> String {
+    let mut preview: String = content.chars().take(MAX_BODY_CHARS).collect();
+    if content.chars().count() > MAX_BODY_CHARS {
+        preview.push('…');
+    }
+    preview
+}
+
+pub struct DesktopNotification {
+    pub title: String,
+    pub body: String,
+    pub target: Option<NoteId>,
+}
+
+/// Watches for mentions, replies, DMs and zaps for the selected account
+/// and pops up desktop notifications for them while the window isn't
+/// focused.
+pub struct Notifier {
+    pub settings: NotificationSettings,
+    /// Muted and watched threads of the selected account
+    pub threads: ThreadNotifications,
+    account: Option<Pubkey>,
+    /// The watched threads changed, so the subscription needs redoing
+    stale: bool,
+    sub: Option<Subscription>,
+    subid: String,
+    clicks_tx: Sender<NoteId>,
+    clicks: Receiver<NoteId>,
+}
+
+impl Notifier {
+    pub fn new(settings: NotificationSettings) -> Self {
+        let (clicks_tx, clicks) = mpsc::channel();
+        Notifier {
+            settings,
+            threads: ThreadNotifications::default(),
+            account: None,
+            stale: false,
+            sub: None,
+            subid: Uuid::new_v4().to_string(),
+            clicks_tx,
+            clicks,
+        }
+    }
+
+    /// Ask a newly connected relay for our notifications
+    pub fn send_initial_filter(&self, pool: &mut RelayPool, relay_url: &str) {
+        if let Some(pubkey) = &self.account {
+            pool.send_to(
+                &ClientMessage::req(
+                    self.subid.clone(),
+                    filters(pubkey, unix_now(), &self.threads),
+                ),
+                relay_url,
+            );
+        }
+    }
+
+    fn resubscribe(&mut self, ndb: &Ndb, pool: &mut RelayPool, account: Option<Pubkey>) {
+        if let Some(sub) = self.sub.take() {
+            if let Err(err) = ndb.unsubscribe(sub) {
+                error!("notifier: unsubscribe failed: {err}");
+            }
+            pool.unsubscribe(self.subid.clone());
+        }
+
+        self.account = account;
+        let Some(pubkey) = &self.account else {
+            return;
+        };
+
+        let filters = filters(pubkey, unix_now(), &self.threads);
+        match ndb.subscribe(&filters) {
+            Ok(sub) => self.sub = Some(sub),
+            Err(err) => error!("notifier: subscribe failed: {err}"),
+        }
+        pool.subscribe(self.subid.clone(), filters);
+    }
+
+    /// Mute or watch a thread for the selected account
+    pub fn set_thread(&mut self, path: &DataPath, root: NoteId, notify: Option<ThreadNotify>) {
+        let Some(account) = self.account else {
+            return;
+        };
+
+        if self.threads.set(root, notify) {
+            storage::save_thread_notifications(path, &account, &self.threads);
+            self.stale = true;
+        }
+    }
+
+    /// Poll for new notifications and show them. Returns a note the user
+    /// clicked on in a notification, if any.
+    pub fn update(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        path: &DataPath,
+        accounts: &Accounts,
+        wot: &WebOfTrust,
+        ctx: &egui::Context,
+    ) -> Option<NoteId> {
+        let selected = accounts.get_selected_account().map(|acc| acc.pubkey);
+        if selected != self.account {
+            self.threads = selected
+                .map(|account| storage::load_thread_notifications(path, &account))
+                .unwrap_or_default();
+            self.resubscribe(ndb, pool, selected);
+        } else if self.stale {
+            self.resubscribe(ndb, pool, selected);
+        }
+        self.stale = false;
+
+        if let (Some(sub), Some(pubkey)) = (self.sub, self.account) {
+            let note_keys = ndb.poll_for_notes(sub, 50);
+            if !note_keys.is_empty() && !ctx.input(|i| i.focused) {
+                let txn = Transaction::new(ndb).expect("txn");
+                for key in note_keys {
+                    let Ok(note) = ndb.get_note_by_key(&txn, key) else {
+                        continue;
+                    };
+
+                    if let Some(notification) =
+                        self.settings
+                            .notification(ndb, &txn, &note, &pubkey, wot, &self.threads)
+                    {
+                        show(notification, Some((self.clicks_tx.clone(), ctx.clone())));
+                    }
+                }
+            }
+        }
+
+        self.clicks.try_recv().ok()
+    }
+}
+
+/// Showing a notification can block on dbus, and waiting for a click
+/// always does, so this happens on its own thread. Clicks on notes are
+/// sent to `clicks` when it's given, waking up its context.
+#[cfg(not(target_os = "android"))]
+pub fn show(notification: DesktopNotification, clicks: Option<(Sender<NoteId>, egui::Context)>) {
+    let spawned = std::thread::Builder::new()
+        .name("notification".to_owned())
+        .spawn(move || {
+            let mut desktop = notify_rust::Notification::new();
+            desktop
+                .appname("Notedeck")
+                .summary(&notification.title)
+                .body(&notification.body);
+
+            #[cfg(all(unix, not(target_os = "macos")))]
+            if notification.target.is_some() && clicks.is_some() {
+                desktop.action("default", "Open");
+            }
+
+            let handle = match desktop.show() {
+                Ok(handle) => handle,
+                Err(err) => {
+                    error!("failed to show notification: {err}");
+                    return;
+                }
+            };
+
+            // only the freedesktop backend tells us about clicks
+            #[cfg(all(unix, not(target_os = "macos")))]
+            if let (Some(target), Some((clicks, ctx))) = (notification.target, clicks) {
+                handle.wait_for_action(|action| {
+                    if action == "default" {
+                        let _ = clicks.send(target);
+                        ctx.request_repaint();
+                    }
+                });
+            }
+
+            #[cfg(not(all(unix, not(target_os = "macos"))))]
+            let _ = (handle, clicks);
+        });
+
+    if let Err(err) = spawned {
+        error!("failed to spawn notification thread: {err}");
+    }
+}
+
+#[cfg(target_os = "android")]
+pub fn show(_notification: DesktopNotification, _clicks: Option<(Sender<NoteId>, egui::Context)>) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+    use nostrdb::NoteBuilder;
+
+    fn note_to<'a>(kind: u32, us: &Pubkey, reply: bool, seckey: &[u8; 32]) -> Note<'a> {
+        let mut builder = NoteBuilder::new()
+            .kind(kind)
+            .content("hi")
+            .start_tag()
+            .tag_str("p")
+            .tag_str(&us.hex());
+
+        if reply {
+            builder = builder.start_tag().tag_str("e").tag_str(&"00".repeat(32));
+        }
+
+        builder.sign(seckey).build().expect("note")
+    }
+
+    #[test]
+    fn test_classify() {
+        let us = FullKeypair::generate();
+        let them = FullKeypair::generate();
+        let seckey = them.secret_key.to_secret_bytes();
+
+        let mention = note_to(1, &us.pubkey, false, &seckey);
+        let reply = note_to(1, &us.pubkey, true, &seckey);
+        let dm = note_to(4, &us.pubkey, false, &seckey);
+        let other = note_to(1, &them.pubkey, false, &seckey);
+
+        assert_eq!(
+            classify(&mention, us.pubkey.bytes()),
+            Some(NotificationKind::Mention)
+        );
+        assert_eq!(
+            classify(&reply, us.pubkey.bytes()),
+            Some(NotificationKind::Reply)
+        );
+        assert_eq!(
+            classify(&dm, us.pubkey.bytes()),
+            Some(NotificationKind::DirectMessage)
+        );
+        assert_eq!(classify(&other, us.pubkey.bytes()), None);
+
+        // our own notes never notify us
+        assert_eq!(classify(&mention, them.pubkey.bytes()), None);
+    }
+
+    #[test]
+    fn test_thread_notifications() {
+        let us = FullKeypair::generate();
+        let seckey = FullKeypair::generate().secret_key.to_secret_bytes();
+        let root = NoteId::new([0; 32]);
+
+        let reply = note_to(1, &us.pubkey, true, &seckey);
+        let mention = note_to(1, &us.pubkey, false, &seckey);
+        assert_eq!(thread_root(&reply), root);
+        assert_eq!(thread_root(&mention), NoteId::new(*mention.id()));
+
+        let mut threads = ThreadNotifications::default();
+        assert!(threads.set(root, Some(ThreadNotify::Watch)));
+        assert!(!threads.set(root, Some(ThreadNotify::Watch)));
+        assert_eq!(threads.watched(), vec![root]);
+
+        assert!(threads.set(root, Some(ThreadNotify::Mute)));
+        assert!(threads.watched().is_empty());
+        assert_eq!(threads.get(&root), Some(ThreadNotify::Mute));
+
+        assert!(threads.set(root, None));
+        assert_eq!(threads.get(&root), None);
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let overnight = QuietHours { start: 22, end: 8 };
+        assert!(overnight.contains(23));
+        assert!(overnight.contains(3));
+        assert!(!overnight.contains(8));
+        assert!(!overnight.contains(12));
+
+        let afternoon = QuietHours { start: 13, end: 15 };
+        assert!(afternoon.contains(14));
+        assert!(!afternoon.contains(15));
+    }
+}
@@ -0,0 +1,600 @@
+use enostr::{FullKeypair, Keypair, Pubkey};
+use nostrdb::{Ndb, Note, NoteBuilder, Transaction};
+use notedeck::{AccountsAction, AppContext, FollowPack};
+use tracing::{error, info};
+
+use crate::{
+    column::Columns,
+    decks::Deck,
+    follow_packs,
+    login_manager::AcquireKeyState,
+    timeline::{self, PubkeySource, Timeline, TimelineKind},
+    Damus,
+};
+
+/// Relays we suggest to people creating a brand new account
+const STARTER_RELAYS: [(&str, bool); 5] = [
+    ("wss://relay.damus.io", true),
+    ("wss://nos.lol", true),
+    ("wss://purplepag.es", true),
+    ("wss://relay.primal.net", false),
+    ("wss://nostr.wine", false),
+];
+
+/// How many packs we suggest for the picked interests
+const SUGGESTED_PACKS: usize = 8;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OnboardingStep {
+    Welcome,
+    CreateKey,
+    BackupKey,
+    Login,
+    Relays,
+    /// New accounts pick topics and follow packs about them, so their home
+    /// timeline isn't empty
+    Interests,
+    Columns,
+}
+
+pub struct StarterRelay {
+    pub url: String,
+    pub selected: bool,
+}
+
+/// The columns the first deck will be created with
+pub struct StarterColumns {
+    pub home: bool,
+    pub notifications: bool,
+    /// The account's own notes and replies
+    pub posts: bool,
+    pub universe: bool,
+    /// Space separated hashtags, each gets its own column
+    pub hashtags: String,
+}
+
+impl Default for StarterColumns {
+    fn default() -> Self {
+        StarterColumns {
+            home: true,
+            notifications: true,
+            posts: true,
+            universe: false,
+            hashtags: "introductions".to_owned(),
+        }
+    }
+}
+
+impl StarterColumns {
+    /// For accounts logging in without going through onboarding, who
+    /// already know what to follow
+    pub fn login() -> Self {
+        StarterColumns {
+            hashtags: String::new(),
+            ..Default::default()
+        }
+    }
+
+    fn hashtags(&self) -> impl Iterator<Item = String> + '_ {
+        self.hashtags
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .map(|tag| tag.trim_start_matches('#').to_lowercase())
+            .filter(|tag| !tag.is_empty())
+    }
+}
+
+/// State for the first-run flow
+pub struct Onboarding {
+    pub step: OnboardingStep,
+    pub login: AcquireKeyState,
+    pub new_key: Option<FullKeypair>,
+    /// The NIP-06 recovery phrase the new key was derived from
+    pub mnemonic: Option<String>,
+    /// What was typed for each word [`Onboarding::backup_positions`] asks for
+    pub backup_confirmation: Vec<String>,
+    pub keypair: Option<Keypair>,
+    pub relays: Vec<StarterRelay>,
+    pub custom_relay: String,
+    pub interests: Vec<&'static str>,
+    /// Packs whose members the new account will follow
+    pub packs: Vec<FollowPack>,
+    pub columns: StarterColumns,
+}
+
+impl Default for Onboarding {
+    fn default() -> Self {
+        Onboarding {
+            step: OnboardingStep::Welcome,
+            login: AcquireKeyState::new(),
+            new_key: None,
+            mnemonic: None,
+            backup_confirmation: vec![],
+            keypair: None,
+            relays: STARTER_RELAYS
+                .iter()
+                .map(|(url, selected)| StarterRelay {
+                    url: url.to_string(),
+                    selected: *selected,
+                })
+                .collect(),
+            custom_relay: String::new(),
+            interests: vec![],
+            packs: vec![],
+            columns: StarterColumns::default(),
+        }
+    }
+}
+
+/// How many words of the recovery phrase we ask people to type back to
+/// show they've written it down
+pub const BACKUP_CHECK_WORDS: usize = 3;
+
+impl Onboarding {
+    pub fn create_key(&mut self) {
+        if self.new_key.is_none() {
+            let (keypair, mnemonic) = FullKeypair::generate_with_mnemonic();
+            self.new_key = Some(keypair);
+            self.mnemonic = Some(mnemonic);
+        }
+        self.backup_confirmation = vec![String::new(); BACKUP_CHECK_WORDS];
+        self.step = OnboardingStep::CreateKey;
+    }
+
+    pub fn mnemonic_words(&self) -> Vec<&str> {
+        self.mnemonic
+            .as_deref()
+            .map(|phrase| phrase.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    /// Which words of the phrase to ask for, counting from zero. Picked
+    /// from the key so they stay put while going back and forth.
+    pub fn backup_positions(&self) -> Vec<usize> {
+        let words = self.mnemonic_words().len();
+        let Some(key) = &self.new_key else {
+            return vec![];
+        };
+        if words < BACKUP_CHECK_WORDS {
+            return vec![];
+        }
+
+        let mut positions = vec![];
+        for byte in key.pubkey.bytes() {
+            let position = *byte as usize % words;
+            if !positions.contains(&position) {
+                positions.push(position);
+            }
+            if positions.len() == BACKUP_CHECK_WORDS {
+                break;
+            }
+        }
+        // a key whose bytes keep landing on the same few words
+        let mut next = 0;
+        while positions.len() < BACKUP_CHECK_WORDS {
+            if !positions.contains(&next) {
+                positions.push(next);
+            }
+            next += 1;
+        }
+        positions.sort_unstable();
+        positions
+    }
+
+    pub fn backup_confirmed(&self) -> bool {
+        let words = self.mnemonic_words();
+        let positions = self.backup_positions();
+        !positions.is_empty()
+            && positions.len() == self.backup_confirmation.len()
+            && positions
+                .iter()
+                .zip(&self.backup_confirmation)
+                .all(|(position, typed)| typed.trim().eq_ignore_ascii_case(words[*position]))
+    }
+
+    pub fn login_with(&mut self, keypair: Keypair) {
+        self.new_key = None;
+        self.mnemonic = None;
+        self.keypair = Some(keypair);
+        self.step = OnboardingStep::Columns;
+    }
+
+    pub fn add_custom_relay(&mut self) {
+        let url = self.custom_relay.trim();
+        if url.is_empty() {
+            return;
+        }
+
+        let url = if url.starts_with("wss://") || url.starts_with("ws://") {
+            url.to_owned()
+        } else {
+            format!("wss://{url}")
+        };
+
+        if !self.relays.iter().any(|relay| relay.url == url) {
+            self.relays.push(StarterRelay {
+                url,
+                selected: true,
+            });
+        }
+
+        self.custom_relay.clear();
+    }
+
+    /// The step the back button should go to
+    pub fn prev_step(&self) -> OnboardingStep {
+        match self.step {
+            OnboardingStep::Welcome => OnboardingStep::Welcome,
+            OnboardingStep::CreateKey | OnboardingStep::Login => OnboardingStep::Welcome,
+            OnboardingStep::BackupKey => OnboardingStep::CreateKey,
+            OnboardingStep::Relays => OnboardingStep::BackupKey,
+            OnboardingStep::Interests => OnboardingStep::Relays,
+            OnboardingStep::Columns => {
+                if self.new_key.is_some() {
+                    OnboardingStep::Interests
+                } else {
+                    OnboardingStep::Login
+                }
+            }
+        }
+    }
+
+    pub fn toggle_interest(&mut self, interest: &'static str) {
+        if let Some(i) = self.interests.iter().position(|picked| *picked == interest) {
+            self.interests.remove(i);
+        } else {
+            self.interests.push(interest);
+        }
+
+        // packs for topics they changed their mind about go too
+        let hashtags = self.hashtags();
+        self.packs.retain(|pack| pack.is_about(&hashtags));
+    }
+
+    fn hashtags(&self) -> Vec<&'static str> {
+        self.interests
+            .iter()
+            .flat_map(|interest| follow_packs::interest_hashtags(interest))
+            .copied()
+            .collect()
+    }
+
+    /// The packs about the picked interests we suggest, biggest first
+    pub fn suggested<'p>(&self, packs: &'p [FollowPack]) -> Vec<&'p FollowPack> {
+        if self.interests.is_empty() {
+            return vec![];
+        }
+
+        let hashtags = self.hashtags();
+        packs
+            .iter()
+            .filter(|pack| pack.is_about(&hashtags))
+            .take(SUGGESTED_PACKS)
+            .collect()
+    }
+
+    pub fn is_chosen(&self, pack: &FollowPack) -> bool {
+        self.packs.iter().any(|chosen| same_pack(chosen, pack))
+    }
+
+    pub fn choose(&mut self, pack: &FollowPack, chosen: bool) {
+        self.packs.retain(|other| !same_pack(other, pack));
+        if chosen {
+            self.packs.push(pack.clone());
+        }
+    }
+
+    /// Everyone in the chosen packs, once each
+    pub fn follows(&self) -> Vec<[u8; 32]> {
+        let mut follows: Vec<[u8; 32]> = vec![];
+        for member in self.packs.iter().flat_map(|pack| &pack.members) {
+            if !follows.contains(member) {
+                follows.push(*member);
+            }
+        }
+        follows
+    }
+
+    pub fn finish(&mut self) -> Option<OnboardingResult> {
+        if let Some(new_key) = self.new_key.take() {
+            self.mnemonic = None;
+            let relays = self
+                .relays
+                .iter()
+                .filter(|relay| relay.selected)
+                .map(|relay| relay.url.clone())
+                .collect();
+
+            return Some(OnboardingResult {
+                keypair: new_key.clone().to_keypair(),
+                new_key: Some(new_key),
+                relays,
+                follows: self.follows(),
+                columns: std::mem::take(&mut self.columns),
+            });
+        }
+
+        Some(OnboardingResult {
+            keypair: self.keypair.take()?,
+            new_key: None,
+            relays: vec![],
+            follows: vec![],
+            columns: std::mem::take(&mut self.columns),
+        })
+    }
+}
+
+pub struct OnboardingResult {
+    pub keypair: Keypair,
+    /// Set when we created the key during onboarding, as opposed to
+    /// logging in with an existing one
+    pub new_key: Option<FullKeypair>,
+    pub relays: Vec<String>,
+    /// Who a new account starts out following, besides itself
+    pub follows: Vec<[u8; 32]>,
+    pub columns: StarterColumns,
+}
+
+impl OnboardingResult {
+    /// Add the account, queue the starter lists for new accounts and
+    /// build the first deck
+    pub fn apply(self, damus: &mut Damus, ctx: &mut AppContext<'_>) {
+        let pubkey = self.keypair.pubkey;
+
+        let txn = Transaction::new(ctx.ndb).expect("txn");
+        let mut action = ctx.accounts.add_account(self.keypair);
+        action.process_action(ctx.unknown_ids, ctx.ndb, &txn);
+        if let Some(AccountsAction::Switch(switch)) = action.accounts_action {
+            ctx.accounts.select_account(switch.switch_to);
+        }
+
+        if let Some(new_key) = &self.new_key {
+            damus.starter_lists = StarterLists::new(new_key, &self.relays, &self.follows);
+        }
+
+        let deck = starter_deck(damus, ctx, &pubkey, &self.columns);
+        damus.decks_cache.add_deck(pubkey, deck);
+
+        info!("onboarding finished for {}", pubkey);
+        crate::storage::save_decks_cache(ctx.path, &damus.decks_cache);
+    }
+}
+
+/// A deck with the starter columns, subscribed and ready to show
+pub fn starter_deck(
+    damus: &mut Damus,
+    ctx: &mut AppContext<'_>,
+    pubkey: &Pubkey,
+    starter: &StarterColumns,
+) -> Deck {
+    let mut columns = Columns::new();
+    for mut timeline in starter_timelines(ctx.ndb, pubkey, starter) {
+        timeline::setup_new_timeline(
+            &mut timeline,
+            ctx.ndb,
+            &mut damus.subscriptions,
+            ctx.pool,
+            ctx.note_cache,
+            damus.since_optimize,
+            Some(pubkey),
+        );
+        columns.add_new_timeline_column(timeline);
+    }
+
+    my_deck(columns)
+}
+
+/// A deck with the starter columns for before the first frame, when every
+/// deck's subscriptions are set up together
+pub fn unsubscribed_starter_deck(ndb: &Ndb, pubkey: &Pubkey, starter: &StarterColumns) -> Deck {
+    let mut columns = Columns::new();
+    for timeline in starter_timelines(ndb, pubkey, starter) {
+        columns.add_new_timeline_column(timeline);
+    }
+
+    my_deck(columns)
+}
+
+fn my_deck(columns: Columns) -> Deck {
+    Deck::new_with_columns(Deck::default().icon, "My Deck".to_owned(), columns)
+}
+
+/// The starter columns' timelines. Their subscriptions still have to be
+/// set up.
+pub fn starter_timelines(ndb: &Ndb, pubkey: &Pubkey, starter: &StarterColumns) -> Vec<Timeline> {
+    let mut kinds = vec![];
+    if starter.home {
+        kinds.push(TimelineKind::contact_list(PubkeySource::DeckAuthor));
+    }
+    if starter.notifications {
+        kinds.push(TimelineKind::notifications(PubkeySource::DeckAuthor));
+    }
+    if starter.posts {
+        kinds.push(TimelineKind::profile(PubkeySource::DeckAuthor));
+    }
+    if starter.universe {
+        kinds.push(TimelineKind::Universe);
+    }
+
+    let mut timelines: Vec<Timeline> = kinds
+        .into_iter()
+        .filter_map(|kind| kind.into_timeline(ndb, Some(pubkey.bytes())))
+        .collect();
+    timelines.extend(starter.hashtags().map(Timeline::hashtag));
+    timelines
+}
+
+/// A brand new account has no relay list or contact list. These are a
+/// NIP-65 relay list with the relays they picked and a contact list that
+/// follows themselves, and anyone from the packs they chose, so their home
+/// timeline has something to load.
+///
+/// They wait here until Columns may publish for the new account. On a
+/// fresh install nobody has answered that yet, so the first try asks.
+#[derive(Default)]
+pub struct StarterLists {
+    notes: Vec<Note<'static>>,
+}
+
+impl StarterLists {
+    pub fn new(kp: &FullKeypair, relays: &[String], follows: &[[u8; 32]]) -> Self {
+        let seckey = kp.secret_key.to_secret_bytes();
+
+        let mut relay_list = NoteBuilder::new().kind(10002).content("");
+        for relay in relays {
+            relay_list = relay_list.start_tag().tag_str("r").tag_str(relay);
+        }
+
+        let mut contact_list = NoteBuilder::new()
+            .kind(3)
+            .content("")
+            .start_tag()
+            .tag_str("p")
+            .tag_str(&kp.pubkey.hex());
+        for pubkey in follows.iter().filter(|pk| *pk != kp.pubkey.bytes()) {
+            contact_list = contact_list
+                .start_tag()
+                .tag_str("p")
+                .tag_str(&hex::encode(pubkey));
+        }
+
+        let notes = [relay_list, contact_list]
+            .into_iter()
+            .filter_map(|builder| {
+                let note = builder.sign(&seckey).build();
+                if note.is_none() {
+                    error!("onboarding: failed to build starter list");
+                }
+                note
+            })
+            .collect();
+
+        StarterLists { notes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Publish whatever we're allowed to. Lists stay queued while the user
+    /// is being asked, or has said no, since they can still allow it in
+    /// settings.
+    pub fn send(&mut self, mut publish: impl FnMut(&Note) -> notedeck::Result<()>) {
+        self.notes.retain(|note| match publish(note) {
+            Ok(()) => false,
+            Err(notedeck::Error::Permission(_)) => true,
+            Err(err) => {
+                error!("onboarding: couldn't publish starter list: {err}");
+                false
+            }
+        });
+    }
+
+    /// Saved locally too, so our relay list and contacts are available
+    /// before the relays echo them back
+    pub fn update(&mut self, ctx: &mut AppContext<'_>) {
+        if self.is_empty() {
+            return;
+        }
+        let (accounts, ndb, pool) = (&*ctx.accounts, ctx.ndb, &mut *ctx.pool);
+        self.send(|note| accounts.publish_note(ndb, pool, note));
+    }
+}
+
+fn same_pack(a: &FollowPack, b: &FollowPack) -> bool {
+    a.author == b.author && a.identifier == b.identifier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notedeck::{Capability, DataPath, Permissions, Settings};
+
+    fn pack(identifier: &str, hashtag: &str, members: &[[u8; 32]]) -> FollowPack {
+        FollowPack {
+            identifier: identifier.to_owned(),
+            title: identifier.to_owned(),
+            members: members.to_vec(),
+            hashtags: vec![hashtag.to_owned()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_backup_confirmation() {
+        let mut onboarding = Onboarding::default();
+        onboarding.create_key();
+        assert_eq!(onboarding.mnemonic_words().len(), 12);
+
+        let positions = onboarding.backup_positions();
+        assert_eq!(positions.len(), BACKUP_CHECK_WORDS);
+        assert!(!onboarding.backup_confirmed());
+
+        let words: Vec<String> = onboarding
+            .mnemonic_words()
+            .into_iter()
+            .map(|word| word.to_uppercase())
+            .collect();
+        onboarding.backup_confirmation = positions.iter().map(|i| words[*i].clone()).collect();
+        assert!(onboarding.backup_confirmed());
+
+        onboarding.backup_confirmation[0] = "wrong".to_owned();
+        assert!(!onboarding.backup_confirmed());
+    }
+
+    fn send(lists: &mut StarterLists, permissions: &Permissions, sent: &mut Vec<u32>) {
+        lists.send(|note| {
+            permissions.check(Capability::Publish)?;
+            sent.push(note.kind());
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_starter_lists_wait_for_publish() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::new(&DataPath::new(tmp.path()));
+        let mut permissions = Permissions::default();
+        permissions.declare(&mut settings, "columns", "Columns", &[Capability::Publish]);
+        permissions.enter(&settings, Some("columns"));
+
+        let kp = FullKeypair::generate();
+        let relays = ["wss://relay.damus.io".to_owned()];
+        let mut lists = StarterLists::new(&kp, &relays, &[[1; 32]]);
+        let mut sent = vec![];
+
+        // a fresh install hasn't answered yet
+        send(&mut lists, &permissions, &mut sent);
+        assert!(sent.is_empty());
+        assert!(!lists.is_empty());
+        assert!(permissions.prompt().is_some());
+
+        permissions.answer(&mut settings, true);
+        send(&mut lists, &permissions, &mut sent);
+        assert_eq!(sent, vec![10002, 3]);
+        assert!(lists.is_empty());
+    }
+
+    #[test]
+    fn test_chosen_packs() {
+        let art = pack("art", "art", &[[1; 32], [2; 32]]);
+        let music = pack("music", "music", &[[2; 32], [3; 32]]);
+        let packs = [art.clone(), music.clone()];
+
+        let mut onboarding = Onboarding::default();
+        assert!(onboarding.suggested(&packs).is_empty());
+
+        onboarding.toggle_interest("Art");
+        onboarding.toggle_interest("Music");
+        assert_eq!(onboarding.suggested(&packs).len(), 2);
+
+        onboarding.choose(&art, true);
+        onboarding.choose(&music, true);
+        onboarding.choose(&music, true);
+        assert_eq!(onboarding.follows(), vec![[1; 32], [2; 32], [3; 32]]);
+
+        // dropping a topic drops its packs
+        onboarding.toggle_interest("Music");
+        assert!(!onboarding.is_chosen(&music));
+        assert_eq!(onboarding.follows(), vec![[1; 32], [2; 32]]);
+    }
+}
@@ -1,42 +1,126 @@
+use crate::geo::Place;
 use enostr::FullKeypair;
 use nostrdb::{Note, NoteBuilder, NoteReply};
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// What we put in the `client` tag of notes we publish
+pub const CLIENT_NAME: &str = "Damus Notedeck";
+
+static CLIENT_TAG: AtomicBool = AtomicBool::new(true);
+
+/// Whether new notes say they came from notedeck, from settings
+pub fn client_tag() -> bool {
+    CLIENT_TAG.load(Ordering::Relaxed)
+}
+
+pub fn set_client_tag(enabled: bool) {
+    CLIENT_TAG.store(enabled, Ordering::Relaxed);
+}
+
+/// Tag the note with our client name, unless the user turned that off
+pub fn add_client_tag(builder: NoteBuilder<'_>, enabled: bool) -> NoteBuilder<'_> {
+    if enabled {
+        builder.start_tag().tag_str("client").tag_str(CLIENT_NAME)
+    } else {
+        builder
+    }
+}
 
 pub struct NewPost {
     pub content: String,
     pub account: FullKeypair,
-}
-
-fn add_client_tag(builder: NoteBuilder<'_>) -> NoteBuilder<'_> {
-    builder
-        .start_tag()
-        .tag_str("client")
-        .tag_str("Damus Notedeck")
+    /// NIP-40 expiration timestamp
+    pub expiration: Option<u64>,
+    pub location: Option<Place>,
+    /// People a reply would mention, but who were taken off it
+    pub unmentioned: HashSet<[u8; 32]>,
+    pub client_tag: bool,
 }
 
 impl NewPost {
     pub fn new(content: String, account: FullKeypair) -> Self {
-        NewPost { content, account }
+        NewPost {
+            content,
+            account,
+            expiration: None,
+            location: None,
+            unmentioned: HashSet::new(),
+            client_tag: client_tag(),
+        }
+    }
+
+    pub fn expires_at(mut self, expiration: Option<u64>) -> Self {
+        self.expiration = expiration;
+        self
+    }
+
+    pub fn location(mut self, location: Option<Place>) -> Self {
+        self.location = location;
+        self
+    }
+
+    pub fn without_mentions(mut self, unmentioned: HashSet<[u8; 32]>) -> Self {
+        self.unmentioned = unmentioned;
+        self
+    }
+
+    pub fn with_client_tag(mut self, client_tag: bool) -> Self {
+        self.client_tag = client_tag;
+        self
+    }
+
+    fn add_expiration_tag<'a>(&self, builder: NoteBuilder<'a>) -> NoteBuilder<'a> {
+        if let Some(expiration) = self.expiration {
+            builder
+                .start_tag()
+                .tag_str("expiration")
+                .tag_str(&expiration.to_string())
+        } else {
+            builder
+        }
+    }
+
+    fn add_location_tags<'a>(&self, builder: NoteBuilder<'a>) -> NoteBuilder<'a> {
+        if let Some(place) = &self.location {
+            builder
+                .start_tag()
+                .tag_str("location")
+                .tag_str(&place.name)
+                .start_tag()
+                .tag_str("g")
+                .tag_str(&place.geohash())
+        } else {
+            builder
+        }
+    }
+
+    /// Tags that every kind of post gets from the composer's options
+    fn add_option_tags<'a>(&self, builder: NoteBuilder<'a>) -> NoteBuilder<'a> {
+        add_client_tag(
+            self.add_location_tags(self.add_expiration_tag(builder)),
+            self.client_tag,
+        )
     }
 
     pub fn to_note(&self, seckey: &[u8; 32]) -> Note {
-        let mut builder = add_client_tag(NoteBuilder::new())
-            .kind(1)
-            .content(&self.content);
+        let mut builder = NoteBuilder::new().kind(1).content(&self.content);
 
         for hashtag in Self::extract_hashtags(&self.content) {
             builder = builder.start_tag().tag_str("t").tag_str(&hashtag);
         }
 
-        builder.sign(seckey).build().expect("note should be ok")
+        self.add_option_tags(builder)
+            .sign(seckey)
+            .build()
+            .expect("note should be ok")
     }
 
     pub fn to_reply(&self, seckey: &[u8; 32], replying_to: &Note) -> Note {
-        let builder = add_client_tag(NoteBuilder::new())
-            .kind(1)
-            .content(&self.content);
+        let builder = NoteBuilder::new().kind(1).content(&self.content);
 
         let nip10 = NoteReply::new(replying_to.tags());
+        let author = hex::encode(replying_to.pubkey());
 
         let mut builder = if let Some(root) = nip10.root() {
             builder
@@ -50,6 +134,7 @@ impl NewPost {
                 .tag_str(&hex::encode(replying_to.id()))
                 .tag_str("")
                 .tag_str("reply")
+                .tag_str(&author)
                 .sign(seckey)
         } else {
             // we're replying to a post that isn't in a thread,
@@ -60,43 +145,18 @@ impl NewPost {
                 .tag_str(&hex::encode(replying_to.id()))
                 .tag_str("")
                 .tag_str("root")
+                .tag_str(&author)
                 .sign(seckey)
         };
 
-        let mut seen_p: HashSet<&[u8; 32]> = HashSet::new();
-
-        builder = builder
-            .start_tag()
-            .tag_str("p")
-            .tag_str(&hex::encode(replying_to.pubkey()));
-
-        seen_p.insert(replying_to.pubkey());
-
-        for tag in replying_to.tags() {
-            if tag.count() < 2 {
+        for pk in reply_mentions(replying_to) {
+            if self.unmentioned.contains(&pk) {
                 continue;
             }
-
-            if tag.get_unchecked(0).variant().str() != Some("p") {
-                continue;
-            }
-
-            let id = if let Some(id) = tag.get_unchecked(1).variant().id() {
-                id
-            } else {
-                continue;
-            };
-
-            if seen_p.contains(id) {
-                continue;
-            }
-
-            seen_p.insert(id);
-
-            builder = builder.start_tag().tag_str("p").tag_str(&hex::encode(id));
+            builder = builder.start_tag().tag_str("p").tag_str(&hex::encode(pk));
         }
 
-        builder
+        self.add_option_tags(builder)
             .sign(seckey)
             .build()
             .expect("expected build to work")
@@ -115,13 +175,15 @@ impl NewPost {
             builder = builder.start_tag().tag_str("t").tag_str(&hashtag);
         }
 
-        builder
+        builder = builder
             .start_tag()
             .tag_str("q")
             .tag_str(&hex::encode(quoting.id()))
             .start_tag()
             .tag_str("p")
-            .tag_str(&hex::encode(quoting.pubkey()))
+            .tag_str(&hex::encode(quoting.pubkey()));
+
+        self.add_option_tags(builder)
             .sign(seckey)
             .build()
             .expect("expected build to work")
@@ -143,6 +205,32 @@ impl NewPost {
     }
 }
 
+/// Who a reply to a note mentions unless told otherwise: the note's
+/// author, then everyone they mentioned
+pub fn reply_mentions(replying_to: &Note) -> Vec<[u8; 32]> {
+    let mut mentions = vec![*replying_to.pubkey()];
+
+    for tag in replying_to.tags() {
+        if tag.count() < 2 {
+            continue;
+        }
+
+        if tag.get_unchecked(0).variant().str() != Some("p") {
+            continue;
+        }
+
+        let Some(id) = tag.get_unchecked(1).variant().id() else {
+            continue;
+        };
+
+        if !mentions.contains(id) {
+            mentions.push(*id);
+        }
+    }
+
+    mentions
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,4 +262,73 @@ mod tests {
             assert_eq!(result, expected, "Failed for input: {}", input);
         }
     }
+
+    #[test]
+    fn test_expiration_tag() {
+        let post = NewPost::new("expiring".to_owned(), FullKeypair::generate())
+            .expires_at(Some(1_700_000_000));
+        let seckey = post.account.secret_key.to_secret_bytes();
+        let note = post.to_note(&seckey);
+
+        assert_eq!(notedeck::note::note_expiration(&note), Some(1_700_000_000));
+    }
+
+    #[test]
+    fn test_client_tag_opt_out() {
+        let account = FullKeypair::generate();
+        let seckey = account.secret_key.to_secret_bytes();
+        let tagged = NewPost::new("gm".to_owned(), account.clone()).with_client_tag(true);
+        let untagged = NewPost::new("gm".to_owned(), account).with_client_tag(false);
+
+        let note = tagged.to_note(&seckey);
+        assert_eq!(notedeck::note::note_client(&note), Some(CLIENT_NAME));
+        assert_eq!(
+            notedeck::note::note_client(&untagged.to_note(&seckey)),
+            None
+        );
+        assert_eq!(
+            notedeck::note::note_client(&tagged.to_quote(&seckey, &note)),
+            Some(CLIENT_NAME)
+        );
+        assert_eq!(
+            notedeck::note::note_client(&untagged.to_reply(&seckey, &note)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_reply_tags() {
+        let alice = FullKeypair::generate();
+        let bob = FullKeypair::generate();
+        let carol = FullKeypair::generate();
+
+        let root = NewPost::new("gm".to_owned(), alice.clone())
+            .to_note(&alice.secret_key.to_secret_bytes());
+        let reply = NewPost::new("gm alice".to_owned(), bob.clone())
+            .to_reply(&bob.secret_key.to_secret_bytes(), &root);
+        assert_eq!(
+            reply_mentions(&reply),
+            vec![*bob.pubkey.bytes(), *alice.pubkey.bytes()]
+        );
+
+        let post = NewPost::new("gm both".to_owned(), carol.clone())
+            .without_mentions(HashSet::from([*alice.pubkey.bytes()]));
+        let nested = post.to_reply(&carol.secret_key.to_secret_bytes(), &reply);
+        let tags = notedeck::note::tag_strings(&nested);
+
+        let marked = |marker: &str| {
+            tags.iter()
+                .find(|tag| tag[0] == "e" && tag.get(3).map(String::as_str) == Some(marker))
+                .map(|tag| tag[1].clone())
+        };
+        assert_eq!(marked("root"), Some(hex::encode(root.id())));
+        assert_eq!(marked("reply"), Some(hex::encode(reply.id())));
+
+        let mentioned: Vec<&String> = tags
+            .iter()
+            .filter(|tag| tag[0] == "p")
+            .map(|tag| &tag[1])
+            .collect();
+        assert_eq!(mentioned, vec![&bob.pubkey.hex()]);
+    }
 }
@@ -0,0 +1,360 @@
+//! Getting ready to read offline. When it's turned on and we're on an
+//! unmetered connection, the last day or so of notes from everyone the
+//! account follows is asked for in the background, then their images are
+//! downloaded into the image cache. Calendar events starting in the next
+//! month come along too, from follows and from the account's RSVPs, so
+//! the details of something we're going to are there without a signal.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use enostr::{Pubkey, RelayPool, RelayStatus};
+use nostrdb::{BlockType, Filter, Ndb, Note, Transaction};
+use notedeck::{
+    calendar::{self, Rsvp, DATE_EVENT_KIND, RSVP_KIND, TIME_EVENT_KIND},
+    contacts,
+    note::tag_strings,
+    time::unix_now,
+    AppContext,
+};
+use poll_promise::Promise;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{images, ui::note::contents::is_image_link};
+
+/// How often to prefetch again while the app stays open
+const PREFETCH_EVERY: u64 = 60 * 60;
+/// How long relays get to send what we asked for before we look at it
+const SETTLE: Duration = Duration::from_secs(20);
+const NOTE_LIMIT: u64 = 1000;
+const EVENT_LIMIT: u64 = 200;
+/// Most images downloaded in one go
+const IMAGE_LIMIT: usize = 200;
+/// How far ahead calendar events are kept ready
+const EVENTS_AHEAD: u64 = 30 * 24 * 60 * 60;
+/// Relays gone for this long means we're offline, not just reconnecting
+const OFFLINE_AFTER: Duration = Duration::from_secs(5);
+
+/// What to prefetch, from settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrefetchParams {
+    pub hours: u64,
+    pub images: bool,
+    /// Wait for Wi-Fi, or anything else that isn't metered
+    pub unmetered_only: bool,
+}
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Idle,
+    /// Asked relays, giving them time to answer
+    Requesting {
+        subid: String,
+        account: Pubkey,
+        since: u64,
+        started: Instant,
+    },
+    Downloading(Promise<usize>),
+}
+
+#[derive(Default)]
+pub struct Prefetch {
+    state: State,
+    /// When the last prefetch finished, unix seconds
+    pub last_run: Option<u64>,
+    /// When we noticed no relay was connected
+    disconnected: Option<Instant>,
+}
+
+fn connected(pool: &RelayPool) -> bool {
+    pool.relays
+        .iter()
+        .any(|relay| relay.status() == RelayStatus::Connected)
+}
+
+impl Prefetch {
+    /// No relay has been reachable for a little while, so everything on
+    /// screen comes from what's saved on this device
+    pub fn is_offline(&self) -> bool {
+        self.disconnected
+            .is_some_and(|since| since.elapsed() > OFFLINE_AFTER)
+    }
+
+    /// Called every frame
+    pub fn update(&mut self, ctx: &mut AppContext<'_>, params: Option<PrefetchParams>) {
+        let online = connected(ctx.pool);
+        if online {
+            self.disconnected = None;
+        } else if self.disconnected.is_none() && !ctx.pool.relays.is_empty() {
+            self.disconnected = Some(Instant::now());
+        }
+
+        match &mut self.state {
+            State::Idle => {
+                let due = !self
+                    .last_run
+                    .is_some_and(|last| unix_now() < last + PREFETCH_EVERY);
+                let Some(params) = params.filter(|_| due && online) else {
+                    return;
+                };
+                if params.unmetered_only && notedeck::network::is_metered() {
+                    return;
+                }
+                let Some(account) = ctx.accounts.get_selected_account().map(|acc| acc.pubkey)
+                else {
+                    return;
+                };
+                self.request(ctx.ndb, ctx.pool, account, params.hours);
+            }
+
+            State::Requesting {
+                subid,
+                account,
+                since,
+                started,
+            } => {
+                if started.elapsed() < SETTLE {
+                    return;
+                }
+                ctx.pool.unsubscribe(subid.clone());
+                let (account, since) = (*account, *since);
+
+                let want_images = params.is_some_and(|params| {
+                    params.images && !(params.unmetered_only && notedeck::network::is_metered())
+                });
+                if !want_images {
+                    self.finish(0);
+                    return;
+                }
+                let urls = image_urls(ctx.ndb, &account, since);
+                let cache_dir = ctx.img_cache.cache_dir.clone();
+                self.state =
+                    State::Downloading(Promise::spawn_thread("prefetch-images", move || {
+                        download(cache_dir, urls)
+                    }));
+            }
+
+            State::Downloading(promise) => {
+                if let Some(downloaded) = promise.ready() {
+                    let downloaded = *downloaded;
+                    self.finish(downloaded);
+                }
+            }
+        }
+    }
+
+    fn request(&mut self, ndb: &Ndb, pool: &mut RelayPool, account: Pubkey, hours: u64) {
+        let since = unix_now().saturating_sub(hours * 60 * 60);
+        let Ok(txn) = Transaction::new(ndb) else {
+            return;
+        };
+        let follows = contacts::followed(ndb, &txn, account.bytes());
+        if follows.is_empty() {
+            // nothing to get ready yet, try again later
+            self.last_run = Some(unix_now());
+            return;
+        }
+
+        let mut filters = vec![
+            Filter::new()
+                .authors(follows.iter())
+                .kinds([1, 6])
+                .since(since)
+                .limit(NOTE_LIMIT)
+                .build(),
+            Filter::new()
+                .authors(follows.iter())
+                .kinds([DATE_EVENT_KIND as u64, TIME_EVENT_KIND as u64])
+                .limit(EVENT_LIMIT)
+                .build(),
+            Filter::new()
+                .authors([account.bytes()])
+                .kinds([RSVP_KIND as u64])
+                .limit(EVENT_LIMIT)
+                .build(),
+        ];
+        if let Some(rsvpd) = rsvpd_events_filter(&rsvpd_events(ndb, &txn, &account)) {
+            filters.push(rsvpd);
+        }
+
+        let subid = Uuid::new_v4().to_string();
+        info!("prefetch: asking for notes from {} follows", follows.len());
+        pool.subscribe(subid.clone(), filters);
+        self.state = State::Requesting {
+            subid,
+            account,
+            since,
+            started: Instant::now(),
+        };
+    }
+
+    fn finish(&mut self, downloaded: usize) {
+        info!("prefetch: done, downloaded {downloaded} images");
+        self.last_run = Some(unix_now());
+        self.state = State::Idle;
+    }
+}
+
+/// `kind:pubkey:d` addresses of the events the account RSVP'd to
+fn rsvpd_events(ndb: &Ndb, txn: &Transaction, account: &Pubkey) -> HashSet<String> {
+    let filter = Filter::new()
+        .authors([account.bytes()])
+        .kinds([RSVP_KIND as u64])
+        .limit(EVENT_LIMIT)
+        .build();
+    let Ok(results) = ndb.query(txn, &[filter], EVENT_LIMIT as i32) else {
+        return HashSet::new();
+    };
+    results
+        .iter()
+        .filter_map(|result| Rsvp::from_note(&result.note))
+        .map(|rsvp| rsvp.event)
+        .collect()
+}
+
+/// One filter for every event at `addresses`. It can match a few more
+/// than asked for, which is fine for getting ready to go offline.
+fn rsvpd_events_filter(addresses: &HashSet<String>) -> Option<Filter> {
+    let mut authors: Vec<[u8; 32]> = vec![];
+    let mut identifiers: Vec<String> = vec![];
+    for address in addresses {
+        let mut parts = address.splitn(3, ':');
+        let (Some(_kind), Some(pubkey), Some(d)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let Ok(pubkey) = Pubkey::from_hex(pubkey) else {
+            continue;
+        };
+        authors.push(*pubkey.bytes());
+        identifiers.push(d.to_owned());
+    }
+    if authors.is_empty() {
+        return None;
+    }
+
+    Some(
+        Filter::new()
+            .authors(authors.iter())
+            .kinds([DATE_EVENT_KIND as u64, TIME_EVENT_KIND as u64])
+            .tags(identifiers, 'd')
+            .build(),
+    )
+}
+
+fn event_address(note: &Note) -> Option<String> {
+    let d = tag_strings(note)
+        .into_iter()
+        .find(|tag| tag[0] == "d")?
+        .get(1)?
+        .to_owned();
+    Some(format!(
+        "{}:{}:{d}",
+        note.kind(),
+        hex::encode(note.pubkey())
+    ))
+}
+
+/// Whether a calendar event is coming up soon enough to keep ready
+fn upcoming(note: &Note, now: u64) -> bool {
+    calendar::event_start(note).is_some_and(|start| start >= now && start < now + EVENTS_AHEAD)
+}
+
+/// Images in the notes and upcoming events we just asked for
+fn image_urls(ndb: &Ndb, account: &Pubkey, since: u64) -> Vec<String> {
+    let Ok(txn) = Transaction::new(ndb) else {
+        return vec![];
+    };
+    let follows = contacts::followed(ndb, &txn, account.bytes());
+    let mut urls: Vec<String> = vec![];
+    let mut push = |url: &str| {
+        if urls.len() < IMAGE_LIMIT && !urls.iter().any(|u| u == url) {
+            urls.push(url.to_owned());
+        }
+    };
+
+    let rsvpd = rsvpd_events(ndb, &txn, account);
+    let events = Filter::new()
+        .kinds([DATE_EVENT_KIND as u64, TIME_EVENT_KIND as u64])
+        .limit(EVENT_LIMIT)
+        .build();
+    let now = unix_now();
+    if let Ok(results) = ndb.query(&txn, &[events], EVENT_LIMIT as i32) {
+        for result in &results {
+            let note = &result.note;
+            let ours = follows.contains(note.pubkey())
+                || event_address(note).is_some_and(|address| rsvpd.contains(&address));
+            if !ours || !upcoming(note, now) {
+                continue;
+            }
+            for tag in tag_strings(note) {
+                if let ("image", Some(url)) = (tag[0].as_str(), tag.get(1)) {
+                    push(url);
+                }
+            }
+        }
+    }
+
+    let notes = Filter::new()
+        .authors(follows.iter())
+        .kinds([1])
+        .since(since)
+        .limit(NOTE_LIMIT)
+        .build();
+    if let Ok(results) = ndb.query(&txn, &[notes], NOTE_LIMIT as i32) {
+        for result in &results {
+            let Ok(blocks) = ndb.get_blocks_by_key(&txn, result.note_key) else {
+                continue;
+            };
+            for block in blocks.iter(&result.note) {
+                if matches!(block.blocktype(), BlockType::Url)
+                    && is_image_link(&block.as_str().to_lowercase())
+                {
+                    push(block.as_str());
+                }
+            }
+        }
+    }
+
+    urls
+}
+
+/// Download what isn't cached yet, one at a time to go easy on the
+/// connection. Returns how many were downloaded.
+fn download(cache_dir: PathBuf, urls: Vec<String>) -> usize {
+    let mut downloaded = 0;
+    for url in urls {
+        match images::prefetch_img(&cache_dir, &url) {
+            Ok(true) => downloaded += 1,
+            Ok(false) => {}
+            Err(err) => error!("prefetch: could not download {url}: {err}"),
+        }
+    }
+    downloaded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rsvpd_events_filter() {
+        assert!(rsvpd_events_filter(&HashSet::new()).is_none());
+
+        let pubkey = Pubkey::new([7; 32]);
+        let addresses: HashSet<String> = [
+            format!("31923:{}:meetup", pubkey.hex()),
+            "31923:not-a-pubkey:party".to_owned(),
+            "garbage".to_owned(),
+        ]
+        .into();
+        let filter = rsvpd_events_filter(&addresses).expect("filter");
+        let json = filter.json().expect("json");
+        assert!(json.contains(&pubkey.hex()));
+        assert!(json.contains("meetup"));
+        assert!(!json.contains("party"));
+    }
+}
@@ -3,11 +3,12 @@ use std::collections::HashMap;
 use enostr::{Filter, FullKeypair, Pubkey, PubkeyRef, RelayPool};
 use nostrdb::{FilterBuilder, Ndb, Note, NoteBuildOptions, NoteBuilder, ProfileRecord};
 
-use notedeck::{filter::default_limit, FilterState};
-use tracing::info;
+use notedeck::{filter::default_limit, toast, tr, Accounts, FilterState};
+use tracing::error;
 
 use crate::{
     multi_subscriber::MultiSubscriber,
+    post::{self, add_client_tag},
     profile_state::ProfileState,
     route::{Route, Router},
     timeline::{PubkeySource, Timeline, TimelineKind, TimelineTab},
@@ -85,7 +86,7 @@ impl Profile {
         let timeline = Timeline::new(
             TimelineKind::profile(source),
             FilterState::ready(filters),
-            TimelineTab::full_tabs(),
+            TimelineTab::profile_tabs(),
         );
 
         Profile {
@@ -113,7 +114,7 @@ impl SaveProfileChanges {
     }
     pub fn to_note(&self) -> Note {
         let sec = &self.kp.secret_key.to_secret_bytes();
-        add_client_tag(NoteBuilder::new())
+        add_client_tag(NoteBuilder::new(), post::client_tag())
             .kind(0)
             .content(&self.state.to_json())
             .options(NoteBuildOptions::default().created_at(true).sign(sec))
@@ -122,13 +123,6 @@ impl SaveProfileChanges {
     }
 }
 
-fn add_client_tag(builder: NoteBuilder<'_>) -> NoteBuilder<'_> {
-    builder
-        .start_tag()
-        .tag_str("client")
-        .tag_str("Damus Notedeck")
-}
-
 pub enum ProfileAction {
     Edit(FullKeypair),
     SaveChanges(SaveProfileChanges),
@@ -137,6 +131,7 @@ pub enum ProfileAction {
 impl ProfileAction {
     pub fn process(
         &self,
+        accounts: &Accounts,
         state_map: &mut HashMap<Pubkey, ProfileState>,
         ndb: &Ndb,
         pool: &mut RelayPool,
@@ -147,14 +142,14 @@ impl ProfileAction {
                 router.route_to(Route::EditProfile(kp.pubkey));
             }
             ProfileAction::SaveChanges(changes) => {
-                let raw_msg = format!("[\"EVENT\",{}]", changes.to_note().json().unwrap());
+                // the edits stay open to try again
+                if let Err(err) = accounts.publish_note(ndb, pool, &changes.to_note()) {
+                    error!("couldn't save profile: {err}");
+                    toast::warning(tr!("toast-profile-save-failed", error = err.to_string()));
+                    return;
+                }
 
-                let _ = ndb.process_client_event(raw_msg.as_str());
                 let _ = state_map.remove_entry(&changes.kp.pubkey);
-
-                info!("sending {}", raw_msg);
-                pool.send(&enostr::ClientMessage::raw(raw_msg));
-
                 router.go_back();
             }
         }
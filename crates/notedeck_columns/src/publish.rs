@@ -0,0 +1,364 @@
+//! Following our notes out to relays. A relay answers each event with an
+//! OK saying whether it took it (NIP-01), so we can tell the user how a
+//! post actually went instead of assuming it went out.
+//!
+//! Our events are in nostrdb, and so in our columns, before any relay has
+//! them. Until one takes an event it's shown as pending, and if none do
+//! it's rolled back out of the columns so it can be retried.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use enostr::{ClientMessage, NoteId, PoolRelay, RelayPool, RelayTarget};
+use nostrdb::{Ndb, Transaction};
+use notedeck::{toast, tr};
+use tracing::{debug, error, info, warn};
+
+/// Relays that haven't answered by then are counted as failed
+const TIMEOUT: Duration = Duration::from_secs(15);
+/// Finished publishes we remember, for the outbox
+const MAX_FINISHED: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayResult {
+    Waiting,
+    Accepted,
+    Rejected(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublishState {
+    /// Some relays haven't answered yet
+    Sending,
+    /// Every relay answered, or we gave up waiting
+    Done { accepted: usize, total: usize },
+}
+
+#[derive(Debug)]
+pub struct Publish {
+    /// Hex id of the event
+    pub id: String,
+    pub relays: BTreeMap<String, RelayResult>,
+    /// Who it was sent to, so sending it again doesn't go further
+    target: RelayTarget,
+    sent_at: Instant,
+}
+
+impl Publish {
+    fn new(id: String, relays: impl Iterator<Item = String>, now: Instant) -> Self {
+        Publish {
+            id,
+            relays: relays.map(|url| (url, RelayResult::Waiting)).collect(),
+            target: RelayTarget::All,
+            sent_at: now,
+        }
+    }
+
+    pub fn state(&self, now: Instant) -> PublishState {
+        let waiting = self
+            .relays
+            .values()
+            .any(|result| *result == RelayResult::Waiting);
+
+        if waiting && now.duration_since(self.sent_at) < TIMEOUT {
+            return PublishState::Sending;
+        }
+
+        PublishState::Done {
+            accepted: self.accepted(),
+            total: self.relays.len(),
+        }
+    }
+
+    pub fn accepted(&self) -> usize {
+        self.relays
+            .values()
+            .filter(|result| **result == RelayResult::Accepted)
+            .count()
+    }
+
+    /// Why each relay that didn't take it didn't
+    pub fn failures(&self) -> Vec<String> {
+        self.relays
+            .iter()
+            .filter_map(|(url, result)| match result {
+                RelayResult::Accepted => None,
+                RelayResult::Rejected(reason) if reason.is_empty() => {
+                    Some(tr!("publish-rejected", relay = url.as_str()))
+                }
+                RelayResult::Rejected(reason) => Some(format!("{url}: {reason}")),
+                RelayResult::Waiting => Some(tr!("publish-no-answer", relay = url.as_str())),
+            })
+            .collect()
+    }
+}
+
+/// Events we've sent and are waiting to hear back about
+#[derive(Default)]
+pub struct Publishes {
+    publishes: Vec<Publish>,
+    /// Ones every relay answered or timed out on, oldest first
+    finished: VecDeque<Publish>,
+}
+
+impl Publishes {
+    /// Start waiting for the relays in the pool to answer about an event
+    pub fn track(&mut self, id: &[u8; 32], pool: &RelayPool) {
+        self.track_to(id, pool, &RelayTarget::All);
+    }
+
+    /// Start waiting on the relays an event was sent to
+    pub fn track_to(&mut self, id: &[u8; 32], pool: &RelayPool, target: &RelayTarget) {
+        // multicast peers don't answer
+        let relays = pool.relays.iter().filter_map(|relay| match relay {
+            PoolRelay::Websocket(_) if target.includes(relay.url()) => Some(relay.url().to_owned()),
+            _ => None,
+        });
+
+        let mut publish = Publish::new(hex::encode(id), relays, Instant::now());
+        publish.target = target.clone();
+        debug!(
+            "waiting on {} relays for {}",
+            publish.relays.len(),
+            publish.id
+        );
+        self.publishes.push(publish);
+    }
+
+    /// A relay's OK for an event
+    pub fn got_ok(&mut self, relay: &str, event_id: &str, accepted: bool, message: &str) {
+        // late answers still count for the outbox
+        let Some(publish) = self
+            .publishes
+            .iter_mut()
+            .chain(self.finished.iter_mut().rev())
+            .find(|p| p.id == event_id)
+        else {
+            return;
+        };
+        let Some(result) = publish.relays.get_mut(relay) else {
+            return;
+        };
+
+        *result = if accepted {
+            RelayResult::Accepted
+        } else {
+            warn!("{relay} rejected {event_id}: {message}");
+            RelayResult::Rejected(message.to_owned())
+        };
+    }
+
+    /// An event from a relay. If it's one we're waiting on, the relay has
+    /// it even if its OK got lost.
+    pub fn got_event(&mut self, relay: &str, event: &str) {
+        for publish in &mut self.publishes {
+            // cheaper than parsing every event, and an id is only quoted
+            // like this as the event's own id
+            if !event.contains(&format!("\"id\":\"{}\"", publish.id)) {
+                continue;
+            }
+            if let Some(result) = publish.relays.get_mut(relay) {
+                *result = RelayResult::Accepted;
+            }
+        }
+    }
+
+    /// Send one of our events from nostrdb again, eg. after it was rolled
+    /// back
+    pub fn resend(&mut self, ndb: &Ndb, pool: &mut RelayPool, id: &NoteId) -> bool {
+        let txn = Transaction::new(ndb).expect("txn");
+        let json = match ndb.get_note_by_id(&txn, id.bytes()).map(|note| note.json()) {
+            Ok(Ok(json)) => json,
+            _ => {
+                error!("can't resend {}, it's not in nostrdb", id.hex());
+                return false;
+            }
+        };
+
+        let hex = id.hex();
+        let target = self
+            .finished
+            .iter()
+            .rev()
+            .chain(self.publishes.iter())
+            .find(|publish| publish.id == hex)
+            .map(|publish| publish.target.clone())
+            .unwrap_or_default();
+
+        pool.send_targeted(&ClientMessage::raw(format!("[\"EVENT\",{json}]")), &target);
+        self.track_to(id.bytes(), pool, &target);
+        true
+    }
+
+    /// Let the user know how the publishes that have finished went.
+    /// Returns the events no relay took, for rolling back.
+    pub fn poll(&mut self, ctx: &egui::Context) -> Vec<NoteId> {
+        let now = Instant::now();
+        let (done, sending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.publishes)
+            .into_iter()
+            .partition(|p| matches!(p.state(now), PublishState::Done { .. }));
+        self.publishes = sending;
+
+        if !self.publishes.is_empty() {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+        set_pending(ctx, self.pending());
+
+        let unsent = done
+            .iter()
+            .filter_map(|publish| {
+                report(publish, now);
+                // with no relays to send to there's nothing to retry on
+                if publish.accepted() > 0 || publish.relays.is_empty() {
+                    return None;
+                }
+                let id: [u8; 32] = hex::decode(&publish.id).ok()?.try_into().ok()?;
+                Some(NoteId::new(id))
+            })
+            .collect();
+
+        self.finished.extend(done);
+        while self.finished.len() > MAX_FINISHED {
+            self.finished.pop_front();
+        }
+
+        unsent
+    }
+
+    /// How sending one of our events from this device went, or is going
+    pub fn get(&self, id: &[u8; 32]) -> Option<&Publish> {
+        let id = hex::encode(id);
+        self.publishes
+            .iter()
+            .chain(self.finished.iter().rev())
+            .find(|publish| publish.id == id)
+    }
+
+    /// Events no relay has taken yet
+    fn pending(&self) -> HashSet<[u8; 32]> {
+        self.publishes
+            .iter()
+            .filter(|publish| publish.accepted() == 0)
+            .filter_map(|publish| hex::decode(&publish.id).ok()?.try_into().ok())
+            .collect()
+    }
+}
+
+fn pending_id() -> egui::Id {
+    egui::Id::new("pending-publishes")
+}
+
+fn set_pending(ctx: &egui::Context, pending: HashSet<[u8; 32]>) {
+    ctx.data_mut(|d| d.insert_temp(pending_id(), Arc::new(pending)));
+}
+
+/// Whether a note of ours is still waiting for a relay to take it
+pub fn is_pending(ctx: &egui::Context, id: &[u8; 32]) -> bool {
+    ctx.data(|d| d.get_temp::<Arc<HashSet<[u8; 32]>>>(pending_id()))
+        .is_some_and(|pending| pending.contains(id))
+}
+
+fn report(publish: &Publish, now: Instant) {
+    let PublishState::Done { accepted, total } = publish.state(now) else {
+        return;
+    };
+    info!("{} accepted by {accepted}/{total} relays", publish.id);
+
+    let mut message = if accepted == 0 && total > 0 {
+        tr!("publish-rolled-back")
+    } else if accepted == 0 {
+        tr!("publish-failed")
+    } else {
+        tr!("publish-accepted", accepted = accepted, total = total)
+    };
+    let failures = publish.failures();
+    for failure in &failures {
+        message.push('\n');
+        message.push_str(failure);
+    }
+
+    if accepted == 0 {
+        toast::error(message);
+    } else if !failures.is_empty() {
+        toast::warning(message);
+    } else {
+        toast::info(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_state() {
+        let start = Instant::now();
+        let relays = [
+            "wss://relay.damus.io",
+            "wss://nos.lol",
+            "wss://slow.example",
+        ];
+        let mut publishes = Publishes::default();
+        publishes.publishes.push(Publish::new(
+            "ab".repeat(32),
+            relays.iter().map(|r| r.to_string()),
+            start,
+        ));
+
+        let id = "ab".repeat(32);
+        publishes.got_ok("wss://relay.damus.io", &id, true, "");
+        publishes.got_ok("wss://nos.lol", &id, false, "blocked: pow required");
+        // answers for other events or relays we didn't send to are ignored
+        publishes.got_ok("wss://other.example", &id, true, "");
+        publishes.got_ok("wss://slow.example", &"cd".repeat(32), true, "");
+
+        let publish = &publishes.publishes[0];
+        assert_eq!(publish.state(start), PublishState::Sending);
+        assert_eq!(
+            publish.state(start + TIMEOUT),
+            PublishState::Done {
+                accepted: 1,
+                total: 3
+            }
+        );
+        assert_eq!(publish.failures().len(), 2);
+        assert!(publish.failures()[0].contains("blocked: pow required"));
+    }
+
+    #[test]
+    fn test_seen_on_relay() {
+        let start = Instant::now();
+        let id = "ab".repeat(32);
+        let mut publishes = Publishes::default();
+        publishes.publishes.push(Publish::new(
+            id.clone(),
+            ["wss://relay.damus.io".to_string()].into_iter(),
+            start,
+        ));
+        assert_eq!(publishes.pending().len(), 1);
+
+        // a reply to it doesn't count
+        publishes.got_event(
+            "wss://relay.damus.io",
+            &format!(
+                r#"["EVENT","sub",{{"id":"{}","tags":[["e","{id}"]]}}]"#,
+                "cd".repeat(32)
+            ),
+        );
+        assert_eq!(publishes.pending().len(), 1);
+
+        publishes.got_event(
+            "wss://relay.damus.io",
+            &format!(r#"["EVENT","sub",{{"id":"{id}","tags":[]}}]"#),
+        );
+        assert!(publishes.pending().is_empty());
+        assert_eq!(
+            publishes.publishes[0].state(start),
+            PublishState::Done {
+                accepted: 1,
+                total: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use tracing::{debug, error};
+
+/// Find and decode every QR code in an image file's contents
+pub fn decode_image(bytes: &[u8]) -> Vec<String> {
+    let image = match image::load_from_memory(bytes) {
+        Ok(image) => image.to_luma8(),
+        Err(err) => {
+            error!("qr: couldn't load image: {err}");
+            return vec![];
+        }
+    };
+
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32).0[0],
+    );
+
+    prepared
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| match grid.decode() {
+            Ok((_meta, content)) => Some(content),
+            Err(err) => {
+                debug!("qr: couldn't decode grid: {err}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// QR codes in any image files that were dropped on the window this frame
+pub fn dropped_codes(ctx: &egui::Context) -> Vec<String> {
+    let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+
+    dropped
+        .iter()
+        .filter_map(|file| {
+            if let Some(bytes) = &file.bytes {
+                Some(bytes.to_vec())
+            } else {
+                let path = file.path.as_ref()?;
+                std::fs::read(path)
+                    .inspect_err(|err| error!("qr: couldn't read {}: {err}", path.display()))
+                    .ok()
+            }
+        })
+        .flat_map(|bytes| decode_image(&bytes))
+        .collect()
+}
+
+fn camera_request_id() -> egui::Id {
+    egui::Id::new("qr-camera-request")
+}
+
+fn scanned_id() -> egui::Id {
+    egui::Id::new("qr-scanned")
+}
+
+/// Ask the chrome to take a picture of a QR code. Only android has a
+/// camera we can reach.
+pub fn request_camera(ctx: &egui::Context) {
+    ctx.data_mut(|d| d.insert_temp(camera_request_id(), true));
+}
+
+pub fn take_camera_request(ctx: &egui::Context) -> bool {
+    ctx.data_mut(|d| d.remove_temp::<bool>(camera_request_id()))
+        .unwrap_or(false)
+}
+
+/// The chrome hands back the codes it found in a picture
+pub fn add_scanned(ctx: &egui::Context, codes: Vec<String>) {
+    ctx.data_mut(|d| {
+        d.get_temp_mut_or_default::<Vec<String>>(scanned_id())
+            .extend(codes)
+    });
+}
+
+/// Codes from the camera since the last call
+pub fn take_scanned(ctx: &egui::Context) -> Vec<String> {
+    ctx.data_mut(|d| d.remove_temp::<Vec<String>>(scanned_id()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::{Color, QrCode};
+    use std::io::Cursor;
+
+    fn qr_png(data: &str) -> Vec<u8> {
+        let code = QrCode::new(data.as_bytes()).unwrap();
+        let width = code.width();
+        let scale = 4;
+        let quiet = 4;
+        let side = ((width + quiet * 2) * scale) as u32;
+        let colors = code.to_colors();
+
+        let image = image::GrayImage::from_fn(side, side, |x, y| {
+            let mx = (x as usize / scale).checked_sub(quiet);
+            let my = (y as usize / scale).checked_sub(quiet);
+            let dark = match (mx, my) {
+                (Some(mx), Some(my)) if mx < width && my < width => {
+                    colors[my * width + mx] == Color::Dark
+                }
+                _ => false,
+            };
+            image::Luma([if dark { 0 } else { 255 }])
+        });
+
+        let mut png = vec![];
+        image
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn test_decode_roundtrip() {
+        let uri = "nostr:npub180cvv07tjdrrgpa0j7j7tmnyl2yr6yr7l8j4s3evf6u64th6gkwsyjh6w6";
+        assert_eq!(decode_image(&qr_png(uri)), vec![uri.to_owned()]);
+        assert!(decode_image(b"not an image").is_empty());
+    }
+}
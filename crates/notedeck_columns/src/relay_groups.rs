@@ -0,0 +1,104 @@
+//! Named sets of relays, like "public" or a paid relay only friends read.
+//! A post can be sent to just one group, so things meant for a few people
+//! don't go out to every relay in the pool.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use enostr::RelayTarget;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayGroup {
+    pub name: String,
+    pub relays: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayGroups {
+    pub groups: Vec<RelayGroup>,
+}
+
+impl RelayGroups {
+    pub fn get(&self, name: &str) -> Option<&RelayGroup> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    /// Where a post for `group` goes. No group, or one that's since been
+    /// deleted, means every relay.
+    pub fn target(&self, group: Option<&str>) -> RelayTarget {
+        match group.and_then(|name| self.get(name)) {
+            Some(group) => RelayTarget::only(group.relays.iter().cloned()),
+            None => RelayTarget::All,
+        }
+    }
+
+    /// Returns false if there's already a group by that name
+    pub fn add(&mut self, name: &str) -> bool {
+        let name = name.trim();
+        if name.is_empty() || self.get(name).is_some() {
+            return false;
+        }
+        self.groups.push(RelayGroup {
+            name: name.to_owned(),
+            relays: BTreeSet::new(),
+        });
+        true
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.groups.retain(|group| group.name != name);
+    }
+
+    /// Put a relay in a group, or take it out if it's there
+    pub fn toggle(&mut self, name: &str, relay: &str) {
+        let Some(group) = self.groups.iter_mut().find(|group| group.name == name) else {
+            return;
+        };
+        if !group.relays.remove(relay) {
+            group.relays.insert(relay.to_owned());
+        }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.groups.iter().map(|group| group.name.clone()).collect()
+    }
+}
+
+fn names_id() -> egui::Id {
+    egui::Id::new("relay-group-names")
+}
+
+/// Let the composer know which groups there are to pick from
+pub fn set_names(ctx: &egui::Context, groups: &RelayGroups) {
+    ctx.data_mut(|d| d.insert_temp(names_id(), Arc::new(groups.names())));
+}
+
+pub fn names(ctx: &egui::Context) -> Arc<Vec<String>> {
+    ctx.data(|d| d.get_temp(names_id())).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_groups() {
+        let mut groups = RelayGroups::default();
+        assert!(groups.add("friends"));
+        assert!(!groups.add(" friends "));
+        groups.toggle("friends", "wss://paid.example/");
+
+        let target = groups.target(Some("friends"));
+        assert!(target.includes("wss://paid.example/"));
+        assert!(!target.includes("wss://relay.damus.io/"));
+        assert_eq!(groups.target(None), RelayTarget::All);
+        assert_eq!(groups.target(Some("gone")), RelayTarget::All);
+
+        groups.toggle("friends", "wss://paid.example/");
+        assert!(groups.get("friends").unwrap().relays.is_empty());
+        groups.remove("friends");
+        assert!(groups.groups.is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use poll_promise::Promise;
+use serde::Deserialize;
+use tracing::debug;
+
+/// A relay's NIP-11 information document. Only the bits we show.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RelayInfoDocument {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub software: Option<String>,
+    pub version: Option<String>,
+    pub supported_nips: Vec<u32>,
+}
+
+impl RelayInfoDocument {
+    /// One line about the relay, eg. "Damus relay · strfry 1.0"
+    pub fn summary(&self) -> Option<String> {
+        let software = self.software.as_deref().map(|software| {
+            // software is usually a repo url, the last path segment is enough
+            let name = software
+                .trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or(software);
+            match &self.version {
+                Some(version) => format!("{name} {version}"),
+                None => name.to_owned(),
+            }
+        });
+
+        let parts: Vec<String> = [self.name.clone(), software]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect();
+
+        (!parts.is_empty()).then(|| parts.join(" · "))
+    }
+}
+
+/// NIP-11 documents, fetched once per relay url
+#[derive(Default)]
+pub struct RelayInfoCache {
+    docs: HashMap<String, Promise<Option<RelayInfoDocument>>>,
+}
+
+impl RelayInfoCache {
+    /// The relay's document, if we've fetched it. Starts fetching it
+    /// otherwise.
+    pub fn get(&mut self, ctx: &egui::Context, relay_url: &str) -> Option<&RelayInfoDocument> {
+        self.docs
+            .entry(relay_url.to_owned())
+            .or_insert_with(|| fetch(ctx, relay_url))
+            .ready()
+            .and_then(|doc| doc.as_ref())
+    }
+}
+
+/// NIP-11 documents are served over http from the relay's own address
+fn http_url(relay_url: &str) -> Option<String> {
+    if let Some(rest) = relay_url.strip_prefix("wss://") {
+        Some(format!("https://{rest}"))
+    } else {
+        relay_url
+            .strip_prefix("ws://")
+            .map(|rest| format!("http://{rest}"))
+    }
+}
+
+fn fetch(ctx: &egui::Context, relay_url: &str) -> Promise<Option<RelayInfoDocument>> {
+    let Some(url) = http_url(relay_url) else {
+        return Promise::from_ready(None);
+    };
+
+    let (sender, promise) = Promise::new();
+    let mut request = ehttp::Request::get(&url);
+    request
+        .headers
+        .insert("Accept".to_owned(), "application/nostr+json".to_owned());
+
+    let ctx = ctx.clone();
+    ehttp::fetch(request, move |response| {
+        let doc = response
+            .map_err(|err| err.to_string())
+            .and_then(|response| {
+                serde_json::from_slice::<RelayInfoDocument>(&response.bytes)
+                    .map_err(|err| err.to_string())
+            });
+
+        if let Err(err) = &doc {
+            debug!("no NIP-11 document for {url}: {err}");
+        }
+
+        sender.send(doc.ok());
+        ctx.request_repaint();
+    });
+
+    promise
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let doc: RelayInfoDocument = serde_json::from_str(
+            r#"{"name":"damus","software":"git+https://github.com/hoytech/strfry","version":"1.0.1","supported_nips":[1,11]}"#,
+        )
+        .unwrap();
+        assert_eq!(doc.summary().as_deref(), Some("damus · strfry 1.0.1"));
+        assert_eq!(RelayInfoDocument::default().summary(), None);
+        assert_eq!(
+            http_url("wss://relay.damus.io/").as_deref(),
+            Some("https://relay.damus.io/")
+        );
+    }
+}
@@ -10,6 +10,8 @@ pub struct RelayPoolManager<'a> {
 pub struct RelayInfo<'a> {
     pub relay_url: &'a str,
     pub status: RelayStatus,
+    /// Messages are being held back by the relay's rate limit
+    pub throttled: bool,
 }
 
 impl<'a> RelayPoolManager<'a> {
@@ -24,6 +26,7 @@ impl<'a> RelayPoolManager<'a> {
             .map(|relay| RelayInfo {
                 relay_url: relay.url(),
                 status: relay.status(),
+                throttled: relay.is_throttled(),
             })
             .collect()
     }
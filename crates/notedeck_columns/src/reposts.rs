@@ -0,0 +1,186 @@
+//! Our own reposts. Columns only ask for text notes by default, so a
+//! repost we just made is put straight into our home and profile columns
+//! rather than left for a subscription that won't see it. For a little
+//! while afterwards it can be taken back with a deletion request.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use enostr::Pubkey;
+use nostrdb::{Ndb, NoteKey, Transaction};
+use notedeck::{NoteCache, UnknownIds};
+use tracing::error;
+
+use crate::{
+    column::Columns,
+    timeline::{kind::ListKind, TimelineCache, TimelineKind},
+};
+
+/// How long a repost can be taken back for
+pub const UNDO_WINDOW: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy)]
+struct Recent {
+    repost: [u8; 32],
+    at: Instant,
+    /// Whether it's been put in our columns yet. nostrdb takes it in
+    /// on its own time.
+    shown: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Reposts {
+    /// By the id of the reposted note
+    recent: HashMap<[u8; 32], Recent>,
+}
+
+fn undoable_id() -> egui::Id {
+    egui::Id::new("undoable-reposts")
+}
+
+/// Notes we reposted recently enough to take it back
+pub fn undoable(ctx: &egui::Context) -> Option<Arc<HashSet<[u8; 32]>>> {
+    ctx.data(|d| d.get_temp::<Arc<HashSet<[u8; 32]>>>(undoable_id()))
+}
+
+impl Reposts {
+    pub fn add(&mut self, note_id: [u8; 32], repost: [u8; 32], now: Instant) {
+        self.recent.insert(
+            note_id,
+            Recent {
+                repost,
+                at: now,
+                shown: false,
+            },
+        );
+    }
+
+    /// Our repost of a note, if it's not too late to take it back
+    pub fn take_back(&mut self, note_id: &[u8; 32], now: Instant) -> Option<[u8; 32]> {
+        let recent = self.recent.remove(note_id)?;
+        (now.duration_since(recent.at) < UNDO_WINDOW).then_some(recent.repost)
+    }
+
+    fn expire(&mut self, now: Instant) {
+        self.recent
+            .retain(|_, recent| now.duration_since(recent.at) < UNDO_WINDOW);
+    }
+
+    /// Show reposts nostrdb has taken in since last time, and let the
+    /// action bars know which ones can still be taken back
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        ndb: &Ndb,
+        columns: &mut Columns,
+        timeline_cache: &mut TimelineCache,
+        note_cache: &mut NoteCache,
+        unknown_ids: &mut UnknownIds,
+        our_pk: Option<&Pubkey>,
+    ) {
+        let now = Instant::now();
+        self.expire(now);
+
+        if let Some(our_pk) = our_pk {
+            let txn = Transaction::new(ndb).expect("txn");
+            for recent in self.recent.values_mut().filter(|recent| !recent.shown) {
+                let Some(key) = note_key(ndb, &txn, &recent.repost) else {
+                    continue;
+                };
+                recent.shown = true;
+
+                let ours = columns
+                    .timelines_mut()
+                    .into_iter()
+                    .filter(|timeline| is_ours(&timeline.kind, our_pk))
+                    .chain(
+                        timeline_cache
+                            .profiles
+                            .get_mut(our_pk)
+                            .map(|profile| &mut profile.timeline),
+                    );
+                for timeline in ours {
+                    // these get it from their subscription
+                    if timeline.kinds.reposts {
+                        continue;
+                    }
+                    if let Err(err) =
+                        timeline.insert(&[key], ndb, &txn, unknown_ids, note_cache, false)
+                    {
+                        error!("couldn't show our repost: {err}");
+                    }
+                }
+            }
+        }
+
+        if let Some(next) = self
+            .recent
+            .values()
+            .map(|recent| UNDO_WINDOW.saturating_sub(now.duration_since(recent.at)))
+            .min()
+        {
+            ctx.request_repaint_after(next);
+        }
+        let undoable: HashSet<[u8; 32]> = self.recent.keys().copied().collect();
+        ctx.data_mut(|d| d.insert_temp(undoable_id(), Arc::new(undoable)));
+    }
+}
+
+/// Take a repost we've taken back out of the columns we put it in
+pub fn remove(
+    ndb: &Ndb,
+    columns: &mut Columns,
+    timeline_cache: &mut TimelineCache,
+    repost: &[u8; 32],
+) {
+    let txn = Transaction::new(ndb).expect("txn");
+    let Some(key) = note_key(ndb, &txn, repost) else {
+        return;
+    };
+
+    for timeline in columns.timelines_mut() {
+        timeline.remove_note(key);
+    }
+    for profile in timeline_cache.profiles.values_mut() {
+        profile.timeline.remove_note(key);
+    }
+}
+
+fn note_key(ndb: &Ndb, txn: &Transaction, id: &[u8; 32]) -> Option<NoteKey> {
+    ndb.get_note_by_id(txn, id).ok()?.key()
+}
+
+/// Our home timeline, or our own profile
+fn is_ours(kind: &TimelineKind, our_pk: &Pubkey) -> bool {
+    match kind {
+        TimelineKind::List(ListKind::Contact(src)) | TimelineKind::Profile(src) => {
+            src.to_pubkey_bytes(our_pk.bytes()) == our_pk.bytes()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_back() {
+        let mut reposts = Reposts::default();
+        let start = Instant::now();
+
+        reposts.add([1; 32], [2; 32], start);
+        assert_eq!(reposts.take_back(&[1; 32], start), Some([2; 32]));
+        // only once
+        assert_eq!(reposts.take_back(&[1; 32], start), None);
+
+        reposts.add([1; 32], [2; 32], start);
+        assert_eq!(reposts.take_back(&[1; 32], start + UNDO_WINDOW), None);
+
+        reposts.add([3; 32], [4; 32], start);
+        reposts.expire(start + UNDO_WINDOW);
+        assert!(reposts.recent.is_empty());
+    }
+}
@@ -1,9 +1,11 @@
 use enostr::{NoteId, Pubkey};
+use notedeck::tr;
 use std::fmt::{self};
 
 use crate::{
     accounts::AccountsRoute,
     column::Columns,
+    moderation::ReportTarget,
     timeline::{kind::ColumnTitle, TimelineId, TimelineRoute},
     ui::add_column::AddColumnRoute,
 };
@@ -14,12 +16,48 @@ pub enum Route {
     Timeline(TimelineRoute),
     Accounts(AccountsRoute),
     Relays,
+    /// Named sets of relays that posts can be sent to
+    RelayGroups,
+    Settings,
+    NotificationSettings,
     ComposeNote,
     AddColumn(AddColumnRoute),
     EditProfile(Pubkey),
     Support,
     NewDeck,
     EditDeck(usize),
+    /// A shareable image of a note
+    Poster(NoteId),
+    /// A note's raw json
+    Source(NoteId),
+    /// Change one of our notes and publish it again
+    EditNote(NoteId),
+    /// Pick a passage of a note to highlight
+    Highlight(NoteId),
+    /// Everyone's highlights of a note
+    Highlights(NoteId),
+    /// Developer view of active subscriptions
+    Subscriptions,
+    /// Nostr Wallet Connect balance and history
+    Wallet,
+    /// The account's bookmarked notes
+    Bookmarks,
+    /// Pick which bookmark lists a note is in
+    SaveBookmark(NoteId),
+    /// Notes and people hidden from columns
+    Hidden,
+    /// Export the account's events, or import an export
+    ExportData,
+    /// Database size, what's in it, and compacting it
+    Storage,
+    /// Report a note or a person
+    Report(ReportTarget),
+    /// Everything the account has published, and how sending it went
+    MyPosts,
+    /// Follow packs, for finding people to follow
+    ExplorePeople,
+    /// Notes and hashtags that are taking off
+    Trending,
 }
 
 impl Route {
@@ -39,6 +77,10 @@ impl Route {
         Route::Relays
     }
 
+    pub fn settings() -> Self {
+        Route::Settings
+    }
+
     pub fn thread(thread_root: NoteId) -> Self {
         Route::Timeline(TimelineRoute::Thread(thread_root))
     }
@@ -70,46 +112,67 @@ impl Route {
                     if let Some(timeline) = columns.find_timeline(*id) {
                         timeline.kind.to_title()
                     } else {
-                        ColumnTitle::simple("Unknown")
+                        localized("route-unknown")
                     }
                 }
-                TimelineRoute::Thread(_id) => ColumnTitle::simple("Thread"),
-                TimelineRoute::Reply(_id) => ColumnTitle::simple("Reply"),
-                TimelineRoute::Quote(_id) => ColumnTitle::simple("Quote"),
-                TimelineRoute::Profile(_pubkey) => ColumnTitle::simple("Profile"),
+                TimelineRoute::Thread(_id) => localized("route-thread"),
+                TimelineRoute::Reply(_id) => localized("route-reply"),
+                TimelineRoute::Quote(_id) => localized("route-quote"),
+                TimelineRoute::Profile(_pubkey) => localized("route-profile"),
             },
 
-            Route::Relays => ColumnTitle::simple("Relays"),
+            Route::Relays => localized("route-relays"),
+            Route::RelayGroups => localized("route-relay-groups"),
+            Route::Settings => localized("route-settings"),
+            Route::NotificationSettings => localized("route-notification-settings"),
 
             Route::Accounts(amr) => match amr {
-                AccountsRoute::Accounts => ColumnTitle::simple("Accounts"),
-                AccountsRoute::AddAccount => ColumnTitle::simple("Add Account"),
+                AccountsRoute::Accounts => localized("route-accounts"),
+                AccountsRoute::AddAccount => localized("route-add-account"),
             },
-            Route::ComposeNote => ColumnTitle::simple("Compose Note"),
+            Route::ComposeNote => localized("route-compose-note"),
             Route::AddColumn(c) => match c {
-                AddColumnRoute::Base => ColumnTitle::simple("Add Column"),
+                AddColumnRoute::Base => localized("route-add-column"),
                 AddColumnRoute::UndecidedNotification => {
-                    ColumnTitle::simple("Add Notifications Column")
+                    localized("route-add-notifications-column")
                 }
                 AddColumnRoute::ExternalNotification => {
-                    ColumnTitle::simple("Add External Notifications Column")
-                }
-                AddColumnRoute::Hashtag => ColumnTitle::simple("Add Hashtag Column"),
-                AddColumnRoute::UndecidedIndividual => {
-                    ColumnTitle::simple("Subscribe to someone's notes")
+                    localized("route-add-external-notifications-column")
                 }
+                AddColumnRoute::Hashtag => localized("route-add-hashtag-column"),
+                AddColumnRoute::UndecidedIndividual => localized("route-add-individual-column"),
                 AddColumnRoute::ExternalIndividual => {
-                    ColumnTitle::simple("Subscribe to someone else's notes")
+                    localized("route-add-external-individual-column")
                 }
             },
-            Route::Support => ColumnTitle::simple("Damus Support"),
-            Route::NewDeck => ColumnTitle::simple("Add Deck"),
-            Route::EditDeck(_) => ColumnTitle::simple("Edit Deck"),
-            Route::EditProfile(_) => ColumnTitle::simple("Edit Profile"),
+            Route::Support => localized("route-support"),
+            Route::NewDeck => localized("route-new-deck"),
+            Route::EditDeck(_) => localized("route-edit-deck"),
+            Route::EditProfile(_) => localized("route-edit-profile"),
+            Route::Poster(_) => localized("route-poster"),
+            Route::Source(_) => localized("route-source"),
+            Route::EditNote(_) => localized("route-edit-note"),
+            Route::Highlight(_) => localized("route-highlight"),
+            Route::Highlights(_) => localized("route-highlights"),
+            Route::Subscriptions => localized("route-subscriptions"),
+            Route::Wallet => localized("route-wallet"),
+            Route::Bookmarks => localized("route-bookmarks"),
+            Route::SaveBookmark(_) => localized("route-save-bookmark"),
+            Route::Hidden => localized("route-hidden"),
+            Route::ExportData => localized("route-export-data"),
+            Route::Storage => localized("route-storage"),
+            Route::Report(_) => localized("route-report"),
+            Route::MyPosts => localized("route-my-posts"),
+            Route::ExplorePeople => localized("route-explore-people"),
+            Route::Trending => localized("route-trending"),
         }
     }
 }
 
+fn localized(id: &str) -> ColumnTitle<'static> {
+    ColumnTitle::formatted(tr!(id))
+}
+
 // TODO: add this to egui-nav so we don't have to deal with returning
 // and navigating headaches
 #[derive(Clone)]
@@ -206,6 +269,9 @@ impl fmt::Display for Route {
             },
 
             Route::Relays => write!(f, "Relays"),
+            Route::RelayGroups => write!(f, "Relay Groups"),
+            Route::Settings => write!(f, "Settings"),
+            Route::NotificationSettings => write!(f, "Notification Settings"),
 
             Route::Accounts(amr) => match amr {
                 AccountsRoute::Accounts => write!(f, "Accounts"),
@@ -218,6 +284,22 @@ impl fmt::Display for Route {
             Route::NewDeck => write!(f, "Add Deck"),
             Route::EditDeck(_) => write!(f, "Edit Deck"),
             Route::EditProfile(_) => write!(f, "Edit Profile"),
+            Route::Poster(_) => write!(f, "Export as Image"),
+            Route::Source(_) => write!(f, "View Source"),
+            Route::EditNote(_) => write!(f, "Edit"),
+            Route::Highlight(_) => write!(f, "Highlight"),
+            Route::Highlights(_) => write!(f, "Highlights"),
+            Route::Subscriptions => write!(f, "Subscriptions"),
+            Route::Wallet => write!(f, "Wallet"),
+            Route::Bookmarks => write!(f, "Bookmarks"),
+            Route::SaveBookmark(_) => write!(f, "Save Bookmark"),
+            Route::Hidden => write!(f, "Hidden"),
+            Route::ExportData => write!(f, "Export Data"),
+            Route::Storage => write!(f, "Storage"),
+            Route::Report(_) => write!(f, "Report"),
+            Route::MyPosts => write!(f, "My Posts"),
+            Route::ExplorePeople => write!(f, "Explore People"),
+            Route::Trending => write!(f, "Trending"),
         }
     }
 }
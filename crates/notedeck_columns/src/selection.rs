@@ -0,0 +1,69 @@
+//! What the user is looking at across columns. The note they last opened
+//! lights up in every column that shows it, and opening something another
+//! column already shows sends them to that column instead of stacking a
+//! duplicate.
+
+use std::time::{Duration, Instant};
+
+use enostr::NoteId;
+
+/// How long a column we sent the user to stays outlined
+const FOCUS_DURATION: Duration = Duration::from_millis(1500);
+
+#[derive(Default)]
+pub struct Selection {
+    note: Option<NoteId>,
+    /// A column we sent the user to and when, so it can be pointed out
+    focus: Option<(usize, Instant)>,
+    /// The focused column still has to be scrolled into view
+    scroll: bool,
+    /// Whether more than one column is on screen. On a phone only the
+    /// first column shows, so sending the user elsewhere would lose them.
+    pub multi_column: bool,
+}
+
+impl Selection {
+    pub fn select_note(&mut self, note_id: NoteId) {
+        self.note = Some(note_id);
+    }
+
+    /// Point out a column that already showed what the user asked for
+    pub fn focus_column(&mut self, col: usize) {
+        self.focus = Some((col, Instant::now()));
+        self.scroll = true;
+    }
+
+    /// The column to outline, if one was focused recently
+    pub fn focused_column(&self) -> Option<usize> {
+        self.focus
+            .filter(|(_, at)| at.elapsed() < FOCUS_DURATION)
+            .map(|(col, _)| col)
+    }
+
+    /// Whether the focused column still needs scrolling into view. Only
+    /// true once per focus.
+    pub fn take_scroll(&mut self) -> bool {
+        std::mem::take(&mut self.scroll)
+    }
+
+    /// Columns were removed or reordered, so indices we hold are stale
+    pub fn columns_changed(&mut self) {
+        self.focus = None;
+        self.scroll = false;
+    }
+
+    /// Share the selected note with note views for this frame
+    pub fn publish(&self, ctx: &egui::Context) {
+        ctx.data_mut(|d| d.insert_temp(selected_note_id(), self.note));
+    }
+}
+
+fn selected_note_id() -> egui::Id {
+    egui::Id::new("selected-note")
+}
+
+/// The note to highlight, as published by the app this frame
+pub fn selected_note(ctx: &egui::Context) -> Option<NoteId> {
+    ctx.data(|d| d.get_temp::<Option<NoteId>>(selected_note_id()))
+        .flatten()
+}
@@ -0,0 +1,300 @@
+//! What the columns app contributes to the shared settings window
+
+use crate::{geo, hidden::HiddenClients, prefetch::PrefetchParams};
+use notedeck::{
+    blocklist::Blocklist,
+    i18n,
+    maintenance::Retention,
+    settings::{Setting, SettingsSection},
+    spell, tr, Settings, WotParams,
+};
+
+pub const APP: &str = "columns";
+
+pub const AUTOSAVE_DRAFTS: &str = "autosave-drafts";
+pub const STARTER_COLUMNS: &str = "starter-columns";
+pub const LANDING: &str = "landing";
+pub const TRUSTED_HASHTAGS: &str = "trusted-hashtags";
+pub const WOT_DEPTH: &str = "wot-depth";
+pub const WOT_FOLLOWERS: &str = "wot-followers";
+pub const WOT_MIN_FOLLOWS: &str = "wot-min-follows";
+pub const GEOCODER: &str = "geocoder";
+pub const MAP_TILES: &str = "map-tiles";
+pub const GIF_SEARCH: &str = "gif-search";
+pub const ANIMATION: &str = "animation";
+pub const TRENDING_WINDOW: &str = "trending-window";
+pub const TRENDING_API: &str = "trending-api";
+pub const SPELLCHECK: &str = "spellcheck";
+pub const SPELLCHECK_LANGUAGE: &str = "spellcheck-language";
+pub const MODERATORS: &str = "moderators";
+pub const MODERATION_ACTION: &str = "moderation-action";
+pub const TOMBSTONES: &str = "tombstones";
+pub const BLOCKED_RELAYS: &str = "blocked-relays";
+pub const BLOCKED_MEDIA_HOSTS: &str = "blocked-media-hosts";
+pub const HIDDEN_CLIENTS: &str = "hidden-clients";
+pub const CLIENT_TAG: &str = "client-tag";
+pub const RETENTION_KINDS: &str = "retention-kinds";
+pub const RETENTION_MONTHS: &str = "retention-months";
+pub const RETENTION_KEEP_TRUSTED: &str = "retention-keep-trusted";
+pub const PREFETCH: &str = "prefetch";
+pub const PREFETCH_HOURS: &str = "prefetch-hours";
+pub const PREFETCH_IMAGES: &str = "prefetch-images";
+pub const PREFETCH_UNMETERED: &str = "prefetch-unmetered";
+
+pub fn register(settings: &mut Settings) {
+    settings.register(
+        SettingsSection::new(APP, tr!("settings-columns"))
+            .setting(
+                Setting::toggle(AUTOSAVE_DRAFTS, tr!("settings-autosave-drafts"), true)
+                    .description(tr!("settings-autosave-drafts-hint")),
+            )
+            .setting(
+                Setting::toggle(STARTER_COLUMNS, tr!("settings-starter-columns"), true)
+                    .description(tr!("settings-starter-columns-hint")),
+            )
+            .setting(
+                Setting::select(
+                    LANDING,
+                    tr!("settings-landing"),
+                    vec![
+                        ("last".to_owned(), tr!("settings-landing-last")),
+                        ("first".to_owned(), tr!("settings-landing-first")),
+                        ("home".to_owned(), tr!("settings-landing-home")),
+                        (
+                            "notifications".to_owned(),
+                            tr!("settings-landing-notifications"),
+                        ),
+                        ("posts".to_owned(), tr!("settings-landing-posts")),
+                    ],
+                    "last",
+                )
+                .description(tr!("settings-landing-hint"))
+                .per_account(),
+            )
+            .setting(
+                Setting::toggle(TRUSTED_HASHTAGS, tr!("settings-trusted-hashtags"), true)
+                    .description(tr!("settings-trusted-hashtags-hint"))
+                    .per_account(),
+            )
+            .setting(
+                Setting::slider(WOT_DEPTH, tr!("settings-wot-depth"), 1.0..=3.0, 1.0, 2.0)
+                    .description(tr!("settings-wot-depth-hint"))
+                    .per_account(),
+            )
+            .setting(
+                Setting::toggle(WOT_FOLLOWERS, tr!("settings-wot-followers"), false)
+                    .description(tr!("settings-wot-followers-hint"))
+                    .per_account(),
+            )
+            .setting(
+                Setting::slider(
+                    WOT_MIN_FOLLOWS,
+                    tr!("settings-wot-min-follows"),
+                    0.0..=10.0,
+                    1.0,
+                    0.0,
+                )
+                .description(tr!("settings-wot-min-follows-hint"))
+                .per_account(),
+            )
+            .setting(
+                Setting::text(GEOCODER, tr!("settings-geocoder"), geo::DEFAULT_GEOCODER)
+                    .description(tr!("settings-geocoder-hint")),
+            )
+            .setting(
+                Setting::text(MAP_TILES, tr!("settings-map-tiles"), geo::DEFAULT_MAP_TILES)
+                    .description(tr!("settings-map-tiles-hint")),
+            )
+            .setting(
+                Setting::text(GIF_SEARCH, tr!("settings-gif-search"), "")
+                    .description(tr!("settings-gif-search-hint")),
+            )
+            .setting(
+                Setting::select(
+                    ANIMATION,
+                    tr!("settings-animation"),
+                    vec![
+                        ("always".to_owned(), tr!("settings-animation-always")),
+                        ("hover".to_owned(), tr!("settings-animation-hover")),
+                        ("static".to_owned(), tr!("settings-animation-static")),
+                    ],
+                    "always",
+                )
+                .description(tr!("settings-animation-hint")),
+            )
+            .setting(
+                Setting::select(
+                    TRENDING_WINDOW,
+                    tr!("settings-trending-window"),
+                    vec![
+                        ("1".to_owned(), tr!("settings-trending-window-hour")),
+                        ("6".to_owned(), tr!("settings-trending-window-hours")),
+                        ("24".to_owned(), tr!("settings-trending-window-day")),
+                    ],
+                    "6",
+                )
+                .description(tr!("settings-trending-window-hint")),
+            )
+            .setting(
+                Setting::text(TRENDING_API, tr!("settings-trending-api"), "")
+                    .description(tr!("settings-trending-api-hint")),
+            )
+            .setting(
+                Setting::toggle(SPELLCHECK, tr!("settings-spellcheck"), true)
+                    .description(tr!("settings-spellcheck-hint")),
+            )
+            .setting(
+                Setting::text(SPELLCHECK_LANGUAGE, tr!("settings-spellcheck-language"), "")
+                    .description(tr!("settings-spellcheck-language-hint")),
+            )
+            .setting(
+                Setting::text(MODERATORS, tr!("settings-moderators"), "")
+                    .description(tr!("settings-moderators-hint"))
+                    .per_account(),
+            )
+            .setting(
+                Setting::select(
+                    MODERATION_ACTION,
+                    tr!("settings-moderation-action"),
+                    vec![
+                        ("off".to_owned(), tr!("settings-moderation-off")),
+                        ("blur".to_owned(), tr!("settings-moderation-blur")),
+                        ("hide".to_owned(), tr!("settings-moderation-hide")),
+                    ],
+                    "blur",
+                )
+                .description(tr!("settings-moderation-action-hint"))
+                .per_account(),
+            )
+            .setting(
+                Setting::toggle(TOMBSTONES, tr!("settings-tombstones"), true)
+                    .description(tr!("settings-tombstones-hint"))
+                    .per_account(),
+            )
+            .setting(
+                Setting::text(BLOCKED_RELAYS, tr!("settings-blocked-relays"), "")
+                    .description(tr!("settings-blocked-relays-hint"))
+                    .per_account(),
+            )
+            .setting(
+                Setting::text(BLOCKED_MEDIA_HOSTS, tr!("settings-blocked-media-hosts"), "")
+                    .description(tr!("settings-blocked-media-hosts-hint"))
+                    .per_account(),
+            )
+            .setting(
+                Setting::text(HIDDEN_CLIENTS, tr!("settings-hidden-clients"), "")
+                    .description(tr!("settings-hidden-clients-hint"))
+                    .per_account(),
+            )
+            .setting(
+                Setting::toggle(CLIENT_TAG, tr!("settings-client-tag"), true)
+                    .description(tr!("settings-client-tag-hint"))
+                    .per_account(),
+            )
+            .setting(
+                Setting::text(RETENTION_KINDS, tr!("settings-retention-kinds"), "1")
+                    .description(tr!("settings-retention-kinds-hint")),
+            )
+            .setting(Setting::slider(
+                RETENTION_MONTHS,
+                tr!("settings-retention-months"),
+                1.0..=60.0,
+                1.0,
+                6.0,
+            ))
+            .setting(
+                Setting::toggle(
+                    RETENTION_KEEP_TRUSTED,
+                    tr!("settings-retention-keep-trusted"),
+                    true,
+                )
+                .description(tr!("settings-retention-keep-trusted-hint")),
+            )
+            .setting(
+                Setting::toggle(PREFETCH, tr!("settings-prefetch"), false)
+                    .description(tr!("settings-prefetch-hint")),
+            )
+            .setting(Setting::slider(
+                PREFETCH_HOURS,
+                tr!("settings-prefetch-hours"),
+                1.0..=72.0,
+                1.0,
+                24.0,
+            ))
+            .setting(Setting::toggle(
+                PREFETCH_IMAGES,
+                tr!("settings-prefetch-images"),
+                true,
+            ))
+            .setting(
+                Setting::toggle(PREFETCH_UNMETERED, tr!("settings-prefetch-unmetered"), true)
+                    .description(tr!("settings-prefetch-unmetered-hint")),
+            ),
+    );
+}
+
+/// Who hashtag columns trust, from the account's settings
+pub fn wot_params(settings: &Settings) -> WotParams {
+    WotParams {
+        depth: settings.number(APP, WOT_DEPTH) as u8,
+        followers_of_follows: settings.bool(APP, WOT_FOLLOWERS),
+        min_follows: settings.number(APP, WOT_MIN_FOLLOWS) as u32,
+    }
+}
+
+/// What compacting the database drops
+pub fn retention(settings: &Settings) -> Retention {
+    Retention {
+        kinds: Retention::parse_kinds(&settings.string(APP, RETENTION_KINDS)),
+        older_than_months: settings.number(APP, RETENTION_MONTHS).max(1.0) as u32,
+        keep_trusted: settings.bool(APP, RETENTION_KEEP_TRUSTED),
+    }
+}
+
+/// What to save for reading offline, `None` if that's off
+pub fn prefetch(settings: &Settings) -> Option<PrefetchParams> {
+    settings.bool(APP, PREFETCH).then(|| PrefetchParams {
+        hours: settings.number(APP, PREFETCH_HOURS).max(1.0) as u64,
+        images: settings.bool(APP, PREFETCH_IMAGES),
+        unmetered_only: settings.bool(APP, PREFETCH_UNMETERED),
+    })
+}
+
+/// The relays and media hosts the account has blocked
+pub fn blocklist(settings: &Settings) -> Blocklist {
+    Blocklist::from_settings(
+        &settings.string(APP, BLOCKED_RELAYS),
+        &settings.string(APP, BLOCKED_MEDIA_HOSTS),
+    )
+}
+
+/// The clients whose notes the account doesn't want in its columns
+pub fn hidden_clients(settings: &Settings) -> HiddenClients {
+    HiddenClients::from_setting(&settings.string(APP, HIDDEN_CLIENTS))
+}
+
+/// Replace the account's blocklist
+pub fn set_blocklist(settings: &mut Settings, blocklist: &Blocklist) {
+    settings.set(APP, BLOCKED_RELAYS, blocklist.relays_setting().into());
+    settings.set(
+        APP,
+        BLOCKED_MEDIA_HOSTS,
+        blocklist.media_hosts_setting().into(),
+    );
+}
+
+/// Which dictionary to check spelling with, `None` if spellcheck is off.
+/// Follows the app language unless one is picked.
+pub fn spellcheck_language(settings: &Settings) -> Option<String> {
+    if !settings.bool(APP, SPELLCHECK) {
+        return None;
+    }
+
+    let picked = settings.string(APP, SPELLCHECK_LANGUAGE);
+    let tag = if picked.trim().is_empty() {
+        i18n::current_language().id.to_owned()
+    } else {
+        picked.trim().to_owned()
+    };
+    Some(spell::dictionary_language(&tag))
+}
@@ -0,0 +1,465 @@
+//! Settings that roam between devices. Column layout and preferences are
+//! kept in a NIP-78 app data event, encrypted to the account itself with
+//! NIP-44. When this device and the relays disagree, whichever side
+//! changed last wins.
+
+use std::time::{Duration, Instant};
+
+use egui::ThemePreference;
+use enostr::{FilledKeypair, Pubkey, RelayPool};
+use nostrdb::{Filter, Ndb, NoteBuilder, Transaction};
+use notedeck::{
+    amount::{self, AmountSettings},
+    datetime::{self, TimeSettings},
+    time::unix_now,
+    toast, tr, Accounts, AppContext, Capability, DataPath, PermissionError,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{hashtags::HashtagSettings, storage, timeline, Damus};
+
+pub const APP_DATA_KIND: u32 = 30078;
+const D_TAG: &str = "notedeck/settings";
+/// Let a burst of changes settle before publishing
+const DEBOUNCE_SECS: u64 = 30;
+/// How often to look for local changes
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// How long to wait after the first failed publish, doubled for each
+/// failure after it
+const RETRY_BACKOFF: Duration = Duration::from_secs(60);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// What roams. Everything is optional so a copy from an older version
+/// doesn't wipe out what it didn't know about.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoamingSettings {
+    pub theme: Option<String>,
+    pub settings: Map<String, Value>,
+    pub decks: Option<Value>,
+    pub hashtags: Option<HashtagSettings>,
+    pub time: Option<TimeSettings>,
+    pub amounts: Option<AmountSettings>,
+}
+
+/// Sync bookkeeping for one account on this device
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncState {
+    pub enabled: bool,
+    /// When the relay copy we last published or applied was made
+    pub synced_at: u64,
+    /// When the settings here last changed
+    pub changed_at: u64,
+    /// Hash of the settings as of `changed_at`
+    hash: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncRequest {
+    /// Publish or apply, whichever side is newer
+    Now,
+    /// Take the relay copy even if this device changed since
+    Restore,
+}
+
+#[derive(Default)]
+pub struct SettingsSync {
+    account: Option<(Pubkey, String)>,
+    pub state: SyncState,
+    last_check: Option<Instant>,
+    request: Option<SyncRequest>,
+    /// Publishes that failed in a row, and when to try again
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl SettingsSync {
+    pub fn request(&mut self, request: SyncRequest) {
+        self.request = Some(request);
+    }
+
+    fn publish_failed(&mut self) {
+        self.failures += 1;
+        self.retry_at = Some(Instant::now() + backoff(self.failures));
+    }
+
+    fn may_retry(&self) -> bool {
+        match self.retry_at {
+            Some(at) => Instant::now() >= at,
+            None => true,
+        }
+    }
+
+    pub fn set_enabled(&mut self, path: &DataPath, enabled: bool) {
+        self.state.enabled = enabled;
+        if let Some((pubkey, _)) = &self.account {
+            storage::save_sync_state(path, pubkey, &self.state);
+        }
+        if enabled {
+            self.request = Some(SyncRequest::Now);
+        }
+    }
+
+    /// Follow the selected account, asking relays for its settings
+    fn follow(&mut self, path: &DataPath, pool: &mut RelayPool, selected: Option<&Pubkey>) {
+        if self.account.as_ref().map(|(pk, _)| pk) == selected {
+            return;
+        }
+
+        if let Some((_, subid)) = self.account.take() {
+            pool.unsubscribe(subid);
+        }
+        self.state = SyncState::default();
+        self.last_check = None;
+        self.failures = 0;
+        self.retry_at = None;
+
+        if let Some(pubkey) = selected {
+            let subid = Uuid::new_v4().to_string();
+            pool.subscribe(subid.clone(), vec![filter(pubkey)]);
+            self.state = storage::load_sync_state(path, pubkey);
+            self.account = Some((*pubkey, subid));
+        }
+    }
+}
+
+fn backoff(failures: u32) -> Duration {
+    RETRY_BACKOFF
+        .saturating_mul(1 << failures.saturating_sub(1).min(16))
+        .min(MAX_RETRY_BACKOFF)
+}
+
+fn filter(pubkey: &Pubkey) -> Filter {
+    Filter::new()
+        .authors([pubkey.bytes()])
+        .kinds([APP_DATA_KIND as u64])
+        .tags([D_TAG.to_owned()], 'd')
+        .limit(1)
+        .build()
+}
+
+fn theme_name(theme: ThemePreference) -> &'static str {
+    match theme {
+        ThemePreference::Dark => "dark",
+        ThemePreference::Light => "light",
+        ThemePreference::System => "system",
+    }
+}
+
+fn parse_theme(name: &str) -> Option<ThemePreference> {
+    match name {
+        "dark" => Some(ThemePreference::Dark),
+        "light" => Some(ThemePreference::Light),
+        "system" => Some(ThemePreference::System),
+        _ => None,
+    }
+}
+
+fn hash(settings: &RoamingSettings) -> String {
+    let json = serde_json::to_string(settings).unwrap_or_default();
+    hex::encode(Sha256::digest(json.as_bytes()))
+}
+
+fn collect(damus: &Damus, ctx: &AppContext<'_>, pubkey: &Pubkey) -> RoamingSettings {
+    RoamingSettings {
+        theme: Some(theme_name(ctx.theme.load()).to_owned()),
+        settings: ctx.settings.snapshot(),
+        decks: storage::decks_to_value(damus.decks_cache.decks(pubkey)),
+        hashtags: Some(damus.hashtag_settings.clone()),
+        time: Some(datetime::settings()),
+        amounts: Some(amount::settings()),
+    }
+}
+
+/// The newest copy on relays that we can read
+fn latest_remote(ndb: &Ndb, keypair: &FilledKeypair) -> Option<(u64, RoamingSettings)> {
+    let txn = Transaction::new(ndb).ok()?;
+    let results = ndb.query(&txn, &[filter(keypair.pubkey)], 1).ok()?;
+    let note = &results.first()?.note;
+
+    let json = match keypair.nip44_decrypt(keypair.pubkey, note.content()) {
+        Ok(json) => json,
+        Err(err) => {
+            error!("could not decrypt synced settings: {err}");
+            return None;
+        }
+    };
+
+    match serde_json::from_str(&json) {
+        Ok(settings) => Some((note.created_at(), settings)),
+        Err(err) => {
+            error!("could not read synced settings: {err}");
+            None
+        }
+    }
+}
+
+fn publish(
+    accounts: &Accounts,
+    ndb: &Ndb,
+    pool: &mut RelayPool,
+    keypair: &FilledKeypair,
+    settings: &RoamingSettings,
+) -> Option<u64> {
+    let json = serde_json::to_string(settings).ok()?;
+    let content = match keypair.nip44_encrypt(keypair.pubkey, &json) {
+        Ok(content) => content,
+        Err(err) => {
+            error!("could not encrypt settings: {err}");
+            return None;
+        }
+    };
+
+    let note = NoteBuilder::new()
+        .kind(APP_DATA_KIND)
+        .content(&content)
+        .start_tag()
+        .tag_str("d")
+        .tag_str(D_TAG)
+        .sign(&keypair.secret_key.to_secret_bytes())
+        .build()?;
+
+    if let Err(err) = accounts.publish_note(ndb, pool, &note) {
+        error!("could not publish settings: {err}");
+        return None;
+    }
+
+    Some(note.created_at())
+}
+
+/// Swap in synced settings, setting up timelines for a synced layout
+fn apply(
+    damus: &mut Damus,
+    ctx: &mut AppContext<'_>,
+    ui_ctx: &egui::Context,
+    pubkey: &Pubkey,
+    synced: RoamingSettings,
+) {
+    if let Some(theme) = synced.theme.as_deref().and_then(parse_theme) {
+        if theme != ctx.theme.load() {
+            ui_ctx.set_theme(theme);
+            ctx.theme.save(theme);
+        }
+    }
+
+    ctx.settings.restore(&synced.settings);
+
+    if let Some(hashtags) = synced.hashtags {
+        if hashtags != damus.hashtag_settings {
+            storage::save_hashtag_settings(ctx.path, &hashtags);
+            damus.hashtag_settings = hashtags;
+        }
+    }
+
+    if let Some(time) = synced.time.filter(|time| *time != datetime::settings()) {
+        datetime::set_settings(ctx.path, time);
+    }
+
+    if let Some(amounts) = synced.amounts.filter(|a| *a != amount::settings()) {
+        amount::set_settings(ctx.path, amounts);
+    }
+
+    let current = storage::decks_to_value(damus.decks_cache.decks(pubkey));
+    let Some(value) = synced.decks.filter(|decks| Some(decks) != current.as_ref()) else {
+        return;
+    };
+    let Some(mut decks) = storage::decks_from_value(value, ctx.ndb, pubkey) else {
+        error!("could not read synced decks");
+        return;
+    };
+
+    for deck in damus.decks_cache.decks_mut(pubkey).decks_mut() {
+        for timeline in deck.columns_mut().timelines_mut() {
+            for sub_id in damus.subscriptions.untrack_timeline(timeline.id) {
+                ctx.pool.unsubscribe(sub_id);
+            }
+            if let Some(sub) = timeline.subscription.take() {
+                if let Err(err) = ctx.ndb.unsubscribe(sub) {
+                    error!("could not unsubscribe a replaced timeline: {err}");
+                }
+            }
+        }
+    }
+
+    for deck in decks.decks_mut() {
+        for timeline in deck.columns_mut().timelines_mut() {
+            timeline::setup_new_timeline(
+                timeline,
+                ctx.ndb,
+                &mut damus.subscriptions,
+                ctx.pool,
+                ctx.note_cache,
+                damus.since_optimize,
+                Some(pubkey),
+            );
+        }
+    }
+
+    damus.decks_cache.add_decks(*pubkey, decks);
+    if !damus.tmp_columns {
+        storage::save_decks_cache(ctx.path, &damus.decks_cache);
+    }
+}
+
+/// Called every frame. Notices local changes, publishes them once they
+/// settle and applies newer copies from relays.
+pub fn update(damus: &mut Damus, ctx: &mut AppContext<'_>, ui_ctx: &egui::Context) {
+    let selected = ctx.accounts.get_selected_account().map(|a| a.pubkey);
+    damus
+        .settings_sync
+        .follow(ctx.path, ctx.pool, selected.as_ref());
+    let Some(pubkey) = selected else {
+        return;
+    };
+
+    let request = damus.settings_sync.request.take();
+    if !damus.settings_sync.state.enabled && request.is_none() {
+        return;
+    }
+
+    let checked_recently = matches!(
+        damus.settings_sync.last_check,
+        Some(at) if at.elapsed() < CHECK_INTERVAL
+    );
+    if checked_recently && request.is_none() {
+        return;
+    }
+    damus.settings_sync.last_check = Some(Instant::now());
+
+    // settings are encrypted to ourselves, watch-only accounts can't read them
+    let Some(keypair) = ctx
+        .accounts
+        .get_selected_account()
+        .and_then(|a| a.to_full())
+        .map(|kp| kp.to_full())
+    else {
+        if request.is_some() {
+            toast::warning(tr!("toast-sync-needs-key"));
+        }
+        return;
+    };
+    let keypair = keypair.to_filled();
+
+    let now = unix_now();
+    let local = collect(damus, ctx, &pubkey);
+    let local_hash = hash(&local);
+    let state = &mut damus.settings_sync.state;
+    if local_hash != state.hash {
+        // the first look on this device counts as older than anything
+        // on relays, so a new install takes the synced settings
+        state.changed_at = if state.hash.is_empty() { 0 } else { now };
+        state.hash = local_hash;
+        storage::save_sync_state(ctx.path, &pubkey, state);
+    }
+
+    let remote = latest_remote(ctx.ndb, &keypair);
+    let remote_at = remote.as_ref().map(|(at, _)| *at).unwrap_or_default();
+    let state = damus.settings_sync.state.clone();
+
+    let take_remote = match request {
+        Some(SyncRequest::Restore) => true,
+        _ => remote_at > state.synced_at && remote_at > state.changed_at,
+    };
+
+    if take_remote {
+        let Some((at, synced)) = remote else {
+            toast::info(tr!("toast-sync-nothing-to-restore"));
+            return;
+        };
+        info!("applying settings synced at {at}");
+        apply(damus, ctx, ui_ctx, &pubkey, synced);
+
+        let applied = hash(&collect(damus, ctx, &pubkey));
+        let state = &mut damus.settings_sync.state;
+        state.hash = applied;
+        state.changed_at = at;
+        state.synced_at = at;
+        storage::save_sync_state(ctx.path, &pubkey, state);
+        if request.is_some() {
+            toast::info(tr!("toast-sync-restored"));
+        }
+        return;
+    }
+
+    let settled = now.saturating_sub(state.changed_at) >= DEBOUNCE_SECS;
+    let may_retry = damus.settings_sync.may_retry();
+    if state.changed_at > state.synced_at && ((settled && may_retry) || request.is_some()) {
+        // nothing is encrypted or signed until the user has said yes, and
+        // while they're being asked we just look again next time
+        match ctx.accounts.permissions().check(Capability::Publish) {
+            Ok(()) => {}
+            Err(PermissionError::Asking(_)) => return,
+            Err(err) => {
+                damus.settings_sync.publish_failed();
+                if request.is_some() {
+                    toast::warning(err.message());
+                }
+                return;
+            }
+        }
+
+        info!("publishing settings changed at {}", state.changed_at);
+        let Some(at) = publish(ctx.accounts, ctx.ndb, ctx.pool, &keypair, &local) else {
+            damus.settings_sync.publish_failed();
+            return;
+        };
+        damus.settings_sync.failures = 0;
+        damus.settings_sync.retry_at = None;
+        damus.settings_sync.state.synced_at = at;
+        storage::save_sync_state(ctx.path, &pubkey, &damus.settings_sync.state);
+        if request.is_some() {
+            toast::info(tr!("toast-sync-published"));
+        }
+    } else if request.is_some() {
+        toast::info(tr!("toast-sync-up-to-date"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roaming_settings_round_trip() {
+        let settings = RoamingSettings {
+            theme: Some("light".to_owned()),
+            settings: Map::from_iter([("columns.gif-search".to_owned(), Value::from(""))]),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(
+            serde_json::from_str::<RoamingSettings>(&json).unwrap(),
+            settings
+        );
+        // a copy from a version that synced less still reads
+        assert_eq!(
+            serde_json::from_str::<RoamingSettings>(r#"{"theme":"light"}"#)
+                .unwrap()
+                .theme
+                .as_deref(),
+            Some("light")
+        );
+        assert_eq!(
+            parse_theme(theme_name(ThemePreference::System)),
+            Some(ThemePreference::System)
+        );
+    }
+
+    #[test]
+    fn test_publish_backoff() {
+        assert_eq!(backoff(1), RETRY_BACKOFF);
+        assert_eq!(backoff(2), RETRY_BACKOFF * 2);
+        assert_eq!(backoff(100), MAX_RETRY_BACKOFF);
+
+        let mut sync = SettingsSync::default();
+        assert!(sync.may_retry());
+        sync.publish_failed();
+        assert!(!sync.may_retry());
+    }
+}
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, fmt, str::FromStr};
 
 use enostr::{NoteId, Pubkey};
-use nostrdb::Ndb;
+use nostrdb::{Ndb, Transaction};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 
@@ -9,9 +9,10 @@ use crate::{
     accounts::AccountsRoute,
     column::{Columns, IntermediaryRoute},
     decks::{Deck, Decks, DecksCache},
+    moderation::ReportTarget,
     route::Route,
-    timeline::{kind::ListKind, PubkeySource, TimelineKind, TimelineRoute},
-    ui::add_column::AddColumnRoute,
+    timeline::{kind::ListKind, ContentKinds, PubkeySource, TimelineKind, TimelineRoute},
+    ui::{add_column::AddColumnRoute, density::Density},
     Error,
 };
 
@@ -65,6 +66,83 @@ pub fn save_decks_cache(path: &DataPath, decks_cache: &DecksCache) {
     }
 }
 
+/// One account's decks in the same shape they're saved in
+pub fn decks_to_value(decks: &Decks) -> Option<serde_json::Value> {
+    serde_json::to_value(SerializableDecks::from_decks(decks)).ok()
+}
+
+pub fn decks_from_value(value: serde_json::Value, ndb: &Ndb, deck_user: &Pubkey) -> Option<Decks> {
+    serde_json::from_value::<SerializableDecks>(value)
+        .ok()?
+        .decks(ndb, deck_user.bytes())
+        .ok()
+}
+
+/// Bumped when shared decks change in a way older versions can't read
+const SHARED_DECK_VERSION: u32 = 1;
+
+/// A single deck as handed to someone else. It's a saved deck with a
+/// version on it, so a deck from a newer notedeck is refused rather than
+/// half read.
+#[derive(Serialize, Deserialize)]
+struct SharedDeck {
+    notedeck_deck: u32,
+    #[serde(flatten)]
+    deck: SerializableDeck,
+}
+
+pub fn deck_to_json(deck: &Deck) -> Option<String> {
+    serde_json::to_string_pretty(&SharedDeck {
+        notedeck_deck: SHARED_DECK_VERSION,
+        deck: SerializableDeck::from_deck(deck),
+    })
+    .ok()
+}
+
+/// A shared deck, read as if it were `deck_user`'s own
+pub struct ImportedDeck {
+    pub deck: Deck,
+    /// Columns that didn't make sense here and were left out
+    pub skipped: usize,
+    /// People the columns are about that we don't have a profile for yet
+    pub unknown: Vec<Pubkey>,
+}
+
+fn parse_shared_deck(json: &str) -> Result<SerializableDeck, Error> {
+    let shared: SharedDeck = serde_json::from_str(json)
+        .map_err(|err| Error::Generic(format!("not a shared deck: {err}")))?;
+    if shared.notedeck_deck > SHARED_DECK_VERSION {
+        return Err(Error::Generic(
+            "deck was shared from a newer version of notedeck".to_owned(),
+        ));
+    }
+    Ok(shared.deck)
+}
+
+pub fn deck_from_json(json: &str, ndb: &Ndb, deck_user: &Pubkey) -> Result<ImportedDeck, Error> {
+    let (deck, skipped) = parse_shared_deck(json)?.read(ndb, deck_user.bytes())?;
+    if deck.columns().num_columns() == 0 {
+        return Err(Error::Generic("deck has no columns we can show".to_owned()));
+    }
+
+    let txn = Transaction::new(ndb)?;
+    let mut unknown: Vec<Pubkey> = Vec::new();
+    for timeline in deck.columns().timelines() {
+        let Some(PubkeySource::Explicit(pubkey)) = timeline.kind.pubkey_source() else {
+            continue;
+        };
+        if !unknown.contains(pubkey) && ndb.get_profile_by_pubkey(&txn, pubkey.bytes()).is_err() {
+            unknown.push(*pubkey);
+        }
+    }
+
+    Ok(ImportedDeck {
+        deck,
+        skipped,
+        unknown,
+    })
+}
+
 #[derive(Serialize, Deserialize)]
 struct SerializableDecksCache {
     #[serde(serialize_with = "serialize_map", deserialize_with = "deserialize_map")]
@@ -158,18 +236,23 @@ impl SerializableDecks {
 struct SerializableDeck {
     metadata: Vec<String>,
     columns: Vec<Vec<String>>,
+    /// What each timeline column shows, where it's not the default
+    #[serde(default)]
+    column_kinds: Vec<Option<ContentKinds>>,
 }
 
 #[derive(PartialEq, Clone)]
 enum MetadataKeyword {
     Icon,
     Name,
+    Density,
 }
 
 impl MetadataKeyword {
     const MAPPING: &'static [(&'static str, MetadataKeyword)] = &[
         ("icon", MetadataKeyword::Icon),
         ("name", MetadataKeyword::Name),
+        ("density", MetadataKeyword::Density),
     ];
 }
 impl fmt::Display for MetadataKeyword {
@@ -244,17 +327,29 @@ fn deserialize_metadata(serialized_metadatas: Vec<String>) -> Option<Vec<Metadat
 impl SerializableDeck {
     pub fn from_deck(deck: &Deck) -> Self {
         let columns = serialize_columns(deck.columns());
+        let column_kinds = serialize_column_kinds(deck.columns());
 
         let metadata = serialize_metadata(vec![
             MetadataPayload::new(MetadataKeyword::Icon, deck.icon.to_string()),
             MetadataPayload::new(MetadataKeyword::Name, deck.name.clone()),
+            MetadataPayload::new(MetadataKeyword::Density, deck.density.as_str().to_owned()),
         ]);
 
-        SerializableDeck { metadata, columns }
+        SerializableDeck {
+            metadata,
+            columns,
+            column_kinds,
+        }
     }
 
     pub fn deck(self, ndb: &Ndb, deck_user: &[u8; 32]) -> Result<Deck, Error> {
-        let columns = deserialize_columns(ndb, deck_user, self.columns);
+        self.read(ndb, deck_user).map(|(deck, _skipped)| deck)
+    }
+
+    /// The deck, and how many of its columns couldn't be read
+    fn read(self, ndb: &Ndb, deck_user: &[u8; 32]) -> Result<(Deck, usize), Error> {
+        let (columns, skipped) =
+            deserialize_columns(ndb, deck_user, self.columns, self.column_kinds);
         let deserialized_metadata = deserialize_metadata(self.metadata)
             .ok_or(Error::Generic("Could not deserialize metadata".to_owned()))?;
 
@@ -268,12 +363,20 @@ impl SerializableDeck {
             .map_or_else(|| "Deck", |f| &f.value)
             .to_string();
 
-        Ok(Deck::new_with_columns(
+        let density = deserialized_metadata
+            .iter()
+            .find(|p| p.keyword == MetadataKeyword::Density)
+            .and_then(|p| Density::parse(&p.value))
+            .unwrap_or_default();
+
+        let mut deck = Deck::new_with_columns(
             icon.parse::<char>()
                 .map_err(|_| Error::Generic("could not convert String -> char".to_owned()))?,
             name,
             columns,
-        ))
+        );
+        deck.density = density;
+        Ok((deck, skipped))
     }
 }
 
@@ -293,20 +396,45 @@ fn serialize_columns(columns: &Columns) -> Vec<Vec<String>> {
     cols_serialized
 }
 
-fn deserialize_columns(ndb: &Ndb, deck_user: &[u8; 32], serialized: Vec<Vec<String>>) -> Columns {
+fn serialize_column_kinds(columns: &Columns) -> Vec<Option<ContentKinds>> {
+    (0..columns.num_columns())
+        .map(|ind| {
+            columns
+                .find_timeline_for_column_index(ind)
+                .map(|timeline| timeline.kinds)
+                .filter(|kinds| *kinds != ContentKinds::default())
+        })
+        .collect()
+}
+
+fn deserialize_columns(
+    ndb: &Ndb,
+    deck_user: &[u8; 32],
+    serialized: Vec<Vec<String>>,
+    column_kinds: Vec<Option<ContentKinds>>,
+) -> (Columns, usize) {
     let mut cols = Columns::new();
-    for serialized_routes in serialized {
+    let mut skipped = 0;
+    for (ind, serialized_routes) in serialized.into_iter().enumerate() {
+        let kinds = column_kinds.get(ind).copied().flatten();
         let mut cur_routes = Vec::new();
         for serialized_route in serialized_routes {
             let selections = Selection::from_serialized(&serialized_route);
             if let Some(route_intermediary) = selections_to_route(selections.clone()) {
                 if let Some(ir) = route_intermediary.intermediary_route(ndb, Some(deck_user)) {
-                    match &ir {
+                    match ir {
                         IntermediaryRoute::Route(Route::Timeline(TimelineRoute::Thread(_)))
                         | IntermediaryRoute::Route(Route::Timeline(TimelineRoute::Profile(_))) => {
                             // Do nothing. TimelineRoute Threads & Profiles not yet supported for deserialization
                         }
-                        _ => cur_routes.push(ir),
+                        IntermediaryRoute::Timeline(timeline) => {
+                            let timeline = match kinds {
+                                Some(kinds) => timeline.with_kinds(kinds),
+                                None => timeline,
+                            };
+                            cur_routes.push(IntermediaryRoute::Timeline(timeline));
+                        }
+                        ir => cur_routes.push(ir),
                     }
                 }
             } else {
@@ -317,12 +445,14 @@ fn deserialize_columns(ndb: &Ndb, deck_user: &[u8; 32], serialized: Vec<Vec<Stri
             }
         }
 
-        if !cur_routes.is_empty() {
+        if cur_routes.is_empty() {
+            skipped += 1;
+        } else {
             cols.insert_intermediary_routes(cur_routes);
         }
     }
 
-    cols
+    (cols, skipped)
 }
 
 #[derive(Clone, Debug)]
@@ -358,6 +488,25 @@ enum Keyword {
     Edit,
     IndividualSelection,
     ExternalIndividualSelection,
+    NotificationSettings,
+    Settings,
+    Poster,
+    Source,
+    Highlight,
+    Highlights,
+    Subscriptions,
+    Wallet,
+    Bookmarks,
+    SaveBookmark,
+    Hidden,
+    ExportData,
+    Storage,
+    Report,
+    MyPosts,
+    ExplorePeople,
+    Trending,
+    RelayGroups,
+    EditNote,
 }
 
 impl Keyword {
@@ -393,6 +542,29 @@ impl Keyword {
         ("support", Keyword::Support, false),
         ("deck", Keyword::Deck, false),
         ("edit", Keyword::Edit, true),
+        (
+            "notification_settings",
+            Keyword::NotificationSettings,
+            false,
+        ),
+        ("settings", Keyword::Settings, false),
+        ("poster", Keyword::Poster, true),
+        ("source", Keyword::Source, true),
+        ("highlight", Keyword::Highlight, true),
+        ("highlights", Keyword::Highlights, true),
+        ("subscriptions", Keyword::Subscriptions, false),
+        ("wallet", Keyword::Wallet, false),
+        ("bookmarks", Keyword::Bookmarks, false),
+        ("save_bookmark", Keyword::SaveBookmark, true),
+        ("hidden", Keyword::Hidden, false),
+        ("export_data", Keyword::ExportData, false),
+        ("storage", Keyword::Storage, false),
+        ("report", Keyword::Report, true),
+        ("my_posts", Keyword::MyPosts, false),
+        ("explore_people", Keyword::ExplorePeople, false),
+        ("trending", Keyword::Trending, false),
+        ("relay_groups", Keyword::RelayGroups, false),
+        ("edit_note", Keyword::EditNote, true),
     ];
 
     fn has_payload(&self) -> bool {
@@ -536,6 +708,11 @@ fn serialize_route(route: &Route, columns: &Columns) -> Option<String> {
             }
         }
         Route::Support => selections.push(Selection::Keyword(Keyword::Support)),
+        Route::Settings => selections.push(Selection::Keyword(Keyword::Settings)),
+        Route::NotificationSettings => {
+            selections.push(Selection::Keyword(Keyword::NotificationSettings))
+        }
+        Route::RelayGroups => selections.push(Selection::Keyword(Keyword::RelayGroups)),
         Route::NewDeck => {
             selections.push(Selection::Keyword(Keyword::Deck));
             selections.push(Selection::Keyword(Keyword::New));
@@ -550,6 +727,46 @@ fn serialize_route(route: &Route, columns: &Columns) -> Option<String> {
             selections.push(Selection::Keyword(Keyword::Edit));
             selections.push(Selection::Payload(pubkey.hex()));
         }
+        Route::Poster(note_id) => {
+            selections.push(Selection::Keyword(Keyword::Poster));
+            selections.push(Selection::Payload(note_id.hex()));
+        }
+        Route::Source(note_id) => {
+            selections.push(Selection::Keyword(Keyword::Source));
+            selections.push(Selection::Payload(note_id.hex()));
+        }
+        Route::EditNote(note_id) => {
+            selections.push(Selection::Keyword(Keyword::EditNote));
+            selections.push(Selection::Payload(note_id.hex()));
+        }
+        Route::Highlight(note_id) => {
+            selections.push(Selection::Keyword(Keyword::Highlight));
+            selections.push(Selection::Payload(note_id.hex()));
+        }
+        Route::Highlights(note_id) => {
+            selections.push(Selection::Keyword(Keyword::Highlights));
+            selections.push(Selection::Payload(note_id.hex()));
+        }
+        Route::Subscriptions => selections.push(Selection::Keyword(Keyword::Subscriptions)),
+        Route::Wallet => selections.push(Selection::Keyword(Keyword::Wallet)),
+        Route::Bookmarks => selections.push(Selection::Keyword(Keyword::Bookmarks)),
+        Route::SaveBookmark(note_id) => {
+            selections.push(Selection::Keyword(Keyword::SaveBookmark));
+            selections.push(Selection::Payload(note_id.hex()));
+        }
+        Route::Hidden => selections.push(Selection::Keyword(Keyword::Hidden)),
+        Route::ExportData => selections.push(Selection::Keyword(Keyword::ExportData)),
+        Route::Storage => selections.push(Selection::Keyword(Keyword::Storage)),
+        Route::MyPosts => selections.push(Selection::Keyword(Keyword::MyPosts)),
+        Route::ExplorePeople => selections.push(Selection::Keyword(Keyword::ExplorePeople)),
+        Route::Trending => selections.push(Selection::Keyword(Keyword::Trending)),
+        Route::Report(target) => {
+            // only the last part can be a payload, so the note id rides
+            // along after the pubkey
+            let note = target.note.map(|note| note.hex()).unwrap_or_default();
+            selections.push(Selection::Keyword(Keyword::Report));
+            selections.push(Selection::Payload(format!("{}{note}", target.pubkey.hex())));
+        }
     }
 
     if selections.is_empty() {
@@ -753,6 +970,109 @@ fn selections_to_route(selections: Vec<Selection>) -> Option<CleanIntermediaryRo
         Selection::Keyword(Keyword::Support) => {
             Some(CleanIntermediaryRoute::ToRoute(Route::Support))
         }
+        Selection::Keyword(Keyword::Settings) => {
+            Some(CleanIntermediaryRoute::ToRoute(Route::Settings))
+        }
+        Selection::Keyword(Keyword::NotificationSettings) => {
+            Some(CleanIntermediaryRoute::ToRoute(Route::NotificationSettings))
+        }
+        Selection::Keyword(Keyword::RelayGroups) => {
+            Some(CleanIntermediaryRoute::ToRoute(Route::RelayGroups))
+        }
+        Selection::Keyword(Keyword::Poster) => {
+            if let Selection::Payload(hex) = selections.get(1)? {
+                Some(CleanIntermediaryRoute::ToRoute(Route::Poster(
+                    NoteId::from_hex(hex.as_str()).ok()?,
+                )))
+            } else {
+                None
+            }
+        }
+        Selection::Keyword(Keyword::Subscriptions) => {
+            Some(CleanIntermediaryRoute::ToRoute(Route::Subscriptions))
+        }
+        Selection::Keyword(Keyword::Wallet) => Some(CleanIntermediaryRoute::ToRoute(Route::Wallet)),
+        Selection::Keyword(Keyword::Source) => {
+            if let Selection::Payload(hex) = selections.get(1)? {
+                Some(CleanIntermediaryRoute::ToRoute(Route::Source(
+                    NoteId::from_hex(hex.as_str()).ok()?,
+                )))
+            } else {
+                None
+            }
+        }
+        Selection::Keyword(Keyword::EditNote) => {
+            if let Selection::Payload(hex) = selections.get(1)? {
+                Some(CleanIntermediaryRoute::ToRoute(Route::EditNote(
+                    NoteId::from_hex(hex.as_str()).ok()?,
+                )))
+            } else {
+                None
+            }
+        }
+        Selection::Keyword(Keyword::Highlight) => {
+            if let Selection::Payload(hex) = selections.get(1)? {
+                Some(CleanIntermediaryRoute::ToRoute(Route::Highlight(
+                    NoteId::from_hex(hex.as_str()).ok()?,
+                )))
+            } else {
+                None
+            }
+        }
+        Selection::Keyword(Keyword::Highlights) => {
+            if let Selection::Payload(hex) = selections.get(1)? {
+                Some(CleanIntermediaryRoute::ToRoute(Route::Highlights(
+                    NoteId::from_hex(hex.as_str()).ok()?,
+                )))
+            } else {
+                None
+            }
+        }
+        Selection::Keyword(Keyword::Bookmarks) => {
+            Some(CleanIntermediaryRoute::ToRoute(Route::Bookmarks))
+        }
+        Selection::Keyword(Keyword::Hidden) => Some(CleanIntermediaryRoute::ToRoute(Route::Hidden)),
+        Selection::Keyword(Keyword::ExportData) => {
+            Some(CleanIntermediaryRoute::ToRoute(Route::ExportData))
+        }
+        Selection::Keyword(Keyword::Storage) => {
+            Some(CleanIntermediaryRoute::ToRoute(Route::Storage))
+        }
+        Selection::Keyword(Keyword::MyPosts) => {
+            Some(CleanIntermediaryRoute::ToRoute(Route::MyPosts))
+        }
+        Selection::Keyword(Keyword::ExplorePeople) => {
+            Some(CleanIntermediaryRoute::ToRoute(Route::ExplorePeople))
+        }
+        Selection::Keyword(Keyword::Trending) => {
+            Some(CleanIntermediaryRoute::ToRoute(Route::Trending))
+        }
+        Selection::Keyword(Keyword::Report) => {
+            let Selection::Payload(payload) = selections.get(1)? else {
+                return None;
+            };
+            let (pubkey, note) = (payload.get(..64)?, payload.get(64..)?);
+            let note = if note.is_empty() {
+                None
+            } else {
+                Some(NoteId::from_hex(note).ok()?)
+            };
+            Some(CleanIntermediaryRoute::ToRoute(Route::Report(
+                ReportTarget {
+                    pubkey: Pubkey::from_hex(pubkey).ok()?,
+                    note,
+                },
+            )))
+        }
+        Selection::Keyword(Keyword::SaveBookmark) => {
+            if let Selection::Payload(hex) = selections.get(1)? {
+                Some(CleanIntermediaryRoute::ToRoute(Route::SaveBookmark(
+                    NoteId::from_hex(hex.as_str()).ok()?,
+                )))
+            } else {
+                None
+            }
+        }
         Selection::Keyword(Keyword::Deck) => match selections.get(1)? {
             Selection::Keyword(Keyword::New) => {
                 Some(CleanIntermediaryRoute::ToRoute(Route::NewDeck))
@@ -794,6 +1114,24 @@ impl fmt::Display for Selection {
 
 #[cfg(test)]
 mod tests {
+    use super::{parse_shared_deck, SHARED_DECK_VERSION};
+
+    #[test]
+    fn test_parse_shared_deck() {
+        let deck = |version: u32| {
+            format!(
+                r#"{{"notedeck_deck":{version},"metadata":["name:News"],"columns":[["universe"]]}}"#
+            )
+        };
+
+        let parsed = parse_shared_deck(&deck(SHARED_DECK_VERSION)).expect("shared deck");
+        assert_eq!(parsed.columns, [["universe"]]);
+        assert!(parsed.column_kinds.is_empty());
+
+        assert!(parse_shared_deck(&deck(SHARED_DECK_VERSION + 1)).is_err());
+        assert!(parse_shared_deck(r#"{"metadata":[],"columns":[]}"#).is_err());
+    }
+
     //use enostr::Pubkey;
 
     //use crate::{route::Route, timeline::TimelineRoute};
@@ -816,7 +1154,7 @@ mod tests {
                 .unwrap();
 
         let app = test_app();
-        let cols = deserialize_columns(&app.ndb, user.bytes(), serialized);
+        let cols = deserialize_columns(&app.ndb, user.bytes(), serialized, vec![]);
 
         assert_eq!(cols.columns().len(), 2);
         let router = cols.column(0).router();
@@ -0,0 +1,26 @@
+use notedeck::{storage::Schema, AppStorage, DataPath};
+use tracing::{debug, error};
+
+use crate::draft::{Drafts, SavedDrafts};
+
+const DRAFTS_KEY: &str = "drafts";
+static DRAFTS_SCHEMA: Schema = Schema::INITIAL;
+
+pub fn load_drafts(path: &DataPath) -> Drafts {
+    let saved: SavedDrafts = AppStorage::new(path, super::APP_STORAGE_ID)
+        .get(DRAFTS_KEY, &DRAFTS_SCHEMA)
+        .unwrap_or_default();
+    Drafts::from_saved(saved)
+}
+
+pub fn save_drafts(path: &DataPath, drafts: &Drafts) {
+    match AppStorage::new(path, super::APP_STORAGE_ID).set(
+        DRAFTS_KEY,
+        &DRAFTS_SCHEMA,
+        &drafts.to_saved(),
+    ) {
+        // this happens every few seconds while typing
+        Ok(()) => debug!("Wrote drafts"),
+        Err(e) => error!("Could not write drafts: {}", e),
+    }
+}
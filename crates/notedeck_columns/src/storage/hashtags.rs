@@ -0,0 +1,24 @@
+use notedeck::{storage::Schema, AppStorage, DataPath};
+use tracing::{error, info};
+
+use crate::hashtags::HashtagSettings;
+
+const HASHTAG_SETTINGS_KEY: &str = "hashtags";
+static HASHTAG_SETTINGS_SCHEMA: Schema = Schema::INITIAL;
+
+pub fn load_hashtag_settings(path: &DataPath) -> HashtagSettings {
+    AppStorage::new(path, super::APP_STORAGE_ID)
+        .get(HASHTAG_SETTINGS_KEY, &HASHTAG_SETTINGS_SCHEMA)
+        .unwrap_or_default()
+}
+
+pub fn save_hashtag_settings(path: &DataPath, settings: &HashtagSettings) {
+    match AppStorage::new(path, super::APP_STORAGE_ID).set(
+        HASHTAG_SETTINGS_KEY,
+        &HASHTAG_SETTINGS_SCHEMA,
+        settings,
+    ) {
+        Ok(()) => info!("Wrote hashtag settings"),
+        Err(e) => error!("Could not write hashtag settings: {}", e),
+    }
+}
@@ -0,0 +1,20 @@
+use notedeck::{storage::Schema, AppStorage, DataPath};
+use tracing::{error, info};
+
+use crate::hidden::Hidden;
+
+const HIDDEN_KEY: &str = "hidden";
+static HIDDEN_SCHEMA: Schema = Schema::INITIAL;
+
+pub fn load_hidden(path: &DataPath) -> Hidden {
+    AppStorage::new(path, super::APP_STORAGE_ID)
+        .get(HIDDEN_KEY, &HIDDEN_SCHEMA)
+        .unwrap_or_default()
+}
+
+pub fn save_hidden(path: &DataPath, hidden: &Hidden) {
+    match AppStorage::new(path, super::APP_STORAGE_ID).set(HIDDEN_KEY, &HIDDEN_SCHEMA, hidden) {
+        Ok(()) => info!("Wrote hidden notes"),
+        Err(e) => error!("Could not write hidden notes: {}", e),
+    }
+}
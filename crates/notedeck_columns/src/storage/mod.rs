@@ -1,5 +1,30 @@
 mod decks;
+mod drafts;
+mod hashtags;
+mod hidden;
 mod migration;
+mod notifications;
+mod relay_groups;
+mod settings_sync;
+mod synced;
+mod wallet;
 
-pub use decks::{load_decks_cache, save_decks_cache, DECKS_CACHE_FILE};
+/// The columns app's id for [`notedeck::AppStorage`]
+pub const APP_STORAGE_ID: &str = "columns";
+
+pub use decks::{
+    deck_from_json, deck_to_json, decks_from_value, decks_to_value, load_decks_cache,
+    save_decks_cache, ImportedDeck, DECKS_CACHE_FILE,
+};
+pub use drafts::{load_drafts, save_drafts};
+pub use hashtags::{load_hashtag_settings, save_hashtag_settings};
+pub use hidden::{load_hidden, save_hidden};
 pub use migration::{deserialize_columns, COLUMNS_FILE};
+pub use notifications::{
+    load_notification_settings, load_thread_notifications, save_notification_settings,
+    save_thread_notifications, NOTIFICATION_SETTINGS_FILE,
+};
+pub use relay_groups::{load_relay_groups, save_relay_groups};
+pub use settings_sync::{load_sync_state, save_sync_state};
+pub use synced::{load_synced, save_synced};
+pub use wallet::{load_wallet_uri, save_wallet_uri};
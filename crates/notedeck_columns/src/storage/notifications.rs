@@ -0,0 +1,63 @@
+use enostr::Pubkey;
+use notedeck::{storage::Schema, AppStorage, DataPath, DataPathType};
+use tracing::{error, info};
+
+use crate::notifications::{NotificationSettings, ThreadNotifications};
+
+/// Where these lived before the columns app had its own storage
+pub static NOTIFICATION_SETTINGS_FILE: &str = "notifications.json";
+
+const NOTIFICATION_SETTINGS_KEY: &str = "notifications";
+static NOTIFICATION_SETTINGS_SCHEMA: Schema = Schema::INITIAL;
+static THREAD_NOTIFICATIONS_SCHEMA: Schema = Schema::INITIAL;
+
+fn app_storage(path: &DataPath) -> AppStorage {
+    let storage = AppStorage::new(path, super::APP_STORAGE_ID);
+    storage.adopt(
+        NOTIFICATION_SETTINGS_KEY,
+        &path.path(DataPathType::Setting),
+        NOTIFICATION_SETTINGS_FILE,
+    );
+    storage
+}
+
+pub fn load_notification_settings(path: &DataPath) -> NotificationSettings {
+    app_storage(path)
+        .get(NOTIFICATION_SETTINGS_KEY, &NOTIFICATION_SETTINGS_SCHEMA)
+        .unwrap_or_default()
+}
+
+pub fn save_notification_settings(path: &DataPath, settings: &NotificationSettings) {
+    match app_storage(path).set(
+        NOTIFICATION_SETTINGS_KEY,
+        &NOTIFICATION_SETTINGS_SCHEMA,
+        settings,
+    ) {
+        Ok(()) => info!("Wrote notification settings"),
+        Err(e) => error!("Could not write notification settings: {}", e),
+    }
+}
+
+fn thread_notifications_key(account: &Pubkey) -> String {
+    format!("thread-notifications-{}", account.hex())
+}
+
+pub fn load_thread_notifications(path: &DataPath, account: &Pubkey) -> ThreadNotifications {
+    AppStorage::new(path, super::APP_STORAGE_ID)
+        .get(
+            &thread_notifications_key(account),
+            &THREAD_NOTIFICATIONS_SCHEMA,
+        )
+        .unwrap_or_default()
+}
+
+pub fn save_thread_notifications(path: &DataPath, account: &Pubkey, threads: &ThreadNotifications) {
+    match AppStorage::new(path, super::APP_STORAGE_ID).set(
+        &thread_notifications_key(account),
+        &THREAD_NOTIFICATIONS_SCHEMA,
+        threads,
+    ) {
+        Ok(()) => info!("Wrote thread notification settings"),
+        Err(e) => error!("Could not write thread notification settings: {}", e),
+    }
+}
@@ -0,0 +1,24 @@
+use notedeck::{storage::Schema, AppStorage, DataPath};
+use tracing::{error, info};
+
+use crate::relay_groups::RelayGroups;
+
+const RELAY_GROUPS_KEY: &str = "relay-groups";
+static RELAY_GROUPS_SCHEMA: Schema = Schema::INITIAL;
+
+pub fn load_relay_groups(path: &DataPath) -> RelayGroups {
+    AppStorage::new(path, super::APP_STORAGE_ID)
+        .get(RELAY_GROUPS_KEY, &RELAY_GROUPS_SCHEMA)
+        .unwrap_or_default()
+}
+
+pub fn save_relay_groups(path: &DataPath, groups: &RelayGroups) {
+    match AppStorage::new(path, super::APP_STORAGE_ID).set(
+        RELAY_GROUPS_KEY,
+        &RELAY_GROUPS_SCHEMA,
+        groups,
+    ) {
+        Ok(()) => info!("Wrote relay groups"),
+        Err(e) => error!("Could not write relay groups: {}", e),
+    }
+}
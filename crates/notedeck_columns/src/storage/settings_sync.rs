@@ -0,0 +1,28 @@
+use enostr::Pubkey;
+use notedeck::{storage::Schema, AppStorage, DataPath};
+use tracing::{debug, error};
+
+use crate::settings_sync::SyncState;
+
+static SYNC_STATE_SCHEMA: Schema = Schema::INITIAL;
+
+fn sync_state_key(account: &Pubkey) -> String {
+    format!("settings-sync-{}", account.hex())
+}
+
+pub fn load_sync_state(path: &DataPath, account: &Pubkey) -> SyncState {
+    AppStorage::new(path, super::APP_STORAGE_ID)
+        .get(&sync_state_key(account), &SYNC_STATE_SCHEMA)
+        .unwrap_or_default()
+}
+
+pub fn save_sync_state(path: &DataPath, account: &Pubkey, state: &SyncState) {
+    match AppStorage::new(path, super::APP_STORAGE_ID).set(
+        &sync_state_key(account),
+        &SYNC_STATE_SCHEMA,
+        state,
+    ) {
+        Ok(()) => debug!("Wrote settings sync state"),
+        Err(e) => error!("Could not write settings sync state: {}", e),
+    }
+}
@@ -0,0 +1,20 @@
+use notedeck::{storage::Schema, AppStorage, DataPath};
+use tracing::{debug, error};
+
+use crate::timeline::Synced;
+
+const SYNCED_KEY: &str = "synced";
+static SYNCED_SCHEMA: Schema = Schema::INITIAL;
+
+pub fn load_synced(path: &DataPath) -> Synced {
+    AppStorage::new(path, super::APP_STORAGE_ID)
+        .get(SYNCED_KEY, &SYNCED_SCHEMA)
+        .unwrap_or_default()
+}
+
+pub fn save_synced(path: &DataPath, synced: &Synced) {
+    match AppStorage::new(path, super::APP_STORAGE_ID).set(SYNCED_KEY, &SYNCED_SCHEMA, synced) {
+        Ok(()) => debug!("Wrote synced timelines"),
+        Err(e) => error!("Could not write synced timelines: {}", e),
+    }
+}
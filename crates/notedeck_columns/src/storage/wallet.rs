@@ -0,0 +1,51 @@
+use enostr::FilledKeypair;
+use notedeck::{storage::Schema, AppStorage, DataPath};
+use tracing::{error, info};
+
+/// Where connections were kept before each account had its own
+const LEGACY_WALLET_KEY: &str = "wallet";
+static WALLET_SCHEMA: Schema = Schema::INITIAL;
+
+/// The uri's secret can spend from the wallet, so it's kept encrypted to
+/// the account it belongs to. Reading it takes the account's secret key,
+/// which is itself only on disk under the password the OS secret store
+/// keeps (or the user's passphrase), so it's as safe as the key is.
+fn wallet_key(keypair: &FilledKeypair) -> String {
+    format!("wallet-{}", keypair.pubkey.hex())
+}
+
+/// The wallet connect uri the account last saved
+pub fn load_wallet_uri(path: &DataPath, keypair: FilledKeypair) -> Option<String> {
+    let storage = AppStorage::new(path, super::APP_STORAGE_ID);
+
+    let Some(encrypted) = storage.get::<String>(&wallet_key(&keypair), &WALLET_SCHEMA) else {
+        // the first account to load after upgrading takes the old one
+        let uri: String = storage.get(LEGACY_WALLET_KEY, &WALLET_SCHEMA)?;
+        info!("moving the saved wallet connection to the selected account");
+        save_wallet_uri(path, keypair, Some(&uri));
+        if let Err(e) = storage.remove(LEGACY_WALLET_KEY) {
+            error!("Could not remove the old wallet connection: {}", e);
+        }
+        return Some(uri);
+    };
+
+    keypair
+        .nip44_decrypt(keypair.pubkey, &encrypted)
+        .inspect_err(|e| error!("Could not decrypt wallet connection: {}", e))
+        .ok()
+}
+
+pub fn save_wallet_uri(path: &DataPath, keypair: FilledKeypair, uri: Option<&str>) {
+    let storage = AppStorage::new(path, super::APP_STORAGE_ID);
+    let key = wallet_key(&keypair);
+    let saved = match uri.map(|uri| keypair.nip44_encrypt(keypair.pubkey, uri)) {
+        Some(Ok(encrypted)) => storage.set(&key, &WALLET_SCHEMA, &encrypted),
+        Some(Err(e)) => Err(e.to_string().into()),
+        None => storage.remove(&key),
+    };
+
+    match saved {
+        Ok(()) => info!("Wrote wallet connection"),
+        Err(e) => error!("Could not write wallet connection: {}", e),
+    }
+}
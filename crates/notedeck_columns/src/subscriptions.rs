@@ -1,4 +1,5 @@
-use crate::timeline::{TimelineId, TimelineKind};
+use crate::timeline::{Synced, TimelineId, TimelineKind};
+use enostr::Filter;
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -25,6 +26,102 @@ pub enum SubKind {
 #[derive(Default)]
 pub struct Subscriptions {
     pub subs: HashMap<String, SubKind>,
+
+    /// REQs sent on behalf of a timeline, so the subscription inspector
+    /// can show what a column asked for
+    pub timeline_reqs: HashMap<String, TimelineReq>,
+
+    /// What relays have sent back for each subscription id, whether or
+    /// not we're tracking it
+    pub relay_status: HashMap<String, HashMap<String, RelaySubStatus>>,
+
+    /// How far relays got with each timeline last time
+    pub synced: Synced,
+}
+
+#[derive(Debug)]
+pub struct TimelineReq {
+    pub timeline: TimelineId,
+    pub filters: Vec<Filter>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RelaySubStatus {
+    pub eose: bool,
+    pub events: u64,
+}
+
+impl Subscriptions {
+    /// Remember a REQ sent for a timeline
+    pub fn track_timeline_req(&mut self, sub_id: &str, timeline: TimelineId, filters: &[Filter]) {
+        self.timeline_reqs.insert(
+            sub_id.to_owned(),
+            TimelineReq {
+                timeline,
+                filters: filters.to_vec(),
+            },
+        );
+    }
+
+    /// Whether any REQs have been sent for a timeline
+    pub fn tracks(&self, timeline: TimelineId) -> bool {
+        self.timeline_reqs
+            .values()
+            .any(|req| req.timeline == timeline)
+    }
+
+    /// Forget the REQs for a timeline, returning their subscription ids
+    pub fn untrack_timeline(&mut self, timeline: TimelineId) -> Vec<String> {
+        self.untrack(timeline, |_| true)
+    }
+
+    /// Forget the REQs that fill a timeline, but not the ones fetching
+    /// what it's built from, like a contact list
+    pub fn untrack_initial(&mut self, timeline: TimelineId) -> Vec<String> {
+        self.untrack(timeline, |kind| matches!(kind, Some(SubKind::Initial)))
+    }
+
+    fn untrack(
+        &mut self,
+        timeline: TimelineId,
+        kind: impl Fn(Option<&SubKind>) -> bool,
+    ) -> Vec<String> {
+        let sub_ids: Vec<String> = self
+            .timeline_reqs
+            .iter()
+            .filter(|(sub_id, req)| req.timeline == timeline && kind(self.subs.get(*sub_id)))
+            .map(|(sub_id, _)| sub_id.clone())
+            .collect();
+
+        for sub_id in &sub_ids {
+            self.timeline_reqs.remove(sub_id);
+            self.relay_status.remove(sub_id);
+            self.subs.remove(sub_id);
+        }
+
+        sub_ids
+    }
+
+    /// Start counting again after sending a REQ with the same id
+    pub fn reset_status(&mut self, sub_id: &str) {
+        self.relay_status.remove(sub_id);
+    }
+
+    pub fn got_event(&mut self, sub_id: &str, relay: &str) {
+        self.status_mut(sub_id, relay).events += 1;
+    }
+
+    pub fn got_eose(&mut self, sub_id: &str, relay: &str) {
+        self.status_mut(sub_id, relay).eose = true;
+    }
+
+    fn status_mut(&mut self, sub_id: &str, relay: &str) -> &mut RelaySubStatus {
+        self.relay_status
+            .entry(sub_id.to_owned())
+            .or_default()
+            .entry(relay.to_owned())
+            .or_default()
+    }
 }
 
 pub fn new_sub_id() -> String {
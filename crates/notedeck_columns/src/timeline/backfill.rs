@@ -0,0 +1,164 @@
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use enostr::{PoolRelay, RelayPool};
+use nostrdb::Filter;
+use tracing::{debug, error};
+
+use crate::subscriptions::{self, SubKind, Subscriptions};
+use crate::timeline::{history::page_filters, TimelineId};
+
+/// Relays that haven't answered by then are given up on
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Most notes to ask each relay for at a time
+const REMOTE_LIMIT: u64 = 500;
+
+#[derive(Debug)]
+enum WindowState {
+    Wanted,
+    Loading { sub_id: String, sent_at: Instant },
+    Done,
+}
+
+#[derive(Debug)]
+struct Window {
+    range: Range<u64>,
+    state: WindowState,
+}
+
+/// Stretches of a timeline's past that nostrdb didn't have enough of, and
+/// that we've asked relays for
+#[derive(Debug, Default)]
+pub struct Backfill {
+    windows: Vec<Window>,
+    /// Goes up each time a window finishes, so whatever was drawn from
+    /// nostrdb knows to look again
+    generation: u64,
+}
+
+impl Backfill {
+    /// Ask relays for notes in `range`, unless we already have
+    pub fn want(&mut self, range: Range<u64>) {
+        if range.is_empty() || self.covered(&range) {
+            return;
+        }
+
+        debug!("backfilling {range:?}");
+        self.windows.push(Window {
+            range,
+            state: WindowState::Wanted,
+        });
+    }
+
+    fn covered(&self, range: &Range<u64>) -> bool {
+        self.windows
+            .iter()
+            .any(|w| w.range.start <= range.start && range.end <= w.range.end)
+    }
+
+    /// Whether relays are still being asked about any of `range`
+    pub fn is_loading(&self, range: &Range<u64>) -> bool {
+        self.windows.iter().any(|w| {
+            !matches!(w.state, WindowState::Done)
+                && w.range.start < range.end
+                && range.start < w.range.end
+        })
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Send the REQs for windows that were asked for, and wrap up the
+    /// ones relays are done with
+    pub fn update(
+        &mut self,
+        timeline: TimelineId,
+        filters: &[Filter],
+        subs: &mut Subscriptions,
+        pool: &mut RelayPool,
+        now: Instant,
+    ) {
+        for window in &mut self.windows {
+            match &window.state {
+                WindowState::Wanted => {
+                    let until = window.range.end.saturating_sub(1);
+                    let filters =
+                        match page_filters(filters, window.range.start, until, REMOTE_LIMIT) {
+                            Ok(filters) => filters,
+                            Err(err) => {
+                                error!("could not backfill timeline: {err}");
+                                window.state = WindowState::Done;
+                                continue;
+                            }
+                        };
+
+                    let sub_id = subscriptions::new_sub_id();
+                    subs.subs.insert(sub_id.clone(), SubKind::OneShot);
+                    subs.track_timeline_req(&sub_id, timeline, &filters);
+                    pool.subscribe(sub_id.clone(), filters);
+                    window.state = WindowState::Loading {
+                        sub_id,
+                        sent_at: now,
+                    };
+                }
+
+                WindowState::Loading { sub_id, sent_at } => {
+                    if !relays_done(subs, pool, sub_id) && now.duration_since(*sent_at) < TIMEOUT {
+                        continue;
+                    }
+
+                    // oneshots are closed as each relay sends EOSE, this
+                    // catches the ones that never did
+                    pool.unsubscribe(sub_id.clone());
+                    subs.subs.remove(sub_id);
+                    subs.timeline_reqs.remove(sub_id);
+                    subs.reset_status(sub_id);
+
+                    window.state = WindowState::Done;
+                    self.generation += 1;
+                }
+
+                WindowState::Done => {}
+            }
+        }
+    }
+}
+
+/// Whether every relay we're connected to has sent EOSE
+fn relays_done(subs: &Subscriptions, pool: &RelayPool, sub_id: &str) -> bool {
+    let Some(status) = subs.relay_status.get(sub_id) else {
+        return false;
+    };
+
+    pool.relays
+        .iter()
+        .filter(|relay| matches!(relay, PoolRelay::Websocket(_)))
+        .all(|relay| status.get(relay.url()).is_some_and(|s| s.eose))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backfill_windows() {
+        let mut backfill = Backfill::default();
+        backfill.want(100..200);
+        // already asked for
+        backfill.want(120..180);
+        backfill.want(150..150);
+        assert_eq!(backfill.windows.len(), 1);
+
+        assert!(backfill.is_loading(&(190..300)));
+        assert!(!backfill.is_loading(&(200..300)));
+
+        backfill.windows[0].state = WindowState::Done;
+        assert!(!backfill.is_loading(&(100..200)));
+
+        // older than what was asked for
+        backfill.want(50..120);
+        assert_eq!(backfill.windows.len(), 2);
+        assert!(backfill.is_loading(&(60..70)));
+    }
+}
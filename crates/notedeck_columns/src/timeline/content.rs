@@ -0,0 +1,143 @@
+use nostrdb::Filter;
+use notedeck::filter;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+/// Every kind a column can be asked to show. Filters asking for any of
+/// these are the ones [`ContentKinds`] rewrites.
+pub const CONTENT_KINDS: [u64; 6] = [1, 6, 16, 30023, 9802, 30311];
+
+/// What a timeline column shows. These end up in the column's filters, so
+/// turning something off means relays never send it. Replies are the
+/// exception: relays can't tell them apart from other text notes, so
+/// they're dropped by the column's tabs instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentKinds {
+    pub notes: bool,
+    pub replies: bool,
+    pub reposts: bool,
+    pub long_form: bool,
+    pub highlights: bool,
+    pub live: bool,
+}
+
+impl Default for ContentKinds {
+    fn default() -> Self {
+        ContentKinds {
+            notes: true,
+            replies: true,
+            reposts: false,
+            long_form: false,
+            highlights: false,
+            live: false,
+        }
+    }
+}
+
+impl ContentKinds {
+    /// The note kinds to ask for
+    pub fn kinds(&self) -> Vec<u64> {
+        let mut kinds = vec![];
+        if self.notes {
+            kinds.push(1);
+        }
+        if self.reposts {
+            kinds.extend([6, 16]);
+        }
+        if self.long_form {
+            kinds.push(30023);
+        }
+        if self.highlights {
+            kinds.push(9802);
+        }
+        if self.live {
+            kinds.push(30311);
+        }
+        kinds
+    }
+
+    /// Whether replies are shown. They're text notes, so there's nothing
+    /// to show without those.
+    pub fn shows_replies(&self) -> bool {
+        self.notes && self.replies
+    }
+
+    /// Rewrite a column's filters to ask for these kinds
+    pub fn apply(&self, filters: Vec<Filter>) -> Vec<Filter> {
+        match filter::replace_kinds(&filters, &CONTENT_KINDS, &self.kinds()) {
+            Ok(replaced) => replaced,
+            Err(err) => {
+                error!("couldn't apply column kinds: {err}");
+                filters
+            }
+        }
+    }
+
+    /// There has to be something left to ask for
+    pub fn is_empty(&self) -> bool {
+        self.kinds().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline::{Timeline, TimelineKind, TimelineTab, ViewFilter};
+    use notedeck::FilterState;
+
+    #[test]
+    fn kinds_follow_toggles() {
+        assert_eq!(ContentKinds::default().kinds(), vec![1]);
+
+        let kinds = ContentKinds {
+            notes: false,
+            replies: false,
+            reposts: true,
+            long_form: true,
+            highlights: false,
+            live: true,
+        };
+        assert_eq!(kinds.kinds(), vec![6, 16, 30023, 30311]);
+        assert!(kinds.kinds().iter().all(|k| CONTENT_KINDS.contains(k)));
+
+        let nothing = ContentKinds {
+            notes: false,
+            replies: false,
+            ..kinds
+        };
+        assert!(!nothing.is_empty());
+        assert!(ContentKinds {
+            reposts: false,
+            long_form: false,
+            live: false,
+            ..nothing
+        }
+        .is_empty());
+    }
+
+    #[test]
+    fn replies_are_left_to_the_tabs() {
+        let mut timeline = Timeline::new(
+            TimelineKind::Universe,
+            FilterState::ready(vec![]),
+            TimelineTab::full_tabs(),
+        );
+        let tabs = |timeline: &Timeline| -> Vec<ViewFilter> {
+            timeline.views.iter().map(|view| view.filter).collect()
+        };
+
+        let no_replies = ContentKinds {
+            replies: false,
+            ..ContentKinds::default()
+        };
+        timeline.set_kinds(no_replies);
+        assert_eq!(tabs(&timeline), vec![ViewFilter::Notes]);
+
+        timeline.set_kinds(ContentKinds::default());
+        assert_eq!(
+            tabs(&timeline),
+            vec![ViewFilter::Notes, ViewFilter::NotesAndReplies]
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use chrono::{DateTime, Local, Months, NaiveDate, NaiveTime};
+use nostrdb::{Filter, Ndb, NoteKey, Transaction};
+use notedeck::{
+    datetime::{self, LocalTime},
+    filter::filters_from_json,
+    NoteCache, NoteRef,
+};
+use serde_json::Value;
+use tracing::error;
+
+use crate::timeline::{TimelineTab, ViewFilter};
+
+/// How many notes to ask nostrdb for at a time
+const PAGE_SIZE: u64 = 100;
+/// Most notes we count when showing how busy the days of a month were
+const DENSITY_LIMIT: u64 = 5000;
+
+/// A timeline's notes from one day in the past, read from nostrdb rather
+/// than the live subscription. Loaded newest first, a page at a time, as
+/// the user scrolls down.
+#[derive(Debug)]
+pub struct TimelineHistory {
+    pub day: NaiveDate,
+    /// Read back from this time of day rather than the end of the day
+    pub time: Option<NaiveTime>,
+    /// Where the picked time landed. The clocks changing can make it
+    /// happen twice or not at all, see [`TimelineHistory::at`].
+    pub local_time: Option<LocalTime>,
+    /// The moment we're reading back from, once a time is picked
+    pub end: Option<u64>,
+    pub tab: TimelineTab,
+    range: Range<u64>,
+    /// created_at of the oldest note we've seen, where the next page starts
+    oldest: Option<u64>,
+    seen: HashSet<NoteKey>,
+    exhausted: bool,
+    /// The [`Backfill`](super::Backfill) generation we last read nostrdb
+    /// at after running out
+    backfilled: u64,
+}
+
+impl TimelineHistory {
+    pub fn new(day: NaiveDate, view_filter: ViewFilter) -> Self {
+        let range = datetime::day_range(&Local, day).unwrap_or(0..0);
+        TimelineHistory {
+            day,
+            time: None,
+            local_time: None,
+            end: None,
+            tab: TimelineTab::new_with_capacity(view_filter, PAGE_SIZE as usize),
+            range,
+            oldest: None,
+            seen: HashSet::new(),
+            exhausted: false,
+            backfilled: 0,
+        }
+    }
+
+    /// Only show notes from up to the end of the given minute. When the
+    /// clocks changed at that time we go with the earlier reading until
+    /// another is picked.
+    pub fn until(mut self, time: Option<NaiveTime>) -> Self {
+        self.time = time;
+        self.local_time =
+            time.and_then(|time| datetime::resolve_local(&Local, self.day.and_time(time)));
+        match self.local_time {
+            Some(local_time) => self.at(local_time.earliest()),
+            None => self,
+        }
+    }
+
+    /// Read back from a particular moment on the day
+    pub fn at(mut self, end: u64) -> Self {
+        let day_end = datetime::day_range(&Local, self.day).map_or(0, |range| range.end);
+        self.end = Some(end);
+        self.range.end = day_end.min(end + 60);
+        self
+    }
+
+    /// Whether we've loaded everything nostrdb has for the day
+    pub fn exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    /// The part of the day older than anything we've shown, for asking
+    /// relays about once nostrdb runs out
+    pub fn remote_range(&self) -> Range<u64> {
+        self.range.start..self.oldest.unwrap_or(self.range.end)
+    }
+
+    /// Keep paging after relays have sent us more of the day. Returns
+    /// false if there's been nothing new since we last ran out.
+    pub fn resume(&mut self, generation: u64) -> bool {
+        if generation <= self.backfilled {
+            return false;
+        }
+        self.backfilled = generation;
+        self.exhausted = false;
+        true
+    }
+
+    /// Load the next page of older notes for the day
+    pub fn load_older(&mut self, ndb: &Ndb, note_cache: &mut NoteCache, filters: &[Filter]) {
+        if self.exhausted {
+            return;
+        }
+
+        let until = self.oldest.unwrap_or(self.range.end.saturating_sub(1));
+        let page = match page_filters(filters, self.range.start, until, PAGE_SIZE) {
+            Ok(page) => page,
+            Err(err) => {
+                error!("could not page timeline history: {err}");
+                self.exhausted = true;
+                return;
+            }
+        };
+
+        let Ok(txn) = Transaction::new(ndb) else {
+            return;
+        };
+
+        let results = match ndb.query(&txn, &page, PAGE_SIZE as i32) {
+            Ok(results) => results,
+            Err(err) => {
+                error!("timeline history query failed: {err}");
+                self.exhausted = true;
+                return;
+            }
+        };
+
+        let mut notes: Vec<NoteRef> = results
+            .into_iter()
+            .map(NoteRef::from_query_result)
+            .filter(|note_ref| self.seen.insert(note_ref.key))
+            .collect();
+
+        // notes sharing the oldest timestamp come back again on the next
+        // page, so we're done once a page has nothing new
+        if notes.is_empty() {
+            self.exhausted = true;
+            return;
+        }
+
+        notes.sort();
+        self.oldest = notes.last().map(|note| note.created_at);
+
+        let view_filter = self.tab.filter.filter();
+        notes.retain(|note_ref| {
+            ndb.get_note_by_key(&txn, note_ref.key)
+                .map(|note| {
+                    view_filter(
+                        note_cache.cached_note_or_insert_mut(note_ref.key, &note),
+                        &note,
+                    )
+                })
+                .unwrap_or(false)
+        });
+
+        self.tab.notes.extend(notes);
+    }
+}
+
+/// The local month starting at `month`, as unix times
+pub fn month_range(month: NaiveDate) -> Option<Range<u64>> {
+    let start = datetime::day_range(&Local, month)?.start;
+    let end = month
+        .checked_add_months(Months::new(1))
+        .and_then(|next| datetime::day_range(&Local, next))?
+        .start;
+    Some(start..end)
+}
+
+/// How many of a timeline's notes nostrdb has for each local day of the
+/// month starting at `month`
+pub fn month_density(ndb: &Ndb, filters: &[Filter], month: NaiveDate) -> HashMap<NaiveDate, usize> {
+    let mut density = HashMap::new();
+
+    let Some(Range { start, end }) = month_range(month) else {
+        return density;
+    };
+
+    let query = match page_filters(filters, start, end.saturating_sub(1), DENSITY_LIMIT) {
+        Ok(query) => query,
+        Err(err) => {
+            error!("could not count timeline history: {err}");
+            return density;
+        }
+    };
+
+    let Ok(txn) = Transaction::new(ndb) else {
+        return density;
+    };
+
+    let results = match ndb.query(&txn, &query, DENSITY_LIMIT as i32) {
+        Ok(results) => results,
+        Err(err) => {
+            error!("timeline history count failed: {err}");
+            return density;
+        }
+    };
+
+    for result in results {
+        let created_at = result.note.created_at() as i64;
+        if let Some(time) = DateTime::from_timestamp(created_at, 0) {
+            let day = time.with_timezone(&Local).date_naive();
+            *density.entry(day).or_insert(0) += 1;
+        }
+    }
+
+    density
+}
+
+/// The timeline's filters narrowed down to `since..=until`. nostrdb
+/// filters can't be edited once built, so go through json.
+pub fn page_filters(
+    filters: &[Filter],
+    since: u64,
+    until: u64,
+    limit: u64,
+) -> notedeck::Result<Vec<Filter>> {
+    let mut page = Vec::with_capacity(filters.len());
+    for filter in filters {
+        let mut value: Value = serde_json::from_str(&filter.json()?)?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.insert("since".to_owned(), since.into());
+            fields.insert("until".to_owned(), until.into());
+            fields.insert("limit".to_owned(), limit.into());
+        }
+        page.push(value);
+    }
+
+    filters_from_json(&Value::Array(page).to_string())
+}
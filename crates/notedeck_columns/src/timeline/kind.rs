@@ -120,6 +120,21 @@ impl TimelineKind {
         TimelineKind::Notifications(pk)
     }
 
+    /// Timelines that aren't tied to anyone in particular, so plenty of
+    /// relays will have nothing for them. Relays are left out of these
+    /// once they've come back empty a few times.
+    pub fn broad_key(&self) -> Option<String> {
+        match self {
+            TimelineKind::Universe => Some("universe".to_owned()),
+            TimelineKind::Hashtag(tag) => Some(format!("#{}", tag.to_lowercase())),
+            TimelineKind::List(_)
+            | TimelineKind::Notifications(_)
+            | TimelineKind::Profile(_)
+            | TimelineKind::Thread(_)
+            | TimelineKind::Generic => None,
+        }
+    }
+
     pub fn into_timeline(self, ndb: &Ndb, default_user: Option<&[u8; 32]>) -> Option<Timeline> {
         match self {
             TimelineKind::Universe => Some(Timeline::new(
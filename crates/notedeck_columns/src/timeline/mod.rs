@@ -1,22 +1,22 @@
 use crate::{
     column::Columns,
-    decks::DecksCache,
     error::Error,
+    media,
     subscriptions::{self, SubKind, Subscriptions},
     thread::Thread,
     Result,
 };
 
 use notedeck::{
-    filter, CachedNote, FilterError, FilterState, FilterStates, NoteCache, NoteRef, RootNoteIdBuf,
-    UnknownIds,
+    filter, repost, CachedNote, FilterError, FilterState, FilterStates, NoteCache, NoteRef,
+    RootNoteIdBuf, UnknownIds,
 };
 
 use std::fmt;
 use std::sync::atomic::{AtomicU32, Ordering};
 
 use egui_virtual_list::VirtualList;
-use enostr::{PoolRelay, Pubkey, RelayPool};
+use enostr::{PoolRelay, Pubkey, RelayPool, RelayStatus};
 use nostrdb::{Filter, Ndb, Note, NoteKey, Subscription, Transaction};
 use std::cell::RefCell;
 use std::hash::Hash;
@@ -24,13 +24,21 @@ use std::rc::Rc;
 
 use tracing::{debug, error, info, warn};
 
+pub mod backfill;
 pub mod cache;
+pub mod content;
+pub mod history;
 pub mod kind;
 pub mod route;
+pub mod synced;
 
+pub use backfill::Backfill;
 pub use cache::{TimelineCache, TimelineCacheKey};
+pub use content::ContentKinds;
+pub use history::TimelineHistory;
 pub use kind::{ColumnTitle, PubkeySource, TimelineKind};
 pub use route::TimelineRoute;
+pub use synced::Synced;
 
 #[derive(Debug, Hash, Copy, Clone, Eq, PartialEq)]
 pub struct TimelineId(u32);
@@ -53,6 +61,9 @@ pub enum ViewFilter {
 
     #[default]
     NotesAndReplies,
+
+    /// Notes with images or videos, shown as a grid
+    Media,
 }
 
 impl ViewFilter {
@@ -60,11 +71,17 @@ impl ViewFilter {
         match self {
             ViewFilter::Notes => "Notes",
             ViewFilter::NotesAndReplies => "Notes & Replies",
+            ViewFilter::Media => "Media",
         }
     }
 
     pub fn filter_notes(cache: &CachedNote, note: &Note) -> bool {
-        !cache.reply.borrow(note.tags()).is_reply()
+        // a repost's e tag isn't a reply
+        repost::is_repost(note) || !cache.reply.borrow(note.tags()).is_reply()
+    }
+
+    pub fn filter_media(_cache: &CachedNote, note: &Note) -> bool {
+        media::has_media(note)
     }
 
     fn identity(_cache: &CachedNote, _note: &Note) -> bool {
@@ -75,6 +92,7 @@ impl ViewFilter {
         match self {
             ViewFilter::Notes => ViewFilter::filter_notes,
             ViewFilter::NotesAndReplies => ViewFilter::identity,
+            ViewFilter::Media => ViewFilter::filter_media,
         }
     }
 }
@@ -89,6 +107,8 @@ pub struct TimelineTab {
     pub selection: i32,
     pub filter: ViewFilter,
     pub list: Rc<RefCell<VirtualList>>,
+    /// Notes that were added to the top while we were scrolled down
+    pub unseen: usize,
 }
 
 impl TimelineTab {
@@ -111,6 +131,15 @@ impl TimelineTab {
         ]
     }
 
+    /// Profiles also get a grid of everything they've posted media in
+    pub fn profile_tabs() -> Vec<Self> {
+        vec![
+            TimelineTab::new(ViewFilter::Notes),
+            TimelineTab::new(ViewFilter::NotesAndReplies),
+            TimelineTab::new(ViewFilter::Media),
+        ]
+    }
+
     pub fn new_with_capacity(filter: ViewFilter, cap: usize) -> Self {
         let selection = 0i32;
         let mut list = VirtualList::new();
@@ -124,6 +153,7 @@ impl TimelineTab {
             selection,
             filter,
             list,
+            unseen: 0,
         }
     }
 
@@ -157,6 +187,7 @@ impl TimelineTab {
                     if !reversed {
                         debug!("inserting {} new notes at start", new_refs.len());
                         list.items_inserted_at_start(new_items);
+                        self.unseen += new_items;
                     }
                 }
             }
@@ -194,6 +225,15 @@ pub struct Timeline {
     pub selected_view: usize,
 
     pub subscription: Option<Subscription>,
+
+    /// Set while reading an earlier day instead of the live timeline
+    pub history: Option<TimelineHistory>,
+
+    /// Older notes we've gone to relays for
+    pub backfill: Backfill,
+
+    /// What kinds of notes the column shows
+    pub kinds: ContentKinds,
 }
 
 impl Timeline {
@@ -263,7 +303,51 @@ impl Timeline {
             views,
             subscription,
             selected_view,
+            history: None,
+            backfill: Backfill::default(),
+            kinds: ContentKinds::default(),
+        }
+    }
+
+    /// Set what a timeline shows before it's been subscribed to
+    pub fn with_kinds(mut self, kinds: ContentKinds) -> Self {
+        if let FilterState::Ready(filters) = &self.filter.initial_state {
+            self.filter = FilterStates::new(FilterState::ready(kinds.apply(filters.clone())));
+        }
+        self.set_kinds(kinds);
+        self
+    }
+
+    /// Change what the timeline shows. Replies are left to the tabs; the
+    /// filters are the caller's to swap out, see [`replace_timeline_filter`].
+    pub fn set_kinds(&mut self, kinds: ContentKinds) {
+        let mut tabs: Vec<ViewFilter> = self.views.iter().map(|view| view.filter).collect();
+        if kinds.shows_replies() {
+            if !tabs.contains(&ViewFilter::NotesAndReplies) && !self.kinds.shows_replies() {
+                let after_notes = tabs
+                    .iter()
+                    .position(|tab| *tab == ViewFilter::Notes)
+                    .map_or(0, |i| i + 1);
+                tabs.insert(after_notes, ViewFilter::NotesAndReplies);
+            }
+        } else {
+            tabs.retain(|tab| *tab != ViewFilter::NotesAndReplies);
+            if !tabs.contains(&ViewFilter::Notes) {
+                tabs.insert(0, ViewFilter::Notes);
+            }
+        }
+
+        if tabs
+            != self
+                .views
+                .iter()
+                .map(|view| view.filter)
+                .collect::<Vec<_>>()
+        {
+            self.views = tabs.into_iter().map(TimelineTab::new).collect();
+            self.selected_view = 0;
         }
+        self.kinds = kinds;
     }
 
     pub fn current_view(&self) -> &TimelineTab {
@@ -295,6 +379,17 @@ impl Timeline {
         self.views.iter_mut().find(|tab| tab.filter == view)
     }
 
+    /// Take a note out of every view, eg. one we've asked relays to delete
+    pub fn remove_note(&mut self, key: NoteKey) {
+        for view in &mut self.views {
+            let before = view.notes.len();
+            view.notes.retain(|note| note.key != key);
+            if view.notes.len() != before {
+                view.list.borrow_mut().reset();
+            }
+        }
+    }
+
     /// Initial insert of notes into a timeline. Subsequent inserts should
     /// just use the insert function
     pub fn insert_new(
@@ -369,12 +464,13 @@ impl Timeline {
                     view.insert(&refs, reversed);
                 }
 
-                ViewFilter::Notes => {
+                ViewFilter::Notes | ViewFilter::Media => {
+                    let filter = view.filter.filter();
                     let mut filtered_refs = Vec::with_capacity(new_refs.len());
                     for (note, nr) in &new_refs {
                         let cached_note = note_cache.cached_note_or_insert(nr.key, note);
 
-                        if ViewFilter::filter_notes(cached_note, note) {
+                        if filter(cached_note, note) {
                             filtered_refs.push(*nr);
                         }
                     }
@@ -467,7 +563,7 @@ pub fn setup_new_timeline(
     our_pk: Option<&Pubkey>,
 ) {
     // if we're ready, setup local subs
-    if is_timeline_ready(ndb, pool, note_cache, timeline, our_pk) {
+    if is_timeline_ready(ndb, pool, subs, note_cache, timeline, our_pk) {
         if let Err(err) = setup_timeline_nostrdb_sub(ndb, note_cache, timeline) {
             error!("setup_new_timeline: {err}");
         }
@@ -478,6 +574,75 @@ pub fn setup_new_timeline(
     }
 }
 
+/// Swap out a timeline's filter, dropping its notes and subscriptions and
+/// setting it up again from scratch
+#[allow(clippy::too_many_arguments)]
+pub fn replace_timeline_filter(
+    timeline: &mut Timeline,
+    filters: Vec<Filter>,
+    ndb: &mut Ndb,
+    subs: &mut Subscriptions,
+    pool: &mut RelayPool,
+    note_cache: &mut NoteCache,
+    our_pk: Option<&Pubkey>,
+) {
+    for sub_id in subs.untrack_timeline(timeline.id) {
+        pool.unsubscribe(sub_id);
+    }
+
+    if let Some(sub) = timeline.subscription.take() {
+        if let Err(err) = ndb.unsubscribe(sub) {
+            error!("replace_timeline_filter: {err}");
+        }
+    }
+
+    for view in &mut timeline.views {
+        view.notes.clear();
+        view.list.borrow_mut().reset();
+    }
+
+    timeline.filter = FilterStates::new(FilterState::ready(filters));
+    setup_new_timeline(timeline, ndb, subs, pool, note_cache, false, our_pk);
+}
+
+/// Send the backfill REQs timelines have asked for, and wrap up the
+/// ones relays are done with
+pub fn update_backfills(columns: &mut Columns, subs: &mut Subscriptions, pool: &mut RelayPool) {
+    let now = std::time::Instant::now();
+    for timeline in columns.timelines_mut() {
+        if let Some(filters) = timeline.filter.get_any_ready() {
+            timeline
+                .backfill
+                .update(timeline.id, filters, subs, pool, now);
+        }
+    }
+}
+
+/// Ask every relay for every timeline again, from the start. For when
+/// notes seem to be missing.
+pub fn refresh_timelines(
+    ndb: &Ndb,
+    columns: &mut Columns,
+    subs: &mut Subscriptions,
+    pool: &mut RelayPool,
+) {
+    subs.synced.clear();
+
+    for timeline in columns.timelines_mut() {
+        for sub_id in subs.untrack_initial(timeline.id) {
+            pool.unsubscribe(sub_id);
+        }
+
+        for relay in &mut pool.relays {
+            // timelines still waiting on a contact list will send theirs
+            // when it comes
+            if matches!(timeline.filter.get(relay.url()), FilterState::Ready(_)) {
+                send_initial_timeline_filter(ndb, false, subs, relay, timeline);
+            }
+        }
+    }
+}
+
 /// Send initial filters for a specific relay. This typically gets called
 /// when we first connect to a new relay for the first time. For
 /// situations where you are adding a new timeline, use
@@ -526,6 +691,11 @@ pub fn send_initial_timeline_filter(
 
         FilterState::Ready(filter) => {
             let filter = filter.to_owned();
+            let synced_since = if can_since_optimize {
+                subs.synced.since(relay.url(), &filter)
+            } else {
+                None
+            };
             let new_filters = filter.into_iter().map(|f| {
                 // limit the size of remote filters
                 let default_limit = filter::default_remote_limit();
@@ -544,21 +714,34 @@ pub fn send_initial_timeline_filter(
                 // and seeing what its limit is. If we have less
                 // notes than the limit, we might want to backfill
                 // older notes
-                if can_since_optimize && filter::should_since_optimize(lim, notes.len()) {
+                if let Some(since) = synced_since {
+                    // the relay sent us everything up to here last time
+                    filter = filter.since_mut(since);
+                } else if can_since_optimize && filter::should_since_optimize(lim, notes.len()) {
                     filter = filter::since_optimize_filter(filter, notes);
                 } else {
                     warn!("Skipping since optimization for {:?}: number of local notes is less than limit, attempting to backfill.", filter);
                 }
 
                 filter
-            }).collect();
+            }).collect::<Vec<Filter>>();
 
             //let sub_id = damus.gen_subid(&SubKind::Initial);
             let sub_id = subscriptions::new_sub_id();
-            subs.subs.insert(sub_id.clone(), SubKind::Initial);
+            let sent = match timeline.kind.broad_key() {
+                Some(key) => relay.subscribe_broad(&key, sub_id.clone(), new_filters.clone()),
+                None => relay
+                    .subscribe(sub_id.clone(), new_filters.clone())
+                    .map(|()| true),
+            };
 
-            if let Err(err) = relay.subscribe(sub_id, new_filters) {
-                error!("error subscribing: {err}");
+            match sent {
+                Ok(true) => {
+                    subs.subs.insert(sub_id.clone(), SubKind::Initial);
+                    subs.track_timeline_req(&sub_id, timeline.id, &new_filters);
+                }
+                Ok(false) => {}
+                Err(err) => error!("error subscribing: {err}"),
             }
         }
 
@@ -586,6 +769,7 @@ fn fetch_contact_list(
     );
 
     subs.subs.insert(sub_id.clone(), sub_kind);
+    subs.track_timeline_req(&sub_id, timeline.id, &filter);
 
     info!("fetching contact list from {}", relay.url());
     if let Err(err) = relay.subscribe(sub_id, filter) {
@@ -618,22 +802,39 @@ fn setup_initial_timeline(
     Ok(())
 }
 
-pub fn setup_initial_nostrdb_subs(
+/// Start the subscriptions of timelines being shown for the first time.
+/// Columns in other decks, or other accounts' decks, wait until they're
+/// switched to, so startup only pays for what's on screen.
+pub fn subscribe_visible(
     ndb: &Ndb,
+    since_optimize: bool,
     note_cache: &mut NoteCache,
-    decks_cache: &mut DecksCache,
-) -> Result<()> {
-    for decks in decks_cache.get_all_decks_mut() {
-        for deck in decks.decks_mut() {
-            for timeline in deck.columns_mut().timelines_mut() {
-                if let Err(err) = setup_timeline_nostrdb_sub(ndb, note_cache, timeline) {
-                    error!("setup_initial_nostrdb_subs: {err}");
-                }
+    subs: &mut Subscriptions,
+    pool: &mut RelayPool,
+    columns: &mut Columns,
+) {
+    for timeline in columns.timelines_mut() {
+        // timelines waiting on a contact list subscribe once it's here
+        if timeline.subscription.is_some() || timeline.filter.get_any_ready().is_none() {
+            continue;
+        }
+
+        if let Err(err) = setup_timeline_nostrdb_sub(ndb, note_cache, timeline) {
+            error!("subscribe_visible: {err}");
+            continue;
+        }
+
+        // relays that opened while this was showing already have it, the
+        // ones that open later get it when they do
+        if subs.tracks(timeline.id) {
+            continue;
+        }
+        for relay in &mut pool.relays {
+            if relay.status() == RelayStatus::Connected {
+                send_initial_timeline_filter(ndb, since_optimize, subs, relay, timeline);
             }
         }
     }
-
-    Ok(())
 }
 
 fn setup_timeline_nostrdb_sub(
@@ -659,6 +860,7 @@ fn setup_timeline_nostrdb_sub(
 pub fn is_timeline_ready(
     ndb: &Ndb,
     pool: &mut RelayPool,
+    subs: &mut Subscriptions,
     note_cache: &mut NoteCache,
     timeline: &mut Timeline,
     our_pk: Option<&Pubkey>,
@@ -718,6 +920,7 @@ pub fn is_timeline_ready(
             false
         }
         Ok(filter) => {
+            let filter = timeline.kinds.apply(filter);
             // we just switched to the ready state, we should send initial
             // queries and setup the local subscription
             info!("Found contact list! Setting up local and remote contact list query");
@@ -729,6 +932,9 @@ pub fn is_timeline_ready(
             //let ck = &timeline.kind;
             //let subid = damus.gen_subid(&SubKind::Column(ck.clone()));
             let subid = subscriptions::new_sub_id();
+            subs.subs
+                .insert(subid.clone(), SubKind::Timeline(timeline.kind.clone()));
+            subs.track_timeline_req(&subid, timeline.id, &filter);
             pool.subscribe(subid, filter);
             true
         }
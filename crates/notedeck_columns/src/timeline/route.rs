@@ -1,11 +1,13 @@
 use crate::{
     column::Columns,
     draft::Drafts,
+    hidden::Hidden,
     nav::RenderNavAction,
     profile::ProfileAction,
     timeline::{TimelineCache, TimelineId, TimelineKind},
     ui::{
         self,
+        density::Density,
         note::{NoteOptions, QuoteRepostView},
         profile::ProfileView,
     },
@@ -13,7 +15,7 @@ use crate::{
 
 use enostr::{NoteId, Pubkey};
 use nostrdb::{Ndb, Transaction};
-use notedeck::{Accounts, ImageCache, MuteFun, NoteCache, UnknownIds};
+use notedeck::{tr, Accounts, ImageCache, MuteFun, NoteCache, Settings, UnknownIds, WebOfTrust};
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum TimelineRoute {
@@ -34,25 +36,53 @@ pub fn render_timeline_route(
     note_cache: &mut NoteCache,
     timeline_cache: &mut TimelineCache,
     accounts: &mut Accounts,
+    settings: &Settings,
+    wot: &WebOfTrust,
+    hidden: &Hidden,
     route: TimelineRoute,
     col: usize,
     textmode: bool,
     ui: &mut egui::Ui,
 ) -> Option<RenderNavAction> {
+    Density::get(ui.ctx()).scale_text(ui.style_mut());
+
     match route {
         TimelineRoute::Timeline(timeline_id) => {
-            let note_options = {
-                let is_universe = if let Some(timeline) = columns.find_timeline(timeline_id) {
-                    timeline.kind == TimelineKind::Universe
-                } else {
-                    false
-                };
+            let kind = columns
+                .find_timeline(timeline_id)
+                .map(|timeline| &timeline.kind);
+            let is_universe = kind == Some(&TimelineKind::Universe);
+            let is_hashtag = matches!(kind, Some(TimelineKind::Hashtag(_)))
+                && settings.bool(crate::settings::APP, crate::settings::TRUSTED_HASHTAGS);
 
+            let note_options = {
                 let mut options = NoteOptions::new(is_universe);
                 options.set_textmode(textmode);
                 options
             };
 
+            if is_hashtag {
+                let untrusted = untrusted_count(ui.ctx(), ndb, columns, timeline_id, wot);
+                if untrusted > 0 {
+                    ui.weak(tr!("timeline-untrusted-hidden", count = untrusted));
+                }
+            }
+
+            let muted = accounts.mutefun();
+            let hidden = hidden.clone();
+            let clients = crate::settings::hidden_clients(settings);
+            let is_hidden: Box<MuteFun> = Box::new(move |note, root| {
+                muted(note, root) || hidden.is_hidden(note) || clients.hides(note)
+            });
+            // anyone can tag anything, so hashtag columns only show people
+            // in the account's web of trust
+            let is_muted: Box<MuteFun> = if is_hashtag {
+                let wot = wot.clone();
+                Box::new(move |note, root| is_hidden(note, root) || !wot.is_trusted(note.pubkey()))
+            } else {
+                is_hidden
+            };
+
             let note_action = ui::TimelineView::new(
                 timeline_id,
                 columns,
@@ -60,7 +90,7 @@ pub fn render_timeline_route(
                 note_cache,
                 img_cache,
                 note_options,
-                &accounts.mutefun(),
+                &is_muted,
             )
             .ui(ui);
 
@@ -153,6 +183,41 @@ pub fn render_timeline_route(
     }
 }
 
+/// How many of a timeline's notes are from outside the web of trust.
+/// Only counted again when the timeline or the web of trust changes.
+fn untrusted_count(
+    ctx: &egui::Context,
+    ndb: &Ndb,
+    columns: &Columns,
+    timeline_id: TimelineId,
+    wot: &WebOfTrust,
+) -> usize {
+    let Some(timeline) = columns.find_timeline(timeline_id) else {
+        return 0;
+    };
+    let notes = &timeline.current_view().notes;
+
+    let id = egui::Id::new(("untrusted-count", timeline_id));
+    let key = (notes.len(), wot.generation());
+    if let Some((cached_key, count)) = ctx.data(|d| d.get_temp::<((usize, u64), usize)>(id)) {
+        if cached_key == key {
+            return count;
+        }
+    }
+
+    let Ok(txn) = Transaction::new(ndb) else {
+        return 0;
+    };
+    let count = notes
+        .iter()
+        .filter_map(|note| ndb.get_note_by_key(&txn, note.key).ok())
+        .filter(|note| !wot.is_trusted(note.pubkey()))
+        .count();
+
+    ctx.data_mut(|d| d.insert_temp(id, (key, count)));
+    count
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn render_profile_route(
     pubkey: &Pubkey,
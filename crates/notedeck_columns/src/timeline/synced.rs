@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use enostr::Filter;
+use serde::{Deserialize, Serialize};
+
+/// Lots of timelines finish syncing at once on startup, so writes are
+/// batched
+const SAVE_DELAY: Duration = Duration::from_secs(5);
+
+/// Relay clocks drift, ask for a little more than we need
+const SINCE_GAP: u64 = 60;
+
+/// The newest note each relay had for each timeline filter when it last
+/// sent us everything it had (EOSE). Timelines ask for notes since then
+/// on the next launch instead of downloading it all again.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Synced {
+    newest: HashMap<String, u64>,
+    #[serde(skip)]
+    unsaved_since: Option<Instant>,
+}
+
+fn key(relay: &str, filters: &[Filter]) -> Option<String> {
+    let filters = filters
+        .iter()
+        .map(|filter| filter.json().ok())
+        .collect::<Option<Vec<String>>>()?;
+    Some(format!("{relay} {}", filters.join(",")))
+}
+
+impl Synced {
+    /// What to ask `relay` for `filters` since, if it has sent them before
+    pub fn since(&self, relay: &str, filters: &[Filter]) -> Option<u64> {
+        let newest = self.newest.get(&key(relay, filters)?)?;
+        Some(newest.saturating_sub(SINCE_GAP))
+    }
+
+    pub fn record(&mut self, relay: &str, filters: &[Filter], created_at: u64) {
+        let Some(key) = key(relay, filters) else {
+            return;
+        };
+
+        let newest = self.newest.entry(key).or_default();
+        if created_at > *newest {
+            *newest = created_at;
+            self.unsaved_since.get_or_insert_with(Instant::now);
+        }
+    }
+
+    /// Forget everything, so timelines are fetched in full again
+    pub fn clear(&mut self) {
+        self.newest.clear();
+        self.unsaved_since.get_or_insert_with(Instant::now);
+    }
+
+    pub fn should_save(&mut self) -> bool {
+        match self.unsaved_since {
+            Some(since) if since.elapsed() >= SAVE_DELAY => {
+                self.unsaved_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synced_since() {
+        let relay = "wss://relay.damus.io";
+        let filters = vec![Filter::new().kinds([1]).limit(500).build()];
+        let other = vec![Filter::new().kinds([6]).limit(500).build()];
+        let mut synced = Synced::default();
+
+        assert_eq!(synced.since(relay, &filters), None);
+
+        synced.record(relay, &filters, 1_000);
+        // older notes don't move it back
+        synced.record(relay, &filters, 500);
+        assert_eq!(synced.since(relay, &filters), Some(1_000 - SINCE_GAP));
+
+        assert_eq!(synced.since("wss://nos.lol", &filters), None);
+        assert_eq!(synced.since(relay, &other), None);
+
+        synced.clear();
+        assert_eq!(synced.since(relay, &filters), None);
+    }
+}
@@ -0,0 +1,523 @@
+//! What's taking off right now, for the Trending column. Notes are ranked
+//! by how fast they're picking up reactions, reposts and zaps inside a
+//! time window, hashtags by how many people are posting with them. People
+//! closer in the web of trust count for more, so a swarm of strangers
+//! can't push something to the top. A remote trending service can be set
+//! up in settings instead.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use nostrdb::{Filter, Ndb, Note, Transaction};
+use notedeck::{note::tag_strings, time::unix_now, WebOfTrust};
+use poll_promise::Promise;
+use serde::Deserialize;
+use tracing::{debug, error};
+
+/// How often the rankings are worked out again while the column is open
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Most reactions, reposts and zaps to read per refresh
+const INTERACTION_LIMIT: i32 = 20_000;
+/// Most notes to read hashtags from per refresh
+const POST_LIMIT: i32 = 10_000;
+/// How many of each we rank
+const TOP_NOTES: usize = 50;
+const TOP_HASHTAGS: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum InteractionKind {
+    Reaction,
+    Repost,
+    Zap,
+}
+
+impl InteractionKind {
+    fn weight(&self) -> f32 {
+        match self {
+            InteractionKind::Reaction => 1.0,
+            InteractionKind::Repost => 2.0,
+            InteractionKind::Zap => 3.0,
+        }
+    }
+}
+
+/// Someone reacting to, reposting or zapping a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Interaction {
+    kind: InteractionKind,
+    actor: [u8; 32],
+    target: [u8; 32],
+    created_at: u64,
+}
+
+impl Interaction {
+    fn from_note(note: &Note) -> Option<Self> {
+        Self::from_tags(
+            note.kind(),
+            *note.pubkey(),
+            note.content(),
+            note.created_at(),
+            &tag_strings(note),
+        )
+    }
+
+    fn from_tags(
+        kind: u32,
+        author: [u8; 32],
+        content: &str,
+        created_at: u64,
+        tags: &[Vec<String>],
+    ) -> Option<Self> {
+        let kind = match kind {
+            // a downvote isn't a sign of something taking off
+            7 if content == "-" => return None,
+            7 => InteractionKind::Reaction,
+            6 => InteractionKind::Repost,
+            9735 => InteractionKind::Zap,
+            _ => return None,
+        };
+
+        let tag_key = |name: &str| {
+            tags.iter()
+                .filter(|tag| tag[0] == name)
+                .filter_map(|tag| parse_id(tag.get(1)?))
+                .last()
+        };
+
+        // the reacted to note is the last e tag, zap receipts are signed
+        // by the zapper service and name the sender in their P tag
+        let target = tag_key("e")?;
+        let actor = match kind {
+            InteractionKind::Zap => tag_key("P")?,
+            _ => author,
+        };
+
+        Some(Interaction {
+            kind,
+            actor,
+            target,
+            created_at,
+        })
+    }
+}
+
+fn parse_id(hex_id: &str) -> Option<[u8; 32]> {
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(hex_id, &mut bytes).ok()?;
+    Some(bytes)
+}
+
+/// How much someone's interactions count, by how far out in the web of
+/// trust they are. Without a web of trust everyone counts the same.
+fn trust_weight(wot: &WebOfTrust, pubkey: &[u8; 32]) -> f32 {
+    if wot.is_empty() {
+        return 1.0;
+    }
+    match wot.hops(pubkey) {
+        Some(0..=1) => 1.0,
+        Some(2) => 0.5,
+        Some(_) => 0.25,
+        None => 0.1,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrendingNote {
+    pub id: [u8; 32],
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrendingHashtag {
+    pub hashtag: String,
+    /// How many people posted with it in the window
+    pub people: usize,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum TrendingSource {
+    #[default]
+    Local,
+    Remote,
+    /// The remote service failed, so these are from local data
+    Fallback(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrendingResults {
+    pub notes: Vec<TrendingNote>,
+    pub hashtags: Vec<TrendingHashtag>,
+    pub source: TrendingSource,
+}
+
+/// Rank notes by their interactions. Each person counts once per kind of
+/// interaction with a note, and recent ones count more than ones from the
+/// start of the window.
+fn rank_notes(
+    interactions: &[Interaction],
+    now: u64,
+    window: Duration,
+    weight: impl Fn(&[u8; 32]) -> f32,
+) -> Vec<TrendingNote> {
+    let window = window.as_secs().max(1) as f32;
+    let mut seen = HashSet::new();
+    let mut scores: HashMap<[u8; 32], f32> = HashMap::new();
+
+    for interaction in interactions {
+        if !seen.insert((interaction.actor, interaction.target, interaction.kind)) {
+            continue;
+        }
+        let age = now.saturating_sub(interaction.created_at) as f32;
+        let recency = 0.5 + 0.5 * (1.0 - (age / window).min(1.0));
+        *scores.entry(interaction.target).or_default() +=
+            interaction.kind.weight() * weight(&interaction.actor) * recency;
+    }
+
+    let mut notes: Vec<TrendingNote> = scores
+        .into_iter()
+        .map(|(id, score)| TrendingNote { id, score })
+        .collect();
+    notes.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+    notes.truncate(TOP_NOTES);
+    notes
+}
+
+/// Rank hashtags by the people who posted with them, each counting once
+fn rank_hashtags(
+    posts: impl Iterator<Item = ([u8; 32], Vec<String>)>,
+    weight: impl Fn(&[u8; 32]) -> f32,
+) -> Vec<TrendingHashtag> {
+    let mut authors: HashMap<String, HashSet<[u8; 32]>> = HashMap::new();
+    for (author, hashtags) in posts {
+        for hashtag in hashtags {
+            authors.entry(hashtag).or_default().insert(author);
+        }
+    }
+
+    let mut hashtags: Vec<TrendingHashtag> = authors
+        .into_iter()
+        .map(|(hashtag, authors)| TrendingHashtag {
+            score: authors.iter().map(&weight).sum(),
+            people: authors.len(),
+            hashtag,
+        })
+        .collect();
+    hashtags.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.hashtag.cmp(&b.hashtag))
+    });
+    hashtags.truncate(TOP_HASHTAGS);
+    hashtags
+}
+
+fn hashtags(note: &Note) -> Vec<String> {
+    let mut hashtags: Vec<String> = tag_strings(note)
+        .into_iter()
+        .filter(|tag| tag[0] == "t")
+        .filter_map(|tag| tag.get(1).map(|t| t.trim_start_matches('#').to_lowercase()))
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    hashtags.sort();
+    hashtags.dedup();
+    hashtags
+}
+
+/// Work out the rankings from what nostrdb has seen in the window
+fn rank_local(ndb: &Ndb, wot: &WebOfTrust, window: Duration) -> TrendingResults {
+    let Ok(txn) = Transaction::new(ndb) else {
+        return TrendingResults::default();
+    };
+    let now = unix_now();
+    let since = now.saturating_sub(window.as_secs());
+    let weight = |pubkey: &[u8; 32]| trust_weight(wot, pubkey);
+
+    let filter = Filter::new()
+        .kinds([6, 7, 9735])
+        .since(since)
+        .limit(INTERACTION_LIMIT as u64)
+        .build();
+    let interactions: Vec<Interaction> = ndb
+        .query(&txn, &[filter], INTERACTION_LIMIT)
+        .map(|results| {
+            results
+                .iter()
+                .filter_map(|result| Interaction::from_note(&result.note))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let filter = Filter::new()
+        .kinds([1])
+        .since(since)
+        .limit(POST_LIMIT as u64)
+        .build();
+    let posts = ndb.query(&txn, &[filter], POST_LIMIT).unwrap_or_default();
+
+    TrendingResults {
+        notes: rank_notes(&interactions, now, window, weight),
+        hashtags: rank_hashtags(
+            posts
+                .iter()
+                .map(|result| (*result.note.pubkey(), hashtags(&result.note))),
+            weight,
+        ),
+        source: TrendingSource::Local,
+    }
+}
+
+/// Notes are either bare ids or objects with the id and, optionally, the
+/// event itself
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RemoteNote {
+    Id(String),
+    Event {
+        id: String,
+        event: Option<serde_json::Value>,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RemoteResponse {
+    #[serde(default)]
+    notes: Vec<RemoteNote>,
+    #[serde(default)]
+    hashtags: Vec<String>,
+}
+
+fn parse_remote(ndb: Option<&Ndb>, bytes: &[u8]) -> Result<TrendingResults, String> {
+    let response: RemoteResponse = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+    let count = response.notes.len() as f32;
+
+    let notes = response
+        .notes
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, note)| {
+            let id = match note {
+                RemoteNote::Id(id) => id,
+                RemoteNote::Event { id, event } => {
+                    // saves asking relays for it
+                    if let (Some(ndb), Some(event)) = (ndb, event) {
+                        let _ = ndb.process_event(&format!("[\"EVENT\",\"trending\",{event}]"));
+                    }
+                    id
+                }
+            };
+            Some(TrendingNote {
+                id: parse_id(&id)?,
+                score: count - i as f32,
+            })
+        })
+        .take(TOP_NOTES)
+        .collect();
+
+    let tags = response.hashtags.len() as f32;
+    let hashtags = response
+        .hashtags
+        .into_iter()
+        .map(|hashtag| hashtag.trim_start_matches('#').to_lowercase())
+        .filter(|hashtag| !hashtag.is_empty())
+        .enumerate()
+        .map(|(i, hashtag)| TrendingHashtag {
+            hashtag,
+            people: 0,
+            score: tags - i as f32,
+        })
+        .take(TOP_HASHTAGS)
+        .collect();
+
+    Ok(TrendingResults {
+        notes,
+        hashtags,
+        source: TrendingSource::Remote,
+    })
+}
+
+fn rank_remote(ndb: &Ndb, url: &str) -> Result<TrendingResults, String> {
+    let response = ehttp::fetch_blocking(&ehttp::Request::get(url))?;
+    if !response.ok {
+        return Err(format!("{} {}", response.status, response.status_text));
+    }
+    parse_remote(Some(ndb), &response.bytes)
+}
+
+/// The latest rankings, refreshed in the background while someone is
+/// looking at them
+#[derive(Default)]
+pub struct Trending {
+    results: TrendingResults,
+    job: Option<Promise<TrendingResults>>,
+    refreshed: Option<Instant>,
+    /// The window and service the results are for
+    params: Option<(Duration, String)>,
+}
+
+impl Trending {
+    /// Pick up finished rankings, and start working them out again when
+    /// they're old or the settings changed
+    pub fn update(
+        &mut self,
+        ndb: &Ndb,
+        wot: &WebOfTrust,
+        window: Duration,
+        api: &str,
+        ctx: &egui::Context,
+    ) {
+        if let Some(job) = self.job.take() {
+            match job.try_take() {
+                Ok(results) => self.results = results,
+                Err(job) => self.job = Some(job),
+            }
+        }
+
+        let params = (window, api.trim().to_owned());
+        let stale = self.params.as_ref() != Some(&params)
+            || !self
+                .refreshed
+                .is_some_and(|refreshed| refreshed.elapsed() < REFRESH_INTERVAL);
+        if stale && self.job.is_none() {
+            self.refreshed = Some(Instant::now());
+            self.job = Some(refresh(ndb.clone(), wot.clone(), params.clone(), ctx));
+            self.params = Some(params);
+        }
+
+        ctx.request_repaint_after(REFRESH_INTERVAL);
+    }
+
+    pub fn results(&self) -> &TrendingResults {
+        &self.results
+    }
+
+    pub fn is_refreshing(&self) -> bool {
+        self.job.is_some()
+    }
+}
+
+fn refresh(
+    ndb: Ndb,
+    wot: WebOfTrust,
+    (window, api): (Duration, String),
+    ctx: &egui::Context,
+) -> Promise<TrendingResults> {
+    let ctx = ctx.clone();
+    Promise::spawn_thread("trending", move || {
+        let results = if api.is_empty() {
+            rank_local(&ndb, &wot, window)
+        } else {
+            match rank_remote(&ndb, &api) {
+                Ok(results) => results,
+                Err(err) => {
+                    error!("trending service {api} failed: {err}");
+                    TrendingResults {
+                        source: TrendingSource::Fallback(err),
+                        ..rank_local(&ndb, &wot, window)
+                    }
+                }
+            }
+        };
+        debug!(
+            "trending: {} notes, {} hashtags",
+            results.notes.len(),
+            results.hashtags.len()
+        );
+        ctx.request_repaint();
+        results
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn test_interactions() {
+        let note = "aa".repeat(32);
+        let sender = "bb".repeat(32);
+        let e = tag(&["e", &note]);
+
+        let reaction = Interaction::from_tags(7, [1; 32], "+", 5, &[e.clone()]).unwrap();
+        assert_eq!(reaction.kind, InteractionKind::Reaction);
+        assert_eq!((reaction.actor, reaction.target), ([1; 32], [0xaa; 32]));
+
+        assert!(Interaction::from_tags(7, [1; 32], "-", 5, &[e.clone()]).is_none());
+        assert!(Interaction::from_tags(1, [1; 32], "", 5, &[e.clone()]).is_none());
+        assert!(Interaction::from_tags(6, [1; 32], "", 5, &[]).is_none());
+
+        let zap = Interaction::from_tags(9735, [9; 32], "", 5, &[e, tag(&["P", &sender])]);
+        assert_eq!(zap.map(|zap| zap.actor), Some([0xbb; 32]));
+    }
+
+    #[test]
+    fn test_rank_notes() {
+        let interaction = |kind, actor: u8, target: u8, created_at| Interaction {
+            kind,
+            actor: [actor; 32],
+            target: [target; 32],
+            created_at,
+        };
+        let window = Duration::from_secs(100);
+        let interactions = vec![
+            interaction(InteractionKind::Reaction, 1, 10, 100),
+            // the same person reacting twice only counts once
+            interaction(InteractionKind::Reaction, 1, 10, 100),
+            interaction(InteractionKind::Zap, 2, 20, 100),
+            // old news counts for less
+            interaction(InteractionKind::Zap, 3, 30, 0),
+            // strangers count for less
+            interaction(InteractionKind::Zap, 4, 40, 100),
+        ];
+
+        let ranked = rank_notes(&interactions, 100, window, |pk| {
+            if pk == &[4; 32] {
+                0.1
+            } else {
+                1.0
+            }
+        });
+        let ids: Vec<u8> = ranked.iter().map(|note| note.id[0]).collect();
+        assert_eq!(ids, vec![20, 30, 10, 40]);
+        assert_eq!(ranked[2].score, 1.0);
+    }
+
+    #[test]
+    fn test_rank_hashtags() {
+        let posts = vec![
+            ([1; 32], vec!["nostr".to_owned()]),
+            ([1; 32], vec!["nostr".to_owned()]),
+            ([2; 32], vec!["nostr".to_owned(), "art".to_owned()]),
+            ([3; 32], vec!["art".to_owned()]),
+            ([4; 32], vec!["art".to_owned()]),
+        ];
+
+        let ranked = rank_hashtags(posts.into_iter(), |_| 1.0);
+        assert_eq!(ranked[0].hashtag, "art");
+        assert_eq!(ranked[0].people, 3);
+        assert_eq!(ranked[1].hashtag, "nostr");
+        assert_eq!(ranked[1].people, 2);
+    }
+
+    #[test]
+    fn test_parse_remote() {
+        let body = format!(
+            r##"{{"notes":["{}",{{"id":"{}"}},"nope"],"hashtags":["#Nostr",""]}}"##,
+            "aa".repeat(32),
+            "bb".repeat(32)
+        );
+        let results = parse_remote(None, body.as_bytes()).unwrap();
+        assert_eq!(results.source, TrendingSource::Remote);
+        let ids: Vec<[u8; 32]> = results.notes.iter().map(|note| note.id).collect();
+        assert_eq!(ids, vec![[0xaa; 32], [0xbb; 32]]);
+        assert!(results.notes[0].score > results.notes[1].score);
+        assert_eq!(results.hashtags[0].hashtag, "nostr");
+        assert_eq!(results.hashtags.len(), 1);
+
+        assert!(parse_remote(None, b"<html>").is_err());
+    }
+}
@@ -0,0 +1,25 @@
+use egui::{Response, Stroke, WidgetInfo, WidgetType};
+
+/// Give a custom painted widget a name screen readers can announce
+pub fn label(resp: &Response, typ: WidgetType, label: &str) {
+    let enabled = resp.enabled();
+    resp.widget_info(|| WidgetInfo::labeled(typ, enabled, label));
+}
+
+/// Painted widgets don't get egui's focus styling, so draw a high
+/// contrast ring around them when they're selected with the keyboard
+pub fn focus_outline(ui: &egui::Ui, resp: &Response) {
+    if !resp.has_focus() {
+        return;
+    }
+
+    let stroke = Stroke::new(2.0, ui.visuals().strong_text_color());
+    ui.painter().rect_stroke(resp.rect.expand(2.0), 4.0, stroke);
+}
+
+/// Name a painted button and outline it when it has focus. Buttons
+/// sensing clicks are already focusable and activate with enter or space.
+pub fn button(ui: &egui::Ui, resp: &Response, name: &str) {
+    label(resp, WidgetType::Button, name);
+    focus_outline(ui, resp);
+}
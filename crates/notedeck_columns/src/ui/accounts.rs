@@ -5,7 +5,9 @@ use egui::{
 use nostrdb::{Ndb, Transaction};
 use notedeck::{Accounts, ImageCache};
 
+use super::a11y;
 use super::profile::preview::SimpleProfilePreview;
+use crate::profile::get_display_name;
 
 pub struct AccountsView<'a> {
     ndb: &'a Ndb,
@@ -84,6 +86,11 @@ impl<'a> AccountsView<'a> {
                     let profile_peview_view = {
                         let max_size = egui::vec2(ui.available_width(), 77.0);
                         let resp = ui.allocate_response(max_size, egui::Sense::click());
+                        a11y::button(
+                            ui,
+                            &resp,
+                            &format!("Switch to {}", get_display_name(profile.as_ref()).name()),
+                        );
                         ui.allocate_new_ui(UiBuilder::new().max_rect(resp.rect), |ui| {
                             let preview =
                                 SimpleProfilePreview::new(profile.as_ref(), img_cache, has_nsec);
@@ -26,6 +26,10 @@ pub enum AddColumnResponse {
     Hashtag,
     UndecidedIndividual,
     ExternalIndividual,
+    Bookmarks,
+    MyPosts,
+    ExplorePeople,
+    Trending,
 }
 
 pub enum NotificationColumnType {
@@ -45,6 +49,10 @@ enum AddColumnOption {
     UndecidedIndividual,
     ExternalIndividual,
     Individual(PubkeySource),
+    Bookmarks,
+    MyPosts,
+    ExplorePeople,
+    Trending,
 }
 
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
@@ -90,6 +98,10 @@ impl AddColumnOption {
                 tlk.into_timeline(ndb, cur_account.map(|a| a.pubkey.bytes()))
                     .map(AddColumnResponse::Timeline)
             }
+            AddColumnOption::Bookmarks => Some(AddColumnResponse::Bookmarks),
+            AddColumnOption::MyPosts => Some(AddColumnResponse::MyPosts),
+            AddColumnOption::ExplorePeople => Some(AddColumnResponse::ExplorePeople),
+            AddColumnOption::Trending => Some(AddColumnResponse::Trending),
         }
     }
 }
@@ -332,7 +344,7 @@ impl<'a> AddColumnView<'a> {
         painter.galley(title_corner_pos, title_galley, fallback_color);
         painter.galley(desc_corner_pos, desc_galley, fallback_color);
 
-        helper.take_animation_response()
+        helper.take_accessible_response(ui, data.title)
     }
 
     fn get_base_options(&self) -> Vec<ColumnOptionData> {
@@ -343,6 +355,12 @@ impl<'a> AddColumnView<'a> {
             icon: egui::include_image!("../../../../assets/icons/universe_icon_dark_4x.png"),
             option: AddColumnOption::Universe,
         });
+        vec.push(ColumnOptionData {
+            title: "Trending",
+            description: "Notes and hashtags taking off right now",
+            icon: egui::include_image!("../../../../assets/icons/repost_icon_4x.png"),
+            option: AddColumnOption::Trending,
+        });
 
         if let Some(acc) = self.cur_account {
             let source = if acc.secret_key.is_some() {
@@ -376,6 +394,26 @@ impl<'a> AddColumnView<'a> {
             icon: egui::include_image!("../../../../assets/icons/profile_icon_4x.png"),
             option: AddColumnOption::UndecidedIndividual,
         });
+        if self.cur_account.is_some() {
+            vec.push(ColumnOptionData {
+                title: "Bookmarks",
+                description: "Notes you've saved, and your bookmark sets",
+                icon: egui::include_image!("../../../../assets/icons/bookmark_icon_4x.png"),
+                option: AddColumnOption::Bookmarks,
+            });
+            vec.push(ColumnOptionData {
+                title: "My posts",
+                description: "Everything you've published, and which relays took it",
+                icon: egui::include_image!("../../../../assets/icons/profile_icon_4x.png"),
+                option: AddColumnOption::MyPosts,
+            });
+            vec.push(ColumnOptionData {
+                title: "Explore people",
+                description: "Follow packs to find people worth following",
+                icon: egui::include_image!("../../../../assets/icons/add_account_icon_4x.png"),
+                option: AddColumnOption::ExplorePeople,
+            });
+        }
 
         vec
     }
@@ -549,6 +587,30 @@ pub fn render_add_column_routes(
                         AddColumnRoute::ExternalIndividual,
                     ));
             }
+            AddColumnResponse::Bookmarks => {
+                app.columns_mut(ctx.accounts)
+                    .column_mut(col)
+                    .router_mut()
+                    .route_to_replaced(crate::route::Route::Bookmarks);
+            }
+            AddColumnResponse::MyPosts => {
+                app.columns_mut(ctx.accounts)
+                    .column_mut(col)
+                    .router_mut()
+                    .route_to_replaced(crate::route::Route::MyPosts);
+            }
+            AddColumnResponse::ExplorePeople => {
+                app.columns_mut(ctx.accounts)
+                    .column_mut(col)
+                    .router_mut()
+                    .route_to_replaced(crate::route::Route::ExplorePeople);
+            }
+            AddColumnResponse::Trending => {
+                app.columns_mut(ctx.accounts)
+                    .column_mut(col)
+                    .router_mut()
+                    .route_to_replaced(crate::route::Route::Trending);
+            }
         };
     }
 }
@@ -1,5 +1,7 @@
 use egui::{Pos2, Rect, Response, Sense};
 
+use super::a11y;
+
 pub fn hover_expand(
     ui: &mut egui::Ui,
     id: egui::Id,
@@ -119,6 +121,13 @@ impl AnimationHelper {
         self.response
     }
 
+    /// Take the response, naming it for screen readers and outlining it
+    /// when it has keyboard focus
+    pub fn take_accessible_response(self, ui: &egui::Ui, label: &str) -> egui::Response {
+        a11y::button(ui, &self.response, label);
+        self.response
+    }
+
     // Scale a minimum position from center to the current animation position
     pub fn scale_from_center(&self, x_min: f32, y_min: f32) -> Pos2 {
         Pos2::new(
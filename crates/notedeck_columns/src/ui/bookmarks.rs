@@ -0,0 +1,366 @@
+use std::collections::{HashMap, HashSet};
+
+use egui::{Frame, RichText};
+use enostr::NoteId;
+use nostrdb::{Ndb, Transaction};
+use notedeck::{tr, BookmarkList, Bookmarks, ImageCache, NoteCache, UnknownIds};
+use uuid::Uuid;
+
+use crate::{
+    actionbar::NoteAction,
+    ui::{self, note::NoteOptions},
+};
+
+pub enum BookmarkAction {
+    /// Sign and publish a new version of a bookmark list
+    Publish(BookmarkList),
+    DeleteSet(String),
+    Note(NoteAction),
+}
+
+/// Pick which of the account's bookmark lists a note is saved in
+pub struct SaveBookmarkView<'a> {
+    bookmarks: &'a Bookmarks,
+    note_id: &'a NoteId,
+    id_string_map: &'a mut HashMap<egui::Id, String>,
+}
+
+impl<'a> SaveBookmarkView<'a> {
+    pub fn new(
+        bookmarks: &'a Bookmarks,
+        note_id: &'a NoteId,
+        id_string_map: &'a mut HashMap<egui::Id, String>,
+    ) -> Self {
+        SaveBookmarkView {
+            bookmarks,
+            note_id,
+            id_string_map,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<BookmarkAction> {
+        let mut action = None;
+        let note_id = self.note_id.bytes();
+
+        Frame::none()
+            .inner_margin(egui::Margin::same(16.0))
+            .show(ui, |ui| {
+                ui.label(RichText::new(tr!("bookmarks-save-to")).strong());
+                ui.add_space(8.0);
+
+                if let Some(list) = list_checkbox(ui, &self.bookmarks.main, note_id) {
+                    action = Some(BookmarkAction::Publish(list));
+                }
+
+                for set in self.bookmarks.sets() {
+                    if let Some(list) = list_checkbox(ui, set, note_id) {
+                        action = Some(BookmarkAction::Publish(list));
+                    }
+                }
+
+                ui.add_space(8.0);
+                if let Some(mut set) = new_set_ui(ui, self.id_string_map) {
+                    set.add(note_id, false);
+                    action = Some(BookmarkAction::Publish(set));
+                }
+            });
+
+        action
+    }
+}
+
+/// Whether the note is in `list`, and whether it's private there. Returns
+/// the changed list when either is toggled.
+fn list_checkbox(
+    ui: &mut egui::Ui,
+    list: &BookmarkList,
+    note_id: &[u8; 32],
+) -> Option<BookmarkList> {
+    let mut saved = list.contains(note_id);
+    let mut private = list.is_private(note_id);
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        changed |= ui.checkbox(&mut saved, list_title(list)).changed();
+        if saved {
+            changed |= ui
+                .checkbox(&mut private, tr!("bookmarks-private"))
+                .on_hover_text(tr!("bookmarks-private-hint"))
+                .changed();
+        }
+    });
+
+    if !changed {
+        return None;
+    }
+
+    let mut list = list.clone();
+    if saved {
+        list.add(note_id, private);
+    } else {
+        list.remove(note_id);
+    }
+    Some(list)
+}
+
+fn list_title(list: &BookmarkList) -> String {
+    match (&list.identifier, &list.title) {
+        (None, _) => tr!("bookmarks-main-list"),
+        (Some(_), Some(title)) if !title.is_empty() => title.clone(),
+        (Some(identifier), _) => identifier.clone(),
+    }
+}
+
+/// A name field and button for starting a new bookmark set
+fn new_set_ui(
+    ui: &mut egui::Ui,
+    id_string_map: &mut HashMap<egui::Id, String>,
+) -> Option<BookmarkList> {
+    let id = ui.id().with("new-bookmark-set");
+    let title = id_string_map.entry(id).or_default();
+
+    let mut created = None;
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(title)
+                .hint_text(tr!("bookmarks-new-set-hint"))
+                .desired_width(180.0),
+        );
+
+        let name = title.trim();
+        if ui
+            .add_enabled(
+                !name.is_empty(),
+                egui::Button::new(tr!("bookmarks-new-set")),
+            )
+            .clicked()
+        {
+            created = Some(BookmarkList::set(Uuid::new_v4().to_string(), name));
+        }
+    });
+
+    if created.is_some() {
+        id_string_map.remove(&id);
+    }
+
+    created
+}
+
+/// A column of bookmarked notes, with a picker for which list or set to
+/// show and controls for managing sets
+pub struct BookmarksView<'a> {
+    ndb: &'a Ndb,
+    note_cache: &'a mut NoteCache,
+    img_cache: &'a mut ImageCache,
+    unknown_ids: &'a mut UnknownIds,
+    bookmarks: &'a Bookmarks,
+    id_string_map: &'a mut HashMap<egui::Id, String>,
+    note_options: NoteOptions,
+}
+
+impl<'a> BookmarksView<'a> {
+    pub fn new(
+        ndb: &'a Ndb,
+        note_cache: &'a mut NoteCache,
+        img_cache: &'a mut ImageCache,
+        unknown_ids: &'a mut UnknownIds,
+        bookmarks: &'a Bookmarks,
+        id_string_map: &'a mut HashMap<egui::Id, String>,
+        note_options: NoteOptions,
+    ) -> Self {
+        BookmarksView {
+            ndb,
+            note_cache,
+            img_cache,
+            unknown_ids,
+            bookmarks,
+            id_string_map,
+            note_options,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<BookmarkAction> {
+        let selected_id = ui.id().with("bookmark-set");
+        let selected = self.id_string_map.get(&selected_id).cloned();
+        let bookmarks = self.bookmarks;
+        let list = bookmarks
+            .get(selected.as_deref())
+            .unwrap_or(&bookmarks.main);
+
+        let mut action = None;
+        Frame::none()
+            .inner_margin(egui::Margin::symmetric(8.0, 4.0))
+            .show(ui, |ui| {
+                if let Some(resp) = self.toolbar(ui, list, selected_id) {
+                    action = Some(resp);
+                }
+            });
+        ui::hline(ui);
+
+        if list.is_empty() {
+            ui.add_space(16.0);
+            ui.vertical_centered(|ui| {
+                ui.label(RichText::new(tr!("bookmarks-empty")).weak());
+            });
+            return action;
+        }
+
+        let txn = Transaction::new(self.ndb).expect("txn");
+        let requested_id = ui.id().with("requested-bookmarks");
+        let mut requested: HashSet<[u8; 32]> =
+            ui.data(|d| d.get_temp(requested_id)).unwrap_or_default();
+
+        // newest bookmarks first
+        let note_ids: Vec<[u8; 32]> = list.note_ids().collect();
+        egui::ScrollArea::vertical()
+            .id_salt(("bookmarks", selected.as_deref()))
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for note_id in note_ids.iter().rev() {
+                    let Ok(note) = self.ndb.get_note_by_id(&txn, note_id) else {
+                        // ask relays for it, once
+                        if requested.insert(*note_id) {
+                            self.unknown_ids.add_note_id_if_missing(
+                                self.ndb,
+                                &txn,
+                                &NoteId::new(*note_id),
+                            );
+                        }
+                        ui::padding(8.0, ui, |ui| {
+                            ui.label(RichText::new(tr!("bookmarks-loading")).weak());
+                        });
+                        ui::hline(ui);
+                        continue;
+                    };
+
+                    ui::padding(8.0, ui, |ui| {
+                        let resp =
+                            ui::NoteView::new(self.ndb, self.note_cache, self.img_cache, &note)
+                                .note_options(self.note_options)
+                                .show(ui);
+
+                        if let Some(note_action) = resp.action {
+                            action = Some(BookmarkAction::Note(note_action));
+                        }
+                        if let Some(context) = resp.context_selection {
+                            context.process(ui, &note);
+                        }
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                            if ui.small_button(tr!("bookmarks-remove")).clicked() {
+                                let mut changed = list.clone();
+                                changed.remove(note_id);
+                                action = Some(BookmarkAction::Publish(changed));
+                            }
+                        });
+                    });
+                    ui::hline(ui);
+                }
+            });
+
+        ui.data_mut(|d| d.insert_temp(requested_id, requested));
+
+        action
+    }
+
+    fn toolbar(
+        &mut self,
+        ui: &mut egui::Ui,
+        list: &BookmarkList,
+        selected_id: egui::Id,
+    ) -> Option<BookmarkAction> {
+        let mut action = None;
+
+        ui.horizontal(|ui| {
+            let mut picked = None;
+            egui::ComboBox::from_id_salt(selected_id)
+                .selected_text(list_title(list))
+                .show_ui(ui, |ui| {
+                    let main = &self.bookmarks.main;
+                    if ui
+                        .selectable_label(list.identifier.is_none(), list_title(main))
+                        .clicked()
+                    {
+                        picked = Some(None);
+                    }
+                    for set in self.bookmarks.sets() {
+                        let selected = set.identifier == list.identifier;
+                        if ui.selectable_label(selected, list_title(set)).clicked() {
+                            picked = Some(set.identifier.clone());
+                        }
+                    }
+                });
+
+            match picked {
+                Some(Some(identifier)) => {
+                    self.id_string_map.insert(selected_id, identifier);
+                }
+                Some(None) => {
+                    self.id_string_map.remove(&selected_id);
+                }
+                None => {}
+            }
+
+            ui.label(RichText::new(list.len().to_string()).weak());
+
+            if let Some(identifier) = &list.identifier {
+                if ui
+                    .small_button(tr!("bookmarks-delete-set"))
+                    .on_hover_text(tr!("bookmarks-delete-set-hint"))
+                    .double_clicked()
+                {
+                    action = Some(BookmarkAction::DeleteSet(identifier.clone()));
+                    self.id_string_map.remove(&selected_id);
+                }
+            }
+        });
+
+        if let Some(identifier) = &list.identifier {
+            if let Some(renamed) = rename_ui(ui, list, identifier, self.id_string_map) {
+                action = Some(BookmarkAction::Publish(renamed));
+            }
+        }
+
+        if let Some(set) = new_set_ui(ui, self.id_string_map) {
+            self.id_string_map
+                .insert(selected_id, set.identifier.clone().unwrap_or_default());
+            action = Some(BookmarkAction::Publish(set));
+        }
+
+        action
+    }
+}
+
+fn rename_ui(
+    ui: &mut egui::Ui,
+    list: &BookmarkList,
+    identifier: &str,
+    id_string_map: &mut HashMap<egui::Id, String>,
+) -> Option<BookmarkList> {
+    let id = ui.id().with(("rename-bookmark-set", identifier));
+    let title = id_string_map
+        .entry(id)
+        .or_insert_with(|| list.title.clone().unwrap_or_default());
+
+    let mut renamed = None;
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(title).desired_width(180.0));
+
+        let name = title.trim();
+        let changed = !name.is_empty() && Some(name) != list.title.as_deref();
+        if ui
+            .add_enabled(changed, egui::Button::new(tr!("bookmarks-rename")))
+            .clicked()
+        {
+            let mut list = list.clone();
+            list.title = Some(name.to_owned());
+            renamed = Some(list);
+        }
+    });
+
+    if renamed.is_some() {
+        id_string_map.remove(&id);
+    }
+
+    renamed
+}
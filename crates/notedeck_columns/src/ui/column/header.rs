@@ -2,10 +2,11 @@ use crate::colors;
 use crate::column::ColumnsAction;
 use crate::nav::RenderNavAction;
 use crate::nav::SwitchingAction;
+use crate::notifications::{self, ThreadNotifications, ThreadNotify};
 use crate::{
     column::Columns,
     route::Route,
-    timeline::{ColumnTitle, TimelineId, TimelineKind, TimelineRoute},
+    timeline::{ColumnTitle, ContentKinds, TimelineId, TimelineKind, TimelineRoute},
     ui::{
         self,
         anim::{AnimationHelper, ICON_EXPANSION_MULTIPLE},
@@ -14,9 +15,9 @@ use crate::{
 
 use egui::Margin;
 use egui::{RichText, Stroke, UiBuilder};
-use enostr::Pubkey;
+use enostr::{NoteId, Pubkey};
 use nostrdb::{Ndb, Transaction};
-use notedeck::{ImageCache, NotedeckTextStyle};
+use notedeck::{tr, ImageCache, NotedeckTextStyle};
 
 pub struct NavTitle<'a> {
     ndb: &'a Ndb,
@@ -25,6 +26,7 @@ pub struct NavTitle<'a> {
     deck_author: Option<&'a Pubkey>,
     routes: &'a [Route],
     col_id: usize,
+    threads: &'a ThreadNotifications,
 }
 
 impl<'a> NavTitle<'a> {
@@ -35,6 +37,7 @@ impl<'a> NavTitle<'a> {
         deck_author: Option<&'a Pubkey>,
         routes: &'a [Route],
         col_id: usize,
+        threads: &'a ThreadNotifications,
     ) -> Self {
         NavTitle {
             ndb,
@@ -43,6 +46,7 @@ impl<'a> NavTitle<'a> {
             deck_author,
             routes,
             col_id,
+            threads,
         }
     }
 
@@ -95,6 +99,12 @@ impl<'a> NavTitle<'a> {
                         ColumnsAction::Switch(from, to_index),
                     )))
                 }
+                TitleResponse::ThreadNotify(root, notify) => {
+                    Some(RenderNavAction::ThreadNotify(root, notify))
+                }
+                TitleResponse::ContentKinds(timeline, kinds) => {
+                    Some(RenderNavAction::ContentKinds(timeline, kinds))
+                }
             }
         } else if back_button_resp.map_or(false, |r| r.clicked()) {
             Some(RenderNavAction::Back)
@@ -116,6 +126,7 @@ impl<'a> NavTitle<'a> {
         //ui.spacing_mut().item_spacing.x = 0.0;
 
         let chev_resp = chevron(ui, 2.0, chev_size, Stroke::new(2.0, color));
+        ui::a11y::button(ui, &chev_resp, "Back");
 
         //ui.spacing_mut().item_spacing.x = spacing_prev;
 
@@ -165,7 +176,7 @@ impl<'a> NavTitle<'a> {
         let cur_img_size = helper.scale_1d_pos_min_max(0.0, img_size);
 
         let animation_rect = helper.get_animation_rect();
-        let animation_resp = helper.take_animation_response();
+        let animation_resp = helper.take_accessible_response(ui, "Remove column");
 
         img.paint_at(ui, animation_rect.shrink((max_size - cur_img_size) / 2.0));
 
@@ -455,8 +466,29 @@ impl<'a> NavTitle<'a> {
             Route::AddColumn(_add_col_route) => {}
             Route::Support => {}
             Route::Relays => {}
+            Route::Settings => {}
+            Route::NotificationSettings => {}
+            Route::RelayGroups => {}
             Route::NewDeck => {}
             Route::EditDeck(_) => {}
+            Route::Poster(_) => {}
+            Route::Source(_) => {}
+            Route::EditNote(_) => {}
+            Route::Highlight(_) => {}
+            Route::Highlights(_) => {}
+            Route::Subscriptions => {}
+            Route::Wallet => {}
+            Route::Bookmarks => {}
+            Route::SaveBookmark(_) => {}
+            Route::Hidden => {}
+            Route::ExportData => {}
+            Route::Storage => {}
+            Route::MyPosts => {}
+            Route::ExplorePeople => {}
+            Route::Trending => {}
+            Route::Report(target) => {
+                self.show_profile(ui, &target.pubkey, pfp_size);
+            }
             Route::EditProfile(pubkey) => {
                 self.show_profile(ui, pubkey, pfp_size);
             }
@@ -501,18 +533,32 @@ impl<'a> NavTitle<'a> {
         }
 
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if let Route::Timeline(TimelineRoute::Thread(note_id)) = top {
+                if let Some((root, notify)) = self.thread_notify_button(ui, note_id) {
+                    return Some(TitleResponse::ThreadNotify(root, notify));
+                }
+            }
+
             if navigating {
                 self.title_presentation(ui, top, 32.0);
                 None
             } else {
                 let move_col = self.move_button_section(ui);
                 let remove_col = self.delete_button_section(ui);
+                let mut kinds = None;
+                if let Route::Timeline(TimelineRoute::Timeline(timeline_id)) = top {
+                    kinds = self
+                        .content_kinds_button(ui, *timeline_id)
+                        .map(|kinds| (*timeline_id, kinds));
+                    jump_to_date_button(ui, *timeline_id);
+                    media_mode_button(ui, *timeline_id);
+                }
                 if let Some(col) = move_col {
                     Some(TitleResponse::MoveColumn(col))
                 } else if remove_col {
                     Some(TitleResponse::RemoveColumn)
                 } else {
-                    None
+                    kinds.map(|(timeline, kinds)| TitleResponse::ContentKinds(timeline, kinds))
                 }
             }
         })
@@ -523,11 +569,80 @@ impl<'a> NavTitle<'a> {
         self.title_pfp(ui, top, pfp_size);
         self.title_label(ui, top);
     }
+
+    /// Mute or watch the thread a note is in
+    fn thread_notify_button(
+        &self,
+        ui: &mut egui::Ui,
+        note_id: &NoteId,
+    ) -> Option<(NoteId, Option<ThreadNotify>)> {
+        let root = Transaction::new(self.ndb)
+            .ok()
+            .and_then(|txn| {
+                let note = self.ndb.get_note_by_id(&txn, note_id.bytes()).ok()?;
+                Some(notifications::thread_root(&note))
+            })
+            .unwrap_or(*note_id);
+        let current = self.threads.get(&root);
+
+        let icon = match current {
+            Some(ThreadNotify::Mute) => "🔕",
+            _ => "🔔",
+        };
+        let mut chosen = None;
+        ui.menu_button(RichText::new(icon).strong(), |ui| {
+            for (notify, label) in [
+                (None, tr!("thread-notify-default")),
+                (Some(ThreadNotify::Watch), tr!("thread-notify-watch")),
+                (Some(ThreadNotify::Mute), tr!("thread-notify-mute")),
+            ] {
+                if ui.selectable_label(current == notify, label).clicked() {
+                    chosen = Some(notify);
+                    ui.close_menu();
+                }
+            }
+        })
+        .response
+        .on_hover_text(tr!("thread-notify"));
+
+        chosen
+            .filter(|notify| *notify != current)
+            .map(|notify| (root, notify))
+    }
+
+    /// Pick which kinds of notes a timeline column asks relays for
+    fn content_kinds_button(
+        &self,
+        ui: &mut egui::Ui,
+        timeline_id: TimelineId,
+    ) -> Option<ContentKinds> {
+        let current = self.columns.find_timeline(timeline_id)?.kinds;
+        let mut kinds = current;
+
+        ui.menu_button(RichText::new("☰").strong(), |ui| {
+            ui.checkbox(&mut kinds.notes, tr!("column-kinds-notes"));
+            ui.add_enabled_ui(kinds.notes, |ui| {
+                ui.checkbox(&mut kinds.replies, tr!("column-kinds-replies"));
+            });
+            ui.checkbox(&mut kinds.reposts, tr!("column-kinds-reposts"));
+            ui.checkbox(&mut kinds.long_form, tr!("column-kinds-long-form"));
+            ui.checkbox(&mut kinds.highlights, tr!("column-kinds-highlights"));
+            ui.checkbox(&mut kinds.live, tr!("column-kinds-live"));
+            ui.label(RichText::new(tr!("column-kinds-hint")).weak().small());
+        })
+        .response
+        .on_hover_text(tr!("column-kinds"));
+
+        // a column has to show something
+        Some(kinds).filter(|kinds| *kinds != current && !kinds.is_empty())
+    }
 }
 
 enum TitleResponse {
     RemoveColumn,
     MoveColumn(usize),
+    ThreadNotify(NoteId, Option<ThreadNotify>),
+    ContentKinds(TimelineId, ContentKinds),
 }
 
 fn prev<R>(xs: &[R]) -> Option<&R> {
@@ -556,6 +671,27 @@ fn chevron(
     r
 }
 
+fn jump_to_date_button(ui: &mut egui::Ui, timeline_id: TimelineId) {
+    let resp = ui
+        .add(egui::Button::new("🗓").frame(false))
+        .on_hover_text(tr!("timeline-jump-to-date"));
+
+    if resp.clicked() {
+        ui::timeline::open_history(ui.ctx(), timeline_id);
+    }
+}
+
+fn media_mode_button(ui: &mut egui::Ui, timeline_id: TimelineId) {
+    let enabled = ui::timeline::is_media_mode(ui.ctx(), timeline_id);
+    let resp = ui
+        .add(egui::SelectableLabel::new(enabled, "▦"))
+        .on_hover_text(tr!("timeline-media-mode"));
+
+    if resp.clicked() {
+        ui::timeline::toggle_media_mode(ui.ctx(), timeline_id);
+    }
+}
+
 fn grab_button() -> impl egui::Widget {
     |ui: &mut egui::Ui| -> egui::Response {
         let max_size = egui::vec2(20.0, 20.0);
@@ -584,6 +720,6 @@ fn grab_button() -> impl egui::Widget {
         painter.circle_filled(bottom_left, cur_circle_radius, color);
         painter.circle_filled(bottom_right, cur_circle_radius, color);
 
-        helper.take_animation_response()
+        helper.take_accessible_response(ui, "Move column")
     }
 }
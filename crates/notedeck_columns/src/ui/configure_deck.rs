@@ -1,9 +1,11 @@
 use crate::{app_style::deck_icon_font_sized, colors::PINK, deck_state::DeckState};
 use egui::{vec2, Button, Color32, Label, RichText, Stroke, Ui, Widget};
-use notedeck::{NamedFontFamily, NotedeckTextStyle};
+use notedeck::{tr, NamedFontFamily, NotedeckTextStyle};
 
 use super::{
+    a11y,
     anim::{AnimationHelper, ICON_EXPANSION_MULTIPLE},
+    density::Density,
     padding,
 };
 
@@ -15,6 +17,7 @@ pub struct ConfigureDeckView<'a> {
 pub struct ConfigureDeckResponse {
     pub icon: char,
     pub name: String,
+    pub density: Density,
 }
 
 static CREATE_TEXT: &str = "Create Deck";
@@ -54,18 +57,17 @@ impl<'a> ConfigureDeckView<'a> {
             ));
 
             ui.add_space(32.0);
-            ui.add(Label::new(RichText::new("Icon").font(title_font)));
-
-            if ui
-                .add(deck_icon(
-                    ui.id().with("config-deck"),
-                    self.state.selected_glyph,
-                    38.0,
-                    64.0,
-                    false,
-                ))
-                .clicked()
-            {
+            ui.add(Label::new(RichText::new("Icon").font(title_font.clone())));
+
+            let icon_resp = ui.add(deck_icon(
+                ui.id().with("config-deck"),
+                self.state.selected_glyph,
+                38.0,
+                64.0,
+                false,
+            ));
+            a11y::label(&icon_resp, egui::WidgetType::Button, "Choose deck icon");
+            if icon_resp.clicked() {
                 self.state.selecting_glyph = !self.state.selecting_glyph;
             }
 
@@ -92,6 +94,17 @@ impl<'a> ConfigureDeckView<'a> {
                 self.state.warn_no_title = false;
             }
 
+            ui.add_space(16.0);
+            ui.add(Label::new(
+                RichText::new(tr!("deck-density")).font(title_font),
+            ));
+            ui.horizontal(|ui| {
+                for density in Density::ALL {
+                    ui.selectable_value(&mut self.state.density, density, density.label());
+                }
+            });
+            ui.add_space(16.0);
+
             show_warnings(ui, self.state.warn_no_icon, self.state.warn_no_title);
 
             let mut resp = None;
@@ -110,6 +123,7 @@ impl<'a> ConfigureDeckView<'a> {
                         resp = Some(ConfigureDeckResponse {
                             icon: glyph,
                             name: self.state.deck_name.clone(),
+                            density: self.state.density,
                         });
                     }
                 }
@@ -203,7 +217,10 @@ pub fn deck_icon(
             painter.galley(top_left, glyph_galley, Color32::WHITE);
         }
 
-        helper.take_animation_response()
+        // callers know what the deck is called, so they name it
+        let resp = helper.take_animation_response();
+        a11y::focus_outline(ui, &resp);
+        resp
     }
 }
 
@@ -229,7 +246,7 @@ fn glyph_icon(glyph: char, font_size: f32, max_size: egui::Vec2, color: Color32)
         };
 
         painter.galley(top_left, glyph_galley, Color32::WHITE);
-        helper.take_animation_response()
+        helper.take_accessible_response(ui, &glyph.to_string())
     }
 }
 
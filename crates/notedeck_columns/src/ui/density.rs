@@ -0,0 +1,102 @@
+use notedeck::tr;
+
+use crate::ui::ProfilePic;
+
+/// How tightly notes are packed in a deck's columns. Set on the egui
+/// context each frame from the active deck, so widgets deep in the note
+/// view can size themselves without it being passed down.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Density {
+    Compact,
+    #[default]
+    Normal,
+    Comfortable,
+}
+
+impl Density {
+    pub const ALL: [Density; 3] = [Density::Compact, Density::Normal, Density::Comfortable];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Density::Compact => "compact",
+            Density::Normal => "normal",
+            Density::Comfortable => "comfortable",
+        }
+    }
+
+    pub fn parse(density: &str) -> Option<Self> {
+        Density::ALL.into_iter().find(|d| d.as_str() == density)
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Density::Compact => tr!("density-compact"),
+            Density::Normal => tr!("density-normal"),
+            Density::Comfortable => tr!("density-comfortable"),
+        }
+    }
+
+    /// Space around each note in a timeline
+    pub fn note_padding(&self) -> f32 {
+        match self {
+            Density::Compact => 4.0,
+            Density::Normal => 8.0,
+            Density::Comfortable => 12.0,
+        }
+    }
+
+    pub fn pfp_size(&self) -> f32 {
+        match self {
+            Density::Compact => ProfilePic::medium_size(),
+            Density::Normal => ProfilePic::default_size(),
+            Density::Comfortable => 48.0,
+        }
+    }
+
+    /// Tallest an image in a note gets
+    pub fn image_height(&self) -> f32 {
+        match self {
+            Density::Compact => 200.0,
+            Density::Normal => 360.0,
+            Density::Comfortable => 480.0,
+        }
+    }
+
+    /// Scale every text style in `style` to this density
+    pub fn scale_text(&self, style: &mut egui::Style) {
+        let scale = match self {
+            Density::Compact => 0.9,
+            Density::Normal => return,
+            Density::Comfortable => 1.1,
+        };
+
+        for font in style.text_styles.values_mut() {
+            font.size *= scale;
+        }
+    }
+
+    pub fn get(ctx: &egui::Context) -> Self {
+        ctx.data(|d| d.get_temp(density_id())).unwrap_or_default()
+    }
+
+    pub fn set(ctx: &egui::Context, density: Density) {
+        ctx.data_mut(|d| d.insert_temp(density_id(), density));
+    }
+}
+
+fn density_id() -> egui::Id {
+    egui::Id::new("deck-density")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_density_round_trip() {
+        for density in Density::ALL {
+            assert_eq!(Density::parse(density.as_str()), Some(density));
+        }
+        assert_eq!(Density::parse("cozy"), None);
+    }
+}
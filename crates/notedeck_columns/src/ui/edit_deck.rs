@@ -1,4 +1,5 @@
 use egui::Widget;
+use notedeck::tr;
 
 use crate::deck_state::DeckState;
 
@@ -16,6 +17,10 @@ static EDIT_TEXT: &str = "Edit Deck";
 pub enum EditDeckResponse {
     Edit(ConfigureDeckResponse),
     Delete,
+    /// Copy the deck as JSON for someone to import
+    CopyJson,
+    /// Publish the deck so it can be imported from an naddr
+    Publish,
 }
 
 impl<'a> EditDeckView<'a> {
@@ -31,6 +36,16 @@ impl<'a> EditDeckView<'a> {
             if ui.add(delete_button()).clicked() {
                 edit_deck_resp = Some(EditDeckResponse::Delete);
             }
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.label(tr!("deck-share"));
+                if ui.button(tr!("deck-share-copy")).clicked() {
+                    edit_deck_resp = Some(EditDeckResponse::CopyJson);
+                }
+                if ui.button(tr!("deck-share-publish")).clicked() {
+                    edit_deck_resp = Some(EditDeckResponse::Publish);
+                }
+            });
         });
 
         if let Some(config_resp) = self.config_view.ui(ui) {
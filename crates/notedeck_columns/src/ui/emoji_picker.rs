@@ -0,0 +1,203 @@
+use egui::{RichText, ScrollArea};
+use notedeck::tr;
+
+/// How many recently used emoji to keep
+const MAX_RECENT: usize = 24;
+const EMOJI_SIZE: f32 = 20.0;
+
+/// Fitzpatrick modifiers, the first one is no modifier
+const SKIN_TONES: [&str; 6] = [
+    "",
+    "\u{1F3FB}",
+    "\u{1F3FC}",
+    "\u{1F3FD}",
+    "\u{1F3FE}",
+    "\u{1F3FF}",
+];
+
+/// Emoji that take a skin tone
+const TONED: &[&str] = &[
+    "👍", "👎", "👋", "👏", "🙌", "🙏", "💪", "✌️", "🤞", "👌", "🤙", "✋", "👊", "🤝", "🫶",
+];
+
+const CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "emoji-smileys",
+        &[
+            "😀", "😂", "🤣", "😊", "😍", "🥰", "😘", "😎", "🤔", "🫡", "😅", "😭", "😡", "😱",
+            "🥳", "😴", "🤯", "🥲", "😬", "🙄", "🤗", "🫠", "😇", "🤡",
+        ],
+    ),
+    ("emoji-people", TONED),
+    (
+        "emoji-animals",
+        &[
+            "🐶", "🐱", "🐸", "🦊", "🐻", "🐼", "🐨", "🦁", "🐮", "🐷", "🐵", "🦄", "🐝", "🦋",
+            "🐢", "🐙", "🦀", "🐬", "🦩", "🌵", "🌸", "🌻", "🍄", "🌈",
+        ],
+    ),
+    (
+        "emoji-food",
+        &[
+            "🍕", "🍔", "🌮", "🍣", "🍜", "🥗", "🍩", "🍪", "🎂", "🍫", "🍿", "🥑", "🍓", "🍌",
+            "☕", "🍵", "🍺", "🍷", "🥂", "🧉",
+        ],
+    ),
+    (
+        "emoji-things",
+        &[
+            "🔥", "✨", "⚡", "💯", "❤️", "💜", "🧡", "💛", "💚", "💙", "🖤", "🤙", "🎉", "🎁",
+            "📅", "⏰", "📍", "💡", "🔑", "🚀", "⚽", "🎸", "🎮", "🫂",
+        ],
+    ),
+    (
+        "emoji-symbols",
+        &[
+            "✅", "❌", "⚠️", "❓", "❗", "➕", "➖", "🔁", "🔒", "🆕", "💤", "♻️", "☑️", "🟣",
+            "🔴", "🟢",
+        ],
+    ),
+];
+
+/// An emoji with a skin tone applied, for the ones that take one
+pub fn with_tone(emoji: &str, tone: usize) -> String {
+    let Some(modifier) = SKIN_TONES.get(tone).filter(|m| !m.is_empty()) else {
+        return emoji.to_owned();
+    };
+    if !TONED.contains(&emoji) {
+        return emoji.to_owned();
+    }
+
+    // the modifier replaces the emoji presentation selector
+    format!("{}{modifier}", emoji.trim_end_matches('\u{FE0F}'))
+}
+
+/// Put `emoji` at the front of the recently used list
+fn remember(recent: &mut Vec<String>, emoji: &str) {
+    recent.retain(|e| e != emoji);
+    recent.insert(0, emoji.to_owned());
+    recent.truncate(MAX_RECENT);
+}
+
+/// A grid of emoji to pick from, with recently used ones first. Recents
+/// and the skin tone are remembered across restarts.
+pub struct EmojiPicker {
+    id: egui::Id,
+}
+
+impl EmojiPicker {
+    pub fn new() -> Self {
+        EmojiPicker {
+            id: egui::Id::new("emoji-picker"),
+        }
+    }
+
+    /// Returns the emoji that was clicked
+    pub fn show(&self, ui: &mut egui::Ui) -> Option<String> {
+        let recent_id = self.id.with("recent");
+        let tone_id = self.id.with("tone");
+        let mut recent: Vec<String> = ui
+            .ctx()
+            .data_mut(|d| d.get_persisted(recent_id))
+            .unwrap_or_default();
+        let mut tone: usize = ui
+            .ctx()
+            .data_mut(|d| d.get_persisted(tone_id))
+            .unwrap_or_default();
+
+        let mut picked = None;
+
+        ui.horizontal(|ui| {
+            ui.weak(tr!("emoji-skin-tone"));
+            for i in 0..SKIN_TONES.len() {
+                let hand = with_tone("👋", i);
+                if ui.selectable_label(tone == i, hand).clicked() && tone != i {
+                    tone = i;
+                    ui.ctx().data_mut(|d| d.insert_persisted(tone_id, tone));
+                }
+            }
+        });
+
+        ScrollArea::vertical()
+            .id_salt(self.id.with("scroll"))
+            .max_height(200.0)
+            .show(ui, |ui| {
+                if !recent.is_empty() {
+                    ui.weak(tr!("emoji-recent"));
+                    if let Some(emoji) = emoji_grid(ui, recent.iter().map(String::as_str), None) {
+                        picked = Some(emoji);
+                    }
+                }
+
+                for (category, emoji) in CATEGORIES {
+                    ui.weak(tr!(category));
+                    if let Some(emoji) = emoji_grid(ui, emoji.iter().copied(), Some(tone)) {
+                        picked = Some(emoji);
+                    }
+                }
+            });
+
+        if let Some(emoji) = &picked {
+            remember(&mut recent, emoji);
+            ui.ctx().data_mut(|d| d.insert_persisted(recent_id, recent));
+        }
+
+        picked
+    }
+}
+
+impl Default for EmojiPicker {
+    fn default() -> Self {
+        EmojiPicker::new()
+    }
+}
+
+/// Recents already have their tone, so `tone` is only given for the
+/// categories
+fn emoji_grid<'a>(
+    ui: &mut egui::Ui,
+    emoji: impl Iterator<Item = &'a str>,
+    tone: Option<usize>,
+) -> Option<String> {
+    let mut picked = None;
+    ui.horizontal_wrapped(|ui| {
+        for emoji in emoji {
+            let emoji = match tone {
+                Some(tone) => with_tone(emoji, tone),
+                None => emoji.to_owned(),
+            };
+            let button = egui::Button::new(RichText::new(&emoji).size(EMOJI_SIZE)).frame(false);
+            if ui.add(button).clicked() {
+                picked = Some(emoji);
+            }
+        }
+    });
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_tone() {
+        assert_eq!(with_tone("👍", 0), "👍");
+        assert_eq!(with_tone("👍", 3), "👍\u{1F3FD}");
+        assert_eq!(with_tone("✌️", 1), "✌\u{1F3FB}");
+        // doesn't take a tone
+        assert_eq!(with_tone("🍕", 5), "🍕");
+    }
+
+    #[test]
+    fn test_remember() {
+        let mut recent = vec!["😀".to_owned(), "🔥".to_owned()];
+        remember(&mut recent, "🔥");
+        assert_eq!(recent, vec!["🔥".to_owned(), "😀".to_owned()]);
+
+        for i in 0..MAX_RECENT {
+            remember(&mut recent, &i.to_string());
+        }
+        assert_eq!(recent.len(), MAX_RECENT);
+        assert_eq!(recent[0], (MAX_RECENT - 1).to_string());
+    }
+}
@@ -0,0 +1,119 @@
+use egui::{Frame, RichText};
+use nostrdb::{Ndb, Transaction};
+use notedeck::{contacts, tr, FollowPack, ImageCache, UnknownIds};
+
+use crate::{
+    follow_packs::{self, INTERESTS},
+    ui::{self, follow_pack::FollowPackPreview},
+};
+
+pub enum ExplorePeopleAction {
+    /// Follow everyone in a pack the account doesn't follow yet
+    FollowAll(Vec<[u8; 32]>),
+}
+
+/// Follow packs from the relays, narrowed down by topic, to find more
+/// people to follow after onboarding
+pub struct ExplorePeopleView<'a> {
+    ndb: &'a Ndb,
+    img_cache: &'a mut ImageCache,
+    unknown_ids: &'a mut UnknownIds,
+    packs: &'a [FollowPack],
+    account: Option<&'a [u8; 32]>,
+}
+
+impl<'a> ExplorePeopleView<'a> {
+    pub fn new(
+        ndb: &'a Ndb,
+        img_cache: &'a mut ImageCache,
+        unknown_ids: &'a mut UnknownIds,
+        packs: &'a [FollowPack],
+        account: Option<&'a [u8; 32]>,
+    ) -> Self {
+        ExplorePeopleView {
+            ndb,
+            img_cache,
+            unknown_ids,
+            packs,
+            account,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<ExplorePeopleAction> {
+        let topic_id = ui.id().with("explore-people-topic");
+        let mut topic: Option<&'static str> = ui.data(|d| d.get_temp(topic_id)).flatten();
+
+        Frame::none()
+            .inner_margin(egui::Margin::symmetric(8.0, 4.0))
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.selectable_value(&mut topic, None, tr!("explore-people-all"));
+                    for (interest, _) in INTERESTS {
+                        ui.selectable_value(&mut topic, Some(interest), interest);
+                    }
+                });
+            });
+        ui.data_mut(|d| d.insert_temp(topic_id, topic));
+        ui::hline(ui);
+
+        let packs: Vec<&FollowPack> = match topic {
+            Some(topic) => {
+                let hashtags = follow_packs::interest_hashtags(topic);
+                self.packs
+                    .iter()
+                    .filter(|pack| pack.is_about(hashtags))
+                    .collect()
+            }
+            None => self.packs.iter().collect(),
+        };
+
+        if packs.is_empty() {
+            ui.add_space(16.0);
+            ui.vertical_centered(|ui| {
+                ui.label(RichText::new(tr!("explore-people-empty")).weak());
+            });
+            return None;
+        }
+
+        let txn = Transaction::new(self.ndb).expect("txn");
+        let followed = self
+            .account
+            .map(|account| contacts::followed(self.ndb, &txn, account))
+            .unwrap_or_default();
+
+        let mut action = None;
+        egui::ScrollArea::vertical()
+            .id_salt(("explore-people", topic))
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for pack in packs {
+                    ui::padding(8.0, ui, |ui| {
+                        FollowPackPreview::new(
+                            pack,
+                            self.ndb,
+                            &txn,
+                            self.img_cache,
+                            self.unknown_ids,
+                        )
+                        .ui(ui);
+
+                        let Some(account) = self.account else {
+                            return;
+                        };
+                        let new: Vec<[u8; 32]> = pack.new_to(account, &followed).copied().collect();
+                        if new.is_empty() {
+                            ui.label(RichText::new(tr!("explore-people-following-all")).weak());
+                        } else if ui
+                            .button(tr!("explore-people-follow-all", count = new.len()))
+                            .clicked()
+                        {
+                            action = Some(ExplorePeopleAction::FollowAll(new));
+                        }
+                    });
+                    ui::hline(ui);
+                }
+            });
+
+        action
+    }
+}
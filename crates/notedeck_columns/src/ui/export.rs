@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+
+use egui::{RichText, TextEdit};
+use notedeck::{tr, NotedeckTextStyle};
+
+use crate::export::{self, BackupPreview, DataExport};
+
+pub enum ExportAction {
+    Export {
+        dest: PathBuf,
+    },
+    Import {
+        dir: PathBuf,
+        rebroadcast: bool,
+    },
+    /// Read another client's backup and show what it would change
+    PreviewBackup {
+        path: PathBuf,
+    },
+    ApplyBackup,
+    DiscardBackup,
+    /// Write the account's blocked relays and media hosts to a file
+    ExportBlocklist {
+        path: PathBuf,
+    },
+    /// Add the entries from an exported blocklist to the account's
+    ImportBlocklist {
+        path: PathBuf,
+    },
+}
+
+/// Export the selected account's events, or bring an export back in
+pub struct ExportView<'a> {
+    export: &'a DataExport,
+}
+
+impl<'a> ExportView<'a> {
+    pub fn new(export: &'a DataExport) -> Self {
+        ExportView { export }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<ExportAction> {
+        let mut action = None;
+        let id = ui.id().with("export");
+        let (mut dest, mut source, mut rebroadcast, mut backup) = ui.ctx().data_mut(|d| {
+            d.get_temp::<(String, String, bool, String)>(id)
+                .unwrap_or_else(|| {
+                    let source = self
+                        .export
+                        .last_export
+                        .as_ref()
+                        .map(|dir| dir.display().to_string())
+                        .unwrap_or_default();
+                    (
+                        export::default_dest().display().to_string(),
+                        source,
+                        false,
+                        String::new(),
+                    )
+                })
+        });
+
+        egui::Frame::none()
+            .inner_margin(egui::Margin::same(16.0))
+            .show(ui, |ui| {
+                let busy = self.export.is_busy();
+
+                section(ui, &tr!("export-title"));
+                ui.weak(tr!("export-hint"));
+                ui.add_space(8.0);
+                ui.label(tr!("export-dest"));
+                ui.add(TextEdit::singleline(&mut dest).desired_width(f32::INFINITY));
+                if ui
+                    .add_enabled(!busy, egui::Button::new(tr!("export-start")))
+                    .clicked()
+                {
+                    action = Some(ExportAction::Export {
+                        dest: PathBuf::from(dest.trim()),
+                    });
+                }
+
+                ui.add_space(24.0);
+                section(ui, &tr!("import-title"));
+                ui.weak(tr!("import-hint"));
+                ui.add_space(8.0);
+                ui.label(tr!("import-source"));
+                ui.add(TextEdit::singleline(&mut source).desired_width(f32::INFINITY));
+                ui.checkbox(&mut rebroadcast, tr!("import-rebroadcast"));
+                let can_import = !busy && !source.trim().is_empty();
+                if ui
+                    .add_enabled(can_import, egui::Button::new(tr!("import-start")))
+                    .clicked()
+                {
+                    action = Some(ExportAction::Import {
+                        dir: PathBuf::from(source.trim()),
+                        rebroadcast,
+                    });
+                }
+
+                ui.add_space(24.0);
+                section(ui, &tr!("backup-title"));
+                ui.weak(tr!("backup-hint"));
+                ui.add_space(8.0);
+                match &self.export.backup {
+                    Some(preview) => action = backup_preview(ui, preview).or(action),
+                    None => {
+                        ui.label(tr!("backup-source"));
+                        ui.add(TextEdit::singleline(&mut backup).desired_width(f32::INFINITY));
+                        if ui
+                            .add_enabled(
+                                !backup.trim().is_empty(),
+                                egui::Button::new(tr!("backup-preview")),
+                            )
+                            .clicked()
+                        {
+                            action = Some(ExportAction::PreviewBackup {
+                                path: PathBuf::from(backup.trim()),
+                            });
+                        }
+                    }
+                }
+
+                ui.add_space(24.0);
+                section(ui, &tr!("blocklist-title"));
+                ui.weak(tr!("blocklist-hint"));
+                ui.add_space(8.0);
+                if let Some(blocklist_action) = blocklist_ui(ui) {
+                    action = Some(blocklist_action);
+                }
+
+                if busy {
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.weak(tr!("export-working"));
+                    });
+                }
+            });
+
+        ui.ctx()
+            .data_mut(|d| d.insert_temp(id, (dest, source, rebroadcast, backup)));
+
+        action
+    }
+}
+
+fn blocklist_ui(ui: &mut egui::Ui) -> Option<ExportAction> {
+    let id = ui.id().with("blocklist-path");
+    let mut path = ui.ctx().data_mut(|d| {
+        d.get_temp::<String>(id).unwrap_or_else(|| {
+            export::default_dest()
+                .join("blocklist.json")
+                .display()
+                .to_string()
+        })
+    });
+
+    ui.label(tr!("blocklist-file"));
+    ui.add(TextEdit::singleline(&mut path).desired_width(f32::INFINITY));
+    let ready = !path.trim().is_empty();
+    let action = ui
+        .horizontal(|ui| {
+            let chosen = PathBuf::from(path.trim());
+            if ui
+                .add_enabled(ready, egui::Button::new(tr!("blocklist-export")))
+                .clicked()
+            {
+                Some(ExportAction::ExportBlocklist { path: chosen })
+            } else if ui
+                .add_enabled(ready, egui::Button::new(tr!("blocklist-import")))
+                .clicked()
+            {
+                Some(ExportAction::ImportBlocklist { path: chosen })
+            } else {
+                None
+            }
+        })
+        .inner;
+
+    ui.ctx().data_mut(|d| d.insert_temp(id, path));
+    action
+}
+
+/// What applying a backup would add, before anything is published
+fn backup_preview(ui: &mut egui::Ui, preview: &BackupPreview) -> Option<ExportAction> {
+    let new = &preview.new;
+    ui.weak(preview.path.display().to_string());
+    ui.label(tr!("backup-new-follows", count = new.follows.len()));
+    ui.label(tr!("backup-new-mutes", count = new.mutes.len()));
+    ui.label(tr!("backup-new-relays", count = new.relays.len()));
+    for relay in &new.relays {
+        ui.weak(format!("  + {}", relay.url));
+    }
+
+    ui.add_space(8.0);
+    ui.horizontal(|ui| {
+        if ui.button(tr!("backup-apply")).clicked() {
+            Some(ExportAction::ApplyBackup)
+        } else if ui.button(tr!("backup-discard")).clicked() {
+            Some(ExportAction::DiscardBackup)
+        } else {
+            None
+        }
+    })
+    .inner
+}
+
+fn section(ui: &mut egui::Ui, title: &str) {
+    ui.label(
+        RichText::new(title)
+            .text_style(NotedeckTextStyle::Body.text_style())
+            .strong(),
+    );
+}
@@ -0,0 +1,100 @@
+use egui::RichText;
+use enostr::Pubkey;
+use nostrdb::{Ndb, Transaction};
+use notedeck::{tr, FollowPack, ImageCache, UnknownIds};
+
+use crate::{
+    profile::get_display_name,
+    ui::{profile::get_profile_url, ProfilePic},
+};
+
+/// How many members' pictures we show before "and N more"
+const PREVIEW_MEMBERS: usize = 8;
+
+/// A follow pack's title, who made it and a row of the people in it, with
+/// everyone listed underneath when expanded. Profiles we don't have yet
+/// are asked for.
+pub struct FollowPackPreview<'a> {
+    pack: &'a FollowPack,
+    ndb: &'a Ndb,
+    txn: &'a Transaction,
+    img_cache: &'a mut ImageCache,
+    unknown_ids: &'a mut UnknownIds,
+}
+
+impl<'a> FollowPackPreview<'a> {
+    pub fn new(
+        pack: &'a FollowPack,
+        ndb: &'a Ndb,
+        txn: &'a Transaction,
+        img_cache: &'a mut ImageCache,
+        unknown_ids: &'a mut UnknownIds,
+    ) -> Self {
+        FollowPackPreview {
+            pack,
+            ndb,
+            txn,
+            img_cache,
+            unknown_ids,
+        }
+    }
+
+    pub fn ui(mut self, ui: &mut egui::Ui) {
+        let pack = self.pack;
+
+        ui.label(RichText::new(&pack.title).strong());
+        let author = self.name(&pack.author);
+        ui.label(
+            RichText::new(tr!("follow-pack-by", author = author))
+                .weak()
+                .small(),
+        );
+        if !pack.description.is_empty() {
+            ui.add(egui::Label::new(pack.description.as_str()).truncate());
+        }
+
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 2.0;
+            for member in pack.members.iter().take(PREVIEW_MEMBERS) {
+                let name = self.name(member);
+                self.picture(ui, member, ProfilePic::small_size())
+                    .on_hover_text(name);
+            }
+            let more = pack.members.len().saturating_sub(PREVIEW_MEMBERS);
+            if more > 0 {
+                ui.add_space(4.0);
+                ui.label(RichText::new(tr!("follow-pack-more", count = more)).weak());
+            }
+        });
+
+        egui::CollapsingHeader::new(tr!("follow-pack-everyone", count = pack.members.len()))
+            .id_salt(("follow-pack", pack.author, pack.identifier.as_str()))
+            .show(ui, |ui| {
+                for member in &pack.members {
+                    ui.horizontal(|ui| {
+                        self.picture(ui, member, 18.0);
+                        ui.label(self.name(member));
+                    });
+                }
+            });
+    }
+
+    fn name(&mut self, pubkey: &[u8; 32]) -> String {
+        let profile = self.profile(pubkey);
+        get_display_name(profile.as_ref()).name().to_owned()
+    }
+
+    fn picture(&mut self, ui: &mut egui::Ui, pubkey: &[u8; 32], size: f32) -> egui::Response {
+        let profile = self.profile(pubkey);
+        ui.add(ProfilePic::new(self.img_cache, get_profile_url(profile.as_ref())).size(size))
+    }
+
+    fn profile(&mut self, pubkey: &[u8; 32]) -> Option<nostrdb::ProfileRecord<'a>> {
+        let profile = self.ndb.get_profile_by_pubkey(self.txn, pubkey).ok();
+        if profile.is_none() {
+            self.unknown_ids
+                .add_pubkey_if_missing(self.ndb, self.txn, &Pubkey::new(*pubkey));
+        }
+        profile
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use egui::{Color32, PopupCloseBehavior, RichText, Sense};
+use notedeck::tr;
+
+/// Something picked from a hashtag's menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashtagAction {
+    OpenColumn(String),
+    Follow(String),
+    Unfollow(String),
+}
+
+fn action_id() -> egui::Id {
+    egui::Id::new("hashtag-action")
+}
+
+fn followed_id() -> egui::Id {
+    egui::Id::new("followed-hashtags")
+}
+
+/// Let hashtag links know which hashtags are followed, lowercased
+pub fn set_followed(ctx: &egui::Context, followed: Arc<HashSet<String>>) {
+    ctx.data_mut(|d| d.insert_temp(followed_id(), followed));
+}
+
+/// The hashtag action picked this frame, if any
+pub fn take_action(ctx: &egui::Context) -> Option<HashtagAction> {
+    ctx.data_mut(|d| d.remove_temp::<HashtagAction>(action_id()))
+}
+
+/// A #hashtag in note content. Clicking it offers to open it as a column
+/// or follow it.
+pub fn hashtag_link(ui: &mut egui::Ui, hashtag: &str, color: Color32) {
+    let resp = ui.add(
+        egui::Label::new(RichText::new(format!("#{hashtag}")).color(color)).sense(Sense::click()),
+    );
+
+    let popup_id = resp.id.with("hashtag-menu");
+    if resp.clicked() {
+        ui.memory_mut(|m| m.toggle_popup(popup_id));
+    }
+
+    let followed = ui
+        .data(|d| d.get_temp::<Arc<HashSet<String>>>(followed_id()))
+        .is_some_and(|followed| followed.contains(&hashtag.to_lowercase()));
+
+    egui::popup_below_widget(
+        ui,
+        popup_id,
+        &resp,
+        PopupCloseBehavior::CloseOnClick,
+        |ui| {
+            ui.set_min_width(160.0);
+
+            let mut action = None;
+            if ui.button(tr!("hashtag-open-column")).clicked() {
+                action = Some(HashtagAction::OpenColumn(hashtag.to_owned()));
+            }
+
+            if followed {
+                if ui.button(tr!("hashtag-unfollow")).clicked() {
+                    action = Some(HashtagAction::Unfollow(hashtag.to_owned()));
+                }
+            } else if ui.button(tr!("hashtag-follow")).clicked() {
+                action = Some(HashtagAction::Follow(hashtag.to_owned()));
+            }
+
+            if let Some(action) = action {
+                ui.data_mut(|d| d.insert_temp(action_id(), action));
+            }
+        },
+    );
+}
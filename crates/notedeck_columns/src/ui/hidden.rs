@@ -0,0 +1,87 @@
+use egui::RichText;
+use nostrdb::{Ndb, Transaction};
+use notedeck::{tr, NotedeckTextStyle};
+
+use crate::hidden::Hidden;
+use crate::profile::get_display_name;
+
+/// How much of a hidden note to show so it can be recognized
+const PREVIEW_CHARS: usize = 80;
+
+/// Lists what's been hidden so it can be brought back
+pub struct HiddenView<'a> {
+    ndb: &'a Ndb,
+    hidden: &'a mut Hidden,
+}
+
+impl<'a> HiddenView<'a> {
+    pub fn new(ndb: &'a Ndb, hidden: &'a mut Hidden) -> Self {
+        HiddenView { ndb, hidden }
+    }
+
+    /// Returns true if anything was changed
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        let txn = Transaction::new(self.ndb).expect("txn");
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Frame::none()
+                .inner_margin(egui::Margin::same(16.0))
+                .show(ui, |ui| {
+                    changed |= ui
+                        .checkbox(
+                            &mut self.hidden.sync_mute_list,
+                            tr!("hidden-sync-mute-list"),
+                        )
+                        .on_hover_text(tr!("hidden-sync-mute-list-hint"))
+                        .changed();
+
+                    ui.add_space(16.0);
+                    section(ui, &tr!("hidden-people"));
+                    if self.hidden.authors.is_empty() {
+                        ui.weak(tr!("hidden-none"));
+                    }
+                    for pubkey in self.hidden.sorted_authors() {
+                        let profile = self.ndb.get_profile_by_pubkey(&txn, pubkey.bytes()).ok();
+                        let name = get_display_name(profile.as_ref()).name().to_owned();
+                        ui.horizontal(|ui| {
+                            ui.label(name);
+                            if ui.small_button(tr!("hidden-unhide")).clicked() {
+                                self.hidden.authors.remove(&pubkey);
+                                changed = true;
+                            }
+                        });
+                    }
+
+                    ui.add_space(16.0);
+                    section(ui, &tr!("hidden-notes"));
+                    if self.hidden.notes.is_empty() {
+                        ui.weak(tr!("hidden-none"));
+                    }
+                    for note_id in self.hidden.sorted_notes() {
+                        let preview = match self.ndb.get_note_by_id(&txn, note_id.bytes()) {
+                            Ok(note) => note.content().chars().take(PREVIEW_CHARS).collect(),
+                            Err(_) => note_id.to_bech().unwrap_or_else(|| note_id.hex()),
+                        };
+                        ui.horizontal(|ui| {
+                            ui.add(egui::Label::new(preview).truncate());
+                            if ui.small_button(tr!("hidden-unhide")).clicked() {
+                                self.hidden.notes.remove(&note_id);
+                                changed = true;
+                            }
+                        });
+                    }
+                });
+        });
+
+        changed
+    }
+}
+
+fn section(ui: &mut egui::Ui, title: &str) {
+    ui.label(
+        RichText::new(title)
+            .text_style(NotedeckTextStyle::Body.text_style())
+            .strong(),
+    );
+}
@@ -0,0 +1,121 @@
+use egui::{RichText, TextEdit};
+use enostr::NoteId;
+use nostrdb::{Ndb, Transaction};
+use notedeck::{tr, NotedeckTextStyle};
+
+use crate::ui;
+
+pub struct HighlightAction {
+    pub passage: String,
+    pub comment: String,
+}
+
+/// Pick a passage out of a note to highlight. Labels can't tell us what's
+/// selected in them, so the note is shown again here as read-only text
+/// that can.
+pub struct HighlightView<'a> {
+    ndb: &'a Ndb,
+    note_id: &'a NoteId,
+    can_sign: bool,
+}
+
+#[derive(Clone, Default)]
+struct HighlightState {
+    /// The last thing selected. Clicking the comment box clears the
+    /// selection, but not what we're going to highlight.
+    passage: String,
+    comment: String,
+}
+
+impl<'a> HighlightView<'a> {
+    pub fn new(ndb: &'a Ndb, note_id: &'a NoteId, can_sign: bool) -> Self {
+        HighlightView {
+            ndb,
+            note_id,
+            can_sign,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<HighlightAction> {
+        let txn = Transaction::new(self.ndb).expect("txn");
+        let Ok(note) = self.ndb.get_note_by_id(&txn, self.note_id.bytes()) else {
+            ui::padding(16.0, ui, |ui| {
+                ui.label(tr!("source-note-not-found"));
+            });
+            return None;
+        };
+
+        let id = ui.id().with(("highlight", self.note_id));
+        let mut state = ui
+            .ctx()
+            .data_mut(|d| d.get_temp::<HighlightState>(id).unwrap_or_default());
+        let mut action = None;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui::padding(16.0, ui, |ui| {
+                ui.label(
+                    RichText::new(tr!("highlight-title"))
+                        .text_style(NotedeckTextStyle::Body.text_style())
+                        .strong(),
+                );
+                ui.weak(tr!("highlight-hint"));
+                ui.add_space(8.0);
+
+                let content = note.content();
+                let output = TextEdit::multiline(&mut &*content)
+                    .desired_width(f32::INFINITY)
+                    .show(ui);
+                if let Some(range) = output.cursor_range {
+                    let range = range.as_sorted_char_range();
+                    if !range.is_empty() {
+                        state.passage = content
+                            .chars()
+                            .skip(range.start)
+                            .take(range.len())
+                            .collect();
+                    }
+                }
+
+                ui.add_space(8.0);
+                if state.passage.trim().is_empty() {
+                    ui.weak(tr!("highlight-nothing-selected"));
+                } else {
+                    ui.label(RichText::new(state.passage.trim()).italics());
+                }
+
+                ui.add_space(8.0);
+                ui.add(
+                    TextEdit::multiline(&mut state.comment)
+                        .hint_text(tr!("highlight-comment"))
+                        .desired_rows(2)
+                        .desired_width(f32::INFINITY),
+                );
+
+                ui.add_space(8.0);
+                if !self.can_sign {
+                    ui.colored_label(ui.visuals().warn_fg_color, tr!("highlight-cant-sign"));
+                }
+                let ready = self.can_sign && !state.passage.trim().is_empty();
+                if ui
+                    .add_enabled(ready, egui::Button::new(tr!("highlight-publish")))
+                    .clicked()
+                {
+                    action = Some(HighlightAction {
+                        passage: state.passage.trim().to_owned(),
+                        comment: state.comment.trim().to_owned(),
+                    });
+                }
+            });
+        });
+
+        ui.ctx().data_mut(|d| {
+            if action.is_some() {
+                d.remove::<HighlightState>(id);
+            } else {
+                d.insert_temp(id, state);
+            }
+        });
+
+        action
+    }
+}
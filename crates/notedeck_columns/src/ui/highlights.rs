@@ -0,0 +1,96 @@
+use std::cmp::Reverse;
+
+use egui::RichText;
+use enostr::NoteId;
+use nostrdb::{Ndb, Transaction};
+use notedeck::{highlight::HighlightSource, tr, ImageCache, NoteCache};
+
+use crate::{
+    actionbar::NoteAction,
+    ui::{self, note::NoteOptions},
+};
+
+/// How many of a note's highlights to list
+const LIST_LIMIT: i32 = 500;
+
+/// Everyone's highlights of a note, newest first
+pub struct HighlightsView<'a> {
+    ndb: &'a Ndb,
+    note_cache: &'a mut NoteCache,
+    img_cache: &'a mut ImageCache,
+    note_id: &'a NoteId,
+    note_options: NoteOptions,
+}
+
+impl<'a> HighlightsView<'a> {
+    pub fn new(
+        ndb: &'a Ndb,
+        note_cache: &'a mut NoteCache,
+        img_cache: &'a mut ImageCache,
+        note_id: &'a NoteId,
+        note_options: NoteOptions,
+    ) -> Self {
+        HighlightsView {
+            ndb,
+            note_cache,
+            img_cache,
+            note_id,
+            note_options,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<NoteAction> {
+        let txn = Transaction::new(self.ndb).expect("txn");
+        let Ok(source) = self.ndb.get_note_by_id(&txn, self.note_id.bytes()) else {
+            ui::padding(16.0, ui, |ui| {
+                ui.label(tr!("source-note-not-found"));
+            });
+            return None;
+        };
+
+        let filters: Vec<_> = HighlightSource::of(&source)
+            .iter()
+            .map(|source| source.filter(LIST_LIMIT as u64))
+            .collect();
+        let mut highlights = self
+            .ndb
+            .query(&txn, &filters, LIST_LIMIT)
+            .unwrap_or_default();
+        // a highlight can match both its id and address filters
+        highlights.sort_by_key(|qr| (Reverse(qr.note.created_at()), qr.note_key.as_u64()));
+        highlights.dedup_by_key(|qr| qr.note_key);
+
+        if highlights.is_empty() {
+            ui.add_space(16.0);
+            ui.vertical_centered(|ui| {
+                ui.label(RichText::new(tr!("highlights-empty")).weak());
+            });
+            return None;
+        }
+
+        let mut action = None;
+        egui::ScrollArea::vertical()
+            .id_salt(("highlights", self.note_id))
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for qr in &highlights {
+                    ui::padding(8.0, ui, |ui| {
+                        let resp =
+                            ui::NoteView::new(self.ndb, self.note_cache, self.img_cache, &qr.note)
+                                .note_options(self.note_options)
+                                .show(ui);
+
+                        if let Some(note_action) = resp.action {
+                            action = Some(note_action);
+                        }
+                        if let Some(context) = resp.context_selection {
+                            context.process(ui, &qr.note);
+                        }
+                    });
+                    ui::hline(ui);
+                }
+            });
+
+        action
+    }
+}
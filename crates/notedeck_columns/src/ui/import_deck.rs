@@ -0,0 +1,95 @@
+use egui::RichText;
+use notedeck::{tr, NotedeckTextStyle};
+
+use crate::deck_share::DeckImport;
+
+use super::padding;
+
+pub enum ImportDeckAction {
+    Read,
+    Add,
+    Clear,
+}
+
+/// Bringing in a deck someone shared, below the new deck form
+pub struct ImportDeckView<'a> {
+    import: &'a mut DeckImport,
+}
+
+impl<'a> ImportDeckView<'a> {
+    pub fn new(import: &'a mut DeckImport) -> Self {
+        ImportDeckView { import }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<ImportDeckAction> {
+        let mut action = None;
+
+        padding(16.0, ui, |ui| {
+            ui.separator();
+            ui.label(
+                RichText::new(tr!("deck-import"))
+                    .text_style(NotedeckTextStyle::Body.text_style())
+                    .strong(),
+            );
+            ui.weak(tr!("deck-import-hint"));
+            ui.add_space(8.0);
+            ui.add(
+                egui::TextEdit::multiline(&mut self.import.input)
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY),
+            );
+
+            ui.horizontal(|ui| {
+                let can_read = !self.import.input.trim().is_empty();
+                if ui
+                    .add_enabled(can_read, egui::Button::new(tr!("deck-import-read")))
+                    .clicked()
+                {
+                    action = Some(ImportDeckAction::Read);
+                }
+                if self.import.is_waiting() {
+                    ui.spinner();
+                    ui.weak(tr!("deck-import-waiting"));
+                }
+            });
+
+            if let Some(error) = &self.import.error {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    tr!("deck-import-failed", error = error.as_str()),
+                );
+            }
+
+            let Some(preview) = &self.import.preview else {
+                return;
+            };
+            ui.add_space(8.0);
+            ui.label(
+                RichText::new(format!("{} {}", preview.deck.icon, preview.deck.name)).strong(),
+            );
+            ui.label(tr!(
+                "deck-import-columns",
+                count = preview.deck.columns().num_columns()
+            ));
+            if preview.skipped > 0 {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    tr!("deck-import-skipped", count = preview.skipped),
+                );
+            }
+            if !preview.unknown.is_empty() {
+                ui.weak(tr!("deck-import-unknown", count = preview.unknown.len()));
+            }
+            ui.horizontal(|ui| {
+                if ui.button(tr!("deck-import-add")).clicked() {
+                    action = Some(ImportDeckAction::Add);
+                }
+                if ui.button(tr!("deck-import-clear")).clicked() {
+                    action = Some(ImportDeckAction::Clear);
+                }
+            });
+        });
+
+        action
+    }
+}
@@ -0,0 +1,777 @@
+use std::f32::consts::FRAC_PI_2;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use egui::{pos2, vec2, Color32, Rect, RichText, Sense, TextureHandle, Vec2};
+use enostr::NoteId;
+use nostrdb::{Ndb, Transaction};
+use notedeck::note::root_note_id_from_selected_id;
+use notedeck::{tr, ImageCache, MuteFun, NoteCache, NoteRef};
+use tracing::error;
+
+use crate::{
+    actionbar::NoteAction,
+    images::{self, ImageType},
+    media::{self, Media, MediaKind},
+};
+
+/// Textures for the grid and viewer are fetched at up to this size
+const MEDIA_SIZE: u32 = 1024;
+const TILE_WIDTH: f32 = 120.0;
+const TILE_SPACING: f32 = 2.0;
+
+/// A piece of media in the grid, and the note it came from
+#[derive(Clone)]
+struct GridItem {
+    note_id: [u8; 32],
+    media: Media,
+}
+
+/// The media pulled out of a list of notes. Rebuilt only when the notes
+/// change, since it means looking at every note.
+#[derive(Clone)]
+struct GridCache {
+    notes: (usize, Option<NoteRef>, Option<NoteRef>),
+    items: Arc<Vec<GridItem>>,
+    media: Arc<Vec<Media>>,
+}
+
+/// A thumbnail grid of the images and videos in `notes`. Only visible rows
+/// are laid out and fetched, so it's fine inside a long scroll area.
+pub struct MediaGrid<'a> {
+    id: egui::Id,
+    notes: &'a [NoteRef],
+    txn: &'a Transaction,
+    ndb: &'a Ndb,
+    note_cache: &'a mut NoteCache,
+    img_cache: &'a mut ImageCache,
+    is_muted: &'a MuteFun,
+}
+
+impl<'a> MediaGrid<'a> {
+    pub fn new(
+        id: egui::Id,
+        notes: &'a [NoteRef],
+        txn: &'a Transaction,
+        ndb: &'a Ndb,
+        note_cache: &'a mut NoteCache,
+        img_cache: &'a mut ImageCache,
+        is_muted: &'a MuteFun,
+    ) -> Self {
+        MediaGrid {
+            id,
+            notes,
+            txn,
+            ndb,
+            note_cache,
+            img_cache,
+            is_muted,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<NoteAction> {
+        let cache = self.cache(ui);
+        if cache.items.is_empty() {
+            ui.add_space(16.0);
+            ui.vertical_centered(|ui| {
+                ui.label(RichText::new(tr!("media-empty")).weak());
+            });
+            return None;
+        }
+
+        let width = ui.available_width();
+        let cols = ((width / TILE_WIDTH).floor() as usize).max(1);
+        let tile = (width - TILE_SPACING * (cols - 1) as f32) / cols as f32;
+        let rows = cache.items.len().div_ceil(cols);
+        let row_height = tile + TILE_SPACING;
+
+        let (rect, _) =
+            ui.allocate_exact_size(vec2(width, rows as f32 * row_height), Sense::hover());
+
+        let visible = ui.clip_rect().intersect(rect);
+        if !visible.is_positive() {
+            return None;
+        }
+        let first_row = ((visible.top() - rect.top()) / row_height).floor() as usize;
+        let last_row = (((visible.bottom() - rect.top()) / row_height).ceil() as usize).min(rows);
+
+        let mut action = None;
+        for row in first_row..last_row {
+            for col in 0..cols {
+                let index = row * cols + col;
+                let Some(item) = cache.items.get(index) else {
+                    break;
+                };
+
+                let min =
+                    rect.min + vec2(col as f32 * (tile + TILE_SPACING), row as f32 * row_height);
+                let tile_rect = Rect::from_min_size(min, vec2(tile, tile));
+                let resp = ui.interact(tile_rect, self.id.with(index), Sense::click());
+
+                self.tile_ui(ui, tile_rect, &item.media, resp.hovered());
+
+                if resp.clicked() {
+                    MediaViewer::open(ui.ctx(), cache.media.clone(), index);
+                }
+
+                resp.context_menu(|ui| {
+                    if ui.button(tr!("media-open-note")).clicked() {
+                        action = Some(NoteAction::OpenThread(NoteId::new(item.note_id)));
+                        ui.close_menu();
+                    }
+                    if ui.button(tr!("media-copy-link")).clicked() {
+                        ui.ctx().copy_text(item.media.url.clone());
+                        ui.close_menu();
+                    }
+                });
+            }
+        }
+
+        action
+    }
+
+    fn tile_ui(&mut self, ui: &mut egui::Ui, rect: Rect, media: &Media, hovered: bool) {
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+        match media.kind {
+            MediaKind::Image => match media_texture(ui.ctx(), self.img_cache, &media.url) {
+                Some(Ok(texture)) => {
+                    painter.image(
+                        texture.id(),
+                        rect,
+                        cover_uv(texture.size_vec2(), rect.size()),
+                        Color32::WHITE,
+                    );
+                }
+                Some(Err(())) => {
+                    painter.text(
+                        rect.center(),
+                        egui::Align2::CENTER_CENTER,
+                        "⚠",
+                        egui::FontId::proportional(20.0),
+                        ui.visuals().weak_text_color(),
+                    );
+                }
+                None => {}
+            },
+            MediaKind::Video => {
+                painter.text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "▶",
+                    egui::FontId::proportional(28.0),
+                    ui.visuals().text_color(),
+                );
+            }
+        }
+
+        if hovered {
+            painter.rect_filled(rect, 2.0, Color32::from_white_alpha(16));
+        }
+    }
+
+    fn cache(&mut self, ui: &mut egui::Ui) -> GridCache {
+        let notes = (
+            self.notes.len(),
+            self.notes.first().copied(),
+            self.notes.last().copied(),
+        );
+
+        let cache_id = self.id.with("media-grid-cache");
+        if let Some(cache) = ui.data(|d| d.get_temp::<GridCache>(cache_id)) {
+            if cache.notes == notes {
+                return cache;
+            }
+        }
+
+        let mut items = Vec::new();
+        for note_ref in self.notes {
+            let Ok(note) = self.ndb.get_note_by_key(self.txn, note_ref.key) else {
+                continue;
+            };
+
+            let muted =
+                root_note_id_from_selected_id(self.ndb, self.note_cache, self.txn, note.id())
+                    .map(|root_id| (self.is_muted)(&note, root_id.bytes()))
+                    .unwrap_or(false);
+            if muted {
+                continue;
+            }
+
+            for media in media::note_media(&note) {
+                items.push(GridItem {
+                    note_id: *note.id(),
+                    media,
+                });
+            }
+        }
+
+        let media = items.iter().map(|item| item.media.clone()).collect();
+        let cache = GridCache {
+            notes,
+            items: Arc::new(items),
+            media: Arc::new(media),
+        };
+        ui.data_mut(|d| d.insert_temp(cache_id, cache.clone()));
+        cache
+    }
+}
+
+/// The texture for an image url, starting the fetch if we haven't yet.
+/// None while it's loading.
+fn media_texture(
+    ctx: &egui::Context,
+    img_cache: &mut ImageCache,
+    url: &str,
+) -> Option<Result<TextureHandle, ()>> {
+    if !img_cache.map().contains_key(url) {
+        let promise = images::fetch_img(
+            img_cache,
+            ctx,
+            url,
+            ImageType::Content(MEDIA_SIZE, MEDIA_SIZE),
+        );
+        img_cache.map_mut().insert(url.to_owned(), promise);
+    }
+
+    match img_cache.get(url)?.ready()? {
+        Ok(texture) => Some(Ok(texture.clone())),
+        Err(_) => Some(Err(())),
+    }
+}
+
+/// uv coordinates that crop an image to fill `target` without stretching
+fn cover_uv(image: Vec2, target: Vec2) -> Rect {
+    let image_ratio = image.x / image.y.max(1.0);
+    let target_ratio = target.x / target.y.max(1.0);
+
+    if image_ratio > target_ratio {
+        let w = target_ratio / image_ratio;
+        Rect::from_min_max(pos2((1.0 - w) / 2.0, 0.0), pos2((1.0 + w) / 2.0, 1.0))
+    } else {
+        let h = image_ratio / target_ratio;
+        Rect::from_min_max(pos2(0.0, (1.0 - h) / 2.0), pos2(1.0, (1.0 + h) / 2.0))
+    }
+}
+
+/// Scale `image` to fit inside `bounds`, keeping its aspect ratio
+fn fit_size(image: Vec2, bounds: Vec2) -> Vec2 {
+    let scale = (bounds.x / image.x.max(1.0)).min(bounds.y / image.y.max(1.0));
+    image * scale
+}
+
+const MAX_ZOOM: f32 = 8.0;
+/// How far a drag has to go before it counts as a swipe to the next image
+const SWIPE_DISTANCE: f32 = 80.0;
+
+/// Full screen view of one piece of media at a time, with the rest of the
+/// set a swipe or arrow key away. Lives in egui memory so it can be opened
+/// from anywhere and drawn above everything else.
+#[derive(Clone)]
+pub struct MediaViewer {
+    media: Arc<Vec<Media>>,
+    index: usize,
+    zoom: f32,
+    pan: Vec2,
+    swipe: f32,
+    /// Quarter turns clockwise
+    rotation: u8,
+}
+
+enum ViewerAction {
+    Close,
+    Go(usize),
+    Export(MediaExport),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum MediaExport {
+    Save,
+    #[cfg(not(target_os = "android"))]
+    Copy,
+}
+
+/// How the last save or copy went
+#[derive(Debug, Clone)]
+enum ExportStatus {
+    Working,
+    Saved(PathBuf),
+    #[cfg(not(target_os = "android"))]
+    Copied,
+    Failed(String),
+}
+
+impl MediaViewer {
+    fn id() -> egui::Id {
+        egui::Id::new("media-viewer")
+    }
+
+    pub fn open(ctx: &egui::Context, media: Arc<Vec<Media>>, index: usize) {
+        if index >= media.len() {
+            return;
+        }
+
+        let viewer = MediaViewer {
+            media,
+            index,
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+            swipe: 0.0,
+            rotation: 0,
+        };
+        ctx.data_mut(|d| {
+            d.remove::<ExportStatus>(Self::status_id());
+            d.insert_temp(Self::id(), viewer);
+        });
+    }
+
+    /// Open a single image, like a profile banner
+    pub fn open_image(ctx: &egui::Context, url: &str) {
+        Self::open(ctx, Arc::new(vec![Media::image(url)]), 0);
+    }
+
+    fn status_id() -> egui::Id {
+        Self::id().with("status")
+    }
+
+    pub fn is_open(ctx: &egui::Context) -> bool {
+        ctx.data(|d| d.get_temp::<MediaViewer>(Self::id()))
+            .is_some()
+    }
+
+    pub fn close(ctx: &egui::Context) {
+        ctx.data_mut(|d| d.remove::<MediaViewer>(Self::id()));
+    }
+
+    /// Draw the viewer if it's open. Call once a frame, after everything
+    /// else. Saved images go to the downloads folder, or `fallback_dir` if
+    /// there isn't one.
+    pub fn show(ctx: &egui::Context, img_cache: &mut ImageCache, fallback_dir: &Path) {
+        let Some(mut viewer) = ctx.data(|d| d.get_temp::<MediaViewer>(Self::id())) else {
+            return;
+        };
+
+        let screen = ctx.screen_rect();
+        let action = egui::Area::new(Self::id())
+            .order(egui::Order::Foreground)
+            .fixed_pos(screen.min)
+            .show(ctx, |ui| viewer.ui(ui, screen, img_cache))
+            .inner;
+
+        match action {
+            Some(ViewerAction::Close) => {
+                Self::close(ctx);
+                return;
+            }
+            Some(ViewerAction::Go(index)) => {
+                viewer.index = index;
+                viewer.zoom = 1.0;
+                viewer.pan = Vec2::ZERO;
+                viewer.swipe = 0.0;
+                viewer.rotation = 0;
+            }
+            Some(ViewerAction::Export(export)) => {
+                let dir = dirs::download_dir()
+                    .or_else(dirs::picture_dir)
+                    .unwrap_or_else(|| fallback_dir.to_owned());
+                export_media(ctx, &viewer.media[viewer.index].url, export, dir);
+            }
+            None => {}
+        }
+
+        ctx.data_mut(|d| d.insert_temp(Self::id(), viewer));
+    }
+
+    fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        screen: Rect,
+        img_cache: &mut ImageCache,
+    ) -> Option<ViewerAction> {
+        let resp = ui.allocate_rect(screen, Sense::click_and_drag());
+        ui.painter()
+            .rect_filled(screen, 0.0, Color32::from_black_alpha(235));
+
+        let mut action = self.input(ui, &resp);
+
+        let media = &self.media[self.index];
+        let bounds = screen.shrink(48.0);
+        match media.kind {
+            MediaKind::Image => match media_texture(ui.ctx(), img_cache, &media.url) {
+                Some(Ok(texture)) => {
+                    // sideways images have to fit the bounds the other way round
+                    let fit_bounds = if self.rotation % 2 == 1 {
+                        vec2(bounds.height(), bounds.width())
+                    } else {
+                        bounds.size()
+                    };
+                    let size = fit_size(texture.size_vec2(), fit_bounds) * self.zoom;
+                    let rect = Rect::from_center_size(bounds.center() + self.pan, size);
+                    egui::Image::new(&texture)
+                        .rotate(self.rotation as f32 * FRAC_PI_2, Vec2::splat(0.5))
+                        .paint_at(ui, rect);
+                }
+                Some(Err(())) => {
+                    ui.put(
+                        bounds,
+                        egui::Label::new(RichText::new(tr!("media-failed")).color(Color32::GRAY)),
+                    );
+                }
+                None => {
+                    ui.put(bounds, egui::Spinner::new().size(32.0));
+                }
+            },
+            MediaKind::Video => {
+                ui.put(
+                    Rect::from_center_size(bounds.center(), vec2(240.0, 40.0)),
+                    egui::Hyperlink::from_label_and_url(
+                        RichText::new(tr!("media-open-video")).size(18.0),
+                        &media.url,
+                    ),
+                );
+            }
+        }
+
+        if let Some(resp) = self.controls(ui, screen) {
+            action = Some(resp);
+        }
+
+        action
+    }
+
+    /// Keyboard, swipe, drag and pinch handling
+    fn input(&mut self, ui: &mut egui::Ui, resp: &egui::Response) -> Option<ViewerAction> {
+        let mut action = None;
+
+        let (escape, left, right, rotate, zoom) = ui.input(|i| {
+            // pinch and ctrl+scroll come through as zoom, plain scrolling
+            // zooms too since there's nothing to scroll
+            let scroll = if i.modifiers.command {
+                1.0
+            } else {
+                (i.smooth_scroll_delta.y / 200.0).exp()
+            };
+
+            (
+                i.key_pressed(egui::Key::Escape),
+                i.key_pressed(egui::Key::ArrowLeft),
+                i.key_pressed(egui::Key::ArrowRight),
+                i.key_pressed(egui::Key::R),
+                i.zoom_delta() * scroll,
+            )
+        });
+
+        if escape {
+            return Some(ViewerAction::Close);
+        }
+        if left {
+            action = self.prev();
+        }
+        if right {
+            action = self.next();
+        }
+        if rotate {
+            self.rotate(1);
+        }
+
+        if zoom != 1.0 {
+            self.zoom = (self.zoom * zoom).clamp(1.0, MAX_ZOOM);
+            if self.zoom == 1.0 {
+                self.pan = Vec2::ZERO;
+            }
+        }
+
+        if resp.double_clicked() {
+            self.zoom = if self.zoom > 1.0 { 1.0 } else { 2.0 };
+            self.pan = Vec2::ZERO;
+        }
+
+        if resp.dragged() {
+            if self.zoom > 1.0 {
+                self.pan += resp.drag_delta();
+            } else {
+                self.swipe += resp.drag_delta().x;
+            }
+        }
+
+        if resp.drag_stopped() {
+            if self.swipe <= -SWIPE_DISTANCE {
+                action = self.next();
+            } else if self.swipe >= SWIPE_DISTANCE {
+                action = self.prev();
+            }
+            self.swipe = 0.0;
+        }
+
+        action
+    }
+
+    fn rotate(&mut self, quarter_turns: u8) {
+        self.rotation = (self.rotation + quarter_turns) % 4;
+        self.pan = Vec2::ZERO;
+    }
+
+    fn prev(&self) -> Option<ViewerAction> {
+        self.index.checked_sub(1).map(ViewerAction::Go)
+    }
+
+    fn next(&self) -> Option<ViewerAction> {
+        (self.index + 1 < self.media.len()).then_some(ViewerAction::Go(self.index + 1))
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui, screen: Rect) -> Option<ViewerAction> {
+        let mut action = None;
+        let button = |text: &str| {
+            egui::Button::new(RichText::new(text).size(20.0).color(Color32::WHITE)).frame(false)
+        };
+
+        let close_rect = Rect::from_min_size(
+            pos2(screen.right() - 48.0, screen.top() + 8.0),
+            vec2(40.0, 40.0),
+        );
+        if ui
+            .put(close_rect, button("✕"))
+            .on_hover_text(tr!("media-viewer-close"))
+            .clicked()
+        {
+            action = Some(ViewerAction::Close);
+        }
+
+        if self.media.len() > 1 {
+            ui.put(
+                Rect::from_min_size(screen.left_top() + vec2(16.0, 16.0), vec2(80.0, 24.0)),
+                egui::Label::new(
+                    RichText::new(format!("{} / {}", self.index + 1, self.media.len()))
+                        .color(Color32::WHITE),
+                ),
+            );
+        }
+
+        let side = vec2(40.0, 80.0);
+        if self.index > 0 {
+            let rect = Rect::from_center_size(pos2(screen.left() + 28.0, screen.center().y), side);
+            if ui
+                .put(rect, button("◀"))
+                .on_hover_text(tr!("media-viewer-previous"))
+                .clicked()
+            {
+                action = self.prev();
+            }
+        }
+
+        if self.index + 1 < self.media.len() {
+            let rect = Rect::from_center_size(pos2(screen.right() - 28.0, screen.center().y), side);
+            if ui
+                .put(rect, button("▶"))
+                .on_hover_text(tr!("media-viewer-next"))
+                .clicked()
+            {
+                action = self.next();
+            }
+        }
+
+        if let Some(resp) = self.toolbar(ui, screen) {
+            action = Some(resp);
+        }
+
+        action
+    }
+
+    /// Rotate, save and copy along the bottom, with how the last save or
+    /// copy went
+    fn toolbar(&mut self, ui: &mut egui::Ui, screen: Rect) -> Option<ViewerAction> {
+        let mut action = None;
+        let media = &self.media[self.index];
+        let is_image = media.kind == MediaKind::Image;
+        let url = media.url.clone();
+
+        let rect = Rect::from_center_size(
+            pos2(screen.center().x, screen.bottom() - 28.0),
+            vec2(screen.width().min(640.0), 32.0),
+        );
+        let mut rotate = 0;
+        ui.allocate_new_ui(egui::UiBuilder::new().max_rect(rect), |ui| {
+            ui.visuals_mut().override_text_color = Some(Color32::WHITE);
+            ui.horizontal_centered(|ui| {
+                if is_image {
+                    if ui
+                        .button("⟲")
+                        .on_hover_text(tr!("media-rotate-left"))
+                        .clicked()
+                    {
+                        rotate = 3;
+                    }
+                    if ui
+                        .button("⟳")
+                        .on_hover_text(tr!("media-rotate-right"))
+                        .clicked()
+                    {
+                        rotate = 1;
+                    }
+                }
+
+                if ui.button(tr!("media-save")).clicked() {
+                    action = Some(ViewerAction::Export(MediaExport::Save));
+                }
+
+                #[cfg(not(target_os = "android"))]
+                if is_image && ui.button(tr!("media-copy-image")).clicked() {
+                    action = Some(ViewerAction::Export(MediaExport::Copy));
+                }
+
+                if ui.button(tr!("media-copy-link")).clicked() {
+                    ui.ctx().copy_text(url);
+                }
+
+                if let Some(status) = ui.data(|d| d.get_temp::<ExportStatus>(Self::status_id())) {
+                    ui.label(export_status_text(&status));
+                }
+            });
+        });
+
+        if rotate != 0 {
+            self.rotate(rotate);
+        }
+
+        if action.is_some() {
+            ui.data_mut(|d| d.insert_temp(Self::status_id(), ExportStatus::Working));
+        }
+
+        action
+    }
+}
+
+fn export_status_text(status: &ExportStatus) -> String {
+    match status {
+        ExportStatus::Working => tr!("media-working"),
+        ExportStatus::Saved(path) => tr!("media-saved", path = path.display().to_string()),
+        #[cfg(not(target_os = "android"))]
+        ExportStatus::Copied => tr!("media-copied"),
+        ExportStatus::Failed(err) => tr!("media-export-failed", error = err.clone()),
+    }
+}
+
+/// Download the original file, since we only keep a resized copy, then
+/// save it or put it on the clipboard
+fn export_media(ctx: &egui::Context, url: &str, export: MediaExport, dir: PathBuf) {
+    if images::is_blocked(url) {
+        let status = ExportStatus::Failed(tr!("media-blocked-host"));
+        ctx.data_mut(|d| d.insert_temp(MediaViewer::status_id(), status));
+        return;
+    }
+
+    let ctx = ctx.clone();
+    let url = url.to_owned();
+    ehttp::fetch(ehttp::Request::get(&url), move |response| {
+        let result = match response {
+            Ok(resp) if resp.ok => finish_export(&url, export, &dir, &resp.bytes),
+            Ok(resp) => Err(format!("{} {}", resp.status, resp.status_text)),
+            Err(err) => Err(err),
+        };
+
+        let status = result.unwrap_or_else(|err| {
+            error!("failed to export {url}: {err}");
+            ExportStatus::Failed(err)
+        });
+        ctx.data_mut(|d| d.insert_temp(MediaViewer::status_id(), status));
+        ctx.request_repaint();
+    });
+}
+
+fn finish_export(
+    url: &str,
+    export: MediaExport,
+    dir: &Path,
+    bytes: &[u8],
+) -> Result<ExportStatus, String> {
+    match export {
+        MediaExport::Save => {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            let path = dir.join(file_name_from_url(url));
+            std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+            Ok(ExportStatus::Saved(path))
+        }
+        #[cfg(not(target_os = "android"))]
+        MediaExport::Copy => {
+            let image = image::load_from_memory(bytes)
+                .map_err(|e| e.to_string())?
+                .into_rgba8();
+            let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+            clipboard
+                .set_image(arboard::ImageData {
+                    width: image.width() as usize,
+                    height: image.height() as usize,
+                    bytes: std::borrow::Cow::Borrowed(image.as_raw()),
+                })
+                .map_err(|e| e.to_string())?;
+            Ok(ExportStatus::Copied)
+        }
+    }
+}
+
+/// The last part of the url's path, with anything that doesn't belong in
+/// a file name replaced
+fn file_name_from_url(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let name: String = path
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if name.trim_matches(['.', '_']).is_empty() {
+        "notedeck-media".to_owned()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cover_uv() {
+        // a wide image in a square tile loses its sides
+        let uv = cover_uv(vec2(200.0, 100.0), vec2(50.0, 50.0));
+        assert_eq!(uv, Rect::from_min_max(pos2(0.25, 0.0), pos2(0.75, 1.0)));
+
+        // a tall one loses its top and bottom
+        let uv = cover_uv(vec2(100.0, 400.0), vec2(50.0, 50.0));
+        assert_eq!(uv, Rect::from_min_max(pos2(0.0, 0.375), pos2(1.0, 0.625)));
+    }
+
+    #[test]
+    fn test_file_name_from_url() {
+        assert_eq!(
+            file_name_from_url("https://example.com/a/cat%20pic.jpg?w=10"),
+            "cat_20pic.jpg"
+        );
+        assert_eq!(file_name_from_url("https://example.com/"), "notedeck-media");
+        assert_eq!(
+            file_name_from_url("https://example.com/.."),
+            "notedeck-media"
+        );
+    }
+
+    #[test]
+    fn test_fit_size() {
+        assert_eq!(
+            fit_size(vec2(200.0, 100.0), vec2(100.0, 100.0)),
+            vec2(100.0, 50.0)
+        );
+        assert_eq!(
+            fit_size(vec2(50.0, 100.0), vec2(100.0, 100.0)),
+            vec2(50.0, 100.0)
+        );
+    }
+}
@@ -1,21 +1,53 @@
+pub mod a11y;
 pub mod account_login_view;
 pub mod accounts;
 pub mod add_column;
 pub mod anim;
+pub mod bookmarks;
 pub mod column;
 pub mod configure_deck;
+pub mod density;
 pub mod edit_deck;
+pub mod emoji_picker;
+pub mod explore_people;
+pub mod export;
+pub mod follow_pack;
+pub mod hashtag;
+pub mod hidden;
+pub mod highlight;
+pub mod highlights;
+pub mod import_deck;
+pub mod media;
 pub mod mention;
+pub mod my_posts;
+pub mod nip05;
 pub mod note;
+pub mod note_edit;
+pub mod notification_settings;
+pub mod offline_banner;
+pub mod onboarding;
+pub mod people_picker;
+pub mod poster;
 pub mod preview;
 pub mod profile;
+pub mod qr;
 pub mod relay;
 pub mod relay_debug;
+pub mod relay_groups;
+pub mod report;
+pub mod session_banner;
+pub mod settings;
 pub mod side_panel;
+pub mod source;
+pub mod storage;
+pub mod subscriptions;
 pub mod support;
 pub mod thread;
 pub mod timeline;
+pub mod trending;
+pub mod unsent;
 pub mod username;
+pub mod wallet;
 
 pub use accounts::AccountsView;
 pub use mention::Mention;
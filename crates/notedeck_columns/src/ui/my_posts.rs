@@ -0,0 +1,262 @@
+use std::time::Instant;
+
+use egui::{Frame, RichText};
+use enostr::{NoteId, Pubkey};
+use nostrdb::{Filter, Ndb, Note, Transaction};
+use notedeck::{
+    calendar::{RSVP_KIND, TIME_EVENT_KIND},
+    edit, time_ago_since, tr, ImageCache, NoteCache,
+};
+
+use crate::{
+    actionbar::NoteAction,
+    deletions, export,
+    publish::{PublishState, Publishes, RelayResult},
+    ui::{self, note::NoteOptions},
+};
+
+/// Most of our events the column lists
+const MY_POSTS_LIMIT: i32 = 200;
+
+const DATE_EVENT_KIND: u32 = 31922;
+
+pub enum MyPostsAction {
+    Note(NoteAction),
+    /// Start a new note with this one's text
+    EditAsNew(NoteId),
+    /// Change it and publish it again
+    Edit(NoteId),
+    Delete(NoteId),
+}
+
+/// Which of our events the column shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KindFilter {
+    #[default]
+    All,
+    Notes,
+    Reposts,
+    Reactions,
+    CalendarEvents,
+    Rsvps,
+    /// Profile, follows and other lists
+    Lists,
+}
+
+impl KindFilter {
+    const ALL: [KindFilter; 7] = [
+        KindFilter::All,
+        KindFilter::Notes,
+        KindFilter::Reposts,
+        KindFilter::Reactions,
+        KindFilter::CalendarEvents,
+        KindFilter::Rsvps,
+        KindFilter::Lists,
+    ];
+
+    fn kinds(&self) -> Vec<u64> {
+        match self {
+            KindFilter::All => KindFilter::ALL[1..]
+                .iter()
+                .flat_map(|filter| filter.kinds())
+                .collect(),
+            KindFilter::Notes => vec![1],
+            KindFilter::Reposts => vec![6],
+            KindFilter::Reactions => vec![7],
+            KindFilter::CalendarEvents => vec![DATE_EVENT_KIND as u64, TIME_EVENT_KIND as u64],
+            KindFilter::Rsvps => vec![RSVP_KIND as u64],
+            KindFilter::Lists => vec![0, 3, 10000, 10001, 10003, 10015, 30000, 30003],
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            KindFilter::All => tr!("my-posts-all"),
+            KindFilter::Notes => tr!("export-kind-note"),
+            KindFilter::Reposts => tr!("export-kind-repost"),
+            KindFilter::Reactions => tr!("export-kind-reaction"),
+            KindFilter::CalendarEvents => tr!("export-kind-calendar-event"),
+            KindFilter::Rsvps => tr!("export-kind-rsvp"),
+            KindFilter::Lists => tr!("my-posts-lists"),
+        }
+    }
+}
+
+/// Everything the account has published, newest first, with how sending it
+/// from this device went
+pub struct MyPostsView<'a> {
+    ndb: &'a Ndb,
+    note_cache: &'a mut NoteCache,
+    img_cache: &'a mut ImageCache,
+    publishes: &'a Publishes,
+    pubkey: &'a Pubkey,
+    note_options: NoteOptions,
+}
+
+impl<'a> MyPostsView<'a> {
+    pub fn new(
+        ndb: &'a Ndb,
+        note_cache: &'a mut NoteCache,
+        img_cache: &'a mut ImageCache,
+        publishes: &'a Publishes,
+        pubkey: &'a Pubkey,
+        note_options: NoteOptions,
+    ) -> Self {
+        MyPostsView {
+            ndb,
+            note_cache,
+            img_cache,
+            publishes,
+            pubkey,
+            note_options,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<MyPostsAction> {
+        let filter_id = ui.id().with("my-posts-kinds");
+        let mut kinds: KindFilter = ui.data(|d| d.get_temp(filter_id)).unwrap_or_default();
+
+        Frame::none()
+            .inner_margin(egui::Margin::symmetric(8.0, 4.0))
+            .show(ui, |ui| {
+                egui::ComboBox::from_id_salt(filter_id)
+                    .selected_text(kinds.label())
+                    .show_ui(ui, |ui| {
+                        for option in KindFilter::ALL {
+                            ui.selectable_value(&mut kinds, option, option.label());
+                        }
+                    });
+            });
+        ui.data_mut(|d| d.insert_temp(filter_id, kinds));
+        ui::hline(ui);
+
+        let txn = Transaction::new(self.ndb).expect("txn");
+        let filter = Filter::new()
+            .authors([self.pubkey.bytes()])
+            .kinds(kinds.kinds())
+            .limit(MY_POSTS_LIMIT as u64)
+            .build();
+        let mut notes: Vec<Note> = self
+            .ndb
+            .query(&txn, &[filter], MY_POSTS_LIMIT)
+            .map(|results| results.into_iter().map(|r| r.note).collect())
+            .unwrap_or_default();
+        notes.sort_by_key(|note| std::cmp::Reverse(note.created_at()));
+
+        // once we've asked for it to be deleted it's gone from here
+        let tombstones = deletions::tombstones(ui.ctx());
+        notes.retain(|note| {
+            !tombstones
+                .as_ref()
+                .is_some_and(|tombstones| tombstones.deleted(note).is_some())
+        });
+
+        if notes.is_empty() {
+            ui.add_space(16.0);
+            ui.vertical_centered(|ui| {
+                ui.label(RichText::new(tr!("my-posts-empty")).weak());
+            });
+            return None;
+        }
+
+        let mut action = None;
+        egui::ScrollArea::vertical()
+            .id_salt(("my-posts", kinds.label()))
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for note in &notes {
+                    ui::padding(8.0, ui, |ui| {
+                        if let Some(resp) = self.note_ui(ui, note) {
+                            action = Some(resp);
+                        }
+                    });
+                    ui::hline(ui);
+                }
+            });
+
+        action
+    }
+
+    fn note_ui(&mut self, ui: &mut egui::Ui, note: &Note) -> Option<MyPostsAction> {
+        let mut action = None;
+        let note_id = NoteId::new(*note.id());
+
+        if matches!(note.kind(), 1 | 6) {
+            let resp = ui::NoteView::new(self.ndb, self.note_cache, self.img_cache, note)
+                .note_options(self.note_options)
+                .show(ui);
+            if let Some(note_action) = resp.action {
+                action = Some(MyPostsAction::Note(note_action));
+            }
+            if let Some(context) = resp.context_selection {
+                context.process(ui, note);
+            }
+        } else {
+            ui.horizontal_wrapped(|ui| {
+                ui.label(RichText::new(export::kind_label(note.kind())).strong());
+                ui.label(RichText::new(time_ago_since(note.created_at())).weak());
+            });
+            let summary = export::summary(note);
+            if !summary.is_empty() {
+                ui.label(summary);
+            }
+        }
+
+        ui.horizontal(|ui| {
+            delivery_ui(ui, note.id(), self.publishes);
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                if ui
+                    .small_button(tr!("my-posts-delete"))
+                    .on_hover_text(tr!("my-posts-delete-hint"))
+                    .double_clicked()
+                {
+                    action = Some(MyPostsAction::Delete(note_id));
+                }
+                if note.kind() == 1 && ui.small_button(tr!("my-posts-edit-as-new")).clicked() {
+                    action = Some(MyPostsAction::EditAsNew(note_id));
+                }
+                let editable = note.kind() == 1 || edit::is_replaceable(note.kind());
+                if editable && ui.small_button(tr!("my-posts-edit")).clicked() {
+                    action = Some(MyPostsAction::Edit(note_id));
+                }
+            });
+        });
+
+        action
+    }
+}
+
+/// How sending a note went, with each relay's answer underneath
+fn delivery_ui(ui: &mut egui::Ui, id: &[u8; 32], publishes: &Publishes) {
+    let Some(publish) = publishes.get(id) else {
+        ui.label(RichText::new(tr!("my-posts-not-sent-here")).weak().small())
+            .on_hover_text(tr!("my-posts-not-sent-here-hint"));
+        return;
+    };
+
+    let summary = match publish.state(Instant::now()) {
+        PublishState::Sending => tr!("my-posts-sending"),
+        PublishState::Done { accepted, total } => {
+            tr!("my-posts-accepted", accepted = accepted, total = total)
+        }
+    };
+
+    egui::CollapsingHeader::new(RichText::new(summary).small())
+        .id_salt(("delivery", publish.id.as_str()))
+        .show(ui, |ui| {
+            for (url, result) in &publish.relays {
+                let (icon, color, reason) = match result {
+                    RelayResult::Accepted => ("✔", ui.visuals().text_color(), None),
+                    RelayResult::Waiting => ("…", ui.visuals().weak_text_color(), None),
+                    RelayResult::Rejected(reason) => {
+                        ("✖", ui.visuals().error_fg_color, Some(reason.as_str()))
+                    }
+                };
+                let resp = ui.label(RichText::new(format!("{icon} {url}")).small().color(color));
+                if let Some(reason) = reason.filter(|reason| !reason.is_empty()) {
+                    resp.on_hover_text(reason);
+                }
+            }
+        });
+}
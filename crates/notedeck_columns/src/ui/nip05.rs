@@ -0,0 +1,29 @@
+use egui::{Response, RichText};
+use notedeck::tr;
+
+use crate::nip05::{self, Nip05Status};
+
+/// A checkmark once the identifier's domain confirms the key, or a warning
+/// when it lists someone else. Nothing while we don't know.
+pub fn badge(ui: &mut egui::Ui, pubkey: &[u8; 32], nip05: &str, size: f32) -> Option<Response> {
+    match nip05::status(ui.ctx(), pubkey, nip05) {
+        Nip05Status::Verified => Some(
+            ui.add(
+                egui::Image::new(egui::include_image!(
+                    "../../../../assets/icons/verified_4x.png"
+                ))
+                .max_height(size),
+            )
+            .on_hover_text(tr!("nip05-verified", nip05 = nip05)),
+        ),
+        Nip05Status::Mismatch => Some(
+            ui.label(
+                RichText::new("⚠")
+                    .size(size)
+                    .color(ui.visuals().warn_fg_color),
+            )
+            .on_hover_text(tr!("nip05-mismatch", nip05 = nip05)),
+        ),
+        Nip05Status::Unchecked | Nip05Status::Unreachable => None,
+    }
+}
@@ -1,15 +1,24 @@
+use std::sync::Arc;
+
 use crate::actionbar::NoteAction;
+use crate::blossom;
+use crate::deep_link;
+use crate::geo::{self, Place};
 use crate::images::ImageType;
+use crate::media::Media;
 use crate::ui::{
     self,
+    density::Density,
+    media::MediaViewer,
     note::{NoteOptions, NoteResponse},
     ProfilePic,
 };
 use egui::{Color32, Hyperlink, Image, RichText};
+use enostr::Address;
 use nostrdb::{BlockType, Mention, Ndb, Note, NoteKey, Transaction};
-use tracing::warn;
+use tracing::{error, warn};
 
-use notedeck::{ImageCache, NoteCache};
+use notedeck::{highlight, tr, ImageCache, NoteCache};
 
 pub struct NoteContents<'a> {
     ndb: &'a Ndb,
@@ -125,7 +134,25 @@ pub fn render_note_preview(
         .inner
 }
 
-fn is_image_link(url: &str) -> bool {
+/// An naddr in a note. Whichever app handles its kind opens it.
+fn address_link(ui: &mut egui::Ui, address: Address, link_color: Color32) {
+    let label = if address.identifier.is_empty() {
+        address.kind.to_string()
+    } else {
+        address.identifier.clone()
+    };
+    let response = ui
+        .add(
+            egui::Label::new(RichText::new(format!("🔗{label}")).color(link_color))
+                .sense(egui::Sense::click()),
+        )
+        .on_hover_cursor(egui::CursorIcon::PointingHand);
+    if response.clicked() {
+        deep_link::open_address(ui.ctx(), address);
+    }
+}
+
+pub(crate) fn is_image_link(url: &str) -> bool {
     url.ends_with("png") || url.ends_with("jpg") || url.ends_with("jpeg")
 }
 
@@ -143,6 +170,10 @@ fn render_note_contents(
     #[cfg(feature = "profiling")]
     puffin::profile_function!();
 
+    if note.kind() == highlight::HIGHLIGHT_KIND {
+        return ui::note::highlight::quote(ui, ndb, txn, note);
+    }
+
     let selectable = options.has_selectable_text();
     let mut images: Vec<String> = vec![];
     let mut note_action: Option<NoteAction> = None;
@@ -191,14 +222,18 @@ fn render_note_contents(
                     }
 
                     _ => {
-                        ui.colored_label(link_color, format!("@{}", &block.as_str()[4..16]));
+                        if let Some(address) = Address::from_bech(block.as_str()) {
+                            address_link(ui, address, link_color);
+                        } else {
+                            ui.colored_label(link_color, format!("@{}", &block.as_str()[4..16]));
+                        }
                     }
                 },
 
                 BlockType::Hashtag => {
                     #[cfg(feature = "profiling")]
                     puffin::profile_scope!("hashtag contents");
-                    ui.colored_label(link_color, format!("#{}", block.as_str()));
+                    ui::hashtag::hashtag_link(ui, block.as_str(), link_color);
                 }
 
                 BlockType::Url => {
@@ -218,7 +253,19 @@ fn render_note_contents(
                 BlockType::Text => {
                     #[cfg(feature = "profiling")]
                     puffin::profile_scope!("text contents");
-                    ui.add(egui::Label::new(block.as_str()).selectable(selectable));
+                    let text = block.as_str();
+                    if notedeck::text::has_rtl(text) {
+                        let font_id = egui::TextStyle::Body.resolve(ui.style());
+                        let color = ui.visuals().text_color();
+                        let width = ui.available_width();
+                        let galley = ui.fonts(|fonts| {
+                            notedeck::text::galley(fonts, text, font_id, color, width)
+                        });
+                        ui.add(egui::Label::new(galley).selectable(selectable));
+                    } else {
+                        let text = notedeck::text::prepare(text).into_owned();
+                        ui.add(egui::Label::new(text).selectable(selectable));
+                    }
                 }
 
                 _ => {
@@ -237,24 +284,83 @@ fn render_note_contents(
     if !images.is_empty() && !options.has_textmode() {
         ui.add_space(2.0);
         let carousel_id = egui::Id::new(("carousel", note.key().expect("expected tx note")));
-        image_carousel(ui, img_cache, images, carousel_id);
+        image_carousel(ui, ndb, txn, img_cache, note.pubkey(), images, carousel_id);
         ui.add_space(2.0);
     }
 
+    if let Some(place) = geo::note_location(note) {
+        let show_map = !hide_media && !options.has_textmode();
+        location_ui(ui, img_cache, &place, show_map);
+    }
+
     let note_action = preview_note_action.or(note_action);
 
     NoteResponse::new(response.response).with_action(note_action)
 }
 
+/// Where the note was posted from, with a map tile when media is shown
+fn location_ui(ui: &mut egui::Ui, img_cache: &mut ImageCache, place: &Place, show_map: bool) {
+    ui.horizontal_wrapped(|ui| {
+        ui.weak(format!("📍 {}", place.name));
+        if ui.small_button(tr!("location-open-in-maps")).clicked() {
+            let url = geo::maps_url(place);
+            if let Err(err) = open::that(&url) {
+                error!("failed to open {url}: {err}");
+            }
+        }
+    });
+
+    if !show_map {
+        return;
+    }
+
+    let size = 256.0;
+    let url = geo::tile_url(&geo::services().tiles, place.lat, place.lon, geo::TILE_ZOOM);
+    if !img_cache.map().contains_key(&url) {
+        let imgtyp = ImageType::Content(size as u32, size as u32);
+        let res = crate::images::fetch_img(img_cache, ui.ctx(), &url, imgtyp);
+        img_cache.map_mut().insert(url.clone(), res);
+    }
+
+    match img_cache.get(&url).and_then(|promise| promise.ready()) {
+        None => {
+            ui.allocate_space(egui::vec2(size, size));
+        }
+        // no map then, the name and button are still there
+        Some(Err(_)) => {}
+        Some(Ok(tile)) => {
+            let rect = ui
+                .add(
+                    Image::new(tile)
+                        .fit_to_exact_size(egui::vec2(size, size))
+                        .rounding(5.0),
+                )
+                .rect;
+
+            let (_, (fx, fy)) = geo::tile_position(place.lat, place.lon, geo::TILE_ZOOM);
+            let pin = rect.min + egui::vec2(fx, fy) * rect.size();
+            ui.painter().circle(
+                pin,
+                6.0,
+                ui.visuals().selection.bg_fill,
+                egui::Stroke::new(2.0, Color32::WHITE),
+            );
+        }
+    }
+}
+
 fn image_carousel(
     ui: &mut egui::Ui,
+    ndb: &Ndb,
+    txn: &Transaction,
     img_cache: &mut ImageCache,
+    author: &[u8; 32],
     images: Vec<String>,
     carousel_id: egui::Id,
 ) {
     // let's make sure everything is within our area
 
-    let height = 360.0;
+    let height = Density::get(ui.ctx()).image_height();
     let width = ui.available_size().x;
     let spinsz = if height > width { width } else { height };
 
@@ -263,21 +369,42 @@ fn image_carousel(
             .id_salt(carousel_id)
             .show(ui, |ui| {
                 ui.horizontal(|ui| {
-                    for image in images {
+                    let media: Arc<Vec<Media>> =
+                        Arc::new(images.iter().map(Media::image).collect());
+                    let imgtyp = ImageType::Content(width.round() as u32, height.round() as u32);
+                    for (index, image) in images.into_iter().enumerate() {
+                        let hover_id = carousel_id.with(("hovered", index));
+                        // playing gifs come from egui's loader, our cache
+                        // only has their first frame
+                        if crate::images::should_animate(ui.ctx(), &image, hover_id) {
+                            if let Some(texture) = crate::images::load_texture(
+                                ui,
+                                &image,
+                                egui::vec2(width, height),
+                                hover_id,
+                            ) {
+                                let img_resp = carousel_image(
+                                    ui,
+                                    Image::new(texture),
+                                    height,
+                                    &media,
+                                    index,
+                                    &image,
+                                );
+                                crate::images::set_hovered(ui.ctx(), hover_id, img_resp.hovered());
+                                continue;
+                            }
+                        }
+
                         // If the cache is empty, initiate the fetch
                         let m_cached_promise = img_cache.map().get(&image);
                         if m_cached_promise.is_none() {
-                            let res = crate::images::fetch_img(
-                                img_cache,
-                                ui.ctx(),
-                                &image,
-                                ImageType::Content(width.round() as u32, height.round() as u32),
-                            );
+                            let res = crate::images::fetch_img(img_cache, ui.ctx(), &image, imgtyp);
                             img_cache.map_mut().insert(image.to_owned(), res);
                         }
 
                         // What is the state of the fetch?
-                        match img_cache.map()[&image].ready() {
+                        match img_cache.get(&image).and_then(|promise| promise.ready()) {
                             // Still waiting
                             None => {
                                 ui.allocate_space(egui::vec2(spinsz, spinsz));
@@ -285,6 +412,23 @@ fn image_carousel(
                             }
                             // Failed to fetch image!
                             Some(Err(_err)) => {
+                                // the host may have lost it, look for the blob on
+                                // the author's media servers
+                                let servers = blossom::server_list(ndb, txn, author);
+                                let mirrors = blossom::repair_urls(ui.ctx(), &image, &servers);
+                                if !mirrors.is_empty() {
+                                    let res = crate::images::fetch_img_from_mirrors(
+                                        img_cache,
+                                        ui.ctx(),
+                                        &image,
+                                        mirrors,
+                                        imgtyp,
+                                    );
+                                    img_cache.map_mut().insert(image.to_owned(), res);
+                                    ui.allocate_space(egui::vec2(spinsz, spinsz));
+                                    continue;
+                                }
+
                                 // FIXME - use content-specific error instead
                                 let no_pfp = crate::images::fetch_img(
                                     img_cache,
@@ -299,18 +443,15 @@ fn image_carousel(
                             }
                             // Use the previously resolved image
                             Some(Ok(img)) => {
-                                let img_resp = ui.add(
-                                    Image::new(img)
-                                        .max_height(height)
-                                        .rounding(5.0)
-                                        .fit_to_original_size(1.0),
+                                let img_resp = carousel_image(
+                                    ui,
+                                    Image::new(img),
+                                    height,
+                                    &media,
+                                    index,
+                                    &image,
                                 );
-                                img_resp.context_menu(|ui| {
-                                    if ui.button("Copy Link").clicked() {
-                                        ui.ctx().copy_text(image);
-                                        ui.close_menu();
-                                    }
-                                });
+                                crate::images::set_hovered(ui.ctx(), hover_id, img_resp.hovered());
                             }
                         }
                     }
@@ -320,3 +461,30 @@ fn image_carousel(
             .inner
     });
 }
+
+fn carousel_image(
+    ui: &mut egui::Ui,
+    image: Image<'_>,
+    height: f32,
+    media: &Arc<Vec<Media>>,
+    index: usize,
+    url: &str,
+) -> egui::Response {
+    let img_resp = ui.add(
+        image
+            .max_height(height)
+            .rounding(5.0)
+            .fit_to_original_size(1.0)
+            .sense(egui::Sense::click()),
+    );
+    if img_resp.clicked() {
+        MediaViewer::open(ui.ctx(), media.clone(), index);
+    }
+    img_resp.context_menu(|ui| {
+        if ui.button("Copy Link").clicked() {
+            ui.ctx().copy_text(url.to_owned());
+            ui.close_menu();
+        }
+    });
+    img_resp
+}
@@ -10,6 +10,15 @@ pub enum NoteContextSelection {
     CopyPubkey,
     CopyNoteId,
     CopyNoteJSON,
+    /// Handled by the note view, which turns it into a NoteAction
+    ExportImage,
+    /// Also turned into a NoteAction by the note view
+    ViewSource,
+    Highlight,
+    Bookmark,
+    Hide,
+    HideAuthor,
+    Report,
 }
 
 impl NoteContextSelection {
@@ -40,6 +49,13 @@ impl NoteContextSelection {
                     Err(err) => error!("error copying note json: {err}"),
                 });
             }
+            NoteContextSelection::ExportImage
+            | NoteContextSelection::ViewSource
+            | NoteContextSelection::Highlight
+            | NoteContextSelection::Bookmark
+            | NoteContextSelection::Hide
+            | NoteContextSelection::HideAuthor
+            | NoteContextSelection::Report => {}
         }
     }
 }
@@ -114,6 +130,7 @@ impl NoteContextButton {
         let min_radius = Self::min_radius();
         let anim_speed = 0.05;
         let response = ui.interact(put_at, id, egui::Sense::click());
+        crate::ui::a11y::button(ui, &response, "Note options");
 
         let hovered = response.hovered();
         let animation_progress = ui.ctx().animate_bool_with_time(id, hovered, anim_speed);
@@ -173,6 +190,35 @@ impl NoteContextButton {
                 context_selection = Some(NoteContextSelection::CopyNoteJSON);
                 ui.close_menu();
             }
+            if ui.button("Export as image").clicked() {
+                context_selection = Some(NoteContextSelection::ExportImage);
+                ui.close_menu();
+            }
+            if ui.button("Highlight…").clicked() {
+                context_selection = Some(NoteContextSelection::Highlight);
+                ui.close_menu();
+            }
+            if ui.button("Bookmark…").clicked() {
+                context_selection = Some(NoteContextSelection::Bookmark);
+                ui.close_menu();
+            }
+            if ui.button("View source").clicked() {
+                context_selection = Some(NoteContextSelection::ViewSource);
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button("Hide note").clicked() {
+                context_selection = Some(NoteContextSelection::Hide);
+                ui.close_menu();
+            }
+            if ui.button("Hide notes from this person").clicked() {
+                context_selection = Some(NoteContextSelection::HideAuthor);
+                ui.close_menu();
+            }
+            if ui.button("Report…").clicked() {
+                context_selection = Some(NoteContextSelection::Report);
+                ui.close_menu();
+            }
         });
 
         context_selection
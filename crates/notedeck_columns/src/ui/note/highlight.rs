@@ -0,0 +1,75 @@
+use egui::{RichText, Sense, Stroke};
+use enostr::NoteId;
+use nostrdb::{Ndb, Note, Transaction};
+use notedeck::{highlight, note::tag_strings, tr};
+
+use crate::{
+    actionbar::NoteAction,
+    profile::get_display_name,
+    ui::{self, note::NoteResponse},
+};
+
+/// A highlight, shown as the passage it picked out with a bar down the
+/// side, the highlighter's comment above it and where it came from below
+pub fn quote(ui: &mut egui::Ui, ndb: &Ndb, txn: &Transaction, note: &Note) -> NoteResponse {
+    let mut action = None;
+
+    let response = ui
+        .vertical(|ui| {
+            if let Some(comment) = highlight::comment(note) {
+                ui.label(comment);
+                ui.add_space(4.0);
+            }
+
+            let bar = ui.visuals().selection.stroke.color;
+            let resp = egui::Frame::none()
+                .inner_margin(egui::Margin {
+                    left: 10.0,
+                    right: 4.0,
+                    top: 2.0,
+                    bottom: 2.0,
+                })
+                .show(ui, |ui| {
+                    ui.add(egui::Label::new(RichText::new(note.content()).italics()).wrap());
+                })
+                .response;
+            ui.painter().line_segment(
+                [resp.rect.left_top(), resp.rect.left_bottom()],
+                Stroke::new(3.0, bar),
+            );
+
+            let source = highlight::source(note)
+                .and_then(|source| highlight::find_source(ndb, txn, &source));
+            if let Some(source) = source {
+                ui.add_space(2.0);
+                let from = tr!("highlight-from", source = source_name(ndb, txn, &source));
+                let link = ui.add(
+                    egui::Label::new(RichText::new(from).small().weak()).sense(Sense::click()),
+                );
+                if link.clicked() {
+                    action = Some(NoteAction::OpenThread(NoteId::new(*source.id())));
+                }
+                if link.hovered() {
+                    ui::show_pointer(ui);
+                }
+            }
+        })
+        .response;
+
+    NoteResponse::new(response).with_action(action)
+}
+
+/// An article's title, or who wrote the note
+fn source_name(ndb: &Ndb, txn: &Transaction, source: &Note) -> String {
+    if let Some(title) = tag_strings(source)
+        .into_iter()
+        .find(|tag| tag[0] == "title")
+        .and_then(|tag| tag.get(1).cloned())
+        .filter(|title| !title.is_empty())
+    {
+        return title;
+    }
+
+    let profile = ndb.get_profile_by_pubkey(txn, source.pubkey()).ok();
+    get_display_name(profile.as_ref()).name().to_owned()
+}
@@ -1,5 +1,6 @@
 pub mod contents;
 pub mod context;
+pub mod highlight;
 pub mod options;
 pub mod post;
 pub mod quote_repost;
@@ -9,23 +10,28 @@ pub mod reply_description;
 pub use contents::NoteContents;
 pub use context::{NoteContextButton, NoteContextSelection};
 pub use options::NoteOptions;
-pub use post::{PostAction, PostResponse, PostType, PostView};
+pub use post::{PostAction, PostPreview, PostResponse, PostStatus, PostType, PostView};
 pub use quote_repost::QuoteRepostView;
 pub use reply::PostReplyView;
 pub use reply_description::reply_desc;
 
 use crate::{
     actionbar::NoteAction,
+    deletions::{self, Deleted},
+    highlights,
+    moderation::{self, ModerationAction, ReportTarget},
     profile::get_display_name,
-    ui::{self, View},
+    publish, reposts, selection,
+    ui::{self, density::Density, View},
 };
 
 use egui::emath::{pos2, Vec2};
-use egui::{Id, Label, Pos2, Rect, Response, RichText, Sense};
+use egui::{Id, Label, PopupCloseBehavior, Pos2, Rect, Response, RichText, Sense};
 use enostr::{NoteId, Pubkey};
 use nostrdb::{Ndb, Note, NoteKey, Transaction};
-use notedeck::{CachedNote, ImageCache, NoteCache, NotedeckTextStyle};
+use notedeck::{repost, tr, CachedNote, ImageCache, NoteCache, NotedeckTextStyle};
 
+use super::profile::card::profile_card_popup;
 use super::profile::preview::one_line_display_name_widget;
 
 pub struct NoteView<'a> {
@@ -205,7 +211,7 @@ impl<'a> NoteView<'a> {
             ui.spacing_mut().item_spacing.x = 4.0;
         }
 
-        let pfp_size = self.options().pfp_size();
+        let pfp_size = self.options().pfp_size(Density::get(ui.ctx()));
 
         let sense = Sense::click();
         match profile
@@ -241,6 +247,7 @@ impl<'a> NoteView<'a> {
                     ui::show_pointer(ui);
                 }
 
+                ui::a11y::button(ui, &resp, "Open profile");
                 resp
             }
             None => ui
@@ -253,11 +260,18 @@ impl<'a> NoteView<'a> {
     }
 
     pub fn show(&mut self, ui: &mut egui::Ui) -> NoteResponse {
+        if let Some(response) = self.deleted_ui(ui) {
+            return response;
+        }
+        if let Some(response) = self.moderated_ui(ui) {
+            return response;
+        }
+
         if self.options().has_textmode() {
             NoteResponse::new(self.textmode_ui(ui))
         } else {
             let txn = self.note.txn().expect("txn");
-            if let Some(note_to_repost) = get_reposted_note(self.ndb, txn, self.note) {
+            if repost::is_repost(self.note) {
                 let profile = self.ndb.get_profile_by_pubkey(txn, self.note.pubkey());
 
                 let style = NotedeckTextStyle::Small;
@@ -281,21 +295,96 @@ impl<'a> NoteView<'a> {
                     let color = ui.style().visuals.noninteractive().fg_stroke.color;
                     ui.add_space(4.0);
                     ui.label(
-                        RichText::new("Reposted")
+                        RichText::new(tr!("note-reposted"))
                             .color(color)
                             .text_style(style.text_style()),
                     );
                 });
-                NoteView::new(self.ndb, self.note_cache, self.img_cache, &note_to_repost).show(ui)
+                match get_reposted_note(self.ndb, txn, self.note) {
+                    Some(reposted) => {
+                        NoteView::new(self.ndb, self.note_cache, self.img_cache, &reposted).show(ui)
+                    }
+                    // asked for with the unknown ids
+                    None => NoteResponse::new(ui.weak(tr!("note-repost-loading"))),
+                }
             } else {
-                self.show_standard(ui)
+                let response = self.show_standard(ui);
+                self.highlight_selected(ui, response.response.rect);
+                response
+            }
+        }
+    }
+
+    /// Mark the note the user opened, in whichever column it shows up
+    fn highlight_selected(&self, ui: &mut egui::Ui, rect: Rect) {
+        if selection::selected_note(ui.ctx()).map(|id| *id.bytes()) != Some(*self.note.id()) {
+            return;
+        }
+
+        let color = ui.visuals().selection.stroke.color;
+        let painter = ui.painter();
+        painter.rect_filled(rect, 0.0, color.gamma_multiply(0.08));
+        painter.rect_filled(
+            Rect::from_min_size(rect.min, Vec2::new(3.0, rect.height())),
+            0.0,
+            color,
+        );
+    }
+
+    /// Notes their authors deleted are gone, or a tombstone for the
+    /// author's own if they want one
+    fn deleted_ui(&mut self, ui: &mut egui::Ui) -> Option<NoteResponse> {
+        let response = match deletions::tombstones(ui.ctx())?.deleted(self.note)? {
+            Deleted::Hide => ui.allocate_response(Vec2::ZERO, Sense::hover()),
+            Deleted::Tombstone => {
+                egui::Frame::none()
+                    .inner_margin(egui::Margin::symmetric(8.0, 12.0))
+                    .show(ui, |ui| ui.weak(tr!("note-deleted")))
+                    .response
             }
+        };
+
+        Some(NoteResponse::new(response))
+    }
+
+    /// Notes labeled by moderators the user trusts are hidden, or blurred
+    /// until the user asks to see them
+    fn moderated_ui(&mut self, ui: &mut egui::Ui) -> Option<NoteResponse> {
+        let labels = moderation::labels(ui.ctx())?;
+        let label = labels.label(self.note)?;
+
+        let revealed_id = Id::new(("moderation-revealed", self.note.id()));
+        if ui.ctx().data(|d| d.get_temp::<bool>(revealed_id)) == Some(true) {
+            return None;
         }
+
+        let response = match labels.action {
+            ModerationAction::Off => return None,
+            ModerationAction::Hide => ui.allocate_response(Vec2::ZERO, Sense::hover()),
+            ModerationAction::Blur => {
+                egui::Frame::none()
+                    .inner_margin(egui::Margin::symmetric(8.0, 12.0))
+                    .show(ui, |ui| {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.weak(tr!("moderation-labeled", label = label));
+                            if ui.small_button(tr!("moderation-show")).clicked() {
+                                ui.ctx().data_mut(|d| d.insert_temp(revealed_id, true));
+                            }
+                        });
+                    })
+                    .response
+            }
+        };
+
+        Some(NoteResponse::new(response))
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn note_header(
         ui: &mut egui::Ui,
+        ndb: &Ndb,
         note_cache: &mut NoteCache,
+        img_cache: &mut ImageCache,
         note: &Note,
         profile: &Result<nostrdb::ProfileRecord<'_>, nostrdb::Error>,
         options: NoteOptions,
@@ -306,13 +395,31 @@ impl<'a> NoteView<'a> {
 
         let note_key = note.key().unwrap();
 
+        let mut open_profile = None;
         let inner_response = ui.horizontal(|ui| {
             ui.spacing_mut().item_spacing.x = 2.0;
-            ui.add(ui::Username::new(profile.as_ref().ok(), note.pubkey()).abbreviated(20));
+            let name = ui
+                .add(ui::Username::new(profile.as_ref().ok(), note.pubkey()).abbreviated(20))
+                .interact(Sense::click());
+            if name.hovered() {
+                ui::show_pointer(ui);
+            }
+            let nip05 = get_display_name(profile.as_ref().ok()).nip05;
+            if let Some(nip05) = nip05 {
+                ui::nip05::badge(ui, note.pubkey(), nip05, 12.0);
+            }
+            if let Some(txn) = note.txn() {
+                open_profile = profile_card_popup(ui, &name, ndb, txn, img_cache, note.pubkey());
+            }
 
             let cached_note = note_cache.cached_note_or_insert_mut(note_key, note);
             render_reltime(ui, cached_note, true);
 
+            if publish::is_pending(ui.ctx(), note.id()) {
+                ui.weak(tr!("note-pending"))
+                    .on_hover_text(tr!("note-pending-hint"));
+            }
+
             if options.has_options_button() {
                 let context_pos = {
                     let size = NoteContextButton::max_width();
@@ -327,7 +434,9 @@ impl<'a> NoteView<'a> {
             }
         });
 
-        NoteResponse::new(inner_response.response).select_option(inner_response.inner)
+        NoteResponse::new(inner_response.response)
+            .with_action(open_profile.map(NoteAction::OpenProfile))
+            .select_option(inner_response.inner)
     }
 
     fn show_standard(&mut self, ui: &mut egui::Ui) -> NoteResponse {
@@ -341,7 +450,10 @@ impl<'a> NoteView<'a> {
 
         let hitbox_id = note_hitbox_id(note_key, self.options(), self.parent);
         let profile = self.ndb.get_profile_by_pubkey(txn, self.note.pubkey());
-        let maybe_hitbox = maybe_note_hitbox(ui, hitbox_id);
+        let maybe_hitbox = maybe_note_hitbox(ui, hitbox_id, || {
+            let name = get_display_name(profile.as_ref().ok()).name();
+            note_description(name, self.note.content())
+        });
         let container_right = {
             let r = ui.available_rect_before_wrap();
             let x = r.max.x;
@@ -360,17 +472,23 @@ impl<'a> NoteView<'a> {
 
                     let size = ui.available_size();
                     ui.vertical(|ui| {
-                        ui.add_sized([size.x, self.options().pfp_size()], |ui: &mut egui::Ui| {
+                        let pfp_size = self.options().pfp_size(Density::get(ui.ctx()));
+                        ui.add_sized([size.x, pfp_size], |ui: &mut egui::Ui| {
                             ui.horizontal_centered(|ui| {
-                                selected_option = NoteView::note_header(
+                                let header = NoteView::note_header(
                                     ui,
+                                    self.ndb,
                                     self.note_cache,
+                                    self.img_cache,
                                     self.note,
                                     &profile,
                                     self.options(),
                                     container_right,
-                                )
-                                .context_selection;
+                                );
+                                selected_option = header.context_selection;
+                                if header.action.is_some() {
+                                    note_action = header.action;
+                                }
                             })
                             .response
                         });
@@ -419,8 +537,7 @@ impl<'a> NoteView<'a> {
                 }
 
                 if self.options().has_actionbar() {
-                    if let Some(action) = render_note_actionbar(ui, self.note.id(), note_key).inner
-                    {
+                    if let Some(action) = render_note_actionbar(ui, self.note, note_key).inner {
                         note_action = Some(action);
                     }
                 }
@@ -434,15 +551,20 @@ impl<'a> NoteView<'a> {
                 };
 
                 ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
-                    selected_option = NoteView::note_header(
+                    let header = NoteView::note_header(
                         ui,
+                        self.ndb,
                         self.note_cache,
+                        self.img_cache,
                         self.note,
                         &profile,
                         self.options(),
                         container_right,
-                    )
-                    .context_selection;
+                    );
+                    selected_option = header.context_selection;
+                    if header.action.is_some() {
+                        note_action = header.action;
+                    }
                     ui.horizontal(|ui| {
                         ui.spacing_mut().item_spacing.x = 2.0;
 
@@ -484,9 +606,7 @@ impl<'a> NoteView<'a> {
                     }
 
                     if self.options().has_actionbar() {
-                        if let Some(action) =
-                            render_note_actionbar(ui, self.note.id(), note_key).inner
-                        {
+                        if let Some(action) = render_note_actionbar(ui, self.note, note_key).inner {
                             note_action = Some(action);
                         }
                     }
@@ -497,6 +617,20 @@ impl<'a> NoteView<'a> {
 
         let note_action = if note_hitbox_clicked(ui, hitbox_id, &response.rect, maybe_hitbox) {
             Some(NoteAction::OpenThread(NoteId::new(*self.note.id())))
+        } else if let Some(NoteContextSelection::ExportImage) = selected_option {
+            Some(NoteAction::ExportImage(NoteId::new(*self.note.id())))
+        } else if let Some(NoteContextSelection::ViewSource) = selected_option {
+            Some(NoteAction::ViewSource(NoteId::new(*self.note.id())))
+        } else if let Some(NoteContextSelection::Highlight) = selected_option {
+            Some(NoteAction::Highlight(NoteId::new(*self.note.id())))
+        } else if let Some(NoteContextSelection::Bookmark) = selected_option {
+            Some(NoteAction::Bookmark(NoteId::new(*self.note.id())))
+        } else if let Some(NoteContextSelection::Hide) = selected_option {
+            Some(NoteAction::Hide(NoteId::new(*self.note.id())))
+        } else if let Some(NoteContextSelection::HideAuthor) = selected_option {
+            Some(NoteAction::HideAuthor(Pubkey::new(*self.note.pubkey())))
+        } else if let Some(NoteContextSelection::Report) = selected_option {
+            Some(NoteAction::Report(ReportTarget::note(self.note)))
         } else {
             note_action
         };
@@ -508,27 +642,8 @@ impl<'a> NoteView<'a> {
 }
 
 fn get_reposted_note<'a>(ndb: &Ndb, txn: &'a Transaction, note: &Note) -> Option<Note<'a>> {
-    let new_note_id: &[u8; 32] = if note.kind() == 6 {
-        let mut res = None;
-        for tag in note.tags().iter() {
-            if tag.count() == 0 {
-                continue;
-            }
-
-            if let Some("e") = tag.get(0).and_then(|t| t.variant().str()) {
-                if let Some(note_id) = tag.get(1).and_then(|f| f.variant().id()) {
-                    res = Some(note_id);
-                    break;
-                }
-            }
-        }
-        res?
-    } else {
-        return None;
-    };
-
-    let note = ndb.get_note_by_id(txn, new_note_id).ok();
-    note.filter(|note| note.kind() == 1)
+    let reposted_id = repost::reposted_id(note)?;
+    ndb.get_note_by_id(txn, &reposted_id).ok()
 }
 
 fn note_hitbox_id(
@@ -539,7 +654,22 @@ fn note_hitbox_id(
     Id::new(("note_size", note_key, note_options, parent))
 }
 
-fn maybe_note_hitbox(ui: &mut egui::Ui, hitbox_id: egui::Id) -> Option<Response> {
+/// What screen readers announce for a note
+fn note_description(name: &str, content: &str) -> String {
+    let max_chars = 200;
+    let mut description = format!("Note by {name}: ");
+    description.extend(content.chars().take(max_chars));
+    if content.chars().count() > max_chars {
+        description.push('…');
+    }
+    description
+}
+
+fn maybe_note_hitbox(
+    ui: &mut egui::Ui,
+    hitbox_id: egui::Id,
+    description: impl FnOnce() -> String,
+) -> Option<Response> {
     ui.ctx()
         .data_mut(|d| d.get_persisted(hitbox_id))
         .map(|note_size: Vec2| {
@@ -553,8 +683,10 @@ fn maybe_note_hitbox(ui: &mut egui::Ui, hitbox_id: egui::Id) -> Option<Response>
 
             let response = ui.interact(rect, ui.id().with(hitbox_id), egui::Sense::click());
 
-            response
-                .widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Other, true, "hitbox"));
+            // the hitbox comes before the note contents, so keyboard focus
+            // lands on the note itself before its links and buttons
+            ui::a11y::label(&response, egui::WidgetType::Other, &description());
+            ui::a11y::focus_outline(ui, &response);
 
             response
         })
@@ -581,29 +713,95 @@ fn note_hitbox_clicked(
 
 fn render_note_actionbar(
     ui: &mut egui::Ui,
-    note_id: &[u8; 32],
+    note: &Note,
     note_key: NoteKey,
 ) -> egui::InnerResponse<Option<NoteAction>> {
     #[cfg(feature = "profiling")]
     puffin::profile_function!();
 
+    let note_id = note.id();
     ui.horizontal(|ui| {
         let reply_resp = reply_button(ui, note_key);
-        let quote_resp = quote_repost_button(ui, note_key);
+
+        let reposted = reposts::undoable(ui.ctx()).is_some_and(|recent| recent.contains(note_id));
+        let repost_resp = repost_button(ui, note_key, reposted);
+        let repost_action = repost_menu(ui, &repost_resp, NoteId::new(*note_id), reposted);
+
+        let highlights = highlights::count(ui.ctx(), note);
+        let highlights_clicked = highlights > 0 && {
+            ui.add_space(6.0);
+            let resp = ui.add(
+                Label::new(
+                    RichText::new(tr!("note-highlights", count = highlights))
+                        .small()
+                        .weak(),
+                )
+                .sense(Sense::click()),
+            );
+            if resp.hovered() {
+                ui::show_pointer(ui);
+            }
+            resp.clicked()
+        };
+
+        if let Some(client) = notedeck::note::note_client(note) {
+            ui.add_space(6.0);
+            ui.add(Label::new(
+                RichText::new(tr!("note-client", client = client))
+                    .small()
+                    .weak(),
+            ))
+            .on_hover_text(tr!("note-client-hint"));
+        }
 
         if reply_resp.clicked() {
             Some(NoteAction::Reply(NoteId::new(*note_id)))
-        } else if quote_resp.clicked() {
-            Some(NoteAction::Quote(NoteId::new(*note_id)))
+        } else if highlights_clicked {
+            Some(NoteAction::Highlights(NoteId::new(*note_id)))
         } else {
-            None
+            repost_action
         }
     })
 }
 
-fn secondary_label(ui: &mut egui::Ui, s: impl Into<String>) {
+/// Repost or quote a note. A repost we just made can be taken back from
+/// here for a little while.
+fn repost_menu(
+    ui: &mut egui::Ui,
+    resp: &Response,
+    note_id: NoteId,
+    reposted: bool,
+) -> Option<NoteAction> {
+    let popup_id = resp.id.with("repost-menu");
+    if resp.clicked() {
+        ui.memory_mut(|m| m.toggle_popup(popup_id));
+    }
+
+    egui::popup_below_widget(ui, popup_id, resp, PopupCloseBehavior::CloseOnClick, |ui| {
+        ui.set_min_width(120.0);
+
+        let repost = if reposted {
+            ui.button(tr!("note-undo-repost"))
+                .clicked()
+                .then_some(NoteAction::UndoRepost(note_id))
+        } else {
+            ui.button(tr!("note-repost"))
+                .clicked()
+                .then_some(NoteAction::Repost(note_id))
+        };
+        let quote = ui
+            .button(tr!("note-quote"))
+            .clicked()
+            .then_some(NoteAction::Quote(note_id));
+
+        repost.or(quote)
+    })
+    .flatten()
+}
+
+fn secondary_label(ui: &mut egui::Ui, s: impl Into<String>) -> egui::Response {
     let color = ui.style().visuals.noninteractive().fg_stroke.color;
-    ui.add(Label::new(RichText::new(s).size(10.0).color(color)));
+    ui.add(Label::new(RichText::new(s).size(10.0).color(color)))
 }
 
 fn render_reltime(
@@ -619,7 +817,22 @@ fn render_reltime(
             secondary_label(ui, "⋅");
         }
 
-        secondary_label(ui, note_cache.reltime_str_mut());
+        let created_at = note_cache.created_at;
+        secondary_label(ui, note_cache.reltime_str_mut())
+            .on_hover_text(notedeck::datetime::settings().format_datetime(created_at));
+
+        if let Some(expiration) = note_cache.expiration {
+            secondary_label(ui, "⋅");
+            if note_cache.is_expired() {
+                secondary_label(ui, "expired");
+            } else {
+                let expires_in = notedeck::time_ago_since(expiration);
+                secondary_label(
+                    ui,
+                    format!("expires in {}", expires_in.trim_start_matches('+')),
+                );
+            }
+        }
 
         if !before {
             secondary_label(ui, "⋅");
@@ -642,6 +855,7 @@ fn reply_button(ui: &mut egui::Ui, note_key: NoteKey) -> egui::Response {
     let rect = rect.translate(egui::vec2(-(expand_size / 2.0), 0.0));
 
     let put_resp = ui.put(rect, egui::Image::new(img_data).max_width(size));
+    ui::a11y::button(ui, &resp, "Reply");
 
     resp.union(put_resp)
 }
@@ -655,7 +869,7 @@ fn repost_icon(dark_mode: bool) -> egui::Image<'static> {
     egui::Image::new(img_data)
 }
 
-fn quote_repost_button(ui: &mut egui::Ui, note_key: NoteKey) -> egui::Response {
+fn repost_button(ui: &mut egui::Ui, note_key: NoteKey, reposted: bool) -> egui::Response {
     let size = 14.0;
     let expand_size = 5.0;
     let anim_speed = 0.05;
@@ -665,7 +879,12 @@ fn quote_repost_button(ui: &mut egui::Ui, note_key: NoteKey) -> egui::Response {
 
     let rect = rect.translate(egui::vec2(-(expand_size / 2.0), -1.0));
 
-    let put_resp = ui.put(rect, repost_icon(ui.visuals().dark_mode).max_width(size));
+    let mut icon = repost_icon(ui.visuals().dark_mode).max_width(size);
+    if reposted {
+        icon = icon.tint(crate::colors::TEAL);
+    }
+    let put_resp = ui.put(rect, icon);
+    ui::a11y::button(ui, &resp, "Repost");
 
     resp.union(put_resp)
 }
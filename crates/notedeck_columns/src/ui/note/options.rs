@@ -1,4 +1,4 @@
-use crate::ui::ProfilePic;
+use crate::ui::{density::Density, ProfilePic};
 use bitflags::bitflags;
 
 bitflags! {
@@ -59,13 +59,14 @@ impl NoteOptions {
         options
     }
 
-    pub fn pfp_size(&self) -> f32 {
+    /// Notes asking for a small picture keep it whatever the density
+    pub fn pfp_size(&self, density: Density) -> f32 {
         if self.has_small_pfp() {
             ProfilePic::small_size()
         } else if self.has_medium_pfp() {
             ProfilePic::medium_size()
         } else {
-            ProfilePic::default_size()
+            density.pfp_size()
         }
     }
 }
@@ -1,16 +1,34 @@
+use crate::blossom;
 use crate::draft::{Draft, Drafts};
-use crate::post::NewPost;
-use crate::ui::{self, Preview, PreviewConfig};
+use crate::geo;
+use crate::gifs;
+use crate::images;
+use crate::post::{reply_mentions, NewPost};
+use crate::profile::get_display_name;
+use crate::relay_groups;
+use crate::ui::{self, emoji_picker::EmojiPicker, Preview, PreviewConfig};
 use crate::Result;
+use egui::text::{CCursor, CCursorRange};
 use egui::widgets::text_edit::TextEdit;
 use egui::{Frame, Layout};
-use enostr::{FilledKeypair, FullKeypair, NoteId, RelayPool};
-use nostrdb::{Ndb, Transaction};
+use enostr::{ClientMessage, FilledKeypair, FullKeypair, NoteId, RelayPool, RelayTarget};
+use nostrdb::{Ndb, Note, Transaction};
 
-use notedeck::{ImageCache, NoteCache};
+use notedeck::{
+    note::tag_strings, time::unix_now, toast, tr, ImageCache, NoteCache, PowMiner, PowPoll,
+    UnsignedNote,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::error;
 
 use super::contents::render_note_preview;
 
+/// Replies to notes older than this get a heads up
+const OLD_REPLY_AGE: u64 = 60 * 60 * 24 * 90;
+
 pub struct PostView<'a> {
     ndb: &'a Ndb,
     draft: &'a mut Draft,
@@ -30,44 +48,171 @@ pub enum PostType {
 
 pub struct PostAction {
     post_type: PostType,
-    post: NewPost,
+    post: PendingPost,
+}
+
+enum PendingPost {
+    /// A post that still needs to be built and signed. If `pow` is
+    /// nonzero we mine for that difficulty before publishing.
+    Compose { post: NewPost, pow: u8 },
+
+    /// A post that's already signed and ready to go, after mining
+    /// proof-of-work or being previewed
+    Signed { id: [u8; 32], msg: ClientMessage },
+}
+
+/// A draft signed ahead of time so its JSON can be checked. Posting sends
+/// this exact event, as long as the draft hasn't changed since.
+pub struct PostPreview {
+    inputs: u64,
+    pub json: String,
+    pub tags: Vec<(String, String)>,
+    action: PostAction,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PostStatus {
+    /// Sent to relays, with the id of the note so we can follow how it's
+    /// received
+    Published([u8; 32]),
+    /// The post is mining proof-of-work in the background. The composer
+    /// will hand us a [`PostAction`] once it's done.
+    Mining,
 }
 
 impl PostAction {
     pub fn new(post_type: PostType, post: NewPost) -> Self {
-        PostAction { post_type, post }
+        PostAction {
+            post_type,
+            post: PendingPost::Compose { post, pow: 0 },
+        }
     }
 
+    pub fn signed(post_type: PostType, note: Note<'_>) -> Result<Self> {
+        Ok(PostAction {
+            post_type,
+            post: PendingPost::Signed {
+                id: *note.id(),
+                msg: ClientMessage::event(note)?,
+            },
+        })
+    }
+
+    /// Mine NIP-13 proof-of-work for the given difficulty before publishing
+    pub fn pow(mut self, difficulty: u8) -> Self {
+        if let PendingPost::Compose { ref mut pow, .. } = self.post {
+            *pow = difficulty;
+        }
+        self
+    }
+
+    pub fn post_type(&self) -> &PostType {
+        &self.post_type
+    }
+
+    /// Publish to the relays in `target`
     pub fn execute(
         &self,
         ndb: &Ndb,
         txn: &Transaction,
         pool: &mut RelayPool,
+        target: &RelayTarget,
         drafts: &mut Drafts,
-    ) -> Result<()> {
-        let seckey = self.post.account.secret_key.to_secret_bytes();
+    ) -> Result<PostStatus> {
+        let (post, pow) = match &self.post {
+            PendingPost::Signed { id, msg } => {
+                pool.send_targeted(msg, target);
+                drafts.get_from_post_type(&self.post_type).clear();
+                return Ok(PostStatus::Published(*id));
+            }
+            PendingPost::Compose { post, pow } => (post, *pow),
+        };
 
-        let note = match self.post_type {
-            PostType::New => self.post.to_note(&seckey),
+        let note = sign_post(&self.post_type, post, ndb, txn)?;
+        let draft = drafts.get_from_post_type(&self.post_type);
 
-            PostType::Reply(target) => {
-                let replying_to = ndb.get_note_by_id(txn, target.bytes())?;
-                self.post.to_reply(&seckey, &replying_to)
-            }
+        if pow > 0 {
+            draft.miner = Some(PowMiner::start(UnsignedNote::from_note(&note), pow));
+            return Ok(PostStatus::Mining);
+        }
 
-            PostType::Quote(target) => {
-                let quoting = ndb.get_note_by_id(txn, target.bytes())?;
-                self.post.to_quote(&seckey, &quoting)
-            }
-        };
+        let id = *note.id();
+        pool.send_targeted(&ClientMessage::event(note)?, target);
+        draft.clear();
+
+        Ok(PostStatus::Published(id))
+    }
+}
+
+/// Build and sign a post the way it'll be published
+fn sign_post<'a>(
+    post_type: &PostType,
+    post: &'a NewPost,
+    ndb: &Ndb,
+    txn: &Transaction,
+) -> Result<Note<'a>> {
+    let seckey = post.account.secret_key.to_secret_bytes();
 
-        pool.send(&enostr::ClientMessage::event(note)?);
-        drafts.get_from_post_type(&self.post_type).clear();
+    Ok(match post_type {
+        PostType::New => post.to_note(&seckey),
 
-        Ok(())
+        PostType::Reply(target) => {
+            let replying_to = ndb.get_note_by_id(txn, target.bytes())?;
+            post.to_reply(&seckey, &replying_to)
+        }
+
+        PostType::Quote(target) => {
+            let quoting = ndb.get_note_by_id(txn, target.bytes())?;
+            post.to_quote(&seckey, &quoting)
+        }
+    })
+}
+
+impl PostPreview {
+    fn new(
+        inputs: u64,
+        post_type: PostType,
+        post: &NewPost,
+        ndb: &Ndb,
+        txn: &Transaction,
+    ) -> Result<Self> {
+        let note = sign_post(&post_type, post, ndb, txn)?;
+        let json = serde_json::from_str::<serde_json::Value>(&note.json()?)
+            .and_then(|value| serde_json::to_string_pretty(&value))
+            .map_err(|err| crate::Error::Generic(err.to_string()))?;
+        let tags = tag_strings(&note)
+            .into_iter()
+            .filter_map(|tag| {
+                let description = tag_description(&tag[0], tag.get(3).map(String::as_str))?;
+                Some((tag[0].clone(), description))
+            })
+            .collect();
+
+        Ok(PostPreview {
+            inputs,
+            json,
+            tags,
+            action: PostAction::signed(post_type, note)?,
+        })
     }
 }
 
+/// What a tag the composer adds is for
+fn tag_description(name: &str, marker: Option<&str>) -> Option<String> {
+    Some(match (name, marker) {
+        ("e", Some("root")) => tr!("preview-tag-root"),
+        ("e", Some("reply")) => tr!("preview-tag-reply"),
+        ("e", _) | ("q", _) => tr!("preview-tag-quote"),
+        ("p", _) => tr!("preview-tag-p"),
+        ("t", _) => tr!("preview-tag-t"),
+        ("client", _) => tr!("preview-tag-client"),
+        ("expiration", _) => tr!("preview-tag-expiration"),
+        ("location", _) | ("g", _) => tr!("preview-tag-location"),
+        ("nonce", _) => tr!("preview-tag-nonce"),
+        _ => return None,
+    })
+}
+
 pub struct PostResponse {
     pub action: Option<PostAction>,
     pub edit_response: egui::Response,
@@ -120,12 +265,24 @@ impl<'a> PostView<'a> {
             );
         }
 
-        let response = ui.add_sized(
-            ui.available_size(),
-            TextEdit::multiline(&mut self.draft.buffer)
+        let text_id = self.text_id();
+        let interactive = !self.draft.is_mining();
+        let mut corrected = false;
+        let buffer = &mut self.draft.buffer;
+        let response = ui.add_sized(ui.available_size(), |ui: &mut egui::Ui| {
+            let output = TextEdit::multiline(buffer)
+                .id(text_id)
                 .hint_text(egui::RichText::new("Write a banger note here...").weak())
-                .frame(false),
-        );
+                .interactive(interactive)
+                .frame(false)
+                .show(ui);
+            corrected = notedeck::ui::spellcheck(ui, &output, buffer);
+            output.response
+        });
+
+        if response.changed() || corrected {
+            self.draft.dirty = true;
+        }
 
         let focused = response.has_focus();
 
@@ -134,6 +291,568 @@ impl<'a> PostView<'a> {
         response
     }
 
+    /// Who a reply will notify, as chips that can be taken off, and a
+    /// heads up when the note being replied to is old
+    fn reply_ui(&mut self, txn: &Transaction, ui: &mut egui::Ui) {
+        let PostType::Reply(target) = &self.post_type else {
+            return;
+        };
+        let Ok(replying_to) = self.ndb.get_note_by_id(txn, target.bytes()) else {
+            return;
+        };
+
+        let age = unix_now().saturating_sub(replying_to.created_at());
+        if age >= OLD_REPLY_AGE {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                tr!(
+                    "reply-old-note",
+                    age = notedeck::time_ago_since(replying_to.created_at())
+                ),
+            );
+        }
+
+        let mentions = reply_mentions(&replying_to);
+        let mut toggled = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(4.0, 4.0);
+            ui.label(egui::RichText::new(tr!("reply-mentions")).weak().small());
+
+            for pk in &mentions {
+                let profile = self.ndb.get_profile_by_pubkey(txn, pk).ok();
+                let name = get_display_name(profile.as_ref()).name().to_owned();
+                let removed = self.draft.unmentioned.contains(pk);
+
+                let text = if removed {
+                    egui::RichText::new(name).small().strikethrough().weak()
+                } else {
+                    egui::RichText::new(format!("{name} ✕")).small()
+                };
+                let hint = if removed {
+                    tr!("reply-mention-add")
+                } else {
+                    tr!("reply-mention-remove")
+                };
+                if ui
+                    .add(egui::Button::new(text).rounding(8.0))
+                    .on_hover_text(hint)
+                    .clicked()
+                {
+                    toggled = Some(*pk);
+                }
+            }
+        });
+
+        if let Some(pk) = toggled {
+            if !self.draft.unmentioned.remove(&pk) {
+                self.draft.unmentioned.insert(pk);
+            }
+            self.draft.dirty = true;
+        }
+    }
+
+    /// Drafts are saved as you type. Let people know when one came back
+    /// from last time, and give them a way to throw it away.
+    fn draft_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if self.draft.restored {
+                ui.label(egui::RichText::new(tr!("draft-restored")).weak().small());
+            }
+
+            if ui.small_button(tr!("draft-discard")).clicked() {
+                self.draft.clear();
+            }
+        });
+    }
+
+    /// Uploads in progress, and what went wrong with the last one
+    fn uploads_ui(&mut self, ui: &mut egui::Ui) {
+        let dragging = ui.ctx().input(|i| !i.raw.hovered_files.is_empty());
+        if !dragging && !self.draft.is_uploading() && self.draft.upload_error.is_none() {
+            return;
+        }
+
+        let mut cancel = None;
+        for (i, upload) in self.draft.uploads.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui
+                    .small_button("✕")
+                    .on_hover_text(tr!("upload-cancel"))
+                    .clicked()
+                {
+                    cancel = Some(i);
+                }
+                ui.add(
+                    egui::ProgressBar::new(upload.progress())
+                        .desired_width(120.0)
+                        .animate(true),
+                );
+                ui.weak(tr!(
+                    "upload-file",
+                    name = upload.name.as_str(),
+                    size = format_size(upload.size)
+                ));
+            });
+        }
+        if let Some(i) = cancel {
+            self.draft.cancel_upload(i);
+        }
+
+        ui.horizontal(|ui| {
+            if self.draft.is_uploading() {
+                ui.weak(tr!("upload-working", count = self.draft.uploads.len()));
+            } else if dragging {
+                ui.weak(tr!("upload-drop-hint"));
+            } else if let Some(err) = &self.draft.upload_error {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    tr!("upload-failed", error = err.as_str()),
+                );
+                if ui.small_button(tr!("upload-dismiss")).clicked() {
+                    self.draft.upload_error = None;
+                }
+            }
+        });
+    }
+
+    fn location_toggle(&self, ui: &mut egui::Ui) {
+        let id = self.id().with("location");
+        let open = ui.ctx().data(|d| d.get_temp::<bool>(id).unwrap_or(false));
+        let active = open || self.draft.location.is_some();
+
+        if ui
+            .selectable_label(active, "📍")
+            .on_hover_text(tr!("location-add"))
+            .clicked()
+        {
+            ui.ctx().data_mut(|d| d.insert_temp(id, !open));
+        }
+    }
+
+    /// Search for a place to tag the note with, or show the one picked
+    fn location_ui(&mut self, ui: &mut egui::Ui) {
+        if let Some(place) = &self.draft.location {
+            let mut remove = false;
+            ui.horizontal(|ui| {
+                ui.label(format!("📍 {}", place.name));
+                remove = ui.small_button(tr!("location-remove")).clicked();
+            });
+            if remove {
+                self.draft.location = None;
+                self.draft.dirty = true;
+            }
+            return;
+        }
+
+        let id = self.id().with("location");
+        if !ui.ctx().data(|d| d.get_temp::<bool>(id).unwrap_or(false)) {
+            return;
+        }
+
+        let query_id = id.with("query");
+        let mut query = ui
+            .ctx()
+            .data_mut(|d| d.get_temp::<String>(query_id).unwrap_or_default());
+
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                TextEdit::singleline(&mut query)
+                    .hint_text(tr!("location-search-hint"))
+                    .desired_width(200.0),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+            if (ui.small_button(tr!("location-search")).clicked() || submitted)
+                && !query.trim().is_empty()
+            {
+                let promise = geo::geocode(ui.ctx(), query.trim());
+                self.draft.location_search = Some((query.trim().to_owned(), promise));
+            }
+        });
+        ui.ctx().data_mut(|d| d.insert_temp(query_id, query));
+
+        let Some((searched, search)) = &self.draft.location_search else {
+            return;
+        };
+
+        let mut picked = None;
+        match search.ready() {
+            None => {
+                ui.spinner();
+            }
+            Some(Err(err)) => {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    tr!("location-search-failed", error = err.as_str()),
+                );
+            }
+            Some(Ok(places)) if places.is_empty() => {
+                ui.weak(tr!("location-no-results", query = searched.as_str()));
+            }
+            Some(Ok(places)) => {
+                for place in places {
+                    if ui.selectable_label(false, &place.name).clicked() {
+                        picked = Some(place.clone());
+                    }
+                }
+            }
+        }
+
+        if let Some(place) = picked {
+            self.draft.location = Some(place);
+            self.draft.location_search = None;
+            self.draft.dirty = true;
+            ui.ctx().data_mut(|d| d.insert_temp(id, false));
+        }
+    }
+
+    fn new_post(&self) -> NewPost {
+        NewPost::new(self.draft.buffer.clone(), self.poster.to_full())
+            .expires_at(self.draft.expires_in.map(|secs| unix_now() + secs))
+            .location(self.draft.location.clone())
+            .without_mentions(self.draft.unmentioned.clone())
+    }
+
+    /// Everything that goes into the signed post, so we know when a
+    /// preview is out of date
+    fn inputs(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.draft.buffer.hash(&mut hasher);
+        self.draft.expires_in.hash(&mut hasher);
+        self.draft
+            .location
+            .as_ref()
+            .map(|place| &place.name)
+            .hash(&mut hasher);
+        self.draft.pow.hash(&mut hasher);
+        crate::post::client_tag().hash(&mut hasher);
+        let mut unmentioned: Vec<&[u8; 32]> = self.draft.unmentioned.iter().collect();
+        unmentioned.sort();
+        unmentioned.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The signed event as it'll be published, for checking its tags
+    /// before sending. Nothing is sent from here.
+    fn json_preview_ui(&mut self, txn: &Transaction, ui: &mut egui::Ui) {
+        let header = egui::CollapsingHeader::new(tr!("preview-json"))
+            .id_salt(self.id().with("json-preview"))
+            .show(ui, |ui| {
+                let inputs = self.inputs();
+                if !self
+                    .draft
+                    .preview
+                    .as_ref()
+                    .is_some_and(|preview| preview.inputs == inputs)
+                {
+                    let post = self.new_post();
+                    match PostPreview::new(inputs, self.post_type.clone(), &post, self.ndb, txn) {
+                        Ok(preview) => self.draft.preview = Some(preview),
+                        Err(err) => {
+                            ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                            return;
+                        }
+                    }
+                }
+                let Some(preview) = &self.draft.preview else {
+                    return;
+                };
+
+                if self.draft.pow > 0 {
+                    ui.weak(tr!("preview-json-pow"));
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new(&preview.json).monospace().small());
+                    });
+                if ui.small_button(tr!("preview-json-copy")).clicked() {
+                    ui.ctx().copy_text(preview.json.clone());
+                }
+
+                egui::Grid::new(self.id().with("json-preview-tags"))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (name, description) in &preview.tags {
+                            ui.monospace(name);
+                            ui.weak(description);
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        // a closed preview shouldn't decide what gets posted
+        if header.body_returned.is_none() {
+            self.draft.preview = None;
+        }
+    }
+
+    fn picker_open(&self, ui: &egui::Ui, picker: &str) -> bool {
+        let id = self.id().with(picker);
+        ui.ctx().data(|d| d.get_temp::<bool>(id).unwrap_or(false))
+    }
+
+    fn picker_toggle(&self, ui: &mut egui::Ui, picker: &str, icon: &str, hint: String) {
+        let open = self.picker_open(ui, picker);
+        if ui
+            .selectable_label(open, icon)
+            .on_hover_text(hint)
+            .clicked()
+        {
+            let id = self.id().with(picker);
+            ui.ctx().data_mut(|d| d.insert_temp(id, !open));
+        }
+    }
+
+    fn emoji_ui(&mut self, ui: &mut egui::Ui) {
+        if !self.picker_open(ui, "emoji") {
+            return;
+        }
+
+        if let Some(emoji) = EmojiPicker::new().show(ui) {
+            self.insert_text(ui, &emoji);
+        }
+    }
+
+    /// Search for a GIF and link it in the note
+    fn gif_ui(&mut self, ui: &mut egui::Ui) {
+        if !self.picker_open(ui, "gif") {
+            return;
+        }
+
+        if gifs::search_url().is_empty() {
+            ui.weak(tr!("gif-not-set-up"));
+            return;
+        }
+
+        let query_id = self.id().with("gif-query");
+        let mut query = ui
+            .ctx()
+            .data_mut(|d| d.get_temp::<String>(query_id).unwrap_or_default());
+
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                TextEdit::singleline(&mut query)
+                    .hint_text(tr!("gif-search-hint"))
+                    .desired_width(200.0),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+            if (ui.small_button(tr!("gif-search")).clicked() || submitted)
+                && !query.trim().is_empty()
+            {
+                self.draft.gif_search = Some(gifs::search(ui.ctx(), query.trim()));
+            }
+        });
+        ui.ctx().data_mut(|d| d.insert_temp(query_id, query));
+
+        let Some(search) = &self.draft.gif_search else {
+            return;
+        };
+
+        let mut picked = None;
+        match search.ready() {
+            None => {
+                ui.spinner();
+            }
+            Some(Err(err)) => {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    tr!("gif-search-failed", error = err.as_str()),
+                );
+            }
+            Some(Ok(gifs)) if gifs.is_empty() => {
+                ui.weak(tr!("gif-no-results"));
+            }
+            Some(Ok(gifs)) => {
+                egui::ScrollArea::vertical()
+                    .id_salt(self.id().with("gif-results"))
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        ui.horizontal_wrapped(|ui| {
+                            for gif in gifs {
+                                let hover_id = self.id().with(("gif-hovered", &gif.preview));
+                                let size = egui::vec2(ui.available_width(), 80.0);
+                                let Some(texture) =
+                                    images::load_texture(ui, &gif.preview, size, hover_id)
+                                else {
+                                    ui.add_sized([80.0, 80.0], egui::Spinner::new());
+                                    continue;
+                                };
+                                let image = egui::Image::new(texture)
+                                    .max_height(80.0)
+                                    .rounding(4.0)
+                                    .sense(egui::Sense::click());
+                                let resp = ui.add(image);
+                                images::set_hovered(ui.ctx(), hover_id, resp.hovered());
+                                if resp.clicked() {
+                                    picked = Some(gif.url.clone());
+                                }
+                            }
+                        });
+                    });
+            }
+        }
+
+        if let Some(url) = picked {
+            let needs_space = self
+                .draft
+                .buffer
+                .chars()
+                .last()
+                .is_some_and(|c| !c.is_whitespace());
+            let text = if needs_space {
+                format!(" {url} ")
+            } else {
+                format!("{url} ")
+            };
+            self.insert_text(ui, &text);
+            self.draft.gif_search = None;
+            let id = self.id().with("gif");
+            ui.ctx().data_mut(|d| d.insert_temp(id, false));
+        }
+    }
+
+    /// Put `text` where the cursor is, or at the end if the text box
+    /// hasn't been focused yet
+    fn insert_text(&mut self, ui: &egui::Ui, text: &str) {
+        let id = self.text_id();
+        let state = TextEdit::load_state(ui.ctx(), id);
+        let at = state
+            .as_ref()
+            .and_then(|state| state.cursor.char_range())
+            .map(|range| range.primary.index)
+            .unwrap_or_else(|| self.draft.buffer.chars().count());
+
+        let byte = self
+            .draft
+            .buffer
+            .char_indices()
+            .nth(at)
+            .map_or(self.draft.buffer.len(), |(i, _)| i);
+        self.draft.buffer.insert_str(byte, text);
+        self.draft.dirty = true;
+
+        if let Some(mut state) = state {
+            let end = CCursor::new(at + text.chars().count());
+            state.cursor.set_char_range(Some(CCursorRange::one(end)));
+            state.store(ui.ctx(), id);
+        }
+    }
+
+    /// Files dropped on the composer go up to the poster's media servers
+    fn attach_dropped_files(&mut self, txn: &Transaction, ui: &egui::Ui, rect: egui::Rect) {
+        let (dropped, pointer) = ui
+            .ctx()
+            .input(|i| (i.raw.dropped_files.clone(), i.pointer.hover_pos()));
+        if dropped.is_empty() {
+            return;
+        }
+
+        let ours = match pointer {
+            Some(pos) => rect.contains(pos),
+            None => self.focused(ui),
+        };
+        if !ours {
+            return;
+        }
+
+        for file in dropped {
+            let name = file
+                .path
+                .as_deref()
+                .and_then(file_name)
+                .unwrap_or(file.name);
+            let bytes = match (file.bytes, &file.path) {
+                (Some(bytes), _) => bytes.to_vec(),
+                (None, Some(path)) => match std::fs::read(path) {
+                    Ok(bytes) => bytes,
+                    Err(err) => {
+                        self.draft.upload_error = Some(err.to_string());
+                        continue;
+                    }
+                },
+                (None, None) => continue,
+            };
+
+            self.attach(txn, ui, name, bytes);
+        }
+    }
+
+    /// Send a file up to the poster's media servers. Its url goes into
+    /// the note once one of them has it.
+    fn attach(&mut self, txn: &Transaction, ui: &egui::Ui, name: String, bytes: Vec<u8>) {
+        let servers = blossom::server_list(self.ndb, txn, self.poster.pubkey.bytes());
+        if servers.is_empty() {
+            self.draft.upload_error = Some(tr!("upload-no-servers"));
+            return;
+        }
+
+        let content_type = blossom::content_type(&bytes);
+        self.draft.uploads.push(blossom::upload(
+            ui.ctx(),
+            servers,
+            self.poster,
+            name,
+            bytes,
+            content_type,
+        ));
+    }
+
+    /// File managers put the paths of copied files on the clipboard as
+    /// text. Pasting those into a focused composer attaches the files
+    /// instead of pasting the paths.
+    fn attach_pasted_files(&mut self, txn: &Transaction, ui: &mut egui::Ui) {
+        if !self.focused(ui) {
+            return;
+        }
+
+        let paths = ui.input_mut(|i| {
+            let mut paths = vec![];
+            i.events.retain(|event| match event {
+                egui::Event::Paste(text) => match pasted_files(text) {
+                    Some(pasted) => {
+                        paths.extend(pasted);
+                        false
+                    }
+                    None => true,
+                },
+                _ => true,
+            });
+            paths
+        });
+
+        for path in paths {
+            match std::fs::read(&path) {
+                Ok(bytes) => {
+                    let name = file_name(&path).unwrap_or_default();
+                    self.attach(txn, ui, name, bytes);
+                }
+                Err(err) => self.draft.upload_error = Some(err.to_string()),
+            }
+        }
+    }
+
+    /// Attach an image copied to the clipboard. egui only hands us pasted
+    /// text, so images come in through this button.
+    #[cfg(not(target_os = "android"))]
+    fn paste_image_button(&mut self, txn: &Transaction, ui: &mut egui::Ui) {
+        if !ui
+            .small_button("📋")
+            .on_hover_text(tr!("upload-paste-image"))
+            .clicked()
+        {
+            return;
+        }
+
+        match clipboard_png() {
+            Ok(bytes) => self.attach(txn, ui, tr!("upload-pasted-image"), bytes),
+            Err(err) => {
+                self.draft.upload_error = Some(tr!("upload-no-clipboard-image", error = err))
+            }
+        }
+    }
+
     fn focused(&self, ui: &egui::Ui) -> bool {
         ui.ctx()
             .data(|d| d.get_temp::<bool>(self.id()).unwrap_or(false))
@@ -143,6 +862,10 @@ impl<'a> PostView<'a> {
         self.id_source.unwrap_or_else(|| egui::Id::new("post"))
     }
 
+    fn text_id(&self) -> egui::Id {
+        self.id().with("text")
+    }
+
     pub fn outer_margin() -> f32 {
         16.0
     }
@@ -152,6 +875,9 @@ impl<'a> PostView<'a> {
     }
 
     pub fn ui(&mut self, txn: &nostrdb::Transaction, ui: &mut egui::Ui) -> PostResponse {
+        self.draft.poll_uploads();
+        self.attach_pasted_files(txn, ui);
+
         let focused = self.focused(ui);
         let stroke = if focused {
             ui.visuals().selection.stroke
@@ -176,63 +902,292 @@ impl<'a> PostView<'a> {
             });
         }
 
-        frame
-            .show(ui, |ui| {
-                ui.vertical(|ui| {
-                    let edit_response = ui.horizontal(|ui| self.editbox(txn, ui)).inner;
-
-                    let action = ui
-                        .horizontal(|ui| {
-                            if let PostType::Quote(id) = self.post_type {
-                                let avail_size = ui.available_size_before_wrap();
-                                ui.with_layout(Layout::left_to_right(egui::Align::TOP), |ui| {
-                                    Frame::none().show(ui, |ui| {
-                                        ui.vertical(|ui| {
-                                            ui.set_max_width(avail_size.x * 0.8);
-                                            render_note_preview(
-                                                ui,
-                                                self.ndb,
-                                                self.note_cache,
-                                                self.img_cache,
-                                                txn,
-                                                id.bytes(),
-                                                nostrdb::NoteKey::new(0),
-                                            );
-                                        });
+        let response = frame.show(ui, |ui| {
+            ui.vertical(|ui| {
+                self.reply_ui(txn, ui);
+                let edit_response = ui.horizontal(|ui| self.editbox(txn, ui)).inner;
+
+                if !self.draft.buffer.is_empty() && !self.draft.is_mining() {
+                    self.draft_ui(ui);
+                }
+
+                self.uploads_ui(ui);
+                self.location_ui(ui);
+                self.emoji_ui(ui);
+                self.gif_ui(ui);
+                if !self.draft.buffer.is_empty() && !self.draft.is_mining() {
+                    self.json_preview_ui(txn, ui);
+                }
+
+                let action = ui
+                    .horizontal(|ui| {
+                        if let PostType::Quote(id) = self.post_type {
+                            let avail_size = ui.available_size_before_wrap();
+                            ui.with_layout(Layout::left_to_right(egui::Align::TOP), |ui| {
+                                Frame::none().show(ui, |ui| {
+                                    ui.vertical(|ui| {
+                                        ui.set_max_width(avail_size.x * 0.8);
+                                        render_note_preview(
+                                            ui,
+                                            self.ndb,
+                                            self.note_cache,
+                                            self.img_cache,
+                                            txn,
+                                            id.bytes(),
+                                            nostrdb::NoteKey::new(0),
+                                        );
                                     });
                                 });
+                            });
+                        }
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::BOTTOM), |ui| {
+                            if self.draft.is_mining() {
+                                return self.mining_ui(ui);
                             }
 
-                            ui.with_layout(egui::Layout::right_to_left(egui::Align::BOTTOM), |ui| {
-                                if ui
-                                    .add_sized(
-                                        [91.0, 32.0],
-                                        post_button(!self.draft.buffer.is_empty()),
-                                    )
-                                    .clicked()
-                                {
-                                    let new_post = NewPost::new(
-                                        self.draft.buffer.clone(),
-                                        self.poster.to_full(),
-                                    );
-                                    Some(PostAction::new(self.post_type.clone(), new_post))
-                                } else {
-                                    None
+                            let action = if ui
+                                .add_sized(
+                                    [91.0, 32.0],
+                                    post_button(
+                                        !self.draft.buffer.is_empty() && !self.draft.is_uploading(),
+                                    ),
+                                )
+                                .clicked()
+                            {
+                                // what was previewed is what gets sent
+                                match self.draft.preview.take() {
+                                    Some(preview)
+                                        if preview.inputs == self.inputs()
+                                            && self.draft.pow == 0 =>
+                                    {
+                                        Some(preview.action)
+                                    }
+                                    _ => Some(
+                                        PostAction::new(self.post_type.clone(), self.new_post())
+                                            .pow(self.draft.pow),
+                                    ),
                                 }
-                            })
-                            .inner
+                            } else {
+                                None
+                            };
+
+                            let (pow, expires_in) = (self.draft.pow, self.draft.expires_in);
+                            let relay_group = self.draft.relay_group.clone();
+                            pow_setting(ui, &mut self.draft.pow);
+                            expiration_setting(ui, self.id(), &mut self.draft.expires_in);
+                            relay_group_setting(ui, self.id(), &mut self.draft.relay_group);
+                            self.location_toggle(ui);
+                            self.picker_toggle(ui, "emoji", "😀", tr!("emoji-add"));
+                            self.picker_toggle(ui, "gif", "GIF", tr!("gif-add"));
+                            #[cfg(not(target_os = "android"))]
+                            self.paste_image_button(txn, ui);
+                            if (pow, expires_in) != (self.draft.pow, self.draft.expires_in)
+                                || relay_group != self.draft.relay_group
+                            {
+                                self.draft.dirty = true;
+                            }
+
+                            action
                         })
-                        .inner;
+                        .inner
+                    })
+                    .inner;
 
-                    PostResponse {
-                        action,
-                        edit_response,
-                    }
-                })
-                .inner
+                PostResponse {
+                    action,
+                    edit_response,
+                }
             })
             .inner
+        });
+
+        self.attach_dropped_files(txn, ui, response.response.rect);
+
+        response.inner
     }
+
+    /// Progress and cancellation while mining proof-of-work. Returns the
+    /// signed post once the miner has found a nonce.
+    fn mining_ui(&mut self, ui: &mut egui::Ui) -> Option<PostAction> {
+        let miner = self.draft.miner.as_ref()?;
+
+        match miner.poll() {
+            PowPoll::Mining(progress) => {
+                if ui
+                    .add_sized([91.0, 32.0], egui::Button::new("Cancel"))
+                    .clicked()
+                {
+                    miner.cancel();
+                }
+
+                ui.label(format!(
+                    "Mining PoW {}/{}",
+                    progress.best.min(progress.target as u32),
+                    progress.target
+                ))
+                .on_hover_text(format!("{} hashes", progress.attempts));
+                ui.spinner();
+
+                ui.ctx().request_repaint_after(Duration::from_millis(100));
+                None
+            }
+
+            PowPoll::Done(unsigned) => {
+                let seckey = self.poster.secret_key.to_secret_bytes();
+                let signed = match unsigned.sign(&seckey) {
+                    Some(note) => PostAction::signed(self.post_type.clone(), note)
+                        .map_err(|err| err.to_string()),
+                    None => Err("signing the mined note failed".to_owned()),
+                };
+                match signed {
+                    Ok(action) => {
+                        self.draft.miner = None;
+                        Some(action)
+                    }
+                    Err(err) => {
+                        // the draft stays as it was. The spent miner reports
+                        // cancelled next frame, so posting can be tried again.
+                        error!("pow: {err}");
+                        toast::error(tr!("toast-pow-sign-failed"));
+                        None
+                    }
+                }
+            }
+
+            PowPoll::Cancelled => {
+                self.draft.miner = None;
+                None
+            }
+        }
+    }
+}
+
+/// The last part of a path, for showing which file is uploading
+fn file_name(path: &Path) -> Option<String> {
+    Some(path.file_name()?.to_string_lossy().into_owned())
+}
+
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1 << 20 => format!("{:.1} MB", b as f64 / (1 << 20) as f64),
+        b if b >= 1 << 10 => format!("{} KB", b >> 10),
+        b => format!("{b} B"),
+    }
+}
+
+/// Media files a paste refers to, if the pasted text is nothing but
+/// paths or file:// uris of ones that exist
+fn pasted_files(text: &str) -> Option<Vec<PathBuf>> {
+    const MEDIA: [&str; 10] = [
+        "png", "jpg", "jpeg", "gif", "webp", "avif", "heic", "mp4", "mov", "webm",
+    ];
+
+    let paths: Vec<PathBuf> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.strip_prefix("file://") {
+            Some(uri) => urlencoding::decode(uri).map(|path| PathBuf::from(path.into_owned())),
+            None => Ok(PathBuf::from(line)),
+        })
+        .collect::<std::result::Result<_, _>>()
+        .ok()?;
+
+    let all_media = !paths.is_empty()
+        && paths.iter().all(|path| {
+            path.is_absolute()
+                && path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| MEDIA.contains(&ext.to_lowercase().as_str()))
+        });
+    all_media.then_some(paths)
+}
+
+/// The clipboard's image as a png, ready to upload
+#[cfg(not(target_os = "android"))]
+fn clipboard_png() -> std::result::Result<Vec<u8>, String> {
+    let image = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_image())
+        .map_err(|e| e.to_string())?;
+    let image = image::RgbaImage::from_raw(
+        image.width as u32,
+        image.height as u32,
+        image.bytes.into_owned(),
+    )
+    .ok_or("the clipboard image is malformed")?;
+
+    let mut png = vec![];
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+fn pow_setting(ui: &mut egui::Ui, difficulty: &mut u8) {
+    ui.add(
+        egui::DragValue::new(difficulty)
+            .range(0..=32)
+            .prefix("PoW "),
+    )
+    .on_hover_text("NIP-13 proof-of-work difficulty. Some relays require this.");
+}
+
+const EXPIRATION_CHOICES: [(Option<u64>, &str); 5] = [
+    (None, "Never"),
+    (Some(60 * 60), "1 hour"),
+    (Some(60 * 60 * 24), "1 day"),
+    (Some(60 * 60 * 24 * 7), "1 week"),
+    (Some(60 * 60 * 24 * 30), "30 days"),
+];
+
+fn expiration_setting(ui: &mut egui::Ui, id: egui::Id, expires_in: &mut Option<u64>) {
+    let selected = EXPIRATION_CHOICES
+        .iter()
+        .find(|(secs, _)| secs == expires_in)
+        .map(|(_, label)| *label)
+        .unwrap_or("Custom");
+
+    egui::ComboBox::from_id_salt(id.with("expiration"))
+        .selected_text(format!("Expires: {selected}"))
+        .show_ui(ui, |ui| {
+            for (secs, label) in EXPIRATION_CHOICES {
+                ui.selectable_value(expires_in, secs, label);
+            }
+        })
+        .response
+        .on_hover_text("Ask relays and clients to drop this note after a while (NIP-40)");
+}
+
+/// Which relays the post goes to. Hidden until a relay group is set up.
+fn relay_group_setting(ui: &mut egui::Ui, id: egui::Id, relay_group: &mut Option<String>) {
+    let names = relay_groups::names(ui.ctx());
+    if names.is_empty() {
+        return;
+    }
+    // a group that was deleted since the draft was written
+    if relay_group
+        .as_ref()
+        .is_some_and(|group| !names.contains(group))
+    {
+        *relay_group = None;
+    }
+
+    let selected = relay_group
+        .clone()
+        .unwrap_or_else(|| tr!("relay-groups-all-relays"));
+    egui::ComboBox::from_id_salt(id.with("relay-group"))
+        .selected_text(tr!("relay-groups-post-to", group = selected))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(relay_group, None, tr!("relay-groups-all-relays"));
+            for name in names.iter() {
+                ui.selectable_value(relay_group, Some(name.clone()), name);
+            }
+        })
+        .response
+        .on_hover_text(tr!("relay-groups-post-to-hint"));
 }
 
 fn post_button(interactive: bool) -> impl egui::Widget {
@@ -0,0 +1,166 @@
+use egui::{RichText, TextEdit};
+use enostr::NoteId;
+use nostrdb::{Ndb, Note, Transaction};
+use notedeck::{
+    edit::{self, Change, EditMode},
+    note::tag_strings,
+    tr, NotedeckTextStyle,
+};
+
+use crate::export;
+
+pub enum NoteEditAction {
+    Publish {
+        content: String,
+        tags: Vec<Vec<String>>,
+    },
+}
+
+/// What's been typed into the edit view so far
+#[derive(Default)]
+pub struct NoteEditDraft {
+    note_id: Option<NoteId>,
+    content: String,
+    /// One JSON array per line
+    tags: String,
+}
+
+impl NoteEditDraft {
+    /// Start from the note, unless we're already editing it
+    fn for_note(&mut self, note: &Note) -> &mut Self {
+        let note_id = NoteId::new(*note.id());
+        if self.note_id != Some(note_id) {
+            let tags = tag_strings(note);
+            *self = NoteEditDraft {
+                note_id: Some(note_id),
+                content: note.content().to_owned(),
+                tags: edit::tags_to_text(&tags),
+            };
+        }
+        self
+    }
+
+    pub fn clear(&mut self) {
+        *self = NoteEditDraft::default();
+    }
+}
+
+/// Changing one of our notes, with what will change shown before it's
+/// published
+pub struct NoteEditView<'a> {
+    ndb: &'a Ndb,
+    note_id: &'a NoteId,
+    draft: &'a mut NoteEditDraft,
+}
+
+impl<'a> NoteEditView<'a> {
+    pub fn new(ndb: &'a Ndb, note_id: &'a NoteId, draft: &'a mut NoteEditDraft) -> Self {
+        NoteEditView {
+            ndb,
+            note_id,
+            draft,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<NoteEditAction> {
+        let txn = Transaction::new(self.ndb).expect("txn");
+        let Ok(note) = self.ndb.get_note_by_id(&txn, self.note_id.bytes()) else {
+            ui.label(tr!("source-note-not-found"));
+            return None;
+        };
+        let draft = self.draft.for_note(&note);
+        let mode = EditMode::of(note.kind());
+        let mut action = None;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Frame::none()
+                .inner_margin(egui::Margin::same(16.0))
+                .show(ui, |ui| {
+                    section(ui, &export::kind_label(note.kind()));
+                    ui.weak(match mode {
+                        EditMode::Republish => tr!("note-edit-republish-hint"),
+                        EditMode::Supersede => tr!("note-edit-supersede-hint"),
+                    });
+                    ui.add_space(8.0);
+
+                    ui.label(tr!("note-edit-content"));
+                    ui.add(
+                        TextEdit::multiline(&mut draft.content)
+                            .desired_rows(4)
+                            .desired_width(f32::INFINITY),
+                    );
+                    ui.add_space(8.0);
+                    ui.label(tr!("note-edit-tags"));
+                    ui.add(
+                        TextEdit::multiline(&mut draft.tags)
+                            .code_editor()
+                            .desired_rows(3)
+                            .desired_width(f32::INFINITY),
+                    );
+
+                    let tags = match edit::parse_tags(&draft.tags) {
+                        Ok(tags) => tags,
+                        Err(line) => {
+                            ui.colored_label(
+                                ui.visuals().error_fg_color,
+                                tr!("note-edit-bad-tag", line = line),
+                            );
+                            return;
+                        }
+                    };
+
+                    ui.add_space(16.0);
+                    section(ui, &tr!("note-edit-changes"));
+                    let changes = edit::diff(&note, &draft.content, &tags);
+                    if !changes.iter().any(Change::is_change) {
+                        ui.weak(tr!("note-edit-no-changes"));
+                        return;
+                    }
+                    changes_ui(ui, &changes);
+
+                    ui.add_space(8.0);
+                    let publish = match mode {
+                        EditMode::Republish => tr!("note-edit-republish"),
+                        EditMode::Supersede => tr!("note-edit-supersede"),
+                    };
+                    if ui.button(publish).clicked() {
+                        action = Some(NoteEditAction::Publish {
+                            content: draft.content.clone(),
+                            tags,
+                        });
+                    }
+                });
+        });
+
+        action
+    }
+}
+
+/// Lines and tags going out in red, coming in in green
+fn changes_ui(ui: &mut egui::Ui, changes: &[Change]) {
+    let removed = ui.visuals().error_fg_color;
+    let added = egui::Color32::from_rgb(0x3c, 0xb3, 0x71);
+
+    for change in changes {
+        let (prefix, text, color) = match change {
+            Change::Same(line) => (" ", line.clone(), ui.visuals().weak_text_color()),
+            Change::Removed(line) => ("-", line.clone(), removed),
+            Change::Added(line) => ("+", line.clone(), added),
+            Change::TagRemoved(tag) => ("-", edit::tags_to_text(&[tag.clone()]), removed),
+            Change::TagAdded(tag) => ("+", edit::tags_to_text(&[tag.clone()]), added),
+        };
+        ui.label(
+            RichText::new(format!("{prefix} {text}"))
+                .monospace()
+                .color(color),
+        );
+    }
+}
+
+fn section(ui: &mut egui::Ui, title: &str) {
+    ui.label(
+        RichText::new(title)
+            .text_style(NotedeckTextStyle::Body.text_style())
+            .strong(),
+    );
+}
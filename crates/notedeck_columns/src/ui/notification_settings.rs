@@ -0,0 +1,313 @@
+use egui::{DragValue, RichText, TextEdit};
+use notedeck::{datetime, toast, tr, NotedeckTextStyle};
+
+use crate::notification_rules::{self, Condition, NotificationRule, RuleAction};
+use crate::notifications::{NotificationKind, NotificationSettings, QuietHours};
+
+const KINDS: [NotificationKind; 4] = [
+    NotificationKind::Mention,
+    NotificationKind::Reply,
+    NotificationKind::DirectMessage,
+    NotificationKind::Zap,
+];
+
+pub struct NotificationSettingsView<'a> {
+    settings: &'a mut NotificationSettings,
+}
+
+impl<'a> NotificationSettingsView<'a> {
+    pub fn new(settings: &'a mut NotificationSettings) -> Self {
+        NotificationSettingsView { settings }
+    }
+
+    /// Returns true if any setting was changed
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let before = self.settings.clone();
+
+        egui::Frame::none()
+            .inner_margin(egui::Margin::same(16.0))
+            .show(ui, |ui| {
+                ui.checkbox(
+                    &mut self.settings.enabled,
+                    tr!("notification-settings-enabled"),
+                );
+                ui.add_space(8.0);
+
+                ui.add_enabled_ui(self.settings.enabled, |ui| {
+                    section(ui, &tr!("notification-settings-notify-about"));
+                    ui.checkbox(
+                        &mut self.settings.mentions,
+                        tr!("notification-settings-mentions"),
+                    );
+                    ui.checkbox(
+                        &mut self.settings.replies,
+                        tr!("notification-settings-replies"),
+                    );
+                    ui.checkbox(
+                        &mut self.settings.direct_messages,
+                        tr!("notification-settings-direct-messages"),
+                    );
+                    ui.checkbox(&mut self.settings.zaps, tr!("notification-settings-zaps"));
+
+                    ui.add_space(8.0);
+                    ui.checkbox(
+                        &mut self.settings.only_follows,
+                        tr!("notification-settings-only-follows"),
+                    )
+                    .on_hover_text(tr!("notification-settings-only-follows-hint"));
+
+                    ui.add_space(8.0);
+                    section(ui, &tr!("notification-settings-quiet-hours"));
+                    self.quiet_hours(ui);
+
+                    ui.add_space(8.0);
+                    section(ui, &tr!("notification-rules"));
+                    self.rules(ui);
+
+                    #[cfg(target_os = "android")]
+                    {
+                        ui.add_space(8.0);
+                        section(ui, &tr!("notification-settings-background"));
+                        ui.checkbox(
+                            &mut self.settings.background,
+                            tr!("notification-settings-background-enabled"),
+                        )
+                        .on_hover_text(tr!("notification-settings-background-hint"));
+                    }
+                });
+            });
+
+        before != *self.settings
+    }
+
+    fn quiet_hours(&mut self, ui: &mut egui::Ui) {
+        let mut quiet = self.settings.quiet_hours.is_some();
+        if ui
+            .checkbox(&mut quiet, tr!("notification-settings-quiet-hours-enabled"))
+            .changed()
+        {
+            self.settings.quiet_hours = quiet.then(QuietHours::default);
+        }
+
+        if let Some(hours) = &mut self.settings.quiet_hours {
+            ui.horizontal(|ui| {
+                ui.label(tr!("notification-settings-quiet-from"));
+                ui.add(hour(&mut hours.start));
+                ui.label(tr!("notification-settings-quiet-until"));
+                ui.add(hour(&mut hours.end));
+            });
+        }
+    }
+
+    fn rules(&mut self, ui: &mut egui::Ui) {
+        ui.weak(tr!("notification-rules-hint"));
+
+        let mut remove = None;
+        let mut raise = None;
+        for (i, rule) in self.settings.rules.iter_mut().enumerate() {
+            ui.push_id(i, |ui| {
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut rule.enabled, "")
+                            .on_hover_text(tr!("notification-rule-enabled"));
+                        egui::ComboBox::from_id_salt("action")
+                            .selected_text(action_label(rule.action))
+                            .show_ui(ui, |ui| {
+                                for action in [RuleAction::Notify, RuleAction::Mute] {
+                                    ui.selectable_value(
+                                        &mut rule.action,
+                                        action,
+                                        action_label(action),
+                                    );
+                                }
+                            });
+                        ui.label(tr!("notification-rule-when"));
+                        if ui
+                            .add_enabled(i > 0, egui::Button::new("⬆"))
+                            .on_hover_text(tr!("notification-rule-raise"))
+                            .clicked()
+                        {
+                            raise = Some(i);
+                        }
+                        if ui
+                            .button("🗑")
+                            .on_hover_text(tr!("notification-rule-remove"))
+                            .clicked()
+                        {
+                            remove = Some(i);
+                        }
+                    });
+                    conditions(ui, rule);
+                });
+            });
+        }
+
+        if let Some(i) = remove {
+            self.settings.rules.remove(i);
+        }
+        if let Some(i) = raise {
+            self.settings.rules.swap(i - 1, i);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("notification-rule-add")).clicked() {
+                self.settings.rules.push(NotificationRule::default());
+            }
+            if ui.button(tr!("notification-rules-copy")).clicked() {
+                let json = notification_rules::to_json(&self.settings.rules);
+                ui.output_mut(|w| w.copied_text = json);
+                toast::info(tr!("toast-notification-rules-copied"));
+            }
+        });
+
+        let id = ui.id().with("rules-import");
+        let mut import = ui
+            .ctx()
+            .data_mut(|d| d.get_temp::<String>(id))
+            .unwrap_or_default();
+        ui.add(
+            TextEdit::multiline(&mut import)
+                .hint_text(tr!("notification-rules-import-hint"))
+                .desired_rows(2)
+                .desired_width(f32::INFINITY),
+        );
+        if ui
+            .add_enabled(
+                !import.trim().is_empty(),
+                egui::Button::new(tr!("notification-rules-import")),
+            )
+            .clicked()
+        {
+            match notification_rules::from_json(&import) {
+                Ok(rules) => {
+                    toast::info(tr!(
+                        "toast-notification-rules-imported",
+                        count = rules.len()
+                    ));
+                    self.settings.rules = rules;
+                    import.clear();
+                }
+                Err(err) => toast::error(tr!(
+                    "toast-notification-rules-invalid",
+                    error = err.to_string()
+                )),
+            }
+        }
+        ui.ctx().data_mut(|d| d.insert_temp(id, import));
+    }
+}
+
+/// The conditions of one rule, with a menu to add more
+fn conditions(ui: &mut egui::Ui, rule: &mut NotificationRule) {
+    let mut remove = None;
+    for (i, condition) in rule.conditions.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                condition_ui(ui, condition);
+                if ui
+                    .small_button("✕")
+                    .on_hover_text(tr!("notification-rule-remove-condition"))
+                    .clicked()
+                {
+                    remove = Some(i);
+                }
+            });
+        });
+    }
+    if let Some(i) = remove {
+        rule.conditions.remove(i);
+    }
+
+    if rule.conditions.is_empty() {
+        ui.weak(tr!("notification-rule-always"));
+    }
+
+    ui.menu_button(tr!("notification-rule-add-condition"), |ui| {
+        let added = [
+            Condition::Kind {
+                kinds: vec![NotificationKind::Mention],
+            },
+            Condition::WithinWot { hops: 1 },
+            Condition::OutsideWot { hops: 2 },
+            Condition::Contains {
+                text: String::new(),
+            },
+            Condition::ZapAtLeast { sats: 1000 },
+        ]
+        .into_iter()
+        .find(|condition| ui.button(condition_label(condition)).clicked());
+
+        if let Some(condition) = added {
+            rule.conditions.push(condition);
+            ui.close_menu();
+        }
+    });
+}
+
+fn condition_ui(ui: &mut egui::Ui, condition: &mut Condition) {
+    ui.label(condition_label(condition));
+    match condition {
+        Condition::Kind { kinds } => {
+            for kind in KINDS {
+                let mut on = kinds.contains(&kind);
+                if ui.checkbox(&mut on, kind_label(kind)).changed() {
+                    if on {
+                        kinds.push(kind);
+                    } else {
+                        kinds.retain(|k| *k != kind);
+                    }
+                }
+            }
+        }
+        Condition::WithinWot { hops } | Condition::OutsideWot { hops } => {
+            ui.add(DragValue::new(hops).range(1..=3));
+        }
+        Condition::Contains { text } => {
+            ui.add(TextEdit::singleline(text).desired_width(160.0));
+        }
+        Condition::ZapAtLeast { sats } => {
+            ui.add(DragValue::new(sats).speed(100.0).suffix(" sats"));
+        }
+    }
+}
+
+fn action_label(action: RuleAction) -> String {
+    match action {
+        RuleAction::Notify => tr!("notification-rule-notify"),
+        RuleAction::Mute => tr!("notification-rule-mute"),
+    }
+}
+
+fn condition_label(condition: &Condition) -> String {
+    match condition {
+        Condition::Kind { .. } => tr!("notification-rule-kind"),
+        Condition::WithinWot { .. } => tr!("notification-rule-within-wot"),
+        Condition::OutsideWot { .. } => tr!("notification-rule-outside-wot"),
+        Condition::Contains { .. } => tr!("notification-rule-contains"),
+        Condition::ZapAtLeast { .. } => tr!("notification-rule-zap-at-least"),
+    }
+}
+
+fn kind_label(kind: NotificationKind) -> String {
+    match kind {
+        NotificationKind::Mention => tr!("notification-settings-mentions"),
+        NotificationKind::Reply => tr!("notification-settings-replies"),
+        NotificationKind::DirectMessage => tr!("notification-settings-direct-messages"),
+        NotificationKind::Zap => tr!("notification-settings-zaps"),
+    }
+}
+
+fn hour(value: &mut u8) -> DragValue<'_> {
+    DragValue::new(value)
+        .range(0..=23)
+        .custom_formatter(|hour, _| datetime::settings().format_hour(hour as u32))
+        .custom_parser(|text| datetime::parse_hour(text).map(f64::from))
+}
+
+fn section(ui: &mut egui::Ui, title: &str) {
+    ui.label(
+        RichText::new(title)
+            .text_style(NotedeckTextStyle::Body.text_style())
+            .strong(),
+    );
+}
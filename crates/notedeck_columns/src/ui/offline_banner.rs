@@ -0,0 +1,17 @@
+use egui::RichText;
+use notedeck::{time_ago_since, tr, NotedeckTextStyle};
+
+/// A strip across the top while no relay can be reached, so it's clear
+/// that what's on screen was saved on this device earlier
+pub fn offline_banner(ui: &mut egui::Ui, last_prefetch: Option<u64>) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label(
+            RichText::new(tr!("offline-banner"))
+                .text_style(NotedeckTextStyle::Body.text_style())
+                .color(ui.visuals().warn_fg_color),
+        );
+        if let Some(last) = last_prefetch {
+            ui.weak(tr!("offline-last-prefetch", ago = time_ago_since(last)));
+        }
+    });
+}
@@ -0,0 +1,413 @@
+use egui::{Button, Color32, RichText, TextEdit, Vec2};
+use nostrdb::{Ndb, Transaction};
+use notedeck::{FollowPack, ImageCache, NotedeckTextStyle, UnknownIds};
+
+use crate::follow_packs::INTERESTS;
+use crate::onboarding::{Onboarding, OnboardingResult, OnboardingStep};
+use crate::ui::account_login_view::{AccountLoginResponse, AccountLoginView};
+use crate::ui::follow_pack::FollowPackPreview;
+
+pub enum OnboardingResponse {
+    Finished(OnboardingResult),
+    /// Use the demo deck for now
+    Skipped,
+}
+
+pub struct OnboardingView<'a> {
+    state: &'a mut Onboarding,
+    ndb: &'a Ndb,
+    img_cache: &'a mut ImageCache,
+    unknown_ids: &'a mut UnknownIds,
+    /// Follow packs to suggest, only needed on the interests step
+    packs: &'a [FollowPack],
+}
+
+impl<'a> OnboardingView<'a> {
+    pub fn new(
+        state: &'a mut Onboarding,
+        ndb: &'a Ndb,
+        img_cache: &'a mut ImageCache,
+        unknown_ids: &'a mut UnknownIds,
+        packs: &'a [FollowPack],
+    ) -> Self {
+        OnboardingView {
+            state,
+            ndb,
+            img_cache,
+            unknown_ids,
+            packs,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<OnboardingResponse> {
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.set_max_width(480.0);
+                    ui.add_space(48.0);
+
+                    match self.state.step {
+                        OnboardingStep::Welcome => self.welcome(ui),
+                        OnboardingStep::CreateKey => {
+                            self.create_key(ui);
+                            None
+                        }
+                        OnboardingStep::BackupKey => {
+                            self.backup_key(ui);
+                            None
+                        }
+                        OnboardingStep::Login => {
+                            self.login(ui);
+                            None
+                        }
+                        OnboardingStep::Relays => {
+                            self.relays(ui);
+                            None
+                        }
+                        OnboardingStep::Interests => {
+                            self.interests(ui);
+                            None
+                        }
+                        OnboardingStep::Columns => self.columns(ui),
+                    }
+                })
+                .inner
+            })
+            .inner
+    }
+
+    fn welcome(&mut self, ui: &mut egui::Ui) -> Option<OnboardingResponse> {
+        ui.label(heading("Welcome to Notedeck"));
+        ui.add_space(8.0);
+        ui.label(body(
+            "Notedeck is a client for nostr, an open social network where you own your identity. \
+             Let's get you set up.",
+        ));
+        ui.add_space(24.0);
+
+        if ui.add(primary_button("Create a new account")).clicked() {
+            self.state.create_key();
+        }
+
+        ui.add_space(8.0);
+
+        if ui.add(secondary_button("I already have a key")).clicked() {
+            self.state.step = OnboardingStep::Login;
+        }
+
+        ui.add_space(16.0);
+
+        if ui
+            .add(Button::new(RichText::new("Skip for now").weak()).frame(false))
+            .clicked()
+        {
+            return Some(OnboardingResponse::Skipped);
+        }
+
+        None
+    }
+
+    fn create_key(&mut self, ui: &mut egui::Ui) {
+        let Some(kp) = &self.state.new_key else {
+            self.state.step = OnboardingStep::Welcome;
+            return;
+        };
+
+        ui.label(heading("Your new keys"));
+        ui.add_space(8.0);
+
+        ui.label(body(
+            "Your public key is how people find you. Share it as much as you like.",
+        ));
+        if let Some(npub) = kp.pubkey.to_bech() {
+            key_label(ui, &npub);
+        }
+
+        ui.add_space(16.0);
+
+        ui.label(body(
+            "Your recovery phrase is your password. Anyone who has it can post as you, and if \
+             you lose it there is no way to recover your account. Write these words down, in \
+             order, and keep them somewhere safe.",
+        ));
+        ui.add_space(8.0);
+        mnemonic_grid(ui, &self.state.mnemonic_words());
+        if let Some(phrase) = &self.state.mnemonic {
+            if ui.button("Copy recovery phrase").clicked() {
+                ui.ctx().copy_text(phrase.clone());
+            }
+        }
+
+        ui.add_space(16.0);
+
+        ui.label(body(
+            "Other apps may ask for your private key instead. It comes from the same phrase.",
+        ));
+        if let Some(nsec) = kp.nsec() {
+            key_label(ui, &nsec);
+            if ui.button("Copy private key").clicked() {
+                ui.ctx().copy_text(nsec);
+            }
+        }
+
+        ui.add_space(24.0);
+
+        if ui.add(primary_button("I wrote it down")).clicked() {
+            self.state.backup_confirmation =
+                vec![String::new(); self.state.backup_confirmation.len()];
+            self.state.step = OnboardingStep::BackupKey;
+        }
+
+        self.back_button(ui);
+    }
+
+    fn backup_key(&mut self, ui: &mut egui::Ui) {
+        ui.label(heading("Confirm your backup"));
+        ui.add_space(8.0);
+        ui.label(body(
+            "Type these words from your recovery phrase, to make sure you have it.",
+        ));
+        ui.add_space(8.0);
+
+        let positions = self.state.backup_positions();
+        for (position, typed) in positions.iter().zip(&mut self.state.backup_confirmation) {
+            ui.horizontal(|ui| {
+                ui.label(body(&format!("Word {}", position + 1)));
+                ui.add(
+                    TextEdit::singleline(typed)
+                        .font(NotedeckTextStyle::Monospace.text_style())
+                        .min_size(Vec2::new(0.0, 32.0)),
+                );
+            });
+        }
+
+        ui.add_space(16.0);
+
+        let confirmed = self.state.backup_confirmed();
+        if ui
+            .add_enabled(confirmed, primary_button("Continue"))
+            .clicked()
+        {
+            self.state.step = OnboardingStep::Relays;
+        }
+
+        self.back_button(ui);
+    }
+
+    fn login(&mut self, ui: &mut egui::Ui) {
+        if let Some(resp) = AccountLoginView::new(&mut self.state.login).ui(ui).inner {
+            match resp {
+                AccountLoginResponse::CreateNew => self.state.create_key(),
+                AccountLoginResponse::LoginWith(keypair) => self.state.login_with(keypair),
+            }
+        }
+
+        self.back_button(ui);
+    }
+
+    fn relays(&mut self, ui: &mut egui::Ui) {
+        ui.label(heading("Pick your relays"));
+        ui.add_space(8.0);
+        ui.label(body(
+            "Relays store and deliver your notes. You can change these at any time.",
+        ));
+        ui.add_space(16.0);
+
+        ui.vertical(|ui| {
+            for relay in &mut self.state.relays {
+                ui.checkbox(&mut relay.selected, relay.url.as_str());
+            }
+        });
+
+        ui.add_space(8.0);
+
+        ui.horizontal(|ui| {
+            let resp = ui.add(
+                TextEdit::singleline(&mut self.state.custom_relay)
+                    .hint_text("wss://your.relay")
+                    .desired_width(320.0),
+            );
+            let submitted = resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Add").clicked() || submitted {
+                self.state.add_custom_relay();
+            }
+        });
+
+        ui.add_space(24.0);
+
+        let any_selected = self.state.relays.iter().any(|relay| relay.selected);
+        if ui
+            .add_enabled(any_selected, primary_button("Continue"))
+            .clicked()
+        {
+            self.state.step = OnboardingStep::Interests;
+        }
+
+        self.back_button(ui);
+    }
+
+    fn interests(&mut self, ui: &mut egui::Ui) {
+        ui.label(heading("What are you into?"));
+        ui.add_space(8.0);
+        ui.label(body(
+            "Pick a few topics and we'll suggest people to follow. You can find more any time \
+             under Explore people.",
+        ));
+        ui.add_space(16.0);
+
+        ui.horizontal_wrapped(|ui| {
+            for (interest, _) in INTERESTS {
+                let picked = self.state.interests.contains(&interest);
+                if ui.selectable_label(picked, interest).clicked() {
+                    self.state.toggle_interest(interest);
+                }
+            }
+        });
+
+        ui.add_space(16.0);
+
+        let suggested = self.state.suggested(self.packs);
+        if !self.state.interests.is_empty() && suggested.is_empty() {
+            ui.label(
+                RichText::new(
+                    "No follow packs for these topics yet, your relays may still be sending them.",
+                )
+                .weak(),
+            );
+        }
+
+        let txn = Transaction::new(self.ndb).expect("txn");
+        for pack in suggested {
+            egui::Frame::group(ui.style()).show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.vertical(|ui| {
+                    FollowPackPreview::new(pack, self.ndb, &txn, self.img_cache, self.unknown_ids)
+                        .ui(ui);
+
+                    let mut chosen = self.state.is_chosen(pack);
+                    let label = format!("Follow all {}", pack.members.len());
+                    if ui.checkbox(&mut chosen, label).changed() {
+                        self.state.choose(pack, chosen);
+                    }
+                });
+            });
+            ui.add_space(8.0);
+        }
+
+        ui.add_space(16.0);
+
+        let follows = self.state.follows().len();
+        let label = if follows == 0 {
+            "Continue".to_owned()
+        } else {
+            format!("Follow {follows} people")
+        };
+        if ui.add(primary_button(&label)).clicked() {
+            self.state.step = OnboardingStep::Columns;
+        }
+
+        self.back_button(ui);
+    }
+
+    fn columns(&mut self, ui: &mut egui::Ui) -> Option<OnboardingResponse> {
+        ui.label(heading("Set up your columns"));
+        ui.add_space(8.0);
+        ui.label(body(
+            "Each column is a timeline. You can add, remove and rearrange them later.",
+        ));
+        ui.add_space(16.0);
+
+        let columns = &mut self.state.columns;
+        ui.vertical(|ui| {
+            ui.checkbox(&mut columns.home, "Home — notes from people you follow");
+            ui.checkbox(&mut columns.notifications, "Notifications");
+            ui.checkbox(&mut columns.posts, "Posts & replies — what you've written");
+            ui.checkbox(
+                &mut columns.universe,
+                "Universe — everything your relays see",
+            );
+        });
+
+        ui.add_space(8.0);
+        ui.label(body("Hashtags to follow"));
+        ui.add(
+            TextEdit::singleline(&mut columns.hashtags)
+                .hint_text("nostr art photography")
+                .min_size(Vec2::new(0.0, 32.0)),
+        );
+
+        ui.add_space(24.0);
+
+        let finished = ui.add(primary_button("Let's go")).clicked();
+
+        self.back_button(ui);
+
+        if finished {
+            self.state.finish().map(OnboardingResponse::Finished)
+        } else {
+            None
+        }
+    }
+
+    fn back_button(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(8.0);
+        if ui
+            .add(Button::new(RichText::new("Back").weak()).frame(false))
+            .clicked()
+        {
+            self.state.step = self.state.prev_step();
+        }
+    }
+}
+
+fn heading(text: &str) -> RichText {
+    RichText::new(text)
+        .text_style(NotedeckTextStyle::Heading2.text_style())
+        .strong()
+}
+
+fn body(text: &str) -> RichText {
+    RichText::new(text).text_style(NotedeckTextStyle::Body.text_style())
+}
+
+/// The recovery phrase, numbered, three words to a row
+fn mnemonic_grid(ui: &mut egui::Ui, words: &[&str]) {
+    egui::Grid::new("onboarding-mnemonic")
+        .num_columns(3)
+        .spacing([24.0, 8.0])
+        .show(ui, |ui| {
+            for (i, word) in words.iter().enumerate() {
+                ui.label(
+                    RichText::new(format!("{:>2}. {word}", i + 1))
+                        .text_style(NotedeckTextStyle::Monospace.text_style()),
+                );
+                if i % 3 == 2 {
+                    ui.end_row();
+                }
+            }
+        });
+}
+
+fn key_label(ui: &mut egui::Ui, key: &str) {
+    ui.add(
+        egui::Label::new(RichText::new(key).text_style(NotedeckTextStyle::Monospace.text_style()))
+            .wrap()
+            .selectable(true),
+    );
+}
+
+fn primary_button(text: &str) -> Button<'static> {
+    Button::new(
+        RichText::new(text)
+            .text_style(NotedeckTextStyle::Body.text_style())
+            .strong(),
+    )
+    .fill(Color32::from_rgb(0xF8, 0x69, 0xB6))
+    .min_size(Vec2::new(240.0, 40.0))
+}
+
+fn secondary_button(text: &str) -> Button<'static> {
+    Button::new(RichText::new(text).text_style(NotedeckTextStyle::Body.text_style()))
+        .min_size(Vec2::new(240.0, 40.0))
+}
@@ -0,0 +1,386 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use egui::{ComboBox, RichText, Sense, TextEdit};
+use enostr::{Keypair, Pubkey};
+use nostrdb::{Ndb, Transaction};
+use notedeck::{tr, ImageCache, WebOfTrust};
+use poll_promise::Promise;
+
+use crate::{
+    deep_link::DeepLink,
+    key_parsing::{perform_key_retrieval, AcquireKeyError},
+    profile::get_display_name,
+    ui::{profile::get_profile_url, ProfilePic},
+};
+
+/// How many people to suggest while typing
+const MAX_SUGGESTIONS: usize = 6;
+/// How many candidates to look at per frame, webs of trust can be big
+const SCAN_LIMIT: usize = 2000;
+
+/// Someone who was picked, and what they're there as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Person {
+    pub pubkey: Pubkey,
+    pub role: Option<String>,
+}
+
+/// The people picked so far, and what's being typed
+#[derive(Default)]
+pub struct PeoplePickerState {
+    pub people: Vec<Person>,
+    query: String,
+    lookup: Option<Promise<Result<Keypair, AcquireKeyError>>>,
+    error: Option<String>,
+}
+
+impl PeoplePickerState {
+    pub fn new(people: Vec<Person>) -> Self {
+        PeoplePickerState {
+            people,
+            ..Default::default()
+        }
+    }
+
+    pub fn contains(&self, pubkey: &Pubkey) -> bool {
+        self.people.iter().any(|person| person.pubkey == *pubkey)
+    }
+
+    /// Returns whether they were added, people are only picked once
+    fn add(&mut self, pubkey: Pubkey, role: Option<String>) -> bool {
+        if self.contains(&pubkey) {
+            return false;
+        }
+        self.people.push(Person { pubkey, role });
+        true
+    }
+}
+
+/// Where suggestions come from, in the order they're given
+pub enum PeopleSource<'a> {
+    /// Accounts someone follows
+    Follows(&'a [[u8; 32]]),
+    /// Everyone in a web of trust
+    Trusted(&'a WebOfTrust),
+}
+
+/// Pick people by typing a name, a key (hex, npub or nprofile) or a
+/// nostr address. Picked people show as chips that can be given a role
+/// and removed. The response is marked changed when the people change.
+pub struct PeoplePicker<'a> {
+    id: egui::Id,
+    state: &'a mut PeoplePickerState,
+    ndb: &'a Ndb,
+    txn: &'a Transaction,
+    img_cache: &'a mut ImageCache,
+    sources: Vec<PeopleSource<'a>>,
+    trusted_only: Option<&'a WebOfTrust>,
+    roles: &'a [&'a str],
+    validate: Option<Box<dyn Fn(&Pubkey) -> Result<(), String> + 'a>>,
+}
+
+impl<'a> PeoplePicker<'a> {
+    pub fn new(
+        id_salt: impl Hash,
+        state: &'a mut PeoplePickerState,
+        ndb: &'a Ndb,
+        txn: &'a Transaction,
+        img_cache: &'a mut ImageCache,
+    ) -> Self {
+        PeoplePicker {
+            id: egui::Id::new(("people-picker", id_salt)),
+            state,
+            ndb,
+            txn,
+            img_cache,
+            sources: vec![],
+            trusted_only: None,
+            roles: &[],
+            validate: None,
+        }
+    }
+
+    pub fn source(mut self, source: PeopleSource<'a>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Only suggest, and only accept, people in this web of trust
+    pub fn trusted_only(mut self, wot: &'a WebOfTrust) -> Self {
+        self.trusted_only = Some(wot);
+        self
+    }
+
+    /// Roles people can be given. The first is the default.
+    pub fn roles(mut self, roles: &'a [&'a str]) -> Self {
+        self.roles = roles;
+        self
+    }
+
+    /// Turn people away with a reason
+    pub fn validate(mut self, validate: impl Fn(&Pubkey) -> Result<(), String> + 'a) -> Self {
+        self.validate = Some(Box::new(validate));
+        self
+    }
+
+    fn check(&self, pubkey: &Pubkey) -> Result<(), String> {
+        if let Some(wot) = self.trusted_only {
+            if !wot.is_trusted(pubkey.bytes()) {
+                return Err(tr!("people-picker-untrusted"));
+            }
+        }
+
+        match &self.validate {
+            Some(validate) => validate(pubkey),
+            None => Ok(()),
+        }
+    }
+
+    fn pick(&mut self, pubkey: Pubkey) -> bool {
+        if let Err(err) = self.check(&pubkey) {
+            self.state.error = Some(err);
+            return false;
+        }
+
+        let role = self.roles.first().map(|role| role.to_string());
+        self.state.error = None;
+        self.state.query.clear();
+        self.state.add(pubkey, role)
+    }
+
+    fn submit(&mut self) -> bool {
+        let query = self.state.query.trim().to_owned();
+        if query.is_empty() {
+            return false;
+        }
+
+        if let Some(pubkey) = parse_pubkey(&query) {
+            return self.pick(pubkey);
+        }
+
+        if query.contains('@') {
+            self.state.lookup = Some(perform_key_retrieval(&query));
+            self.state.error = None;
+        } else {
+            self.state.error = Some(tr!("people-picker-invalid"));
+        }
+        false
+    }
+
+    /// A nostr address lookup that finished
+    fn poll_lookup(&mut self) -> bool {
+        let Some(lookup) = self.state.lookup.take() else {
+            return false;
+        };
+
+        match lookup.try_take() {
+            Ok(Ok(keypair)) => self.pick(keypair.pubkey),
+            Ok(Err(err)) => {
+                self.state.error = Some(err.to_string());
+                false
+            }
+            Err(lookup) => {
+                self.state.lookup = Some(lookup);
+                false
+            }
+        }
+    }
+
+    /// People from the sources whose names match what's typed
+    fn suggestions(&self) -> Vec<Pubkey> {
+        let query = self.state.query.trim().to_lowercase();
+        if query.is_empty() || parse_pubkey(&query).is_some() {
+            return vec![];
+        }
+
+        let mut seen = HashSet::new();
+        let candidates = self.sources.iter().flat_map(|source| {
+            let (follows, trusted) = match source {
+                PeopleSource::Follows(follows) => (Some(follows.iter()), None),
+                PeopleSource::Trusted(wot) => (None, Some(wot.iter())),
+            };
+            follows
+                .into_iter()
+                .flatten()
+                .chain(trusted.into_iter().flatten())
+        });
+
+        candidates
+            .take(SCAN_LIMIT)
+            .filter(|pubkey| seen.insert(**pubkey))
+            .map(|pubkey| Pubkey::new(*pubkey))
+            .filter(|pubkey| !self.state.contains(pubkey))
+            .filter(|pubkey| self.check(pubkey).is_ok())
+            .filter(|pubkey| {
+                let profile = self
+                    .ndb
+                    .get_profile_by_pubkey(self.txn, pubkey.bytes())
+                    .ok();
+                let name = get_display_name(profile.as_ref());
+                [name.username, name.display_name, name.nip05]
+                    .into_iter()
+                    .flatten()
+                    .any(|name| name.to_lowercase().contains(&query))
+            })
+            .take(MAX_SUGGESTIONS)
+            .collect()
+    }
+
+    fn person_ui(&mut self, ui: &mut egui::Ui, pubkey: &Pubkey, size: f32) -> egui::Response {
+        let profile = self
+            .ndb
+            .get_profile_by_pubkey(self.txn, pubkey.bytes())
+            .ok();
+        ui.horizontal(|ui| {
+            ui.add(ProfilePic::new(self.img_cache, get_profile_url(profile.as_ref())).size(size));
+            ui.label(get_display_name(profile.as_ref()).name());
+        })
+        .response
+    }
+
+    fn chips_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut removed = None;
+        let mut changed = false;
+
+        ui.horizontal_wrapped(|ui| {
+            for i in 0..self.state.people.len() {
+                let pubkey = self.state.people[i].pubkey;
+                egui::Frame::group(ui.style())
+                    .inner_margin(egui::Margin::symmetric(6.0, 2.0))
+                    .rounding(12.0)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            self.person_ui(ui, &pubkey, 18.0);
+
+                            if !self.roles.is_empty() {
+                                let role = &mut self.state.people[i].role;
+                                let shown = role.clone().unwrap_or_default();
+                                ComboBox::from_id_salt(self.id.with(("role", i)))
+                                    .selected_text(RichText::new(shown).small())
+                                    .width(80.0)
+                                    .show_ui(ui, |ui| {
+                                        for option in self.roles {
+                                            let selected = role.as_deref() == Some(*option);
+                                            if ui.selectable_label(selected, *option).clicked()
+                                                && !selected
+                                            {
+                                                *role = Some(option.to_string());
+                                                changed = true;
+                                            }
+                                        }
+                                    });
+                            }
+
+                            if ui.small_button("✕").clicked() {
+                                removed = Some(i);
+                            }
+                        });
+                    });
+            }
+        });
+
+        if let Some(i) = removed {
+            self.state.people.remove(i);
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
+        let mut changed = self.poll_lookup();
+
+        let mut response = ui
+            .vertical(|ui| {
+                changed |= self.chips_ui(ui);
+
+                ui.horizontal(|ui| {
+                    let input = ui.add(
+                        TextEdit::singleline(&mut self.state.query)
+                            .id(self.id.with("query"))
+                            .hint_text(tr!("people-picker-hint"))
+                            .desired_width(240.0),
+                    );
+                    if input.changed() {
+                        self.state.error = None;
+                    }
+                    if input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        changed |= self.submit();
+                        input.request_focus();
+                    }
+
+                    if self.state.lookup.is_some() {
+                        ui.spinner();
+                    } else if ui
+                        .add_enabled(!self.state.query.trim().is_empty(), egui::Button::new("+"))
+                        .clicked()
+                    {
+                        changed |= self.submit();
+                    }
+                });
+
+                if let Some(err) = &self.state.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                for pubkey in self.suggestions() {
+                    let resp = self.person_ui(ui, &pubkey, 24.0).interact(Sense::click());
+                    if resp.hovered() {
+                        crate::ui::show_pointer(ui);
+                    }
+                    if resp.clicked() {
+                        changed |= self.pick(pubkey);
+                    }
+                }
+            })
+            .response;
+
+        if changed {
+            response.mark_changed();
+        }
+        response
+    }
+}
+
+/// A key typed as hex, npub or nprofile, with or without `nostr:`
+fn parse_pubkey(text: &str) -> Option<Pubkey> {
+    let text = text.trim();
+    if let Ok(pubkey) = Pubkey::from_hex(text) {
+        return Some(pubkey);
+    }
+
+    match DeepLink::parse(text)? {
+        DeepLink::Profile(pubkey) => Some(pubkey),
+        DeepLink::Note(_) | DeepLink::Address(_) | DeepLink::WalletConnect(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEX: &str = "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d";
+
+    #[test]
+    fn test_parse_pubkey() {
+        let pubkey = Pubkey::from_hex(HEX).unwrap();
+        let npub = pubkey.to_bech().unwrap();
+
+        assert_eq!(parse_pubkey(HEX), Some(pubkey));
+        assert_eq!(parse_pubkey(&format!(" {npub} ")), Some(pubkey));
+        assert_eq!(parse_pubkey(&format!("nostr:{npub}")), Some(pubkey));
+        assert_eq!(parse_pubkey("jack@cash.app"), None);
+        assert_eq!(parse_pubkey("jack"), None);
+    }
+
+    #[test]
+    fn test_people_are_picked_once() {
+        let pubkey = Pubkey::from_hex(HEX).unwrap();
+        let mut state = PeoplePickerState::default();
+
+        assert!(state.add(pubkey, Some("host".to_owned())));
+        assert!(!state.add(pubkey, None));
+        assert_eq!(state.people.len(), 1);
+        assert_eq!(state.people[0].role.as_deref(), Some("host"));
+    }
+}
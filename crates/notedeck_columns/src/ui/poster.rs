@@ -0,0 +1,276 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use egui::{ColorImage, Frame, Image, Label, Rect, RichText};
+use enostr::NoteId;
+use nostrdb::{Ndb, Note, Transaction};
+use notedeck::{datetime, text, tr, ImageCache};
+use tracing::error;
+
+use crate::images::ImageType;
+use crate::profile::get_display_name;
+use crate::ui::{note::contents::is_image_link, qr::QrCodeView, ProfilePic};
+
+const POSTER_WIDTH: f32 = 360.0;
+/// Longer notes are cut off with …
+const MAX_POSTER_ROWS: usize = 14;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Export {
+    Save,
+    #[cfg(not(target_os = "android"))]
+    Copy,
+}
+
+/// A shareable card for a note, with a QR code linking back to it. The
+/// card is drawn like any other widget and exported by screenshotting the
+/// window and cropping to it.
+pub struct PosterView<'a> {
+    ndb: &'a Ndb,
+    img_cache: &'a mut ImageCache,
+    note_id: &'a NoteId,
+    /// Where to save when the platform has no downloads folder
+    fallback_dir: &'a Path,
+}
+
+impl<'a> PosterView<'a> {
+    pub fn new(
+        ndb: &'a Ndb,
+        img_cache: &'a mut ImageCache,
+        note_id: &'a NoteId,
+        fallback_dir: &'a Path,
+    ) -> Self {
+        PosterView {
+            ndb,
+            img_cache,
+            note_id,
+            fallback_dir,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let txn = Transaction::new(self.ndb).expect("txn");
+        let Ok(note) = self.ndb.get_note_by_id(&txn, self.note_id.bytes()) else {
+            ui.label(tr!("poster-note-not-found"));
+            return;
+        };
+
+        let id = ui.id().with(("poster", self.note_id));
+        let pending_id = id.with("pending");
+        let status_id = id.with("status");
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            Frame::none()
+                .inner_margin(egui::Margin::same(16.0))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.button(tr!("poster-save")).clicked() {
+                            request_export(ui, pending_id, Export::Save);
+                        }
+
+                        #[cfg(not(target_os = "android"))]
+                        if ui.button(tr!("poster-copy")).clicked() {
+                            request_export(ui, pending_id, Export::Copy);
+                        }
+                    });
+
+                    if let Some(status) = ui.data(|d| d.get_temp::<String>(status_id)) {
+                        ui.label(RichText::new(status).weak());
+                    }
+
+                    ui.add_space(12.0);
+
+                    let card = self.card(ui, &txn, &note);
+
+                    let pending = ui.data(|d| d.get_temp::<Export>(pending_id));
+                    if let (Some(export), Some(screenshot)) = (pending, take_screenshot(ui)) {
+                        ui.data_mut(|d| d.remove::<Export>(pending_id));
+                        let image = screenshot.region(&card, Some(ui.ctx().pixels_per_point()));
+                        let status = self.export(export, &image);
+                        ui.data_mut(|d| d.insert_temp(status_id, status));
+                    }
+                });
+        });
+    }
+
+    fn card(&mut self, ui: &mut egui::Ui, txn: &Transaction, note: &Note<'_>) -> Rect {
+        Frame::none()
+            .fill(ui.visuals().extreme_bg_color)
+            .rounding(12.0)
+            .inner_margin(egui::Margin::same(20.0))
+            .show(ui, |ui| {
+                ui.set_width(POSTER_WIDTH);
+
+                if let Some(hero) = hero_image(note.content()) {
+                    self.hero(ui, hero);
+                    ui.add_space(12.0);
+                }
+
+                let profile = self.ndb.get_profile_by_pubkey(txn, note.pubkey()).ok();
+                ui.horizontal(|ui| {
+                    let pfp = profile
+                        .as_ref()
+                        .and_then(|p| p.record().profile())
+                        .and_then(|p| p.picture())
+                        .unwrap_or(ProfilePic::no_pfp_url());
+                    ui.add(ProfilePic::new(self.img_cache, pfp).size(ProfilePic::medium_size()));
+                    ui.label(RichText::new(get_display_name(profile.as_ref()).name()).strong());
+                });
+
+                ui.add_space(8.0);
+                let text = poster_text(note.content());
+                let font_id = egui::FontId::proportional(17.0);
+                let color = ui.visuals().text_color();
+                let width = ui.available_width();
+                let galley = ui.fonts(|fonts| {
+                    text::clamped_galley(fonts, &text, font_id, color, width, MAX_POSTER_ROWS)
+                });
+                ui.add(Label::new(galley));
+                ui.add_space(12.0);
+
+                ui.label(
+                    RichText::new(datetime::settings().format_datetime(note.created_at())).weak(),
+                );
+                if let Some(location) = location(note) {
+                    ui.label(RichText::new(location).weak());
+                }
+
+                if let Some(bech) = self.note_id.to_bech() {
+                    ui.add_space(12.0);
+                    ui.vertical_centered(|ui| {
+                        ui.add(QrCodeView::new(&format!("nostr:{bech}")).size(140.0));
+                    });
+                }
+            })
+            .response
+            .rect
+    }
+
+    fn hero(&mut self, ui: &mut egui::Ui, url: &str) {
+        if self.img_cache.map().get(url).is_none() {
+            let promise = crate::images::fetch_img(
+                self.img_cache,
+                ui.ctx(),
+                url,
+                ImageType::Content(POSTER_WIDTH as u32, POSTER_WIDTH as u32),
+            );
+            self.img_cache.map_mut().insert(url.to_owned(), promise);
+        }
+
+        match self.img_cache.get(url).and_then(|promise| promise.ready()) {
+            Some(Ok(texture)) => {
+                ui.add(Image::new(texture).max_width(POSTER_WIDTH).rounding(8.0));
+            }
+            Some(Err(_)) => {}
+            None => {
+                ui.add_sized([POSTER_WIDTH, POSTER_WIDTH / 2.0], egui::Spinner::new());
+            }
+        }
+    }
+
+    fn export(&self, export: Export, image: &ColorImage) -> String {
+        match export {
+            Export::Save => match save_png(image, &self.export_dir(), self.note_id) {
+                Ok(path) => tr!("poster-saved", path = path.display().to_string()),
+                Err(err) => {
+                    error!("failed to save poster: {err}");
+                    tr!("poster-save-failed", error = err)
+                }
+            },
+            #[cfg(not(target_os = "android"))]
+            Export::Copy => match copy_image(image) {
+                Ok(()) => tr!("poster-copied"),
+                Err(err) => {
+                    error!("failed to copy poster: {err}");
+                    tr!("poster-copy-failed", error = err)
+                }
+            },
+        }
+    }
+
+    fn export_dir(&self) -> PathBuf {
+        dirs::download_dir()
+            .or_else(dirs::picture_dir)
+            .unwrap_or_else(|| self.fallback_dir.to_owned())
+    }
+}
+
+fn request_export(ui: &egui::Ui, pending_id: egui::Id, export: Export) {
+    ui.data_mut(|d| d.insert_temp(pending_id, export));
+    ui.ctx()
+        .send_viewport_cmd(egui::ViewportCommand::Screenshot);
+}
+
+fn take_screenshot(ui: &egui::Ui) -> Option<Arc<ColorImage>> {
+    ui.input(|i| {
+        i.raw.events.iter().find_map(|event| match event {
+            egui::Event::Screenshot { image, .. } => Some(image.clone()),
+            _ => None,
+        })
+    })
+}
+
+fn hero_image(content: &str) -> Option<&str> {
+    content
+        .split_whitespace()
+        .find(|word| word.starts_with("https://") && is_image_link(&word.to_lowercase()))
+}
+
+/// The note text without image links
+fn poster_text(content: &str) -> String {
+    content
+        .split(' ')
+        .filter(|word| !is_image_link(&word.trim().to_lowercase()))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim()
+        .to_owned()
+}
+
+fn location<'a>(note: &Note<'a>) -> Option<&'a str> {
+    note.tags()
+        .iter()
+        .filter(|tag| tag.count() >= 2)
+        .find(|tag| tag.get_unchecked(0).variant().str() == Some("location"))
+        .and_then(|tag| tag.get_unchecked(1).variant().str())
+}
+
+fn save_png(image: &ColorImage, dir: &Path, note_id: &NoteId) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let hex = note_id.hex();
+    let path = dir.join(format!("notedeck-{}.png", &hex[..12]));
+    image::save_buffer(
+        &path,
+        image.as_raw(),
+        image.width() as u32,
+        image.height() as u32,
+        image::ExtendedColorType::Rgba8,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+#[cfg(not(target_os = "android"))]
+fn copy_image(image: &ColorImage) -> Result<(), String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    clipboard
+        .set_image(arboard::ImageData {
+            width: image.width(),
+            height: image.height(),
+            bytes: std::borrow::Cow::Borrowed(image.as_raw()),
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poster_text() {
+        let content = "meetup tonight https://example.com/flyer.png see you there";
+        assert_eq!(hero_image(content), Some("https://example.com/flyer.png"));
+        assert_eq!(poster_text(content), "meetup tonight see you there");
+        assert_eq!(hero_image("no pictures"), None);
+    }
+}
@@ -0,0 +1,173 @@
+use egui::PopupCloseBehavior;
+use enostr::Pubkey;
+use nostrdb::{Ndb, ProfileRecord, Transaction};
+use notedeck::{contacts, tr, ImageCache};
+
+use super::{about_section_widget, banner, display_name_widget, get_display_name, get_profile_url};
+use crate::ui::ProfilePic;
+
+const CARD_WIDTH: f32 = 300.0;
+
+/// Something picked from a profile card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileCardAction {
+    OpenProfile(Pubkey),
+    Follow(Pubkey),
+    Unfollow(Pubkey),
+}
+
+fn viewer_id() -> egui::Id {
+    egui::Id::new("profile-card-viewer")
+}
+
+fn follow_id() -> egui::Id {
+    egui::Id::new("profile-card-follow")
+}
+
+/// Let cards know which account is looking at them, so they can offer to
+/// follow. `None` when the account can't sign.
+pub fn set_viewer(ctx: &egui::Context, viewer: Option<Pubkey>) {
+    ctx.data_mut(|d| d.insert_temp(viewer_id(), viewer));
+}
+
+/// The follow or unfollow picked from a card this frame, if any
+pub fn take_follow(ctx: &egui::Context) -> Option<ProfileCardAction> {
+    ctx.data_mut(|d| d.remove_temp::<ProfileCardAction>(follow_id()))
+}
+
+/// Someone's banner, picture, name and about, with buttons to open their
+/// profile and follow them
+pub struct ProfileCard<'a, 'cache> {
+    pubkey: &'a [u8; 32],
+    profile: Option<&'a ProfileRecord<'a>>,
+    img_cache: &'cache mut ImageCache,
+    following: Option<bool>,
+}
+
+impl<'a, 'cache> ProfileCard<'a, 'cache> {
+    pub fn new(
+        pubkey: &'a [u8; 32],
+        profile: Option<&'a ProfileRecord<'a>>,
+        img_cache: &'cache mut ImageCache,
+    ) -> Self {
+        ProfileCard {
+            pubkey,
+            profile,
+            img_cache,
+            following: None,
+        }
+    }
+
+    /// Whether the viewer follows them. The follow button is left out
+    /// when we don't know.
+    pub fn following(mut self, following: Option<bool>) -> Self {
+        self.following = following;
+        self
+    }
+
+    pub fn ui(self, ui: &mut egui::Ui) -> Option<ProfileCardAction> {
+        let pubkey = Pubkey::new(*self.pubkey);
+        let mut action = None;
+
+        ui.set_width(CARD_WIDTH);
+        ui.vertical(|ui| {
+            banner(
+                ui,
+                self.profile
+                    .and_then(|p| p.record().profile())
+                    .and_then(|p| p.banner()),
+                80.0,
+            );
+
+            crate::ui::padding(12.0, ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        ProfilePic::new(self.img_cache, get_profile_url(self.profile)).size(48.0),
+                    );
+                    ui.add(display_name_widget(
+                        get_display_name(self.profile),
+                        Some(self.pubkey),
+                        false,
+                    ));
+                });
+
+                if let Some(profile) = self.profile {
+                    ui.add(about_section_widget(profile));
+                }
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("profile-card-open")).clicked() {
+                        action = Some(ProfileCardAction::OpenProfile(pubkey));
+                    }
+
+                    match self.following {
+                        Some(true) => {
+                            if ui.button(tr!("profile-card-unfollow")).clicked() {
+                                action = Some(ProfileCardAction::Unfollow(pubkey));
+                            }
+                        }
+                        Some(false) => {
+                            if ui.button(tr!("profile-card-follow")).clicked() {
+                                action = Some(ProfileCardAction::Follow(pubkey));
+                            }
+                        }
+                        None => {}
+                    }
+                });
+            });
+        });
+
+        action
+    }
+}
+
+/// Show a card for `pubkey` below `resp` when it's clicked. Opening the
+/// profile is handed back to the caller, follows go to the app through
+/// [`take_follow`].
+pub fn profile_card_popup(
+    ui: &mut egui::Ui,
+    resp: &egui::Response,
+    ndb: &Ndb,
+    txn: &Transaction,
+    img_cache: &mut ImageCache,
+    pubkey: &[u8; 32],
+) -> Option<Pubkey> {
+    let popup_id = resp.id.with("profile-card");
+    if resp.clicked() {
+        ui.memory_mut(|m| m.toggle_popup(popup_id));
+    }
+    if !ui.memory(|m| m.is_popup_open(popup_id)) {
+        return None;
+    }
+
+    let profile = ndb.get_profile_by_pubkey(txn, pubkey).ok();
+    let viewer = ui
+        .data(|d| d.get_temp::<Option<Pubkey>>(viewer_id()))
+        .flatten();
+    let following = viewer
+        .filter(|viewer| viewer.bytes() != pubkey)
+        .and_then(|viewer| contacts::follows(ndb, txn, viewer.bytes(), pubkey));
+
+    let action = egui::popup_below_widget(
+        ui,
+        popup_id,
+        resp,
+        PopupCloseBehavior::CloseOnClickOutside,
+        |ui| {
+            ProfileCard::new(pubkey, profile.as_ref(), img_cache)
+                .following(following)
+                .ui(ui)
+        },
+    )
+    .flatten()?;
+
+    ui.memory_mut(|m| m.close_popup());
+    match action {
+        ProfileCardAction::OpenProfile(pubkey) => Some(pubkey),
+        follow => {
+            ui.data_mut(|d| d.insert_temp(follow_id(), follow));
+            None
+        }
+    }
+}
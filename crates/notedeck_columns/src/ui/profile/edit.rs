@@ -87,7 +87,7 @@ impl<'a> EditProfileView<'a> {
 
         in_frame(ui, |ui| {
             ui.add(label("About"));
-            ui.add(multiline_textedit(&mut self.state.about));
+            ui.add(spellchecked_textedit(&mut self.state.about));
         });
 
         in_frame(ui, |ui| {
@@ -144,7 +144,7 @@ fn singleline_textedit(data: &mut String) -> impl egui::Widget + '_ {
         .desired_width(f32::INFINITY)
 }
 
-fn multiline_textedit(data: &mut String) -> impl egui::Widget + '_ {
+fn multiline_textedit(data: &mut String) -> TextEdit<'_> {
     TextEdit::multiline(data)
         // .min_size(vec2(0.0, 40.0))
         .vertical_align(egui::Align::TOP)
@@ -153,6 +153,16 @@ fn multiline_textedit(data: &mut String) -> impl egui::Widget + '_ {
         .desired_rows(1)
 }
 
+fn spellchecked_textedit(data: &mut String) -> impl egui::Widget + '_ {
+    move |ui: &mut egui::Ui| -> egui::Response {
+        let mut output = multiline_textedit(data).show(ui);
+        if notedeck::ui::spellcheck(ui, &output, data) {
+            output.response.mark_changed();
+        }
+        output.response
+    }
+}
+
 fn in_frame(ui: &mut egui::Ui, contents: impl FnOnce(&mut egui::Ui)) {
     egui::Frame::none().show(ui, |ui| {
         ui.spacing_mut().item_spacing = egui::vec2(0.0, 8.0);
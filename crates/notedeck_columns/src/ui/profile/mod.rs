@@ -1,9 +1,9 @@
+pub mod card;
 pub mod edit;
 pub mod picture;
 pub mod preview;
 
 pub use edit::EditProfileView;
-use egui::load::TexturePoll;
 use egui::{vec2, Color32, Label, Layout, Rect, RichText, Rounding, ScrollArea, Sense, Stroke};
 use enostr::{Pubkey, PubkeyRef};
 use nostrdb::{Ndb, ProfileRecord, Transaction};
@@ -14,16 +14,21 @@ use tracing::error;
 use crate::{
     actionbar::NoteAction,
     colors, images,
+    moderation::ReportTarget,
+    nip05::{self, Nip05Status},
     profile::get_display_name,
-    timeline::{TimelineCache, TimelineCacheKey},
+    timeline::{TimelineCache, TimelineCacheKey, ViewFilter},
     ui::{
+        a11y,
+        media::{MediaGrid, MediaViewer},
         note::NoteOptions,
+        qr::QrCodeView,
         timeline::{tabs_ui, TimelineTabView},
     },
     NostrName,
 };
 
-use notedeck::{Accounts, ImageCache, MuteFun, NoteCache, NotedeckTextStyle, UnknownIds};
+use notedeck::{tr, Accounts, ImageCache, MuteFun, NoteCache, NotedeckTextStyle, UnknownIds};
 
 pub struct ProfileView<'a> {
     pubkey: &'a Pubkey,
@@ -38,6 +43,13 @@ pub struct ProfileView<'a> {
     is_muted: &'a MuteFun,
 }
 
+/// Which QR code is showing under a profile
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ProfileQr {
+    Pubkey,
+    Lightning,
+}
+
 pub enum ProfileViewAction {
     EditProfile,
     Note(NoteAction),
@@ -80,9 +92,7 @@ impl<'a> ProfileView<'a> {
                 let mut action = None;
                 let txn = Transaction::new(self.ndb).expect("txn");
                 if let Ok(profile) = self.ndb.get_profile_by_pubkey(&txn, self.pubkey.bytes()) {
-                    if self.profile_body(ui, profile) {
-                        action = Some(ProfileViewAction::EditProfile);
-                    }
+                    action = self.profile_body(ui, profile);
                 }
                 let profile_timeline = self
                     .timeline_cache
@@ -109,18 +119,32 @@ impl<'a> ProfileView<'a> {
                     error!("Profile::poll_notes_into_view: {e}");
                 }
 
-                if let Some(note_action) = TimelineTabView::new(
-                    profile_timeline.current_view(),
-                    reversed,
-                    self.note_options,
-                    &txn,
-                    self.ndb,
-                    self.note_cache,
-                    self.img_cache,
-                    self.is_muted,
-                )
-                .show(ui)
-                {
+                let note_action = if profile_timeline.current_view().filter == ViewFilter::Media {
+                    MediaGrid::new(
+                        profile_timeline.view_id(),
+                        &profile_timeline.current_view().notes,
+                        &txn,
+                        self.ndb,
+                        self.note_cache,
+                        self.img_cache,
+                        self.is_muted,
+                    )
+                    .show(ui)
+                } else {
+                    TimelineTabView::new(
+                        profile_timeline.current_view(),
+                        reversed,
+                        self.note_options,
+                        &txn,
+                        self.ndb,
+                        self.note_cache,
+                        self.img_cache,
+                        self.is_muted,
+                    )
+                    .show(ui)
+                };
+
+                if let Some(note_action) = note_action {
                     action = Some(ProfileViewAction::Note(note_action));
                 }
 
@@ -129,14 +153,19 @@ impl<'a> ProfileView<'a> {
             .inner
     }
 
-    fn profile_body(&mut self, ui: &mut egui::Ui, profile: ProfileRecord<'_>) -> bool {
-        let mut action = false;
+    fn profile_body(
+        &mut self,
+        ui: &mut egui::Ui,
+        profile: ProfileRecord<'_>,
+    ) -> Option<ProfileViewAction> {
+        let mut action = None;
         ui.vertical(|ui| {
-            banner(
-                ui,
-                profile.record().profile().and_then(|p| p.banner()),
-                120.0,
-            );
+            let banner_url = profile.record().profile().and_then(|p| p.banner());
+            if banner(ui, banner_url, 120.0).clicked() {
+                if let Some(url) = banner_url.filter(|url| !url.is_empty()) {
+                    MediaViewer::open_image(ui.ctx(), url);
+                }
+            }
 
             let padding = 12.0;
             crate::ui::padding(padding, ui, |ui| {
@@ -166,7 +195,15 @@ impl<'a> ProfileView<'a> {
                     if self.accounts.contains_full_kp(self.pubkey) {
                         ui.with_layout(Layout::right_to_left(egui::Align::Max), |ui| {
                             if ui.add(edit_profile_button()).clicked() {
-                                action = true;
+                                action = Some(ProfileViewAction::EditProfile);
+                            }
+                        });
+                    } else {
+                        ui.with_layout(Layout::right_to_left(egui::Align::Max), |ui| {
+                            if ui.button(tr!("profile-report")).clicked() {
+                                action = Some(ProfileViewAction::Note(NoteAction::Report(
+                                    ReportTarget::profile(*self.pubkey),
+                                )));
                             }
                         });
                     }
@@ -174,12 +211,22 @@ impl<'a> ProfileView<'a> {
 
                 ui.add_space(18.0);
 
-                ui.add(display_name_widget(get_display_name(Some(&profile)), false));
+                ui.add(display_name_widget(
+                    get_display_name(Some(&profile)),
+                    Some(self.pubkey.bytes()),
+                    false,
+                ));
 
                 ui.add_space(8.0);
 
                 ui.add(about_section_widget(&profile));
 
+                let qr_id = egui::Id::new(("profile_qr", self.pubkey));
+                let mut shown_qr = ui.data(|d| d.get_temp::<ProfileQr>(qr_id));
+                let toggle = |shown: &mut Option<ProfileQr>, qr: ProfileQr| {
+                    *shown = if *shown == Some(qr) { None } else { Some(qr) };
+                };
+
                 ui.horizontal_wrapped(|ui| {
                     if let Some(website_url) = profile
                         .record()
@@ -196,8 +243,34 @@ impl<'a> ProfileView<'a> {
                         .and_then(|p| p.lud16())
                         .filter(|s| !s.is_empty())
                     {
-                        handle_lud16(ui, lud16);
+                        if handle_lud16(ui, lud16) {
+                            toggle(&mut shown_qr, ProfileQr::Lightning);
+                        }
                     }
+
+                    if qr_button(ui).clicked() {
+                        toggle(&mut shown_qr, ProfileQr::Pubkey);
+                    }
+                });
+
+                let qr_data = match shown_qr {
+                    Some(ProfileQr::Pubkey) => self.pubkey.to_bech().map(|b| format!("nostr:{b}")),
+                    Some(ProfileQr::Lightning) => profile
+                        .record()
+                        .profile()
+                        .and_then(|p| p.lud16())
+                        .map(|lud16| format!("lightning:{lud16}")),
+                    None => None,
+                };
+
+                if let Some(data) = qr_data {
+                    ui.add_space(8.0);
+                    ui.add(QrCodeView::new(&data));
+                }
+
+                ui.data_mut(|d| match shown_qr {
+                    Some(qr) => d.insert_temp(qr_id, qr),
+                    None => d.remove::<ProfileQr>(qr_id),
                 });
             });
         });
@@ -222,12 +295,23 @@ fn handle_link(ui: &mut egui::Ui, website_url: &str) {
     }
 }
 
-fn handle_lud16(ui: &mut egui::Ui, lud16: &str) {
+/// Returns true when clicked, to show its QR code
+fn handle_lud16(ui: &mut egui::Ui, lud16: &str) -> bool {
     ui.image(egui::include_image!(
         "../../../../../assets/icons/zap_4x.png"
     ));
 
-    let _ = ui.label(RichText::new(lud16).color(colors::PINK));
+    ui.label(RichText::new(lud16).color(colors::PINK))
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+        .on_hover_text(tr!("profile-show-qr"))
+        .interact(Sense::click())
+        .clicked()
+}
+
+fn qr_button(ui: &mut egui::Ui) -> egui::Response {
+    ui.label(RichText::new(tr!("profile-qr-code")).color(colors::PINK))
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+        .interact(Sense::click())
 }
 
 fn copy_key_widget(pfp_rect: &egui::Rect) -> impl egui::Widget + '_ {
@@ -242,6 +326,7 @@ fn copy_key_widget(pfp_rect: &egui::Rect) -> impl egui::Widget + '_ {
             ui.id().with("custom_painter"),
             Sense::click(),
         );
+        a11y::button(ui, &resp, "Copy public key");
 
         let copy_key_rounding = Rounding::same(100.0);
         let fill_color = if resp.hovered() {
@@ -275,6 +360,7 @@ fn copy_key_widget(pfp_rect: &egui::Rect) -> impl egui::Widget + '_ {
 fn edit_profile_button() -> impl egui::Widget + 'static {
     |ui: &mut egui::Ui| -> egui::Response {
         let (rect, resp) = ui.allocate_exact_size(vec2(124.0, 32.0), Sense::click());
+        a11y::button(ui, &resp, "Edit profile");
         let painter = ui.painter_at(rect);
         let rect = painter.round_rect_to_pixels(rect);
 
@@ -332,12 +418,18 @@ fn edit_profile_button() -> impl egui::Widget + 'static {
     }
 }
 
-fn display_name_widget(name: NostrName<'_>, add_placeholder_space: bool) -> impl egui::Widget + '_ {
+/// `pubkey` is needed to show whether their nip05 checks out
+fn display_name_widget<'a>(
+    name: NostrName<'a>,
+    pubkey: Option<&'a [u8; 32]>,
+    add_placeholder_space: bool,
+) -> impl egui::Widget + 'a {
     move |ui: &mut egui::Ui| -> egui::Response {
         let disp_resp = name.display_name.map(|disp_name| {
             ui.add(
                 Label::new(
-                    RichText::new(disp_name).text_style(NotedeckTextStyle::Heading3.text_style()),
+                    RichText::new(notedeck::text::prepare(disp_name))
+                        .text_style(NotedeckTextStyle::Heading3.text_style()),
                 )
                 .selectable(false),
             )
@@ -357,12 +449,16 @@ fn display_name_widget(name: NostrName<'_>, add_placeholder_space: bool) -> impl
                 });
 
                 let nip05_resp = name.nip05.map(|nip05| {
-                    ui.image(egui::include_image!(
-                        "../../../../../assets/icons/verified_4x.png"
-                    ));
-                    ui.add(Label::new(
-                        RichText::new(nip05).size(16.0).color(colors::TEAL),
-                    ))
+                    let status = pubkey.map(|pk| nip05::status(ui.ctx(), pk, nip05));
+                    if let Some(pubkey) = pubkey {
+                        crate::ui::nip05::badge(ui, pubkey, nip05, 16.0);
+                    }
+                    let color = if status == Some(Nip05Status::Verified) {
+                        colors::TEAL
+                    } else {
+                        colors::MID_GRAY
+                    };
+                    ui.add(Label::new(RichText::new(nip05).size(16.0).color(color)))
                 });
 
                 (username_resp, nip05_resp)
@@ -404,7 +500,7 @@ where
 {
     move |ui: &mut egui::Ui| {
         if let Some(about) = profile.record().profile().and_then(|p| p.about()) {
-            let resp = ui.label(about);
+            let resp = notedeck::ui::safe_markdown(ui, about);
             ui.add_space(8.0);
             resp
         } else {
@@ -416,18 +512,20 @@ where
 
 fn banner_texture(ui: &mut egui::Ui, banner_url: &str) -> Option<egui::load::SizedTexture> {
     // TODO: cache banner
-    if !banner_url.is_empty() {
-        let texture_load_res =
-            egui::Image::new(banner_url).load_for_size(ui.ctx(), ui.available_size());
-        if let Ok(texture_poll) = texture_load_res {
-            match texture_poll {
-                TexturePoll::Pending { .. } => {}
-                TexturePoll::Ready { texture, .. } => return Some(texture),
-            }
-        }
+    if banner_url.is_empty() {
+        return None;
     }
 
-    None
+    images::load_texture(
+        ui,
+        banner_url,
+        ui.available_size(),
+        banner_hover_id(banner_url),
+    )
+}
+
+fn banner_hover_id(banner_url: &str) -> egui::Id {
+    egui::Id::new(("banner-hovered", banner_url))
 }
 
 fn banner(ui: &mut egui::Ui, banner_url: Option<&str>, height: f32) -> egui::Response {
@@ -435,12 +533,16 @@ fn banner(ui: &mut egui::Ui, banner_url: Option<&str>, height: f32) -> egui::Res
         banner_url
             .and_then(|url| banner_texture(ui, url))
             .map(|texture| {
-                images::aspect_fill(
+                let resp = images::aspect_fill(
                     ui,
-                    Sense::hover(),
+                    Sense::click(),
                     texture.id,
                     texture.size.x / texture.size.y,
-                )
+                );
+                if let Some(url) = banner_url {
+                    images::set_hovered(ui.ctx(), banner_hover_id(url), resp.hovered());
+                }
+                resp
             })
             .unwrap_or_else(|| ui.label(""))
     })
@@ -1,5 +1,5 @@
 use crate::images::ImageType;
-use crate::ui::{Preview, PreviewConfig};
+use crate::ui::{a11y, Preview, PreviewConfig};
 use egui::{vec2, Sense, TextureHandle};
 use nostrdb::{Ndb, Transaction};
 use tracing::info;
@@ -14,7 +14,9 @@ pub struct ProfilePic<'cache, 'url> {
 
 impl egui::Widget for ProfilePic<'_, '_> {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
-        render_pfp(ui, self.cache, self.url, self.size)
+        let resp = render_pfp(ui, self.cache, self.url, self.size);
+        a11y::label(&resp, egui::WidgetType::Image, "Profile picture");
+        resp
     }
 }
 
@@ -80,7 +82,7 @@ fn render_pfp(
         img_cache.map_mut().insert(url.to_owned(), res);
     }
 
-    match img_cache.map()[url].ready() {
+    match img_cache.get(url).and_then(|promise| promise.ready()) {
         None => paint_circle(ui, ui_size),
 
         // Failed to fetch profile!
@@ -96,7 +98,7 @@ fn render_pfp(
                 img_cache.map_mut().insert(url.to_owned(), no_pfp);
             }
 
-            match img_cache.map().get(url).unwrap().ready() {
+            match img_cache.get(url).and_then(|promise| promise.ready()) {
                 None => paint_circle(ui, ui_size),
                 Some(Err(_e)) => {
                     //error!("Image load error: {:?}", e);
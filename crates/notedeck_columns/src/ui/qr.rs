@@ -0,0 +1,59 @@
+use egui::{Color32, Rect, Sense, Vec2};
+use qrcode::{Color, QrCode};
+
+/// Modules of quiet zone around the code, most scanners want at least 4
+const QUIET_ZONE: usize = 4;
+
+/// A QR code painted as a square of `size` points. Always black on white so
+/// it scans in dark mode too.
+pub struct QrCodeView<'a> {
+    data: &'a str,
+    size: f32,
+}
+
+impl<'a> QrCodeView<'a> {
+    pub fn new(data: &'a str) -> Self {
+        QrCodeView { data, size: 160.0 }
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+impl egui::Widget for QrCodeView<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(self.size), Sense::hover());
+
+        let Ok(code) = QrCode::new(self.data.as_bytes()) else {
+            ui.painter().rect_filled(rect, 0.0, Color32::WHITE);
+            return response;
+        };
+
+        paint(ui.painter(), rect, &code);
+        crate::ui::a11y::label(&response, egui::WidgetType::Image, "QR code");
+        response
+    }
+}
+
+fn paint(painter: &egui::Painter, rect: Rect, code: &QrCode) {
+    let width = code.width();
+    let modules = width + QUIET_ZONE * 2;
+    let module = rect.width() / modules as f32;
+
+    painter.rect_filled(rect, 0.0, Color32::WHITE);
+
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Light {
+            continue;
+        }
+
+        let x = (i % width + QUIET_ZONE) as f32;
+        let y = (i / width + QUIET_ZONE) as f32;
+        let min = rect.min + Vec2::new(x, y) * module;
+        // a hair of overlap so there are no seams between modules
+        let dark = Rect::from_min_size(min, Vec2::splat(module + 0.5));
+        painter.rect_filled(dark, 0.0, Color32::BLACK);
+    }
+}
@@ -1,32 +1,108 @@
-use crate::relay_pool_manager::{RelayPoolManager, RelayStatus};
-use crate::ui::{Preview, PreviewConfig, View};
+use crate::relay_info::RelayInfoCache;
+use crate::relay_pool_manager::{RelayInfo, RelayPoolManager, RelayStatus};
+use crate::ui::{Preview, PreviewConfig};
 use egui::{Align, Button, Frame, Layout, Margin, Rgba, RichText, Rounding, Ui, Vec2};
 
-use enostr::RelayPool;
-use notedeck::NotedeckTextStyle;
+use enostr::{Pubkey, RelayPool};
+use notedeck::{tr, NotedeckTextStyle, RelaySpec, SessionCapability};
+use std::collections::HashMap;
 
 pub struct RelayView<'a> {
     manager: RelayPoolManager<'a>,
+    session: SessionCapability,
+    id_string_map: &'a mut HashMap<egui::Id, String>,
+    draft: &'a mut RelayListDraft,
+    relay_info: &'a mut RelayInfoCache,
+    advertised: Vec<RelaySpec>,
+    local: Vec<String>,
 }
 
-impl View for RelayView<'_> {
-    fn ui(&mut self, ui: &mut egui::Ui) {
+pub enum RelayAction {
+    /// Use a relay without advertising it
+    AddLocal(String),
+    RemoveLocal(String),
+    /// Publish a new NIP-65 relay list
+    Publish(Vec<RelaySpec>),
+    RelayGroups,
+}
+
+/// Unpublished edits to the selected account's relay list
+#[derive(Default)]
+pub struct RelayListDraft {
+    account: Option<Pubkey>,
+    relays: Option<Vec<RelaySpec>>,
+}
+
+impl RelayListDraft {
+    fn relays(&mut self, account: Option<Pubkey>, advertised: &[RelaySpec]) -> &mut Vec<RelaySpec> {
+        if self.account != account {
+            self.account = account;
+            self.relays = None;
+        }
+        self.relays.get_or_insert_with(|| advertised.to_vec())
+    }
+
+    fn is_dirty(&self, advertised: &[RelaySpec]) -> bool {
+        self.relays
+            .as_ref()
+            .is_some_and(|relays| relays.as_slice() != advertised)
+    }
+
+    pub fn clear(&mut self) {
+        self.relays = None;
+    }
+}
+
+impl<'a> RelayView<'a> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        manager: RelayPoolManager<'a>,
+        session: SessionCapability,
+        id_string_map: &'a mut HashMap<egui::Id, String>,
+        draft: &'a mut RelayListDraft,
+        relay_info: &'a mut RelayInfoCache,
+        account: Option<Pubkey>,
+        advertised: Vec<RelaySpec>,
+        local: Vec<String>,
+    ) -> Self {
+        // start over if the account changed under us
+        draft.relays(account, &advertised);
+
+        RelayView {
+            manager,
+            session,
+            id_string_map,
+            draft,
+            relay_info,
+            advertised,
+            local,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<RelayAction> {
         ui.add_space(24.0);
 
-        ui.horizontal(|ui| {
-            ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
-                ui.label(
-                    RichText::new("Relays").text_style(NotedeckTextStyle::Heading2.text_style()),
-                );
-            });
+        let header_action = ui
+            .horizontal(|ui| {
+                ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+                    ui.label(
+                        RichText::new(tr!("relays-title"))
+                            .text_style(NotedeckTextStyle::Heading2.text_style()),
+                    );
+                });
 
-            // TODO: implement manually adding relays
-            // ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-            //     if ui.add(add_relay_button()).clicked() {
-            //         // TODO: navigate to 'add relay view'
-            //     };
-            // });
-        });
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    ui.button(tr!("relays-groups"))
+                        .clicked()
+                        .then_some(RelayAction::RelayGroups)
+                })
+                .inner
+            })
+            .inner;
+
+        ui.add_space(8.0);
+
+        let mut action = self.add_relay_ui(ui).or(header_action);
 
         ui.add_space(8.0);
 
@@ -34,85 +110,326 @@ impl View for RelayView<'_> {
             .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysHidden)
             .auto_shrink([false; 2])
             .show(ui, |ui| {
-                if let Some(indices) = self.show_relays(ui) {
-                    self.manager.remove_relays(indices);
+                if let Some(list_action) = self.relay_list_ui(ui) {
+                    action = Some(list_action);
                 }
+
+                if let Some(local_action) = self.local_relays_ui(ui) {
+                    action = Some(local_action);
+                }
+
+                self.other_relays_ui(ui);
             });
+
+        action
     }
-}
 
-impl<'a> RelayView<'a> {
-    pub fn new(manager: RelayPoolManager<'a>) -> Self {
-        RelayView { manager }
+    fn add_relay_ui(&mut self, ui: &mut Ui) -> Option<RelayAction> {
+        let missing = self.session.missing(SessionCapability::PubkeyOnly);
+        let id = ui.id().with("add-relay");
+        let text_buffer = self.id_string_map.entry(id).or_default();
+
+        let submitted = ui
+            .horizontal(|ui| {
+                let input = ui.add_enabled(
+                    missing.is_none(),
+                    egui::TextEdit::singleline(text_buffer)
+                        .hint_text("wss://relay.example.com")
+                        .vertical_align(Align::Center)
+                        .desired_width(ui.available_width() - 120.0)
+                        .min_size(Vec2::new(0.0, 32.0)),
+                );
+                let entered = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                let button = ui.add_enabled(missing.is_none(), add_relay_button());
+                let button = if let Some(reason) = &missing {
+                    button.on_disabled_hover_text(reason)
+                } else {
+                    button
+                };
+
+                entered || button.clicked()
+            })
+            .inner;
+
+        let typed = normalize_relay_url(text_buffer);
+
+        // preview what they're about to add once they stop typing
+        if let Some(url) = &typed {
+            let stopped_typing = ui.input(|i| i.time - i.last_key_time().unwrap_or(0.0)) > 0.5;
+            if stopped_typing {
+                if let Some(summary) = self.relay_info.get(ui.ctx(), url).and_then(|d| d.summary())
+                {
+                    ui.label(RichText::new(summary).weak());
+                }
+            } else {
+                ui.ctx()
+                    .request_repaint_after(std::time::Duration::from_millis(500));
+            }
+        }
+
+        if !submitted {
+            return None;
+        }
+
+        let url = typed?;
+        self.id_string_map.remove(&id);
+
+        // people who can sign get it added to their relay list, everyone
+        // else just uses it locally
+        if self.session.can_sign() {
+            let account = self.draft.account;
+            let relays = self.draft.relays(account, &self.advertised);
+            if !relays.iter().any(|r| r.url == url) {
+                relays.push(RelaySpec::new(&url, true, true));
+            }
+            None
+        } else {
+            Some(RelayAction::AddLocal(url))
+        }
     }
 
-    pub fn panel(&mut self, ui: &mut egui::Ui) {
-        egui::CentralPanel::default().show(ui.ctx(), |ui| self.ui(ui));
+    pub fn panel(&mut self, ui: &mut egui::Ui) -> Option<RelayAction> {
+        egui::CentralPanel::default()
+            .show(ui.ctx(), |ui| self.ui(ui))
+            .inner
     }
 
-    /// Show the current relays, and returns the indices of relays the user requested to delete
-    fn show_relays(&'a self, ui: &mut Ui) -> Option<Vec<usize>> {
-        let mut indices_to_remove: Option<Vec<usize>> = None;
-        for (index, relay_info) in self.manager.get_relay_infos().iter().enumerate() {
+    /// The NIP-65 relay list, editable if we can sign a new one
+    fn relay_list_ui(&mut self, ui: &mut Ui) -> Option<RelayAction> {
+        let can_edit = self.session.can_sign();
+        let dirty = self.draft.is_dirty(&self.advertised);
+        let infos = self.manager.get_relay_infos();
+
+        section(ui, &tr!("relays-relay-list"));
+        if !can_edit {
+            ui.label(RichText::new(tr!("relays-relay-list-read-only")).weak());
+        }
+
+        let account = self.draft.account;
+        let relays = self.draft.relays(account, &self.advertised);
+        if relays.is_empty() {
+            ui.label(RichText::new(tr!("relays-relay-list-empty")).weak());
+        }
+
+        let mut remove = None;
+        for (index, relay) in relays.iter_mut().enumerate() {
             ui.add_space(8.0);
-            ui.vertical_centered_justified(|ui| {
-                relay_frame(ui).show(ui, |ui| {
-                    ui.horizontal(|ui| {
-                        ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
-                            Frame::none()
-                                // This frame is needed to add margin because the label will be added to the outer frame first and centered vertically before the connection status is added so the vertical centering isn't accurate.
-                                // TODO: remove this hack and actually center the url & status at the same time
-                                .inner_margin(Margin::symmetric(0.0, 4.0))
-                                .show(ui, |ui| {
-                                    egui::ScrollArea::horizontal()
-                                        .id_salt(index)
-                                        .max_width(
-                                            ui.max_rect().width()
-                                                - get_right_side_width(relay_info.status),
-                                        ) // TODO: refactor to dynamically check the size of the 'right to left' portion and set the max width to be the screen width minus padding minus 'right to left' width
-                                        .show(ui, |ui| {
-                                            ui.label(
-                                                RichText::new(relay_info.relay_url)
-                                                    .text_style(
-                                                        NotedeckTextStyle::Monospace.text_style(),
-                                                    )
-                                                    .color(
-                                                        ui.style()
-                                                            .visuals
-                                                            .noninteractive()
-                                                            .fg_stroke
-                                                            .color,
-                                                    ),
-                                            );
-                                        });
-                                });
-                        });
+            let (status, throttled) = relay_status(&infos, &relay.url);
+            let summary = self
+                .relay_info
+                .get(ui.ctx(), &relay.url)
+                .and_then(|doc| doc.summary());
 
-                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                            if ui.add(delete_button(ui.visuals().dark_mode)).clicked() {
-                                indices_to_remove.get_or_insert_with(Vec::new).push(index);
-                            };
+            relay_frame(ui).show(ui, |ui| {
+                relay_row(ui, index, &relay.url, status, throttled, |ui| {
+                    if can_edit && ui.add(delete_button(ui.visuals().dark_mode)).clicked() {
+                        remove = Some(index);
+                    }
+                });
 
-                            show_connection_status(ui, relay_info.status);
-                        });
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(can_edit, |ui| {
+                        ui.checkbox(&mut relay.read, tr!("relays-read"));
+                        ui.checkbox(&mut relay.write, tr!("relays-write"));
                     });
+
+                    if let Some(summary) = summary {
+                        ui.label(RichText::new(summary).weak());
+                    }
                 });
             });
+
+            // a relay that's neither is just both, keep the checkboxes honest
+            if !relay.read && !relay.write {
+                relay.read = true;
+                relay.write = true;
+            }
         }
 
-        indices_to_remove
+        if let Some(index) = remove {
+            relays.remove(index);
+        }
+
+        if !can_edit {
+            return None;
+        }
+
+        ui.add_space(8.0);
+        let (publish, discard) = ui
+            .horizontal(|ui| {
+                let publish = ui.add_enabled(dirty, Button::new(tr!("relays-publish")));
+                let discard = ui.add_enabled(dirty, Button::new(tr!("relays-discard")));
+                (publish.clicked(), discard.clicked())
+            })
+            .inner;
+
+        if discard {
+            self.draft.clear();
+            None
+        } else {
+            publish.then(|| RelayAction::Publish(relays.clone()))
+        }
+    }
+
+    fn local_relays_ui(&mut self, ui: &mut Ui) -> Option<RelayAction> {
+        if self.local.is_empty() {
+            return None;
+        }
+
+        let infos = self.manager.get_relay_infos();
+        let mut action = None;
+
+        ui.add_space(16.0);
+        section(ui, &tr!("relays-local"));
+        ui.label(RichText::new(tr!("relays-local-hint")).weak());
+
+        for (index, url) in self.local.iter().enumerate() {
+            ui.add_space(8.0);
+            let (status, throttled) = relay_status(&infos, url);
+            relay_frame(ui).show(ui, |ui| {
+                relay_row(ui, ("local", index), url, status, throttled, |ui| {
+                    if ui.add(delete_button(ui.visuals().dark_mode)).clicked() {
+                        action = Some(RelayAction::RemoveLocal(url.clone()));
+                    }
+                });
+            });
+        }
+
+        action
+    }
+
+    /// Anything else we're connected to, like the bootstrap relays
+    fn other_relays_ui(&mut self, ui: &mut Ui) {
+        let listed = |url: &str| {
+            self.advertised.iter().any(|r| r.url == url)
+                || self.draft.relays.iter().flatten().any(|r| r.url == url)
+                || self.local.iter().any(|l| l == url)
+        };
+
+        let infos = self.manager.get_relay_infos();
+        let others: Vec<&RelayInfo> = infos
+            .iter()
+            .filter(|info| !listed(info.relay_url))
+            .collect();
+        if others.is_empty() {
+            return;
+        }
+
+        ui.add_space(16.0);
+        section(ui, &tr!("relays-other"));
+
+        for (index, info) in others.into_iter().enumerate() {
+            ui.add_space(8.0);
+            relay_frame(ui).show(ui, |ui| {
+                relay_row(
+                    ui,
+                    ("other", index),
+                    info.relay_url,
+                    info.status,
+                    info.throttled,
+                    |_| {},
+                );
+            });
+        }
     }
 }
 
-fn get_right_side_width(status: RelayStatus) -> f32 {
-    match status {
+fn relay_status(infos: &[RelayInfo<'_>], url: &str) -> (RelayStatus, bool) {
+    infos
+        .iter()
+        .find(|info| info.relay_url == url)
+        .map(|info| (info.status, info.throttled))
+        .unwrap_or((RelayStatus::Disconnected, false))
+}
+
+/// A relay's url and connection status, with room for buttons on the
+/// right
+fn relay_row(
+    ui: &mut Ui,
+    id_salt: impl std::hash::Hash,
+    url: &str,
+    status: RelayStatus,
+    throttled: bool,
+    buttons: impl FnOnce(&mut Ui),
+) {
+    ui.horizontal(|ui| {
+        ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+            Frame::none()
+                // This frame is needed to add margin because the label will be added to the outer frame first and centered vertically before the connection status is added so the vertical centering isn't accurate.
+                // TODO: remove this hack and actually center the url & status at the same time
+                .inner_margin(Margin::symmetric(0.0, 4.0))
+                .show(ui, |ui| {
+                    egui::ScrollArea::horizontal()
+                        .id_salt(id_salt)
+                        .max_width(ui.max_rect().width() - get_right_side_width(status, throttled)) // TODO: refactor to dynamically check the size of the 'right to left' portion and set the max width to be the screen width minus padding minus 'right to left' width
+                        .show(ui, |ui| {
+                            ui.label(
+                                RichText::new(url)
+                                    .text_style(NotedeckTextStyle::Monospace.text_style())
+                                    .color(ui.style().visuals.noninteractive().fg_stroke.color),
+                            );
+                        });
+                });
+        });
+
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            buttons(ui);
+
+            show_connection_status(ui, status);
+
+            if throttled {
+                show_throttled(ui);
+            }
+        });
+    });
+}
+
+fn section(ui: &mut Ui, title: &str) {
+    ui.label(
+        RichText::new(title)
+            .text_style(NotedeckTextStyle::Body.text_style())
+            .strong(),
+    );
+}
+
+fn get_right_side_width(status: RelayStatus, throttled: bool) -> f32 {
+    let status_width = match status {
         RelayStatus::Connected => 150.0,
         RelayStatus::Connecting => 160.0,
         RelayStatus::Disconnected => 175.0,
+    };
+
+    if throttled {
+        status_width + 80.0
+    } else {
+        status_width
     }
 }
 
-#[allow(unused)]
+fn show_throttled(ui: &mut Ui) {
+    ui.spinner();
+    ui.label(RichText::new("Throttled").color(ui.visuals().warn_fg_color))
+        .on_hover_text("Sending slowly to stay under this relay's rate limit");
+}
+
+/// Turn whatever was typed into a websocket url, assuming wss:// if no
+/// scheme was given
+fn normalize_relay_url(input: &str) -> Option<String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+
+    let url = if input.starts_with("wss://") || input.starts_with("ws://") {
+        input.to_owned()
+    } else {
+        format!("wss://{input}")
+    };
+
+    url::Url::parse(&url).ok().map(|url| url.to_string())
+}
+
 fn add_relay_button() -> egui::Button<'static> {
     Button::new("+ Add relay").min_size(Vec2::new(0.0, 32.0))
 }
@@ -188,12 +505,18 @@ mod preview {
 
     pub struct RelayViewPreview {
         pool: RelayPool,
+        id_string_map: HashMap<egui::Id, String>,
+        draft: RelayListDraft,
+        relay_info: RelayInfoCache,
     }
 
     impl RelayViewPreview {
         fn new() -> Self {
             RelayViewPreview {
                 pool: sample_pool(),
+                id_string_map: HashMap::new(),
+                draft: RelayListDraft::default(),
+                relay_info: RelayInfoCache::default(),
             }
         }
     }
@@ -201,7 +524,17 @@ mod preview {
     impl App for RelayViewPreview {
         fn update(&mut self, _app: &mut AppContext<'_>, ui: &mut egui::Ui) {
             self.pool.try_recv();
-            RelayView::new(RelayPoolManager::new(&mut self.pool)).ui(ui);
+            RelayView::new(
+                RelayPoolManager::new(&mut self.pool),
+                SessionCapability::FullKey,
+                &mut self.id_string_map,
+                &mut self.draft,
+                &mut self.relay_info,
+                None,
+                vec![RelaySpec::new("wss://relay.damus.io", true, true)],
+                vec![],
+            )
+            .ui(ui);
         }
     }
 
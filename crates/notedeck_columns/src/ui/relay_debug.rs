@@ -1,17 +1,55 @@
-use egui::ScrollArea;
-use enostr::{RelayLogEvent, SubsDebug};
+use std::time::Instant;
+
+use egui::{RichText, ScrollArea};
+use enostr::{PoolRelay, RelayLogEvent, RelayMetrics, RelayStats, SubsDebug};
+
+/// How much of a message to show inline, the rest is in the hover text
+const SUMMARY_CHARS: usize = 120;
 
 pub struct RelayDebugView<'a> {
     debug: &'a mut SubsDebug,
+    relays: &'a [PoolRelay],
 }
 
 impl<'a> RelayDebugView<'a> {
-    pub fn new(debug: &'a mut SubsDebug) -> Self {
-        Self { debug }
+    pub fn new(debug: &'a mut SubsDebug, relays: &'a [PoolRelay]) -> Self {
+        Self { debug, relays }
     }
 }
 
 impl RelayDebugView<'_> {
+    fn filters_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            let pause_label = if self.debug.paused { "Resume" } else { "Pause" };
+            if ui.button(pause_label).clicked() {
+                self.debug.paused = !self.debug.paused;
+            }
+
+            if ui.button("Clear").clicked() {
+                self.debug.clear_events();
+            }
+
+            ui.add(
+                egui::TextEdit::singleline(&mut self.debug.search)
+                    .hint_text("Search")
+                    .desired_width(160.0),
+            );
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            for label in RelayLogEvent::LABELS {
+                let mut shown = !self.debug.hidden_labels.contains(label);
+                if ui.checkbox(&mut shown, label).changed() {
+                    if shown {
+                        self.debug.hidden_labels.remove(label);
+                    } else {
+                        self.debug.hidden_labels.insert(label);
+                    }
+                }
+            }
+        });
+    }
+
     pub fn ui(&mut self, ui: &mut egui::Ui) {
         ScrollArea::vertical()
             .id_salt(ui.id().with("relays_debug"))
@@ -19,6 +57,11 @@ impl RelayDebugView<'_> {
             .show(ui, |ui| {
                 ui.label("Active Relays:");
                 for (relay_str, data) in self.debug.get_data() {
+                    let metrics = self
+                        .relays
+                        .iter()
+                        .find(|relay| relay.url() == relay_str.as_str())
+                        .and_then(|relay| relay.metrics());
                     egui::CollapsingHeader::new(format!(
                         "{} {} {}",
                         relay_str,
@@ -27,6 +70,9 @@ impl RelayDebugView<'_> {
                     ))
                     .default_open(true)
                     .show(ui, |ui| {
+                        if let Some(metrics) = metrics {
+                            ui.label(format_metrics(metrics, Instant::now()));
+                        }
                         ui.horizontal_wrapped(|ui| {
                             for (i, sub_data) in data.sub_data.values().enumerate() {
                                 ui.label(format!(
@@ -71,60 +117,43 @@ impl RelayDebugView<'_> {
                     self.debug.relay_events_selection = make_selection
                 }
             });
-        let show_relay_evs =
-            |ui: &mut egui::Ui, relay: Option<String>, events: Vec<RelayLogEvent>| {
-                for ev in events {
-                    ui.horizontal_wrapped(|ui| {
-                        if let Some(r) = &relay {
-                            ui.label("relay").on_hover_text(r.clone());
-                        }
-                        match ev {
-                            RelayLogEvent::Send(client_message) => {
-                                ui.label("SEND: ");
-                                let msg = &match client_message {
-                                    enostr::ClientMessage::Event { .. } => "Event",
-                                    enostr::ClientMessage::Req { .. } => "Req",
-                                    enostr::ClientMessage::Close { .. } => "Close",
-                                    enostr::ClientMessage::Raw(_) => "Raw",
-                                };
-
-                                if let Ok(json) = client_message.to_json() {
-                                    ui.label(*msg).on_hover_text(json)
-                                } else {
-                                    ui.label(*msg)
-                                }
-                            }
-                            RelayLogEvent::Recieve(e) => {
-                                ui.label("RECIEVE: ");
-                                match e {
-                                    enostr::OwnedRelayEvent::Opened => ui.label("Opened"),
-                                    enostr::OwnedRelayEvent::Closed => ui.label("Closed"),
-                                    enostr::OwnedRelayEvent::Other(s) => {
-                                        ui.label("Other").on_hover_text(s)
-                                    }
-                                    enostr::OwnedRelayEvent::Error(s) => {
-                                        ui.label("Error").on_hover_text(s)
-                                    }
-                                    enostr::OwnedRelayEvent::Message(s) => {
-                                        ui.label("Message").on_hover_text(s)
-                                    }
-                                }
-                            }
-                        }
-                    });
-                }
-            };
+        self.filters_ui(ui);
+
+        let debug = &*self.debug;
+        let show_relay_evs = |ui: &mut egui::Ui, relay: Option<&String>, data: &RelayStats| {
+            for ev in data.events.iter().filter(|ev| debug.is_shown(ev)) {
+                ui.horizontal_wrapped(|ui| {
+                    if let Some(r) = relay {
+                        ui.label("relay").on_hover_text(r);
+                    }
+
+                    let direction = match ev {
+                        RelayLogEvent::Send(_) => "⬆",
+                        RelayLogEvent::Recieve(_) => "⬇",
+                    };
+                    ui.label(RichText::new(format!("{direction} {}", ev.label())).monospace());
+
+                    let details = ev.details();
+                    let summary: String = details.chars().take(SUMMARY_CHARS).collect();
+                    let response = ui.label(RichText::new(summary).weak());
+                    if details.chars().count() > SUMMARY_CHARS {
+                        response.on_hover_text(details);
+                    }
+                });
+            }
+        };
 
         ScrollArea::vertical()
             .id_salt(ui.id().with("events"))
+            .stick_to_bottom(!debug.paused)
             .show(ui, |ui| {
-                if let Some(relay) = &self.debug.relay_events_selection {
-                    if let Some(data) = self.debug.get_data().get(relay) {
-                        show_relay_evs(ui, None, data.events.clone());
+                if let Some(relay) = &debug.relay_events_selection {
+                    if let Some(data) = debug.get_data().get(relay) {
+                        show_relay_evs(ui, None, data);
                     }
                 } else {
-                    for (relay, data) in self.debug.get_data() {
-                        show_relay_evs(ui, Some(relay.clone()), data.events.clone());
+                    for (relay, data) in debug.get_data() {
+                        show_relay_evs(ui, Some(relay), data);
                     }
                 }
             });
@@ -132,12 +161,16 @@ impl RelayDebugView<'_> {
         self.debug.try_increment_stats();
     }
 
-    pub fn window(ctx: &egui::Context, debug: &mut SubsDebug) {
-        let mut open = true;
+    pub fn window(
+        ctx: &egui::Context,
+        debug: &mut SubsDebug,
+        relays: &[PoolRelay],
+        open: &mut bool,
+    ) {
         egui::Window::new("Relay Debugger")
-            .open(&mut open)
+            .open(open)
             .show(ctx, |ui| {
-                RelayDebugView::new(debug).ui(ui);
+                RelayDebugView::new(debug, relays).ui(ui);
             });
     }
 }
@@ -150,6 +183,19 @@ fn format_sec(c: &enostr::TransferStats) -> String {
     )
 }
 
+fn format_metrics(metrics: &RelayMetrics, now: Instant) -> String {
+    let eose = metrics
+        .eose_latency()
+        .map(|latency| format!("{} ms", latency.as_millis()))
+        .unwrap_or_else(|| "-".to_owned());
+    format!(
+        "{:.1} events/s, EOSE {}, {:.0}% duplicates",
+        metrics.events_per_second(now),
+        eose,
+        metrics.duplicate_ratio() * 100.0
+    )
+}
+
 fn format_total(c: &enostr::TransferStats) -> String {
     format!(
         "total: ⬇{} ⬆️{}",
@@ -0,0 +1,95 @@
+use egui::{RichText, TextEdit};
+use notedeck::{tr, NotedeckTextStyle};
+
+use crate::relay_groups::RelayGroups;
+
+/// Naming sets of relays and picking which relays are in each
+pub struct RelayGroupsView<'a> {
+    groups: &'a mut RelayGroups,
+    /// Relays in the pool, the ones a group can be made of
+    relays: Vec<String>,
+}
+
+impl<'a> RelayGroupsView<'a> {
+    pub fn new(groups: &'a mut RelayGroups, relays: Vec<String>) -> Self {
+        RelayGroupsView { groups, relays }
+    }
+
+    /// Returns true if a group was changed
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let before = self.groups.clone();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Frame::none()
+                .inner_margin(egui::Margin::same(16.0))
+                .show(ui, |ui| {
+                    ui.weak(tr!("relay-groups-hint"));
+                    ui.add_space(8.0);
+
+                    let mut remove = None;
+                    let mut toggle = None;
+                    for group in &self.groups.groups {
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                RichText::new(&group.name)
+                                    .text_style(NotedeckTextStyle::Body.text_style())
+                                    .strong(),
+                            );
+                            if ui.small_button(tr!("relay-groups-delete")).clicked() {
+                                remove = Some(group.name.clone());
+                            }
+                        });
+                        for relay in &self.relays {
+                            let mut included = group.relays.contains(relay);
+                            if ui.checkbox(&mut included, relay).changed() {
+                                toggle = Some((group.name.clone(), relay.clone()));
+                            }
+                        }
+                        // relays in the group that aren't connected now
+                        for relay in group.relays.iter().filter(|r| !self.relays.contains(r)) {
+                            ui.horizontal(|ui| {
+                                ui.weak(tr!("relay-groups-not-connected", relay = relay.as_str()));
+                                if ui.small_button("✕").clicked() {
+                                    toggle = Some((group.name.clone(), relay.clone()));
+                                }
+                            });
+                        }
+                        ui.add_space(12.0);
+                    }
+                    if let Some(name) = remove {
+                        self.groups.remove(&name);
+                    }
+                    if let Some((name, relay)) = toggle {
+                        self.groups.toggle(&name, &relay);
+                    }
+
+                    self.add_group_ui(ui);
+                });
+        });
+
+        before != *self.groups
+    }
+
+    fn add_group_ui(&mut self, ui: &mut egui::Ui) {
+        let id = ui.id().with("new-relay-group");
+        let mut name: String = ui.data(|d| d.get_temp(id)).unwrap_or_default();
+
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut name)
+                    .hint_text(tr!("relay-groups-name-hint"))
+                    .desired_width(200.0),
+            );
+            let can_add = !name.trim().is_empty() && self.groups.get(name.trim()).is_none();
+            if ui
+                .add_enabled(can_add, egui::Button::new(tr!("relay-groups-add")))
+                .clicked()
+                && self.groups.add(&name)
+            {
+                name.clear();
+            }
+        });
+
+        ui.data_mut(|d| d.insert_temp(id, name));
+    }
+}
@@ -0,0 +1,117 @@
+use egui::{RichText, TextEdit};
+use nostrdb::{Ndb, Transaction};
+use notedeck::{tr, NotedeckTextStyle};
+
+use crate::moderation::{ReportTarget, ReportType};
+use crate::profile::get_display_name;
+
+pub struct ReportAction {
+    pub kind: ReportType,
+    pub reason: String,
+    /// Also publish a NIP-32 label for moderation relays
+    pub label: bool,
+}
+
+/// Pick why a note or person is being reported
+pub struct ReportView<'a> {
+    ndb: &'a Ndb,
+    target: &'a ReportTarget,
+    can_sign: bool,
+}
+
+#[derive(Clone)]
+struct ReportState {
+    kind: ReportType,
+    reason: String,
+    label: bool,
+}
+
+impl<'a> ReportView<'a> {
+    pub fn new(ndb: &'a Ndb, target: &'a ReportTarget, can_sign: bool) -> Self {
+        ReportView {
+            ndb,
+            target,
+            can_sign,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<ReportAction> {
+        let id = ui
+            .id()
+            .with(("report", self.target.pubkey, self.target.note));
+        let mut state = ui.ctx().data_mut(|d| {
+            d.get_temp::<ReportState>(id).unwrap_or(ReportState {
+                kind: ReportType::Spam,
+                reason: String::new(),
+                label: false,
+            })
+        });
+        let mut action = None;
+
+        egui::Frame::none()
+            .inner_margin(egui::Margin::same(16.0))
+            .show(ui, |ui| {
+                let name = Transaction::new(self.ndb)
+                    .ok()
+                    .map(|txn| {
+                        let profile = self
+                            .ndb
+                            .get_profile_by_pubkey(&txn, self.target.pubkey.bytes())
+                            .ok();
+                        get_display_name(profile.as_ref()).name().to_owned()
+                    })
+                    .unwrap_or_default();
+                let title = if self.target.note.is_some() {
+                    tr!("report-note-title", name = name)
+                } else {
+                    tr!("report-profile-title", name = name)
+                };
+                ui.label(
+                    RichText::new(title)
+                        .text_style(NotedeckTextStyle::Body.text_style())
+                        .strong(),
+                );
+                ui.weak(tr!("report-hint"));
+                ui.add_space(8.0);
+
+                for kind in ReportType::ALL {
+                    ui.radio_value(&mut state.kind, kind, kind.label());
+                }
+
+                ui.add_space(8.0);
+                ui.add(
+                    TextEdit::multiline(&mut state.reason)
+                        .hint_text(tr!("report-reason"))
+                        .desired_rows(3)
+                        .desired_width(f32::INFINITY),
+                );
+                ui.checkbox(&mut state.label, tr!("report-label"))
+                    .on_hover_text(tr!("report-label-hint"));
+
+                ui.add_space(8.0);
+                if !self.can_sign {
+                    ui.colored_label(ui.visuals().warn_fg_color, tr!("report-cant-sign"));
+                }
+                if ui
+                    .add_enabled(self.can_sign, egui::Button::new(tr!("report-send")))
+                    .clicked()
+                {
+                    action = Some(ReportAction {
+                        kind: state.kind,
+                        reason: state.reason.trim().to_owned(),
+                        label: state.label,
+                    });
+                }
+            });
+
+        ui.ctx().data_mut(|d| {
+            if action.is_some() {
+                d.remove::<ReportState>(id);
+            } else {
+                d.insert_temp(id, state);
+            }
+        });
+
+        action
+    }
+}
@@ -0,0 +1,26 @@
+use egui::{Button, RichText};
+use notedeck::{tr, NotedeckTextStyle, SessionCapability};
+
+/// A strip across the top of the app letting people know why they can't
+/// post. Returns true when the login button was clicked.
+pub fn session_banner(ui: &mut egui::Ui, session: SessionCapability) -> bool {
+    let message = match session {
+        SessionCapability::ReadOnly => tr!("session-read-only-banner"),
+        SessionCapability::PubkeyOnly => tr!("session-pubkey-only-banner"),
+        SessionCapability::FullKey => return false,
+    };
+
+    ui.horizontal(|ui| {
+        ui.label(
+            RichText::new(message)
+                .text_style(NotedeckTextStyle::Body.text_style())
+                .color(ui.visuals().warn_fg_color),
+        );
+
+        ui.add(Button::new(
+            RichText::new(tr!("session-log-in")).text_style(NotedeckTextStyle::Body.text_style()),
+        ))
+        .clicked()
+    })
+    .inner
+}
@@ -0,0 +1,365 @@
+use egui::{ComboBox, RichText};
+use notedeck::{
+    amount::{self, AmountSettings, AmountUnit, Currency},
+    datetime::{self, ClockFormat, TimeSettings, WeekStart},
+    i18n::{self, Language},
+    tr, NotedeckTextStyle,
+};
+
+use crate::hashtags::HashtagSettings;
+
+pub enum SettingsAction {
+    Relays,
+    Notifications,
+    /// Switch languages, `None` follows the system
+    Language(Option<Language>),
+    Time(TimeSettings),
+    Amounts(AmountSettings),
+    Hashtags(HashtagSettings),
+    /// Publish a new Blossom server list for the selected account
+    MediaServers(Vec<String>),
+    Subscriptions,
+    /// Fetch every timeline from relays again
+    RefreshTimelines,
+    Wallet,
+    Hidden,
+    ExportData,
+    Storage,
+    /// Turn settings sync on or off for the selected account
+    SyncSettings(bool),
+    SyncNow,
+    RestoreSettings,
+}
+
+/// The top level settings page
+pub struct SettingsView<'a> {
+    hashtags: &'a HashtagSettings,
+    /// `None` when the selected account can't sign a new list
+    media_servers: Option<&'a [String]>,
+    new_server: &'a mut String,
+    /// `None` when the selected account can't sign, so can't sync
+    sync_enabled: Option<bool>,
+}
+
+impl<'a> SettingsView<'a> {
+    pub fn new(
+        hashtags: &'a HashtagSettings,
+        media_servers: Option<&'a [String]>,
+        new_server: &'a mut String,
+        sync_enabled: Option<bool>,
+    ) -> Self {
+        SettingsView {
+            hashtags,
+            media_servers,
+            new_server,
+            sync_enabled,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<SettingsAction> {
+        let mut action = None;
+
+        egui::Frame::none()
+            .inner_margin(egui::Margin::same(16.0))
+            .show(ui, |ui| {
+                section(ui, &tr!("settings-language"));
+                if let Some(language) = language_picker(ui) {
+                    action = Some(SettingsAction::Language(language));
+                }
+
+                ui.add_space(16.0);
+
+                section(ui, &tr!("settings-time"));
+                if let Some(settings) = time_settings(ui) {
+                    action = Some(SettingsAction::Time(settings));
+                }
+
+                ui.add_space(16.0);
+
+                section(ui, &tr!("settings-amounts"));
+                if let Some(settings) = amount_settings(ui) {
+                    action = Some(SettingsAction::Amounts(settings));
+                }
+
+                ui.add_space(16.0);
+
+                section(ui, &tr!("settings-hashtags"));
+                let mut hashtags = self.hashtags.clone();
+                if ui
+                    .checkbox(
+                        &mut hashtags.merge_into_home,
+                        tr!("settings-merge-hashtags"),
+                    )
+                    .changed()
+                {
+                    action = Some(SettingsAction::Hashtags(hashtags));
+                }
+
+                ui.add_space(16.0);
+
+                if let Some(servers) = self.media_servers {
+                    section(ui, &tr!("settings-media-servers"));
+                    if let Some(servers) = media_servers(ui, servers, self.new_server) {
+                        action = Some(SettingsAction::MediaServers(servers));
+                    }
+
+                    ui.add_space(16.0);
+                }
+
+                if let Some(mut enabled) = self.sync_enabled {
+                    section(ui, &tr!("settings-sync"));
+                    if ui
+                        .checkbox(&mut enabled, tr!("settings-sync-enabled"))
+                        .on_hover_text(tr!("settings-sync-hint"))
+                        .changed()
+                    {
+                        action = Some(SettingsAction::SyncSettings(enabled));
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button(tr!("settings-sync-now")).clicked() {
+                            action = Some(SettingsAction::SyncNow);
+                        }
+                        if ui
+                            .button(tr!("settings-sync-restore"))
+                            .on_hover_text(tr!("settings-sync-restore-hint"))
+                            .clicked()
+                        {
+                            action = Some(SettingsAction::RestoreSettings);
+                        }
+                    });
+
+                    ui.add_space(16.0);
+                }
+
+                if ui.button(tr!("settings-relays")).clicked() {
+                    action = Some(SettingsAction::Relays);
+                }
+
+                if ui.button(tr!("settings-notifications")).clicked() {
+                    action = Some(SettingsAction::Notifications);
+                }
+
+                if ui.button(tr!("settings-wallet")).clicked() {
+                    action = Some(SettingsAction::Wallet);
+                }
+
+                if ui.button(tr!("settings-hidden")).clicked() {
+                    action = Some(SettingsAction::Hidden);
+                }
+
+                if ui.button(tr!("settings-export-data")).clicked() {
+                    action = Some(SettingsAction::ExportData);
+                }
+
+                if ui.button(tr!("settings-storage")).clicked() {
+                    action = Some(SettingsAction::Storage);
+                }
+
+                if ui.button(tr!("settings-subscriptions")).clicked() {
+                    action = Some(SettingsAction::Subscriptions);
+                }
+
+                if ui
+                    .button(tr!("settings-refresh-timelines"))
+                    .on_hover_text(tr!("settings-refresh-timelines-hint"))
+                    .clicked()
+                {
+                    action = Some(SettingsAction::RefreshTimelines);
+                }
+
+                if ui.button(tr!("settings-all")).clicked() {
+                    notedeck::settings::open_window(ui.ctx());
+                }
+            });
+
+        action
+    }
+}
+
+/// Returns the new list when a server is added or removed
+fn media_servers(
+    ui: &mut egui::Ui,
+    servers: &[String],
+    new_server: &mut String,
+) -> Option<Vec<String>> {
+    let mut changed = None;
+
+    ui.weak(tr!("settings-media-servers-hint"));
+    for (i, server) in servers.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(server);
+            if ui
+                .small_button(tr!("settings-media-servers-remove"))
+                .clicked()
+            {
+                let mut servers = servers.to_vec();
+                servers.remove(i);
+                changed = Some(servers);
+            }
+        });
+    }
+
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(new_server).hint_text("https://blossom.example.com"));
+
+        let server = new_server.trim().trim_end_matches('/');
+        let valid = url::Url::parse(server)
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+            && !servers.iter().any(|s| s == server);
+        if ui
+            .add_enabled(valid, egui::Button::new(tr!("settings-media-servers-add")))
+            .clicked()
+        {
+            let mut servers = servers.to_vec();
+            servers.push(server.to_owned());
+            new_server.clear();
+            changed = Some(servers);
+        }
+    });
+
+    changed
+}
+
+/// Returns the new choice when it changes
+fn language_picker(ui: &mut egui::Ui) -> Option<Option<Language>> {
+    let chosen = i18n::chosen_language();
+    let system = tr!(
+        "settings-language-system",
+        language = i18n::current_language().name
+    );
+    let selected_text = chosen.map(|l| l.name.to_owned()).unwrap_or(system.clone());
+
+    let mut picked = chosen;
+    ComboBox::from_id_salt("language-picker")
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut picked, None, system);
+            for language in i18n::LANGUAGES {
+                ui.selectable_value(&mut picked, Some(*language), language.name);
+            }
+        });
+
+    (picked != chosen).then_some(picked)
+}
+
+/// Returns the new settings when they change
+fn time_settings(ui: &mut egui::Ui) -> Option<TimeSettings> {
+    let current = datetime::settings();
+    let mut settings = current;
+
+    egui::Grid::new("time-settings")
+        .num_columns(2)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            ui.label(tr!("settings-clock"));
+            ComboBox::from_id_salt("clock-picker")
+                .selected_text(clock_label(settings.clock))
+                .show_ui(ui, |ui| {
+                    for clock in [
+                        ClockFormat::Auto,
+                        ClockFormat::TwelveHour,
+                        ClockFormat::TwentyFourHour,
+                    ] {
+                        ui.selectable_value(&mut settings.clock, clock, clock_label(clock));
+                    }
+                });
+            ui.end_row();
+
+            ui.label(tr!("settings-week-start"));
+            ComboBox::from_id_salt("week-start-picker")
+                .selected_text(week_start_label(settings.week_start))
+                .show_ui(ui, |ui| {
+                    for day in [
+                        WeekStart::Auto,
+                        WeekStart::Sunday,
+                        WeekStart::Monday,
+                        WeekStart::Saturday,
+                    ] {
+                        ui.selectable_value(&mut settings.week_start, day, week_start_label(day));
+                    }
+                });
+            ui.end_row();
+        });
+
+    (settings != current).then_some(settings)
+}
+
+/// Returns the new settings when they change
+fn amount_settings(ui: &mut egui::Ui) -> Option<AmountSettings> {
+    let current = amount::settings();
+    let mut settings = current;
+
+    egui::Grid::new("amount-settings")
+        .num_columns(2)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            ui.label(tr!("settings-amount-unit"));
+            ComboBox::from_id_salt("amount-unit-picker")
+                .selected_text(unit_label(settings.unit))
+                .show_ui(ui, |ui| {
+                    for unit in [AmountUnit::Sats, AmountUnit::Bits, AmountUnit::Fiat] {
+                        ui.selectable_value(&mut settings.unit, unit, unit_label(unit));
+                    }
+                });
+            ui.end_row();
+
+            if settings.unit == AmountUnit::Fiat {
+                ui.label(tr!("settings-currency"));
+                ComboBox::from_id_salt("currency-picker")
+                    .selected_text(settings.currency.code())
+                    .show_ui(ui, |ui| {
+                        for currency in Currency::ALL {
+                            ui.selectable_value(&mut settings.currency, currency, currency.code());
+                        }
+                    });
+                ui.end_row();
+            }
+        });
+
+    if settings.unit == AmountUnit::Fiat {
+        let rate = amount::prices().filter(|p| p.rate(settings.currency).is_some());
+        let note = match rate {
+            Some(prices) => tr!(
+                "settings-rate-from",
+                time = datetime::settings().format_datetime(prices.fetched_at)
+            ),
+            None => tr!("settings-rate-missing"),
+        };
+        ui.weak(note);
+    }
+
+    (settings != current).then_some(settings)
+}
+
+fn unit_label(unit: AmountUnit) -> String {
+    match unit {
+        AmountUnit::Sats => tr!("settings-unit-sats"),
+        AmountUnit::Bits => tr!("settings-unit-bits"),
+        AmountUnit::Fiat => tr!("settings-unit-fiat"),
+    }
+}
+
+fn clock_label(clock: ClockFormat) -> String {
+    match clock {
+        ClockFormat::Auto => tr!("settings-clock-auto"),
+        ClockFormat::TwelveHour => tr!("settings-clock-12"),
+        ClockFormat::TwentyFourHour => tr!("settings-clock-24"),
+    }
+}
+
+fn week_start_label(day: WeekStart) -> String {
+    match day {
+        WeekStart::Auto => tr!("settings-week-start-auto"),
+        WeekStart::Sunday => tr!("settings-week-start-sunday"),
+        WeekStart::Monday => tr!("settings-week-start-monday"),
+        WeekStart::Saturday => tr!("settings-week-start-saturday"),
+    }
+}
+
+fn section(ui: &mut egui::Ui, title: &str) {
+    ui.label(
+        RichText::new(title)
+            .text_style(NotedeckTextStyle::Body.text_style())
+            .strong(),
+    );
+}
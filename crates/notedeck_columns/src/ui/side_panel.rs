@@ -15,9 +15,12 @@ use crate::{
     support::Support,
 };
 
-use notedeck::{Accounts, ImageCache, NotedeckTextStyle, ThemeHandler, UserAccount};
+use notedeck::{
+    Accounts, ImageCache, NotedeckTextStyle, SessionCapability, ThemeHandler, UserAccount,
+};
 
 use super::{
+    a11y,
     anim::{AnimationHelper, ICON_EXPANSION_MULTIPLE},
     configure_deck::deck_icon,
     profile::preview::get_account_url,
@@ -109,14 +112,15 @@ impl<'a> DesktopSidePanel<'a> {
                         ui.add_space(4.0);
                         ui.add(milestone_name());
                         ui.add_space(16.0);
-                        let is_interactive = self
-                            .selected_account
-                            .is_some_and(|s| s.secret_key.is_some());
-                        let compose_resp = ui.add(compose_note_button(is_interactive));
-                        let compose_resp = if is_interactive {
+                        let session = SessionCapability::new(self.selected_account);
+                        let missing = session.missing(SessionCapability::FullKey);
+                        let compose_resp = ui.add(compose_note_button(missing.is_none()));
+                        let compose_resp = if let Some(reason) = missing {
                             compose_resp
+                                .on_hover_cursor(egui::CursorIcon::NotAllowed)
+                                .on_hover_text(reason)
                         } else {
-                            compose_resp.on_hover_cursor(egui::CursorIcon::NotAllowed)
+                            compose_resp
                         };
                         // let search_resp = ui.add(search_button());
                         let column_resp = ui.add(add_column_button(dark_mode));
@@ -270,7 +274,7 @@ impl<'a> DesktopSidePanel<'a> {
 
         ui.put(helper.get_animation_rect(), widget);
 
-        helper.take_animation_response()
+        helper.take_accessible_response(ui, "Accounts")
     }
 
     pub fn perform_action(
@@ -297,11 +301,11 @@ impl<'a> DesktopSidePanel<'a> {
                 }
             }
             SidePanelAction::Settings => {
-                if router.routes().iter().any(|&r| r == Route::Relays) {
-                    // return if we are already routing to accounts
+                if router.routes().iter().any(|&r| r == Route::Settings) {
+                    // return if we are already routing to settings
                     router.go_back();
                 } else {
-                    router.route_to(Route::relays());
+                    router.route_to(Route::settings());
                 }
             }
             SidePanelAction::Columns => {
@@ -399,7 +403,7 @@ fn settings_button(dark_mode: bool) -> impl Widget {
                 .shrink((max_size - cur_img_size) / 2.0),
         );
 
-        helper.take_animation_response()
+        helper.take_accessible_response(ui, "Settings")
     }
 }
 
@@ -426,7 +430,7 @@ fn add_column_button(dark_mode: bool) -> impl Widget {
                 .shrink((max_size - cur_img_size) / 2.0),
         );
 
-        helper.take_animation_response()
+        helper.take_accessible_response(ui, "Add column")
     }
 }
 
@@ -477,7 +481,7 @@ fn compose_note_button(interactive: bool) -> impl Widget {
         painter.circle_filled(west_edge, use_edge_circle_radius, Color32::WHITE);
         painter.circle_filled(east_edge, use_edge_circle_radius, Color32::WHITE);
 
-        helper.take_animation_response()
+        helper.take_accessible_response(ui, "Compose note")
     }
 }
 
@@ -520,7 +524,7 @@ fn search_button() -> impl Widget {
             circle_stroke,
         );
 
-        helper.take_animation_response()
+        helper.take_accessible_response(ui, "Search")
     }
 }
 
@@ -557,7 +561,7 @@ fn support_button() -> impl Widget {
                 .shrink((max_size - cur_img_size) / 2.0),
         );
 
-        helper.take_animation_response()
+        helper.take_accessible_response(ui, "Help")
     }
 }
 
@@ -579,7 +583,7 @@ fn add_deck_button() -> impl Widget {
                 .shrink((max_size - cur_img_size) / 2.0),
         );
 
-        helper.take_animation_response()
+        helper.take_accessible_response(ui, "Add deck")
     }
 }
 
@@ -611,6 +615,7 @@ fn show_decks<'a>(
             40.0,
             highlight,
         ));
+        a11y::label(&deck_icon_resp, egui::WidgetType::Button, &deck.name);
         if deck_icon_resp.clicked() || deck_icon_resp.secondary_clicked() {
             clicked_index = Some(index);
         }
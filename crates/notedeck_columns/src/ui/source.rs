@@ -0,0 +1,107 @@
+use egui::{Color32, Frame, RichText};
+use enostr::{NoteId, NoteVerification};
+use nostrdb::{Ndb, Transaction};
+use notedeck::tr;
+use serde_json::Value;
+
+/// A note's raw event: pretty printed json, its tags, and whether its id
+/// and signature check out
+pub struct SourceView<'a> {
+    ndb: &'a Ndb,
+    note_id: &'a NoteId,
+}
+
+impl<'a> SourceView<'a> {
+    pub fn new(ndb: &'a Ndb, note_id: &'a NoteId) -> Self {
+        SourceView { ndb, note_id }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let txn = Transaction::new(self.ndb).expect("txn");
+        let json = match self.ndb.get_note_by_id(&txn, self.note_id.bytes()) {
+            Ok(note) => note.json().ok(),
+            Err(_) => None,
+        };
+
+        let Some(json) = json else {
+            ui.label(tr!("source-note-not-found"));
+            return;
+        };
+
+        let value: Option<Value> = serde_json::from_str(&json).ok();
+        let pretty = value
+            .as_ref()
+            .and_then(|value| serde_json::to_string_pretty(value).ok())
+            .unwrap_or_else(|| json.clone());
+
+        // checking the signature isn't free, only do it once per note
+        let verification_id = ui.id().with(("source-verification", self.note_id));
+        let verification = ui.data_mut(|d| {
+            *d.get_temp_mut_or_insert_with(verification_id, || NoteVerification::check(&json))
+        });
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            Frame::none()
+                .inner_margin(egui::Margin::same(16.0))
+                .show(ui, |ui| {
+                    verification_label(ui, verification);
+
+                    ui.add_space(12.0);
+                    ui.label(RichText::new(tr!("source-tags")).strong());
+                    tags_table(ui, value.as_ref());
+
+                    ui.add_space(12.0);
+                    if ui.button(tr!("source-copy")).clicked() {
+                        ui.output_mut(|w| w.copied_text = pretty.clone());
+                    }
+
+                    ui.add(
+                        egui::TextEdit::multiline(&mut pretty.as_str())
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+        });
+    }
+}
+
+fn verification_label(ui: &mut egui::Ui, verification: NoteVerification) {
+    let (text, color) = match verification {
+        NoteVerification::Valid => (tr!("source-valid"), Color32::from_rgb(0x3c, 0xb3, 0x71)),
+        NoteVerification::BadId => (tr!("source-bad-id"), ui.visuals().error_fg_color),
+        NoteVerification::BadSignature => {
+            (tr!("source-bad-signature"), ui.visuals().error_fg_color)
+        }
+        NoteVerification::BadDelegation => {
+            (tr!("source-bad-delegation"), ui.visuals().error_fg_color)
+        }
+        NoteVerification::Malformed => (tr!("source-malformed"), ui.visuals().warn_fg_color),
+    };
+
+    ui.label(RichText::new(text).color(color));
+}
+
+fn tags_table(ui: &mut egui::Ui, event: Option<&Value>) {
+    let tags = event
+        .and_then(|event| event.get("tags"))
+        .and_then(|tags| tags.as_array())
+        .filter(|tags| !tags.is_empty());
+
+    let Some(tags) = tags else {
+        ui.label(RichText::new(tr!("source-no-tags")).weak());
+        return;
+    };
+
+    egui::Grid::new("source-tags").striped(true).show(ui, |ui| {
+        for tag in tags {
+            for field in tag.as_array().into_iter().flatten() {
+                let text = field
+                    .as_str()
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| field.to_string());
+                ui.label(RichText::new(text).monospace());
+            }
+            ui.end_row();
+        }
+    });
+}
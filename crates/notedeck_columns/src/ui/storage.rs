@@ -0,0 +1,175 @@
+use egui::RichText;
+use notedeck::{maintenance::Retention, tr, NotedeckTextStyle};
+
+use crate::{
+    export,
+    maintenance::{Maintenance, Task},
+};
+
+/// Kinds to list in the stats, the rest are summed up
+const SHOWN_KINDS: usize = 10;
+
+pub enum StorageAction {
+    Count,
+    Compact,
+    Cancel,
+    /// Open the settings window to change what compaction drops
+    EditRetention,
+}
+
+/// How big the database is, what's in it, and compacting it
+pub struct StorageView<'a> {
+    maintenance: &'a Maintenance,
+    retention: &'a Retention,
+}
+
+fn size(bytes: u64) -> String {
+    const MB: f64 = (1 << 20) as f64;
+    let mb = bytes as f64 / MB;
+    if mb >= 1024.0 {
+        format!("{:.1} GB", mb / 1024.0)
+    } else {
+        format!("{mb:.1} MB")
+    }
+}
+
+impl<'a> StorageView<'a> {
+    pub fn new(maintenance: &'a Maintenance, retention: &'a Retention) -> Self {
+        StorageView {
+            maintenance,
+            retention,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<StorageAction> {
+        let mut action = None;
+        let running = self.maintenance.running();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Frame::none()
+                .inner_margin(egui::Margin::same(16.0))
+                .show(ui, |ui| {
+                    section(ui, &tr!("storage-title"));
+                    match &self.maintenance.stats {
+                        Some(stats) => {
+                            ui.label(tr!(
+                                "storage-summary",
+                                count = stats.notes,
+                                size = size(stats.bytes)
+                            ));
+                            ui.add_space(8.0);
+                            let kinds = stats.largest_kinds();
+                            egui::Grid::new("storage-kinds")
+                                .num_columns(2)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for (kind, count) in kinds.iter().take(SHOWN_KINDS) {
+                                        ui.label(export::kind_label(*kind));
+                                        ui.label(count.to_string());
+                                        ui.end_row();
+                                    }
+                                    let rest: usize =
+                                        kinds.iter().skip(SHOWN_KINDS).map(|(_, n)| n).sum();
+                                    if rest > 0 {
+                                        ui.weak(tr!("storage-other-kinds"));
+                                        ui.weak(rest.to_string());
+                                        ui.end_row();
+                                    }
+                                });
+                        }
+                        None => {
+                            ui.weak(tr!("storage-not-counted"));
+                        }
+                    }
+                    ui.add_space(8.0);
+                    if ui
+                        .add_enabled(running.is_none(), egui::Button::new(tr!("storage-count")))
+                        .clicked()
+                    {
+                        action = Some(StorageAction::Count);
+                    }
+
+                    ui.add_space(16.0);
+                    section(ui, &tr!("storage-retention"));
+                    if self.retention.kinds.is_empty() {
+                        ui.weak(tr!("storage-retention-none"));
+                    } else {
+                        let kinds = self
+                            .retention
+                            .kinds
+                            .iter()
+                            .map(|kind| export::kind_label(*kind))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        let months = self.retention.older_than_months;
+                        ui.label(if self.retention.keep_trusted {
+                            tr!(
+                                "storage-retention-untrusted",
+                                kinds = kinds,
+                                months = months
+                            )
+                        } else {
+                            tr!("storage-retention-all", kinds = kinds, months = months)
+                        });
+                    }
+                    ui.weak(tr!("storage-retention-hint"));
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button(tr!("storage-retention-edit")).clicked() {
+                            action = Some(StorageAction::EditRetention);
+                        }
+                        let can_compact = running.is_none() && !self.retention.kinds.is_empty();
+                        if ui
+                            .add_enabled(can_compact, egui::Button::new(tr!("storage-compact")))
+                            .clicked()
+                        {
+                            action = Some(StorageAction::Compact);
+                        }
+                    });
+                    if self.maintenance.compacted {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            tr!("storage-compacted-restart"),
+                        );
+                    }
+
+                    if let Some((task, scanned)) = running {
+                        ui.add_space(16.0);
+                        let label = match task {
+                            Task::Stats => tr!("storage-counting", count = scanned),
+                            Task::Compact => tr!("storage-compacting", count = scanned),
+                        };
+                        match self.maintenance.stats.as_ref().filter(|s| s.notes > 0) {
+                            Some(stats) => {
+                                ui.add(
+                                    egui::ProgressBar::new(
+                                        (scanned as f32 / stats.notes as f32).min(1.0),
+                                    )
+                                    .text(label),
+                                );
+                            }
+                            None => {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label(label);
+                                });
+                            }
+                        }
+                        if ui.button(tr!("storage-cancel")).clicked() {
+                            action = Some(StorageAction::Cancel);
+                        }
+                    }
+                });
+        });
+
+        action
+    }
+}
+
+fn section(ui: &mut egui::Ui, title: &str) {
+    ui.label(
+        RichText::new(title)
+            .text_style(NotedeckTextStyle::Body.text_style())
+            .strong(),
+    );
+}
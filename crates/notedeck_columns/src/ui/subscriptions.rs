@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+use egui::{CollapsingHeader, RichText};
+use enostr::Filter;
+use nostrdb::Ndb;
+use notedeck::{filter::filters_from_json, FilterState};
+
+use crate::{
+    column::Columns,
+    multi_subscriber::MultiSubscriber,
+    subscriptions::{RelaySubStatus, Subscriptions},
+    timeline::{Timeline, TimelineCache, TimelineId},
+    verify::VerifyStats,
+};
+
+/// Developer view of every subscription we know about: what each column
+/// asked nostrdb and relays for, and what came back. Meant for figuring
+/// out why a column is empty.
+pub struct SubscriptionsView<'a> {
+    ndb: &'a Ndb,
+    columns: &'a Columns,
+    timeline_cache: &'a TimelineCache,
+    subs: &'a Subscriptions,
+    verify: &'a VerifyStats,
+    id_string_map: &'a mut HashMap<egui::Id, String>,
+}
+
+pub enum SubscriptionAction {
+    /// Send a REQ again with the same subscription id
+    Resend {
+        sub_id: String,
+        filters: Vec<Filter>,
+    },
+    /// Replace a timeline's filter and subscribe again
+    EditFilter {
+        timeline: TimelineId,
+        filters: Vec<Filter>,
+    },
+}
+
+impl<'a> SubscriptionsView<'a> {
+    pub fn new(
+        ndb: &'a Ndb,
+        columns: &'a Columns,
+        timeline_cache: &'a TimelineCache,
+        subs: &'a Subscriptions,
+        verify: &'a VerifyStats,
+        id_string_map: &'a mut HashMap<egui::Id, String>,
+    ) -> Self {
+        SubscriptionsView {
+            ndb,
+            columns,
+            timeline_cache,
+            subs,
+            verify,
+            id_string_map,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<SubscriptionAction> {
+        let mut action = None;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Frame::none()
+                .inner_margin(egui::Margin::same(16.0))
+                .show(ui, |ui| {
+                    ui.label(format!(
+                        "{} active nostrdb subscriptions",
+                        self.ndb.subscription_count()
+                    ));
+                    ui.label(format!(
+                        "{} events waiting to be checked, {} delegated notes verified, {} dropped, {} deleted by their author",
+                        self.verify.pending.load(Ordering::Relaxed),
+                        self.verify.delegated.load(Ordering::Relaxed),
+                        self.verify.dropped.load(Ordering::Relaxed),
+                        self.verify.deleted.load(Ordering::Relaxed),
+                    ));
+
+                    ui.add_space(8.0);
+                    ui.label(RichText::new("Columns").strong());
+                    for timeline in self.columns.timelines() {
+                        if let Some(resp) = self.timeline_ui(ui, timeline) {
+                            action = Some(resp);
+                        }
+                    }
+
+                    ui.add_space(8.0);
+                    ui.label(RichText::new("Threads and profiles").strong());
+                    let cached = self
+                        .timeline_cache
+                        .threads
+                        .values()
+                        .map(|thread| (&thread.timeline, &thread.subscription))
+                        .chain(
+                            self.timeline_cache
+                                .profiles
+                                .values()
+                                .map(|profile| (&profile.timeline, &profile.subscription)),
+                        );
+
+                    for (timeline, subscriber) in cached {
+                        if let Some(resp) = self.cached_ui(ui, timeline, subscriber.as_ref()) {
+                            action = Some(resp);
+                        }
+                    }
+                });
+        });
+
+        action
+    }
+
+    fn timeline_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        timeline: &Timeline,
+    ) -> Option<SubscriptionAction> {
+        let mut action = None;
+
+        CollapsingHeader::new(format!("{} {:?}", timeline.kind, timeline.id))
+            .id_salt(("subscriptions", timeline.id))
+            .show(ui, |ui| {
+                local_ui(ui, timeline);
+                filter_states_ui(ui, timeline);
+
+                if let Some(filters) = self.filter_editor(ui, timeline) {
+                    action = Some(SubscriptionAction::EditFilter {
+                        timeline: timeline.id,
+                        filters,
+                    });
+                }
+
+                let mut reqs: Vec<_> = self
+                    .subs
+                    .timeline_reqs
+                    .iter()
+                    .filter(|(_, req)| req.timeline == timeline.id)
+                    .collect();
+                reqs.sort_by_key(|(sub_id, _)| sub_id.as_str());
+
+                if reqs.is_empty() {
+                    ui.label(RichText::new("No REQs sent").weak());
+                }
+
+                for (sub_id, req) in reqs {
+                    if let Some(resp) = remote_ui(ui, sub_id, &req.filters, self.subs) {
+                        action = Some(resp);
+                    }
+                }
+            });
+
+        action
+    }
+
+    fn cached_ui(
+        &self,
+        ui: &mut egui::Ui,
+        timeline: &Timeline,
+        subscriber: Option<&MultiSubscriber>,
+    ) -> Option<SubscriptionAction> {
+        let mut action = None;
+
+        CollapsingHeader::new(format!("{} {:?}", timeline.kind, timeline.id))
+            .id_salt(("subscriptions", timeline.id))
+            .show(ui, |ui| {
+                local_ui(ui, timeline);
+
+                let unified = subscriber.and_then(|s| s.sub.as_ref().map(|sub| (s, sub)));
+                let Some((subscriber, unified)) = unified else {
+                    ui.label(RichText::new("Not subscribed").weak());
+                    return;
+                };
+
+                ui.label(format!("nostrdb subscription {}", unified.local.id()));
+                action = remote_ui(ui, &unified.remote, subscriber.filters(), self.subs);
+            });
+
+        action
+    }
+
+    /// Edit a timeline's filter as json. Returns the new filters when
+    /// they're applied.
+    fn filter_editor(&mut self, ui: &mut egui::Ui, timeline: &Timeline) -> Option<Vec<Filter>> {
+        let id = ui.id().with(("filter-editor", timeline.id));
+        let error_id = id.with("error");
+
+        let Some(text) = self.id_string_map.get_mut(&id) else {
+            if ui.button("Edit filter").clicked() {
+                let current = timeline
+                    .filter
+                    .get_any_ready()
+                    .map(|filters| filters_json(filters))
+                    .unwrap_or_else(|| "[]".to_owned());
+                self.id_string_map.insert(id, current);
+            }
+            return None;
+        };
+
+        ui.add(
+            egui::TextEdit::multiline(text)
+                .code_editor()
+                .desired_rows(4)
+                .desired_width(f32::INFINITY),
+        );
+
+        let mut applied = None;
+        let mut close = false;
+        ui.horizontal(|ui| {
+            if ui.button("Apply").clicked() {
+                match filters_from_json(text) {
+                    Ok(filters) => {
+                        applied = Some(filters);
+                        close = true;
+                    }
+                    Err(err) => {
+                        ui.data_mut(|d| d.insert_temp(error_id, err.to_string()));
+                    }
+                }
+            }
+
+            if ui.button("Cancel").clicked() {
+                close = true;
+            }
+        });
+
+        if let Some(err) = ui.data(|d| d.get_temp::<String>(error_id)) {
+            ui.label(RichText::new(err).color(ui.visuals().error_fg_color));
+        }
+
+        if close {
+            self.id_string_map.remove(&id);
+            ui.data_mut(|d| d.remove::<String>(error_id));
+        }
+
+        applied
+    }
+}
+
+fn local_ui(ui: &mut egui::Ui, timeline: &Timeline) {
+    match &timeline.subscription {
+        Some(sub) => ui.label(format!("nostrdb subscription {}", sub.id())),
+        None => ui.label(RichText::new("No nostrdb subscription").weak()),
+    };
+
+    ui.label(format!("{} notes", timeline.all_or_any_notes().len()));
+}
+
+fn filter_states_ui(ui: &mut egui::Ui, timeline: &Timeline) {
+    ui.label(format!(
+        "Filter: {}",
+        filter_state_name(&timeline.filter.initial_state)
+    ));
+
+    let mut states: Vec<_> = timeline.filter.states.iter().collect();
+    states.sort_by_key(|(relay, _)| relay.as_str());
+    for (relay, state) in states {
+        ui.label(
+            RichText::new(format!("{relay}: {}", filter_state_name(state)))
+                .monospace()
+                .weak(),
+        );
+    }
+}
+
+fn filter_state_name(state: &FilterState) -> String {
+    match state {
+        FilterState::NeedsRemote(_) => "needs remote data".to_owned(),
+        FilterState::FetchingRemote(unified) => format!("fetching remote ({})", unified.remote),
+        FilterState::GotRemote(_) => "got remote data".to_owned(),
+        FilterState::Ready(filters) => format!("ready {}", filters_json(filters)),
+        FilterState::Broken(err) => format!("broken: {err}"),
+    }
+}
+
+/// A REQ and what each relay has sent back for it
+fn remote_ui(
+    ui: &mut egui::Ui,
+    sub_id: &str,
+    filters: &[Filter],
+    subs: &Subscriptions,
+) -> Option<SubscriptionAction> {
+    let mut action = None;
+
+    ui.add_space(4.0);
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(format!("REQ {sub_id}")).monospace())
+            .on_hover_text(filters_json(filters));
+
+        if ui.button("Re-send").clicked() {
+            action = Some(SubscriptionAction::Resend {
+                sub_id: sub_id.to_owned(),
+                filters: filters.to_vec(),
+            });
+        }
+    });
+
+    let Some(relays) = subs.relay_status.get(sub_id) else {
+        ui.label(RichText::new("Nothing back from relays yet").weak());
+        return action;
+    };
+
+    let mut relays: Vec<(&String, &RelaySubStatus)> = relays.iter().collect();
+    relays.sort_by_key(|(relay, _)| relay.as_str());
+
+    egui::Grid::new(("relay-status", sub_id))
+        .striped(true)
+        .show(ui, |ui| {
+            for (relay, status) in relays {
+                ui.label(RichText::new(relay).monospace());
+                ui.label(if status.eose { "EOSE" } else { "waiting" });
+                ui.label(format!("{} events", status.events));
+                ui.end_row();
+            }
+        });
+
+    action
+}
+
+fn filters_json(filters: &[Filter]) -> String {
+    let filters: Vec<String> = filters.iter().filter_map(|f| f.json().ok()).collect();
+    format!("[{}]", filters.join(","))
+}
@@ -2,16 +2,28 @@ use crate::actionbar::NoteAction;
 use crate::timeline::TimelineTab;
 use crate::{
     column::Columns,
-    timeline::{TimelineId, ViewFilter},
+    timeline::{
+        history::{month_density, month_range},
+        Timeline, TimelineHistory, TimelineId, ViewFilter,
+    },
     ui,
-    ui::note::NoteOptions,
+    ui::{density::Density, media::MediaGrid, note::NoteOptions},
 };
+use chrono::{Datelike, Local, NaiveDate, NaiveTime};
 use egui::containers::scroll_area::ScrollBarVisibility;
 use egui::{Direction, Layout};
 use egui_tabs::TabColor;
 use nostrdb::{Ndb, Transaction};
 use notedeck::note::root_note_id_from_selected_id;
-use notedeck::{ImageCache, MuteFun, NoteCache};
+use notedeck::{
+    datetime::{self, LocalTime},
+    tr,
+    ui::{DatePicker, MiniMonth, TimePicker},
+    ImageCache, MuteFun, NoteCache,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::{error, warn};
 
 pub struct TimelineView<'a> {
@@ -87,7 +99,7 @@ fn timeline_ui(
 
     */
 
-    let scroll_id = {
+    let (scroll_id, unseen, len) = {
         let timeline = if let Some(timeline) = columns.find_timeline_mut(timeline_id) {
             timeline
         } else {
@@ -102,28 +114,253 @@ fn timeline_ui(
         // need this for some reason??
         ui.add_space(3.0);
 
-        egui::Id::new(("tlscroll", timeline.view_id()))
+        if take_history_request(ui.ctx(), timeline_id) && timeline.history.is_none() {
+            let view_filter = timeline.current_view().filter;
+            timeline.history = Some(TimelineHistory::new(datetime::today(), view_filter));
+        }
+
+        if timeline.history.is_some() {
+            return history_ui(
+                ui,
+                ndb,
+                timeline,
+                note_cache,
+                img_cache,
+                note_options,
+                is_muted,
+            );
+        }
+
+        if timeline.current_view().filter == ViewFilter::Media
+            || is_media_mode(ui.ctx(), timeline_id)
+        {
+            return media_ui(ui, ndb, timeline, note_cache, img_cache, is_muted);
+        }
+
+        let view = timeline.current_view();
+        (
+            egui::Id::new(("tlscroll", timeline.view_id())),
+            view.unseen,
+            view.notes.len(),
+        )
     };
 
+    let jump_id = scroll_id.with("jump");
+    let row_height_id = scroll_id.with("row_height");
+    let jump: Option<Jump> = ui.data(|d| d.get_temp(jump_id));
+
+    let mut scroll_area = egui::ScrollArea::vertical()
+        .id_salt(scroll_id)
+        .animated(false)
+        .auto_shrink([false, false])
+        .scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible);
+
+    let mut scroll_to = None;
+    match jump {
+        Some(Jump::Top) => {
+            scroll_area = scroll_area.vertical_scroll_offset(0.0);
+            ui.data_mut(|d| d.remove::<Jump>(jump_id));
+        }
+
+        Some(Jump::Note { index, estimated }) => {
+            // the note probably isn't laid out yet, so get close using the
+            // average row height and let the list line it up once it's
+            // visible
+            if !estimated {
+                let row_height: f32 = ui.data(|d| d.get_temp(row_height_id)).unwrap_or(0.0);
+                scroll_area = scroll_area.vertical_scroll_offset(index as f32 * row_height);
+                ui.data_mut(|d| {
+                    d.insert_temp(
+                        jump_id,
+                        Jump::Note {
+                            index,
+                            estimated: true,
+                        },
+                    )
+                });
+            }
+            scroll_to = Some(index);
+        }
+
+        None => {}
+    }
+
+    let output = scroll_area.show(ui, |ui| {
+        let timeline = if let Some(timeline) = columns.find_timeline_mut(timeline_id) {
+            timeline
+        } else {
+            error!("tried to render timeline in column, but timeline was missing");
+            // TODO (jb55): render error when timeline is missing?
+            // this shouldn't happen...
+            return None;
+        };
+
+        let txn = Transaction::new(ndb).expect("failed to create txn");
+        let mut view = TimelineTabView::new(
+            timeline.current_view(),
+            reversed,
+            note_options,
+            &txn,
+            ndb,
+            note_cache,
+            img_cache,
+            is_muted,
+        )
+        .scroll_to(scroll_to);
+
+        let action = view.show(ui);
+        if view.scrolled_to() {
+            ui.data_mut(|d| d.remove::<Jump>(jump_id));
+        }
+        action
+    });
+
+    if len > 0 {
+        let row_height = output.content_size.y / len as f32;
+        ui.data_mut(|d| d.insert_temp(row_height_id, row_height));
+    }
+
+    let offset = output.state.offset.y;
+    if offset <= 0.0 && unseen > 0 {
+        if let Some(timeline) = columns.find_timeline_mut(timeline_id) {
+            timeline.current_view_mut().unseen = 0;
+        }
+    }
+
+    if offset > output.inner_rect.height() {
+        if let Some(jump) = jump_button(ui, output.inner_rect, unseen) {
+            ui.data_mut(|d| d.insert_temp(jump_id, jump));
+        }
+    }
+
+    output.inner
+}
+
+fn media_mode_id(timeline_id: TimelineId) -> egui::Id {
+    egui::Id::new(("timeline-media-mode", timeline_id))
+}
+
+/// Whether a column is showing a grid of its media instead of notes
+pub fn is_media_mode(ctx: &egui::Context, timeline_id: TimelineId) -> bool {
+    ctx.data(|d| d.get_temp(media_mode_id(timeline_id)))
+        .unwrap_or(false)
+}
+
+pub fn toggle_media_mode(ctx: &egui::Context, timeline_id: TimelineId) {
+    let enabled = is_media_mode(ctx, timeline_id);
+    ctx.data_mut(|d| d.insert_temp(media_mode_id(timeline_id), !enabled));
+}
+
+fn media_ui(
+    ui: &mut egui::Ui,
+    ndb: &Ndb,
+    timeline: &Timeline,
+    note_cache: &mut NoteCache,
+    img_cache: &mut ImageCache,
+    is_muted: &MuteFun,
+) -> Option<NoteAction> {
+    let scroll_id = egui::Id::new(("tlmedia", timeline.view_id()));
     egui::ScrollArea::vertical()
         .id_salt(scroll_id)
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            let txn = Transaction::new(ndb).expect("failed to create txn");
+            MediaGrid::new(
+                scroll_id,
+                &timeline.current_view().notes,
+                &txn,
+                ndb,
+                note_cache,
+                img_cache,
+                is_muted,
+            )
+            .show(ui)
+        })
+        .inner
+}
+
+fn history_request_id(timeline_id: TimelineId) -> egui::Id {
+    egui::Id::new(("timeline-history", timeline_id))
+}
+
+/// Switch a timeline over to reading an earlier day, starting with today
+pub fn open_history(ctx: &egui::Context, timeline_id: TimelineId) {
+    ctx.data_mut(|d| d.insert_temp(history_request_id(timeline_id), true));
+}
+
+fn take_history_request(ctx: &egui::Context, timeline_id: TimelineId) -> bool {
+    ctx.data_mut(|d| d.remove_temp::<bool>(history_request_id(timeline_id)))
+        .unwrap_or(false)
+}
+
+enum HistoryAction {
+    Day(NaiveDate),
+    /// Read back from this time of day, or the end of the day
+    Time(Option<NaiveTime>),
+    /// Read back from this moment, when the clocks changed at the time
+    /// picked and it could mean more than one
+    At(u64),
+    Close,
+}
+
+/// A timeline showing one day from nostrdb. Older pages are loaded as we
+/// get near the bottom.
+fn history_ui(
+    ui: &mut egui::Ui,
+    ndb: &Ndb,
+    timeline: &mut Timeline,
+    note_cache: &mut NoteCache,
+    img_cache: &mut ImageCache,
+    note_options: NoteOptions,
+    is_muted: &MuteFun,
+) -> Option<NoteAction> {
+    let view_filter = timeline.current_view().filter;
+    let history = timeline.history.as_ref()?;
+    let (day, time, end, history_filter) =
+        (history.day, history.time, history.end, history.tab.filter);
+
+    let mut action = history_bar(ui, timeline.id, history);
+    if is_calendar_open(ui.ctx(), timeline.id) {
+        action = history_calendar(ui, ndb, timeline, day).or(action);
+    }
+
+    match action {
+        Some(HistoryAction::Day(day)) => {
+            timeline.history = Some(TimelineHistory::new(day, view_filter).until(time));
+        }
+        Some(HistoryAction::Time(time)) => {
+            timeline.history = Some(TimelineHistory::new(day, view_filter).until(time));
+        }
+        Some(HistoryAction::At(end)) => {
+            timeline.history = Some(TimelineHistory::new(day, view_filter).until(time).at(end));
+        }
+        Some(HistoryAction::Close) => {
+            timeline.history = None;
+            return None;
+        }
+        // switched tabs
+        None if history_filter != view_filter => {
+            let mut history = TimelineHistory::new(day, view_filter).until(time);
+            if let Some(end) = end {
+                history = history.at(end);
+            }
+            timeline.history = Some(history);
+        }
+        None => {}
+    }
+
+    let history = timeline.history.as_mut()?;
+    let backfilling = timeline.backfill.is_loading(&history.remote_range());
+    let output = egui::ScrollArea::vertical()
+        .id_salt(("tlhistory", timeline.id, history.day))
         .animated(false)
         .auto_shrink([false, false])
         .scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible)
         .show(ui, |ui| {
-            let timeline = if let Some(timeline) = columns.find_timeline_mut(timeline_id) {
-                timeline
-            } else {
-                error!("tried to render timeline in column, but timeline was missing");
-                // TODO (jb55): render error when timeline is missing?
-                // this shouldn't happen...
-                return None;
-            };
-
             let txn = Transaction::new(ndb).expect("failed to create txn");
-            TimelineTabView::new(
-                timeline.current_view(),
-                reversed,
+            let action = TimelineTabView::new(
+                &history.tab,
+                false,
                 note_options,
                 &txn,
                 ndb,
@@ -131,9 +368,307 @@ fn timeline_ui(
                 img_cache,
                 is_muted,
             )
-            .show(ui)
+            .show(ui);
+
+            if backfilling {
+                ui.add_space(8.0);
+                ui.vertical_centered(|ui| {
+                    ui.spinner();
+                    ui.label(egui::RichText::new(tr!("timeline-history-backfilling")).weak());
+                });
+            } else if history.exhausted() {
+                ui.add_space(8.0);
+                ui.vertical_centered(|ui| {
+                    ui.label(egui::RichText::new(tr!("timeline-history-end")).weak());
+                });
+            }
+
+            action
+        });
+
+    let below = output.content_size.y - output.state.offset.y - output.inner_rect.height();
+    if below < output.inner_rect.height() && !history.exhausted() {
+        if let Some(filters) = timeline.filter.get_any_ready() {
+            history.load_older(ndb, note_cache, filters);
+            ui.ctx().request_repaint();
+        }
+    }
+
+    // nostrdb has nothing older for the day, see if relays do
+    if history.exhausted() && !history.resume(timeline.backfill.generation()) {
+        timeline.backfill.want(history.remote_range());
+    }
+    if backfilling {
+        ui.ctx().request_repaint_after(Duration::from_millis(250));
+    }
+
+    output.inner
+}
+
+/// Previous and next day buttons around the day being shown, which can
+/// also be picked or typed in, and the time of day to read back from
+fn history_bar(
+    ui: &mut egui::Ui,
+    timeline_id: TimelineId,
+    history: &TimelineHistory,
+) -> Option<HistoryAction> {
+    let (day, time) = (history.day, history.time);
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if ui
+            .button("◀")
+            .on_hover_text(tr!("timeline-previous-day"))
+            .clicked()
+        {
+            action = day.pred_opt().map(HistoryAction::Day);
+        }
+
+        let mut picked = day;
+        let picker = DatePicker::new(history_request_id(timeline_id), &mut picked)
+            .max_date(datetime::today());
+        if ui.add(picker).changed() {
+            action = Some(HistoryAction::Day(picked));
+        }
+
+        let next = ui
+            .add_enabled(day < datetime::today(), egui::Button::new("▶"))
+            .on_hover_text(tr!("timeline-next-day"));
+        if next.clicked() {
+            action = day.succ_opt().map(HistoryAction::Day);
+        }
+
+        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.button(tr!("timeline-back-to-latest")).clicked() {
+                action = Some(HistoryAction::Close);
+            }
+
+            let open = is_calendar_open(ui.ctx(), timeline_id);
+            if ui
+                .selectable_label(open, tr!("timeline-month"))
+                .on_hover_text(tr!("timeline-calendar"))
+                .clicked()
+            {
+                ui.data_mut(|d| d.insert_temp(calendar_id(timeline_id), !open));
+            }
+        });
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(tr!("timeline-history-until"));
+
+        // reading back from the end of the day until a time is picked
+        let end_of_day = NaiveTime::from_hms_opt(23, 59, 0).unwrap_or(NaiveTime::MIN);
+        let mut picked = time.unwrap_or(end_of_day);
+        if ui
+            .add(TimePicker::new(
+                history_request_id(timeline_id),
+                &mut picked,
+            ))
+            .changed()
+        {
+            action = Some(HistoryAction::Time(Some(picked)));
+        }
+
+        if time.is_some() && ui.small_button(tr!("timeline-history-whole-day")).clicked() {
+            action = Some(HistoryAction::Time(None));
+        }
+    });
+
+    if let Some(local_time) = history.local_time {
+        action = clock_change_ui(ui, local_time, history.end).or(action);
+    }
+    ui::hline(ui);
+
+    action
+}
+
+/// The picked time in UTC, and a warning with both readings of it when
+/// the clocks changed at that time
+fn clock_change_ui(
+    ui: &mut egui::Ui,
+    local_time: LocalTime,
+    end: Option<u64>,
+) -> Option<HistoryAction> {
+    let Some(candidates) = local_time.candidates() else {
+        let utc = datetime::format_utc(local_time.earliest());
+        ui.weak(tr!("timeline-history-utc", time = utc));
+        return None;
+    };
+
+    let warning = match local_time {
+        LocalTime::Skipped { .. } => tr!("timeline-history-time-skipped"),
+        _ => tr!("timeline-history-time-repeated"),
+    };
+    ui.add(egui::Label::new(egui::RichText::new(warning).color(ui.visuals().warn_fg_color)).wrap());
+
+    let settings = datetime::settings();
+    let mut action = None;
+    ui.horizontal_wrapped(|ui| {
+        for candidate in candidates {
+            let label = format!(
+                "{} {}",
+                settings.format_time(candidate),
+                datetime::utc_offset(candidate)
+            );
+            if ui
+                .selectable_label(end == Some(candidate), label)
+                .on_hover_text(datetime::format_utc(candidate))
+                .clicked()
+            {
+                action = Some(HistoryAction::At(candidate));
+            }
+        }
+    });
+
+    if let Some(end) = end {
+        ui.weak(tr!(
+            "timeline-history-utc",
+            time = datetime::format_utc(end)
+        ));
+    }
+
+    action
+}
+
+fn calendar_id(timeline_id: TimelineId) -> egui::Id {
+    history_request_id(timeline_id).with("calendar")
+}
+
+fn is_calendar_open(ctx: &egui::Context, timeline_id: TimelineId) -> bool {
+    ctx.data(|d| d.get_temp::<bool>(calendar_id(timeline_id)))
+        .unwrap_or(false)
+}
+
+/// A month to pick a day from, with dots for how busy each day was.
+/// Months from before the live timeline reaches back are fetched from
+/// relays too.
+fn history_calendar(
+    ui: &mut egui::Ui,
+    ndb: &Ndb,
+    timeline: &mut Timeline,
+    day: NaiveDate,
+) -> Option<HistoryAction> {
+    let id = calendar_id(timeline.id);
+    let month = MiniMonth::shown_month(ui.ctx(), id, day);
+
+    if let Some(range) = month_range(month) {
+        let oldest = timeline
+            .all_or_any_notes()
+            .last()
+            .map(|note| note.created_at);
+        if !matches!(oldest, Some(oldest) if oldest <= range.start) {
+            let end = oldest.map_or(range.end, |oldest| oldest.min(range.end));
+            timeline.backfill.want(range.start..end);
+        }
+    }
+
+    let loading = loading_days(timeline, month);
+    if !loading.is_empty() {
+        ui.ctx().request_repaint_after(Duration::from_millis(250));
+    }
+
+    // counting a month of notes isn't free, so only do it once per month,
+    // and again when relays have sent more
+    let density_id = id.with((month, timeline.backfill.generation()));
+    let density = match ui.data(|d| d.get_temp::<Arc<HashMap<NaiveDate, usize>>>(density_id)) {
+        Some(density) => density,
+        None => match timeline.filter.get_any_ready() {
+            Some(filters) => {
+                let density = Arc::new(month_density(ndb, filters, month));
+                ui.data_mut(|d| d.insert_temp(density_id, density.clone()));
+                density
+            }
+            None => Arc::default(),
+        },
+    };
+
+    ui.add_space(4.0);
+    let picked = MiniMonth::new(id, day)
+        .max_date(datetime::today())
+        .density(&density)
+        .loading(&loading)
+        .ui(ui);
+    ui.add_space(4.0);
+    ui::hline(ui);
+
+    picked.map(HistoryAction::Day)
+}
+
+/// Days of the month starting at `month` that relays are still being
+/// asked about
+fn loading_days(timeline: &Timeline, month: NaiveDate) -> HashSet<NaiveDate> {
+    month
+        .iter_days()
+        .take_while(|day| day.month() == month.month())
+        .filter(|day| {
+            datetime::day_range(&Local, *day)
+                .is_some_and(|range| timeline.backfill.is_loading(&range))
         })
-        .inner
+        .collect()
+}
+
+/// Where a timeline should scroll to on the next frame
+#[derive(Debug, Clone, Copy)]
+enum Jump {
+    Top,
+    /// Scroll so the note at `index` is at the top. `estimated` is set once
+    /// we've moved close enough for the list to lay it out.
+    Note {
+        index: usize,
+        estimated: bool,
+    },
+}
+
+/// A floating button to get back to the top of a long timeline. Right
+/// click or long press for more places to jump to.
+fn jump_button(ui: &mut egui::Ui, column: egui::Rect, unseen: usize) -> Option<Jump> {
+    let size = egui::vec2(40.0, 40.0);
+    let margin = 16.0;
+    let rect = egui::Rect::from_min_size(
+        egui::pos2(
+            column.right() - size.x - margin,
+            column.bottom() - size.y - margin,
+        ),
+        size,
+    );
+
+    let label = if unseen > 0 {
+        format!("↑ {}", notedeck::ui::badge_text(unseen))
+    } else {
+        "↑".to_owned()
+    };
+
+    let resp = ui
+        .put(
+            rect,
+            egui::Button::new(egui::RichText::new(label).strong())
+                .rounding(size.y / 2.0)
+                .fill(ui.visuals().widgets.inactive.bg_fill),
+        )
+        .on_hover_text(tr!("timeline-jump-to-top"));
+
+    let mut jump = resp.clicked().then_some(Jump::Top);
+
+    resp.context_menu(|ui| {
+        if ui.button(tr!("timeline-jump-to-top")).clicked() {
+            jump = Some(Jump::Top);
+            ui.close_menu();
+        }
+
+        let unread = ui.add_enabled(
+            unseen > 0,
+            egui::Button::new(tr!("timeline-jump-to-oldest-new")),
+        );
+        if unread.clicked() {
+            jump = Some(Jump::Note {
+                index: unseen - 1,
+                estimated: false,
+            });
+            ui.close_menu();
+        }
+    });
+
+    jump
 }
 
 pub fn tabs_ui(ui: &mut egui::Ui, selected: usize, views: &[TimelineTab]) -> usize {
@@ -154,17 +689,18 @@ pub fn tabs_ui(ui: &mut egui::Ui, selected: usize, views: &[TimelineTab]) -> usi
             let ind = state.index();
 
             let txt = match views[ind as usize].filter {
-                ViewFilter::Notes => "Notes",
-                ViewFilter::NotesAndReplies => "Notes & Replies",
+                ViewFilter::Notes => tr!("timeline-notes"),
+                ViewFilter::NotesAndReplies => tr!("timeline-notes-and-replies"),
+                ViewFilter::Media => tr!("timeline-media"),
             };
 
-            let res = ui.add(egui::Label::new(txt).selectable(false));
+            let res = ui.add(egui::Label::new(&txt).selectable(false));
 
             // underline
             if state.is_selected() {
                 let rect = res.rect;
                 let underline =
-                    shrink_range_to_width(rect.x_range(), get_label_width(ui, txt) * 1.15);
+                    shrink_range_to_width(rect.x_range(), get_label_width(ui, &txt) * 1.15);
                 let underline_y = ui.painter().round_to_pixel(rect.bottom()) - 1.5;
                 return (underline, underline_y);
             }
@@ -232,6 +768,8 @@ pub struct TimelineTabView<'a> {
     note_cache: &'a mut NoteCache,
     img_cache: &'a mut ImageCache,
     is_muted: &'a MuteFun,
+    scroll_to: Option<usize>,
+    scrolled_to: bool,
 }
 
 impl<'a> TimelineTabView<'a> {
@@ -255,9 +793,22 @@ impl<'a> TimelineTabView<'a> {
             note_cache,
             img_cache,
             is_muted,
+            scroll_to: None,
+            scrolled_to: false,
         }
     }
 
+    /// Scroll so the note at this index is at the top, once it's laid out
+    pub fn scroll_to(mut self, index: Option<usize>) -> Self {
+        self.scroll_to = index;
+        self
+    }
+
+    /// Did we reach the note we were asked to scroll to?
+    pub fn scrolled_to(&self) -> bool {
+        self.scrolled_to
+    }
+
     pub fn show(&mut self, ui: &mut egui::Ui) -> Option<NoteAction> {
         let mut action: Option<NoteAction> = None;
         let len = self.tab.notes.len();
@@ -295,8 +846,15 @@ impl<'a> TimelineTabView<'a> {
                     false
                 };
 
-                if !muted {
-                    ui::padding(8.0, ui, |ui| {
+                // NIP-40: relays may still hand us notes that have expired
+                let expired = self
+                    .note_cache
+                    .cached_note_or_insert(note_key, &note)
+                    .is_expired();
+
+                if !muted && !expired {
+                    let padding = Density::get(ui.ctx()).note_padding();
+                    ui::padding(padding, ui, |ui| {
                         let resp =
                             ui::NoteView::new(self.ndb, self.note_cache, self.img_cache, &note)
                                 .note_options(self.note_options)
@@ -314,6 +872,11 @@ impl<'a> TimelineTabView<'a> {
                     ui::hline(ui);
                 }
 
+                if self.scroll_to == Some(ind) {
+                    ui.scroll_to_rect(ui.min_rect(), Some(egui::Align::TOP));
+                    self.scrolled_to = true;
+                }
+
                 1
             });
 
@@ -0,0 +1,164 @@
+use egui::{Frame, RichText};
+use enostr::NoteId;
+use nostrdb::{Ndb, Transaction};
+use notedeck::{tr, ImageCache, NoteCache, UnknownIds};
+
+use crate::{
+    actionbar::NoteAction,
+    trending::{TrendingResults, TrendingSource},
+    ui::{self, note::NoteOptions},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Tab {
+    #[default]
+    Notes,
+    Hashtags,
+}
+
+/// Notes and hashtags that are taking off
+pub struct TrendingView<'a> {
+    ndb: &'a Ndb,
+    note_cache: &'a mut NoteCache,
+    img_cache: &'a mut ImageCache,
+    unknown_ids: &'a mut UnknownIds,
+    results: &'a TrendingResults,
+    refreshing: bool,
+    note_options: NoteOptions,
+}
+
+impl<'a> TrendingView<'a> {
+    pub fn new(
+        ndb: &'a Ndb,
+        note_cache: &'a mut NoteCache,
+        img_cache: &'a mut ImageCache,
+        unknown_ids: &'a mut UnknownIds,
+        results: &'a TrendingResults,
+        refreshing: bool,
+        note_options: NoteOptions,
+    ) -> Self {
+        TrendingView {
+            ndb,
+            note_cache,
+            img_cache,
+            unknown_ids,
+            results,
+            refreshing,
+            note_options,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<NoteAction> {
+        let tab_id = ui.id().with("trending-tab");
+        let mut tab: Tab = ui.data(|d| d.get_temp(tab_id)).unwrap_or_default();
+
+        Frame::none()
+            .inner_margin(egui::Margin::symmetric(8.0, 4.0))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut tab, Tab::Notes, tr!("trending-notes"));
+                    ui.selectable_value(&mut tab, Tab::Hashtags, tr!("trending-hashtags"));
+                    if self.refreshing {
+                        ui.spinner();
+                    }
+                });
+                if let TrendingSource::Fallback(err) = &self.results.source {
+                    ui.label(
+                        RichText::new(tr!("trending-service-failed"))
+                            .small()
+                            .color(ui.visuals().warn_fg_color),
+                    )
+                    .on_hover_text(err);
+                }
+            });
+        ui.data_mut(|d| d.insert_temp(tab_id, tab));
+        ui::hline(ui);
+
+        let empty = match tab {
+            Tab::Notes => self.results.notes.is_empty(),
+            Tab::Hashtags => self.results.hashtags.is_empty(),
+        };
+        if empty {
+            ui.add_space(16.0);
+            ui.vertical_centered(|ui| {
+                let text = if self.refreshing {
+                    tr!("trending-loading")
+                } else {
+                    tr!("trending-empty")
+                };
+                ui.label(RichText::new(text).weak());
+            });
+            return None;
+        }
+
+        let mut action = None;
+        egui::ScrollArea::vertical()
+            .id_salt(("trending", tab == Tab::Notes))
+            .auto_shrink([false, false])
+            .show(ui, |ui| match tab {
+                Tab::Notes => action = self.notes_ui(ui),
+                Tab::Hashtags => self.hashtags_ui(ui),
+            });
+
+        action
+    }
+
+    fn notes_ui(&mut self, ui: &mut egui::Ui) -> Option<NoteAction> {
+        let txn = Transaction::new(self.ndb).expect("txn");
+        let mut action = None;
+
+        for trending in &self.results.notes {
+            let Ok(note) = self.ndb.get_note_by_id(&txn, &trending.id) else {
+                // shows up once relays send it
+                self.unknown_ids
+                    .add_note_id_if_missing(self.ndb, &txn, &NoteId::new(trending.id));
+                continue;
+            };
+
+            ui::padding(8.0, ui, |ui| {
+                let resp = ui::NoteView::new(self.ndb, self.note_cache, self.img_cache, &note)
+                    .note_options(self.note_options)
+                    .show(ui);
+                if let Some(note_action) = resp.action {
+                    action = Some(note_action);
+                }
+                if let Some(context) = resp.context_selection {
+                    context.process(ui, &note);
+                }
+            });
+            ui::hline(ui);
+        }
+
+        action
+    }
+
+    fn hashtags_ui(&mut self, ui: &mut egui::Ui) {
+        let link_color = ui.visuals().hyperlink_color;
+        let top = self
+            .results
+            .hashtags
+            .first()
+            .map_or(1.0, |top| top.score.max(f32::EPSILON));
+
+        for (rank, trending) in self.results.hashtags.iter().enumerate() {
+            ui::padding(8.0, ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(format!("{}", rank + 1)).weak());
+                    ui::hashtag::hashtag_link(ui, &trending.hashtag, link_color);
+                    if trending.people > 0 {
+                        ui.label(
+                            RichText::new(tr!("trending-people", count = trending.people))
+                                .weak()
+                                .small(),
+                        );
+                    }
+                });
+                ui.add(
+                    egui::ProgressBar::new(trending.score / top)
+                        .desired_height(4.0)
+                        .desired_width(ui.available_width()),
+                );
+            });
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use egui::RichText;
+use enostr::NoteId;
+use nostrdb::{Ndb, Transaction};
+use notedeck::tr;
+
+use crate::hidden::Hidden;
+
+/// How much of an unsent note to show so it can be recognized
+const PREVIEW_CHARS: usize = 80;
+
+pub enum UnsentAction {
+    Retry(NoteId),
+    /// Leave it out of the columns for good
+    Discard(NoteId),
+}
+
+/// Notes that no relay took and were rolled back, with a way to send them
+/// again. Only shown while there are some.
+pub fn unsent_window(ctx: &egui::Context, ndb: &Ndb, hidden: &Hidden) -> Option<UnsentAction> {
+    if hidden.unsent.is_empty() {
+        return None;
+    }
+
+    let txn = Transaction::new(ndb).expect("txn");
+    let mut action = None;
+    egui::Window::new(tr!("unsent-title"))
+        .collapsible(true)
+        .resizable(false)
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -16.0))
+        .show(ctx, |ui| {
+            ui.label(RichText::new(tr!("unsent-hint")).weak());
+            ui.add_space(8.0);
+            for id in hidden.sorted_unsent() {
+                let preview = match ndb.get_note_by_id(&txn, id.bytes()) {
+                    Ok(note) => note.content().chars().take(PREVIEW_CHARS).collect(),
+                    Err(_) => id.to_bech().unwrap_or_else(|| id.hex()),
+                };
+                ui.horizontal(|ui| {
+                    if ui.small_button(tr!("unsent-retry")).clicked() {
+                        action = Some(UnsentAction::Retry(id));
+                    }
+                    if ui.small_button(tr!("unsent-discard")).clicked() {
+                        action = Some(UnsentAction::Discard(id));
+                    }
+                    ui.add(egui::Label::new(preview).truncate());
+                });
+            }
+        });
+
+    action
+}
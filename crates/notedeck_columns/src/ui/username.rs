@@ -74,6 +74,8 @@ fn colored_name(name: &str, color: Option<Color32>) -> RichText {
 }
 
 fn ui_abbreviate_name(ui: &mut egui::Ui, name: &str, len: usize, color: Option<Color32>) {
+    let name = notedeck::text::prepare(name);
+    let name = name.as_ref();
     let should_abbrev = name.len() > len;
     let name = if should_abbrev {
         let closest = crate::abbrev::floor_char_boundary(name, len);
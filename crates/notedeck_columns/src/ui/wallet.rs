@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+
+use egui::{Frame, RichText, ScrollArea};
+use enostr::{NwcDirection, NwcTransaction, NwcUri, Pubkey};
+use nostrdb::{Ndb, Transaction};
+use notedeck::{amount, datetime, tr, ImageCache};
+
+use crate::{
+    actionbar::NoteAction,
+    profile::get_display_name,
+    ui,
+    wallet::{Wallet, WalletStatus},
+};
+
+pub enum WalletAction {
+    /// Try a connection before saving it
+    Pair(String),
+    /// Take a picture of a wallet's QR code
+    ScanQr,
+    SavePairing,
+    CancelPairing,
+    Disconnect,
+    Refresh,
+    Pay(String),
+    MakeInvoice {
+        amount_msats: u64,
+        description: String,
+    },
+    Note(NoteAction),
+}
+
+/// Balance, history, and sending and receiving for the connected wallet
+pub struct WalletView<'a> {
+    wallet: Option<&'a Wallet>,
+    pairing: Option<&'a Wallet>,
+    ndb: &'a Ndb,
+    img_cache: &'a mut ImageCache,
+    id_string_map: &'a mut HashMap<egui::Id, String>,
+}
+
+impl<'a> WalletView<'a> {
+    pub fn new(
+        wallet: Option<&'a Wallet>,
+        pairing: Option<&'a Wallet>,
+        ndb: &'a Ndb,
+        img_cache: &'a mut ImageCache,
+        id_string_map: &'a mut HashMap<egui::Id, String>,
+    ) -> Self {
+        WalletView {
+            wallet,
+            pairing,
+            ndb,
+            img_cache,
+            id_string_map,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<WalletAction> {
+        let mut action = None;
+
+        Frame::none()
+            .inner_margin(egui::Margin::same(16.0))
+            .show(ui, |ui| {
+                action = match (self.pairing, self.wallet) {
+                    (Some(pairing), _) => pairing_ui(ui, pairing),
+                    (None, None) => connect_ui(ui, self.id_string_map),
+                    (None, Some(wallet)) => {
+                        wallet_ui(ui, wallet, self.ndb, self.img_cache, self.id_string_map)
+                    }
+                };
+            });
+
+        action
+    }
+}
+
+fn connect_ui(
+    ui: &mut egui::Ui,
+    id_string_map: &mut HashMap<egui::Id, String>,
+) -> Option<WalletAction> {
+    ui.label(tr!("wallet-connect-hint"));
+    ui.add_space(8.0);
+
+    let id = ui.id().with("wallet-uri");
+    let uri = id_string_map.entry(id).or_default();
+    ui.add(
+        egui::TextEdit::singleline(uri)
+            .hint_text("nostr+walletconnect://…")
+            .password(true)
+            .desired_width(f32::INFINITY),
+    );
+
+    let valid = NwcUri::parse(uri).is_ok();
+    if !valid && !uri.trim().is_empty() {
+        ui.colored_label(ui.visuals().error_fg_color, tr!("wallet-invalid-uri"));
+    }
+
+    ui.add_space(8.0);
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(valid, egui::Button::new(tr!("wallet-connect")))
+            .clicked()
+        {
+            action = id_string_map.remove(&id).map(WalletAction::Pair);
+        }
+        if cfg!(target_os = "android") && ui.button(tr!("wallet-scan-qr")).clicked() {
+            action = Some(WalletAction::ScanQr);
+        }
+    });
+    if !cfg!(target_os = "android") {
+        ui.weak(tr!("wallet-drop-qr"));
+    }
+
+    action
+}
+
+/// How trying out a new connection went, before it's saved
+fn pairing_ui(ui: &mut egui::Ui, pairing: &Wallet) -> Option<WalletAction> {
+    ui.label(RichText::new(tr!("wallet-pairing")).strong());
+    if let Some(lud16) = &pairing.uri().lud16 {
+        ui.weak(lud16);
+    }
+    ui.add_space(8.0);
+
+    match (&pairing.info, &pairing.status) {
+        (_, WalletStatus::Error(err)) => {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                tr!("wallet-error", error = err.as_str()),
+            );
+        }
+        (None, _) => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.weak(if pairing.is_connected() {
+                    tr!("wallet-pairing-testing")
+                } else {
+                    tr!("wallet-connecting")
+                });
+            });
+        }
+        (Some(info), _) => {
+            let name = info
+                .alias
+                .clone()
+                .unwrap_or_else(|| tr!("wallet-pairing-no-alias"));
+            ui.label(tr!("wallet-pairing-works", wallet = name));
+            if let Some(network) = &info.network {
+                ui.weak(network);
+            }
+            if let Some(msats) = pairing.balance {
+                ui.label(
+                    RichText::new(amount::format_msats(msats))
+                        .size(28.0)
+                        .strong(),
+                );
+            }
+            ui.weak(tr!(
+                "wallet-pairing-methods",
+                methods = info.methods.join(", ")
+            ));
+        }
+    }
+
+    ui.add_space(12.0);
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(
+                pairing.info.is_some(),
+                egui::Button::new(tr!("wallet-save")),
+            )
+            .clicked()
+        {
+            action = Some(WalletAction::SavePairing);
+        }
+        if ui.button(tr!("wallet-cancel")).clicked() {
+            action = Some(WalletAction::CancelPairing);
+        }
+    });
+
+    action
+}
+
+fn wallet_ui(
+    ui: &mut egui::Ui,
+    wallet: &Wallet,
+    ndb: &Ndb,
+    img_cache: &mut ImageCache,
+    id_string_map: &mut HashMap<egui::Id, String>,
+) -> Option<WalletAction> {
+    let mut action = None;
+
+    ui.horizontal(|ui| {
+        let balance = match wallet.balance {
+            Some(msats) => amount::format_msats(msats),
+            None if wallet.is_connected() => tr!("wallet-loading"),
+            None => tr!("wallet-connecting"),
+        };
+        ui.label(RichText::new(balance).size(28.0).strong());
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.button(tr!("wallet-disconnect")).clicked() {
+                action = Some(WalletAction::Disconnect);
+            }
+            if ui.button(tr!("wallet-refresh")).clicked() {
+                action = Some(WalletAction::Refresh);
+            }
+        });
+    });
+    if let Some(lud16) = &wallet.uri().lud16 {
+        ui.weak(lud16);
+    }
+
+    ui.add_space(12.0);
+    ui.separator();
+
+    if let Some(send) = send_ui(ui, id_string_map) {
+        action = Some(send);
+    }
+    ui.add_space(8.0);
+    if let Some(receive) = receive_ui(ui, id_string_map) {
+        action = Some(receive);
+    }
+    status_ui(ui, &wallet.status);
+
+    ui.add_space(12.0);
+    ui.separator();
+    ui.label(RichText::new(tr!("wallet-transactions")).strong());
+    ui.add_space(4.0);
+
+    if wallet.transactions.is_empty() {
+        ui.weak(tr!("wallet-no-transactions"));
+        return action;
+    }
+
+    let Ok(txn) = Transaction::new(ndb) else {
+        return action;
+    };
+
+    ScrollArea::vertical().show(ui, |ui| {
+        for transaction in &wallet.transactions {
+            if let Some(note_action) = transaction_ui(ui, ndb, &txn, img_cache, transaction) {
+                action = Some(WalletAction::Note(note_action));
+            }
+        }
+    });
+
+    action
+}
+
+fn send_ui(
+    ui: &mut egui::Ui,
+    id_string_map: &mut HashMap<egui::Id, String>,
+) -> Option<WalletAction> {
+    let id = ui.id().with("wallet-invoice");
+    let invoice = id_string_map.entry(id).or_default();
+
+    ui.label(RichText::new(tr!("wallet-send")).strong());
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(invoice)
+                .hint_text(tr!("wallet-invoice-hint"))
+                .desired_width(ui.available_width() - 80.0),
+        );
+
+        let amount = amount::bolt11_msats(invoice);
+        let pay = ui
+            .add_enabled(amount.is_some(), egui::Button::new(tr!("wallet-pay")))
+            .on_disabled_hover_text(tr!("wallet-invoice-no-amount"));
+        if let Some(msats) = amount {
+            ui.weak(amount::format_msats(msats));
+        }
+        pay.clicked()
+    })
+    .inner
+    .then(|| id_string_map.remove(&id).map(WalletAction::Pay))
+    .flatten()
+}
+
+fn receive_ui(
+    ui: &mut egui::Ui,
+    id_string_map: &mut HashMap<egui::Id, String>,
+) -> Option<WalletAction> {
+    let amount_id = ui.id().with("wallet-receive-amount");
+    let description_id = ui.id().with("wallet-receive-description");
+
+    ui.label(RichText::new(tr!("wallet-receive")).strong());
+    let clicked = ui
+        .horizontal(|ui| {
+            let sats = id_string_map.entry(amount_id).or_default();
+            ui.add(
+                egui::TextEdit::singleline(sats)
+                    .hint_text(tr!("wallet-amount-sats"))
+                    .desired_width(100.0),
+            );
+            let valid = sats.trim().parse::<u64>().is_ok_and(|sats| sats > 0);
+
+            let description = id_string_map.entry(description_id).or_default();
+            ui.add(
+                egui::TextEdit::singleline(description)
+                    .hint_text(tr!("wallet-description"))
+                    .desired_width(ui.available_width() - 120.0),
+            );
+
+            ui.add_enabled(valid, egui::Button::new(tr!("wallet-create-invoice")))
+                .clicked()
+        })
+        .inner;
+
+    if !clicked {
+        return None;
+    }
+
+    let sats: u64 = id_string_map.remove(&amount_id)?.trim().parse().ok()?;
+    let description = id_string_map.remove(&description_id).unwrap_or_default();
+    Some(WalletAction::MakeInvoice {
+        amount_msats: sats * 1000,
+        description,
+    })
+}
+
+fn status_ui(ui: &mut egui::Ui, status: &WalletStatus) {
+    match status {
+        WalletStatus::Idle => {}
+        WalletStatus::Working => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.weak(tr!("wallet-working"));
+            });
+        }
+        WalletStatus::Paid => {
+            ui.label(tr!("wallet-paid"));
+        }
+        WalletStatus::Invoice(invoice) => {
+            ui.add_space(8.0);
+            ui.add(ui::qr::QrCodeView::new(invoice).size(200.0));
+            ui.horizontal(|ui| {
+                ui.add(egui::Label::new(RichText::new(invoice).small().weak()).truncate());
+                if ui.small_button(tr!("wallet-copy-invoice")).clicked() {
+                    ui.ctx().copy_text(invoice.clone());
+                }
+            });
+        }
+        WalletStatus::Error(err) => {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                tr!("wallet-error", error = err.as_str()),
+            );
+        }
+    }
+}
+
+/// A transaction, with who it was from or to when it was a zap
+fn transaction_ui(
+    ui: &mut egui::Ui,
+    ndb: &Ndb,
+    txn: &Transaction,
+    img_cache: &mut ImageCache,
+    transaction: &NwcTransaction,
+) -> Option<NoteAction> {
+    let zap = transaction.zap();
+    let (arrow, counterparty): (&str, Option<Pubkey>) = match transaction.direction {
+        NwcDirection::Incoming => ("↓", zap.map(|zap| zap.sender)),
+        NwcDirection::Outgoing => ("↑", zap.map(|zap| zap.recipient)),
+    };
+
+    let mut action = None;
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(arrow).strong());
+
+        if let Some(pubkey) = counterparty {
+            let profile = ndb.get_profile_by_pubkey(txn, pubkey.bytes()).ok();
+            let pfp = ui
+                .add(
+                    ui::ProfilePic::new(img_cache, ui::profile::get_profile_url(profile.as_ref()))
+                        .size(24.0),
+                )
+                .interact(egui::Sense::click());
+            let name = ui.add(
+                egui::Label::new(get_display_name(profile.as_ref()).name())
+                    .sense(egui::Sense::click()),
+            );
+            if pfp.clicked() || name.clicked() {
+                action = Some(NoteAction::OpenProfile(pubkey));
+            }
+        } else if let Some(description) = transaction
+            .description
+            .as_deref()
+            .filter(|d| !d.is_empty() && zap.is_none())
+        {
+            ui.add(egui::Label::new(description).truncate());
+        }
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.weak(datetime::settings().format_datetime(transaction.created_at));
+            ui.label(amount::format_msats(transaction.amount));
+
+            if let Some(note_id) = zap.and_then(|zap| zap.note) {
+                if ui
+                    .small_button(tr!("wallet-open-note"))
+                    .on_hover_text(tr!("wallet-zapped-note"))
+                    .clicked()
+                {
+                    action = Some(NoteAction::OpenThread(note_id));
+                }
+            }
+        });
+    });
+
+    action
+}
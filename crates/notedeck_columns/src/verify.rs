@@ -0,0 +1,258 @@
+//! Events from relays are handed to nostrdb from a small pool of worker
+//! threads instead of the UI thread, a batch per frame. nostrdb's own
+//! ingester threads check ids and signatures and drop bad notes. What it
+//! doesn't know about is NIP-26 delegation, so notes claiming to be posted
+//! on someone's behalf are checked here before they can reach a timeline.
+//! Events their authors asked to delete (NIP-09) are dropped here too, so
+//! a slow relay can't bring them back.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+
+use enostr::NoteVerification;
+use nostrdb::Ndb;
+use notedeck::deletion::Deletions;
+use tracing::{debug, error};
+
+/// What the workers have been up to, for the subscriptions view
+#[derive(Debug, Default)]
+pub struct VerifyStats {
+    /// Events handed to the workers that they haven't gotten to yet
+    pub pending: AtomicUsize,
+    /// Delegated notes whose delegation checked out
+    pub delegated: AtomicUsize,
+    /// Notes dropped for a bad delegation or signature
+    pub dropped: AtomicUsize,
+    /// Events dropped because their author deleted them
+    pub deleted: AtomicUsize,
+}
+
+type SharedDeletions = Arc<RwLock<Arc<Deletions>>>;
+
+struct Incoming {
+    raw: String,
+    /// Events from other clients, like over multicast, rather than relays
+    client: bool,
+}
+
+#[derive(Default)]
+pub struct Verifier {
+    batch: Vec<Incoming>,
+    sender: Option<Sender<Vec<Incoming>>>,
+    pub stats: Arc<VerifyStats>,
+    deletions: SharedDeletions,
+}
+
+impl Verifier {
+    /// A relay event, as the whole `["EVENT", subid, note]` message
+    pub fn queue(&mut self, raw: &str) {
+        self.batch.push(Incoming {
+            raw: raw.to_owned(),
+            client: false,
+        });
+    }
+
+    /// An event from another client, as `["EVENT", note]`
+    pub fn queue_client(&mut self, raw: &str) {
+        self.batch.push(Incoming {
+            raw: raw.to_owned(),
+            client: true,
+        });
+    }
+
+    /// The deletions to drop events for from now on
+    pub fn set_deletions(&self, deletions: Arc<Deletions>) {
+        if let Ok(mut current) = self.deletions.write() {
+            *current = deletions;
+        }
+    }
+
+    /// Hand this frame's events to the workers, starting them if needed
+    pub fn flush(&mut self, ndb: &Ndb) {
+        if self.batch.is_empty() {
+            return;
+        }
+
+        let batch = std::mem::take(&mut self.batch);
+        self.stats.pending.fetch_add(batch.len(), Ordering::Relaxed);
+
+        let sender = self
+            .sender
+            .get_or_insert_with(|| start(ndb, &self.stats, &self.deletions));
+        if let Err(mpsc::SendError(batch)) = sender.send(batch) {
+            // the workers are gone, don't lose the events over it
+            error!("verify workers stopped, ingesting on the ui thread");
+            self.sender = None;
+            for incoming in batch {
+                ingest(ndb, &incoming);
+            }
+            self.stats.pending.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+fn worker_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get() / 2)
+        .unwrap_or(1)
+        .clamp(1, 4)
+}
+
+fn start(
+    ndb: &Ndb,
+    stats: &Arc<VerifyStats>,
+    deletions: &SharedDeletions,
+) -> Sender<Vec<Incoming>> {
+    let (sender, receiver) = mpsc::channel();
+    let receiver = Arc::new(Mutex::new(receiver));
+
+    for i in 0..worker_count() {
+        let (ndb, receiver, stats) = (ndb.clone(), receiver.clone(), stats.clone());
+        let deletions = deletions.clone();
+        let spawned = std::thread::Builder::new()
+            .name(format!("verify-{i}"))
+            .spawn(move || work(&ndb, &receiver, &stats, &deletions));
+        if let Err(err) = spawned {
+            error!("failed to spawn verify worker: {err}");
+        }
+    }
+
+    sender
+}
+
+fn work(
+    ndb: &Ndb,
+    receiver: &Mutex<Receiver<Vec<Incoming>>>,
+    stats: &VerifyStats,
+    deletions: &SharedDeletions,
+) {
+    loop {
+        let batch = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        // the app is shutting down
+        let Ok(batch) = batch else {
+            return;
+        };
+
+        let deletions = deletions
+            .read()
+            .map(|deletions| deletions.clone())
+            .unwrap_or_default();
+        for incoming in &batch {
+            stats.pending.fetch_sub(1, Ordering::Relaxed);
+            if is_deleted(&incoming.raw, &deletions) {
+                stats.deleted.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+
+            match check(&incoming.raw) {
+                Check::Plain => ingest(ndb, incoming),
+                Check::Delegated => {
+                    stats.delegated.fetch_add(1, Ordering::Relaxed);
+                    ingest(ndb, incoming);
+                }
+                Check::Invalid(verification) => {
+                    debug!("dropping event: {verification:?}");
+                    stats.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+fn ingest(ndb: &Ndb, incoming: &Incoming) {
+    let result = if incoming.client {
+        ndb.process_client_event(&incoming.raw)
+    } else {
+        ndb.process_event(&incoming.raw)
+    };
+    if let Err(err) = result {
+        error!("error processing event {}: {err}", incoming.raw);
+    }
+}
+
+/// A top level hex field of an event, without parsing all of it. Keys
+/// inside the content are escaped, so they can't match.
+fn hex_field(raw: &str, name: &str) -> Option<[u8; 32]> {
+    let key = format!("\"{name}\":\"");
+    let start = raw.find(&key)? + key.len();
+    hex::decode(raw.get(start..start + 64)?)
+        .ok()?
+        .try_into()
+        .ok()
+}
+
+fn is_deleted(raw: &str, deletions: &Deletions) -> bool {
+    if deletions.is_empty() {
+        return false;
+    }
+    match (hex_field(raw, "id"), hex_field(raw, "pubkey")) {
+        (Some(id), Some(author)) => deletions.is_deleted_id(&id, &author),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum Check {
+    /// Nothing for us to check, nostrdb does the rest
+    Plain,
+    Delegated,
+    Invalid(NoteVerification),
+}
+
+fn check(raw: &str) -> Check {
+    // hardly anything is delegated, so don't parse what can't be
+    if !raw.contains("\"delegation\"") {
+        return Check::Plain;
+    }
+
+    let note = serde_json::from_str::<serde_json::Value>(raw)
+        .ok()
+        .and_then(|message| message.as_array()?.last().cloned())
+        .map(|note| note.to_string());
+    let Some(note) = note else {
+        return Check::Invalid(NoteVerification::Malformed);
+    };
+
+    match NoteVerification::check(&note) {
+        NoteVerification::Valid => Check::Delegated,
+        invalid => Check::Invalid(invalid),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let note = r#"{"id":"70b10f70c1318967eddf12527799411b1a9780ad9c43858f5e5fcd45486a13a5","pubkey":"379e863e8357163b5bce5d2688dc4f1dcc2d505222fb8d74db600f30535dfdfe","created_at":1612809991,"kind":1,"tags":[],"content":"test","sig":"273a9cd5d11455590f4359500bccb7a89428262b96b3ea87a756b770964472f8c3e87f5d5e64d8d2e859a71462a3f477b554565c4f2f326cb01dd7620db71502"}"#;
+        assert_eq!(check(&format!(r#"["EVENT","sub",{note}]"#)), Check::Plain);
+
+        // a delegation tag changes the id, so this can't pass
+        let delegated = note.replace(
+            r#""tags":[]"#,
+            r#""tags":[["delegation","379e863e8357163b5bce5d2688dc4f1dcc2d505222fb8d74db600f30535dfdfe","kind=1","00"]]"#,
+        );
+        assert_eq!(
+            check(&format!(r#"["EVENT","sub",{delegated}]"#)),
+            Check::Invalid(NoteVerification::BadId)
+        );
+        assert_eq!(
+            check(r#"["EVENT","sub",{"tags":[["delegation"]]"#),
+            Check::Invalid(NoteVerification::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_hex_field() {
+        let raw = r#"["EVENT","sub",{"content":"{\"id\":\"00\"}","id":"70b10f70c1318967eddf12527799411b1a9780ad9c43858f5e5fcd45486a13a5","pubkey":"379e"}]"#;
+        assert_eq!(hex_field(raw, "id").map(|id| id[0]), Some(0x70));
+        // too short to be a key
+        assert_eq!(hex_field(raw, "pubkey"), None);
+        assert!(!is_deleted(raw, &Deletions::default()));
+    }
+}
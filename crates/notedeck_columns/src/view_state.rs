@@ -5,6 +5,9 @@ use enostr::Pubkey;
 use crate::deck_state::DeckState;
 use crate::login_manager::AcquireKeyState;
 use crate::profile_state::ProfileState;
+use crate::relay_info::RelayInfoCache;
+use crate::ui::note_edit::NoteEditDraft;
+use crate::ui::relay::RelayListDraft;
 
 /// Various state for views
 #[derive(Default)]
@@ -14,6 +17,9 @@ pub struct ViewState {
     pub id_state_map: HashMap<egui::Id, AcquireKeyState>,
     pub id_string_map: HashMap<egui::Id, String>,
     pub pubkey_to_profile_state: HashMap<Pubkey, ProfileState>,
+    pub relay_list_draft: RelayListDraft,
+    pub relay_info: RelayInfoCache,
+    pub note_edit: NoteEditDraft,
 }
 
 impl ViewState {
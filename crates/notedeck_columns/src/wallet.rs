@@ -0,0 +1,238 @@
+use notedeck::time::unix_now;
+use std::collections::HashMap;
+
+use enostr::{
+    ClientMessage, NoteId, NwcInfo, NwcRequest, NwcTransaction, NwcUri, RelayEvent, RelayMessage,
+    RelayPool,
+};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// How many transactions to ask for at once
+const TRANSACTIONS_PAGE: u32 = 50;
+
+/// What a request we're waiting on was for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    Info,
+    Balance,
+    Transactions,
+    Pay,
+    MakeInvoice,
+}
+
+/// The outcome of the last payment or invoice request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletStatus {
+    Idle,
+    Working,
+    Paid,
+    Invoice(String),
+    Error(String),
+}
+
+/// A wallet connected over Nostr Wallet Connect. It has its own relay
+/// pool, since the wallet service usually listens somewhere other than
+/// the account's relays.
+pub struct Wallet {
+    uri: NwcUri,
+    pool: RelayPool,
+    subid: String,
+    /// Requests sent, or waiting for a relay to connect
+    pending: HashMap<NoteId, Pending>,
+    unsent: Vec<ClientMessage>,
+    connected: bool,
+    /// Only asked for when pairing, to know the connection works
+    pub info: Option<NwcInfo>,
+    pub balance: Option<u64>,
+    pub transactions: Vec<NwcTransaction>,
+    pub status: WalletStatus,
+}
+
+impl Wallet {
+    pub fn connect(uri: NwcUri, wakeup: impl Fn() + Send + Sync + Clone + 'static) -> Self {
+        let mut wallet = Wallet::open(uri, wakeup);
+        wallet.refresh();
+        wallet
+    }
+
+    /// Try a connection before it's saved: ask the wallet who it is and
+    /// for the balance, and nothing else
+    pub fn pair(uri: NwcUri, wakeup: impl Fn() + Send + Sync + Clone + 'static) -> Self {
+        let mut wallet = Wallet::open(uri, wakeup);
+        wallet.status = WalletStatus::Working;
+        wallet.send(NwcRequest::GetInfo, Pending::Info);
+        wallet.send(NwcRequest::GetBalance, Pending::Balance);
+        wallet
+    }
+
+    fn open(uri: NwcUri, wakeup: impl Fn() + Send + Sync + Clone + 'static) -> Self {
+        let mut pool = RelayPool::new();
+        if let Err(err) = pool.add_urls(uri.relays.iter().cloned().collect(), wakeup) {
+            error!("wallet: failed to add relays: {err}");
+        }
+
+        Wallet {
+            uri,
+            pool,
+            subid: Uuid::new_v4().to_string(),
+            pending: HashMap::new(),
+            unsent: vec![],
+            connected: false,
+            info: None,
+            balance: None,
+            transactions: vec![],
+            status: WalletStatus::Idle,
+        }
+    }
+
+    pub fn uri(&self) -> &NwcUri {
+        &self.uri
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Ask for the balance and recent transactions
+    pub fn refresh(&mut self) {
+        self.send(NwcRequest::GetBalance, Pending::Balance);
+        self.send(
+            NwcRequest::ListTransactions {
+                limit: TRANSACTIONS_PAGE,
+                offset: 0,
+            },
+            Pending::Transactions,
+        );
+    }
+
+    pub fn pay(&mut self, invoice: String) {
+        self.status = WalletStatus::Working;
+        self.send(NwcRequest::PayInvoice { invoice }, Pending::Pay);
+    }
+
+    pub fn make_invoice(&mut self, amount_msats: u64, description: String) {
+        self.status = WalletStatus::Working;
+        self.send(
+            NwcRequest::MakeInvoice {
+                amount_msats,
+                description,
+            },
+            Pending::MakeInvoice,
+        );
+    }
+
+    fn send(&mut self, request: NwcRequest, pending: Pending) {
+        let (id, msg) = match self.uri.request(&request) {
+            Ok(request) => request,
+            Err(err) => {
+                error!(
+                    "wallet: could not build {} request: {err}",
+                    request.method()
+                );
+                return;
+            }
+        };
+
+        debug!("wallet: sending {}", request.method());
+        self.pending.insert(id, pending);
+        if self.connected {
+            self.pool.send(&msg);
+        } else {
+            self.unsent.push(msg);
+        }
+    }
+
+    /// Handle whatever the wallet relays sent. Call every frame.
+    pub fn poll(&mut self) {
+        self.pool.keepalive_ping(|| {});
+
+        // NOTE: we don't use the while let loop due to borrow issues
+        #[allow(clippy::while_let_loop)]
+        loop {
+            let ev = if let Some(ev) = self.pool.try_recv() {
+                ev.into_owned()
+            } else {
+                break;
+            };
+
+            match (&ev.event).into() {
+                RelayEvent::Opened => {
+                    self.connected = true;
+                    let filter = self.uri.response_filter(unix_now().saturating_sub(60));
+                    self.pool.send_to(
+                        &ClientMessage::req(self.subid.clone(), vec![filter]),
+                        &ev.relay,
+                    );
+                    for msg in &self.unsent {
+                        self.pool.send_to(msg, &ev.relay);
+                    }
+                }
+                RelayEvent::Message(RelayMessage::Event(_subid, note)) => self.handle_event(note),
+                RelayEvent::Error(err) => debug!("wallet: relay {} error: {err}", ev.relay),
+                _ => {}
+            }
+        }
+
+        if self.connected {
+            self.unsent.clear();
+        }
+    }
+
+    fn handle_event(&mut self, note: &str) {
+        let (request, response) = match self.uri.decrypt_response(note) {
+            Ok(response) => response,
+            Err(err) => {
+                error!("wallet: bad response: {err}");
+                return;
+            }
+        };
+
+        let Some(pending) = request.and_then(|id| self.pending.remove(&id)) else {
+            // an answer to a request from a previous session
+            return;
+        };
+
+        if let Some(err) = response.error {
+            error!(
+                "wallet: {} failed: {} {}",
+                response.result_type, err.code, err.message
+            );
+            if matches!(pending, Pending::Info | Pending::Pay | Pending::MakeInvoice) {
+                self.status = WalletStatus::Error(err.message);
+            }
+            return;
+        }
+
+        match pending {
+            Pending::Info => {
+                self.info = response.info();
+                self.status = match &self.info {
+                    Some(_) => WalletStatus::Idle,
+                    None => WalletStatus::Error("the wallet didn't say what it is".to_owned()),
+                };
+            }
+            Pending::Balance => self.balance = response.balance(),
+            Pending::Transactions => {
+                if let Some(transactions) = response.transactions() {
+                    self.transactions = transactions;
+                }
+            }
+            Pending::Pay => {
+                info!("wallet: payment sent");
+                self.status = WalletStatus::Paid;
+                self.refresh();
+            }
+            Pending::MakeInvoice => {
+                self.status = match response.invoice() {
+                    Some(invoice) => WalletStatus::Invoice(invoice.to_owned()),
+                    None => WalletStatus::Error("the wallet didn't send an invoice".to_owned()),
+                };
+            }
+        }
+    }
+
+    pub fn disconnect(mut self) {
+        self.pool.unsubscribe(self.subid.clone());
+    }
+}
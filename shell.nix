@@ -2,11 +2,14 @@
 , android ? "https://github.com/tadfisher/android-nixpkgs/archive/refs/tags/2024-04-02.tar.gz"
 , use_android ? true
 , android_emulator ? false
+, use_tray ? false
 }:
 with pkgs;
 
 let
-  x11libs = lib.makeLibraryPath [ xorg.libX11 xorg.libXcursor xorg.libXrandr xorg.libXi libglvnd vulkan-loader vulkan-validation-layers libxkbcommon wayland ];
+  x11libs = lib.makeLibraryPath ([ xorg.libX11 xorg.libXcursor xorg.libXrandr xorg.libXi libglvnd vulkan-loader vulkan-validation-layers libxkbcommon wayland ]
+    # the tray loads appindicator at runtime
+    ++ lib.optional use_tray libayatana-appindicator);
 in
 mkShell ({
   nativeBuildInputs = [
@@ -23,6 +26,10 @@ mkShell ({
     #wabt
     #gdb
     #heaptrack
+  ] ++ lib.optionals (use_tray && stdenv.isLinux) [
+    gtk3
+    xdotool
+    libayatana-appindicator
   ] ++ lib.optionals use_android [
     jre
     openssl